@@ -44,7 +44,7 @@ impl SigProviderImpl {
 
 #[async_trait::async_trait]
 impl Middleware<VerificationSuccess> for SigProvider {
-    async fn call(&self, output: &VerificationSuccess) {
+    async fn call(&self, output: &VerificationSuccess) -> Result<(), anyhow::Error> {
         let abi = output
             .abi
             .as_ref()
@@ -55,12 +55,17 @@ impl Middleware<VerificationSuccess> for SigProvider {
                 inner.create_signatures(abi).await;
             });
         }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "sig_provider"
     }
 }
 
 #[async_trait::async_trait]
 impl Middleware<SourcifySuccess> for SigProvider {
-    async fn call(&self, output: &SourcifySuccess) {
+    async fn call(&self, output: &SourcifySuccess) -> Result<(), anyhow::Error> {
         let abi = serde_json::to_string(&output.abi);
         if let Ok(abi) = abi {
             let inner = self.inner.clone();
@@ -68,5 +73,10 @@ impl Middleware<SourcifySuccess> for SigProvider {
                 inner.create_signatures(abi).await;
             });
         }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "sig_provider"
     }
 }
@@ -0,0 +1,323 @@
+//! Encoding and decoding of values according to the contract ABI.
+
+use crate::signatures::function_signature;
+use ethabi::{
+    ethereum_types::U256,
+    token::{LenientTokenizer, Tokenizer},
+    Event, Function, Log, Param, Token,
+};
+use serde::Serialize;
+
+/// Decoded value of a function (or event) parameter.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct DecodedParam {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: serde_json::Value,
+}
+
+/// Finds the function by its name or canonical signature (e.g., "balanceOf" or "balanceOf(address)").
+/// If only the name is provided, overloaded functions are distinguished by the number of arguments, if known.
+pub fn find_function<'a>(
+    contract: &'a ethabi::Contract,
+    function: &str,
+    arguments: Option<usize>,
+) -> Result<&'a Function, String> {
+    let candidates: Vec<_> = if function.contains('(') {
+        contract
+            .functions()
+            .filter(|candidate| function_signature(candidate) == function)
+            .collect()
+    } else {
+        contract
+            .functions_by_name(function)
+            .map(|candidates| {
+                candidates
+                    .iter()
+                    .filter(|candidate| {
+                        arguments.map_or(true, |arguments| candidate.inputs.len() == arguments)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    match candidates.as_slice() {
+        [found] => Ok(*found),
+        [] => Err(match arguments {
+            Some(arguments) => {
+                format!("Function {function} taking {arguments} arguments is not found")
+            }
+            None => format!("Function {function} is not found"),
+        }),
+        _ => Err(format!(
+            "Function {function} is ambiguous; specify its full signature"
+        )),
+    }
+}
+
+/// Parses the abi provided either as a json array of entries, or as a single entry.
+pub fn parse_abi(abi: serde_json::Value) -> Result<ethabi::Contract, serde_json::Error> {
+    match abi {
+        serde_json::Value::Object(_) => serde_json::from_value(serde_json::Value::Array(vec![abi])),
+        abi => serde_json::from_value(abi),
+    }
+}
+
+/// Finds the function called by the calldata by its selector.
+pub fn find_function_by_selector<'a>(
+    contract: &'a ethabi::Contract,
+    calldata: &[u8],
+) -> Option<&'a Function> {
+    let selector = calldata.get(..4)?;
+    contract
+        .functions()
+        .find(|function| function.short_signature() == selector)
+}
+
+/// Finds the non-anonymous event emitted the log by its `topic0`.
+pub fn find_event_by_topic<'a>(
+    contract: &'a ethabi::Contract,
+    topic0: &ethabi::Hash,
+) -> Option<&'a Event> {
+    contract
+        .events()
+        .find(|event| !event.anonymous && event.signature() == *topic0)
+}
+
+/// Returns `true` if the function does not modify the state, so it can be called via `eth_call`.
+pub fn is_read_only(function: &Function) -> bool {
+    #[allow(deprecated)]
+    let constant = function.constant.unwrap_or_default();
+    constant
+        || matches!(
+            function.state_mutability,
+            ethabi::StateMutability::View | ethabi::StateMutability::Pure
+        )
+}
+
+/// Parses string arguments into tokens of the corresponding parameter types.
+///
+/// Arrays and tuples are expected in the form of "[1,2,3]" and "(0x...,true)",
+/// addresses and bytes may be provided either with or without "0x" prefix.
+pub fn tokenize(params: &[Param], arguments: &[String]) -> Result<Vec<Token>, String> {
+    if params.len() != arguments.len() {
+        return Err(format!(
+            "Expected {} arguments, got {}",
+            params.len(),
+            arguments.len()
+        ));
+    }
+    params
+        .iter()
+        .zip(arguments)
+        .enumerate()
+        .map(|(index, (param, argument))| {
+            LenientTokenizer::tokenize(&param.kind, argument).map_err(|err| {
+                format!(
+                    "Invalid value of argument {} ({}): {err}",
+                    param_name(&param.name, index),
+                    param.kind
+                )
+            })
+        })
+        .collect()
+}
+
+/// Pairs decoded tokens with their parameters.
+pub fn decode_params(params: &[Param], tokens: Vec<Token>) -> Vec<DecodedParam> {
+    params
+        .iter()
+        .zip(tokens)
+        .enumerate()
+        .map(|(index, (param, token))| DecodedParam {
+            name: param_name(&param.name, index),
+            kind: param.kind.to_string(),
+            value: token_to_json(token),
+        })
+        .collect()
+}
+
+/// Pairs parameters of the parsed log with their types.
+/// Indexed parameters of dynamic types are represented by their hashes.
+pub fn decode_log_params(event: &Event, log: Log) -> Vec<DecodedParam> {
+    event
+        .inputs
+        .iter()
+        .zip(log.params)
+        .enumerate()
+        .map(|(index, (input, param))| DecodedParam {
+            name: param_name(&input.name, index),
+            kind: input.kind.to_string(),
+            value: token_to_json(param.value),
+        })
+        .collect()
+}
+
+/// Converts the token into json. Integers are represented as decimal strings
+/// and byte sequences as "0x" prefixed hex strings, so that no precision is lost.
+pub fn token_to_json(token: Token) -> serde_json::Value {
+    match token {
+        Token::Address(address) => format!("0x{}", hex::encode(address.as_bytes())).into(),
+        Token::Bytes(bytes) | Token::FixedBytes(bytes) => {
+            format!("0x{}", hex::encode(bytes)).into()
+        }
+        Token::Uint(value) => value.to_string().into(),
+        Token::Int(value) if value.bit(255) => format!("-{}", !value + U256::one()).into(),
+        Token::Int(value) => value.to_string().into(),
+        Token::Bool(value) => value.into(),
+        Token::String(value) => value.into(),
+        Token::Array(tokens) | Token::FixedArray(tokens) | Token::Tuple(tokens) => {
+            tokens.into_iter().map(token_to_json).collect()
+        }
+    }
+}
+
+/// Unnamed parameters (e.g., function outputs or event inputs) are named by their position.
+fn param_name(name: &str, index: usize) -> String {
+    if name.is_empty() {
+        index.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethabi::ParamType;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn contract() -> ethabi::Contract {
+        serde_json::from_value(json!([
+            {
+                "type": "function",
+                "name": "balanceOf",
+                "inputs": [{ "name": "owner", "type": "address" }],
+                "outputs": [{ "name": "", "type": "uint256" }],
+                "stateMutability": "view"
+            },
+            {
+                "type": "function",
+                "name": "balanceOf",
+                "inputs": [
+                    { "name": "owner", "type": "address" },
+                    { "name": "id", "type": "uint256" }
+                ],
+                "outputs": [{ "name": "", "type": "uint256" }],
+                "stateMutability": "view"
+            },
+            {
+                "type": "function",
+                "name": "transfer",
+                "inputs": [
+                    { "name": "to", "type": "address" },
+                    { "name": "amount", "type": "uint256" }
+                ],
+                "outputs": [{ "name": "", "type": "bool" }],
+                "stateMutability": "nonpayable"
+            }
+        ]))
+        .expect("valid abi")
+    }
+
+    #[test]
+    fn finds_overloaded_functions() {
+        let contract = contract();
+        let function = find_function(&contract, "balanceOf", Some(2)).expect("function exists");
+        assert_eq!(2, function.inputs.len());
+        let function =
+            find_function(&contract, "balanceOf(address)", None).expect("signature is exact");
+        assert_eq!(1, function.inputs.len());
+        find_function(&contract, "balanceOf", None).expect_err("function is overloaded");
+        find_function(&contract, "balanceOf", Some(3)).expect_err("no function with 3 arguments");
+        find_function(&contract, "unknown", None).expect_err("no such function");
+    }
+
+    #[test]
+    fn parses_single_abi_entry() {
+        let entry = json!({
+            "type": "event",
+            "name": "Transfer",
+            "inputs": [
+                { "name": "from", "type": "address", "indexed": true },
+                { "name": "to", "type": "address", "indexed": true },
+                { "name": "value", "type": "uint256", "indexed": false }
+            ],
+            "anonymous": false
+        });
+        let contract = parse_abi(entry).expect("valid abi entry");
+        let topic0 = contract.event("Transfer").unwrap().signature();
+        assert!(find_event_by_topic(&contract, &topic0).is_some());
+    }
+
+    #[test]
+    fn finds_functions_by_selector() {
+        let contract = contract();
+        // transfer(address,uint256)
+        let calldata = hex::decode("a9059cbb0000").unwrap();
+        let function = find_function_by_selector(&contract, &calldata).expect("function exists");
+        assert_eq!("transfer", function.name);
+        assert!(find_function_by_selector(&contract, &calldata[..3]).is_none());
+    }
+
+    #[test]
+    fn read_only_functions() {
+        let contract = contract();
+        assert!(is_read_only(find_function(&contract, "balanceOf", Some(1)).unwrap()));
+        assert!(!is_read_only(find_function(&contract, "transfer", None).unwrap()));
+    }
+
+    #[test]
+    fn tokenizes_arguments() {
+        let contract = contract();
+        let function = find_function(&contract, "transfer", None).unwrap();
+        let arguments = vec![
+            "0x000000000000000000000000000000000000dead".to_string(),
+            "1000".to_string(),
+        ];
+        let tokens = tokenize(&function.inputs, &arguments).expect("valid arguments");
+        assert_eq!(Token::Uint(U256::from(1000u64)), tokens[1]);
+
+        let arguments = vec!["0xdead".to_string(), "1000".to_string()];
+        let err = tokenize(&function.inputs, &arguments).expect_err("invalid address");
+        assert!(err.contains("argument to"), "unexpected error: {err}");
+        tokenize(&function.inputs, &arguments[..1]).expect_err("missing argument");
+    }
+
+    #[test]
+    fn decodes_tokens_into_json() {
+        let params = vec![
+            Param {
+                name: "".into(),
+                kind: ParamType::Int(256),
+                internal_type: None,
+            },
+            Param {
+                name: "values".into(),
+                kind: ParamType::Array(Box::new(ParamType::Bytes)),
+                internal_type: None,
+            },
+        ];
+        let tokens = vec![
+            Token::Int(U256::MAX),
+            Token::Array(vec![Token::Bytes(vec![0xca, 0xfe])]),
+        ];
+        assert_eq!(
+            vec![
+                DecodedParam {
+                    name: "0".into(),
+                    kind: "int256".into(),
+                    value: json!("-1"),
+                },
+                DecodedParam {
+                    name: "values".into(),
+                    kind: "bytes[]".into(),
+                    value: json!(["0xcafe"]),
+                },
+            ],
+            decode_params(&params, tokens)
+        );
+    }
+}
@@ -0,0 +1,369 @@
+use crate::{
+    auth::{AdminAuth, API_KEY_HEADER},
+    chains::Chain,
+    request_id,
+    settings::AccessLogSettings,
+    DB,
+};
+use actix_web::{
+    body::{BodySize, BoxBody, MessageBody},
+    dev::{Payload, Service, ServiceRequest, ServiceResponse},
+    error::PayloadError,
+    http::Method,
+    web::{self, Bytes},
+    Error, HttpMessage,
+};
+use chrono::{Duration, Utc};
+use cron::Schedule;
+use futures::{future::LocalBoxFuture, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use smart_contract_verifier::scheduler;
+use std::{
+    cell::RefCell,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    rc::Rc,
+    str::FromStr,
+    time::Instant,
+};
+
+/// Only requests to the api are recorded (health checks and metrics are not).
+const API_PREFIX: &str = "/api/v1/";
+
+/// Number of hex characters of api key hashes stored to tell keys apart.
+const API_KEY_FINGERPRINT_LENGTH: usize = 16;
+
+/// A request submitted to the service along with its outcome.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AccessLogRecord {
+    pub request_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    /// Address of the client, taking `Forwarded` and `X-Forwarded-For` headers into account.
+    pub client_ip: Option<String>,
+    /// Prefix of the sha256 hash of the api key provided with the request.
+    /// Api keys themselves are never stored.
+    pub api_key: Option<String>,
+    /// Name of the admin the request has been authenticated as.
+    pub admin: Option<String>,
+    pub contract_address: Option<String>,
+    /// Json request body (with sources redacted if configured) as a json string.
+    /// Is `None` for non-json and too large bodies.
+    pub request: Option<String>,
+    pub request_size: usize,
+    pub status_code: u16,
+    /// Size of the response body. Is `None` for streamed responses of unknown size.
+    pub response_size: Option<u64>,
+    pub duration_ms: u64,
+    pub received_at: bson::DateTime,
+}
+
+/// Records requests changing the state of the service (i.e., all but `GET` ones)
+/// into the access log of the current chain.
+pub struct AccessLog {
+    settings: AccessLogSettings,
+    chain: Chain,
+}
+
+impl AccessLog {
+    pub fn new(settings: AccessLogSettings, chain: Chain) -> Self {
+        Self { settings, chain }
+    }
+
+    fn is_recorded(request: &ServiceRequest) -> bool {
+        let read_only = [Method::GET, Method::HEAD, Method::OPTIONS].contains(request.method());
+        !read_only && request.path().starts_with(API_PREFIX)
+    }
+
+    fn client_ip(&self, request: &ServiceRequest) -> Option<String> {
+        let address = request.connection_info().realip_remote_addr()?.to_string();
+        let ip = IpAddr::from_str(&address)
+            .or_else(|_| SocketAddr::from_str(&address).map(|address| address.ip()));
+        match ip {
+            Ok(ip) if self.settings.anonymize_ips => Some(anonymize(ip).to_string()),
+            Ok(ip) => Some(ip.to_string()),
+            // Addresses are expected to be valid, but are not rejected otherwise
+            Err(_) if self.settings.anonymize_ips => None,
+            Err(_) => Some(address),
+        }
+    }
+
+    /// Converts the captured request body into the form it is stored in.
+    fn request_body(&self, body: &CapturedBody) -> (Option<String>, Option<String>) {
+        if body.truncated {
+            return (None, None);
+        }
+        let mut request: Value = match serde_json::from_slice(&body.content) {
+            Ok(request) => request,
+            Err(_) => return (None, None),
+        };
+        let contract_address = ["contract_address", "address"]
+            .into_iter()
+            .find_map(|key| request.get(key)?.as_str())
+            .map(str::to_lowercase);
+        if self.settings.redact_sources {
+            redact_sources(&mut request);
+        }
+        (Some(request.to_string()), contract_address)
+    }
+}
+
+/// Records the request into the access log, if enabled and the request changes the state.
+/// Should be registered inside of `TracingLogger` middleware, so that request ids are assigned.
+///
+/// The request body is captured while being read by the handler, so that it is passed through
+/// unchanged. Response bodies are not read (they may be large, e.g. exports), only their sizes are
+/// recorded.
+pub fn record<S, B>(
+    mut request: ServiceRequest,
+    service: &S,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    let log = request
+        .app_data::<web::Data<AccessLog>>()
+        .filter(|_| AccessLog::is_recorded(&request))
+        .cloned();
+    let log = match log {
+        Some(log) => log,
+        None => {
+            let response = service.call(request);
+            return Box::pin(async move { Ok(response.await?.map_into_boxed_body()) });
+        }
+    };
+
+    let received_at = bson::DateTime::now();
+    let started = Instant::now();
+    let api_key = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|key| key.to_str().ok())
+        .map(|key| hex::encode(Sha256::digest(key))[..API_KEY_FINGERPRINT_LENGTH].to_string());
+    let admin = request
+        .app_data::<web::Data<AdminAuth>>()
+        .and_then(|auth| auth.authenticate(request.request()));
+    let mut record = AccessLogRecord {
+        request_id: request_id::request_id(&request),
+        method: request.method().to_string(),
+        path: request.path().to_string(),
+        client_ip: log.client_ip(&request),
+        api_key,
+        admin,
+        contract_address: None,
+        request: None,
+        request_size: 0,
+        status_code: 0,
+        response_size: None,
+        duration_ms: 0,
+        received_at,
+    };
+
+    let body = Rc::new(RefCell::new(CapturedBody::default()));
+    let max_body_size = log.settings.max_body_size;
+    let payload = request.take_payload().inspect({
+        let body = body.clone();
+        move |chunk| {
+            if let Ok(chunk) = chunk {
+                body.borrow_mut().push(chunk, max_body_size)
+            }
+        }
+    });
+    let payload: Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>> = Box::pin(payload);
+    request.set_payload(Payload::from(payload));
+
+    let response = service.call(request);
+    Box::pin(async move {
+        let response = response.await?;
+
+        let body = body.borrow();
+        (record.request, record.contract_address) = log.request_body(&body);
+        record.request_size = body.size;
+        record.status_code = response.status().as_u16();
+        record.response_size = match response.response().body().size() {
+            BodySize::None => Some(0),
+            BodySize::Sized(size) => Some(size),
+            BodySize::Stream => None,
+        };
+        record.duration_ms = started.elapsed().as_millis() as u64;
+        // The namespace depends on the tenant of the request, which is not known to spawned tasks
+        let namespace = log.chain.namespace();
+        tokio::spawn(async move {
+            let database = DB::new().await.change_name(&namespace);
+            if let Err(err) = database.add_access_log_record(record).await {
+                tracing::error!("{}", err);
+            }
+        });
+
+        Ok(response.map_into_boxed_body())
+    })
+}
+
+/// Periodically removes access log records older than the retention period.
+/// Receiving times of records stored as strings are converted to dates once on start.
+pub fn spawn_cleanup_job(retention_days: u32, chain: Chain) {
+    let schedule = Schedule::from_str("0 0 * * * * *").expect("Is valid schedule"); // every hour
+    let namespace = chain.namespace();
+    tokio::spawn({
+        let namespace = namespace.clone();
+        async move {
+            // Records have been stored with rfc3339 strings as receiving times before
+            let database = DB::new().await.change_name(&namespace);
+            match database.convert_access_log_receiving_times().await {
+                Ok(0) => {}
+                Ok(converted) => tracing::info!(
+                    "receiving times of {} access log records converted to dates",
+                    converted
+                ),
+                Err(err) => tracing::error!("{}", err),
+            }
+        }
+    });
+    scheduler::spawn_job(schedule, "remove outdated access log records", move || {
+        let namespace = namespace.clone();
+        async move {
+            let received_before = Utc::now() - Duration::days(retention_days.into());
+            let database = DB::new().await.change_name(&namespace);
+            match database
+                .remove_access_log_records_before(bson::DateTime::from_chrono(received_before))
+                .await
+            {
+                Ok(removed) => {
+                    tracing::debug!("{} outdated access log records removed", removed)
+                }
+                Err(err) => tracing::error!("{}", err),
+            }
+        }
+    });
+}
+
+/// Request body captured while being read by the handler.
+#[derive(Default)]
+struct CapturedBody {
+    content: Vec<u8>,
+    size: usize,
+    /// Is set if the body has exceeded the max size and has not been captured entirely.
+    truncated: bool,
+}
+
+impl CapturedBody {
+    fn push(&mut self, chunk: &Bytes, max_size: usize) {
+        self.size += chunk.len();
+        if self.size > max_size {
+            self.truncated = true;
+            self.content = Vec::new();
+        } else {
+            self.content.extend_from_slice(chunk);
+        }
+    }
+}
+
+/// Replaces source contents of verification requests with their sizes: `sources`
+/// and `files` maps, `source_code` of flattened contracts and sources of standard json `input`.
+fn redact_sources(request: &mut Value) {
+    let object = match request.as_object_mut() {
+        Some(object) => object,
+        None => return,
+    };
+    for key in ["sources", "files"] {
+        if let Some(Value::Object(sources)) = object.get_mut(key) {
+            sources.values_mut().for_each(redact);
+        }
+    }
+    if let Some(source_code) = object.get_mut("source_code") {
+        redact(source_code);
+    }
+    if let Some(input) = object.get_mut("input") {
+        // Is provided as a json string; inputs which cannot be parsed are dropped entirely
+        let mut parsed = match &mut *input {
+            Value::String(text) => serde_json::from_str(text.as_str()).unwrap_or(Value::Null),
+            other => other.take(),
+        };
+        if let Some(Value::Object(sources)) = parsed.get_mut("sources") {
+            for source in sources.values_mut() {
+                match source.get_mut("content") {
+                    Some(content) => redact(content),
+                    None => redact(source),
+                }
+            }
+        }
+        *input = parsed;
+    }
+}
+
+fn redact(content: &mut Value) {
+    if let Value::String(text) = content {
+        *content = Value::String(format!("<redacted: {} bytes>", text.len()));
+    }
+}
+
+fn anonymize(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            IpAddr::from([a, b, c, 0])
+        }
+        IpAddr::V6(ip) => {
+            let mut segments = ip.segments();
+            segments[3..].fill(0);
+            IpAddr::from(segments)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn sources_are_redacted() {
+        let mut multi_part = json!({
+            "contract_address": "0xcafe",
+            "sources": { "A.sol": "contract A {}" },
+            "evm_version": "london",
+        });
+        redact_sources(&mut multi_part);
+        assert_eq!(
+            json!({
+                "contract_address": "0xcafe",
+                "sources": { "A.sol": "<redacted: 13 bytes>" },
+                "evm_version": "london",
+            }),
+            multi_part
+        );
+
+        let mut standard_json = json!({
+            "contract_address": "0xcafe",
+            "input": r#"{"language":"Solidity","sources":{"A.sol":{"content":"contract A {}"}}}"#,
+        });
+        redact_sources(&mut standard_json);
+        assert_eq!(
+            json!({
+                "contract_address": "0xcafe",
+                "input": {
+                    "language": "Solidity",
+                    "sources": { "A.sol": { "content": "<redacted: 13 bytes>" } }
+                },
+            }),
+            standard_json
+        );
+    }
+
+    #[test]
+    fn ips_are_anonymized() {
+        assert_eq!(
+            IpAddr::from_str("192.168.1.0").unwrap(),
+            anonymize(IpAddr::from_str("192.168.1.42").unwrap())
+        );
+        assert_eq!(
+            IpAddr::from_str("2001:db8:85a3::").unwrap(),
+            anonymize(IpAddr::from_str("2001:db8:85a3:8d3:1319:8a2e:370:7348").unwrap())
+        );
+    }
+}
@@ -0,0 +1,443 @@
+use crate::{chains::Chain, persistence::VerifiedContract, DB};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use smart_contract_verifier::middleware::Middleware;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingSeverity {
+    #[default]
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+/// Potential issue found in the verified sources.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Finding {
+    /// Name of the analyzer the issue has been found by (e.g., "basic").
+    #[serde(default)]
+    pub analyzer: String,
+    /// Name of the check the issue has been found by (e.g., "tx-origin").
+    pub check: String,
+    pub severity: FindingSeverity,
+    pub message: String,
+    /// Location of the issue in the AST format: "{offset}:{length}:{source id}".
+    pub src: Option<String>,
+    /// Source file and the line (starting from 1) the issue has been found at.
+    /// Are resolved from `src` after the analysis, if not provided by the analyzer.
+    pub file_name: Option<String>,
+    pub line: Option<usize>,
+}
+
+impl Finding {
+    fn new(check: &str, severity: FindingSeverity, message: &str, node: &Value) -> Self {
+        Self {
+            analyzer: String::new(),
+            check: check.to_string(),
+            severity,
+            message: message.to_string(),
+            src: node.get("src").and_then(Value::as_str).map(str::to_string),
+            file_name: None,
+            line: None,
+        }
+    }
+}
+
+/// Findings of all analyzers run over the verified contract.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ContractAnalysis {
+    pub contract_address: String,
+    /// Names of the analyzers the contract has been analyzed by.
+    pub analyzers: Vec<String>,
+    pub findings: Vec<Finding>,
+    pub analyzed_at: DateTime<Utc>,
+}
+
+/// Compact json AST of a compiled source file, as returned by the solidity compiler.
+#[derive(Clone, Copy, Debug)]
+pub struct SourceAst<'a> {
+    pub file_name: &'a str,
+    pub ast: &'a Value,
+}
+
+/// Verified contract passed to the analyzers, along with ASTs of its sources.
+#[derive(Clone, Debug)]
+pub struct AnalyzedContract<'a> {
+    pub verified: &'a VerifiedContract,
+    pub asts: Vec<SourceAst<'a>>,
+}
+
+/// Analysis run over verified contracts after they have been stored.
+/// New analyzers are added to [`StaticAnalysis`] with [`StaticAnalysis::with`].
+#[async_trait::async_trait]
+pub trait Analyzer: Send + Sync {
+    /// Name of the analyzer returned along with the findings.
+    fn name(&self) -> &'static str;
+
+    /// Errors are logged, and do not prevent findings of other analyzers from being stored.
+    async fn analyze(&self, contract: &AnalyzedContract<'_>) -> Result<Vec<Finding>, String>;
+}
+
+/// Runs the analyzers over verified solidity contracts in the background,
+/// and stores their findings aside from the verified contracts.
+/// Contracts without ASTs (e.g., vyper ones) are not analyzed.
+#[derive(Clone)]
+pub struct StaticAnalysis {
+    analyzers: Vec<Arc<dyn Analyzer>>,
+    /// Findings less severe than that are not stored.
+    min_severity: FindingSeverity,
+    /// Chain contracts are verified for. Findings are stored in its namespace.
+    chain: Chain,
+}
+
+impl StaticAnalysis {
+    pub fn new(min_severity: FindingSeverity, chain: Chain) -> Self {
+        Self {
+            analyzers: vec![],
+            min_severity,
+            chain,
+        }
+    }
+
+    pub fn with(mut self, analyzer: impl Analyzer + 'static) -> Self {
+        self.analyzers.push(Arc::new(analyzer));
+        self
+    }
+
+    pub async fn analyze(&self, verified: &VerifiedContract) -> Option<ContractAnalysis> {
+        let debug_info = verified.debug_info.as_ref()?;
+        let asts: Vec<_> = debug_info
+            .sources
+            .iter()
+            .filter_map(|(file_name, source)| {
+                let ast = source.ast.as_ref()?;
+                Some(SourceAst { file_name, ast })
+            })
+            .collect();
+        if asts.is_empty() {
+            return None;
+        }
+        let contract = AnalyzedContract { verified, asts };
+
+        let mut analyzers = Vec::new();
+        let mut findings = Vec::new();
+        for analyzer in &self.analyzers {
+            match analyzer.analyze(&contract).await {
+                Ok(analyzer_findings) => {
+                    analyzers.push(analyzer.name().to_string());
+                    findings.extend(analyzer_findings.into_iter().map(|finding| Finding {
+                        analyzer: analyzer.name().to_string(),
+                        ..finding
+                    }));
+                }
+                Err(err) => tracing::warn!(
+                    "{} analysis of {} has failed: {}",
+                    analyzer.name(),
+                    verified.contract_address,
+                    err
+                ),
+            }
+        }
+        findings.retain(|finding| finding.severity >= self.min_severity);
+
+        let file_names: BTreeMap<_, _> = debug_info
+            .sources
+            .iter()
+            .map(|(file_name, source)| (source.id, file_name))
+            .collect();
+        for finding in &mut findings {
+            let location = finding.src.as_deref().and_then(|src| {
+                let mut parts = src.split(':');
+                let offset: usize = parts.next()?.parse().ok()?;
+                let id: u32 = parts.nth(1)?.parse().ok()?;
+                Some((offset, *file_names.get(&id)?))
+            });
+            if let Some((offset, file_name)) = location {
+                finding.line = verified
+                    .result
+                    .sources
+                    .get(file_name)
+                    .and_then(|content| content.as_bytes().get(..offset))
+                    .map(|before| before.iter().filter(|&&byte| byte == b'\n').count() + 1);
+                finding.file_name = Some(file_name.clone());
+            }
+        }
+        findings.sort_by(|a, b| b.severity.cmp(&a.severity));
+
+        Some(ContractAnalysis {
+            contract_address: verified.contract_address.to_lowercase(),
+            analyzers,
+            findings,
+            analyzed_at: Utc::now(),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware<VerifiedContract> for StaticAnalysis {
+    async fn call(&self, verified: &VerifiedContract) -> Result<(), anyhow::Error> {
+        let analysis = self.clone();
+        let verified = verified.clone();
+        let namespace = self.chain.namespace();
+        tokio::spawn(async move {
+            let analysis = match analysis.analyze(&verified).await {
+                Some(analysis) => analysis,
+                None => return,
+            };
+            let database = DB::new().await.change_name(&namespace);
+            if let Err(err) = database.set_contract_analysis(analysis).await {
+                tracing::error!("{}", err);
+            }
+        });
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "analysis"
+    }
+}
+
+/// Lightweight checks of common vulnerabilities, which may have false positives
+/// and are not a replacement for an audit.
+pub struct BasicChecks;
+
+impl BasicChecks {
+    fn check(&self, asts: &[SourceAst]) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for source in asts {
+            visit(source.ast, &BTreeSet::new(), &mut findings);
+        }
+        findings
+    }
+}
+
+#[async_trait::async_trait]
+impl Analyzer for BasicChecks {
+    fn name(&self) -> &'static str {
+        "basic"
+    }
+
+    async fn analyze(&self, contract: &AnalyzedContract<'_>) -> Result<Vec<Finding>, String> {
+        Ok(self.check(&contract.asts))
+    }
+}
+
+/// Members of addresses making calls which failures are returned rather than reverted.
+const LOW_LEVEL_CALLS: [&str; 5] = ["call", "delegatecall", "staticcall", "send", "callcode"];
+
+fn node_type(node: &Value) -> Option<&str> {
+    node.get("nodeType")?.as_str()
+}
+
+/// Returns whether the node is `{base}.{member}` (e.g., `tx.origin`).
+fn is_member_of(node: &Value, base: &str, member: &str) -> bool {
+    node_type(node) == Some("MemberAccess")
+        && node.get("memberName").and_then(Value::as_str) == Some(member)
+        && node
+            .get("expression")
+            .and_then(|expression| expression.get("name"))
+            .and_then(Value::as_str)
+            == Some(base)
+}
+
+/// Returns the name of the low-level call (e.g., "call", "send") made by the function call node
+/// along with the expression of the called address, unwrapping call options (`.call{value: 1}(...)`) and legacy modifiers (`.call.value(1)(...)`).
+fn low_level_call(call: &Value) -> Option<(&str, &Value)> {
+    let mut callee = call.get("expression")?;
+    loop {
+        match node_type(callee)? {
+            "FunctionCallOptions" => callee = callee.get("expression")?,
+            "FunctionCall" => callee = callee.get("expression")?.get("expression")?,
+            "MemberAccess" => break,
+            _ => return None,
+        }
+    }
+    let member = callee.get("memberName")?.as_str()?;
+    if !LOW_LEVEL_CALLS.contains(&member) {
+        return None;
+    }
+    Some((member, callee.get("expression")?))
+}
+
+/// Parameters of public and external functions, which values are controlled by callers.
+fn user_inputs(function: &Value) -> BTreeSet<u64> {
+    let visibility = function.get("visibility").and_then(Value::as_str);
+    if !matches!(visibility, Some("public" | "external")) {
+        return BTreeSet::new();
+    }
+    function
+        .get("parameters")
+        .and_then(|parameters| parameters.get("parameters"))
+        .and_then(Value::as_array)
+        .map(|parameters| {
+            parameters
+                .iter()
+                .filter_map(|parameter| parameter.get("id")?.as_u64())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn visit(node: &Value, inputs: &BTreeSet<u64>, findings: &mut Vec<Finding>) {
+    let children: Box<dyn Iterator<Item = &Value>> = match node {
+        Value::Array(values) => Box::new(values.iter()),
+        Value::Object(fields) => Box::new(fields.values()),
+        _ => return,
+    };
+    let function_inputs;
+    let inputs = match node_type(node) {
+        Some("FunctionDefinition") => {
+            function_inputs = user_inputs(node);
+            &function_inputs
+        }
+        _ => inputs,
+    };
+    check(node, inputs, findings);
+    for child in children {
+        visit(child, inputs, findings);
+    }
+}
+
+fn check(node: &Value, inputs: &BTreeSet<u64>, findings: &mut Vec<Finding>) {
+    match node_type(node) {
+        Some("BinaryOperation") => {
+            let operator = node.get("operator").and_then(Value::as_str);
+            let (left, right) = match (node.get("leftExpression"), node.get("rightExpression")) {
+                (Some(left), Some(right)) => (left, right),
+                _ => return,
+            };
+            // `tx.origin == msg.sender` is a common check that the caller is not a contract
+            let origin_compared = [(left, right), (right, left)]
+                .iter()
+                .any(|(a, b)| is_member_of(a, "tx", "origin") && !is_member_of(b, "msg", "sender"));
+            if matches!(operator, Some("==" | "!=")) && origin_compared {
+                findings.push(Finding::new(
+                    "tx-origin",
+                    FindingSeverity::Medium,
+                    "tx.origin is used for authorization, which allows phishing by malicious contracts",
+                    node,
+                ));
+            }
+        }
+        Some("FunctionCall") => {
+            let callee = node.get("expression");
+            let callee_name = callee
+                .filter(|callee| node_type(callee) == Some("Identifier"))
+                .and_then(|callee| callee.get("name"))
+                .and_then(Value::as_str);
+            if matches!(callee_name, Some("selfdestruct" | "suicide")) {
+                findings.push(Finding::new(
+                    "selfdestruct",
+                    FindingSeverity::Low,
+                    "The contract may be destroyed with selfdestruct",
+                    node,
+                ));
+            }
+            if let Some(("delegatecall" | "callcode", target)) = low_level_call(node) {
+                let target = target.get("referencedDeclaration").and_then(Value::as_u64);
+                if target.map_or(false, |target| inputs.contains(&target)) {
+                    findings.push(Finding::new(
+                        "delegatecall-input",
+                        FindingSeverity::High,
+                        "delegatecall is made to an address provided by the caller",
+                        node,
+                    ));
+                }
+            }
+        }
+        Some("ExpressionStatement") => {
+            let call = node
+                .get("expression")
+                .filter(|expression| node_type(expression) == Some("FunctionCall"));
+            if let Some((member, _)) = call.and_then(low_level_call) {
+                findings.push(Finding::new(
+                    "unchecked-call",
+                    FindingSeverity::Medium,
+                    &format!("Return value of the low-level {member} is not checked"),
+                    node,
+                ));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn identifier(name: &str, declaration: u64) -> Value {
+        json!({ "nodeType": "Identifier", "name": name, "referencedDeclaration": declaration })
+    }
+
+    fn member(expression: Value, member: &str) -> Value {
+        json!({ "nodeType": "MemberAccess", "memberName": member, "expression": expression })
+    }
+
+    #[test]
+    fn basic_checks_find_issues() {
+        let origin_check = json!({
+            "nodeType": "BinaryOperation",
+            "operator": "==",
+            "src": "10:20:0",
+            "leftExpression": member(identifier("tx", 1), "origin"),
+            "rightExpression": identifier("owner", 2),
+        });
+        let contract_check = json!({
+            "nodeType": "BinaryOperation",
+            "operator": "==",
+            "leftExpression": member(identifier("tx", 1), "origin"),
+            "rightExpression": member(identifier("msg", 3), "sender"),
+        });
+        let delegatecall = json!({
+            "nodeType": "ExpressionStatement",
+            "expression": {
+                "nodeType": "FunctionCall",
+                "expression": member(identifier("target", 10), "delegatecall"),
+                "arguments": [],
+            },
+        });
+        let destruct = json!({
+            "nodeType": "FunctionCall",
+            "expression": identifier("selfdestruct", 4),
+            "arguments": [],
+        });
+        let ast = json!({
+            "nodeType": "SourceUnit",
+            "nodes": [{
+                "nodeType": "FunctionDefinition",
+                "visibility": "external",
+                "parameters": { "parameters": [{ "id": 10, "name": "target" }] },
+                "body": { "statements": [origin_check, contract_check, delegatecall, destruct] },
+            }],
+        });
+
+        let findings = BasicChecks.check(&[SourceAst {
+            file_name: "A.sol",
+            ast: &ast,
+        }]);
+        let checks: Vec<_> = findings
+            .iter()
+            .map(|finding| finding.check.as_str())
+            .collect();
+        assert_eq!(
+            vec![
+                "tx-origin",
+                "unchecked-call",
+                "delegatecall-input",
+                "selfdestruct"
+            ],
+            checks
+        );
+        assert_eq!(Some("10:20:0".to_string()), findings[0].src);
+    }
+}
@@ -0,0 +1,388 @@
+use crate::{
+    archive::ArchiveError,
+    dependencies::DependencyError,
+    etherscan_import::EtherscanImportError,
+    git_import::GitImportError,
+    uploads::UploadError,
+    verification_response::{Diagnostics, SourceWarning},
+};
+use actix_multipart::MultipartError;
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    http::StatusCode,
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use smart_contract_verifier::{
+    CompilerError, ContractNotFound, EvictionError, SourcifyError, VerificationError,
+};
+use std::fmt::{self, Display, Formatter};
+
+/// Machine-readable reason of the failure. Is stable across releases,
+/// in contrast to the message which is meant for humans only.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    InvalidRequest,
+    UnknownChain,
+    InvalidCompilerVersion,
+    InvalidBytecode,
+    CompilerNotFound,
+    CompilerVersionMismatch,
+    CompilationFailed,
+    NoMatchingContracts,
+    HiddenCharacters,
+    PrecompileAddress,
+    Create2AddressMismatch,
+    ContractNotFound,
+    ContractNotVerified,
+    NotFound,
+    Unauthorized,
+    Conflict,
+    PayloadTooLarge,
+    TooManyRequests,
+    QuotaExceeded,
+    ImportTimeout,
+    CompilationTimeout,
+    ExplorerUnavailable,
+    PackageRegistryUnavailable,
+    CompilerFetchFailed,
+    NodeUnavailable,
+    NotReady,
+    ReadOnly,
+    StorageError,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest
+            | ErrorCode::UnknownChain
+            | ErrorCode::InvalidCompilerVersion
+            | ErrorCode::InvalidBytecode
+            | ErrorCode::CompilerNotFound
+            | ErrorCode::CompilerVersionMismatch
+            | ErrorCode::CompilationFailed
+            | ErrorCode::NoMatchingContracts
+            | ErrorCode::HiddenCharacters
+            | ErrorCode::PrecompileAddress
+            | ErrorCode::Create2AddressMismatch => StatusCode::BAD_REQUEST,
+            ErrorCode::ContractNotFound | ErrorCode::ContractNotVerified | ErrorCode::NotFound => {
+                StatusCode::NOT_FOUND
+            }
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Conflict => StatusCode::CONFLICT,
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::QuotaExceeded => StatusCode::FORBIDDEN,
+            ErrorCode::ImportTimeout | ErrorCode::CompilationTimeout => StatusCode::GATEWAY_TIMEOUT,
+            ErrorCode::NotReady => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::ReadOnly => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::CompilerFetchFailed
+            | ErrorCode::ExplorerUnavailable
+            | ErrorCode::PackageRegistryUnavailable
+            | ErrorCode::NodeUnavailable => StatusCode::BAD_GATEWAY,
+            ErrorCode::StorageError | ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Body of every error response: `{"code": ..., "message": ..., "details": ...}`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Code specific data (e.g., diagnostics of the candidate contracts). Is `null` if absent.
+    pub details: Option<Value>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Display) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: impl Serialize) -> Self {
+        self.details = serde_json::to_value(details).ok();
+        self
+    }
+
+    pub fn invalid_request(message: impl Display) -> Self {
+        Self::new(ErrorCode::InvalidRequest, message)
+    }
+
+    pub fn not_found(message: impl Display) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn not_verified(address: impl Display) -> Self {
+        Self::new(
+            ErrorCode::ContractNotVerified,
+            format!("Contract {address} is not verified"),
+        )
+    }
+
+    /// Failure to fetch the data from the node.
+    pub fn node(err: impl Display) -> Self {
+        Self::new(ErrorCode::NodeUnavailable, err)
+    }
+
+    /// Failure to read or write the database.
+    pub fn storage(err: impl Display) -> Self {
+        Self::new(ErrorCode::StorageError, err)
+    }
+
+    pub fn internal(err: impl Display) -> Self {
+        Self::new(ErrorCode::InternalError, err)
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.code.status()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self)
+    }
+}
+
+impl From<ContractNotFound> for ApiError {
+    fn from(not_found: ContractNotFound) -> Self {
+        Self::new(ErrorCode::ContractNotFound, &not_found).with_details(not_found)
+    }
+}
+
+impl From<VerificationError> for ApiError {
+    fn from(err: VerificationError) -> Self {
+        match err {
+            VerificationError::Initialization(_) => Self::new(ErrorCode::InvalidBytecode, err),
+            VerificationError::VersionNotFound(_) => Self::new(ErrorCode::CompilerNotFound, err),
+            VerificationError::Compilation(ref errors) => {
+                let errors = errors.clone();
+                Self::new(ErrorCode::CompilationFailed, err).with_details(errors)
+            }
+            VerificationError::NoMatchingContracts(ref diagnostics) => {
+                let diagnostics: Vec<Diagnostics> =
+                    diagnostics.iter().cloned().map(Diagnostics::from).collect();
+                Self::new(ErrorCode::NoMatchingContracts, err).with_details(diagnostics)
+            }
+            VerificationError::CompilerVersionMismatch(ref mismatch) => {
+                let details = serde_json::json!({
+                    "expected": mismatch.expected.to_string(),
+                    "found": mismatch.found.to_string(),
+                });
+                Self::new(ErrorCode::CompilerVersionMismatch, err).with_details(details)
+            }
+            VerificationError::ContractNotFound(not_found) => not_found.into(),
+            VerificationError::HiddenCharacters(ref findings) => {
+                let warnings: Vec<SourceWarning> =
+                    findings.iter().cloned().map(SourceWarning::from).collect();
+                Self::new(ErrorCode::HiddenCharacters, err).with_details(warnings)
+            }
+            VerificationError::Internal(ref internal) => {
+                match internal.downcast_ref::<CompilerError>() {
+                    Some(CompilerError::Fetch(_)) => Self::new(ErrorCode::CompilerFetchFailed, err),
+                    Some(CompilerError::Timeout(_)) => {
+                        Self::new(ErrorCode::CompilationTimeout, err)
+                    }
+                    _ => Self::internal(err),
+                }
+            }
+        }
+    }
+}
+
+impl From<SourcifyError> for ApiError {
+    fn from(err: SourcifyError) -> Self {
+        match err {
+            SourcifyError::Internal(_) => Self::internal(err),
+            SourcifyError::Verification(_) | SourcifyError::Validation(_) => {
+                Self::invalid_request(err)
+            }
+        }
+    }
+}
+
+impl From<EvictionError> for ApiError {
+    fn from(err: EvictionError) -> Self {
+        match err {
+            EvictionError::NotCached(_) => Self::not_found(err),
+            EvictionError::Retained(_) | EvictionError::External(_) | EvictionError::Leased(_) => {
+                Self::new(ErrorCode::Conflict, err)
+            }
+            EvictionError::Io(_) => Self::internal(err),
+        }
+    }
+}
+
+impl From<ArchiveError> for ApiError {
+    fn from(err: ArchiveError) -> Self {
+        match err {
+            ArchiveError::TooLarge(_) => Self::new(ErrorCode::PayloadTooLarge, err),
+            err => Self::invalid_request(err),
+        }
+    }
+}
+
+impl From<GitImportError> for ApiError {
+    fn from(err: GitImportError) -> Self {
+        match err {
+            GitImportError::TooLarge(_) => Self::new(ErrorCode::PayloadTooLarge, err),
+            GitImportError::Timeout(_) => Self::new(ErrorCode::ImportTimeout, err),
+            GitImportError::Io(_) => Self::internal(err),
+            err => Self::invalid_request(err),
+        }
+    }
+}
+
+impl From<EtherscanImportError> for ApiError {
+    fn from(err: EtherscanImportError) -> Self {
+        match err {
+            EtherscanImportError::Unavailable(_) | EtherscanImportError::Api(_) => {
+                Self::new(ErrorCode::ExplorerUnavailable, err)
+            }
+            EtherscanImportError::Timeout(_) => Self::new(ErrorCode::ImportTimeout, err),
+            EtherscanImportError::TooLarge(_) => Self::new(ErrorCode::PayloadTooLarge, err),
+            err => Self::invalid_request(err),
+        }
+    }
+}
+
+impl From<DependencyError> for ApiError {
+    fn from(err: DependencyError) -> Self {
+        match err {
+            DependencyError::Unavailable(_) => {
+                Self::new(ErrorCode::PackageRegistryUnavailable, err)
+            }
+            DependencyError::Cache(..) => Self::internal(err),
+            err => Self::invalid_request(err),
+        }
+    }
+}
+
+impl From<UploadError> for ApiError {
+    fn from(err: UploadError) -> Self {
+        match err {
+            UploadError::TooLarge(_) => Self::new(ErrorCode::PayloadTooLarge, err),
+            UploadError::UnexpectedOffset(_) | UploadError::Completed(_) => {
+                Self::new(ErrorCode::Conflict, err)
+            }
+            UploadError::Storage(_) => Self::storage(err),
+            err => Self::invalid_request(err),
+        }
+    }
+}
+
+impl From<MultipartError> for ApiError {
+    fn from(err: MultipartError) -> Self {
+        Self::invalid_request(err)
+    }
+}
+
+/// Error handler of json request bodies which could not be deserialized.
+pub fn json_error(err: JsonPayloadError, _request: &HttpRequest) -> actix_web::Error {
+    match err {
+        JsonPayloadError::Overflow { .. } | JsonPayloadError::OverflowKnownLength { .. } => {
+            ApiError::new(ErrorCode::PayloadTooLarge, err).into()
+        }
+        err => ApiError::invalid_request(err).into(),
+    }
+}
+
+/// Error handler of path parameters which could not be deserialized (e.g., invalid addresses).
+pub fn path_error(err: PathError, _request: &HttpRequest) -> actix_web::Error {
+    ApiError::invalid_request(err).into()
+}
+
+/// Error handler of query parameters which could not be deserialized.
+pub fn query_error(err: QueryPayloadError, _request: &HttpRequest) -> actix_web::Error {
+    ApiError::invalid_request(err).into()
+}
+
+/// Default service answering requests to unknown routes.
+pub async fn route_not_found(request: HttpRequest) -> ApiError {
+    ApiError::not_found(format!(
+        "Route {} {} not found",
+        request.method(),
+        request.path()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use smart_contract_verifier::tools::hidden_characters;
+
+    #[actix_rt::test]
+    async fn error_is_serialized_with_code() {
+        let err = ApiError::invalid_request("Invalid selector: 0x12");
+        let response = err.error_response();
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(
+            json!({
+                "code": "INVALID_REQUEST",
+                "message": "Invalid selector: 0x12",
+                "details": null,
+            }),
+            serde_json::from_slice::<Value>(&body).unwrap()
+        );
+    }
+
+    #[test]
+    fn verification_errors_are_mapped() {
+        let not_found = ContractNotFound {
+            address: "0xcafecafecafecafecafecafecafecafecafecafe"
+                .parse()
+                .unwrap(),
+            chain_id: Some(9001),
+            hint: None,
+        };
+        let err = ApiError::from(VerificationError::ContractNotFound(not_found));
+        assert_eq!(ErrorCode::ContractNotFound, err.code);
+        assert_eq!(StatusCode::NOT_FOUND, err.status_code());
+        assert_eq!(
+            Some(json!(9001)),
+            err.details.unwrap().get("chain_id").cloned()
+        );
+
+        let err = ApiError::from(VerificationError::Compilation(vec!["error".to_string()]));
+        assert_eq!(ErrorCode::CompilationFailed, err.code);
+        assert_eq!(Some(json!(["error"])), err.details);
+
+        let finding = hidden_characters::scan("Main.sol", "contract A {} // \u{202e}")
+            .pop()
+            .unwrap();
+        let err = ApiError::from(VerificationError::HiddenCharacters(vec![finding]));
+        assert_eq!(ErrorCode::HiddenCharacters, err.code);
+        assert_eq!(StatusCode::BAD_REQUEST, err.status_code());
+        assert_eq!(
+            Some(json!("U+202E")),
+            err.details.unwrap()[0].get("code_point").cloned()
+        );
+
+        let timeout = CompilerError::Timeout(std::time::Duration::from_secs(300));
+        let err = ApiError::from(VerificationError::Internal(anyhow::anyhow!(timeout)));
+        assert_eq!(ErrorCode::CompilationTimeout, err.code);
+        assert_eq!(StatusCode::GATEWAY_TIMEOUT, err.status_code());
+
+        let err = ApiError::from(VerificationError::Internal(anyhow::anyhow!("internal")));
+        assert_eq!(ErrorCode::InternalError, err.code);
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, err.status_code());
+    }
+}
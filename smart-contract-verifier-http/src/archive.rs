@@ -0,0 +1,313 @@
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    io::{Cursor, Read},
+    path::{Component, Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Max number of entries in the archive.
+const MAX_ENTRIES: usize = 2000;
+/// Extensions of the files which are extracted. Other entries (e.g., images or build artifacts)
+/// are skipped without being decompressed.
+const EXTRACTED_EXTENSIONS: [&str; 3] = ["sol", "vy", "json"];
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("invalid zip archive: {0}")]
+    Invalid(#[from] zip::result::ZipError),
+    #[error("archive contains more than {MAX_ENTRIES} entries")]
+    TooManyEntries,
+    #[error("extracted files exceed {0} bytes")]
+    TooLarge(usize),
+    #[error("entry {0} points outside of the archive")]
+    UnsafePath(String),
+    #[error("file {0} is not valid utf-8")]
+    InvalidUtf8(String),
+    #[error("cannot read {0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("{0} is not valid json: {1}")]
+    InvalidJson(String, serde_json::Error),
+    #[error("archive contains no solidity sources")]
+    NoSources,
+}
+
+/// Project extracted from the archive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Project {
+    pub sources: BTreeMap<PathBuf, String>,
+    /// Fields of the multi-part verification request (e.g., `compiler_version`, `evm_version`)
+    /// derived from metadata or standard-json input found in the archive.
+    pub settings: Map<String, Value>,
+}
+
+/// Extracts the zipped project in memory. Decompression is cpu-bound,
+/// so it should be run on blocking threads.
+///
+/// Solidity files (`.sol`) become sources, with a single top-level directory (if any) stripped.
+/// If the archive contains solc `metadata.json`, or standard-json input (`*.json` with
+/// "language" and "sources" keys), compiler settings are taken from it, as well as sources
+/// if there are no `.sol` files.
+pub fn extract_project(archive: &[u8], max_size: usize) -> Result<Project, ArchiveError> {
+    let files = extract_files(archive, max_size)?;
+    let files = strip_common_root(files);
+
+    let mut project = Project::default();
+    let mut json_sources = BTreeMap::new();
+    for (path, content) in &files {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("sol") => {
+                project.sources.insert(path.clone(), content.clone());
+            }
+            Some("json") => {
+                let name = path.to_string_lossy().to_string();
+                let json: Value = match serde_json::from_str(content) {
+                    Ok(json) => json,
+                    // Json files unrelated to compilation (e.g., package.json) are allowed
+                    Err(_) if !is_compilation_file(path) => continue,
+                    Err(err) => return Err(ArchiveError::InvalidJson(name, err)),
+                };
+                if json.get("language").is_some() && json.get("sources").is_some() {
+                    apply_compilation_json(&mut project.settings, &mut json_sources, &json);
+                }
+            }
+            _ => {}
+        }
+    }
+    if project.sources.is_empty() {
+        project.sources = json_sources;
+    }
+    if project.sources.is_empty() {
+        return Err(ArchiveError::NoSources);
+    }
+    Ok(project)
+}
+
+fn is_compilation_file(path: &Path) -> bool {
+    path.file_name().map_or(false, |name| {
+        name == "metadata.json" || name.to_string_lossy().contains("standard")
+    })
+}
+
+fn extract_files(
+    archive: &[u8],
+    max_size: usize,
+) -> Result<BTreeMap<PathBuf, String>, ArchiveError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(archive))?;
+    if archive.len() > MAX_ENTRIES {
+        return Err(ArchiveError::TooManyEntries);
+    }
+
+    let mut size = 0;
+    let mut files = BTreeMap::new();
+    for index in 0..archive.len() {
+        let file = archive.by_index(index)?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        let path = safe_path(&name).ok_or_else(|| ArchiveError::UnsafePath(name.clone()))?;
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        if !extension.map_or(false, |extension| EXTRACTED_EXTENSIONS.contains(&extension)) {
+            continue;
+        }
+
+        // Declared sizes could not be trusted, so reading is limited by the remaining quota
+        let mut content = Vec::new();
+        let remaining = max_size - size;
+        file.take(remaining as u64 + 1)
+            .read_to_end(&mut content)
+            .map_err(|err| ArchiveError::Io(name.clone(), err))?;
+        size += content.len();
+        if size > max_size {
+            return Err(ArchiveError::TooLarge(max_size));
+        }
+
+        let content = String::from_utf8(content).map_err(|_| ArchiveError::InvalidUtf8(name))?;
+        files.insert(path, content);
+    }
+    Ok(files)
+}
+
+/// Returns the relative path of the entry, or `None` if it could point outside of the archive.
+pub(crate) fn safe_path(name: &str) -> Option<PathBuf> {
+    if name.contains('\0') || name.contains('\\') {
+        return None;
+    }
+    let mut path = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(component) => path.push(component),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// Strips the top-level directory shared by all files (e.g., "project/" of "project.zip").
+fn strip_common_root(files: BTreeMap<PathBuf, String>) -> BTreeMap<PathBuf, String> {
+    let root = files
+        .keys()
+        .map(|path| path.components().next())
+        .reduce(|a, b| if a == b { a } else { None })
+        .flatten();
+    let root = match root {
+        Some(root) if files.keys().all(|path| path.components().count() > 1) => {
+            PathBuf::from(root.as_os_str())
+        }
+        _ => return files,
+    };
+    files
+        .into_iter()
+        .map(|(path, content)| {
+            let path = path
+                .strip_prefix(&root)
+                .map(Path::to_path_buf)
+                .unwrap_or(path);
+            (path, content)
+        })
+        .collect()
+}
+
+/// Takes settings and sources from solc metadata or standard-json input.
+fn apply_compilation_json(
+    settings: &mut Map<String, Value>,
+    sources: &mut BTreeMap<PathBuf, String>,
+    json: &Value,
+) {
+    let compiler_settings = &json["settings"];
+    if let Some(version) = json["compiler"]["version"].as_str() {
+        settings.insert("compiler_version".into(), Value::from(version));
+    }
+    if let Some(evm_version) = compiler_settings["evmVersion"].as_str() {
+        settings.insert("evm_version".into(), Value::from(evm_version));
+    }
+    let optimizer = &compiler_settings["optimizer"];
+    if optimizer["enabled"].as_bool() == Some(true) {
+        let runs = optimizer["runs"].as_u64().unwrap_or(200);
+        settings.insert("optimization_runs".into(), Value::from(runs));
+    }
+    let libraries = libraries(&compiler_settings["libraries"]);
+    if !libraries.is_empty() {
+        settings.insert("contract_libraries".into(), Value::from(libraries));
+    }
+
+    for (path, source) in json["sources"].as_object().into_iter().flatten() {
+        if let Some(content) = source["content"].as_str() {
+            sources.insert(PathBuf::from(path), content.to_string());
+        }
+    }
+}
+
+/// Flattens libraries, which are either `{"file": {"Lib": "0x.."}}` (standard-json input)
+/// or `{"file:Lib": "0x.."}` (metadata), into fully qualified library names mapped to addresses.
+fn libraries(libraries: &Value) -> Map<String, Value> {
+    let mut result = Map::new();
+    for (key, value) in libraries.as_object().into_iter().flatten() {
+        match value {
+            Value::String(address) => {
+                result.insert(key.clone(), Value::from(address.as_str()));
+            }
+            Value::Object(file_libraries) => {
+                for (name, address) in file_libraries {
+                    result.insert(format!("{key}:{name}"), address.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+
+    fn zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, content) in files {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn sources_are_extracted() {
+        let archive = zip(&[
+            ("project/contracts/A.sol", "contract A {}"),
+            ("project/package.json", "{ \"name\": \"project\" }"),
+            ("project/README.md", "# Project"),
+        ]);
+        let project = extract_project(&archive, 1024).unwrap();
+        assert_eq!(
+            BTreeMap::from([(
+                PathBuf::from("contracts/A.sol"),
+                "contract A {}".to_string()
+            )]),
+            project.sources
+        );
+        assert!(project.settings.is_empty());
+    }
+
+    #[test]
+    fn settings_are_taken_from_metadata() {
+        let metadata = r#"{
+            "compiler": { "version": "0.8.14+commit.80d49f37" },
+            "language": "Solidity",
+            "settings": {
+                "evmVersion": "london",
+                "optimizer": { "enabled": true, "runs": 1000 },
+                "libraries": { "contracts/L.sol:L": "0xcafecafecafecafecafecafecafecafecafecafe" }
+            },
+            "sources": { "contracts/A.sol": { "keccak256": "0x12" } }
+        }"#;
+        let archive = zip(&[("A.sol", "contract A {}"), ("metadata.json", metadata)]);
+        let project = extract_project(&archive, 1024).unwrap();
+        let expected = serde_json::json!({
+            "compiler_version": "0.8.14+commit.80d49f37",
+            "evm_version": "london",
+            "optimization_runs": 1000,
+            "contract_libraries": { "contracts/L.sol:L": "0xcafecafecafecafecafecafecafecafecafecafe" },
+        });
+        assert_eq!(expected.as_object().unwrap(), &project.settings);
+    }
+
+    #[test]
+    fn unsafe_archives_are_rejected() {
+        let archive = zip(&[("../A.sol", "contract A {}")]);
+        assert!(matches!(
+            extract_project(&archive, 1024),
+            Err(ArchiveError::UnsafePath(_))
+        ));
+        let archive = zip(&[("/etc/A.sol", "contract A {}")]);
+        assert!(matches!(
+            extract_project(&archive, 1024),
+            Err(ArchiveError::UnsafePath(_))
+        ));
+        let archive = zip(&[("A.sol", &"a".repeat(2048))]);
+        assert!(matches!(
+            extract_project(&archive, 1024),
+            Err(ArchiveError::TooLarge(1024))
+        ));
+    }
+
+    #[test]
+    fn unrelated_files_are_skipped() {
+        let archive = zip(&[
+            ("A.sol", "contract A {}"),
+            ("logo.png", "PNG"),
+            ("artifacts/A.bin", &"a".repeat(2048)),
+        ]);
+        let project = extract_project(&archive, 1024).unwrap();
+        assert_eq!(
+            vec![PathBuf::from("A.sol")],
+            project.sources.into_keys().collect::<Vec<_>>()
+        );
+    }
+}
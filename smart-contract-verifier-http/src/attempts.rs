@@ -0,0 +1,197 @@
+use crate::{metrics, DB};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{SourcifyError, VerificationError};
+use std::time::Duration;
+
+/// The reason a verification attempt has failed with.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// The contract has already been verified, and the request has not been made by an admin.
+    AlreadyVerified,
+    Initialization,
+    VersionNotFound,
+    Compilation,
+    Internal,
+    NoMatchingContracts,
+    CompilerVersionMismatch,
+    ContractNotFound,
+    HiddenCharacters,
+}
+
+impl FailureReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureReason::AlreadyVerified => "already_verified",
+            FailureReason::Initialization => "initialization",
+            FailureReason::VersionNotFound => "version_not_found",
+            FailureReason::Compilation => "compilation",
+            FailureReason::Internal => "internal",
+            FailureReason::NoMatchingContracts => "no_matching_contracts",
+            FailureReason::CompilerVersionMismatch => "compiler_version_mismatch",
+            FailureReason::ContractNotFound => "contract_not_found",
+            FailureReason::HiddenCharacters => "hidden_characters",
+        }
+    }
+}
+
+impl From<&VerificationError> for FailureReason {
+    fn from(error: &VerificationError) -> Self {
+        match error {
+            VerificationError::Initialization(_) => FailureReason::Initialization,
+            VerificationError::VersionNotFound(_) => FailureReason::VersionNotFound,
+            VerificationError::Compilation(_) => FailureReason::Compilation,
+            VerificationError::Internal(_) => FailureReason::Internal,
+            VerificationError::NoMatchingContracts(_) => FailureReason::NoMatchingContracts,
+            VerificationError::CompilerVersionMismatch(_) => {
+                FailureReason::CompilerVersionMismatch
+            }
+            VerificationError::ContractNotFound(_) => FailureReason::ContractNotFound,
+            VerificationError::HiddenCharacters(_) => FailureReason::HiddenCharacters,
+        }
+    }
+}
+
+impl From<&SourcifyError> for FailureReason {
+    fn from(error: &SourcifyError) -> Self {
+        match error {
+            SourcifyError::Internal(_) => FailureReason::Internal,
+            SourcifyError::Verification(_) => FailureReason::NoMatchingContracts,
+            // Sourcify rejects requests it cannot start verification for (e.g., without metadata)
+            SourcifyError::Validation(_) => FailureReason::Initialization,
+        }
+    }
+}
+
+/// A single verification attempt, either successful or not.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct VerificationAttempt {
+    pub contract_address: String,
+    /// Is set when the attempt is recorded. `None` for attempts recorded
+    /// before the language has been stored (all of them are solidity ones).
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Verification endpoint used (e.g., "multi-part", "json").
+    pub method: String,
+    pub compiler_version: String,
+    /// Compiler settings provided by the user as a json string.
+    pub settings: Option<String>,
+    /// `None` if the attempt has succeeded.
+    pub failure_reason: Option<FailureReason>,
+    pub message: Option<String>,
+    pub duration_ms: u64,
+    pub attempted_at: DateTime<Utc>,
+}
+
+impl VerificationAttempt {
+    pub fn new(
+        contract_address: &str,
+        method: &str,
+        compiler_version: impl ToString,
+        settings: Option<String>,
+        failure: Option<(FailureReason, String)>,
+        duration: Duration,
+    ) -> Self {
+        let (failure_reason, message) = match failure {
+            Some((reason, message)) => (Some(reason), Some(message)),
+            None => (None, None),
+        };
+        Self {
+            contract_address: contract_address.to_lowercase(),
+            language: None,
+            method: method.to_string(),
+            compiler_version: compiler_version.to_string(),
+            settings,
+            failure_reason,
+            message,
+            duration_ms: duration.as_millis() as u64,
+            attempted_at: Utc::now(),
+        }
+    }
+}
+
+/// Converts the verification error into the failure details stored with the attempt.
+pub fn failure(error: &VerificationError) -> (FailureReason, String) {
+    (error.into(), error.to_string())
+}
+
+/// Same as [`failure`], but for errors of verification through Sourcify.
+pub fn sourcify_failure(error: &SourcifyError) -> (FailureReason, String) {
+    (error.into(), error.to_string())
+}
+
+/// Stores the attempt into the database and accounts its failure reason (if any) in metrics.
+///
+/// Storing errors are only logged, as they should not affect the verification response.
+pub async fn record(database: &DB, language: &str, mut attempt: VerificationAttempt) {
+    attempt.language = Some(language.to_string());
+    if let Some(reason) = attempt.failure_reason {
+        metrics::count_verification_failure(language, &attempt.method, reason.as_str());
+    }
+    if let Err(err) = database.add_verification_attempt(attempt).await {
+        tracing::error!("{}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn failure_reason_labels_match_serialization() {
+        for reason in [
+            FailureReason::AlreadyVerified,
+            FailureReason::Initialization,
+            FailureReason::VersionNotFound,
+            FailureReason::Compilation,
+            FailureReason::Internal,
+            FailureReason::NoMatchingContracts,
+            FailureReason::CompilerVersionMismatch,
+            FailureReason::ContractNotFound,
+            FailureReason::HiddenCharacters,
+        ] {
+            assert_eq!(
+                serde_json::to_value(reason).unwrap(),
+                serde_json::Value::String(reason.as_str().to_string()),
+            );
+        }
+    }
+
+    #[test]
+    fn attempt_without_failure() {
+        let attempt = VerificationAttempt::new(
+            "0xABCD",
+            "json",
+            "v0.8.14+commit.80d49f37",
+            None,
+            None,
+            Duration::from_millis(1500),
+        );
+        assert_eq!("0xabcd", attempt.contract_address);
+        assert_eq!(None, attempt.failure_reason);
+        assert_eq!(None, attempt.message);
+        assert_eq!(1500, attempt.duration_ms);
+    }
+
+    #[test]
+    fn attempt_with_failure() {
+        let attempt = VerificationAttempt::new(
+            "0xabcd",
+            "multi-part",
+            "v0.8.14+commit.80d49f37",
+            None,
+            Some(failure(&VerificationError::NoMatchingContracts(vec![]))),
+            Duration::default(),
+        );
+        assert_eq!(
+            Some(FailureReason::NoMatchingContracts),
+            attempt.failure_reason
+        );
+        assert_eq!(
+            Some(VerificationError::NoMatchingContracts(vec![]).to_string()),
+            attempt.message
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use ethers_core::{
+    types::{Address, Signature},
+    utils::{keccak256, to_checksum},
+};
+use ethers_signers::{LocalWallet, Signer};
+use serde::Serialize;
+use std::{collections::BTreeMap, str::FromStr};
+
+/// Statement that the contract has been verified with the given sources.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct Statement {
+    pub contract_address: String,
+    pub chain_id: u64,
+    /// Keccak-256 hash of the json object mapping source paths into their contents
+    /// (keys are sorted, no whitespaces).
+    pub source_hash: String,
+    /// Is unknown for contracts verified before verification times have been stored.
+    pub verified_at: Option<DateTime<Utc>>,
+    pub issued_at: DateTime<Utc>,
+}
+
+impl Statement {
+    /// The message signed by the service, one `key: value` pair per line.
+    pub fn message(&self) -> String {
+        let verified_at = self
+            .verified_at
+            .map(|verified_at| verified_at.to_rfc3339_opts(SecondsFormat::Secs, true))
+            .unwrap_or_else(|| "unknown".to_string());
+        format!(
+            "Contract verification attestation\n\
+            contract_address: {}\n\
+            chain_id: {}\n\
+            source_hash: {}\n\
+            verified_at: {}\n\
+            issued_at: {}",
+            self.contract_address,
+            self.chain_id,
+            self.source_hash,
+            verified_at,
+            self.issued_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        )
+    }
+}
+
+/// Signed statement, which could be checked by anyone knowing the address of the service.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct Attestation {
+    pub statement: Statement,
+    pub message: String,
+    /// Address of the service key the message is signed with.
+    pub signer: String,
+    /// EIP-191 (`personal_sign`) signature of the message.
+    pub signature: String,
+}
+
+/// Signs attestations with the configured key of the service.
+pub struct Attestor {
+    wallet: LocalWallet,
+}
+
+impl Attestor {
+    pub fn new(private_key: &str) -> anyhow::Result<Self> {
+        let wallet = LocalWallet::from_str(private_key.trim_start_matches("0x"))
+            .map_err(|err| anyhow::anyhow!("invalid attestation private key: {err}"))?;
+        Ok(Self { wallet })
+    }
+
+    pub fn signer(&self) -> Address {
+        self.wallet.address()
+    }
+
+    pub fn attest(&self, statement: Statement) -> Attestation {
+        let message = statement.message();
+        let signature: Signature = self
+            .wallet
+            .sign_hash(ethers_core::utils::hash_message(&message));
+        Attestation {
+            statement,
+            message,
+            signer: to_checksum(&self.signer(), None),
+            signature: format!("0x{signature}"),
+        }
+    }
+}
+
+/// Hash of the sources the contract has been verified with.
+pub fn source_hash(sources: &BTreeMap<String, String>) -> String {
+    let sources = serde_json::to_vec(sources).expect("sources are serializable");
+    format!("0x{}", hex::encode(keccak256(sources)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn attestation_is_signed_by_service() {
+        let attestor =
+            Attestor::new("0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+                .unwrap();
+        let statement = Statement {
+            contract_address: "0xbebebebebebebebebebebebebebebebebebebebe".to_string(),
+            chain_id: 9001,
+            source_hash: source_hash(&BTreeMap::from([(
+                "A.sol".to_string(),
+                "contract A {}".to_string(),
+            )])),
+            verified_at: None,
+            issued_at: DateTime::parse_from_rfc3339("2022-12-01T10:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        };
+
+        let attestation = attestor.attest(statement);
+        assert!(attestation
+            .message
+            .ends_with("verified_at: unknown\nissued_at: 2022-12-01T10:00:00Z"));
+        let signature = Signature::from_str(&attestation.signature).unwrap();
+        assert_eq!(
+            attestor.signer(),
+            signature.recover(attestation.message.as_str()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,502 @@
+use crate::{
+    blobs::BlobStore, chains::Chain, compiler_inputs::ArchivedCompilerInput,
+    handlers::contract_standard_json::reconstruct_compiler_input,
+    verification_response::VerificationResult, verified_contract_result::Verified_Contract_Result,
+    DB,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{
+    rpc::{BlockId, BytecodeFetcher},
+    solidity, Address, MatchAgainst, SolidityClient, VerificationError, Version,
+};
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::Mutex;
+
+/// Number of contracts whose code is fetched at once before being audited.
+const CODE_PREFETCH_SIZE: usize = 100;
+
+/// The result of re-verifying a stored contract against its current on-chain bytecode.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditStatus {
+    /// On-chain bytecode still corresponds to the stored sources.
+    Unchanged,
+    /// On-chain bytecode does not correspond to the stored sources anymore
+    /// (e.g., the contract has been destroyed and redeployed, or is metamorphic).
+    BytecodeChanged,
+    /// There is no code at the contract address anymore.
+    Destroyed,
+    /// Re-verification could not be completed.
+    Failed,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub contract_address: String,
+    pub status: AuditStatus,
+    pub message: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Report of replaying the verification of a stored contract: the archived compiler input
+/// is recompiled with the recorded compiler version and matched against the current on-chain code.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub contract_address: String,
+    pub compiler_version: String,
+    pub status: AuditStatus,
+    /// Match type of the stored verification.
+    pub recorded_match_type: Option<String>,
+    /// Match type of the replayed verification, if the match still holds.
+    pub match_type: Option<String>,
+    /// Whether the recompiled deployed bytecode is identical to the stored one.
+    /// Is `None` if the contract has not been recompiled, or no bytecode has been stored.
+    pub reproducible: Option<bool>,
+    pub message: Option<String>,
+    pub replayed_at: DateTime<Utc>,
+}
+
+impl ReplayReport {
+    pub fn to_audit_record(&self) -> AuditRecord {
+        AuditRecord {
+            contract_address: self.contract_address.clone(),
+            status: self.status,
+            message: self.message.clone(),
+            checked_at: self.replayed_at,
+        }
+    }
+}
+
+/// Re-runs verification for all stored contracts and records the results into the audit history.
+/// Contracts are re-verified by a client without middlewares, so that they are neither stored
+/// again nor reported (e.g., to webhooks) as newly verified.
+pub struct Auditor {
+    client: Arc<SolidityClient>,
+    fetcher: Arc<BytecodeFetcher>,
+    /// Chain contracts are verified for. Its stored contracts are audited.
+    chain: Chain,
+    /// Sources of the stored contracts may be kept in the blob store.
+    blobs: Option<Arc<BlobStore>>,
+    running: Mutex<()>,
+}
+
+impl Auditor {
+    pub fn new(
+        client: &SolidityClient,
+        fetcher: Arc<BytecodeFetcher>,
+        chain: Chain,
+        blobs: Option<Arc<BlobStore>>,
+    ) -> Self {
+        Self {
+            client: Arc::new(client.without_middlewares()),
+            fetcher,
+            chain,
+            blobs,
+            running: Mutex::new(()),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.try_lock().is_err()
+    }
+
+    /// Audits all stored contracts.
+    ///
+    /// Only one audit may be in progress at a time. Returns `false` if
+    /// there is one already, and the call has been ignored.
+    pub async fn run(&self) -> bool {
+        let _guard = match self.running.try_lock() {
+            Ok(guard) => guard,
+            Err(_) => {
+                tracing::warn!("audit of stored contracts is already in progress");
+                return false;
+            }
+        };
+
+        let database = DB::new()
+            .await
+            .change_name(&self.chain.namespace())
+            .with_blobs(self.blobs.clone());
+        let addresses = match database.contract_addresses().await {
+            Ok(addresses) => addresses,
+            Err(err) => {
+                tracing::error!("cannot load stored contract addresses: {}", err);
+                return true;
+            }
+        };
+
+        tracing::info!("auditing {} stored contracts", addresses.len());
+        for chunk in addresses.chunks(CODE_PREFETCH_SIZE) {
+            let codes = self.fetcher.get_code_batch(chunk).await;
+            for (address, code) in chunk.iter().zip(codes) {
+                let cvr = match database.find_contract_verify_response(address).await {
+                    Ok(Some(cvr)) => cvr,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        tracing::error!("cannot load stored contract {}: {}", address, err);
+                        continue;
+                    }
+                };
+
+                let (status, message) = self.audit(&cvr, code).await;
+                if status != AuditStatus::Unchanged {
+                    tracing::warn!(
+                        "audit of contract {} resulted in {:?}: {:?}",
+                        address,
+                        status,
+                        message
+                    );
+                }
+                let record = AuditRecord {
+                    contract_address: address.clone(),
+                    status,
+                    message,
+                    checked_at: Utc::now(),
+                };
+                if let Err(err) = database.add_audit_record(record).await {
+                    tracing::error!("{}", err);
+                }
+            }
+        }
+        tracing::info!("audit of stored contracts finished");
+
+        true
+    }
+
+    async fn audit(
+        &self,
+        cvr: &Verified_Contract_Result,
+        code: Result<Option<String>, anyhow::Error>,
+    ) -> (AuditStatus, Option<String>) {
+        if let Some(status) = code_status(code) {
+            return status;
+        }
+
+        let request = match verification_request(cvr) {
+            Ok(request) => request,
+            Err(err) => return (AuditStatus::Failed, Some(err)),
+        };
+
+        match solidity::standard_json::verify(self.client.clone(), request).await {
+            Ok(_) => (AuditStatus::Unchanged, None),
+            Err(
+                err @ (VerificationError::NoMatchingContracts(_)
+                | VerificationError::CompilerVersionMismatch(_)),
+            ) => (AuditStatus::BytecodeChanged, Some(err.to_string())),
+            Err(err) => (AuditStatus::Failed, Some(err.to_string())),
+        }
+    }
+
+    /// Replays the verification of the stored contract with its archived compiler input.
+    pub async fn replay(
+        &self,
+        cvr: &Verified_Contract_Result,
+        archived: &ArchivedCompilerInput,
+    ) -> ReplayReport {
+        let mut report = ReplayReport {
+            contract_address: cvr.contract_address.clone(),
+            compiler_version: archived.compiler_version.clone(),
+            status: AuditStatus::Failed,
+            recorded_match_type: cvr.result.match_type.clone(),
+            match_type: None,
+            reproducible: None,
+            message: None,
+            replayed_at: Utc::now(),
+        };
+        let code = self.fetcher.get_code(&cvr.contract_address).await;
+        if let Some((status, message)) = code_status(code) {
+            report.status = status;
+            report.message = message;
+            return report;
+        }
+
+        let request = match replay_request(cvr, archived) {
+            Ok(request) => request,
+            Err(err) => {
+                report.message = Some(err);
+                return report;
+            }
+        };
+        match solidity::standard_json::verify(self.client.clone(), request).await {
+            Ok(success) => {
+                let result = VerificationResult::from(success);
+                report.status = AuditStatus::Unchanged;
+                report.reproducible = cvr
+                    .result
+                    .local_deployed_bytecode
+                    .as_ref()
+                    .map(|stored| Some(stored) == result.local_deployed_bytecode.as_ref());
+                report.match_type = result.match_type;
+            }
+            Err(
+                err @ (VerificationError::NoMatchingContracts(_)
+                | VerificationError::CompilerVersionMismatch(_)),
+            ) => {
+                report.status = AuditStatus::BytecodeChanged;
+                report.message = Some(err.to_string());
+            }
+            Err(err) => report.message = Some(err.to_string()),
+        }
+        report
+    }
+}
+
+pub(crate) fn verification_request(
+    cvr: &Verified_Contract_Result,
+) -> Result<solidity::standard_json::VerificationRequest, String> {
+    let compiler_version = Version::from_str(&cvr.result.compiler_version)
+        .map_err(|err| format!("invalid stored compiler version: {err}"))?;
+    let input = reconstruct_compiler_input(&cvr.result)
+        .map_err(|err| format!("invalid stored compiler settings: {err}"))?;
+    Ok(solidity::standard_json::VerificationRequest {
+        contract_address: Address::from_str(&cvr.contract_address)
+            .map_err(|err| format!("invalid stored contract address: {err}"))?,
+        // Creation transaction input is not stored, and is not required
+        // to check the currently deployed bytecode.
+        creation_bytecode: None,
+        compiler_version,
+        block: BlockId::Latest,
+        // Only the stored contract is checked, even if others share its bytecode
+        contract_name: Some(format!(
+            "{}:{}",
+            cvr.result.file_name, cvr.result.contract_name
+        )),
+        match_against: MatchAgainst::Deployed,
+        content: solidity::standard_json::StandardJsonContent {
+            input,
+            extra_settings: Default::default(),
+        },
+    })
+}
+
+/// Request verifying the stored contract with its archived compiler input
+/// and the exact compiler version it has been verified with.
+fn replay_request(
+    cvr: &Verified_Contract_Result,
+    archived: &ArchivedCompilerInput,
+) -> Result<solidity::standard_json::VerificationRequest, String> {
+    if !cvr.result.file_name.ends_with(".sol") {
+        return Err("only solidity contracts can be replayed".to_string());
+    }
+    let mut request = verification_request(cvr)?;
+    request.compiler_version = Version::from_str(&archived.compiler_version)
+        .map_err(|err| format!("invalid archived compiler version: {err}"))?;
+    let (input, extra_settings) =
+        solidity::extra_settings::split_input(archived.decode_input()?)
+            .map_err(|err| format!("invalid archived compiler input: {err}"))?;
+    request.content = solidity::standard_json::StandardJsonContent {
+        input,
+        extra_settings,
+    };
+    Ok(request)
+}
+
+/// Returns the status of the contract if it cannot be re-verified because of its on-chain code.
+fn code_status(
+    code: Result<Option<String>, anyhow::Error>,
+) -> Option<(AuditStatus, Option<String>)> {
+    match code {
+        Err(err) => Some((
+            AuditStatus::Failed,
+            Some(format!("cannot fetch code: {err}")),
+        )),
+        Ok(code) if is_empty_code(code.as_deref()) => Some((AuditStatus::Destroyed, None)),
+        Ok(_) => None,
+    }
+}
+
+fn is_empty_code(code: Option<&str>) -> bool {
+    code.map(|code| code.trim_start_matches("0x").is_empty())
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::{Compressed, ContentEncoding};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn archived(compiler_version: &str, input: serde_json::Value) -> ArchivedCompilerInput {
+        ArchivedCompilerInput {
+            contract_address: "0xbebebebebebebebebebebebebebebebebebebebe".to_string(),
+            compiler_version: compiler_version.to_string(),
+            input: Compressed::with_encoding(&input, ContentEncoding::Gzip).unwrap(),
+            input_hash: None,
+            archived_at: Utc::now(),
+        }
+    }
+
+    fn cvr() -> Verified_Contract_Result {
+        let result: VerificationResult = serde_json::from_value(json!({
+            "file_name": "A.sol",
+            "contract_name": "A",
+            "match_type": "full",
+            "compiler_version": "v0.8.14+commit.80d49f37",
+            "evm_version": "london",
+            "optimization": true,
+            "optimization_runs": 200,
+            "contract_libraries": {},
+            "abi": "[]",
+            "sources": { "A.sol": "contract A {}" },
+            "compiler_settings": r#"{"evmVersion":"london","optimizer":{"enabled":true,"runs":200}}"#,
+        }))
+        .unwrap();
+        Verified_Contract_Result {
+            contract_address: "0xbebebebebebebebebebebebebebebebebebebebe".to_string(),
+            result,
+            sources_of: None,
+            sources_blob: None,
+            compressed_sources: None,
+            repository: None,
+            content_hash: None,
+            license_ids: vec![],
+            verified_at: None,
+            job_id: None,
+            creation: None,
+            token_info: None,
+        }
+    }
+
+    #[test]
+    fn empty_code() {
+        assert!(is_empty_code(None));
+        assert!(is_empty_code(Some("0x")));
+        assert!(is_empty_code(Some("")));
+        assert!(!is_empty_code(Some("0x6080")));
+    }
+
+    #[test]
+    fn contracts_without_code_are_not_reverified() {
+        assert_eq!(
+            Some((AuditStatus::Destroyed, None)),
+            code_status(Ok(Some("0x".to_string())))
+        );
+        assert_eq!(Some((AuditStatus::Destroyed, None)), code_status(Ok(None)));
+        assert_eq!(
+            Some((
+                AuditStatus::Failed,
+                Some("cannot fetch code: timeout".to_string())
+            )),
+            code_status(Err(anyhow::anyhow!("timeout")))
+        );
+        assert_eq!(None, code_status(Ok(Some("0x6080".to_string()))));
+    }
+
+    #[test]
+    fn stored_contract_is_reverified_against_deployed_code() {
+        let cvr = cvr();
+        let request = verification_request(&cvr).unwrap();
+        assert_eq!(
+            Address::from_str(&cvr.contract_address).unwrap(),
+            request.contract_address
+        );
+        assert_eq!(
+            "v0.8.14+commit.80d49f37",
+            request.compiler_version.to_string()
+        );
+        assert_eq!(Some("A.sol:A"), request.contract_name.as_deref());
+        assert_eq!(MatchAgainst::Deployed, request.match_against);
+        assert!(request.creation_bytecode.is_none());
+
+        let input = request.content.input;
+        assert_eq!("Solidity", input.language);
+        assert_eq!(
+            vec!["A.sol"],
+            input
+                .sources
+                .keys()
+                .map(|path| path.to_string_lossy())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(200), input.settings.optimizer.runs);
+    }
+
+    #[test]
+    fn archived_input_is_replayed() {
+        let archived = archived(
+            "v0.8.15+commit.e14f2714",
+            json!({
+                "language": "Solidity",
+                "sources": { "A.sol": { "content": "contract A { uint a; }" } },
+                "settings": {
+                    "optimizer": { "enabled": true, "runs": 999 },
+                    "eofVersion": 1,
+                    "outputSelection": { "*": { "*": ["*"] } },
+                },
+            }),
+        );
+        let request = replay_request(&cvr(), &archived).unwrap();
+        // The archived input is compiled as is, rather than the one reconstructed from the contract
+        assert_eq!(
+            "v0.8.15+commit.e14f2714",
+            request.compiler_version.to_string()
+        );
+        assert_eq!(Some("A.sol:A"), request.contract_name.as_deref());
+        assert_eq!(MatchAgainst::Deployed, request.match_against);
+        let content = request.content;
+        assert_eq!(Some(999), content.input.settings.optimizer.runs);
+        assert_eq!(
+            "contract A { uint a; }",
+            content
+                .input
+                .sources
+                .values()
+                .next()
+                .unwrap()
+                .content
+                .as_str()
+        );
+        // Settings `CompilerInput` does not model are kept
+        assert_eq!(Some(&json!(1)), content.extra_settings.get("eofVersion"));
+    }
+
+    #[test]
+    fn invalid_archived_inputs_are_not_replayed() {
+        let input = json!({
+            "language": "Solidity",
+            "sources": { "A.sol": { "content": "contract A {}" } },
+            "settings": {},
+        });
+
+        let mut vyper = cvr();
+        vyper.result.file_name = "A.vy".to_string();
+        let err = replay_request(&vyper, &archived("v0.8.14+commit.80d49f37", input.clone()))
+            .err()
+            .unwrap();
+        assert_eq!("only solidity contracts can be replayed", err);
+
+        let err = replay_request(&cvr(), &archived("latest", input))
+            .err()
+            .unwrap();
+        assert!(
+            err.starts_with("invalid archived compiler version"),
+            "{err}"
+        );
+
+        let invalid_input = json!({ "language": "Solidity", "sources": 42 });
+        let err = replay_request(&cvr(), &archived("v0.8.14+commit.80d49f37", invalid_input))
+            .err()
+            .unwrap();
+        assert!(err.starts_with("invalid archived compiler input"), "{err}");
+    }
+
+    #[test]
+    fn invalid_stored_contracts_are_not_reverified() {
+        let mut invalid_version = cvr();
+        invalid_version.result.compiler_version = "latest".to_string();
+        let err = verification_request(&invalid_version).err().unwrap();
+        assert!(err.starts_with("invalid stored compiler version"), "{err}");
+
+        let mut invalid_settings = cvr();
+        invalid_settings.result.compiler_settings = "{".to_string();
+        let err = verification_request(&invalid_settings).err().unwrap();
+        assert!(err.starts_with("invalid stored compiler settings"), "{err}");
+
+        let mut invalid_address = cvr();
+        invalid_address.contract_address = "0xcafe".to_string();
+        let err = verification_request(&invalid_address).err().unwrap();
+        assert!(err.starts_with("invalid stored contract address"), "{err}");
+    }
+}
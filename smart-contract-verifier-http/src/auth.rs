@@ -0,0 +1,96 @@
+use crate::api_error::{ApiError, ErrorCode};
+use actix_web::HttpRequest;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// The header admin api keys are expected to be provided through.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+pub type KeyHash = [u8; 32];
+
+/// Sha256 hash of the api key. Only hashes of the keys are kept in memory.
+pub fn key_hash(key: &str) -> KeyHash {
+    Sha256::digest(key).into()
+}
+
+fn constant_time_eq(a: &KeyHash, b: &KeyHash) -> bool {
+    a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Returns the value registered for the api key the request has been made with, if any.
+/// All the hashes are compared in constant time, so that the response time
+/// tells nothing about how close the provided key is to the registered ones.
+pub fn lookup<'a, T>(keys: &'a [(KeyHash, T)], request: &HttpRequest) -> Option<&'a T> {
+    let key = request.headers().get(API_KEY_HEADER)?.to_str().ok()?;
+    let hash = key_hash(key);
+    keys.iter().fold(None, |found, (registered, value)| {
+        match constant_time_eq(registered, &hash) {
+            true => Some(value),
+            false => found,
+        }
+    })
+}
+
+/// Resolves admin api keys provided with requests into corresponding admin names.
+#[derive(Clone, Debug, Default)]
+pub struct AdminAuth {
+    /// Hashes of api keys with the names of the admins they belong to.
+    admins: Vec<(KeyHash, String)>,
+}
+
+impl AdminAuth {
+    /// Initializes authentication from a map of admin names into their api keys.
+    pub fn new(api_keys: &BTreeMap<String, String>) -> Self {
+        let admins = api_keys
+            .iter()
+            .map(|(name, key)| (key_hash(key), name.clone()))
+            .collect();
+        Self { admins }
+    }
+
+    /// Returns the name of the admin the request has been made by, if any.
+    pub fn authenticate(&self, request: &HttpRequest) -> Option<String> {
+        lookup(&self.admins, request).cloned()
+    }
+
+    /// Same as [`AdminAuth::authenticate`], but fails with 401 Unauthorized
+    /// if the request has not been made by an admin.
+    pub fn require_admin(&self, request: &HttpRequest) -> Result<String, ApiError> {
+        self.authenticate(request).ok_or_else(|| {
+            ApiError::new(ErrorCode::Unauthorized, "Valid admin api key is required")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use pretty_assertions::assert_eq;
+
+    fn auth() -> AdminAuth {
+        AdminAuth::new(&BTreeMap::from([
+            ("alice".to_string(), "alice-key".to_string()),
+            ("bob".to_string(), "bob-key".to_string()),
+        ]))
+    }
+
+    #[test]
+    fn authenticates_known_keys() {
+        let request = TestRequest::default()
+            .insert_header((API_KEY_HEADER, "bob-key"))
+            .to_http_request();
+        assert_eq!(Some("bob".to_string()), auth().authenticate(&request));
+    }
+
+    #[test]
+    fn rejects_unknown_or_missing_keys() {
+        let request = TestRequest::default()
+            .insert_header((API_KEY_HEADER, "unknown-key"))
+            .to_http_request();
+        assert_eq!(None, auth().authenticate(&request));
+
+        let request = TestRequest::default().to_http_request();
+        assert_eq!(None, auth().authenticate(&request));
+    }
+}
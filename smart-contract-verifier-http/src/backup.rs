@@ -0,0 +1,280 @@
+use crate::{chains::Chain, leader, object_storage::ObjectStorage, DB};
+use anyhow::Context;
+use chrono::Utc;
+use cron::Schedule;
+use futures::TryStreamExt;
+use mongodb::bson::Document;
+use smart_contract_verifier::scheduler;
+use std::{
+    collections::BTreeMap,
+    io::{Cursor, Read, Seek, Write},
+    sync::Arc,
+};
+use tokio::sync::mpsc;
+
+const SNAPSHOT_EXTENSION: &str = ".zip";
+const COLLECTION_EXTENSION: &str = ".bson";
+const BACKUP_JOB: &str = "backup";
+/// Max number of documents read from the database ahead of the snapshot writer.
+const DUMP_BUFFER_SIZE: usize = 1024;
+
+/// Periodically stores snapshots of the database of the current chain,
+/// keeping only `keep_last` latest of them. Of several replicas of the service,
+/// each scheduled snapshot is stored by one only.
+pub fn spawn_backup_job(
+    storage: ObjectStorage,
+    schedule: Schedule,
+    keep_last: usize,
+    chain: Chain,
+) {
+    let storage = Arc::new(storage);
+    let job_schedule = schedule.clone();
+    scheduler::spawn_job(job_schedule, "backup the database", move || {
+        let storage = storage.clone();
+        let schedule = schedule.clone();
+        let namespace = chain.namespace();
+        async move {
+            if let Err(err) = backup(&storage, &schedule, keep_last, &namespace).await {
+                tracing::error!("database backup failed: {:#}", err);
+            }
+        }
+    });
+}
+
+async fn backup(
+    storage: &ObjectStorage,
+    schedule: &Schedule,
+    keep_last: usize,
+    namespace: &str,
+) -> anyhow::Result<()> {
+    let database = DB::new().await.change_name(namespace);
+    let claimed = leader::claim_scheduled_run(&database, BACKUP_JOB, schedule)
+        .await
+        .map_err(anyhow::Error::msg)?;
+    if !claimed {
+        tracing::debug!("database backup is run by another replica");
+        return Ok(());
+    }
+
+    // Documents are written into a temporary file as they are read,
+    // so that the database is never held in memory whole
+    let (entries, mut received) = mpsc::channel(DUMP_BUFFER_SIZE);
+    let writing = tokio::task::spawn_blocking(move || -> anyhow::Result<std::fs::File> {
+        let entries = std::iter::from_fn(|| received.blocking_recv());
+        let mut file = encode(tempfile::tempfile()?, entries)?;
+        file.rewind()?;
+        Ok(file)
+    });
+    let dumped = dump(&database, entries).await;
+    let file = writing.await??;
+    let documents = dumped?;
+    let size = file.metadata()?.len();
+
+    let name = format!(
+        "{}{}{}",
+        namespace_prefix(namespace),
+        Utc::now().format("%Y%m%dT%H%M%SZ"),
+        SNAPSHOT_EXTENSION
+    );
+    storage
+        .save_file(&name, tokio::fs::File::from_std(file))
+        .await
+        .with_context(|| format!("cannot save snapshot {name}"))?;
+    tracing::info!(
+        documents,
+        size,
+        "database snapshot {} has been stored",
+        name
+    );
+
+    let snapshots = snapshots(storage, namespace).await?;
+    for name in outdated(&snapshots, keep_last) {
+        match storage.remove(name).await {
+            Ok(()) => tracing::debug!("outdated snapshot {} has been removed", name),
+            Err(err) => tracing::warn!("cannot remove outdated snapshot {}: {:#}", name, err),
+        }
+    }
+    Ok(())
+}
+
+/// Restores the latest snapshot into the database of the current chain.
+/// Nothing is restored if there are verified contracts in the database already,
+/// so that existing data is never overridden.
+pub async fn restore_latest(storage: &ObjectStorage, chain: &Chain) -> anyhow::Result<()> {
+    let namespace = chain.namespace();
+    let database = DB::new().await.change_name(&namespace);
+    let verified = database
+        .count_verified_contracts()
+        .await
+        .map_err(anyhow::Error::msg)?;
+    if verified > 0 {
+        tracing::info!(
+            verified,
+            "database is not empty, snapshot restoration is skipped"
+        );
+        return Ok(());
+    }
+
+    let name = match snapshots(storage, &namespace).await?.pop() {
+        Some(name) => name,
+        None => {
+            tracing::warn!("there are no snapshots of {} to be restored", namespace);
+            return Ok(());
+        }
+    };
+    let snapshot = storage
+        .load(&name)
+        .await
+        .with_context(|| format!("cannot load snapshot {name}"))?;
+    let collections = tokio::task::spawn_blocking(move || decode(&snapshot))
+        .await?
+        .with_context(|| format!("snapshot {name} is invalid"))?;
+    database
+        .restore_collections(collections)
+        .await
+        .map_err(anyhow::Error::msg)?;
+    // Have been dropped along with the replaced collections
+    database
+        .create_indexes()
+        .await
+        .map_err(anyhow::Error::msg)?;
+    tracing::info!("database has been restored from snapshot {}", name);
+    Ok(())
+}
+
+/// Sends documents of all collections of the database to be written into the snapshot.
+/// Returns the number of the sent documents.
+async fn dump(database: &DB, entries: mpsc::Sender<Entry>) -> anyhow::Result<usize> {
+    let mut documents = 0;
+    for collection in database
+        .collection_names()
+        .await
+        .map_err(anyhow::Error::msg)?
+    {
+        let mut cursor = database
+            .dump_collection(&collection)
+            .await
+            .map_err(anyhow::Error::msg)?;
+        send(&entries, Entry::Collection(collection.clone())).await?;
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .with_context(|| format!("cannot dump the {collection} collection"))?
+        {
+            send(&entries, Entry::Document(document)).await?;
+            documents += 1;
+        }
+    }
+    Ok(documents)
+}
+
+async fn send(entries: &mpsc::Sender<Entry>, entry: Entry) -> anyhow::Result<()> {
+    entries
+        .send(entry)
+        .await
+        .map_err(|_| anyhow::anyhow!("snapshot writer has stopped"))
+}
+
+/// Returns names of the stored snapshots of the namespace, the oldest go first
+/// (names contain creation times formatted to be sorted lexicographically).
+async fn snapshots(storage: &ObjectStorage, namespace: &str) -> anyhow::Result<Vec<String>> {
+    let mut names = storage.list(&namespace_prefix(namespace)).await?;
+    names.retain(|name| name.ends_with(SNAPSHOT_EXTENSION));
+    Ok(names)
+}
+
+fn namespace_prefix(namespace: &str) -> String {
+    format!("{namespace}-")
+}
+
+/// Returns snapshots to be removed, so that only `keep_last` latest ones are kept.
+fn outdated(snapshots: &[String], keep_last: usize) -> &[String] {
+    &snapshots[..snapshots.len().saturating_sub(keep_last)]
+}
+
+/// Part of the snapshot, written in the order the parts are dumped.
+#[derive(Debug)]
+enum Entry {
+    /// Starts the file of the collection the following documents belong to.
+    Collection(String),
+    Document(Document),
+}
+
+/// Snapshot is a zip archive containing a `<collection>.bson` file per each collection,
+/// which consists of the collection documents one after another (as dumped by `mongodump`).
+fn encode<W: Write + Seek>(writer: W, entries: impl Iterator<Item = Entry>) -> anyhow::Result<W> {
+    let mut writer = zip::ZipWriter::new(writer);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for entry in entries {
+        match entry {
+            Entry::Collection(collection) => {
+                writer.start_file(format!("{collection}{COLLECTION_EXTENSION}"), options)?
+            }
+            Entry::Document(document) => document.to_writer(&mut writer)?,
+        }
+    }
+    Ok(writer.finish()?)
+}
+
+fn decode(snapshot: &[u8]) -> anyhow::Result<BTreeMap<String, Vec<Document>>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(snapshot))?;
+    let mut collections = BTreeMap::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let collection = match file.name().strip_suffix(COLLECTION_EXTENSION) {
+            Some(collection) => collection.to_string(),
+            None => continue,
+        };
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+
+        let mut reader = content.as_slice();
+        let mut documents = Vec::new();
+        while !reader.is_empty() {
+            documents.push(Document::from_reader(&mut reader)?);
+        }
+        collections.insert(collection, documents);
+    }
+    Ok(collections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{doc, DateTime};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn snapshot_is_decoded() {
+        let collections = BTreeMap::from([
+            (
+                "cvr".to_string(),
+                vec![
+                    doc! { "contract_address": "0xbebe", "verified_at": DateTime::from_millis(1669852800000) },
+                    doc! { "contract_address": "0xcafe", "result": { "file_name": "A.sol" } },
+                ],
+            ),
+            ("audit".to_string(), vec![]),
+        ]);
+        let entries = collections.iter().flat_map(|(collection, documents)| {
+            std::iter::once(Entry::Collection(collection.clone()))
+                .chain(documents.iter().cloned().map(Entry::Document))
+        });
+        let snapshot = encode(Cursor::new(Vec::new()), entries)
+            .unwrap()
+            .into_inner();
+        assert_eq!(collections, decode(&snapshot).unwrap());
+    }
+
+    #[test]
+    fn latest_snapshots_are_kept() {
+        let snapshots = [
+            "mainnet-20221201T000000Z.zip".to_string(),
+            "mainnet-20221202T000000Z.zip".to_string(),
+            "mainnet-20221203T000000Z.zip".to_string(),
+        ];
+        assert_eq!(&snapshots[..1], outdated(&snapshots, 2));
+        assert!(outdated(&snapshots, 5).is_empty());
+    }
+}
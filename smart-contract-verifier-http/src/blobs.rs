@@ -0,0 +1,103 @@
+use crate::{compression::ContentEncoding, object_storage::ObjectStorage};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Stores values serialized as json under keys derived from their contents,
+/// so that identical values (e.g., sources of clones) are stored once.
+/// Values are compressed with the configured encoding, which is tracked by the key extension.
+///
+/// If enabled, large values (sources, compiler outputs) are kept in the blob store
+/// rather than in database documents. It is passed to the database by its users
+/// (see [`crate::DB::with_blobs`]), as documents may reference the blobs.
+pub struct BlobStore {
+    storage: ObjectStorage,
+    /// Values smaller than that (in bytes) are not offloaded.
+    min_size: usize,
+    /// Encoding newly stored values are compressed with.
+    encoding: ContentEncoding,
+}
+
+impl BlobStore {
+    pub fn new(storage: ObjectStorage, min_size: usize, encoding: ContentEncoding) -> Self {
+        Self {
+            storage,
+            min_size,
+            encoding,
+        }
+    }
+
+    /// Stores the value if it is large enough, returning the key it could be loaded by.
+    pub async fn offload<T: Serialize>(
+        &self,
+        kind: &str,
+        value: &T,
+    ) -> Result<Option<String>, String> {
+        let content = serde_json::to_vec(value)
+            .map_err(|err| format!("Cannot serialize the {kind}: {err}"))?;
+        if content.len() < self.min_size {
+            return Ok(None);
+        }
+        let key = blob_key(kind, &content, self.encoding);
+        let content = self
+            .encoding
+            .encode(&content)
+            .map_err(|err| format!("Cannot compress the {kind}: {err}"))?;
+        match self.storage.save(&key, content).await {
+            Ok(()) => Ok(Some(key)),
+            Err(err) => Err(format!("Cannot save the {kind} blob: {err:#}")),
+        }
+    }
+
+    pub async fn load<T: DeserializeOwned>(&self, key: &str) -> Result<T, String> {
+        let content = self
+            .storage
+            .load(key)
+            .await
+            .map_err(|err| format!("Cannot load the {key} blob: {err:#}"))?;
+        let content = ContentEncoding::from_file_name(key)
+            .decode(&content)
+            .map_err(|err| format!("Cannot decompress the {key} blob: {err}"))?;
+        serde_json::from_slice(&content).map_err(|err| format!("The {key} blob is invalid: {err}"))
+    }
+}
+
+fn blob_key(kind: &str, content: &[u8], encoding: ContentEncoding) -> String {
+    format!(
+        "{kind}/{}.json{}",
+        hex::encode(Sha256::digest(content)),
+        encoding.extension()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn only_large_values_are_offloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let blobs = BlobStore::new(
+            ObjectStorage::Local(dir.path().to_path_buf()),
+            32,
+            ContentEncoding::Zstd,
+        );
+
+        let small = BTreeMap::from([("A.sol", "contract A {}")]);
+        assert_eq!(None, blobs.offload("sources", &small).await.unwrap());
+
+        let large = BTreeMap::from([("A.sol", "contract A {}"), ("B.sol", "contract B {}")]);
+        let key = blobs.offload("sources", &large).await.unwrap().unwrap();
+        assert!(key.starts_with("sources/"));
+        assert!(key.ends_with(".zst"));
+        let loaded: BTreeMap<String, String> = blobs.load(&key).await.unwrap();
+        assert_eq!(
+            BTreeMap::from([
+                ("A.sol".to_string(), "contract A {}".to_string()),
+                ("B.sol".to_string(), "contract B {}".to_string())
+            ]),
+            loaded
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{
+    chains::Chain,
+    compiler_inputs::ArchivedCompilerInput,
+    leader,
+    persistence::{ResultMiddlewares, VerifiedContract},
+    DB,
+};
+use actix_web::web;
+use anyhow::Context;
+use serde::Deserialize;
+use smart_contract_verifier::{
+    rpc::BlockId, solidity, Address, MatchAgainst, SolidityClient, Version,
+};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+const BOOTSTRAP_JOB: &str = "bootstrap";
+/// Time the bootstrap is claimed by a replica for. Once it passes, the bootstrap
+/// is run by the next starting replica, if the claiming one has not completed it.
+const BOOTSTRAP_CLAIM_HOURS: i64 = 1;
+
+/// Manifest of the canonical contracts of the chain (e.g., WEVMOS or ERC-20 contracts
+/// of the `x/erc20` module token pairs), seeded into a fresh deployment.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub contracts: Vec<CanonicalContract>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct CanonicalContract {
+    /// Is used in logs only.
+    pub label: String,
+    pub address: Address,
+    pub compiler_version: String,
+    /// Name of the contract to verify, plain or fully qualified (`file.sol:Name`).
+    pub contract_name: Option<String>,
+    /// Standard json input the contract has been compiled with.
+    /// Relative paths are resolved against the directory of the manifest.
+    pub input: PathBuf,
+}
+
+impl Manifest {
+    /// Loads the manifest, so that invalid manifests prevent the service from starting.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read manifest {}", path.display()))?;
+        let mut manifest: Manifest = toml::from_str(&content)
+            .with_context(|| format!("invalid manifest {}", path.display()))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for contract in &mut manifest.contracts {
+            Version::from_str(&contract.compiler_version).map_err(|err| {
+                anyhow::anyhow!("invalid compiler version of {}: {err}", contract.label)
+            })?;
+            contract.input = dir.join(&contract.input);
+        }
+        Ok(manifest)
+    }
+}
+
+/// Verifies the canonical contracts of the manifest in the background and stores them
+/// the same way as contracts verified by users, so that a fresh deployment is not empty,
+/// and clones of the canonical contracts are verified by similarity.
+///
+/// Contracts which are verified already are skipped, so that only the first startup
+/// (and the ones following manifest updates) verifies them. Of several replicas
+/// starting at once, only one bootstraps the contracts.
+pub fn spawn_bootstrap(
+    manifest: Manifest,
+    client: web::Data<SolidityClient>,
+    middlewares: web::Data<ResultMiddlewares>,
+    chain: Chain,
+) {
+    tokio::spawn(async move {
+        let database = DB::new().await.change_name(&chain.namespace());
+        let claim = chrono::Duration::hours(BOOTSTRAP_CLAIM_HOURS);
+        match leader::claim_once(&database, BOOTSTRAP_JOB, claim).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::info!("canonical contracts are bootstrapped by another replica");
+                return;
+            }
+            Err(err) => {
+                tracing::error!("cannot bootstrap canonical contracts: {}", err);
+                return;
+            }
+        }
+        let (mut seeded, mut skipped) = (0, 0);
+        for contract in &manifest.contracts {
+            match database
+                .is_contract_verified(contract.address.as_str())
+                .await
+            {
+                Ok(true) => {
+                    skipped += 1;
+                    continue;
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    tracing::error!("cannot bootstrap {}: {}", contract.label, err);
+                    continue;
+                }
+            }
+            match verify(contract, client.clone(), &middlewares).await {
+                Ok(verified) => match middlewares.run(&verified).await {
+                    Ok(()) => seeded += 1,
+                    Err(err) => tracing::error!("cannot store {}: {}", contract.label, err),
+                },
+                Err(err) => tracing::error!("cannot bootstrap {}: {}", contract.label, err),
+            }
+        }
+        if let Err(err) = leader::release(&database, BOOTSTRAP_JOB).await {
+            tracing::warn!("cannot release the bootstrap claim: {}", err);
+        }
+        tracing::info!(
+            seeded,
+            skipped,
+            failed = manifest.contracts.len() - seeded - skipped,
+            "canonical contracts have been bootstrapped"
+        );
+    });
+}
+
+async fn verify(
+    contract: &CanonicalContract,
+    client: web::Data<SolidityClient>,
+    middlewares: &ResultMiddlewares,
+) -> Result<VerifiedContract, String> {
+    let input = tokio::fs::read(&contract.input)
+        .await
+        .map_err(|err| format!("cannot read {}: {err}", contract.input.display()))?;
+    let input = serde_json::from_slice(&input)
+        .map_err(|err| format!("invalid standard json input: {err}"))?;
+    let (input, extra_settings) = solidity::extra_settings::split_input(input)
+        .map_err(|err| format!("invalid standard json input: {err}"))?;
+    let request = solidity::standard_json::VerificationRequest {
+        contract_address: contract.address.clone(),
+        creation_bytecode: None,
+        compiler_version: Version::from_str(&contract.compiler_version)
+            .map_err(|err| err.to_string())?,
+        block: BlockId::Latest,
+        contract_name: contract.contract_name.clone(),
+        match_against: MatchAgainst::Deployed,
+        content: solidity::standard_json::StandardJsonContent {
+            input,
+            extra_settings,
+        },
+    };
+    let success = solidity::standard_json::verify(client.into_inner(), request)
+        .await
+        .map_err(|err| format!("verification failed: {err}"))?;
+
+    let contract_address = contract.address.to_string();
+    Ok(VerifiedContract {
+        debug_info: middlewares.debug_info(&contract_address, &success),
+        compiler_input: ArchivedCompilerInput::new(
+            &contract_address,
+            &success,
+            middlewares.encoding(),
+        ),
+        contract_address,
+        result: middlewares.verification_result(success),
+        repository: None,
+        replaced_by: None,
+        job_id: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn manifest_is_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("canonical.toml");
+        std::fs::write(
+            &path,
+            r#"
+            [[contracts]]
+            label = "WEVMOS"
+            address = "0xd4949664cd82660aae99bedc034a0dea8a0bd517"
+            compiler_version = "v0.4.18+commit.9cf6e910"
+            contract_name = "WETH9"
+            input = "inputs/wevmos.json"
+            "#,
+        )
+        .unwrap();
+        let manifest = Manifest::load(&path).unwrap();
+        assert_eq!(1, manifest.contracts.len());
+        assert_eq!(
+            dir.path().join("inputs/wevmos.json"),
+            manifest.contracts[0].input
+        );
+
+        std::fs::write(
+            &path,
+            r#"
+            [[contracts]]
+            label = "WEVMOS"
+            address = "0xd4949664cd82660aae99bedc034a0dea8a0bd517"
+            compiler_version = "0.4.18"
+            input = "inputs/wevmos.json"
+            "#,
+        )
+        .unwrap();
+        assert!(Manifest::load(&path).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{auth::API_KEY_HEADER, tenants, verification_response::VerificationResult};
+use actix_web::{
+    http::header::{self, CacheControl, CacheDirective, ETag, EntityTag, Header, IfNoneMatch},
+    HttpRequest, HttpResponse,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Period (in seconds) clients and CDNs may reuse the response without revalidation.
+/// Is short, as stored verifications could be removed or overridden by admins.
+const MAX_AGE: u32 = 60;
+
+/// Hex encoded sha256 hash of the verification result, sources included.
+/// Is stored along with the result and used as an ETag of read endpoints.
+pub fn content_hash(result: &VerificationResult) -> String {
+    let content = serde_json::to_vec(result).expect("verification result is serializable");
+    hex::encode(Sha256::digest(content))
+}
+
+/// Returns 304 Not Modified if the client already has the content with the given hash
+/// (judging by `If-None-Match` header), and the content as json otherwise.
+///
+/// Contracts of tenants are looked up by the same urls as public ones, so responses vary
+/// by api keys, and responses served for tenants are not stored by shared caches.
+pub fn cached_json<T: Serialize>(
+    request: &HttpRequest,
+    content_hash: &str,
+    body: &T,
+) -> HttpResponse {
+    let etag = EntityTag::new_strong(content_hash.to_string());
+    let visibility = match tenants::current() {
+        Some(_) => CacheDirective::Private,
+        None => CacheDirective::Public,
+    };
+    let cache_control = CacheControl(vec![visibility, CacheDirective::MaxAge(MAX_AGE)]);
+    let not_modified = is_not_modified(request, &etag);
+    let mut response = match not_modified {
+        true => HttpResponse::NotModified(),
+        false => HttpResponse::Ok(),
+    };
+    response
+        .insert_header(ETag(etag))
+        .insert_header(cache_control)
+        .insert_header((header::VARY, API_KEY_HEADER));
+    match not_modified {
+        true => response.finish(),
+        false => response.json(body),
+    }
+}
+
+fn is_not_modified(request: &HttpRequest, etag: &EntityTag) -> bool {
+    match IfNoneMatch::parse(request) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(items)) => items.iter().any(|item| item.weak_eq(etag)),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        http::{header, StatusCode},
+        test::TestRequest,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn unchanged_content_is_not_returned() {
+        let request = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"other\", W/\"1234\""))
+            .to_http_request();
+        let response = cached_json(&request, "1234", &"content");
+        assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+        assert_eq!(
+            Some("\"1234\""),
+            response
+                .headers()
+                .get(header::ETAG)
+                .and_then(|etag| etag.to_str().ok())
+        );
+    }
+
+    #[test]
+    fn changed_content_is_returned() {
+        let request = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"1234\""))
+            .to_http_request();
+        let response = cached_json(&request, "5678", &"content");
+        assert_eq!(StatusCode::OK, response.status());
+        let response = cached_json(
+            &TestRequest::default().to_http_request(),
+            "5678",
+            &"content",
+        );
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response.headers().contains_key(header::CACHE_CONTROL));
+    }
+
+    #[tokio::test]
+    async fn tenant_content_is_not_cached_publicly() {
+        let value_of = |response: &HttpResponse, name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let request = TestRequest::default().to_http_request();
+        let response = cached_json(&request, "1234", &"content");
+        assert_eq!(
+            Some("public, max-age=60".to_string()),
+            value_of(&response, header::CACHE_CONTROL)
+        );
+        assert_eq!(
+            Some("x-api-key".to_string()),
+            value_of(&response, header::VARY)
+        );
+
+        let response = tenants::scope(Some("appchain".to_string()), async {
+            cached_json(&request, "1234", &"content")
+        })
+        .await;
+        assert_eq!(
+            Some("private, max-age=60".to_string()),
+            value_of(&response, header::CACHE_CONTROL)
+        );
+    }
+}
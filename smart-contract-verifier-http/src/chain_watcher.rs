@@ -0,0 +1,238 @@
+use crate::{
+    chains::Chain,
+    derived::{self, DerivedVerification},
+    intents,
+    settings::ChainWatcherSettings,
+    DisplayBytes, DB,
+};
+use anyhow::Context;
+use chrono::Utc;
+use futures::{SinkExt, StreamExt};
+use smart_contract_verifier::rpc::{self, BytecodeFetcher};
+use std::{ops::RangeInclusive, str::FromStr, sync::Arc, time::Duration};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Subscribes to new blocks of the chain and marks contracts created in them as verified
+/// by similarity, if they are clones of verified contracts (see [`derived::find_source`]).
+///
+/// The subscription is renewed after disconnections; blocks produced in the meantime
+/// are processed once it is renewed, up to `max_catch_up_blocks` latest of them.
+///
+/// If `match_intents` is set, created contracts are matched against pending verification
+/// intents first (see [`intents::match_deployment`]).
+pub fn spawn_watcher(
+    settings: ChainWatcherSettings,
+    fetcher: Arc<BytecodeFetcher>,
+    match_intents: bool,
+    chain: Chain,
+) {
+    let namespace = chain.namespace();
+    tokio::spawn(async move {
+        let database = DB::new().await.change_name(&namespace);
+        let mut last_block = None;
+        loop {
+            let watched = watch(
+                &database,
+                &fetcher,
+                &settings,
+                match_intents,
+                &mut last_block,
+            );
+            if let Err(err) = watched.await {
+                tracing::warn!("chain watcher has been disconnected: {:#}", err);
+            }
+            tokio::time::sleep(Duration::from_secs(settings.reconnect_delay_secs)).await;
+        }
+    });
+}
+
+async fn watch(
+    database: &DB,
+    fetcher: &BytecodeFetcher,
+    settings: &ChainWatcherSettings,
+    match_intents: bool,
+    last_block: &mut Option<u64>,
+) -> anyhow::Result<()> {
+    let (mut socket, _) = tokio_tungstenite::connect_async(settings.ws_url.as_str())
+        .await
+        .context("cannot connect to the websocket rpc")?;
+    let subscribe = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "eth_subscribe",
+        "params": ["newHeads"],
+    });
+    socket.send(Message::Text(subscribe.to_string())).await?;
+    tracing::info!("chain watcher has subscribed to new blocks");
+
+    while let Some(message) = socket.next().await {
+        let head = match message? {
+            Message::Text(text) => new_head(&text)?,
+            Message::Close(_) => break,
+            _ => None,
+        };
+        let head = match head {
+            Some(head) => head,
+            None => continue,
+        };
+        for block_number in blocks_to_process(*last_block, head, settings.max_catch_up_blocks) {
+            // The block is processed again along with the next head, unless it falls out
+            // of `max_catch_up_blocks` by then
+            if let Err(err) = process_block(database, fetcher, block_number, match_intents).await {
+                tracing::warn!("cannot process block {}: {}", block_number, err);
+                break;
+            }
+            *last_block = Some(block_number);
+        }
+    }
+    Err(anyhow::anyhow!("the subscription has been closed"))
+}
+
+/// Returns the number of the block announced by the message, if it is a new heads notification.
+fn new_head(message: &str) -> anyhow::Result<Option<u64>> {
+    let message: serde_json::Value = serde_json::from_str(message)?;
+    if let Some(error) = message.get("error") {
+        anyhow::bail!("the subscription has failed: {error}");
+    }
+    let number = match message
+        .pointer("/params/result/number")
+        .and_then(|number| number.as_str())
+    {
+        Some(number) => number,
+        None => return Ok(None),
+    };
+    Ok(Some(u64::from_str_radix(
+        number.trim_start_matches("0x"),
+        16,
+    )?))
+}
+
+/// Returns blocks following the last processed one up to the new head. Blocks are processed
+/// again if the head does not follow the last block (e.g., the chain has been reorganized).
+fn blocks_to_process(last_block: Option<u64>, head: u64, max_catch_up: u64) -> RangeInclusive<u64> {
+    match last_block {
+        Some(last_block) if last_block < head => {
+            (last_block + 1).max(head.saturating_sub(max_catch_up))..=head
+        }
+        _ => head..=head,
+    }
+}
+
+/// What has been done with a contract created in the block.
+enum Processed {
+    MatchedIntent,
+    Derived,
+}
+
+async fn process_block(
+    database: &DB,
+    fetcher: &BytecodeFetcher,
+    block_number: u64,
+    match_intents: bool,
+) -> Result<(), String> {
+    let created = fetcher
+        .find_created_contracts(block_number)
+        .await
+        .map_err(|err| format!("cannot find created contracts: {err:#}"))?;
+    if created.is_empty() {
+        return Ok(());
+    }
+
+    let addresses: Vec<_> = created
+        .iter()
+        .map(|contract| contract.address.to_string())
+        .collect();
+    let codes = fetcher.get_code_batch(&addresses).await;
+    let (mut matched, mut derived) = (0, 0);
+    for (contract, code) in created.into_iter().zip(codes) {
+        let code = match code
+            .ok()
+            .flatten()
+            .map(|code| DisplayBytes::from_str(&code))
+        {
+            Some(Ok(code)) => code,
+            // E.g., the contract has self-destructed in the same block
+            _ => continue,
+        };
+        // Failures of a contract do not prevent other contracts of the block from being processed
+        match process_contract(database, &contract, &code, match_intents).await {
+            Ok(Some(Processed::MatchedIntent)) => matched += 1,
+            Ok(Some(Processed::Derived)) => derived += 1,
+            Ok(None) => {}
+            Err(err) => tracing::warn!(
+                block_number,
+                "cannot process contract {}: {}",
+                contract.address,
+                err
+            ),
+        }
+    }
+    if matched > 0 {
+        tracing::info!(
+            block_number,
+            "{} contracts created in the block match verification intents",
+            matched
+        );
+    }
+    if derived > 0 {
+        tracing::info!(
+            block_number,
+            "{} contracts created in the block have been verified by similarity",
+            derived
+        );
+    }
+    Ok(())
+}
+
+async fn process_contract(
+    database: &DB,
+    contract: &rpc::CreatedContract,
+    code: &DisplayBytes,
+    match_intents: bool,
+) -> Result<Option<Processed>, String> {
+    let address = &contract.address;
+    if let (true, Some(init_code)) = (match_intents, &contract.init_code) {
+        if intents::match_deployment(database, address, init_code).await? {
+            return Ok(Some(Processed::MatchedIntent));
+        }
+    }
+    match derived::find_source(database, address, &code.0).await? {
+        Some((source_address, kind)) => {
+            database
+                .add_derived_verification(DerivedVerification {
+                    contract_address: address.to_string(),
+                    source_address,
+                    kind,
+                    derived_at: Utc::now(),
+                })
+                .await?;
+            Ok(Some(Processed::Derived))
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn new_heads_are_parsed() {
+        let notification = r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"subscription":"0x9ce5","result":{"number":"0x1b4","hash":"0xdc0818"}}}"#;
+        assert_eq!(Some(0x1b4), new_head(notification).unwrap());
+        let subscribed = r#"{"jsonrpc":"2.0","id":1,"result":"0x9ce5"}"#;
+        assert_eq!(None, new_head(subscribed).unwrap());
+        let failed = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"notifications not supported"}}"#;
+        assert!(new_head(failed).is_err());
+    }
+
+    #[test]
+    fn missed_blocks_are_caught_up() {
+        assert_eq!(10..=10, blocks_to_process(None, 10, 100));
+        assert_eq!(8..=10, blocks_to_process(Some(7), 10, 100));
+        assert_eq!(5..=10, blocks_to_process(Some(1), 10, 5));
+        // Reorganization
+        assert_eq!(10..=10, blocks_to_process(Some(12), 10, 100));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    rate_limit,
+    settings::ChainsSettings,
+    tenants,
+};
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Payload, Service, ServiceRequest, ServiceResponse},
+    web, Error, FromRequest, HttpRequest,
+};
+use futures::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    future::{ready, Ready},
+};
+
+/// Name of the database verified contracts were stored in before namespacing by chains.
+pub const LEGACY_NAMESPACE: &str = "evmos";
+
+/// Chain verified contracts belong to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Chain {
+    /// Name of the chain in the registry (e.g., "mainnet").
+    pub name: String,
+    pub chain_id: u64,
+}
+
+impl Chain {
+    /// Name of the database verified contracts of the chain are stored in.
+    /// Each tenant has its own namespace for each chain.
+    pub fn namespace(&self) -> String {
+        match tenants::current() {
+            None => format!("evmos_{}", self.chain_id),
+            Some(tenant) => format!("evmos_{tenant}_{}", self.chain_id),
+        }
+    }
+}
+
+/// Chains known to the service by their names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chains {
+    /// Chain contracts are verified for, i.e., the one rpc providers belong to.
+    current: Chain,
+    registry: BTreeMap<String, Chain>,
+}
+
+impl Chains {
+    pub fn new(settings: &ChainsSettings) -> anyhow::Result<Self> {
+        let registry: BTreeMap<_, _> = settings
+            .registry
+            .iter()
+            .map(|(name, chain)| {
+                let chain = Chain {
+                    name: name.clone(),
+                    chain_id: chain.chain_id,
+                };
+                (name.clone(), chain)
+            })
+            .collect();
+        let current = registry.get(&settings.current).cloned().ok_or_else(|| {
+            anyhow::anyhow!("chain {} is missing in the registry", settings.current)
+        })?;
+        Ok(Self { current, registry })
+    }
+
+    /// Chain contracts are verified for. Verified contracts are stored in its namespace.
+    pub fn current(&self) -> &Chain {
+        &self.current
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Chain> {
+        self.registry.get(name)
+    }
+
+    /// Chain requested via the `chain` query parameter. The current chain is used if omitted.
+    fn requested(&self, query_string: &str) -> Result<&Chain, ApiError> {
+        let query = web::Query::<ChainQuery>::from_query(query_string)
+            .map_err(ApiError::invalid_request)?;
+        match &query.chain {
+            None => Ok(&self.current),
+            Some(name) => self.get(name).ok_or_else(|| {
+                ApiError::new(ErrorCode::UnknownChain, format!("Unknown chain {name}"))
+            }),
+        }
+    }
+}
+
+impl Default for Chains {
+    fn default() -> Self {
+        Self::new(&ChainsSettings::default()).expect("default settings are valid")
+    }
+}
+
+#[derive(Deserialize)]
+struct ChainQuery {
+    chain: Option<String>,
+}
+
+/// Chain requested via the `chain` query parameter. The current chain is used if omitted.
+impl FromRequest for Chain {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let chain = match req.app_data::<web::Data<Chains>>() {
+            Some(chains) => chains.requested(req.query_string()).cloned(),
+            None => Err(ApiError::internal("chains are not configured")),
+        };
+        ready(chain)
+    }
+}
+
+/// Rejects verification requests for chains other than the current one, as contracts
+/// are verified against the code read from rpc providers of the current chain only.
+pub fn reject_other_chains<S, B>(
+    request: ServiceRequest,
+    service: &S,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    let chains = request
+        .app_data::<web::Data<Chains>>()
+        .filter(|_| rate_limit::is_verification(&request));
+    if let Some(chains) = chains {
+        let rejected = match chains.requested(request.query_string()) {
+            Ok(chain) if chain == chains.current() => None,
+            Ok(chain) => Some(ApiError::new(
+                ErrorCode::UnknownChain,
+                format!(
+                    "Contracts are verified for {} chain only, not for {}",
+                    chains.current().name,
+                    chain.name
+                ),
+            )),
+            Err(err) => Some(err),
+        };
+        if let Some(err) = rejected {
+            return Box::pin(async move { Err(err.into()) });
+        }
+    }
+    let response = service.call(request);
+    Box::pin(async move { Ok(response.await?.map_into_boxed_body()) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::ChainSettings;
+    use actix_web::{http::StatusCode, test, App, HttpResponse};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn chains_are_namespaced() {
+        let chains = Chains::default();
+        assert_eq!("mainnet", chains.current.name);
+        assert_eq!("evmos_9001", chains.current.namespace());
+        assert_eq!("evmos_9000", chains.registry["testnet"].namespace());
+
+        let settings = ChainsSettings {
+            current: "devnet".to_string(),
+            registry: BTreeMap::from([("mainnet".to_string(), ChainSettings { chain_id: 9001 })]),
+            ..Default::default()
+        };
+        Chains::new(&settings).expect_err("current chain is not registered");
+    }
+
+    #[actix_rt::test]
+    async fn chains_are_requested_by_names() {
+        let chains = Chains::default();
+        let request = |uri: &str| {
+            let request = test::TestRequest::get()
+                .uri(uri)
+                .app_data(web::Data::new(chains.clone()))
+                .to_http_request();
+            Chain::extract(&request)
+        };
+        let chain = request("/api/v1/contracts/0xcafe").await.unwrap();
+        assert_eq!(chains.current, chain);
+        let chain = request("/api/v1/contracts/0xcafe?chain=testnet")
+            .await
+            .unwrap();
+        assert_eq!(chains.registry["testnet"], chain);
+        let err = request("/api/v1/contracts/0xcafe?chain=devnet")
+            .await
+            .unwrap_err();
+        assert_eq!(ErrorCode::UnknownChain, err.code);
+    }
+
+    #[actix_rt::test]
+    async fn other_chains_are_not_verified() {
+        const VERIFY_PATH: &str = "/api/v1/solidity/verify/standard-json";
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Chains::default()))
+                .wrap_fn(reject_other_chains)
+                .route(VERIFY_PATH, web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+        let verify = |query: &str| {
+            test::TestRequest::post()
+                .uri(&format!("{VERIFY_PATH}{query}"))
+                .to_request()
+        };
+
+        let response = test::call_service(&app, verify("")).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let response = test::call_service(&app, verify("?chain=mainnet")).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let err = app
+            .call(verify("?chain=testnet"))
+            .await
+            .err()
+            .expect("testnet contracts are not verified");
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            err.as_response_error().status_code()
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use crate::compression::{Compressed, ContentEncoding};
+use chrono::{DateTime, Utc};
+use ethers_solc::CompilerInput;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use smart_contract_verifier::{solidity::normalization, VerificationSuccess};
+
+/// The exact compiler input (standard-json) a contract has been verified with.
+/// Along with the compiler version, it allows anyone to reproduce the verification
+/// byte-for-byte without trusting the service.
+///
+/// Unlike the input reconstructed from the stored verification, it includes
+/// the output selection and any settings the verification has been done with.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ArchivedCompilerInput {
+    pub contract_address: String,
+    /// Full version of the compiler (e.g., "v0.8.14+commit.80d49f37").
+    pub compiler_version: String,
+    /// Compressed json of the input. Inputs are always compressed,
+    /// with gzip if compression of stored values is disabled.
+    pub input: Compressed,
+    /// Hash of the normalized input, the same for inputs submitted in equivalent forms
+    /// (e.g., with other line endings). Is missing for inputs archived before it was introduced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_hash: Option<String>,
+    pub archived_at: DateTime<Utc>,
+}
+
+impl ArchivedCompilerInput {
+    /// Returns `None` if the input could not be compressed, as the archive
+    /// is auxiliary and should not prevent the verification from being stored.
+    /// The input is compressed with the encoding of stored values (or gzip, if it is disabled).
+    /// Settings not modelled by `CompilerInput` (e.g., `eofVersion`) are archived as well,
+    /// so that the input could be compiled as is.
+    pub fn new(
+        contract_address: &str,
+        success: &VerificationSuccess,
+        encoding: ContentEncoding,
+    ) -> Option<Self> {
+        let encoding = match encoding {
+            ContentEncoding::Identity => ContentEncoding::Gzip,
+            encoding => encoding,
+        };
+        let input = compiler_input(&success.compiler_input, &success.extra_settings)
+            .and_then(|input| Compressed::with_encoding(&input, encoding))
+            .map_err(|err| {
+                tracing::error!("cannot archive compiler input of {contract_address}: {err}")
+            })
+            .ok()?;
+        Some(Self {
+            contract_address: contract_address.to_lowercase(),
+            compiler_version: success.compiler_version.to_string(),
+            input,
+            input_hash: Some(normalization::input_hash(&success.compiler_input)),
+            archived_at: Utc::now(),
+        })
+    }
+
+    pub fn decode_input(&self) -> Result<Value, String> {
+        self.input.decode()
+    }
+}
+
+/// Returns the input the contract has been compiled with, the extra settings merged back.
+fn compiler_input(
+    compiler_input: &CompilerInput,
+    extra_settings: &Map<String, Value>,
+) -> Result<Value, String> {
+    let mut input = serde_json::to_value(compiler_input)
+        .map_err(|err| format!("Cannot serialize the input: {err}"))?;
+    if let Some(settings) = input.get_mut("settings").and_then(Value::as_object_mut) {
+        settings.extend(extra_settings.clone());
+    }
+    Ok(input)
+}
+
+/// Downloadable form of the archived input.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CompilerInputDownload {
+    pub contract_address: String,
+    pub compiler_version: String,
+    /// https://docs.soliditylang.org/en/latest/using-the-compiler.html#input-description
+    pub input: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_hash: Option<String>,
+    pub archived_at: DateTime<Utc>,
+}
+
+impl TryFrom<ArchivedCompilerInput> for CompilerInputDownload {
+    type Error = String;
+
+    fn try_from(archived: ArchivedCompilerInput) -> Result<Self, Self::Error> {
+        Ok(Self {
+            input: archived.decode_input()?,
+            contract_address: archived.contract_address,
+            compiler_version: archived.compiler_version,
+            input_hash: archived.input_hash,
+            archived_at: archived.archived_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn archived_input_is_decoded() {
+        let input = json!({
+            "language": "Solidity",
+            "sources": { "A.sol": { "content": "contract A {}" } },
+            "settings": { "outputSelection": { "*": { "*": ["abi", "evm.bytecode"] } } },
+        });
+        let archived = ArchivedCompilerInput {
+            contract_address: "0xcafe".into(),
+            compiler_version: "v0.8.14+commit.80d49f37".into(),
+            input: Compressed::with_encoding(&input, ContentEncoding::Gzip).unwrap(),
+            input_hash: None,
+            archived_at: Utc::now(),
+        };
+        let download = CompilerInputDownload::try_from(archived.clone()).unwrap();
+        assert_eq!(input, download.input);
+        assert_eq!(archived.compiler_version, download.compiler_version);
+    }
+
+    #[test]
+    fn extra_settings_are_archived() {
+        let input: CompilerInput = serde_json::from_value(json!({
+            "language": "Solidity",
+            "sources": { "A.sol": { "content": "contract A {}" } },
+            "settings": { "viaIR": true },
+        }))
+        .unwrap();
+        let extra_settings = Map::from_iter([
+            ("evmVersion".to_string(), json!("osaka")),
+            ("eofVersion".to_string(), json!(1)),
+        ]);
+
+        let archived = compiler_input(&input, &extra_settings).unwrap();
+        assert_eq!(json!("osaka"), archived["settings"]["evmVersion"]);
+        assert_eq!(json!(1), archived["settings"]["eofVersion"]);
+        assert_eq!(json!(true), archived["settings"]["viaIR"]);
+    }
+}
@@ -0,0 +1,129 @@
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_with::serde_as;
+use std::io::{Read, Write};
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Encoding newly stored sources and compiler outputs are compressed with
+/// (see `CompressionSettings`). Stored values are decompressed with the encoding
+/// they have been stored with.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentEncoding {
+    /// Values are stored as is.
+    #[default]
+    #[serde(alias = "none")]
+    Identity,
+    Gzip,
+    Zstd,
+}
+
+impl ContentEncoding {
+    pub fn encode(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            ContentEncoding::Identity => Ok(data.to_vec()),
+            ContentEncoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            ContentEncoding::Zstd => zstd::encode_all(data, ZSTD_LEVEL),
+        }
+    }
+
+    pub fn decode(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            ContentEncoding::Identity => Ok(data.to_vec()),
+            ContentEncoding::Gzip => {
+                let mut decoded = Vec::new();
+                GzDecoder::new(data).read_to_end(&mut decoded)?;
+                Ok(decoded)
+            }
+            ContentEncoding::Zstd => zstd::decode_all(data),
+        }
+    }
+
+    /// Suffix of the file names compressed with the encoding.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ContentEncoding::Identity => "",
+            ContentEncoding::Gzip => ".gz",
+            ContentEncoding::Zstd => ".zst",
+        }
+    }
+
+    /// Returns the encoding the file has been compressed with judging by its name.
+    pub fn from_file_name(name: &str) -> Self {
+        [ContentEncoding::Gzip, ContentEncoding::Zstd]
+            .into_iter()
+            .find(|encoding| name.ends_with(encoding.extension()))
+            .unwrap_or(ContentEncoding::Identity)
+    }
+}
+
+/// Value serialized as json and compressed, along with the encoding it is compressed with.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Compressed {
+    pub encoding: ContentEncoding,
+    #[serde_as(as = "serde_with::Bytes")]
+    pub data: Vec<u8>,
+}
+
+impl Compressed {
+    /// Compresses the value with the given encoding.
+    /// Returns `None` if compression is disabled.
+    pub fn new<T: Serialize>(value: &T, encoding: ContentEncoding) -> Result<Option<Self>, String> {
+        if encoding == ContentEncoding::Identity {
+            return Ok(None);
+        }
+        Self::with_encoding(value, encoding).map(Some)
+    }
+
+    pub fn with_encoding<T: Serialize>(
+        value: &T,
+        encoding: ContentEncoding,
+    ) -> Result<Self, String> {
+        let json = serde_json::to_vec(value)
+            .map_err(|err| format!("Cannot serialize the value: {err}"))?;
+        let data = encoding
+            .encode(&json)
+            .map_err(|err| format!("Cannot compress the value: {err}"))?;
+        Ok(Self { encoding, data })
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, String> {
+        let json = self
+            .encoding
+            .decode(&self.data)
+            .map_err(|err| format!("Cannot decompress the value: {err}"))?;
+        serde_json::from_slice(&json).map_err(|err| format!("Decompressed value is invalid: {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn encodings_are_reversible() {
+        let data = "contract A {}\n".repeat(100);
+        for encoding in [
+            ContentEncoding::Identity,
+            ContentEncoding::Gzip,
+            ContentEncoding::Zstd,
+        ] {
+            let encoded = encoding.encode(data.as_bytes()).unwrap();
+            if encoding != ContentEncoding::Identity {
+                assert!(encoded.len() < data.len(), "{encoding:?} should compress");
+            }
+            assert_eq!(data.as_bytes(), encoding.decode(&encoded).unwrap());
+            assert_eq!(
+                encoding,
+                ContentEncoding::from_file_name(&format!("sources.json{}", encoding.extension()))
+            );
+        }
+    }
+}
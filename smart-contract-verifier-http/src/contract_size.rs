@@ -0,0 +1,124 @@
+use ethers_solc::artifacts::Contract;
+use serde::{Deserialize, Serialize};
+
+/// Max size of the deployed code (EIP-170).
+pub const MAX_CODE_SIZE: usize = 24_576;
+/// Max size of the creation code (EIP-3860).
+pub const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+
+const TX_GAS: u64 = 21_000;
+const CREATE_GAS: u64 = 32_000;
+const TX_DATA_ZERO_GAS: u64 = 4;
+const TX_DATA_NON_ZERO_GAS: u64 = 16;
+const INITCODE_WORD_GAS: u64 = 2;
+const CODE_DEPOSIT_GAS: u64 = 200;
+
+/// Sizes of the verified contract and the estimated cost of its deployment,
+/// computed from the compiled artifacts.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ContractSize {
+    /// Size of the deployed code in bytes.
+    pub deployed_size: usize,
+    /// Bytes left before the deployed code reaches the 24KB limit; negative if exceeded.
+    pub deployed_size_headroom: i64,
+    pub exceeds_size_limit: bool,
+    /// Size of the creation code in bytes, without constructor arguments.
+    pub creation_size: usize,
+    /// Bytes left before the creation code reaches the 48KB limit; negative if exceeded.
+    pub creation_size_headroom: i64,
+    /// Gas of the deployment transaction: intrinsic costs of the transaction and its data,
+    /// and the code deposit cost. Includes the constructor execution cost only if
+    /// `execution_cost_included`, as it is known to the compiler only if `evm.gasEstimates`
+    /// output is selected (and is finite).
+    pub estimated_deployment_gas: u64,
+    pub execution_cost_included: bool,
+}
+
+impl ContractSize {
+    pub fn new(creation_code: &[u8], deployed_code: &[u8], constructor_args: &[u8]) -> Self {
+        let data_gas: u64 = creation_code
+            .iter()
+            .chain(constructor_args)
+            .map(|byte| match byte {
+                0 => TX_DATA_ZERO_GAS,
+                _ => TX_DATA_NON_ZERO_GAS,
+            })
+            .sum();
+        let initcode_words = ((creation_code.len() + constructor_args.len() + 31) / 32) as u64;
+        let estimated_deployment_gas = TX_GAS
+            + CREATE_GAS
+            + data_gas
+            + INITCODE_WORD_GAS * initcode_words
+            + CODE_DEPOSIT_GAS * deployed_code.len() as u64;
+        Self {
+            deployed_size: deployed_code.len(),
+            deployed_size_headroom: MAX_CODE_SIZE as i64 - deployed_code.len() as i64,
+            exceeds_size_limit: deployed_code.len() > MAX_CODE_SIZE,
+            creation_size: creation_code.len(),
+            creation_size_headroom: MAX_INITCODE_SIZE as i64 - creation_code.len() as i64,
+            estimated_deployment_gas,
+            execution_cost_included: false,
+        }
+    }
+
+    /// Adds the constructor execution cost estimated by the compiler, if any.
+    pub fn with_gas_estimates(mut self, contract: &Contract) -> Self {
+        let execution_cost = contract
+            .evm
+            .as_ref()
+            .and_then(|evm| evm.gas_estimates.as_ref())
+            .and_then(|estimates| estimates.creation.execution_cost.parse::<u64>().ok());
+        if let Some(execution_cost) = execution_cost {
+            self.estimated_deployment_gas += execution_cost;
+            self.execution_cost_included = true;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn deployment_gas_is_estimated() {
+        let size = ContractSize::new(&[0x60, 0x80, 0x00], &[0x60, 0x80], &[0x00; 32]);
+        assert_eq!(
+            ContractSize {
+                deployed_size: 2,
+                deployed_size_headroom: 24_574,
+                exceeds_size_limit: false,
+                creation_size: 3,
+                creation_size_headroom: 49_149,
+                // 21000 + 32000 + (2 * 16 + 33 * 4) + 2 * 2 + 2 * 200
+                estimated_deployment_gas: 53_568,
+                execution_cost_included: false,
+            },
+            size
+        );
+
+        let contract: Contract = serde_json::from_value(serde_json::json!({
+            "evm": {
+                "gasEstimates": {
+                    "creation": {
+                        "codeDepositCost": "400",
+                        "executionCost": "120",
+                        "totalCost": "520"
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let size = size.with_gas_estimates(&contract);
+        assert!(size.execution_cost_included);
+        assert_eq!(53_688, size.estimated_deployment_gas);
+    }
+
+    #[test]
+    fn size_limit_excess_is_reported() {
+        let size = ContractSize::new(&[], &vec![0x5b; MAX_CODE_SIZE + 1], &[]);
+        assert!(size.exceeds_size_limit);
+        assert_eq!(-1, size.deployed_size_headroom);
+    }
+}
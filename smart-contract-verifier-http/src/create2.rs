@@ -0,0 +1,144 @@
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    DisplayBytes,
+};
+use ethers_core::types::{H160, H256};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{
+    tools::contract_address, Address, ContractDiagnostics, VerificationSuccess,
+};
+use std::str::FromStr;
+
+/// Deployment of the contract by a factory with `CREATE2`. The creation transaction
+/// calls the factory, so its input is not the creation bytecode of the contract;
+/// the init code the factory has passed to `CREATE2` is matched instead.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Create2Deployment {
+    /// Address of the factory the contract has been deployed by.
+    pub factory: Address,
+    /// 32 bytes salt passed to `CREATE2`.
+    pub salt: DisplayBytes,
+    /// (optional) Init code passed to `CREATE2`. If omitted, is derived from the creation
+    /// bytecode of the compiled contract and `constructor_arguments`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init_code: Option<DisplayBytes>,
+    /// (optional) Constructor arguments appended to the derived init code.
+    /// Cannot be set along with `init_code`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constructor_arguments: Option<DisplayBytes>,
+}
+
+impl Create2Deployment {
+    /// Validates the deployment and returns the init code to be used as the creation bytecode,
+    /// if it has been provided. Fails if the provided init code does not derive the address.
+    pub fn creation_bytecode(
+        &self,
+        contract_address: &Address,
+    ) -> Result<Option<DisplayBytes>, ApiError> {
+        if self.salt.0.len() != 32 {
+            return Err(ApiError::invalid_request(format!(
+                "CREATE2 salt must be 32 bytes long, got {} bytes",
+                self.salt.0.len()
+            )));
+        }
+        let init_code = match (&self.init_code, &self.constructor_arguments) {
+            (Some(_), Some(_)) => {
+                return Err(ApiError::invalid_request(
+                    "Only one of CREATE2 init code and constructor arguments may be provided",
+                ))
+            }
+            (Some(init_code), None) => init_code,
+            (None, _) => return Ok(None),
+        };
+        let derived = self.address(&init_code.0);
+        if derived != h160(contract_address) {
+            return Err(ApiError::new(
+                ErrorCode::Create2AddressMismatch,
+                format!(
+                    "The init code deployed by {} with the salt is created at {derived:?}, not at {contract_address}",
+                    self.factory
+                ),
+            ));
+        }
+        Ok(Some(init_code.clone()))
+    }
+
+    /// Returns diagnostics describing the mismatch, if the init code derived from the verified
+    /// contract does not derive the address. Init code which has been provided is checked
+    /// by [`Self::creation_bytecode`] before verification.
+    pub fn mismatch(
+        &self,
+        contract_address: &Address,
+        success: &VerificationSuccess,
+    ) -> Option<ContractDiagnostics> {
+        if self.init_code.is_some() {
+            return None;
+        }
+        let mut init_code = success.creation_bytecode.0.to_vec();
+        if let Some(constructor_arguments) = &self.constructor_arguments {
+            init_code.extend_from_slice(&constructor_arguments.0);
+        }
+        let derived = self.address(&init_code);
+        (derived != h160(contract_address)).then(|| ContractDiagnostics {
+            file_path: success.file_path.clone(),
+            contract_name: Some(success.contract_name.clone()),
+            reason: format!(
+                "The init code of the compiled contract deployed by {} with the salt is created at {derived:?}",
+                self.factory
+            ),
+            first_diverging_offset: None,
+            metadata_hash_only: false,
+            length_differs: false,
+            hints: vec![],
+        })
+    }
+
+    fn address(&self, init_code: &[u8]) -> H160 {
+        contract_address::create2_address(
+            h160(&self.factory),
+            H256::from_slice(&self.salt.0),
+            init_code,
+        )
+    }
+}
+
+fn h160(address: &Address) -> H160 {
+    H160::from_str(address.as_str()).expect("address is validated on creation")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn deployment(init_code: Option<&str>) -> Create2Deployment {
+        Create2Deployment {
+            factory: Address::from_str("0x0000000000000000000000000000000000000000").unwrap(),
+            salt: DisplayBytes::from([0u8; 32].to_vec()),
+            init_code: init_code.map(|init_code| DisplayBytes::from_str(init_code).unwrap()),
+            constructor_arguments: None,
+        }
+    }
+
+    #[test]
+    fn init_code_is_checked_against_address() {
+        let address = Address::from_str("0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38").unwrap();
+        assert_eq!(
+            Some(DisplayBytes::from([0x00])),
+            deployment(Some("0x00"))
+                .creation_bytecode(&address)
+                .unwrap()
+        );
+        assert_eq!(None, deployment(None).creation_bytecode(&address).unwrap());
+
+        let err = deployment(Some("0x01"))
+            .creation_bytecode(&address)
+            .unwrap_err();
+        assert_eq!(ErrorCode::Create2AddressMismatch, err.code);
+
+        let mut invalid_salt = deployment(Some("0x00"));
+        invalid_salt.salt = DisplayBytes::from([0u8; 20].to_vec());
+        assert!(invalid_salt.creation_bytecode(&address).is_err());
+    }
+}
@@ -1,7 +1,10 @@
-use mongodb::{Client, Database, Collection, options::{ClientOptions, ResolverConfig}};
-use chrono::{TimeZone, Utc};
-use mongodb::bson::doc;
-use crate::verified_contract_result::Verified_Contract_Result;
+use mongodb::{Client, ClientSession, Database, Collection, IndexModel, options::{AggregateOptions, ClientOptions, CountOptions, FindOneAndUpdateOptions, FindOneOptions, FindOptions, IndexOptions, ReplaceOptions, ResolverConfig, ReturnDocument, UpdateOptions}};
+use chrono::{SecondsFormat, TimeZone, Utc};
+use futures::TryStreamExt;
+use std::{collections::{BTreeMap, BTreeSet}, sync::Arc};
+use tracing::instrument;
+use mongodb::bson::{doc, oid::ObjectId, Bson, DateTime, Document};
+use crate::{access_log::AccessLogRecord, analysis::ContractAnalysis, attempts::VerificationAttempt, audit::AuditRecord, blobs::BlobStore, caching, compiler_inputs::ArchivedCompilerInput, compression::{Compressed, ContentEncoding}, debug_info::DebugInfo, derived::DerivedVerification, highlights::{DuplicatedCode, RecentContract}, intents::VerificationIntent, jobs::{JobAhead, VerificationJob}, licenses, metadata_availability::MetadataAvailability, proxy_history::ImplementationChange, signatures::{EventSignature, FunctionSignature}, standards::Standard, stats::{self, AttemptGroup, SettingsGroup}, token_info::TokenInfo, verified_contract_result::{AdminAction, AdminActionKind, ContractCreation, Verified_Contract_Result}};
 
 
 /// Define cvr from result of smart contract verification.
@@ -16,6 +19,12 @@ pub struct DB {
 
     /// Database name and chain name are the same.
     db_name: String,
+
+    /// Large values are kept in the blob store rather than in documents, if it is enabled.
+    blobs: Option<Arc<BlobStore>>,
+
+    /// Encoding newly stored sources are compressed with.
+    encoding: ContentEncoding,
 }
 
 impl DB {
@@ -32,6 +41,8 @@ impl DB {
         DB {
             mongo: (Client::with_uri_str(client_uri).await.expect("Cannot connect to MongoDB instance.")),
             db_name: "unexpected_db".to_string(),
+            blobs: None,
+            encoding: ContentEncoding::Identity,
         }
     }
 
@@ -43,6 +54,17 @@ impl DB {
         }
     }
 
+    /// Sets the blob store large values are kept in (and loaded from).
+    pub fn with_blobs(self, blobs: Option<Arc<BlobStore>>) -> DB {
+        DB { blobs, ..self }
+    }
+
+    /// Sets the encoding newly stored sources are compressed with.
+    /// Stored sources are decompressed with the encoding they have been stored with.
+    pub fn with_encoding(self, encoding: ContentEncoding) -> DB {
+        DB { encoding, ..self }
+    }
+
     /// Returns the MongoDB database.
     /// # Usage
     /// ```rs
@@ -61,16 +83,1338 @@ impl DB {
         self.db().collection("cvr")
     }
 
+    /// Creates indexes used by lookups other than by contract address.
+    pub async fn create_indexes(&self) -> Result<(), String> {
+        let code_hash_index = IndexModel::builder().keys(doc! { "result.code_hash": 1 }).build();
+        if self.cvr_collection().create_index(code_hash_index, None).await.is_err() {
+            return Err("Cannot create the code hash index.".into());
+        }
+        let licenses_index = IndexModel::builder().keys(doc! { "license_ids": 1 }).build();
+        if self.cvr_collection().create_index(licenses_index, None).await.is_err() {
+            return Err("Cannot create the licenses index.".into());
+        }
+        let count_index = IndexModel::builder().keys(doc! { "count": -1 }).build();
+        if self.code_hash_counts_collection().create_index(count_index, None).await.is_err() {
+            return Err("Cannot create the code hash count index.".into());
+        }
+        let intents_index = IndexModel::builder().keys(doc! { "init_code_hash": 1, "state": 1 }).build();
+        if self.verification_intents_collection().create_index(intents_index, None).await.is_err() {
+            return Err("Cannot create the verification intents index.".into());
+        }
+        let attempts_index = IndexModel::builder().keys(doc! { "compiler_version": 1, "_id": -1 }).build();
+        if self.attempts_collection().create_index(attempts_index, None).await.is_err() {
+            return Err("Cannot create the verification attempts index.".into());
+        }
+        let jobs_index = IndexModel::builder().keys(doc! { "state": 1, "_id": 1 }).build();
+        if self.verification_jobs_collection().create_index(jobs_index, None).await.is_err() {
+            return Err("Cannot create the verification jobs index.".into());
+        }
+        let leases_index = IndexModel::builder().keys(doc! { "state": 1, "available_at": 1 }).build();
+        if self.verification_jobs_collection().create_index(leases_index, None).await.is_err() {
+            return Err("Cannot create the verification job leases index.".into());
+        }
+        let finished_jobs_index = IndexModel::builder().keys(doc! { "finished_at": 1 }).build();
+        if self.verification_jobs_collection().create_index(finished_jobs_index, None).await.is_err() {
+            return Err("Cannot create the finished verification jobs index.".into());
+        }
+        // Changes have been recorded by every replica of the service before, the unique index could not be created otherwise
+        self.remove_duplicated_implementation_changes().await?;
+        let implementations_index = IndexModel::builder().keys(doc! { "proxy_address": 1, "implementation": 1, "block_number": 1 }).options(IndexOptions::builder().unique(true).build()).build();
+        match self.implementations_collection().create_index(implementations_index, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot create the proxy implementations index.".into()),
+        }
+    }
+
+    /// Moves collections of the `legacy` database into this one. Collections which
+    /// already exist in this database are left as is. Returns names of the moved collections.
+    pub async fn migrate_from(&self, legacy: &str) -> Result<Vec<String>, String> {
+        let existing = match self.db().list_collection_names(None).await {
+            Ok(existing) => existing,
+            Err(_) => return Err("Cannot list the database collections.".into()),
+        };
+        let legacy_collections = match self.mongo.database(legacy).list_collection_names(None).await {
+            Ok(legacy_collections) => legacy_collections,
+            Err(_) => return Err("Cannot list the legacy database collections.".into()),
+        };
+
+        let mut moved = Vec::new();
+        for collection in legacy_collections {
+            if collection.starts_with("system.") {
+                continue;
+            }
+            if existing.contains(&collection) {
+                tracing::warn!("collection {} exists in both {} and {} databases, and is not moved", collection, legacy, self.db_name);
+                continue;
+            }
+            let command = doc! {
+                "renameCollection": format!("{}.{}", legacy, collection),
+                "to": format!("{}.{}", self.db_name, collection),
+            };
+            if self.mongo.database("admin").run_command(command, None).await.is_err() {
+                return Err(format!("Cannot move the {} collection.", collection));
+            }
+            moved.push(collection);
+        }
+        Ok(moved)
+    }
+
+    /// Detects licenses of contracts stored before licenses have been detected. Content hashes
+    /// of the updated results are reset, so that they are recomputed. Returns the number of updated contracts.
+    pub async fn backfill_licenses(&self) -> Result<u64, String> {
+        let filter = doc! { "result.licenses": { "$exists": false } };
+        let mut cursor = match self.db().collection::<Document>("cvr").find(filter, None).await {
+            Ok(cursor) => cursor,
+            Err(_) => return Err("Cannot load contracts without licenses.".into()),
+        };
+        let mut updated = 0;
+        while let Some(document) = cursor.try_next().await.map_err(|_| "Cannot load contracts without licenses.".to_string())? {
+            let id = document.get_object_id("_id").map_err(|_| "Stored contract has no id.".to_string())?;
+            let cvr = match mongodb::bson::from_document::<Verified_Contract_Result>(document) {
+                Ok(cvr) => self.resolve_sources(cvr).await,
+                Err(err) => Err(format!("Stored contract {} is invalid: {}", id, err)),
+            };
+            let cvr = match cvr {
+                Ok(cvr) => cvr,
+                Err(err) => {
+                    tracing::warn!("cannot detect licenses: {}", err);
+                    continue;
+                }
+            };
+            // Is set even if no license is declared, so that the contract is not looked at again
+            let licenses = licenses::detect(&cvr.result.sources);
+            let license_ids = licenses::distinct_identifiers(&licenses);
+            let update = doc! { "$set": { "result.licenses": licenses, "license_ids": license_ids }, "$unset": { "content_hash": "" } };
+            if self.cvr_collection().update_one(doc! { "_id": id }, update, None).await.is_err() {
+                return Err(format!("Cannot store licenses of {}.", cvr.contract_address));
+            }
+            updated += 1;
+        }
+        Ok(updated)
+    }
+
+    /// Returns names of the collections of the database, except for system ones.
+    pub async fn collection_names(&self) -> Result<Vec<String>, String> {
+        match self.db().list_collection_names(None).await {
+            Ok(names) => Ok(names.into_iter().filter(|name| !name.starts_with("system.")).collect()),
+            Err(_) => Err("Cannot list the database collections.".into()),
+        }
+    }
+
+    /// Returns a cursor over all documents of the collection, so that they are dumped one by one.
+    pub async fn dump_collection(&self, name: &str) -> Result<mongodb::Cursor<Document>, String> {
+        match self.db().collection::<Document>(name).find(None, None).await {
+            Ok(cursor) => Ok(cursor),
+            Err(_) => Err(format!("Cannot dump the {} collection.", name)),
+        }
+    }
+
+    /// Inserts the given documents into temporary collections, and renames them over
+    /// the collections of the database once all of them are inserted, so that a failed
+    /// restoration leaves the database as is. Indexes of the replaced collections are dropped,
+    /// so should be created again afterwards.
+    pub async fn restore_collections(&self, collections: BTreeMap<String, Vec<Document>>) -> Result<(), String> {
+        let mut restored = Vec::new();
+        for (name, documents) in collections {
+            if documents.is_empty() {
+                continue;
+            }
+            let temporary = self.db().collection::<Document>(&format!("{name}.restoring"));
+            // Might be left by a restoration which has failed before
+            if temporary.drop(None).await.is_err() {
+                return Err(format!("Cannot drop the temporary {} collection.", name));
+            }
+            if temporary.insert_many(documents, None).await.is_err() {
+                return Err(format!("Cannot restore the {} collection.", name));
+            }
+            restored.push(name);
+        }
+        for name in restored {
+            let command = doc! {
+                "renameCollection": format!("{}.{}.restoring", self.db_name, name),
+                "to": format!("{}.{}", self.db_name, name),
+                "dropTarget": true,
+            };
+            if self.mongo.database("admin").run_command(command, None).await.is_err() {
+                return Err(format!("Cannot replace the {} collection.", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Claims the run of the scheduled job for the instance until the given time. Returns
+    /// whether the run has been claimed, i.e. the previous claim has expired.
+    pub async fn claim_scheduled_run(&self, job: &str, instance: &str, until: DateTime) -> Result<bool, String> {
+        let collection = self.db().collection::<Document>("scheduled_runs");
+        // Concurrent upserts by `_id` are retried by the server, so that
+        // the document is created once, already expired
+        let options = UpdateOptions::builder().upsert(true).build();
+        let created = doc! { "$setOnInsert": { "instance": "", "until": DateTime::from_millis(0) } };
+        if collection.update_one(doc! { "_id": job }, created, options).await.is_err() {
+            return Err("Cannot create the scheduled run.".into());
+        }
+        let filter = doc! { "_id": job, "until": { "$lte": DateTime::now() } };
+        let update = doc! { "$set": { "instance": instance, "until": until } };
+        match collection.find_one_and_update(filter, update, None).await {
+            Ok(claimed) => Ok(claimed.is_some()),
+            Err(_) => Err("Cannot claim the scheduled run.".into()),
+        }
+    }
+
+    /// Expires the claim of the scheduled run, if it is held by the instance.
+    pub async fn release_scheduled_run(&self, job: &str, instance: &str) -> Result<(), String> {
+        let collection = self.db().collection::<Document>("scheduled_runs");
+        let update = doc! { "$set": { "until": DateTime::from_millis(0) } };
+        match collection.update_one(doc! { "_id": job, "instance": instance }, update, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot release the scheduled run.".into()),
+        }
+    }
+
     /// Adds a new response of contract verification to the contract_verify_response collection of the database.
+    ///
+    /// If there is a stored contract with the same code hash and sources (e.g., another clone
+    /// deployed by a factory), the sources are not stored again but shared with that contract.
+    /// Otherwise, large sources are kept in the blob store, if it is enabled,
+    /// or are compressed, if compression is enabled.
     /// # Usage
     /// ```rs
     /// database.add_contract_verify_response(Contract_verify_response).await;
     /// ```
-    pub async fn add_contract_verify_response(&self, cvr: Verified_Contract_Result) -> Result<(), String> {
-        match self.cvr_collection().insert_one(cvr, None).await {
+    #[instrument(skip(self, cvr), level = "debug")]
+    pub async fn add_contract_verify_response(&self, mut cvr: Verified_Contract_Result) -> Result<(), String> {
+        // Is computed before sources are moved out, so that it covers them as well
+        cvr.content_hash = Some(caching::content_hash(&cvr.result));
+        cvr.license_ids = licenses::distinct_identifiers(&cvr.result.licenses);
+        let compressed_sources = Compressed::new(&cvr.result.sources, self.encoding)?;
+        if let Some(sources_of) = self.find_sources_owner(&cvr, compressed_sources.as_ref()).await {
+            tracing::debug!("sources of {} are shared with {}", cvr.contract_address, sources_of);
+            cvr.sources_of = Some(sources_of);
+            cvr.result.sources = BTreeMap::new();
+        } else if let Some(blobs) = &self.blobs {
+            if let Some(key) = blobs.offload("sources", &cvr.result.sources).await? {
+                cvr.sources_blob = Some(key);
+                cvr.result.sources = BTreeMap::new();
+            }
+        }
+        if cvr.sources_of.is_none() && cvr.sources_blob.is_none() && compressed_sources.is_some() {
+            cvr.compressed_sources = compressed_sources;
+            cvr.result.sources = BTreeMap::new();
+        }
+        let code_hash = cvr.result.code_hash.clone();
+        let (contract_address, contract_name) = (cvr.contract_address.clone(), cvr.result.contract_name.clone());
+        if self.cvr_collection().insert_one(cvr, None).await.is_err() {
+            return Err("Cannot save the contract_verify_response.".into());
+        }
+        // The counter is auxiliary, so the verification is stored anyway
+        if let Some(code_hash) = code_hash {
+            let update = doc! { "$inc": { "count": 1 }, "$set": { "contract_address": contract_address, "contract_name": contract_name } };
+            let options = UpdateOptions::builder().upsert(true).build();
+            if let Err(err) = self.code_hash_counts_collection().update_one(doc! { "_id": &code_hash }, update, options).await {
+                tracing::warn!("cannot count contracts with code hash {}: {}", code_hash, err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the address of a stored contract with the same code hash and sources
+    /// as the given one, which stores the sources itself (either as is, or compressed the same way).
+    async fn find_sources_owner(&self, cvr: &Verified_Contract_Result, compressed_sources: Option<&Compressed>) -> Option<String> {
+        let code_hash = cvr.result.code_hash.as_ref()?;
+        let sources = mongodb::bson::to_bson(&cvr.result.sources).ok()?;
+        let mut same_sources = vec![doc! { "result.sources": sources }];
+        if let Some(compressed_sources) = compressed_sources {
+            same_sources.push(doc! { "compressed_sources": mongodb::bson::to_bson(compressed_sources).ok()? });
+        }
+        let filter = doc! {
+            "result.code_hash": code_hash,
+            "$or": same_sources,
+            "sources_of": { "$exists": false },
+            "contract_address": { "$ne": &cvr.contract_address },
+        };
+        match self.cvr_collection().find_one(filter, None).await {
+            Ok(owner) => owner.map(|owner| owner.contract_address),
+            Err(err) => {
+                tracing::warn!("cannot look up contracts with the same code hash: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Loads sources shared with another contract, kept in the blob store or compressed into the result.
+    pub async fn resolve_sources(&self, mut cvr: Verified_Contract_Result) -> Result<Verified_Contract_Result, String> {
+        if let Some(sources_of) = &cvr.sources_of {
+            let options = FindOneOptions::builder().sort(doc! { "_id": -1 }).build();
+            let filter = doc! { "contract_address": sources_of, "sources_of": { "$exists": false } };
+            let owner = match self.cvr_collection().find_one(filter, options).await {
+                Ok(Some(owner)) => owner,
+                Ok(None) => return Err(format!("Shared sources of {} are missing.", cvr.contract_address)),
+                Err(_) => return Err("Cannot load the shared sources.".into()),
+            };
+            cvr.result.sources = owner.result.sources;
+            cvr.sources_blob = owner.sources_blob;
+            cvr.compressed_sources = owner.compressed_sources;
+        }
+        if let Some(key) = cvr.sources_blob.take() {
+            let blobs = self.blobs.as_ref().ok_or_else(|| format!("Sources of {} are kept in the blob store, which is disabled.", cvr.contract_address))?;
+            cvr.result.sources = blobs.load(&key).await?;
+        }
+        if let Some(compressed_sources) = cvr.compressed_sources.take() {
+            cvr.result.sources = compressed_sources.decode()?;
+        }
+        Ok(cvr)
+    }
+
+    /// Returns addresses of stored contracts having the given hash of the metadata-stripped code.
+    pub async fn find_contracts_by_code_hash(&self, code_hash: &str) -> Result<Vec<String>, String> {
+        match self.cvr_collection().distinct("contract_address", doc! { "result.code_hash": code_hash }, None).await {
+            Ok(addresses) => Ok(addresses
+                .into_iter()
+                .filter_map(|address| address.as_str().map(str::to_string))
+                .collect()),
+            Err(_) => Err("Cannot load contracts by the code hash.".into()),
+        }
+    }
+
+    /// Returns the latest verification result stored for the given contract address, if any.
+    /// # Usage
+    /// ```rs
+    /// let cvr = database.find_contract_verify_response("0x...").await?;
+    /// ```
+    #[instrument(skip(self), level = "debug")]
+    pub async fn find_contract_verify_response(&self, contract_address: &str) -> Result<Option<Verified_Contract_Result>, String> {
+        // The same address may have been verified several times; the latest record wins
+        let options = FindOneOptions::builder().sort(doc! { "_id": -1 }).build();
+        match self.cvr_collection().find_one(doc! { "contract_address": contract_address.to_lowercase() }, options).await {
+            Ok(Some(cvr)) => self.resolve_sources(cvr).await.map(Some),
+            Ok(None) => Ok(None),
+            Err(_) => Err("Cannot load the contract_verify_response.".into()),
+        }
+    }
+
+    /// Returns whether the contract is verified, without loading its sources.
+    pub async fn is_contract_verified(&self, contract_address: &str) -> Result<bool, String> {
+        let options = CountOptions::builder().limit(1).build();
+        match self.cvr_collection().count_documents(doc! { "contract_address": contract_address.to_lowercase() }, options).await {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err("Cannot check whether the contract is verified.".into()),
+        }
+    }
+
+    /// Sets the transaction the contract has been created by, which is located
+    /// after the contract has been stored.
+    pub async fn set_contract_creation(&self, contract_address: &str, creation: &ContractCreation) -> Result<(), String> {
+        let creation = mongodb::bson::to_bson(creation).map_err(|_| "Cannot serialize the contract creation.".to_string())?;
+        match self.cvr_collection().update_many(doc! { "contract_address": contract_address.to_lowercase() }, doc! { "$set": { "creation": creation } }, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the contract creation.".into()),
+        }
+    }
+
+    /// Sets token metadata of the contract, which is read after the contract has been stored.
+    pub async fn set_token_info(&self, contract_address: &str, token_info: &TokenInfo) -> Result<(), String> {
+        let token_info = mongodb::bson::to_bson(token_info).map_err(|_| "Cannot serialize the token info.".to_string())?;
+        match self.cvr_collection().update_many(doc! { "contract_address": contract_address.to_lowercase() }, doc! { "$set": { "token_info": token_info } }, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the token info.".into()),
+        }
+    }
+
+    /// Returns addresses of stored contracts which ABI implements the given standard.
+    pub async fn find_contracts_by_standard(&self, standard: Standard) -> Result<Vec<String>, String> {
+        let standard = mongodb::bson::to_bson(&standard).map_err(|_| "Cannot serialize the standard.".to_string())?;
+        match self.cvr_collection().distinct("contract_address", doc! { "result.standards": standard }, None).await {
+            Ok(addresses) => Ok(addresses
+                .into_iter()
+                .filter_map(|address| address.as_str().map(str::to_string))
+                .collect()),
+            Err(_) => Err("Cannot load contracts by the standard.".into()),
+        }
+    }
+
+    /// Returns token metadata stored with the latest verification result of the contract.
+    /// The outer `None` is returned if the contract is not verified at all.
+    pub async fn find_token_info(&self, contract_address: &str) -> Result<Option<Option<TokenInfo>>, String> {
+        let options = FindOneOptions::builder().sort(doc! { "_id": -1 }).projection(doc! { "token_info": 1 }).build();
+        match self.db().collection::<Document>("cvr").find_one(doc! { "contract_address": contract_address.to_lowercase() }, options).await {
+            Ok(Some(document)) => match document.get_document("token_info") {
+                Ok(token_info) => mongodb::bson::from_document(token_info.clone()).map(|token_info| Some(Some(token_info))).map_err(|_| "Stored token info is invalid.".into()),
+                Err(_) => Ok(Some(None)),
+            },
+            Ok(None) => Ok(None),
+            Err(_) => Err("Cannot load the token info.".into()),
+        }
+    }
+
+    /// Returns addresses of all contracts stored in the database.
+    /// Returns a cursor over the latest verification of each stored contract, so that all of them
+    /// are loaded by a single query. Their sources are to be resolved with [`DB::resolve_sources`].
+    pub async fn latest_contract_verify_responses(&self) -> Result<mongodb::Cursor<Verified_Contract_Result>, String> {
+        let pipeline = vec![
+            doc! { "$sort": { "contract_address": 1, "_id": -1 } },
+            doc! { "$group": { "_id": "$contract_address", "cvr": { "$first": "$$ROOT" } } },
+            doc! { "$replaceRoot": { "newRoot": "$cvr" } },
+        ];
+        let options = AggregateOptions::builder().allow_disk_use(true).build();
+        match self.cvr_collection().aggregate(pipeline, options).await {
+            Ok(cursor) => Ok(cursor.with_type()),
+            Err(_) => Err("Cannot load the contract_verify_responses.".into()),
+        }
+    }
+
+    pub async fn contract_addresses(&self) -> Result<Vec<String>, String> {
+        match self.cvr_collection().distinct("contract_address", None, None).await {
+            Ok(addresses) => Ok(addresses
+                .into_iter()
+                .filter_map(|address| address.as_str().map(str::to_string))
+                .collect()),
+            Err(_) => Err("Cannot load contract addresses.".into()),
+        }
+    }
+
+    /// Returns compiler versions stored contracts have been verified with.
+    pub async fn compiler_versions(&self) -> Result<Vec<String>, String> {
+        match self.cvr_collection().distinct("result.compiler_version", None, None).await {
+            Ok(versions) => Ok(versions
+                .into_iter()
+                .filter_map(|version| version.as_str().map(str::to_string))
+                .collect()),
+            Err(_) => Err("Cannot load compiler versions.".into()),
+        }
+    }
+
+    /// Returns up to `limit` compiler versions the most of stored contracts have been verified with.
+    pub async fn most_used_compiler_versions(&self, limit: i64) -> Result<Vec<String>, String> {
+        let usage = self.compiler_version_usage(limit).await?;
+        Ok(usage.into_iter().map(|(version, _)| version).collect())
+    }
+
+    /// Returns up to `limit` compiler versions the most of stored contracts have been verified with,
+    /// along with the number of contracts verified with each of them.
+    pub async fn compiler_version_usage(&self, limit: i64) -> Result<Vec<(String, u64)>, String> {
+        let pipeline = vec![
+            doc! { "$group": { "_id": "$result.compiler_version", "count": { "$sum": 1 } } },
+            doc! { "$sort": { "count": -1 } },
+            doc! { "$limit": limit },
+        ];
+        let documents: Result<Vec<Document>, _> = match self.cvr_collection().aggregate(pipeline, None).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        documents
+            .map(|documents| {
+                documents
+                    .iter()
+                    .filter_map(|document| {
+                        let version = document.get_str("_id").ok()?;
+                        // Sums are stored as i64 once they exceed i32
+                        let count = stats::number(document.get("count"))?;
+                        Some((version.to_string(), count))
+                    })
+                    .collect()
+            })
+            .map_err(|_| "Cannot load compiler versions.".into())
+    }
+
+    /// Groups stored solidity contracts by their compiler settings and the month they have been verified in.
+    pub async fn group_compiler_settings(&self) -> Result<Vec<SettingsGroup>, String> {
+        let pipeline = vec![
+            doc! { "$match": { "result.file_name": { "$regex": "\\.sol$" } } },
+            doc! { "$group": {
+                "_id": {
+                    "compiler_version": "$result.compiler_version",
+                    "evm_version": "$result.evm_version",
+                    "optimization": "$result.optimization",
+                    "optimization_runs": "$result.optimization_runs",
+                    "month": { "$substrCP": [{ "$ifNull": ["$verified_at", ""] }, 0, 7] },
+                },
+                "contracts": { "$sum": 1 },
+            } },
+        ];
+        let documents: Result<Vec<Document>, _> = match self.cvr_collection().aggregate(pipeline, None).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        documents
+            .map(|documents| documents.iter().map(SettingsGroup::from_document).collect())
+            .map_err(|_| "Cannot aggregate compiler settings of verified contracts.".into())
+    }
+
+    /// Returns the number of distinct addresses of stored contracts.
+    pub async fn count_verified_contracts(&self) -> Result<u64, String> {
+        let pipeline = vec![
+            doc! { "$group": { "_id": "$contract_address" } },
+            doc! { "$count": "count" },
+        ];
+        let documents: Result<Vec<Document>, _> = match self.cvr_collection().aggregate(pipeline, None).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        documents
+            .map(|documents| documents.first().and_then(|document| stats::number(document.get("count"))).unwrap_or_default())
+            .map_err(|_| "Cannot count verified contracts.".into())
+    }
+
+    /// Returns up to `limit` most recently stored contracts, the latest go first.
+    /// If `license` is set, only contracts declaring the license (alone or in an expression) are returned.
+    pub async fn find_recent_contracts(&self, limit: i64, license: Option<&str>) -> Result<Vec<RecentContract>, String> {
+        let filter = license.map(|license| doc! { "license_ids": license });
+        let projection = doc! { "contract_address": 1, "result.contract_name": 1, "result.compiler_version": 1, "result.licenses": 1, "verified_at": 1 };
+        let options = FindOptions::builder().sort(doc! { "_id": -1 }).limit(limit).projection(projection).build();
+        let documents: Result<Vec<Document>, _> = match self.db().collection::<Document>("cvr").find(filter, options).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        documents
+            .map(|documents| documents.iter().map(RecentContract::from_document).collect())
+            .map_err(|_| "Cannot load recent contracts.".into())
+    }
+
+    fn code_hash_counts_collection(&self) -> Collection<Document> {
+        self.db().collection("code_hash_counts")
+    }
+
+    /// Daily numbers of verification requests, counted towards the quotas of tenants.
+    fn quota_usage_collection(&self) -> Collection<Document> {
+        self.db().collection("quota_usage")
+    }
+
+    /// Returns up to `limit` codes shared by the most of stored contracts (at least two of them).
+    pub async fn find_top_duplicated_codes(&self, limit: i64) -> Result<Vec<DuplicatedCode>, String> {
+        let options = FindOptions::builder().sort(doc! { "count": -1, "_id": 1 }).limit(limit).build();
+        let documents: Result<Vec<Document>, _> = match self.code_hash_counts_collection().find(doc! { "count": { "$gt": 1 } }, options).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        documents
+            .map(|documents| documents.iter().map(DuplicatedCode::from_document).collect())
+            .map_err(|_| "Cannot load duplicated codes.".into())
+    }
+
+    /// Counts stored contracts by their code hashes, unless they are counted already
+    /// (e.g., for contracts stored before the counters were introduced).
+    ///
+    /// Contracts stored while the counters are seeded are counted by `add_contract_verify_response`,
+    /// so only the ones stored before the seeding has started are counted here, and their counts
+    /// are added to the counters rather than replacing them.
+    pub async fn seed_code_hash_counts(&self) -> Result<(), String> {
+        match self.code_hash_counts_collection().estimated_document_count(None).await {
+            Ok(0) => {}
+            Ok(_) => return Ok(()),
+            Err(_) => return Err("Cannot count the code hash counters.".into()),
+        }
+        let started = ObjectId::new();
+        let pipeline = vec![
+            doc! { "$match": { "_id": { "$lt": started }, "result.code_hash": { "$type": "string" } } },
+            doc! { "$sort": { "_id": 1 } },
+            doc! { "$group": {
+                "_id": "$result.code_hash",
+                "count": { "$sum": 1 },
+                "contract_address": { "$last": "$contract_address" },
+                "contract_name": { "$last": "$result.contract_name" },
+            } },
+            // Counters created since the seeding has started keep the contracts stored the latest
+            doc! { "$merge": {
+                "into": "code_hash_counts",
+                "on": "_id",
+                "whenMatched": [{ "$set": { "count": { "$add": ["$count", "$$new.count"] } } }],
+                "whenNotMatched": "insert",
+            } },
+        ];
+        let result: Result<Vec<Document>, _> = match self.cvr_collection().aggregate(pipeline, None).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        result.map(|_| ()).map_err(|_| "Cannot count contracts by code hashes.".into())
+    }
+
+    fn audit_collection(&self) -> Collection<AuditRecord> {
+        self.db().collection("audit")
+    }
+
+    /// Adds a result of stored contract re-verification to the audit history.
+    /// # Usage
+    /// ```rs
+    /// database.add_audit_record(audit_record).await;
+    /// ```
+    pub async fn add_audit_record(&self, record: AuditRecord) -> Result<(), String> {
+        match self.audit_collection().insert_one(record, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the audit record.".into()),
+        }
+    }
+
+    /// Returns the audit history of the given contract address, the latest records go first.
+    pub async fn find_audit_records(&self, contract_address: &str) -> Result<Vec<AuditRecord>, String> {
+        let options = FindOptions::builder().sort(doc! { "_id": -1 }).build();
+        let records = match self.audit_collection().find(doc! { "contract_address": contract_address.to_lowercase() }, options).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        records.map_err(|_| "Cannot load the audit records.".into())
+    }
+
+    fn cvr_history_collection(&self) -> Collection<Verified_Contract_Result> {
+        self.db().collection("cvr_history")
+    }
+
+    fn admin_actions_collection(&self) -> Collection<AdminAction> {
+        self.db().collection("admin_actions")
+    }
+
+    /// Moves all verification results stored for the contract into the history collection,
+    /// so that they are not served anymore, and records who has done that.
+    ///
+    /// Returns `false` if there were no stored results for the contract.
+    ///
+    /// All the changes are made in a single transaction (which requires MongoDB to run as a replica set),
+    /// so that results are neither lost nor duplicated in the history if the service stops in between.
+    /// # Usage
+    /// ```rs
+    /// database.retire_contract_verify_responses("0x...", AdminActionKind::Removed, "admin").await?;
+    /// ```
+    pub async fn retire_contract_verify_responses(&self, contract_address: &str, action: AdminActionKind, actor: &str) -> Result<bool, String> {
+        let mut session = self.mongo.start_session(None).await.map_err(|_| "Cannot start a database session.".to_string())?;
+        session.start_transaction(None).await.map_err(|_| "Cannot start a database transaction.".to_string())?;
+        // The transaction is aborted once the session is dropped, if any of the changes has failed
+        let retired = self.retire_contract_verify_responses_in(&mut session, contract_address, action, actor).await?;
+        match session.commit_transaction().await {
+            Ok(_) => Ok(retired),
+            Err(_) => Err("Cannot commit the retirement of the contract_verify_responses.".into()),
+        }
+    }
+
+    async fn retire_contract_verify_responses_in(&self, session: &mut ClientSession, contract_address: &str, action: AdminActionKind, actor: &str) -> Result<bool, String> {
+        let contract_address = contract_address.to_lowercase();
+        let filter = doc! { "contract_address": &contract_address };
+
+        let cvrs: Vec<Verified_Contract_Result> = match self.cvr_collection().find_with_session(filter.clone(), None, session).await {
+            Ok(mut cursor) => cursor.stream(session).try_collect().await.map_err(|_| "Cannot load the contract_verify_responses.".to_string())?,
+            Err(_) => return Err("Cannot load the contract_verify_responses.".into()),
+        };
+        if cvrs.is_empty() {
+            return Ok(false);
+        }
+
+        // Contracts sharing sources with the retired one get their own copy of the sources
+        if let Some(owner) = cvrs.iter().rev().find(|cvr| cvr.sources_of.is_none()) {
+            let update = shared_sources_update(owner)?;
+            if self.cvr_collection().update_many_with_session(doc! { "sources_of": &contract_address }, update, None, session).await.is_err() {
+                return Err("Cannot copy the shared sources.".into());
+            }
+        }
+
+        // Previous results are retained as history rather than hard-deleted
+        if self.cvr_history_collection().insert_many_with_session(&cvrs, None, session).await.is_err() {
+            return Err("Cannot save the contract_verify_responses history.".into());
+        }
+        if self.cvr_collection().delete_many_with_session(filter, None, session).await.is_err() {
+            return Err("Cannot remove the contract_verify_responses.".into());
+        }
+        for code_hash in cvrs.iter().filter_map(|cvr| cvr.result.code_hash.as_ref()) {
+            if self.code_hash_counts_collection().update_one_with_session(doc! { "_id": code_hash }, doc! { "$inc": { "count": -1 } }, None, session).await.is_err() {
+                return Err("Cannot update the code hash counters.".into());
+            }
+        }
+        // Counters point to the latest of the remaining contracts, and are removed once none remains
+        let code_hashes: BTreeSet<_> = cvrs.iter().filter_map(|cvr| cvr.result.code_hash.clone()).collect();
+        for code_hash in code_hashes {
+            let options = FindOneOptions::builder().sort(doc! { "_id": -1 }).build();
+            let latest = self.cvr_collection().find_one_with_session(doc! { "result.code_hash": &code_hash }, options, session).await.map_err(|_| "Cannot load the contract_verify_response.".to_string())?;
+            let updated = match latest {
+                Some(latest) => {
+                    let update = doc! { "$set": { "contract_address": latest.contract_address, "contract_name": latest.result.contract_name } };
+                    self.code_hash_counts_collection().update_one_with_session(doc! { "_id": &code_hash }, update, None, session).await.map(|_| ())
+                }
+                None => self.code_hash_counts_collection().delete_one_with_session(doc! { "_id": &code_hash }, None, session).await.map(|_| ()),
+            };
+            if updated.is_err() {
+                return Err("Cannot update the code hash counters.".into());
+            }
+        }
+        // Clones are not verified by similarity with the retired contract anymore
+        if self.derived_collection().delete_many_with_session(doc! { "source_address": &contract_address }, None, session).await.is_err() {
+            return Err("Cannot remove the derived verifications.".into());
+        }
+        // The input the contract has been verified with is not served anymore either
+        if self.compiler_inputs_collection().delete_one_with_session(doc! { "contract_address": &contract_address }, None, session).await.is_err() {
+            return Err("Cannot remove the archived compiler input.".into());
+        }
+
+        let admin_action = AdminAction {
+            contract_address,
+            action,
+            actor: actor.to_string(),
+            performed_at: Utc::now(),
+        };
+        match self.admin_actions_collection().insert_one_with_session(admin_action, None, session).await {
+            Ok(_) => Ok(true),
+            Err(_) => Err("Cannot save the admin action.".into()),
+        }
+    }
+
+    /// Returns admin actions performed over the given contract address, the latest records go first.
+    pub async fn find_admin_actions(&self, contract_address: &str) -> Result<Vec<AdminAction>, String> {
+        let options = FindOptions::builder().sort(doc! { "_id": -1 }).build();
+        let actions = match self.admin_actions_collection().find(doc! { "contract_address": contract_address.to_lowercase() }, options).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        actions.map_err(|_| "Cannot load the admin actions.".into())
+    }
+
+    fn attempts_collection(&self) -> Collection<VerificationAttempt> {
+        self.db().collection("attempts")
+    }
+
+    /// Adds a verification attempt (either successful or not) to the attempts collection of the database.
+    #[instrument(skip(self, attempt), level = "debug")]
+    pub async fn add_verification_attempt(&self, attempt: VerificationAttempt) -> Result<(), String> {
+        match self.attempts_collection().insert_one(attempt, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the verification attempt.".into()),
+        }
+    }
+
+    /// Returns verification attempts made for the given contract address, the latest records go first.
+    pub async fn find_verification_attempts(&self, contract_address: &str) -> Result<Vec<VerificationAttempt>, String> {
+        let options = FindOptions::builder().sort(doc! { "_id": -1 }).build();
+        let attempts = match self.attempts_collection().find(doc! { "contract_address": contract_address.to_lowercase() }, options).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        attempts.map_err(|_| "Cannot load the verification attempts.".into())
+    }
+
+    /// Groups verification attempts matching the filter by the given key expression,
+    /// counting total and successful attempts along with their duration. Groups are sorted by the key.
+    pub async fn group_verification_attempts(&self, filter: Document, key: Bson) -> Result<Vec<AttemptGroup>, String> {
+        let pipeline = vec![
+            doc! { "$match": filter },
+            doc! { "$group": {
+                "_id": key,
+                "total": { "$sum": 1 },
+                "successful": { "$sum": { "$cond": [{ "$eq": [{ "$ifNull": ["$failure_reason", null] }, null] }, 1, 0] } },
+                "duration_ms": { "$sum": "$duration_ms" },
+            } },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+        let documents: Result<Vec<Document>, _> = match self.attempts_collection().aggregate(pipeline, None).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        documents
+            .map(|documents| documents.iter().map(AttemptGroup::from_document).collect())
+            .map_err(|_| "Cannot aggregate the verification attempts.".into())
+    }
+
+    /// Counts a verification request made on the given day (e.g., "2022-12-01") towards the daily quota.
+    /// Returns the number of verification requests made that day, the counted one included.
+    pub async fn increment_daily_verifications(&self, day: &str) -> Result<u64, String> {
+        let options = FindOneAndUpdateOptions::builder().upsert(true).return_document(ReturnDocument::After).build();
+        match self.quota_usage_collection().find_one_and_update(doc! { "_id": day }, doc! { "$inc": { "verifications": 1_i64 } }, options).await {
+            Ok(usage) => Ok(usage.and_then(|usage| usage.get_i64("verifications").ok()).unwrap_or_default() as u64),
+            Err(_) => Err("Cannot count the verification towards the quota.".into()),
+        }
+    }
+
+    /// Takes back a verification request counted towards the daily quota on the given day.
+    pub async fn refund_daily_verification(&self, day: &str) -> Result<(), String> {
+        match self.quota_usage_collection().update_one(doc! { "_id": day, "verifications": { "$gt": 0_i64 } }, doc! { "$inc": { "verifications": -1_i64 } }, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot refund the verification counted towards the quota.".into()),
+        }
+    }
+
+    /// Returns the average duration (in milliseconds) of the latest `window` verification attempts
+    /// made with each of the given compiler versions. Versions no attempts have been made with are omitted.
+    /// Each version is aggregated separately, so that only the latest attempts are read (by the index
+    /// on the compiler version and id) rather than all the attempts made with the version.
+    pub async fn average_attempt_durations(&self, compiler_versions: Vec<String>, window: i64) -> Result<BTreeMap<String, u64>, String> {
+        let mut durations = BTreeMap::new();
+        for compiler_version in compiler_versions {
+            let pipeline = vec![
+                doc! { "$match": { "compiler_version": &compiler_version } },
+                doc! { "$sort": { "_id": -1 } },
+                doc! { "$limit": window },
+                doc! { "$group": { "_id": null, "duration_ms": { "$avg": "$duration_ms" } } },
+            ];
+            let documents: Result<Vec<Document>, _> = match self.attempts_collection().aggregate(pipeline, None).await {
+                Ok(cursor) => cursor.try_collect().await,
+                Err(err) => Err(err),
+            };
+            let documents = documents.map_err(|_| "Cannot aggregate durations of the verification attempts.".to_string())?;
+            if let Some(duration_ms) = documents.first().and_then(|document| document.get_f64("duration_ms").ok()) {
+                durations.insert(compiler_version, duration_ms as u64);
+            }
+        }
+        Ok(durations)
+    }
+
+    /// Returns addresses of up to `limit` contracts which have failed verification attempts
+    /// and are not verified yet. Contracts attempted the latest go first.
+    pub async fn unverified_attempt_addresses(&self, limit: i64) -> Result<Vec<String>, String> {
+        let pipeline = vec![
+            doc! { "$match": { "failure_reason": { "$ne": null } } },
+            doc! { "$group": { "_id": "$contract_address", "attempted_at": { "$max": "$attempted_at" } } },
+            doc! { "$sort": { "attempted_at": -1 } },
+            doc! { "$lookup": { "from": "cvr", "localField": "_id", "foreignField": "contract_address", "as": "verified" } },
+            doc! { "$match": { "verified": { "$size": 0 } } },
+            doc! { "$limit": limit },
+        ];
+        let documents: Result<Vec<Document>, _> = match self.attempts_collection().aggregate(pipeline, None).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        documents
+            .map(|documents| documents.iter().filter_map(|document| Some(document.get_str("_id").ok()?.to_string())).collect())
+            .map_err(|_| "Cannot load addresses of failed attempts.".into())
+    }
+
+    /// Returns the size (in bytes) of the documents and indexes stored in the database.
+    pub async fn storage_size(&self) -> Result<u64, String> {
+        let db_stats = match self.db().run_command(doc! { "dbStats": 1 }, None).await {
+            Ok(db_stats) => db_stats,
+            Err(_) => return Err("Cannot load the database stats.".into()),
+        };
+        let size = |key: &str| stats::number(db_stats.get(key)).unwrap_or_default();
+        Ok(size("dataSize") + size("indexSize"))
+    }
+
+    fn derived_collection(&self) -> Collection<DerivedVerification> {
+        self.db().collection("derived")
+    }
+
+    /// Adds a verification derived from another verified contract.
+    /// The previous derivation for the same contract is replaced.
+    #[instrument(skip(self, derived), level = "debug")]
+    pub async fn add_derived_verification(&self, derived: DerivedVerification) -> Result<(), String> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+        let filter = doc! { "contract_address": &derived.contract_address };
+        match self.derived_collection().replace_one(filter, derived, options).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the derived verification.".into()),
+        }
+    }
+
+    /// Returns verifications derived from the given verified contract.
+    pub async fn find_derived_verifications(&self, source_address: &str) -> Result<Vec<DerivedVerification>, String> {
+        let derived = match self.derived_collection().find(doc! { "source_address": source_address.to_lowercase() }, None).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        derived.map_err(|_| "Cannot load the derived verifications.".into())
+    }
+
+    fn implementations_collection(&self) -> Collection<ImplementationChange> {
+        self.db().collection("proxy_implementations")
+    }
+
+    /// Adds an implementation the proxy has been observed to delegate calls to.
+    /// The change is not added again if it has been recorded at the same block already.
+    #[instrument(skip(self, change), level = "debug")]
+    pub async fn add_implementation_change(&self, change: ImplementationChange) -> Result<(), String> {
+        let filter = doc! { "proxy_address": &change.proxy_address, "implementation": &change.implementation, "block_number": change.block_number as i64 };
+        let change = mongodb::bson::to_document(&change).map_err(|_| "Cannot serialize the implementation change.".to_string())?;
+        let options = UpdateOptions::builder().upsert(true).build();
+        match self.implementations_collection().update_one(filter, doc! { "$setOnInsert": change }, options).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the implementation change.".into()),
+        }
+    }
+
+    /// Removes all but the first of the implementation changes recorded for the same proxy at the same block.
+    async fn remove_duplicated_implementation_changes(&self) -> Result<u64, String> {
+        let pipeline = vec![
+            doc! { "$sort": { "_id": 1 } },
+            doc! { "$group": { "_id": { "proxy_address": "$proxy_address", "implementation": "$implementation", "block_number": "$block_number" }, "ids": { "$push": "$_id" } } },
+            doc! { "$match": { "ids.1": { "$exists": true } } },
+        ];
+        let options = AggregateOptions::builder().allow_disk_use(true).build();
+        let groups: Vec<Document> = match self.implementations_collection().aggregate(pipeline, options).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        }
+        .map_err(|_| "Cannot look up duplicated implementation changes.".to_string())?;
+        let duplicates: Vec<Bson> = groups.iter().filter_map(|group| group.get_array("ids").ok()).flat_map(|ids| ids.iter().skip(1).cloned()).collect();
+        if duplicates.is_empty() {
+            return Ok(0);
+        }
+        match self.implementations_collection().delete_many(doc! { "_id": { "$in": duplicates } }, None).await {
+            Ok(result) => Ok(result.deleted_count),
+            Err(_) => Err("Cannot remove duplicated implementation changes.".into()),
+        }
+    }
+
+    /// Updates the verification status of the recorded implementation.
+    pub async fn set_implementation_status(&self, change: &ImplementationChange) -> Result<(), String> {
+        let filter = doc! { "proxy_address": &change.proxy_address, "implementation": &change.implementation, "block_number": change.block_number as i64 };
+        let status = mongodb::bson::to_bson(&change.status).map_err(|_| "Cannot serialize the implementation status.".to_string())?;
+        match self.implementations_collection().update_one(filter, doc! { "$set": { "status": status, "source_address": change.source_address.clone() } }, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot update the implementation change.".into()),
+        }
+    }
+
+    /// Returns implementations of the proxy in the order they have been observed.
+    pub async fn find_implementation_changes(&self, proxy_address: &str) -> Result<Vec<ImplementationChange>, String> {
+        let options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+        let changes = match self.implementations_collection().find(doc! { "proxy_address": proxy_address.to_lowercase() }, options).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        changes.map_err(|_| "Cannot load the implementation changes.".into())
+    }
+
+    /// Returns the implementation of the proxy observed the latest, if any.
+    pub async fn find_latest_implementation_change(&self, proxy_address: &str) -> Result<Option<ImplementationChange>, String> {
+        let options = FindOneOptions::builder().sort(doc! { "_id": -1 }).build();
+        match self.implementations_collection().find_one(doc! { "proxy_address": proxy_address.to_lowercase() }, options).await {
+            Ok(change) => Ok(change),
+            Err(_) => Err("Cannot load the implementation change.".into()),
+        }
+    }
+
+    fn debug_info_collection(&self) -> Collection<DebugInfo> {
+        self.db().collection("debug_info")
+    }
+
+    /// Adds source maps and ASTs of the verified contract.
+    /// The debug info of the previous verification of the contract is replaced.
+    #[instrument(skip(self, debug_info), level = "debug")]
+    pub async fn add_debug_info(&self, mut debug_info: DebugInfo) -> Result<(), String> {
+        if let Some(blobs) = &self.blobs {
+            if let Some(key) = blobs.offload("debug-info", &debug_info.sources).await? {
+                debug_info.sources_blob = Some(key);
+                debug_info.sources = BTreeMap::new();
+            }
+        }
+        if debug_info.sources_blob.is_none() {
+            if let Some(compressed_sources) = Compressed::new(&debug_info.sources, self.encoding)? {
+                debug_info.compressed_sources = Some(compressed_sources);
+                debug_info.sources = BTreeMap::new();
+            }
+        }
+        let options = ReplaceOptions::builder().upsert(true).build();
+        let filter = doc! { "contract_address": &debug_info.contract_address };
+        match self.debug_info_collection().replace_one(filter, debug_info, options).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the debug info.".into()),
+        }
+    }
+
+    /// Returns source maps and ASTs of the verified contract, if stored.
+    pub async fn find_debug_info(&self, contract_address: &str) -> Result<Option<DebugInfo>, String> {
+        let mut debug_info = match self.debug_info_collection().find_one(doc! { "contract_address": contract_address.to_lowercase() }, None).await {
+            Ok(Some(debug_info)) => debug_info,
+            Ok(None) => return Ok(None),
+            Err(_) => return Err("Cannot load the debug info.".into()),
+        };
+        if let Some(key) = debug_info.sources_blob.take() {
+            let blobs = self.blobs.as_ref().ok_or_else(|| "The debug info is kept in the blob store, which is disabled.".to_string())?;
+            debug_info.sources = blobs.load(&key).await?;
+        }
+        if let Some(compressed_sources) = debug_info.compressed_sources.take() {
+            debug_info.sources = compressed_sources.decode()?;
+        }
+        Ok(Some(debug_info))
+    }
+
+    fn compiler_inputs_collection(&self) -> Collection<ArchivedCompilerInput> {
+        self.db().collection("compiler_inputs")
+    }
+
+    /// Archives the exact compiler input of the verified contract.
+    /// The input of the previous verification of the contract is replaced.
+    #[instrument(skip(self, compiler_input), level = "debug")]
+    pub async fn add_compiler_input(&self, compiler_input: ArchivedCompilerInput) -> Result<(), String> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+        let filter = doc! { "contract_address": &compiler_input.contract_address };
+        match self.compiler_inputs_collection().replace_one(filter, compiler_input, options).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the compiler input.".into()),
+        }
+    }
+
+    /// Returns the archived compiler input of the verified contract, if any.
+    pub async fn find_compiler_input(&self, contract_address: &str) -> Result<Option<ArchivedCompilerInput>, String> {
+        self.compiler_inputs_collection()
+            .find_one(doc! { "contract_address": contract_address.to_lowercase() }, None)
+            .await
+            .map_err(|_| "Cannot load the compiler input.".into())
+    }
+
+    fn metadata_availability_collection(&self) -> Collection<MetadataAvailability> {
+        self.db().collection("metadata_availability")
+    }
+
+    /// Records the result of the latest metadata availability check of the contract.
+    #[instrument(skip(self, availability), level = "debug")]
+    pub async fn set_metadata_availability(&self, availability: MetadataAvailability) -> Result<(), String> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+        let filter = doc! { "contract_address": &availability.contract_address };
+        match self.metadata_availability_collection().replace_one(filter, availability, options).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the metadata availability.".into()),
+        }
+    }
+
+    /// Returns the result of the latest metadata availability check of the contract, if any.
+    pub async fn find_metadata_availability(&self, contract_address: &str) -> Result<Option<MetadataAvailability>, String> {
+        self.metadata_availability_collection()
+            .find_one(doc! { "contract_address": contract_address.to_lowercase() }, None)
+            .await
+            .map_err(|_| "Cannot load the metadata availability.".into())
+    }
+
+    fn analysis_collection(&self) -> Collection<ContractAnalysis> {
+        self.db().collection("analysis")
+    }
+
+    /// Stores findings of the static analysis of the contract, replacing the previous ones.
+    pub async fn set_contract_analysis(&self, analysis: ContractAnalysis) -> Result<(), String> {
+        let filter = doc! { "contract_address": &analysis.contract_address };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        match self.analysis_collection().replace_one(filter, analysis, options).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the contract analysis.".into()),
+        }
+    }
+
+    pub async fn find_contract_analysis(&self, contract_address: &str) -> Result<Option<ContractAnalysis>, String> {
+        self.analysis_collection()
+            .find_one(doc! { "contract_address": contract_address.to_lowercase() }, None)
+            .await
+            .map_err(|_| "Cannot load the contract analysis.".into())
+    }
+
+    fn function_signatures_collection(&self) -> Collection<FunctionSignature> {
+        self.db().collection("function_signatures")
+    }
+
+    /// Adds function signatures to the selectors database. Already known signatures are skipped.
+    pub async fn add_function_signatures(&self, signatures: Vec<FunctionSignature>) -> Result<(), String> {
+        let options = UpdateOptions::builder().upsert(true).build();
+        for signature in signatures {
+            let filter = doc! { "selector": &signature.selector, "signature": &signature.signature };
+            let update = doc! { "$setOnInsert": { "selector": &signature.selector, "signature": &signature.signature } };
+            if self.function_signatures_collection().update_one(filter, update, options.clone()).await.is_err() {
+                return Err("Cannot save the function signature.".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns known signatures for any of the given selectors.
+    pub async fn find_function_signatures(&self, selectors: &[String]) -> Result<Vec<FunctionSignature>, String> {
+        let options = FindOptions::builder().sort(doc! { "signature": 1 }).build();
+        let signatures = match self.function_signatures_collection().find(doc! { "selector": { "$in": selectors } }, options).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        signatures.map_err(|_| "Cannot load the function signatures.".into())
+    }
+
+    fn event_signatures_collection(&self) -> Collection<EventSignature> {
+        self.db().collection("event_signatures")
+    }
+
+    /// Adds event signatures to the events database. Already known signatures are skipped.
+    pub async fn add_event_signatures(&self, signatures: Vec<EventSignature>) -> Result<(), String> {
+        let options = UpdateOptions::builder().upsert(true).build();
+        for signature in signatures {
+            let filter = doc! { "topic0": &signature.topic0, "signature": &signature.signature };
+            let update = doc! { "$setOnInsert": { "topic0": &signature.topic0, "signature": &signature.signature } };
+            if self.event_signatures_collection().update_one(filter, update, options.clone()).await.is_err() {
+                return Err("Cannot save the event signature.".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns known signatures for any of the given event topics.
+    pub async fn find_event_signatures(&self, topics: &[String]) -> Result<Vec<EventSignature>, String> {
+        let options = FindOptions::builder().sort(doc! { "signature": 1 }).build();
+        let signatures = match self.event_signatures_collection().find(doc! { "topic0": { "$in": topics } }, options).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        signatures.map_err(|_| "Cannot load the event signatures.".into())
+    }
+
+    fn access_log_collection(&self) -> Collection<AccessLogRecord> {
+        self.db().collection("access_log")
+    }
+
+    /// Adds a submitted request along with its outcome to the access log.
+    #[instrument(skip(self, record), level = "debug")]
+    pub async fn add_access_log_record(&self, record: AccessLogRecord) -> Result<(), String> {
+        match self.access_log_collection().insert_one(record, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the access log record.".into()),
+        }
+    }
+
+    /// Returns at most `limit` access log records matching the filter, the latest records go first.
+    pub async fn find_access_log_records(&self, filter: Document, limit: i64) -> Result<Vec<AccessLogRecord>, String> {
+        let options = FindOptions::builder().sort(doc! { "_id": -1 }).limit(limit).build();
+        let records = match self.access_log_collection().find(filter, options).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        records.map_err(|_| "Cannot load the access log records.".into())
+    }
+
+    /// Removes access log records received before the given time. Returns the number of removed records.
+    pub async fn remove_access_log_records_before(&self, received_before: DateTime) -> Result<u64, String> {
+        match self.access_log_collection().delete_many(doc! { "received_at": { "$lt": received_before } }, None).await {
+            Ok(result) => Ok(result.deleted_count),
+            Err(_) => Err("Cannot remove outdated access log records.".into()),
+        }
+    }
+
+    /// Converts receiving times of access log records stored as rfc3339 strings into dates,
+    /// so that the records are matched by time filters. Returns the number of converted records.
+    pub async fn convert_access_log_receiving_times(&self) -> Result<u64, String> {
+        let update = vec![doc! { "$set": { "received_at": { "$toDate": "$received_at" } } }];
+        match self.access_log_collection().update_many(doc! { "received_at": { "$type": "string" } }, update, None).await {
+            Ok(result) => Ok(result.modified_count),
+            Err(_) => Err("Cannot convert receiving times of access log records.".into()),
+        }
+    }
+
+    fn verification_jobs_collection(&self) -> Collection<VerificationJob> {
+        self.db().collection("verification_jobs")
+    }
+
+    #[instrument(skip(self, job), level = "debug")]
+    pub async fn add_verification_job(&self, job: VerificationJob) -> Result<(), String> {
+        match self.verification_jobs_collection().insert_one(job, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the verification job.".into()),
+        }
+    }
+
+    pub async fn find_verification_job(&self, job_id: ObjectId) -> Result<Option<VerificationJob>, String> {
+        match self.verification_jobs_collection().find_one(doc! { "_id": job_id }, None).await {
+            Ok(job) => Ok(job),
+            Err(_) => Err("Cannot load the verification job.".into()),
+        }
+    }
+
+    /// Deletes the job unless it has been leased already.
+    pub async fn delete_queued_verification_job(&self, job_id: ObjectId) -> Result<(), String> {
+        match self.verification_jobs_collection().delete_one(doc! { "_id": job_id, "state": "queued" }, None).await {
             Ok(_) => Ok(()),
-            Err(_) => Err("Cannot save the contract_verify_response.".into()),
+            Err(_) => Err("Cannot delete the verification job.".into()),
         }
     }
 
+    /// Counts running jobs and queued jobs submitted before the given one, grouped by
+    /// the state and compiler version. The jobs themselves are not loaded.
+    pub async fn count_verification_jobs_ahead(&self, job_id: ObjectId) -> Result<Vec<JobAhead>, String> {
+        let pipeline = vec![
+            doc! { "$match": { "$or": [{ "state": "running" }, { "state": "queued", "_id": { "$lt": job_id } }] } },
+            doc! { "$group": { "_id": { "state": "$state", "compiler_version": "$compiler_version" }, "count": { "$sum": 1_i64 } } },
+            doc! { "$project": { "_id": 0, "state": "$_id.state", "compiler_version": "$_id.compiler_version", "count": 1 } },
+        ];
+        let documents: Result<Vec<Document>, _> = match self.verification_jobs_collection().aggregate(pipeline, None).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        documents
+            .map_err(|_| "Cannot count the verification jobs.".to_string())?
+            .into_iter()
+            .map(|document| mongodb::bson::from_document(document).map_err(|_| "Cannot count the verification jobs.".to_string()))
+            .collect()
+    }
+
+    /// Leases the earliest submitted job which is either queued, or has been leased by
+    /// a worker which has not renewed the lease in time. The update is atomic, so that
+    /// each job is leased to one worker at a time.
+    pub async fn lease_verification_job(&self, worker: &str, lease_until: DateTime, max_attempts: u32) -> Result<Option<VerificationJob>, String> {
+        let filter = doc! {
+            "state": { "$in": ["queued", "running"] },
+            "available_at": { "$lte": DateTime::now() },
+            "attempts": { "$lt": i64::from(max_attempts) },
+        };
+        let update = doc! {
+            "$set": { "state": "running", "worker": worker, "available_at": lease_until },
+            "$inc": { "attempts": 1 },
+        };
+        let options = FindOneAndUpdateOptions::builder().sort(doc! { "_id": 1 }).return_document(ReturnDocument::After).build();
+        match self.verification_jobs_collection().find_one_and_update(filter, update, options).await {
+            Ok(job) => Ok(job),
+            Err(_) => Err("Cannot lease the verification job.".into()),
+        }
+    }
+
+    /// Updates the job with the given fields, if the lease of the job is still held by the worker
+    /// it has been leased to. Returns whether the job has been updated.
+    pub async fn update_leased_verification_job(&self, job: &VerificationJob, fields: Document) -> Result<bool, String> {
+        let filter = doc! {
+            "_id": job.id,
+            "state": "running",
+            "worker": &job.worker,
+            "attempts": i64::from(job.attempts),
+        };
+        match self.verification_jobs_collection().update_one(filter, doc! { "$set": fields }, None).await {
+            Ok(result) => Ok(result.matched_count > 0),
+            Err(_) => Err("Cannot update the verification job.".into()),
+        }
+    }
+
+    /// Fails running jobs which have used all the attempts and whose workers have stopped.
+    /// Returns the number of failed jobs.
+    pub async fn fail_abandoned_verification_jobs(&self, max_attempts: u32) -> Result<u64, String> {
+        let filter = doc! {
+            "state": "running",
+            "available_at": { "$lte": DateTime::now() },
+            "attempts": { "$gte": i64::from(max_attempts) },
+        };
+        let update = doc! {
+            "$set": {
+                "state": "failed",
+                "error": { "code": "INTERNAL_ERROR", "message": "Verification has been interrupted", "details": Bson::Null },
+                "finished_at": Utc::now().to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            },
+        };
+        match self.verification_jobs_collection().update_many(filter, update, None).await {
+            Ok(result) => Ok(result.modified_count),
+            Err(_) => Err("Cannot update abandoned verification jobs.".into()),
+        }
+    }
+
+    /// Removes finished and failed jobs finished before the given time (formatted as rfc3339).
+    /// Returns the number of removed jobs.
+    pub async fn remove_verification_jobs_finished_before(&self, finished_before: &str) -> Result<u64, String> {
+        let filter = doc! { "state": { "$in": ["finished", "failed"] }, "finished_at": { "$lt": finished_before } };
+        match self.verification_jobs_collection().delete_many(filter, None).await {
+            Ok(result) => Ok(result.deleted_count),
+            Err(_) => Err("Cannot remove outdated verification jobs.".into()),
+        }
+    }
+
+    fn verification_intents_collection(&self) -> Collection<VerificationIntent> {
+        self.db().collection("verification_intents")
+    }
+
+    #[instrument(skip(self, intent), level = "debug")]
+    pub async fn add_verification_intent(&self, intent: VerificationIntent) -> Result<(), String> {
+        match self.verification_intents_collection().insert_one(intent, None).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Cannot save the verification intent.".into()),
+        }
+    }
+
+    pub async fn find_verification_intent(&self, intent_id: ObjectId) -> Result<Option<VerificationIntent>, String> {
+        match self.verification_intents_collection().find_one(doc! { "_id": intent_id }, None).await {
+            Ok(intent) => Ok(intent),
+            Err(_) => Err("Cannot load the verification intent.".into()),
+        }
+    }
+
+    /// Returns pending intents with the given init code hash in the order they have been submitted.
+    pub async fn find_pending_verification_intents(&self, init_code_hash: &str) -> Result<Vec<VerificationIntent>, String> {
+        let filter = doc! {
+            "init_code_hash": init_code_hash,
+            "state": "pending",
+            "expires_at": { "$gt": DateTime::now() },
+        };
+        let options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+        let intents = match self.verification_intents_collection().find(filter, options).await {
+            Ok(cursor) => cursor.try_collect().await,
+            Err(err) => Err(err),
+        };
+        intents.map_err(|_| "Cannot load the verification intents.".into())
+    }
+
+    /// Marks the pending intent as matched by the contract verified by the job. The update is atomic,
+    /// so that each intent is matched by one contract. Returns whether the intent has been matched.
+    pub async fn match_verification_intent(&self, intent_id: ObjectId, contract_address: &str, job_id: ObjectId) -> Result<bool, String> {
+        let filter = doc! {
+            "_id": intent_id,
+            "state": "pending",
+            "expires_at": { "$gt": DateTime::now() },
+        };
+        let update = doc! {
+            "$set": { "state": "matched", "contract_address": contract_address, "job_id": job_id, "matched_at": Utc::now().to_rfc3339() },
+        };
+        match self.verification_intents_collection().update_one(filter, update, None).await {
+            Ok(result) => Ok(result.modified_count > 0),
+            Err(_) => Err("Cannot match the verification intent.".into()),
+        }
+    }
+
+}
+
+/// Update giving contracts which share sources with the retired `owner` their own copy of the sources,
+/// kept the same way as the owner keeps them (as is, in the blob store, or compressed).
+fn shared_sources_update(owner: &Verified_Contract_Result) -> Result<Document, String> {
+    let sources = mongodb::bson::to_bson(&owner.result.sources).map_err(|_| "Cannot serialize the sources.".to_string())?;
+    let mut update = doc! { "$set": { "result.sources": sources }, "$unset": { "sources_of": "" } };
+    if let Some(key) = &owner.sources_blob {
+        update.insert("$set", doc! { "result.sources": {}, "sources_blob": key });
+    }
+    if let Some(compressed_sources) = &owner.compressed_sources {
+        let compressed_sources = mongodb::bson::to_bson(compressed_sources).map_err(|_| "Cannot serialize the sources.".to_string())?;
+        update.insert("$set", doc! { "result.sources": {}, "compressed_sources": compressed_sources });
+    }
+    Ok(update)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verification_response::VerificationResult;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn owner() -> Verified_Contract_Result {
+        let result: VerificationResult = serde_json::from_value(json!({
+            "file_name": "A.sol",
+            "contract_name": "A",
+            "compiler_version": "v0.8.14+commit.80d49f37",
+            "evm_version": "london",
+            "constructor_arguments": null,
+            "optimization": false,
+            "optimization_runs": null,
+            "contract_libraries": {},
+            "abi": "[]",
+            "sources": { "A.sol": "contract A {}" },
+            "compiler_settings": "{}",
+        }))
+        .unwrap();
+        Verified_Contract_Result {
+            contract_address: "0xbebebebebebebebebebebebebebebebebebebebe".to_string(),
+            result,
+            sources_of: None,
+            sources_blob: None,
+            compressed_sources: None,
+            repository: None,
+            content_hash: None,
+            license_ids: vec![],
+            verified_at: None,
+            job_id: None,
+            creation: None,
+            token_info: None,
+        }
+    }
+
+    #[test]
+    fn shared_sources_are_copied() {
+        assert_eq!(
+            doc! { "$set": { "result.sources": { "A.sol": "contract A {}" } }, "$unset": { "sources_of": "" } },
+            shared_sources_update(&owner()).unwrap()
+        );
+    }
+
+    #[test]
+    fn shared_blob_is_referenced() {
+        let owner = Verified_Contract_Result { sources_blob: Some("sources/abc".to_string()), ..owner() };
+        assert_eq!(
+            doc! { "$set": { "result.sources": {}, "sources_blob": "sources/abc" }, "$unset": { "sources_of": "" } },
+            shared_sources_update(&owner).unwrap()
+        );
+    }
 }
@@ -0,0 +1,138 @@
+use crate::compression::Compressed;
+use ethers_solc::CompilerOutput;
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::VerificationSuccess;
+use std::collections::BTreeMap;
+
+/// Source maps and ASTs of a verified contract, which allow step-debuggers and coverage
+/// tools to map program counters of the on-chain code back to the source lines.
+///
+/// Is stored aside from the verified contract due to its size, and only if enabled.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DebugInfo {
+    pub contract_address: String,
+    /// Source map of the creation bytecode
+    /// (https://docs.soliditylang.org/en/latest/internals/source_mappings.html).
+    pub creation_source_map: Option<String>,
+    /// Source map of the deployed bytecode.
+    pub deployed_source_map: Option<String>,
+    /// Compiled source files by their paths. Source maps refer to the files by ids.
+    pub sources: BTreeMap<String, DebugSource>,
+    /// If set, `sources` are not stored in the database, but kept in the blob store
+    /// under the given key, as ASTs may be too large for database documents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sources_blob: Option<String>,
+    /// If set, `sources` are not stored in the database as is, but compressed into this field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compressed_sources: Option<Compressed>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DebugSource {
+    pub id: u32,
+    pub ast: Option<serde_json::Value>,
+}
+
+impl DebugInfo {
+    /// Extracts the debug info of the verified contract from the compiler output.
+    /// Returns `None` if the compiler has returned no source maps (e.g., for vyper contracts).
+    pub fn new(contract_address: &str, success: &VerificationSuccess) -> Option<Self> {
+        Self::from_output(
+            contract_address,
+            &success.compiler_output,
+            &success.file_path,
+            &success.contract_name,
+        )
+    }
+
+    fn from_output(
+        contract_address: &str,
+        output: &CompilerOutput,
+        file_path: &str,
+        contract_name: &str,
+    ) -> Option<Self> {
+        let evm = output
+            .contracts
+            .get(file_path)?
+            .get(contract_name)?
+            .evm
+            .as_ref()?;
+        let creation_source_map = evm
+            .bytecode
+            .as_ref()
+            .and_then(|bytecode| bytecode.source_map.clone());
+        let deployed_source_map = evm
+            .deployed_bytecode
+            .as_ref()
+            .and_then(|deployed| deployed.bytecode.as_ref())
+            .and_then(|bytecode| bytecode.source_map.clone());
+        if creation_source_map.is_none() && deployed_source_map.is_none() {
+            return None;
+        }
+
+        let sources = output
+            .sources
+            .iter()
+            .map(|(path, source)| {
+                let ast = source
+                    .ast
+                    .as_ref()
+                    .and_then(|ast| serde_json::to_value(ast).ok());
+                (path.clone(), DebugSource { id: source.id, ast })
+            })
+            .collect();
+        Some(Self {
+            contract_address: contract_address.to_lowercase(),
+            creation_source_map,
+            deployed_source_map,
+            sources,
+            sources_blob: None,
+            compressed_sources: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn debug_info_is_extracted() {
+        let ast = json!({
+            "absolutePath": "A.sol",
+            "id": 2,
+            "nodeType": "SourceUnit",
+            "nodes": [],
+            "src": "0:13:0",
+        });
+        let output: CompilerOutput = serde_json::from_value(json!({
+            "contracts": {
+                "A.sol": {
+                    "A": {
+                        "evm": {
+                            "bytecode": { "object": "0x6080", "sourceMap": "0:13:0:-:0;;;" },
+                            "deployedBytecode": { "object": "0x6080", "sourceMap": "0:13:0:-:0;;" },
+                        },
+                    },
+                },
+            },
+            "sources": { "A.sol": { "id": 0, "ast": ast } },
+        }))
+        .unwrap();
+
+        let info = DebugInfo::from_output("0xBEBE", &output, "A.sol", "A").unwrap();
+        assert_eq!("0xbebe", info.contract_address);
+        assert_eq!(Some("0:13:0:-:0;;;"), info.creation_source_map.as_deref());
+        assert_eq!(Some("0:13:0:-:0;;"), info.deployed_source_map.as_deref());
+        assert_eq!(0, info.sources["A.sol"].id);
+        assert!(info.sources["A.sol"].ast.is_some());
+
+        assert_eq!(
+            None,
+            DebugInfo::from_output("0xBEBE", &output, "A.sol", "B"),
+            "missing contract has no debug info"
+        );
+    }
+}
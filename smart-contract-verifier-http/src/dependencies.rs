@@ -0,0 +1,576 @@
+use crate::{git_import, settings::DependenciesSettings};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, VecDeque},
+    fmt,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use thiserror::Error;
+use url::Url;
+
+/// Max number of files injected into a single request.
+const MAX_INJECTED: usize = 1000;
+/// Max number of package versions declared by a single request.
+const MAX_DECLARED: usize = 20;
+
+#[derive(Error, Debug)]
+pub enum DependencyError {
+    #[error("invalid package {0}, expected <name>@<exact version>")]
+    InvalidPackage(String),
+    #[error("no more than {MAX_DECLARED} packages may be declared")]
+    TooManyPackages,
+    #[error("package {0} is not available, and fetching packages is disabled")]
+    FetchDisabled(String),
+    #[error("package {0} is not found in the npm registry")]
+    NotFound(String),
+    #[error("npm registry is unavailable: {0}")]
+    Unavailable(String),
+    #[error("package {0} exceeds {1} megabytes")]
+    TooLarge(String, u64),
+    #[error("invalid tarball of package {0}: {1}")]
+    InvalidTarball(String, std::io::Error),
+    #[error("cannot cache package {0}: {1}")]
+    Cache(String, std::io::Error),
+}
+
+/// Exact version of the npm package declared by the request (e.g., "@openzeppelin/contracts@4.9.3").
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageVersion {
+    pub name: String,
+    pub version: semver::Version,
+}
+
+impl FromStr for PackageVersion {
+    type Err = DependencyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DependencyError::InvalidPackage(s.to_string());
+        // The name of scoped packages starts with '@' as well
+        let (name, version) = s.rsplit_once('@').ok_or_else(invalid)?;
+        if !is_valid_name(name) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            version: semver::Version::parse(version).map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl fmt::Display for PackageVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+/// Npm package names are restricted to url-safe characters, so that
+/// they could be used as directory names as well.
+fn is_valid_name(name: &str) -> bool {
+    let is_valid_part = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with('.')
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-._~".contains(c))
+    };
+    match name.strip_prefix('@') {
+        Some(scoped) => matches!(
+            scoped.split_once('/'),
+            Some((scope, package)) if is_valid_part(scope) && is_valid_part(package)
+        ),
+        None => is_valid_part(name),
+    }
+}
+
+/// Sources of well-known packages (e.g., "@openzeppelin/contracts") pinned to configured
+/// versions, which are injected into multi-part requests importing them without
+/// providing their files.
+#[derive(Debug, Clone, Default)]
+pub struct PackageRegistry {
+    /// Solidity files of the pinned versions by their import paths
+    /// (e.g., "@openzeppelin/contracts/access/Ownable.sol"). Are shared by the registries
+    /// of the requests declaring their own package versions.
+    files: Arc<BTreeMap<PathBuf, String>>,
+    /// Files of the package versions declared by the request, which replace
+    /// all pinned files of the same packages.
+    declared_files: BTreeMap<PathBuf, String>,
+    declared_packages: Vec<String>,
+    registry_dir: PathBuf,
+    /// Set if package versions missing in `registry_dir` may be fetched.
+    npm: Option<NpmRegistry>,
+}
+
+#[derive(Debug, Clone)]
+struct NpmRegistry {
+    client: reqwest::Client,
+    url: Url,
+    max_package_size_mb: u64,
+}
+
+/// Part of the package version document returned by the npm registry.
+#[derive(Debug, Deserialize)]
+struct VersionManifest {
+    dist: Dist,
+}
+
+#[derive(Debug, Deserialize)]
+struct Dist {
+    tarball: Url,
+}
+
+impl PackageRegistry {
+    /// Loads solidity files of the pinned versions of the packages, each of which
+    /// is expected in `<registry_dir>/<package>@<version>` directory.
+    pub fn load(settings: &DependenciesSettings) -> anyhow::Result<Self> {
+        let mut files = BTreeMap::new();
+        for (package, version) in &settings.packages {
+            let dir = settings.registry_dir.join(format!("{package}@{version}"));
+            collect_files(&dir, Path::new(package), &mut files).map_err(|err| {
+                anyhow::anyhow!("cannot load package {package}@{version} from {dir:?}: {err}")
+            })?;
+        }
+        let npm = match &settings.npm_registry_url {
+            Some(url) => {
+                std::fs::create_dir_all(&settings.registry_dir)?;
+                let client = reqwest::Client::builder()
+                    .timeout(Duration::from_secs(settings.fetch_timeout_secs))
+                    .build()?;
+                // Package names are joined to the url, which requires a trailing slash
+                let mut url = url.clone();
+                if !url.path().ends_with('/') {
+                    url.set_path(&format!("{}/", url.path()));
+                }
+                Some(NpmRegistry {
+                    client,
+                    url,
+                    max_package_size_mb: settings.max_package_size_mb,
+                })
+            }
+            None => None,
+        };
+        Ok(Self {
+            files: Arc::new(files),
+            declared_files: BTreeMap::new(),
+            declared_packages: vec![],
+            registry_dir: settings.registry_dir.clone(),
+            npm,
+        })
+    }
+
+    /// Returns the registry with the package versions declared by the request
+    /// replacing the pinned ones. Versions missing in `registry_dir` are fetched
+    /// from the npm registry and cached there. Files of the pinned versions are not copied.
+    pub async fn with_packages(
+        &self,
+        packages: &[PackageVersion],
+    ) -> Result<Cow<'_, Self>, DependencyError> {
+        if packages.is_empty() {
+            return Ok(Cow::Borrowed(self));
+        }
+        if packages.len() > MAX_DECLARED {
+            return Err(DependencyError::TooManyPackages);
+        }
+        let mut registry = self.clone();
+        for package in packages {
+            let dir = self.registry_dir.join(package.to_string());
+            if !dir.is_dir() {
+                self.fetch(package, &dir).await?;
+            }
+            let mut files = BTreeMap::new();
+            collect_files(&dir, Path::new(&package.name), &mut files)
+                .map_err(|err| DependencyError::Cache(package.to_string(), err))?;
+            registry
+                .declared_files
+                .retain(|path, _| !path.starts_with(&package.name));
+            registry.declared_files.extend(files);
+            registry.declared_packages.push(package.name.clone());
+        }
+        Ok(Cow::Owned(registry))
+    }
+
+    /// Returns the file by its import path, looking it up in the declared
+    /// package versions first.
+    fn file(&self, import_path: &Path) -> Option<(&PathBuf, &String)> {
+        let declared = self
+            .declared_packages
+            .iter()
+            .any(|package| import_path.starts_with(package));
+        match declared {
+            true => self.declared_files.get_key_value(import_path),
+            false => self.files.get_key_value(import_path),
+        }
+    }
+
+    async fn fetch(&self, package: &PackageVersion, dir: &Path) -> Result<(), DependencyError> {
+        let npm = self
+            .npm
+            .as_ref()
+            .ok_or_else(|| DependencyError::FetchDisabled(package.to_string()))?;
+        let tarball = npm.tarball(package).await?;
+        tracing::info!("caching package {} into {:?}", package, dir);
+
+        let (registry_dir, dir) = (self.registry_dir.clone(), dir.to_path_buf());
+        let (package, max_size) = (package.to_string(), npm.max_package_size_mb);
+        tokio::task::spawn_blocking(move || {
+            unpack(&tarball, &registry_dir, &dir, &package, max_size)
+        })
+        .await
+        .expect("unpacking does not panic")
+    }
+
+    /// Adds files of the registry packages which are imported by the sources (directly or
+    /// through other injected files) but are missing in them. Imports are resolved with
+    /// the remappings the way the compiler does. Returns paths of the added files.
+    pub fn inject(
+        &self,
+        sources: &mut BTreeMap<PathBuf, String>,
+        remappings: &[String],
+    ) -> Vec<PathBuf> {
+        // Paths of the files to be checked, along with their import paths
+        // in the registry for the injected ones
+        let mut queue: VecDeque<(PathBuf, Option<PathBuf>)> =
+            sources.keys().map(|path| (path.clone(), None)).collect();
+        let mut injected = Vec::new();
+        while let Some((path, registry_path)) = queue.pop_front() {
+            let content = match sources.get(&path) {
+                Some(content) => content.clone(),
+                None => continue,
+            };
+            for import in git_import::imports(&content) {
+                let resolved = match git_import::resolve_import(&path, &import, remappings) {
+                    Some(resolved) if !sources.contains_key(&resolved) => resolved,
+                    _ => continue,
+                };
+                // Relative imports of the injected files are looked up next to them
+                let relative = import.starts_with("./") || import.starts_with("../");
+                let import_path = match (relative, &registry_path) {
+                    (false, _) => git_import::normalize(Path::new(&import)),
+                    (true, Some(registry_path)) => git_import::normalize(
+                        &registry_path
+                            .parent()
+                            .unwrap_or_else(|| Path::new(""))
+                            .join(&import),
+                    ),
+                    (true, None) => None,
+                };
+                let (import_path, file) =
+                    match import_path.and_then(|import_path| self.file(&import_path)) {
+                        Some(found) => found,
+                        None => continue,
+                    };
+                if injected.len() >= MAX_INJECTED {
+                    return injected;
+                }
+                sources.insert(resolved.clone(), file.clone());
+                injected.push(resolved.clone());
+                queue.push_back((resolved, Some(import_path.clone())));
+            }
+        }
+        injected
+    }
+}
+
+impl NpmRegistry {
+    async fn tarball(&self, package: &PackageVersion) -> Result<Vec<u8>, DependencyError> {
+        // Api keys of private mirrors may be passed in the url
+        let unavailable =
+            |err: reqwest::Error| DependencyError::Unavailable(err.without_url().to_string());
+        let too_large = || DependencyError::TooLarge(package.to_string(), self.max_package_size_mb);
+        let url = self
+            .url
+            .join(&format!("{}/{}", package.name, package.version))
+            .map_err(|_| DependencyError::InvalidPackage(package.to_string()))?;
+        let response = self.client.get(url).send().await.map_err(unavailable)?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(DependencyError::NotFound(package.to_string()));
+        }
+        let manifest: VersionManifest = response
+            .error_for_status()
+            .map_err(unavailable)?
+            .json()
+            .await
+            .map_err(unavailable)?;
+
+        let max_size = self.max_package_size_mb.saturating_mul(1024 * 1024);
+        let response = self
+            .client
+            .get(manifest.dist.tarball)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(unavailable)?;
+        if response
+            .content_length()
+            .map_or(false, |length| length > max_size)
+        {
+            return Err(too_large());
+        }
+        let tarball = response.bytes().await.map_err(unavailable)?;
+        if tarball.len() as u64 > max_size {
+            return Err(too_large());
+        }
+        Ok(tarball.to_vec())
+    }
+}
+
+/// Extracts solidity files of the gzipped npm tarball into `dir`. Files are extracted into
+/// a temporary directory first, so that partially extracted packages are never cached.
+fn unpack(
+    tarball: &[u8],
+    registry_dir: &Path,
+    dir: &Path,
+    package: &str,
+    max_size_mb: u64,
+) -> Result<(), DependencyError> {
+    let invalid = |err| DependencyError::InvalidTarball(package.to_string(), err);
+    let cache = |err| DependencyError::Cache(package.to_string(), err);
+    let max_size = max_size_mb.saturating_mul(1024 * 1024);
+
+    let tmp_dir = tempfile::tempdir_in(registry_dir).map_err(cache)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(tarball));
+    let mut total_size = 0;
+    for entry in archive.entries().map_err(invalid)? {
+        let mut entry = entry.map_err(invalid)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        // Files of npm tarballs are placed into a single top-level directory (usually "package")
+        let path: PathBuf = entry
+            .path()
+            .map_err(invalid)?
+            .components()
+            .skip(1)
+            .collect();
+        let path = match git_import::normalize(&path) {
+            Some(path)
+                if path
+                    .extension()
+                    .map_or(false, |extension| extension == "sol") =>
+            {
+                path
+            }
+            _ => continue,
+        };
+        total_size += entry.size();
+        if total_size > max_size {
+            return Err(DependencyError::TooLarge(package.to_string(), max_size_mb));
+        }
+        let mut content = String::new();
+        entry.read_to_string(&mut content).map_err(invalid)?;
+        let target = tmp_dir.path().join(path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(cache)?;
+        }
+        std::fs::write(target, content).map_err(cache)?;
+    }
+
+    if let Some(parent) = dir.parent() {
+        std::fs::create_dir_all(parent).map_err(cache)?;
+    }
+    match std::fs::rename(tmp_dir.path(), dir) {
+        // The same package may have been cached by a concurrent request
+        Err(_) if dir.is_dir() => Ok(()),
+        result => result.map_err(cache),
+    }
+}
+
+pub(crate) fn collect_files(
+    dir: &Path,
+    prefix: &Path,
+    files: &mut BTreeMap<PathBuf, String>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let import_path = prefix.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_files(&path, &import_path, files)?;
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension == "sol")
+        {
+            files.insert(import_path, std::fs::read_to_string(&path)?);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use pretty_assertions::assert_eq;
+
+    fn registry() -> PackageRegistry {
+        let files = BTreeMap::from([
+            (
+                "@openzeppelin/contracts/token/ERC20/ERC20.sol".into(),
+                r#"import "./IERC20.sol"; import "../../utils/Context.sol";"#.into(),
+            ),
+            (
+                "@openzeppelin/contracts/token/ERC20/IERC20.sol".into(),
+                "interface IERC20 {}".into(),
+            ),
+            (
+                "@openzeppelin/contracts/utils/Context.sol".into(),
+                "abstract contract Context {}".into(),
+            ),
+        ]);
+        PackageRegistry {
+            files: Arc::new(files),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn missing_imports_are_injected() {
+        let mut sources = BTreeMap::from([(
+            PathBuf::from("src/Token.sol"),
+            r#"import "@openzeppelin/contracts/token/ERC20/ERC20.sol"; import "./Missing.sol";"#
+                .to_string(),
+        )]);
+        let injected = registry().inject(&mut sources, &[]);
+        assert_eq!(
+            vec![
+                PathBuf::from("@openzeppelin/contracts/token/ERC20/ERC20.sol"),
+                PathBuf::from("@openzeppelin/contracts/token/ERC20/IERC20.sol"),
+                PathBuf::from("@openzeppelin/contracts/utils/Context.sol"),
+            ],
+            injected
+        );
+        assert_eq!(4, sources.len());
+    }
+
+    #[test]
+    fn provided_and_remapped_files_are_respected() {
+        let remappings = vec!["@openzeppelin/=lib/openzeppelin-contracts/".to_string()];
+        let mut sources = BTreeMap::from([
+            (
+                PathBuf::from("src/Token.sol"),
+                r#"import "@openzeppelin/contracts/token/ERC20/ERC20.sol";"#.to_string(),
+            ),
+            (
+                PathBuf::from("lib/openzeppelin-contracts/contracts/token/ERC20/IERC20.sol"),
+                "interface IERC20 { function custom() external; }".to_string(),
+            ),
+        ]);
+        let injected = registry().inject(&mut sources, &remappings);
+        assert_eq!(
+            vec![
+                PathBuf::from("lib/openzeppelin-contracts/contracts/token/ERC20/ERC20.sol"),
+                PathBuf::from("lib/openzeppelin-contracts/contracts/utils/Context.sol"),
+            ],
+            injected
+        );
+        assert_eq!(
+            "interface IERC20 { function custom() external; }",
+            sources[Path::new("lib/openzeppelin-contracts/contracts/token/ERC20/IERC20.sol")]
+        );
+    }
+
+    #[tokio::test]
+    async fn declared_packages_replace_pinned_ones() {
+        let registry_dir = tempfile::tempdir().unwrap();
+        for (path, content) in [
+            (
+                "@openzeppelin/contracts@5.0.0/utils/Context.sol",
+                "// 5.0.0",
+            ),
+            ("solmate@6.2.0/tokens/ERC20.sol", "// solmate"),
+        ] {
+            let path = registry_dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        let registry = PackageRegistry {
+            registry_dir: registry_dir.path().to_path_buf(),
+            ..registry()
+        };
+        let packages = [
+            PackageVersion::from_str("@openzeppelin/contracts@5.0.0").unwrap(),
+            PackageVersion::from_str("solmate@6.2.0").unwrap(),
+        ];
+        let declared = registry.with_packages(&packages).await.unwrap();
+
+        let mut sources = BTreeMap::from([(
+            PathBuf::from("src/Token.sol"),
+            r#"import "@openzeppelin/contracts/utils/Context.sol";
+import "@openzeppelin/contracts/token/ERC20/IERC20.sol";
+import "solmate/tokens/ERC20.sol";"#
+                .to_string(),
+        )]);
+        let injected = declared.inject(&mut sources, &[]);
+        assert_eq!(
+            vec![
+                PathBuf::from("@openzeppelin/contracts/utils/Context.sol"),
+                PathBuf::from("solmate/tokens/ERC20.sol"),
+            ],
+            injected
+        );
+        assert_eq!(
+            "// 5.0.0",
+            sources[Path::new("@openzeppelin/contracts/utils/Context.sol")]
+        );
+        // Pinned files are shared rather than copied, and are kept as is
+        assert!(Arc::ptr_eq(&registry.files, &declared.files));
+        assert!(registry.declared_files.is_empty());
+    }
+
+    #[test]
+    fn package_versions_are_parsed() {
+        let package = PackageVersion::from_str("@openzeppelin/contracts@4.9.3").unwrap();
+        assert_eq!("@openzeppelin/contracts", package.name);
+        assert_eq!("4.9.3", package.version.to_string());
+        assert!(PackageVersion::from_str("solmate@6.2.0").is_ok());
+
+        for invalid in [
+            "@openzeppelin/contracts",
+            "@openzeppelin/contracts@^4.9.0",
+            "@openzeppelin/../contracts@4.9.3",
+            "../contracts@4.9.3",
+            "Contracts@4.9.3",
+        ] {
+            assert!(
+                PackageVersion::from_str(invalid).is_err(),
+                "{invalid} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn tarballs_are_unpacked() {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, content) in [
+            ("package/package.json", "{}"),
+            ("package/token/ERC20/ERC20.sol", "contract ERC20 {}"),
+            ("package/README.md", "# Contracts"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, content.as_bytes())
+                .unwrap();
+        }
+        let tarball = builder.into_inner().unwrap().finish().unwrap();
+
+        let registry_dir = tempfile::tempdir().unwrap();
+        let dir = registry_dir.path().join("@openzeppelin/contracts@4.9.3");
+        unpack(&tarball, registry_dir.path(), &dir, "contracts", 1).unwrap();
+        let mut files = BTreeMap::new();
+        collect_files(&dir, Path::new("@openzeppelin/contracts"), &mut files).unwrap();
+        assert_eq!(
+            BTreeMap::from([(
+                PathBuf::from("@openzeppelin/contracts/token/ERC20/ERC20.sol"),
+                "contract ERC20 {}".to_string()
+            )]),
+            files
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{DisplayBytes, DB};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{
+    rpc::BytecodeFetcher,
+    tools::{code_hash::stripped_code_hash, minimal_proxy},
+};
+use std::{str::FromStr, sync::Arc};
+
+/// Max number of contracts checked for being clones of each verified contract.
+/// Contracts attempted the latest are checked first.
+const MAX_FAN_OUT_CANDIDATES: i64 = 500;
+
+/// The way the contract code is related to the code of the verified contract.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DerivationKind {
+    /// EIP-1167 minimal proxy delegating calls to the verified contract.
+    MinimalProxy,
+    /// The same code as the verified contract has, up to the metadata.
+    IdenticalCode,
+}
+
+/// A contract considered verified by similarity with the verified one.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DerivedVerification {
+    pub contract_address: String,
+    /// The verified contract the verification has been derived from.
+    pub source_address: String,
+    pub kind: DerivationKind,
+    pub derived_at: DateTime<Utc>,
+}
+
+/// Checks addresses of contracts which verification has been attempted but not succeeded,
+/// and marks clones of the just verified contract as verified by similarity.
+///
+/// Runs in the background after the contract has been stored, so that the verification
+/// response is not delayed.
+pub fn spawn_fan_out(
+    fetcher: Arc<BytecodeFetcher>,
+    namespace: String,
+    source_address: String,
+    code_hash: Option<String>,
+) {
+    tokio::spawn(async move {
+        let database = DB::new().await.change_name(&namespace);
+        let fan_out = fan_out(&database, &fetcher, &source_address, code_hash.as_deref());
+        if let Err(err) = fan_out.await {
+            tracing::error!(
+                "cannot derive verifications from {}: {}",
+                source_address,
+                err
+            );
+        }
+    });
+}
+
+async fn fan_out(
+    database: &DB,
+    fetcher: &BytecodeFetcher,
+    source_address: &str,
+    code_hash: Option<&str>,
+) -> Result<(), String> {
+    let candidates = database
+        .unverified_attempt_addresses(MAX_FAN_OUT_CANDIDATES)
+        .await?;
+    if candidates.is_empty() {
+        return Ok(());
+    }
+
+    let codes = fetcher.get_code_batch(&candidates).await;
+    let derived: Vec<_> = candidates
+        .into_iter()
+        .zip(codes)
+        .filter_map(|(address, code)| {
+            let code = DisplayBytes::from_str(&code.ok()??).ok()?;
+            let kind = derivation_kind(&code.0, source_address, code_hash)?;
+            Some(DerivedVerification {
+                contract_address: address,
+                source_address: source_address.to_string(),
+                kind,
+                derived_at: Utc::now(),
+            })
+        })
+        .collect();
+    tracing::info!(
+        "{} verifications have been derived from {}",
+        derived.len(),
+        source_address
+    );
+    for derived in derived {
+        database.add_derived_verification(derived).await?;
+    }
+    Ok(())
+}
+
+/// Looks up the verified contract the code is a clone of: the implementation of the EIP-1167
+/// minimal proxy, or a contract with the same code up to the metadata (by the code hash index).
+pub async fn find_source(
+    database: &DB,
+    contract_address: &str,
+    code: &[u8],
+) -> Result<Option<(String, DerivationKind)>, String> {
+    if code.is_empty() {
+        return Ok(None);
+    }
+    if let Some(implementation) = minimal_proxy::implementation(code) {
+        let implementation = format!("0x{}", hex::encode(implementation.as_bytes()));
+        let verified = database.is_contract_verified(&implementation).await?;
+        return Ok(verified.then_some((implementation, DerivationKind::MinimalProxy)));
+    }
+    let code_hash = format!("{:#x}", stripped_code_hash(code));
+    let source_address = database
+        .find_contracts_by_code_hash(&code_hash)
+        .await?
+        .into_iter()
+        .find(|address| address != contract_address);
+    Ok(source_address.map(|address| (address, DerivationKind::IdenticalCode)))
+}
+
+/// Returns how the code is related to the verified contract, if it is a clone of the contract.
+fn derivation_kind(
+    code: &[u8],
+    source_address: &str,
+    code_hash: Option<&str>,
+) -> Option<DerivationKind> {
+    if let Some(implementation) = minimal_proxy::implementation(code) {
+        let implementation = format!("0x{}", hex::encode(implementation.as_bytes()));
+        return (implementation == source_address).then_some(DerivationKind::MinimalProxy);
+    }
+    let code_hash = code_hash?;
+    (!code.is_empty() && format!("{:#x}", stripped_code_hash(code)) == code_hash)
+        .then_some(DerivationKind::IdenticalCode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const SOURCE: &str = "0xbebebebebebebebebebebebebebebebebebebebe";
+
+    #[test]
+    fn minimal_proxies_are_derived() {
+        let proxy = hex::decode(
+            "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf3",
+        )
+        .unwrap();
+        assert_eq!(
+            Some(DerivationKind::MinimalProxy),
+            derivation_kind(&proxy, SOURCE, None)
+        );
+        let other = "0xcafecafecafecafecafecafecafecafecafecafe";
+        assert_eq!(None, derivation_kind(&proxy, other, None));
+    }
+
+    #[test]
+    fn identical_code_is_derived() {
+        let code = hex::decode("6080604052").unwrap();
+        let code_hash = format!("{:#x}", stripped_code_hash(&code));
+        assert_eq!(
+            Some(DerivationKind::IdenticalCode),
+            derivation_kind(&code, SOURCE, Some(&code_hash))
+        );
+        assert_eq!(
+            None,
+            derivation_kind(
+                &hex::decode("6080604053").unwrap(),
+                SOURCE,
+                Some(&code_hash)
+            )
+        );
+        assert_eq!(None, derivation_kind(&code, SOURCE, None));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{dependencies::collect_files, settings::Erc20ModuleSettings, DisplayBytes};
+use serde::Deserialize;
+use smart_contract_verifier::{
+    rpc::{self, BytecodeFetcher},
+    solidity,
+    tools::code_hash::stripped_code_hash,
+    Address, Version,
+};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Version of the ERC-20 contract the `x/erc20` module deploys for token pairs.
+/// The module contract is compiled into the chain binary, so all the contracts deployed
+/// between two chain upgrades have the same code, verifiable with the canonical sources.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ModuleVersion {
+    /// Height of the chain upgrade the version has been introduced at.
+    pub upgrade_height: u64,
+    /// Hash of the deployed code of the module contract with the metadata stripped
+    /// (the same as `code_hash` of verification results), precomputed from the chain binary.
+    /// Module contracts are deployed by the module itself without creation transactions,
+    /// so the version is told by the code. If not set, the version is tried for any contract.
+    #[serde(default)]
+    pub code_hash: Option<String>,
+    pub compiler_version: String,
+    pub contract_name: String,
+    #[serde(default = "default_evm_version")]
+    pub evm_version: String,
+    pub optimization_runs: Option<usize>,
+    /// Directory containing the canonical sources of the module contract.
+    /// Source names are relative to it; the directory is relative to the manifest.
+    #[serde(rename = "sources")]
+    pub sources_dir: PathBuf,
+    #[serde(skip)]
+    pub sources: BTreeMap<PathBuf, String>,
+}
+
+fn default_evm_version() -> String {
+    "default".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    versions: Vec<ModuleVersion>,
+}
+
+/// Versions of the module contract by the upgrade heights they have been introduced at.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModuleVersions {
+    versions: Vec<ModuleVersion>,
+}
+
+impl ModuleVersions {
+    /// Loads the manifest and the sources of all versions, so that invalid versions
+    /// prevent the service from starting.
+    pub fn load(settings: &Erc20ModuleSettings) -> anyhow::Result<Self> {
+        let path = &settings.manifest;
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("cannot read manifest {path:?}: {err}"))?;
+        let manifest: Manifest = toml::from_str(&content)
+            .map_err(|err| anyhow::anyhow!("invalid manifest {path:?}: {err}"))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut versions = manifest.versions;
+        for version in &mut versions {
+            let height = version.upgrade_height;
+            Version::from_str(&version.compiler_version).map_err(|err| {
+                anyhow::anyhow!("invalid compiler version of the version at {height}: {err}")
+            })?;
+            if let Some(code_hash) = &mut version.code_hash {
+                *code_hash = code_hash.to_lowercase();
+            }
+            version.sources_dir = dir.join(&version.sources_dir);
+            collect_files(&version.sources_dir, Path::new(""), &mut version.sources).map_err(
+                |err| anyhow::anyhow!("cannot load sources of the version at {height}: {err}"),
+            )?;
+        }
+        versions.sort_by_key(|version| version.upgrade_height);
+        Ok(Self { versions })
+    }
+
+    /// Versions the contract with the given code hash is verified against: the ones
+    /// with the same precomputed hash go first, followed by the ones without hashes,
+    /// the latest first. Versions with other hashes could not match, so are skipped.
+    /// If the code hash is unknown, all versions are tried, the latest first.
+    pub fn candidates(&self, code_hash: Option<&str>) -> Vec<&ModuleVersion> {
+        let mut candidates: Vec<_> = self.versions.iter().rev().collect();
+        if let Some(code_hash) = code_hash {
+            candidates.retain(|version| {
+                version
+                    .code_hash
+                    .as_deref()
+                    .map_or(true, |expected| expected == code_hash)
+            });
+            // Stable sort keeps the latest versions first within each group
+            candidates.sort_by_key(|version| version.code_hash.is_none());
+        }
+        candidates
+    }
+}
+
+/// Hash of the deployed code of the contract, as precomputed for the module versions.
+/// Returns `None` if the code cannot be read.
+pub async fn deployed_code_hash(
+    fetcher: &BytecodeFetcher,
+    contract_address: &Address,
+) -> Option<String> {
+    let code = match solidity::multi_part::get_Code(fetcher, contract_address).await {
+        Ok(code) => code.filter(|code| !rpc::is_empty_code(Some(code)))?,
+        Err(err) => {
+            tracing::debug!("cannot read code of {}: {}", contract_address, err);
+            return None;
+        }
+    };
+    let code = DisplayBytes::from_str(&code).ok()?;
+    Some(format!("{:#x}", stripped_code_hash(&code.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn versions_with_the_same_code_go_first() {
+        let version = |upgrade_height, code_hash: Option<&str>| ModuleVersion {
+            upgrade_height,
+            code_hash: code_hash.map(str::to_string),
+            compiler_version: "v0.8.6+commit.11564f7e".into(),
+            contract_name: "ERC20MinterBurnerDecimals".into(),
+            evm_version: default_evm_version(),
+            optimization_runs: Some(200),
+            sources_dir: PathBuf::new(),
+            sources: BTreeMap::new(),
+        };
+        let versions = ModuleVersions {
+            versions: vec![
+                version(0, None),
+                version(1_000, Some("0x01")),
+                version(5_000, Some("0x05")),
+            ],
+        };
+        let heights = |code_hash| {
+            versions
+                .candidates(code_hash)
+                .into_iter()
+                .map(|version| version.upgrade_height)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![1_000, 0], heights(Some("0x01")));
+        assert_eq!(vec![5_000, 0], heights(Some("0x05")));
+        assert_eq!(vec![0], heights(Some("0xff")));
+        assert_eq!(vec![5_000, 1_000, 0], heights(None));
+    }
+}
@@ -0,0 +1,332 @@
+use crate::git_import::{self, GitImportError};
+use serde::Deserialize;
+use serde_json::Value;
+use smart_contract_verifier::Address;
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+use thiserror::Error;
+use url::Url;
+
+/// Max size (in bytes) of the explorer api response, which includes all sources of the contract.
+const MAX_RESPONSE_SIZE: usize = 32 * 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum EtherscanImportError {
+    #[error("explorer api url should be a public https url")]
+    InvalidUrl,
+    #[error("explorer api url is not allowed: {0}")]
+    UnsafeUrl(String),
+    #[error("explorer api is unavailable: {0}")]
+    Unavailable(String),
+    #[error("explorer api has not responded in {0} seconds")]
+    Timeout(u64),
+    #[error("explorer api response exceeds {0} bytes")]
+    TooLarge(usize),
+    #[error("explorer api has returned an error: {0}")]
+    Api(String),
+    #[error("contract {0} is not verified on the explorer")]
+    NotVerified(String),
+    #[error("{0} contracts are not supported")]
+    UnsupportedLanguage(String),
+    #[error("invalid {field} returned by the explorer: {value}")]
+    InvalidField { field: &'static str, value: String },
+}
+
+/// Contract verified on another chain, as returned by `getsourcecode` action
+/// of an Etherscan-compatible api.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportedContract {
+    pub contract_name: String,
+    pub compiler_version: String,
+    pub sources: BTreeMap<PathBuf, String>,
+    pub evm_version: String,
+    pub optimization_runs: Option<usize>,
+    /// Libraries linked on the other chain.
+    pub contract_libraries: BTreeMap<String, String>,
+    pub remappings: Vec<String>,
+}
+
+/// Raw item of the `getsourcecode` response. All values are returned as strings.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SourceCodeItem {
+    source_code: String,
+    contract_name: String,
+    compiler_version: String,
+    optimization_used: String,
+    runs: String,
+    #[serde(rename = "EVMVersion")]
+    evm_version: String,
+    #[serde(default)]
+    library: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse {
+    status: String,
+    message: String,
+    result: Value,
+}
+
+/// Fetches sources and compiler settings of the contract verified on the chain
+/// the explorer api (e.g., "https://api.etherscan.io/api") belongs to.
+///
+/// The api url is validated the same way as urls of imported git repositories,
+/// and the api is requested at the validated addresses only, without following redirects.
+pub async fn import_contract(
+    api_url: &Url,
+    api_key: Option<&str>,
+    address: &Address,
+    timeout: Duration,
+) -> Result<ImportedContract, EtherscanImportError> {
+    let addresses = git_import::validate_url(api_url)
+        .await
+        .map_err(|err| match err {
+            GitImportError::InvalidUrl => EtherscanImportError::InvalidUrl,
+            err => EtherscanImportError::UnsafeUrl(err.to_string()),
+        })?;
+    let mut url = api_url.clone();
+    url.query_pairs_mut()
+        .append_pair("module", "contract")
+        .append_pair("action", "getsourcecode")
+        .append_pair("address", address);
+    if let Some(api_key) = api_key {
+        url.query_pairs_mut().append_pair("apikey", api_key);
+    }
+
+    let client = git_import::pinned_client(api_url, &addresses)
+        .build()
+        .map_err(|err| EtherscanImportError::Unavailable(err.to_string()))?;
+
+    let unavailable = |err: reqwest::Error| match err.is_timeout() {
+        true => EtherscanImportError::Timeout(timeout.as_secs()),
+        // Api keys are passed in the url, so it is never included into the message
+        false => EtherscanImportError::Unavailable(err.without_url().to_string()),
+    };
+    let mut response = client
+        .get(url)
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(unavailable)?;
+    let too_large = EtherscanImportError::TooLarge(MAX_RESPONSE_SIZE);
+    if response.content_length().unwrap_or_default() > MAX_RESPONSE_SIZE as u64 {
+        return Err(too_large);
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(unavailable)? {
+        if body.len() + chunk.len() > MAX_RESPONSE_SIZE {
+            return Err(too_large);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let response: ApiResponse = serde_json::from_slice(&body)
+        .map_err(|err| EtherscanImportError::Unavailable(err.to_string()))?;
+    if response.status != "1" {
+        let reason = match response.result {
+            Value::String(reason) => reason,
+            _ => response.message,
+        };
+        return Err(EtherscanImportError::Api(reason));
+    }
+    let item: SourceCodeItem = serde_json::from_value(response.result)
+        .ok()
+        .and_then(|items: Vec<SourceCodeItem>| items.into_iter().next())
+        .ok_or_else(|| EtherscanImportError::Api("unexpected response format".into()))?;
+    parse_item(item, address)
+}
+
+fn parse_item(
+    item: SourceCodeItem,
+    address: &Address,
+) -> Result<ImportedContract, EtherscanImportError> {
+    if item.source_code.is_empty() {
+        return Err(EtherscanImportError::NotVerified(address.to_string()));
+    }
+    if item.compiler_version.starts_with("vyper") {
+        return Err(EtherscanImportError::UnsupportedLanguage("Vyper".into()));
+    }
+    let mut contract = ImportedContract {
+        contract_name: item.contract_name,
+        compiler_version: item.compiler_version,
+        evm_version: match item.evm_version.to_lowercase().as_str() {
+            "" | "default" => "default".to_string(),
+            evm_version => evm_version.to_string(),
+        },
+        ..Default::default()
+    };
+    if item.optimization_used == "1" {
+        let runs = item
+            .runs
+            .parse()
+            .map_err(|_| EtherscanImportError::InvalidField {
+                field: "Runs",
+                value: item.runs.clone(),
+            })?;
+        contract.optimization_runs = Some(runs);
+    }
+    contract.contract_libraries = item
+        .library
+        .split(';')
+        .filter_map(|library| library.split_once(':'))
+        .map(|(name, address)| (name.to_string(), address.to_string()))
+        .collect();
+
+    let source_code = item.source_code.trim();
+    if let Some(input) = source_code
+        .strip_prefix("{{")
+        .and_then(|input| input.strip_suffix("}}"))
+    {
+        // Standard json input wrapped into an extra pair of braces
+        let input: Value = serde_json::from_str(&format!("{{{input}}}")).map_err(|err| {
+            EtherscanImportError::InvalidField {
+                field: "SourceCode",
+                value: err.to_string(),
+            }
+        })?;
+        contract.sources = sources(&input["sources"])?;
+        apply_settings(&mut contract, &input["settings"]);
+    } else if source_code.starts_with('{') {
+        // Json object of source files
+        let files: Value = serde_json::from_str(source_code).map_err(|err| {
+            EtherscanImportError::InvalidField {
+                field: "SourceCode",
+                value: err.to_string(),
+            }
+        })?;
+        contract.sources = sources(&files)?;
+    } else {
+        let path = PathBuf::from(format!("{}.sol", contract.contract_name));
+        contract.sources = BTreeMap::from([(path, item.source_code)]);
+    }
+    Ok(contract)
+}
+
+/// Collects `{"<path>": {"content": "<source>"}}` sources.
+fn sources(files: &Value) -> Result<BTreeMap<PathBuf, String>, EtherscanImportError> {
+    let invalid = || EtherscanImportError::InvalidField {
+        field: "SourceCode",
+        value: "sources are missing".into(),
+    };
+    files
+        .as_object()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|(path, file)| {
+            let content = file["content"].as_str().ok_or_else(invalid)?;
+            Ok((PathBuf::from(path), content.to_string()))
+        })
+        .collect()
+}
+
+/// Takes settings of the standard json input which multi-part verification supports.
+fn apply_settings(contract: &mut ImportedContract, settings: &Value) {
+    if let Some(evm_version) = settings["evmVersion"].as_str() {
+        contract.evm_version = evm_version.to_string();
+    }
+    if settings["optimizer"]["enabled"] == Value::Bool(true) {
+        let runs = settings["optimizer"]["runs"].as_u64().unwrap_or(200);
+        contract.optimization_runs = Some(runs as usize);
+    }
+    if let Some(remappings) = settings["remappings"].as_array() {
+        contract.remappings = remappings
+            .iter()
+            .filter_map(|remapping| Some(remapping.as_str()?.to_string()))
+            .collect();
+    }
+    if let Some(libraries) = settings["libraries"].as_object() {
+        // Libraries are grouped by files in the standard json input
+        contract.contract_libraries.extend(
+            libraries
+                .values()
+                .filter_map(Value::as_object)
+                .flatten()
+                .filter_map(|(name, address)| Some((name.clone(), address.as_str()?.to_string()))),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    fn item(source_code: &str) -> SourceCodeItem {
+        SourceCodeItem {
+            source_code: source_code.to_string(),
+            contract_name: "Token".into(),
+            compiler_version: "v0.8.14+commit.80d49f37".into(),
+            optimization_used: "1".into(),
+            runs: "200".into(),
+            evm_version: "Default".into(),
+            library: "SafeMath:0xcafecafecafecafecafecafecafecafecafecafe".into(),
+        }
+    }
+
+    #[test]
+    fn source_formats_are_parsed() {
+        let address = Address::from_str("0xcafecafecafecafecafecafecafecafecafecafe").unwrap();
+
+        let single = parse_item(item("contract Token {}"), &address).unwrap();
+        assert_eq!(
+            ImportedContract {
+                contract_name: "Token".into(),
+                compiler_version: "v0.8.14+commit.80d49f37".into(),
+                sources: BTreeMap::from([("Token.sol".into(), "contract Token {}".into())]),
+                evm_version: "default".into(),
+                optimization_runs: Some(200),
+                contract_libraries: BTreeMap::from([(
+                    "SafeMath".into(),
+                    "0xcafecafecafecafecafecafecafecafecafecafe".into()
+                )]),
+                remappings: vec![],
+            },
+            single
+        );
+
+        let multi_file = parse_item(
+            item(r#"{"src/Token.sol": {"content": "contract Token {}"}}"#),
+            &address,
+        )
+        .unwrap();
+        assert_eq!(
+            BTreeMap::from([("src/Token.sol".into(), "contract Token {}".to_string())]),
+            multi_file.sources
+        );
+
+        let standard_json = parse_item(
+            item(
+                r#"{{
+                    "language": "Solidity",
+                    "sources": {"src/Token.sol": {"content": "contract Token {}"}},
+                    "settings": {
+                        "optimizer": {"enabled": true, "runs": 1000},
+                        "evmVersion": "istanbul",
+                        "remappings": ["@oz/=lib/oz/"]
+                    }
+                }}"#,
+            ),
+            &address,
+        )
+        .unwrap();
+        assert_eq!(Some(1000), standard_json.optimization_runs);
+        assert_eq!("istanbul", standard_json.evm_version);
+        assert_eq!(vec!["@oz/=lib/oz/".to_string()], standard_json.remappings);
+        assert_eq!(multi_file.sources, standard_json.sources);
+    }
+
+    #[test]
+    fn unverified_contracts_are_rejected() {
+        let address = Address::from_str("0xcafecafecafecafecafecafecafecafecafecafe").unwrap();
+        assert!(matches!(
+            parse_item(item(""), &address),
+            Err(EtherscanImportError::NotVerified(_))
+        ));
+        let mut vyper = item("# @version 0.3.7");
+        vyper.compiler_version = "vyper:0.3.7".into();
+        assert!(matches!(
+            parse_item(vyper, &address),
+            Err(EtherscanImportError::UnsupportedLanguage(_))
+        ));
+    }
+}
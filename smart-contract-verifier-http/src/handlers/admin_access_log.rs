@@ -0,0 +1,149 @@
+use crate::{
+    access_log::AccessLogRecord, api_error::ApiError, auth::AdminAuth, chains::Chains, DB,
+};
+use actix_web::{web, web::Json, HttpRequest};
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, doc, Document};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct AccessLogQuery {
+    pub contract_address: Option<Address>,
+    pub client_ip: Option<String>,
+    /// Fingerprint of the api key, as returned in the records.
+    pub api_key: Option<String>,
+    pub admin: Option<String>,
+    /// Only records received at or after that time are returned.
+    pub since: Option<DateTime<Utc>>,
+    /// Only records received before that time are returned.
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+impl AccessLogQuery {
+    fn filter(&self) -> Document {
+        let mut filter = Document::new();
+        if let Some(contract_address) = &self.contract_address {
+            filter.insert("contract_address", contract_address.as_str());
+        }
+        let fields = [
+            ("client_ip", &self.client_ip),
+            ("api_key", &self.api_key),
+            ("admin", &self.admin),
+        ];
+        for (field, value) in fields {
+            if let Some(value) = value {
+                filter.insert(field, value.as_str());
+            }
+        }
+        let mut received_at = Document::new();
+        if let Some(since) = self.since {
+            received_at.insert("$gte", bson::DateTime::from_chrono(since));
+        }
+        if let Some(until) = self.until {
+            received_at.insert("$lt", bson::DateTime::from_chrono(until));
+        }
+        if !received_at.is_empty() {
+            filter.insert("received_at", received_at);
+        }
+        filter
+    }
+}
+
+/// Access log record as returned by the api, with the receiving time formatted as rfc3339.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct AccessLogEntry {
+    pub request_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub client_ip: Option<String>,
+    pub api_key: Option<String>,
+    pub admin: Option<String>,
+    pub contract_address: Option<String>,
+    pub request: Option<String>,
+    pub request_size: usize,
+    pub status_code: u16,
+    pub response_size: Option<u64>,
+    pub duration_ms: u64,
+    pub received_at: DateTime<Utc>,
+}
+
+impl From<AccessLogRecord> for AccessLogEntry {
+    fn from(record: AccessLogRecord) -> Self {
+        Self {
+            request_id: record.request_id,
+            method: record.method,
+            path: record.path,
+            client_ip: record.client_ip,
+            api_key: record.api_key,
+            admin: record.admin,
+            contract_address: record.contract_address,
+            request: record.request,
+            request_size: record.request_size,
+            status_code: record.status_code,
+            response_size: record.response_size,
+            duration_ms: record.duration_ms,
+            received_at: record.received_at.to_chrono(),
+        }
+    }
+}
+
+/// Returns the latest access log records matching the query.
+#[instrument(skip(request, auth, chains), level = "debug")]
+pub async fn get_access_log(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    chains: web::Data<Chains>,
+    query: web::Query<AccessLogQuery>,
+) -> Result<Json<Vec<AccessLogEntry>>, ApiError> {
+    auth.require_admin(&request)?;
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(ApiError::invalid_request(format!(
+            "Limit should be from 1 to {MAX_LIMIT}"
+        )));
+    }
+    let database = DB::new().await.change_name(&chains.current().namespace());
+    let records = database
+        .find_access_log_records(query.filter(), limit)
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(Json(
+        records.into_iter().map(AccessLogEntry::from).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn query_to_filter() {
+        let query = AccessLogQuery {
+            contract_address: Some(
+                "0xCAFECAFECAFECAFECAFECAFECAFECAFECAFECAFE"
+                    .parse()
+                    .unwrap(),
+            ),
+            api_key: Some("0123456789abcdef".into()),
+            since: Some("2022-12-01T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            doc! {
+                "contract_address": "0xcafecafecafecafecafecafecafecafecafecafe",
+                "api_key": "0123456789abcdef",
+                "received_at": {
+                    "$gte": bson::DateTime::parse_rfc3339_str("2022-12-01T00:00:00Z").unwrap()
+                },
+            },
+            query.filter()
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    audit::{AuditRecord, Auditor, ReplayReport},
+    auth::AdminAuth,
+    blobs::BlobStore,
+    chains::Chains,
+    DB,
+};
+use actix_web::{web, web::Json, HttpRequest, HttpResponse};
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+/// Starts re-verification of all stored contracts in the background.
+pub async fn start_audit(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    auditor: web::Data<Auditor>,
+) -> Result<HttpResponse, ApiError> {
+    auth.require_admin(&request)?;
+    if auditor.is_running() {
+        return Err(ApiError::new(
+            ErrorCode::Conflict,
+            "Audit is already in progress",
+        ));
+    }
+
+    let auditor = auditor.into_inner();
+    tokio::spawn(async move { auditor.run().await });
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Replays the verification of the contract with its archived compiler input, and records
+/// the result into the audit history. Unlike the audit, it runs synchronously on a single contract.
+#[instrument(skip(request, auth, auditor, chains, blobs), level = "debug")]
+pub async fn replay(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    auditor: web::Data<Auditor>,
+    chains: web::Data<Chains>,
+    blobs: Option<web::Data<BlobStore>>,
+    address: web::Path<Address>,
+) -> Result<Json<ReplayReport>, ApiError> {
+    auth.require_admin(&request)?;
+    let database = DB::new()
+        .await
+        .change_name(&chains.current().namespace())
+        .with_blobs(blobs.map(web::Data::into_inner));
+    let cvr = database
+        .find_contract_verify_response(&address)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| ApiError::not_verified(&address))?;
+    let archived = database
+        .find_compiler_input(&address)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| {
+            ApiError::not_found(format!(
+                "Compiler input of contract {address} has not been archived"
+            ))
+        })?;
+
+    let report = auditor.replay(&cvr, &archived).await;
+    database
+        .add_audit_record(report.to_audit_record())
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(Json(report))
+}
+
+#[instrument(skip(request, auth, chains), level = "debug")]
+pub async fn get_audit_history(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    chains: web::Data<Chains>,
+    address: web::Path<Address>,
+) -> Result<Json<Vec<AuditRecord>>, ApiError> {
+    auth.require_admin(&request)?;
+    let database = DB::new().await.change_name(&chains.current().namespace());
+    let records = database
+        .find_audit_records(&address)
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(Json(records))
+}
@@ -0,0 +1,67 @@
+use crate::{api_error::ApiError, auth::AdminAuth};
+use actix_web::{web, web::Json, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use smart_contract_verifier::{CachedCompiler, SolidityClient, Version};
+use std::str::FromStr;
+use tracing::instrument;
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct CachedCompilerResponse {
+    pub version: String,
+    pub path: String,
+    /// Hex encoded checksum pinned for the binary.
+    pub sha256: Option<String>,
+    pub size: Option<u64>,
+    pub last_used: Option<DateTime<Utc>>,
+    pub retained: bool,
+}
+
+impl From<CachedCompiler> for CachedCompilerResponse {
+    fn from(compiler: CachedCompiler) -> Self {
+        Self {
+            version: compiler.version.to_string(),
+            path: compiler.path.to_string_lossy().into_owned(),
+            sha256: compiler.sha256.map(|sha256| format!("{sha256:#x}")),
+            size: compiler.size,
+            last_used: compiler.last_used.map(DateTime::from),
+            retained: compiler.retained,
+        }
+    }
+}
+
+/// Lists solidity compiler binaries available locally, along with their pinned checksums.
+#[instrument(skip(request, auth, client), level = "debug")]
+pub async fn list_compilers(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    client: web::Data<SolidityClient>,
+) -> Result<Json<Vec<CachedCompilerResponse>>, ApiError> {
+    auth.require_admin(&request)?;
+    let compilers = client
+        .compilers()
+        .cached_compilers()
+        .into_iter()
+        .map(CachedCompilerResponse::from)
+        .collect();
+    Ok(Json(compilers))
+}
+
+/// Removes the solidity compiler binary from the disk.
+#[instrument(skip(request, auth, client), level = "debug")]
+pub async fn evict_compiler(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    client: web::Data<SolidityClient>,
+    version: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let admin = auth.require_admin(&request)?;
+    let version = Version::from_str(&version).map_err(ApiError::invalid_request)?;
+    match client.compilers().evict(&version).await {
+        Ok(()) => {
+            tracing::info!("compiler {} has been evicted by {}", version, admin);
+            Ok(HttpResponse::NoContent().finish())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{
+    api_error::ApiError,
+    auth::AdminAuth,
+    chains::Chains,
+    precompiles,
+    verified_contract_result::{AdminAction, AdminActionKind},
+    DB,
+};
+use actix_web::{web, web::Json, HttpRequest, HttpResponse};
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+/// Removes the stored verification of the contract.
+///
+/// Removed results are moved into the history rather than being deleted.
+#[instrument(skip(request, auth, chains), level = "debug")]
+pub async fn delete_contract(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    chains: web::Data<Chains>,
+    address: web::Path<Address>,
+) -> Result<HttpResponse, ApiError> {
+    let admin = auth.require_admin(&request)?;
+
+    let database = DB::new().await.change_name(&chains.current().namespace());
+    let removed = database
+        .retire_contract_verify_responses(&address, AdminActionKind::Removed, &admin)
+        .await
+        .map_err(ApiError::storage)?;
+    if !removed {
+        return Err(ApiError::not_verified(address));
+    }
+
+    tracing::info!("contract {} has been removed by {}", address, admin);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[instrument(skip(request, auth, chains), level = "debug")]
+pub async fn get_admin_actions(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    chains: web::Data<Chains>,
+    address: web::Path<Address>,
+) -> Result<Json<Vec<AdminAction>>, ApiError> {
+    auth.require_admin(&request)?;
+
+    let database = DB::new().await.change_name(&chains.current().namespace());
+    let actions = database
+        .find_admin_actions(&address)
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(Json(actions))
+}
+
+/// Describes how a new verification relates to the one already stored for the contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Submission {
+    /// The contract has not been verified yet.
+    New,
+    /// The contract has been verified, and the admin overrides the existing verification.
+    Replacement { admin: String },
+    /// The contract has been verified, and the request has not been made by an admin.
+    Rejected,
+}
+
+impl Submission {
+    /// The admin overriding the existing verification, if any.
+    pub fn replaced_by(&self) -> Option<String> {
+        match self {
+            Submission::Replacement { admin } => Some(admin.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Checks whether the contract may be verified by the sender, given the admin
+/// the sender has been authenticated as (if any).
+/// Only admins are allowed to re-verify contracts which have already been verified,
+/// and no one is allowed to verify precompiles.
+pub async fn check_submission(
+    database: &DB,
+    admin: Option<String>,
+    contract_address: &str,
+) -> Result<Submission, ApiError> {
+    precompiles::ensure_not_precompile(contract_address)?;
+    let verified = database
+        .is_contract_verified(contract_address)
+        .await
+        .map_err(ApiError::storage)?;
+    let submission = match (verified, admin) {
+        (false, _) => Submission::New,
+        (true, Some(admin)) => Submission::Replacement { admin },
+        (true, None) => Submission::Rejected,
+    };
+    Ok(submission)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api_error::ErrorCode, auth::API_KEY_HEADER};
+    use actix_web::test::TestRequest;
+    use pretty_assertions::assert_eq;
+    use std::{collections::BTreeMap, str::FromStr};
+
+    #[actix_rt::test]
+    async fn contracts_are_removed_by_admins_only() {
+        let auth = AdminAuth::new(&BTreeMap::from([(
+            "alice".to_string(),
+            "secret".to_string(),
+        )]));
+        let address = Address::from_str("0xbebebebebebebebebebebebebebebebebebebebe").unwrap();
+        let request = TestRequest::default()
+            .insert_header((API_KEY_HEADER, "not a secret"))
+            .to_http_request();
+
+        let err = delete_contract(
+            request,
+            web::Data::new(auth),
+            web::Data::new(Chains::default()),
+            web::Path::from(address),
+        )
+        .await
+        .unwrap_err();
+        // Nothing is retired without the valid api key
+        assert_eq!(ErrorCode::Unauthorized, err.code);
+    }
+}
@@ -0,0 +1,243 @@
+use super::contract_metadata;
+use crate::{
+    api_error::ApiError, auth::AdminAuth, blobs::BlobStore, chains::Chains,
+    verified_contract_result::Verified_Contract_Result, DB,
+};
+use actix_web::{
+    web::{self, Bytes},
+    HttpRequest, HttpResponse,
+};
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{convert::Infallible, sync::Arc};
+use tracing::instrument;
+
+/// Format of exported contracts, i.e., the service the export is meant to be imported into.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// Files of the Sourcify repository (`metadata.json` and sources) of each contract.
+    /// Non-solidity contracts are skipped, as they have no metadata.
+    Sourcify,
+    /// Smart contract records as imported by Blockscout.
+    Blockscout,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    format: ExportFormat,
+}
+
+/// Streams all verified contracts of the current chain as newline delimited json,
+/// one contract per line.
+///
+/// Contracts which cannot be loaded are skipped, so that a single broken record
+/// does not prevent the rest from being exported.
+#[instrument(skip(request, auth, chains, blobs), level = "debug")]
+pub async fn export(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    chains: web::Data<Chains>,
+    blobs: Option<web::Data<BlobStore>>,
+    query: web::Query<ExportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let admin = auth.require_admin(&request)?;
+
+    let format = query.format;
+    let chain = chains.current();
+    let chain_id = chain.chain_id;
+    let database = Arc::new(
+        DB::new()
+            .await
+            .change_name(&chain.namespace())
+            .with_blobs(blobs.map(web::Data::into_inner)),
+    );
+    let cvrs = database
+        .latest_contract_verify_responses()
+        .await
+        .map_err(ApiError::storage)?;
+    tracing::info!(?format, "export has been started by {}", admin);
+
+    let lines = cvrs
+        .then(move |cvr| {
+            let database = database.clone();
+            async move {
+                let cvr = match cvr {
+                    Ok(cvr) => cvr,
+                    Err(err) => {
+                        tracing::error!("cannot load stored contract: {}", err);
+                        return None;
+                    }
+                };
+                let address = cvr.contract_address.clone();
+                match database.resolve_sources(cvr).await {
+                    Ok(cvr) => Some(cvr),
+                    Err(err) => {
+                        tracing::error!("cannot load stored contract {}: {}", address, err);
+                        None
+                    }
+                }
+            }
+        })
+        .filter_map(move |cvr| async move {
+            let record = match format {
+                ExportFormat::Sourcify => to_sourcify(&cvr?, chain_id),
+                ExportFormat::Blockscout => Some(to_blockscout(&cvr?)),
+            }?;
+            let mut line = serde_json::to_vec(&record).expect("record is serializable");
+            line.push(b'\n');
+            Some(Ok::<_, Infallible>(Bytes::from(line)))
+        });
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(lines))
+}
+
+/// Files of the contract laid out as in the Sourcify repository
+/// (`contracts/{full_match,partial_match}/{chain_id}/{address}/`).
+pub(super) fn to_sourcify(cvr: &Verified_Contract_Result, chain_id: u64) -> Option<Value> {
+    if !cvr.result.file_name.ends_with(".sol") {
+        return None;
+    }
+    let metadata = match contract_metadata::metadata(&cvr.result) {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            tracing::error!("cannot build metadata of {}: {}", cvr.contract_address, err);
+            return None;
+        }
+    };
+    let mut files = serde_json::Map::new();
+    files.insert("metadata.json".into(), json!(metadata.to_string()));
+    for (path, content) in &cvr.result.sources {
+        files.insert(format!("sources/{path}"), json!(content));
+    }
+    Some(json!({
+        "chain_id": chain_id,
+        "address": cvr.contract_address,
+        // Contracts stored before the match type has been recorded are not assumed to be full matches
+        "match_type": cvr.result.match_type,
+        "files": files,
+    }))
+}
+
+/// Smart contract record in the shape of the Blockscout verification results.
+pub(super) fn to_blockscout(cvr: &Verified_Contract_Result) -> Value {
+    let result = &cvr.result;
+    let secondary_sources: Vec<_> = result
+        .sources
+        .iter()
+        .filter(|(path, _)| *path != &result.file_name)
+        .map(|(path, content)| json!({ "file_name": path, "contract_source_code": content }))
+        .collect();
+    let external_libraries: Vec<_> = result
+        .contract_libraries
+        .iter()
+        .map(|(name, address)| json!({ "name": name, "address_hash": address }))
+        .collect();
+    let abi = result
+        .abi
+        .as_deref()
+        .and_then(|abi| serde_json::from_str::<Value>(abi).ok());
+    let compiler_settings = serde_json::from_str::<Value>(&result.compiler_settings).ok();
+    json!({
+        "address_hash": cvr.contract_address,
+        "name": result.contract_name,
+        "file_path": result.file_name,
+        "compiler_version": result.compiler_version,
+        "evm_version": result.evm_version,
+        "optimization": result.optimization.unwrap_or_default(),
+        "optimization_runs": result.optimization_runs,
+        "contract_source_code": result.sources.get(&result.file_name),
+        "secondary_sources": secondary_sources,
+        "constructor_arguments": result.constructor_arguments,
+        "abi": abi,
+        "external_libraries": external_libraries,
+        "compiler_settings": compiler_settings,
+        "is_vyper_contract": result.file_name.ends_with(".vy"),
+        "partially_verified": result.match_type.as_deref() == Some("partial"),
+        "verified_at": cvr.verified_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verification_response::VerificationResult;
+    use pretty_assertions::assert_eq;
+
+    fn cvr() -> Verified_Contract_Result {
+        let result: VerificationResult = serde_json::from_value(json!({
+            "file_name": "A.sol",
+            "contract_name": "A",
+            "match_type": "partial",
+            "compiler_version": "v0.8.14+commit.80d49f37",
+            "evm_version": "london",
+            "constructor_arguments": null,
+            "optimization": true,
+            "optimization_runs": 200,
+            "contract_libraries": { "Lib": "0xcafecafecafecafecafecafecafecafecafecafe" },
+            "abi": "[]",
+            "sources": { "A.sol": "contract A {}", "Lib.sol": "library Lib {}" },
+            "compiler_settings": "{}",
+        }))
+        .unwrap();
+        Verified_Contract_Result {
+            contract_address: "0xbebebebebebebebebebebebebebebebebebebebe".to_string(),
+            result,
+            sources_of: None,
+            sources_blob: None,
+            compressed_sources: None,
+            repository: None,
+            content_hash: None,
+            license_ids: vec![],
+            verified_at: None,
+            job_id: None,
+            creation: None,
+            token_info: None,
+        }
+    }
+
+    #[test]
+    fn contracts_are_exported_for_blockscout() {
+        let record = to_blockscout(&cvr());
+        assert_eq!(json!("contract A {}"), record["contract_source_code"]);
+        assert_eq!(
+            json!([{ "file_name": "Lib.sol", "contract_source_code": "library Lib {}" }]),
+            record["secondary_sources"]
+        );
+        assert_eq!(
+            json!([{ "name": "Lib", "address_hash": "0xcafecafecafecafecafecafecafecafecafecafe" }]),
+            record["external_libraries"]
+        );
+        assert_eq!(json!(true), record["partially_verified"]);
+    }
+
+    #[test]
+    fn contracts_are_exported_for_sourcify() {
+        let record = to_sourcify(&cvr(), 9001).unwrap();
+        assert_eq!(json!("partial"), record["match_type"]);
+        let files: Vec<_> = record["files"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        assert_eq!(
+            vec!["metadata.json", "sources/A.sol", "sources/Lib.sol"],
+            files
+        );
+
+        let mut vyper = cvr();
+        vyper.result.file_name = "A.vy".to_string();
+        assert_eq!(None, to_sourcify(&vyper, 9001));
+    }
+
+    #[test]
+    fn unknown_match_type_is_not_exported_as_full() {
+        let mut cvr = cvr();
+        cvr.result.match_type = None;
+        let record = to_sourcify(&cvr, 9001).unwrap();
+        assert_eq!(Value::Null, record["match_type"]);
+    }
+}
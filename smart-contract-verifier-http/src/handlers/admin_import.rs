@@ -0,0 +1,468 @@
+use super::admin_export::ExportFormat;
+use crate::{
+    api_error::ApiError,
+    audit,
+    auth::AdminAuth,
+    compiler_inputs::ArchivedCompilerInput,
+    licenses,
+    persistence::{ResultMiddlewares, VerifiedContract},
+    standards,
+    verification_response::VerificationResult,
+    verified_contract_result::Verified_Contract_Result,
+    DisplayBytes, DB,
+};
+use actix_web::{web, web::Json, HttpRequest};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use smart_contract_verifier::{
+    solidity,
+    sourcify::{Files, Success as SourcifySuccess},
+    Address, MatchType, SolidityClient,
+};
+use std::{collections::BTreeMap, sync::Arc};
+use tracing::instrument;
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    format: ExportFormat,
+    /// If set, records are stored without being re-verified against the on-chain code.
+    #[serde(default)]
+    skip_validation: bool,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Records of contracts which are verified already. They are never overridden.
+    pub already_verified: usize,
+    pub failures: Vec<ImportFailure>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ImportFailure {
+    /// Number of the line (starting from 1) the record is located at.
+    pub line: usize,
+    pub contract_address: Option<Address>,
+    pub reason: String,
+}
+
+/// Contract record parsed from the import.
+#[derive(Debug, PartialEq, Eq)]
+struct ImportRecord {
+    contract_address: Address,
+    result: VerificationResult,
+}
+
+/// Ingests newline delimited json records in the format of the export (e.g., a previous
+/// export, or a Sourcify repository dump converted into lines) into the current chain.
+///
+/// The body is processed line by line while being received, so that large dumps
+/// are not kept in memory. Records which cannot be imported are reported in the summary.
+#[instrument(skip(request, auth, middlewares, client, body), level = "debug")]
+pub async fn import(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
+    client: web::Data<SolidityClient>,
+    query: web::Query<ImportQuery>,
+    mut body: web::Payload,
+) -> Result<Json<ImportSummary>, ApiError> {
+    let admin = auth.require_admin(&request)?;
+    tracing::info!(?query, "import has been started by {}", admin);
+
+    let importer = Importer {
+        database: DB::new()
+            .await
+            .change_name(&middlewares.chain().namespace()),
+        middlewares: middlewares.into_inner(),
+        client: client.into_inner(),
+        format: query.format,
+        skip_validation: query.skip_validation,
+    };
+    let mut summary = ImportSummary::default();
+    let mut buffer = Vec::new();
+    let mut line_number = 0;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(ApiError::invalid_request)?;
+        buffer.extend_from_slice(&chunk);
+        while let Some(position) = buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=position).collect();
+            line_number += 1;
+            importer.import_line(&mut summary, line_number, &line).await;
+        }
+    }
+    importer
+        .import_line(&mut summary, line_number + 1, &buffer)
+        .await;
+
+    tracing::info!(
+        imported = summary.imported,
+        already_verified = summary.already_verified,
+        failed = summary.failures.len(),
+        "import finished"
+    );
+    Ok(Json(summary))
+}
+
+struct Importer {
+    database: DB,
+    middlewares: Arc<ResultMiddlewares>,
+    client: Arc<SolidityClient>,
+    format: ExportFormat,
+    skip_validation: bool,
+}
+
+impl Importer {
+    async fn import_line(&self, summary: &mut ImportSummary, line: usize, content: &[u8]) {
+        let content = String::from_utf8_lossy(content);
+        if content.trim().is_empty() {
+            return;
+        }
+        let fail = |contract_address: Option<&Address>, reason: String| ImportFailure {
+            line,
+            contract_address: contract_address.cloned(),
+            reason,
+        };
+
+        let chain_id = self.middlewares.chain().chain_id;
+        let record = match parse_record(self.format, &content, chain_id) {
+            Ok(record) => record,
+            Err(reason) => return summary.failures.push(fail(None, reason)),
+        };
+        let address = record.contract_address.clone();
+        match self.database.is_contract_verified(address.as_str()).await {
+            Ok(true) => {
+                summary.already_verified += 1;
+                return;
+            }
+            Ok(false) => {}
+            Err(err) => return summary.failures.push(fail(Some(&address), err)),
+        }
+
+        let verified = match self.skip_validation {
+            true => VerifiedContract {
+                contract_address: address.to_string(),
+                result: record.result,
+                repository: None,
+                replaced_by: None,
+                debug_info: None,
+                compiler_input: None,
+                job_id: None,
+            },
+            false => match self.validate(record).await {
+                Ok(verified) => verified,
+                Err(reason) => return summary.failures.push(fail(Some(&address), reason)),
+            },
+        };
+        if let Err(err) = self.middlewares.run(&verified).await {
+            return summary.failures.push(fail(Some(&address), err.to_string()));
+        }
+        summary.imported += 1;
+    }
+
+    /// Re-verifies the record against the current on-chain code. The result of the
+    /// verification is stored instead of the imported one.
+    async fn validate(&self, record: ImportRecord) -> Result<VerifiedContract, String> {
+        if !record.result.file_name.ends_with(".sol") {
+            return Err("only solidity contracts can be validated".to_string());
+        }
+        let contract_address = record.contract_address.to_string();
+        let cvr = Verified_Contract_Result {
+            contract_address: contract_address.clone(),
+            result: record.result,
+            sources_of: None,
+            sources_blob: None,
+            compressed_sources: None,
+            repository: None,
+            content_hash: None,
+            license_ids: vec![],
+            verified_at: None,
+            job_id: None,
+            creation: None,
+            token_info: None,
+        };
+        let request = audit::verification_request(&cvr)?;
+        let success = solidity::standard_json::verify(self.client.clone(), request)
+            .await
+            .map_err(|err| format!("verification failed: {err}"))?;
+        Ok(VerifiedContract {
+            debug_info: self.middlewares.debug_info(&contract_address, &success),
+            compiler_input: ArchivedCompilerInput::new(
+                &contract_address,
+                &success,
+                self.middlewares.encoding(),
+            ),
+            contract_address,
+            result: self.middlewares.verification_result(success),
+            repository: None,
+            replaced_by: None,
+            job_id: None,
+        })
+    }
+}
+
+fn parse_record(format: ExportFormat, line: &str, chain_id: u64) -> Result<ImportRecord, String> {
+    match format {
+        ExportFormat::Sourcify => {
+            let record: SourcifyRecord =
+                serde_json::from_str(line).map_err(|err| format!("invalid record: {err}"))?;
+            record.into_import_record(chain_id)
+        }
+        ExportFormat::Blockscout => {
+            let record: BlockscoutRecord =
+                serde_json::from_str(line).map_err(|err| format!("invalid record: {err}"))?;
+            record.into_import_record()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcifyRecord {
+    chain_id: u64,
+    address: Address,
+    #[serde(default)]
+    match_type: Option<String>,
+    /// File contents by their paths inside of the contract directory of the repository.
+    files: BTreeMap<String, Value>,
+}
+
+impl SourcifyRecord {
+    fn into_import_record(self, chain_id: u64) -> Result<ImportRecord, String> {
+        if self.chain_id != chain_id {
+            return Err(format!(
+                "record belongs to chain {}, while contracts of chain {} are imported",
+                self.chain_id, chain_id
+            ));
+        }
+        let match_type = match self.match_type.as_deref() {
+            Some("partial") => MatchType::Partial,
+            _ => MatchType::Full,
+        };
+        let files = self
+            .files
+            .into_iter()
+            .map(|(path, content)| match content {
+                Value::String(content) => (path, content),
+                // `metadata.json` may be given as an object
+                content => (path, content.to_string()),
+            })
+            .collect();
+        let success = SourcifySuccess::try_from((Files(files), match_type))
+            .map_err(|err| format!("invalid sourcify files: {err:#}"))?;
+        let mut result = VerificationResult::from(success);
+        result.compiler_settings = input_settings(&result.compiler_settings)?;
+        Ok(ImportRecord {
+            contract_address: self.address,
+            result,
+        })
+    }
+}
+
+/// Converts settings of the metadata into the settings of the compiler input:
+/// libraries keyed by `<file>:<library>` are grouped by files.
+fn input_settings(metadata_settings: &str) -> Result<String, String> {
+    let mut settings: Map<String, Value> = serde_json::from_str(metadata_settings)
+        .map_err(|err| format!("invalid metadata settings: {err}"))?;
+    settings.remove("compilationTarget");
+    let mut libraries: BTreeMap<String, Map<String, Value>> = BTreeMap::new();
+    if let Some(Value::Object(flattened)) = settings.remove("libraries") {
+        for (name, address) in flattened {
+            let (file, library) = name.rsplit_once(':').unwrap_or(("", &name));
+            libraries
+                .entry(file.to_string())
+                .or_default()
+                .insert(library.to_string(), address);
+        }
+    }
+    settings.insert("libraries".into(), json!(libraries));
+    Ok(Value::Object(settings).to_string())
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockscoutRecord {
+    address_hash: Address,
+    name: String,
+    /// Is missing in records of contracts verified by older Blockscout versions.
+    file_path: Option<String>,
+    compiler_version: String,
+    evm_version: Option<String>,
+    #[serde(default)]
+    optimization: bool,
+    optimization_runs: Option<usize>,
+    contract_source_code: String,
+    #[serde(default)]
+    secondary_sources: Vec<BlockscoutSource>,
+    constructor_arguments: Option<DisplayBytes>,
+    abi: Option<Value>,
+    #[serde(default)]
+    external_libraries: Vec<BlockscoutLibrary>,
+    compiler_settings: Option<Value>,
+    #[serde(default)]
+    is_vyper_contract: bool,
+    #[serde(default)]
+    partially_verified: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockscoutSource {
+    file_name: String,
+    contract_source_code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockscoutLibrary {
+    name: String,
+    address_hash: String,
+}
+
+impl BlockscoutRecord {
+    fn into_import_record(self) -> Result<ImportRecord, String> {
+        let extension = if self.is_vyper_contract { "vy" } else { "sol" };
+        let file_name = self
+            .file_path
+            .unwrap_or_else(|| format!("{}.{extension}", self.name));
+        let evm_version = self.evm_version.unwrap_or_else(|| "default".to_string());
+        let contract_libraries: BTreeMap<_, _> = self
+            .external_libraries
+            .into_iter()
+            .map(|library| (library.name, library.address_hash))
+            .collect();
+        // Settings are not stored by older Blockscout versions, so are restored from the fields
+        let compiler_settings = self.compiler_settings.unwrap_or_else(|| {
+            let mut settings = json!({
+                "optimizer": { "enabled": self.optimization, "runs": self.optimization_runs },
+                "libraries": { &file_name: &contract_libraries },
+            });
+            if evm_version != "default" {
+                settings["evmVersion"] = json!(evm_version);
+            }
+            settings
+        });
+        let mut sources: BTreeMap<_, _> = self
+            .secondary_sources
+            .into_iter()
+            .map(|source| (source.file_name, source.contract_source_code))
+            .collect();
+        sources.insert(file_name.clone(), self.contract_source_code);
+        let abi = self.abi.map(|abi| abi.to_string());
+
+        let result = VerificationResult {
+            fully_qualified_name: Some(format!("{}:{}", file_name, self.name)),
+            file_name,
+            contract_name: self.name,
+            other_matches: vec![],
+            match_type: Some(
+                match self.partially_verified {
+                    true => MatchType::Partial,
+                    false => MatchType::Full,
+                }
+                .as_str()
+                .to_string(),
+            ),
+            compiler_version: self.compiler_version,
+            evm_version,
+            constructor_arguments: self.constructor_arguments,
+            optimization: Some(self.optimization),
+            optimization_runs: self.optimization_runs,
+            contract_libraries,
+            standards: abi
+                .as_deref()
+                .map(standards::detect_from_json)
+                .unwrap_or_default(),
+            abi,
+            licenses: licenses::detect(&sources),
+            sources,
+            compiler_settings: compiler_settings.to_string(),
+            // Neither compilation messages nor local compilation results are exported
+            compiler_messages: vec![],
+            code_hash: None,
+            contract_size: None,
+            impersonation_warning: None,
+            source_warnings: vec![],
+            local_creation_bytecode: None,
+            local_deployed_bytecode: None,
+            local_creation_input_parts: None,
+            local_deployed_bytecode_parts: None,
+            devdoc: None,
+            userdoc: None,
+            storage_layout: None,
+            extra_outputs: BTreeMap::new(),
+        };
+        Ok(ImportRecord {
+            contract_address: self.address_hash,
+            result,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::admin_export::{to_blockscout, to_sourcify},
+        *,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn cvr() -> Verified_Contract_Result {
+        let result: VerificationResult = serde_json::from_value(json!({
+            "file_name": "A.sol",
+            "contract_name": "A",
+            "fully_qualified_name": "A.sol:A",
+            "match_type": "partial",
+            "compiler_version": "v0.8.14+commit.80d49f37",
+            "evm_version": "london",
+            "constructor_arguments": "0xcafe",
+            "optimization": true,
+            "optimization_runs": 200,
+            "contract_libraries": { "Lib": "0xcafecafecafecafecafecafecafecafecafecafe" },
+            "abi": "[]",
+            "sources": { "A.sol": "contract A {}", "Lib.sol": "library Lib {}" },
+            "compiler_settings": r#"{"evmVersion":"london","libraries":{"Lib.sol":{"Lib":"0xcafecafecafecafecafecafecafecafecafecafe"}},"optimizer":{"enabled":true,"runs":200}}"#,
+        }))
+        .unwrap();
+        Verified_Contract_Result {
+            contract_address: "0xbebebebebebebebebebebebebebebebebebebebe".to_string(),
+            result,
+            sources_of: None,
+            sources_blob: None,
+            compressed_sources: None,
+            repository: None,
+            content_hash: None,
+            license_ids: vec![],
+            verified_at: None,
+            job_id: None,
+            creation: None,
+            token_info: None,
+        }
+    }
+
+    #[test]
+    fn blockscout_export_is_imported() {
+        let cvr = cvr();
+        let line = to_blockscout(&cvr).to_string();
+
+        let record = parse_record(ExportFormat::Blockscout, &line, 9001).unwrap();
+        assert_eq!(cvr.contract_address, record.contract_address.to_string());
+        assert_eq!(cvr.result, record.result);
+    }
+
+    #[test]
+    fn sourcify_export_is_imported() {
+        let cvr = cvr();
+        let line = to_sourcify(&cvr, 9001).unwrap().to_string();
+
+        let record = parse_record(ExportFormat::Sourcify, &line, 9001).unwrap();
+        assert_eq!(cvr.contract_address, record.contract_address.to_string());
+        assert_eq!("A", record.result.contract_name);
+        assert_eq!(Some("partial"), record.result.match_type.as_deref());
+        assert_eq!(cvr.result.sources, record.result.sources);
+        let settings: Value = serde_json::from_str(&record.result.compiler_settings).unwrap();
+        assert_eq!(
+            json!({ "Lib.sol": { "Lib": "0xcafecafecafecafecafecafecafecafecafecafe" } }),
+            settings["libraries"]
+        );
+
+        parse_record(ExportFormat::Sourcify, &line, 9000).expect_err("chain should differ");
+    }
+}
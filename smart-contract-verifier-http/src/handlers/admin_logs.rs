@@ -0,0 +1,42 @@
+use crate::{api_error::ApiError, auth::AdminAuth, tracer};
+use actix_web::{web, web::Json, HttpRequest};
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Log levels in `RUST_LOG` syntax (e.g., "info,smart_contract_verifier=debug").
+    pub filter: String,
+}
+
+/// Returns log levels of the logs written to stdout.
+#[instrument(skip(request, auth), level = "debug")]
+pub async fn get_log_filter(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+) -> Result<Json<LogFilter>, ApiError> {
+    auth.require_admin(&request)?;
+    let filter =
+        tracer::log_filter().ok_or_else(|| ApiError::internal("logs are not initialized"))?;
+    Ok(Json(LogFilter { filter }))
+}
+
+/// Changes log levels of the logs written to stdout until the service is restarted.
+#[instrument(skip(request, auth), level = "debug")]
+pub async fn set_log_filter(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    params: Json<LogFilter>,
+) -> Result<Json<LogFilter>, ApiError> {
+    let admin = auth.require_admin(&request)?;
+    let params = params.into_inner();
+    tracer::set_log_filter(&params.filter).map_err(ApiError::invalid_request)?;
+    tracing::info!(
+        "log filter has been changed to '{}' by {}",
+        params.filter,
+        admin
+    );
+    // Directives are returned in the normalized form
+    let filter = tracer::log_filter().unwrap_or(params.filter);
+    Ok(Json(LogFilter { filter }))
+}
@@ -0,0 +1,37 @@
+use crate::{
+    analysis::{ContractAnalysis, FindingSeverity},
+    api_error::ApiError,
+    chains::Chain,
+    DB,
+};
+use actix_web::{web, web::Json};
+use serde::Deserialize;
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+#[derive(Debug, Deserialize)]
+pub struct AnalysisQuery {
+    /// Findings of lower severities are omitted.
+    min_severity: Option<FindingSeverity>,
+}
+
+/// Returns findings of the static analysis run over the sources of the contract after verification.
+#[instrument(level = "debug")]
+pub async fn get_analysis(
+    address: web::Path<Address>,
+    query: web::Query<AnalysisQuery>,
+    chain: Chain,
+) -> Result<Json<ContractAnalysis>, ApiError> {
+    let database = DB::new().await.change_name(&chain.namespace());
+    let mut analysis = database
+        .find_contract_analysis(&address)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| ApiError::not_found(format!("Contract {address} has not been analyzed")))?;
+    if let Some(min_severity) = query.min_severity {
+        analysis
+            .findings
+            .retain(|finding| finding.severity >= min_severity);
+    }
+    Ok(Json(analysis))
+}
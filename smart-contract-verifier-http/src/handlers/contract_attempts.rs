@@ -0,0 +1,17 @@
+use crate::{api_error::ApiError, attempts::VerificationAttempt, chains::Chain, DB};
+use actix_web::{web, web::Json};
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+#[instrument(level = "debug")]
+pub async fn get_attempts(
+    address: web::Path<Address>,
+    chain: Chain,
+) -> Result<Json<Vec<VerificationAttempt>>, ApiError> {
+    let database = DB::new().await.change_name(&chain.namespace());
+    let attempts = database
+        .find_verification_attempts(&address)
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(Json(attempts))
+}
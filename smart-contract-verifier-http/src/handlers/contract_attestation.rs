@@ -0,0 +1,31 @@
+use super::contract_lookup::find_verified;
+use crate::{
+    api_error::ApiError,
+    attestation::{self, Attestation, Attestor, Statement},
+    blobs::BlobStore,
+    chains::Chain,
+};
+use actix_web::{web, web::Json};
+use chrono::Utc;
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+/// Returns a statement that the contract has been verified with the stored sources,
+/// signed by the service key.
+#[instrument(skip(attestor, blobs), level = "debug")]
+pub async fn get_attestation(
+    attestor: web::Data<Attestor>,
+    address: web::Path<Address>,
+    chain: Chain,
+    blobs: Option<web::Data<BlobStore>>,
+) -> Result<Json<Attestation>, ApiError> {
+    let cvr = find_verified(&chain, blobs, &address).await?;
+    let statement = Statement {
+        contract_address: cvr.contract_address.to_lowercase(),
+        chain_id: chain.chain_id,
+        source_hash: attestation::source_hash(&cvr.result.sources),
+        verified_at: cvr.verified_at,
+        issued_at: Utc::now(),
+    };
+    Ok(Json(attestor.attest(statement)))
+}
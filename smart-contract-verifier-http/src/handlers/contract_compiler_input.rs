@@ -0,0 +1,23 @@
+use crate::{api_error::ApiError, chains::Chain, compiler_inputs::CompilerInputDownload, DB};
+use actix_web::{web, web::Json};
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+#[instrument(level = "debug")]
+pub async fn get_compiler_input(
+    address: web::Path<Address>,
+    chain: Chain,
+) -> Result<Json<CompilerInputDownload>, ApiError> {
+    let database = DB::new().await.change_name(&chain.namespace());
+    let archived = database
+        .find_compiler_input(&address)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| {
+            ApiError::not_found(format!(
+                "Compiler input of contract {address} has not been archived"
+            ))
+        })?;
+    let download = CompilerInputDownload::try_from(archived).map_err(ApiError::internal)?;
+    Ok(Json(download))
+}
@@ -0,0 +1,24 @@
+use crate::{api_error::ApiError, blobs::BlobStore, chains::Chain, debug_info::DebugInfo, DB};
+use actix_web::{web, web::Json};
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+#[instrument(skip(blobs), level = "debug")]
+pub async fn get_debug_info(
+    address: web::Path<Address>,
+    chain: Chain,
+    blobs: Option<web::Data<BlobStore>>,
+) -> Result<Json<DebugInfo>, ApiError> {
+    let database = DB::new()
+        .await
+        .change_name(&chain.namespace())
+        .with_blobs(blobs.map(web::Data::into_inner));
+    let debug_info = database
+        .find_debug_info(&address)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| {
+            ApiError::not_found(format!("Debug info of contract {address} is not available"))
+        })?;
+    Ok(Json(debug_info))
+}
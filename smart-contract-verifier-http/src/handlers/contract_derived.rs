@@ -0,0 +1,17 @@
+use crate::{api_error::ApiError, chains::Chain, derived::DerivedVerification, DB};
+use actix_web::{web, web::Json};
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+#[instrument(level = "debug")]
+pub async fn get_derived(
+    address: web::Path<Address>,
+    chain: Chain,
+) -> Result<Json<Vec<DerivedVerification>>, ApiError> {
+    let database = DB::new().await.change_name(&chain.namespace());
+    let derived = database
+        .find_derived_verifications(&address)
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(Json(derived))
+}
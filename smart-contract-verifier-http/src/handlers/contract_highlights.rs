@@ -0,0 +1,71 @@
+use crate::{
+    api_error::ApiError,
+    chains::Chain,
+    highlights::{DuplicatedCode, RecentContract},
+    DB,
+};
+use actix_web::{web, web::Json};
+use serde::Deserialize;
+use tracing::instrument;
+
+const DEFAULT_LIMIT: i64 = 10;
+const MAX_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct HighlightsQuery {
+    pub limit: Option<i64>,
+}
+
+impl HighlightsQuery {
+    fn limit(&self) -> Result<i64, ApiError> {
+        validate_limit(self.limit)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct RecentQuery {
+    pub limit: Option<i64>,
+    /// If set, only contracts declaring the license (e.g., "MIT") by SPDX identifiers are listed.
+    /// License expressions (e.g., "MIT OR Apache-2.0") are matched by any of their licenses.
+    pub license: Option<String>,
+}
+
+fn validate_limit(limit: Option<i64>) -> Result<i64, ApiError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(ApiError::invalid_request(format!(
+            "Limit should be from 1 to {MAX_LIMIT}"
+        )));
+    }
+    Ok(limit)
+}
+
+/// Returns the most recently verified contracts, the latest go first.
+#[instrument(level = "debug")]
+pub async fn get_recent(
+    query: web::Query<RecentQuery>,
+    chain: Chain,
+) -> Result<Json<Vec<RecentContract>>, ApiError> {
+    let limit = validate_limit(query.limit)?;
+    let database = DB::new().await.change_name(&chain.namespace());
+    let contracts = database
+        .find_recent_contracts(limit, query.license.as_deref())
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(Json(contracts))
+}
+
+/// Returns codes shared by the most of verified contracts (e.g., clones deployed by factories).
+#[instrument(level = "debug")]
+pub async fn get_top_duplicated(
+    query: web::Query<HighlightsQuery>,
+    chain: Chain,
+) -> Result<Json<Vec<DuplicatedCode>>, ApiError> {
+    let limit = query.limit()?;
+    let database = DB::new().await.change_name(&chain.namespace());
+    let codes = database
+        .find_top_duplicated_codes(limit)
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(Json(codes))
+}
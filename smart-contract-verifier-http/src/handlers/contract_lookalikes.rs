@@ -0,0 +1,60 @@
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    chains::Chains,
+    DisplayBytes, DB,
+};
+use actix_web::{web, web::Json};
+use serde::Serialize;
+use smart_contract_verifier::{
+    rpc::{self, BytecodeFetcher},
+    solidity,
+    tools::code_hash::stripped_code_hash,
+    Address,
+};
+use std::str::FromStr;
+use tracing::instrument;
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LookalikesResponse {
+    /// Hash of the deployed code with the metadata stripped.
+    pub code_hash: String,
+    /// Verified contracts having the same code hash.
+    pub contracts: Vec<String>,
+}
+
+/// Returns verified contracts which deployed code is the same as the code
+/// deployed at the address, up to the metadata appended by the compiler.
+#[instrument(skip(fetcher, chains), level = "debug")]
+pub async fn get_lookalikes(
+    fetcher: web::Data<BytecodeFetcher>,
+    chains: web::Data<Chains>,
+    address: web::Path<Address>,
+) -> Result<Json<LookalikesResponse>, ApiError> {
+    let address = address.into_inner();
+    let code = solidity::multi_part::get_Code(&fetcher, &address)
+        .await
+        .map_err(ApiError::node)?
+        .filter(|code| !rpc::is_empty_code(Some(code)))
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::ContractNotFound,
+                format!("Contract {address} has no deployed code"),
+            )
+        })?;
+    let code = DisplayBytes::from_str(&code)
+        .map_err(|err| ApiError::internal(format!("Invalid deployed code: {err:?}")))?;
+    let code_hash = format!("{:#x}", stripped_code_hash(&code.0));
+
+    let database = DB::new().await.change_name(&chains.current().namespace());
+    let contracts = database
+        .find_contracts_by_code_hash(&code_hash)
+        .await
+        .map_err(ApiError::storage)?
+        .into_iter()
+        .filter(|contract| contract != address.as_str())
+        .collect();
+    Ok(Json(LookalikesResponse {
+        code_hash,
+        contracts,
+    }))
+}
@@ -0,0 +1,105 @@
+use crate::{
+    api_error::ApiError, blobs::BlobStore, caching, chains::Chain, precompiles,
+    Verified_Contract_Result, DB,
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde_json::Value;
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+/// Returns the stored verification of the contract.
+/// Precompiles are returned as verified, along with their canonical abis.
+#[instrument(skip(http_request, blobs), level = "debug")]
+pub async fn get_contract(
+    http_request: HttpRequest,
+    address: web::Path<Address>,
+    chain: Chain,
+    blobs: Option<web::Data<BlobStore>>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(precompile) = precompiles::find(&address) {
+        return Ok(HttpResponse::Ok().json(precompile.lookup()));
+    }
+    let cvr = find_verified(&chain, blobs, &address).await?;
+    Ok(caching::cached_json(
+        &http_request,
+        &cvr.content_hash(),
+        &cvr.without_storage_details(),
+    ))
+}
+
+/// Returns the abi of the verified contract as json.
+#[instrument(skip(http_request, blobs), level = "debug")]
+pub async fn get_abi(
+    http_request: HttpRequest,
+    address: web::Path<Address>,
+    chain: Chain,
+    blobs: Option<web::Data<BlobStore>>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(precompile) = precompiles::find(&address) {
+        return Ok(HttpResponse::Ok().json(precompile.abi()));
+    }
+    let cvr = find_verified(&chain, blobs, &address).await?;
+    let abi = cvr.result.abi.as_deref().ok_or_else(|| {
+        ApiError::not_found(format!("Abi of contract {address} is not available"))
+    })?;
+    let abi: Value = serde_json::from_str(abi)
+        .map_err(|err| ApiError::internal(format!("Stored abi is invalid: {err}")))?;
+    Ok(caching::cached_json(
+        &http_request,
+        &cvr.content_hash(),
+        &abi,
+    ))
+}
+
+/// Returns sources of the verified contract by their file names.
+#[instrument(skip(http_request, blobs), level = "debug")]
+pub async fn get_sources(
+    http_request: HttpRequest,
+    address: web::Path<Address>,
+    chain: Chain,
+    blobs: Option<web::Data<BlobStore>>,
+) -> Result<HttpResponse, ApiError> {
+    let cvr = find_verified(&chain, blobs, &address).await?;
+    Ok(caching::cached_json(
+        &http_request,
+        &cvr.content_hash(),
+        &cvr.result.sources,
+    ))
+}
+
+/// Returns the storage layout of the verified contract as returned by the compiler.
+#[instrument(skip(http_request, blobs), level = "debug")]
+pub async fn get_storage_layout(
+    http_request: HttpRequest,
+    address: web::Path<Address>,
+    chain: Chain,
+    blobs: Option<web::Data<BlobStore>>,
+) -> Result<HttpResponse, ApiError> {
+    let cvr = find_verified(&chain, blobs, &address).await?;
+    let storage_layout = cvr.result.storage_layout.as_ref().ok_or_else(|| {
+        ApiError::not_found(format!(
+            "Storage layout of contract {address} is not available"
+        ))
+    })?;
+    Ok(caching::cached_json(
+        &http_request,
+        &cvr.content_hash(),
+        storage_layout,
+    ))
+}
+
+pub(super) async fn find_verified(
+    chain: &Chain,
+    blobs: Option<web::Data<BlobStore>>,
+    address: &str,
+) -> Result<Verified_Contract_Result, ApiError> {
+    let database = DB::new()
+        .await
+        .change_name(&chain.namespace())
+        .with_blobs(blobs.map(web::Data::into_inner));
+    database
+        .find_contract_verify_response(address)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| ApiError::not_verified(address))
+}
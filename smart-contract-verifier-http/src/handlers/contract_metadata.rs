@@ -0,0 +1,187 @@
+use super::contract_lookup::find_verified;
+use crate::{
+    api_error::ApiError, blobs::BlobStore, caching, chains::Chain, licenses::license,
+    verification_response::VerificationResult,
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde_json::{json, Map, Value};
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+/// Returns solidity metadata (the `metadata.json` file) of the verified contract
+/// generated from the stored compiler input and output.
+#[instrument(skip(http_request, blobs), level = "debug")]
+pub async fn get_metadata(
+    http_request: HttpRequest,
+    address: web::Path<Address>,
+    chain: Chain,
+    blobs: Option<web::Data<BlobStore>>,
+) -> Result<HttpResponse, ApiError> {
+    let cvr = find_verified(&chain, blobs, &address).await?;
+    if !cvr.result.file_name.ends_with(".sol") {
+        return Err(ApiError::not_found(format!(
+            "Metadata is available for solidity contracts only, while {} is not",
+            cvr.result.file_name
+        )));
+    }
+    let metadata = metadata(&cvr.result).map_err(|err| {
+        ApiError::internal(format!("Stored compiler settings are invalid: {err}"))
+    })?;
+    Ok(caching::cached_json(
+        &http_request,
+        &cvr.content_hash(),
+        &metadata,
+    ))
+}
+
+/// Generates the metadata in the format produced by the compiler.
+/// Keys of json objects are sorted, as in the canonical form.
+pub fn metadata(result: &VerificationResult) -> Result<Value, serde_json::Error> {
+    let mut settings: Map<String, Value> = serde_json::from_str(&result.compiler_settings)?;
+    settings.remove("outputSelection");
+    let libraries = settings.remove("libraries").unwrap_or_default();
+    settings.insert("libraries".into(), flatten_libraries(libraries));
+    settings.insert(
+        "compilationTarget".into(),
+        json!({ &result.file_name: &result.contract_name }),
+    );
+    settings.entry("remappings").or_insert_with(|| json!([]));
+    let metadata_settings = settings
+        .entry("metadata")
+        .or_insert_with(|| json!({ "bytecodeHash": "ipfs" }));
+    let use_literal_content = metadata_settings["useLiteralContent"] == json!(true);
+
+    let sources: Map<String, Value> = result
+        .sources
+        .iter()
+        .map(|(path, content)| {
+            let mut source = json!({
+                "keccak256": format!("0x{}", hex::encode(ethers_core::utils::keccak256(content))),
+            });
+            if let Some(license) = license(content) {
+                source["license"] = json!(license);
+            }
+            if use_literal_content {
+                source["content"] = json!(content);
+            }
+            (path.clone(), source)
+        })
+        .collect();
+
+    let abi = match &result.abi {
+        Some(abi) => serde_json::from_str(abi)?,
+        None => json!([]),
+    };
+    Ok(json!({
+        "compiler": {
+            "version": result.compiler_version.trim_start_matches('v'),
+        },
+        "language": "Solidity",
+        "output": {
+            "abi": abi,
+            "devdoc": documentation(result.devdoc.as_ref(), "dev"),
+            "userdoc": documentation(result.userdoc.as_ref(), "user"),
+        },
+        "settings": settings,
+        "sources": sources,
+        "version": 1,
+    }))
+}
+
+/// Libraries are keyed by `<file>:<library>` in the metadata,
+/// while in the compiler input they are grouped by files.
+fn flatten_libraries(libraries: Value) -> Value {
+    let libraries = match libraries {
+        Value::Object(libraries) => libraries,
+        _ => return json!({}),
+    };
+    let flattened: Map<String, Value> = libraries
+        .into_iter()
+        .flat_map(|(file, libs)| match libs {
+            Value::Object(libs) => libs
+                .into_iter()
+                .map(|(name, address)| (format!("{file}:{name}"), address))
+                .collect(),
+            // Already flattened
+            address => vec![(file, address)],
+        })
+        .collect();
+    Value::Object(flattened)
+}
+
+/// Documentation is not available for contracts verified before it has been stored,
+/// so empty one is returned for them. Empty `methods` are always present in the metadata.
+fn documentation(doc: Option<&Value>, kind: &str) -> Value {
+    let mut doc = doc
+        .cloned()
+        .unwrap_or_else(|| json!({ "kind": kind, "version": 1 }));
+    if let Value::Object(doc) = &mut doc {
+        doc.entry("methods").or_insert_with(|| json!({}));
+    }
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn metadata_is_generated() {
+        let result: VerificationResult = serde_json::from_value(json!({
+            "file_name": "contracts/A.sol",
+            "contract_name": "A",
+            "compiler_version": "v0.8.14+commit.80d49f37",
+            "evm_version": "london",
+            "constructor_arguments": null,
+            "optimization": true,
+            "optimization_runs": 200,
+            "contract_libraries": {},
+            "abi": "[]",
+            "sources": {
+                "contracts/A.sol": "// SPDX-License-Identifier: MIT\ncontract A {}",
+            },
+            "compiler_settings": r#"{
+                "optimizer": { "enabled": true, "runs": 200 },
+                "outputSelection": { "*": { "*": ["abi"] } },
+                "evmVersion": "london",
+                "libraries": { "contracts/Lib.sol": { "Lib": "0xcafecafecafecafecafecafecafecafecafecafe" } }
+            }"#,
+            "devdoc": { "kind": "dev", "version": 1, "title": "A contract" },
+        }))
+        .unwrap();
+
+        let expected = json!({
+            "compiler": { "version": "0.8.14+commit.80d49f37" },
+            "language": "Solidity",
+            "output": {
+                "abi": [],
+                "devdoc": { "kind": "dev", "methods": {}, "title": "A contract", "version": 1 },
+                "userdoc": { "kind": "user", "methods": {}, "version": 1 },
+            },
+            "settings": {
+                "compilationTarget": { "contracts/A.sol": "A" },
+                "evmVersion": "london",
+                "libraries": {
+                    "contracts/Lib.sol:Lib": "0xcafecafecafecafecafecafecafecafecafecafe"
+                },
+                "metadata": { "bytecodeHash": "ipfs" },
+                "optimizer": { "enabled": true, "runs": 200 },
+                "remappings": [],
+            },
+            "sources": {
+                "contracts/A.sol": {
+                    "keccak256": format!(
+                        "0x{}",
+                        hex::encode(ethers_core::utils::keccak256(
+                            "// SPDX-License-Identifier: MIT\ncontract A {}"
+                        ))
+                    ),
+                    "license": "MIT",
+                },
+            },
+            "version": 1,
+        });
+        assert_eq!(expected, metadata(&result).unwrap());
+    }
+}
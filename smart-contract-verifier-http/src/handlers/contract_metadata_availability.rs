@@ -0,0 +1,24 @@
+use crate::{api_error::ApiError, chains::Chain, metadata_availability::MetadataAvailability, DB};
+use actix_web::{web, web::Json};
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+/// Returns whether the metadata file referenced by the code of the contract
+/// has been available on IPFS or Swarm when last checked after verification.
+#[instrument(level = "debug")]
+pub async fn get_metadata_availability(
+    address: web::Path<Address>,
+    chain: Chain,
+) -> Result<Json<MetadataAvailability>, ApiError> {
+    let database = DB::new().await.change_name(&chain.namespace());
+    let availability = database
+        .find_metadata_availability(&address)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| {
+            ApiError::not_found(format!(
+                "Metadata availability of contract {address} has not been checked"
+            ))
+        })?;
+    Ok(Json(availability))
+}
@@ -0,0 +1,17 @@
+use crate::{api_error::ApiError, chains::Chain, proxy_history::ImplementationChange, DB};
+use actix_web::{web, web::Json};
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+#[instrument(level = "debug")]
+pub async fn get_implementations(
+    address: web::Path<Address>,
+    chain: Chain,
+) -> Result<Json<Vec<ImplementationChange>>, ApiError> {
+    let database = DB::new().await.change_name(&chain.namespace());
+    let implementations = database
+        .find_implementation_changes(&address)
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(Json(implementations))
+}
@@ -0,0 +1,103 @@
+use crate::{
+    abi::{self, DecodedParam},
+    api_error::ApiError,
+    blobs::BlobStore,
+    chains::{Chain, Chains},
+    precompiles,
+    signatures::function_signature,
+    DisplayBytes, DB,
+};
+use actix_web::{web, web::Json};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{rpc::BytecodeFetcher, solidity, Address};
+use std::str::FromStr;
+use tracing::instrument;
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ReadRequest {
+    /// Function name, or its canonical signature if the function is overloaded.
+    pub function: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ReadResponse {
+    /// Canonical signature of the called function.
+    pub function: String,
+    pub outputs: Vec<DecodedParam>,
+}
+
+#[instrument(skip(fetcher, chains, blobs, params), level = "debug")]
+pub async fn read(
+    fetcher: web::Data<BytecodeFetcher>,
+    chains: web::Data<Chains>,
+    blobs: Option<web::Data<BlobStore>>,
+    address: web::Path<Address>,
+    params: Json<ReadRequest>,
+) -> Result<Json<ReadResponse>, ApiError> {
+    let address = address.into_inner();
+    let params = params.into_inner();
+
+    let contract = stored_abi(chains.current(), blobs, &address).await?;
+    let function = abi::find_function(&contract, &params.function, Some(params.args.len()))
+        .map_err(ApiError::invalid_request)?;
+    let signature = function_signature(function);
+    if !abi::is_read_only(function) {
+        return Err(ApiError::invalid_request(format!(
+            "Function {signature} is neither view nor pure"
+        )));
+    }
+    let tokens =
+        abi::tokenize(&function.inputs, &params.args).map_err(ApiError::invalid_request)?;
+    let calldata = function
+        .encode_input(&tokens)
+        .map_err(|err| ApiError::invalid_request(format!("Cannot encode arguments: {err}")))?;
+
+    let calldata = format!("0x{}", hex::encode(calldata));
+    let output = solidity::multi_part::call(&fetcher, &address, &calldata)
+        .await
+        .map_err(ApiError::node)?
+        .ok_or_else(|| {
+            ApiError::invalid_request(format!("Call of {signature} has been reverted"))
+        })?;
+    let output = DisplayBytes::from_str(&output)
+        .map_err(|err| ApiError::internal(format!("Invalid call output: {err:?}")))?;
+    let tokens = function
+        .decode_output(&output.0)
+        .map_err(|err| ApiError::internal(format!("Cannot decode call output: {err}")))?;
+
+    Ok(Json(ReadResponse {
+        outputs: abi::decode_params(&function.outputs, tokens),
+        function: signature,
+    }))
+}
+
+/// Loads the abi of the verified contract from the database.
+/// Canonical abis are used for precompiles.
+pub async fn stored_abi(
+    chain: &Chain,
+    blobs: Option<web::Data<BlobStore>>,
+    address: &str,
+) -> Result<ethabi::Contract, ApiError> {
+    if let Some(precompile) = precompiles::find(address) {
+        return serde_json::from_value(precompile.abi())
+            .map_err(|err| ApiError::internal(format!("Precompile abi is invalid: {err}")));
+    }
+    let database = DB::new()
+        .await
+        .change_name(&chain.namespace())
+        .with_blobs(blobs.map(web::Data::into_inner));
+    let cvr = database
+        .find_contract_verify_response(address)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| ApiError::not_verified(address))?;
+    let abi = cvr
+        .result
+        .abi
+        .as_deref()
+        .ok_or_else(|| ApiError::invalid_request(format!("Contract {address} has no abi")))?;
+    serde_json::from_str(abi)
+        .map_err(|err| ApiError::internal(format!("Stored abi is invalid: {err}")))
+}
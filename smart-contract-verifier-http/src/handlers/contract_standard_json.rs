@@ -0,0 +1,155 @@
+use crate::{
+    api_error::ApiError, blobs::BlobStore, caching, chains::Chain,
+    verification_response::VerificationResult, DB,
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use ethers_solc::{
+    artifacts::{Settings, Source, Sources},
+    CompilerInput,
+};
+use smart_contract_verifier::Address;
+use std::path::PathBuf;
+use tracing::instrument;
+
+#[instrument(skip(http_request, blobs), level = "debug")]
+pub async fn get_standard_json(
+    http_request: HttpRequest,
+    address: web::Path<Address>,
+    chain: Chain,
+    blobs: Option<web::Data<BlobStore>>,
+) -> Result<HttpResponse, ApiError> {
+    let database = DB::new()
+        .await
+        .change_name(&chain.namespace())
+        .with_blobs(blobs.map(web::Data::into_inner));
+    let cvr = database
+        .find_contract_verify_response(&address)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| ApiError::not_verified(address))?;
+
+    let input = reconstruct_compiler_input(&cvr.result).map_err(|err| {
+        ApiError::internal(format!("Stored compiler settings are invalid: {err}"))
+    })?;
+    Ok(caching::cached_json(
+        &http_request,
+        &cvr.content_hash(),
+        &input,
+    ))
+}
+
+/// Reconstructs the standard-json input the contract has been successfully verified with.
+///
+/// Stored settings are the exact settings used on local compilation (including the metadata
+/// bytecode hash variant chosen for multi-part requests), so the result reproduces the build bit-for-bit.
+pub fn reconstruct_compiler_input(
+    result: &VerificationResult,
+) -> Result<CompilerInput, serde_json::Error> {
+    let settings: Settings = serde_json::from_str(&result.compiler_settings)?;
+    let sources: Sources = result
+        .sources
+        .iter()
+        .map(|(path, content)| {
+            (
+                PathBuf::from(path),
+                Source {
+                    content: content.clone(),
+                },
+            )
+        })
+        .collect();
+
+    Ok(CompilerInput {
+        language: language(&result.file_name).to_string(),
+        sources,
+        settings,
+    })
+}
+
+fn language(file_name: &str) -> &'static str {
+    if file_name.ends_with(".yul") {
+        "Yul"
+    } else if file_name.ends_with(".vy") {
+        "Vyper"
+    } else {
+        "Solidity"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
+
+    fn verification_result(file_name: &str, compiler_settings: &str) -> VerificationResult {
+        VerificationResult {
+            file_name: file_name.to_string(),
+            contract_name: "A".to_string(),
+            fully_qualified_name: None,
+            other_matches: vec![],
+            match_type: None,
+            compiler_version: "v0.8.14+commit.80d49f37".to_string(),
+            evm_version: "london".to_string(),
+            constructor_arguments: None,
+            optimization: Some(true),
+            optimization_runs: Some(200),
+            contract_libraries: BTreeMap::new(),
+            abi: None,
+            sources: BTreeMap::from([(file_name.to_string(), "content".to_string())]),
+            compiler_settings: compiler_settings.to_string(),
+            standards: vec![],
+            licenses: vec![],
+            compiler_messages: vec![],
+            code_hash: None,
+            contract_size: None,
+            impersonation_warning: None,
+            source_warnings: vec![],
+            local_creation_bytecode: None,
+            local_deployed_bytecode: None,
+            local_creation_input_parts: None,
+            local_deployed_bytecode_parts: None,
+            devdoc: None,
+            userdoc: None,
+            storage_layout: None,
+            extra_outputs: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn reconstructs_solidity_input() {
+        let settings = r#"{"optimizer":{"enabled":true,"runs":200},"metadata":{"bytecodeHash":"none"},"outputSelection":{},"evmVersion":"london","libraries":{}}"#;
+        let input = reconstruct_compiler_input(&verification_result("A.sol", settings))
+            .expect("settings are valid");
+
+        assert_eq!("Solidity", input.language);
+        assert_eq!(
+            Some(&Source {
+                content: "content".into()
+            }),
+            input.sources.get(&PathBuf::from("A.sol"))
+        );
+        assert_eq!(Some(200), input.settings.optimizer.runs);
+        assert_eq!(
+            serde_json::from_str::<Settings>(settings).unwrap().metadata,
+            input.settings.metadata,
+            "metadata settings must be preserved"
+        );
+    }
+
+    #[test]
+    fn detects_language_by_file_extension() {
+        let settings = r#"{"optimizer":{},"outputSelection":{}}"#;
+        for (file_name, expected) in [("A.sol", "Solidity"), ("A.yul", "Yul"), ("A.vy", "Vyper")] {
+            let input = reconstruct_compiler_input(&verification_result(file_name, settings))
+                .expect("settings are valid");
+            assert_eq!(expected, input.language, "invalid language for {file_name}");
+        }
+    }
+
+    #[test]
+    fn invalid_settings_fail() {
+        reconstruct_compiler_input(&verification_result("A.sol", "not a json"))
+            .expect_err("settings are not valid json");
+    }
+}
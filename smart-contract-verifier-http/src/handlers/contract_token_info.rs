@@ -0,0 +1,24 @@
+use crate::{api_error::ApiError, chains::Chain, token_info::TokenInfo, DB};
+use actix_web::{web, web::Json};
+use smart_contract_verifier::Address;
+use tracing::instrument;
+
+/// Returns name, symbol and other metadata of the verified token, read from chain on verification.
+#[instrument(level = "debug")]
+pub async fn get_token_info(
+    address: web::Path<Address>,
+    chain: Chain,
+) -> Result<Json<TokenInfo>, ApiError> {
+    let database = DB::new().await.change_name(&chain.namespace());
+    let token_info = database
+        .find_token_info(&address)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| ApiError::not_verified(&address))?
+        .ok_or_else(|| {
+            ApiError::not_found(format!(
+                "Contract {address} is neither an ERC-20 nor an ERC-721 token"
+            ))
+        })?;
+    Ok(Json(token_info))
+}
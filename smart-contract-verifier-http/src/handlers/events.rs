@@ -0,0 +1,86 @@
+use crate::{api_error::ApiError, chains::Chain, signatures::normalize_topic, DB};
+use actix_web::{web, web::Json};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::instrument;
+
+/// Max number of topics which can be resolved by a single bulk request.
+const MAX_BULK_TOPICS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct EventResponse {
+    pub topic0: String,
+    pub signatures: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct BulkLookupRequest {
+    pub topics: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct BulkLookupResponse {
+    /// Maps requested topics into known signatures.
+    pub results: BTreeMap<String, Vec<String>>,
+}
+
+#[instrument(level = "debug")]
+pub async fn get_event(
+    topic0: web::Path<String>,
+    chain: Chain,
+) -> Result<Json<EventResponse>, ApiError> {
+    let topic0 = normalize_topic(&topic0)
+        .ok_or_else(|| ApiError::invalid_request(format!("Invalid topic: {topic0}")))?;
+    let mut results = lookup(&chain, vec![topic0.clone()]).await?;
+    Ok(Json(EventResponse {
+        signatures: results.remove(&topic0).unwrap_or_default(),
+        topic0,
+    }))
+}
+
+#[instrument(skip(params), level = "debug")]
+pub async fn lookup_events(
+    params: Json<BulkLookupRequest>,
+    chain: Chain,
+) -> Result<Json<BulkLookupResponse>, ApiError> {
+    if params.topics.len() > MAX_BULK_TOPICS {
+        return Err(ApiError::invalid_request(format!(
+            "At most {MAX_BULK_TOPICS} topics can be requested at once"
+        )));
+    }
+    let topics = params
+        .into_inner()
+        .topics
+        .iter()
+        .map(|topic| {
+            normalize_topic(topic)
+                .ok_or_else(|| ApiError::invalid_request(format!("Invalid topic: {topic}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(BulkLookupResponse {
+        results: lookup(&chain, topics).await?,
+    }))
+}
+
+async fn lookup(
+    chain: &Chain,
+    topics: Vec<String>,
+) -> Result<BTreeMap<String, Vec<String>>, ApiError> {
+    let database = DB::new().await.change_name(&chain.namespace());
+    let signatures = database
+        .find_event_signatures(&topics)
+        .await
+        .map_err(ApiError::storage)?;
+
+    let mut results: BTreeMap<_, _> = topics
+        .into_iter()
+        .map(|topic| (topic, Vec::new()))
+        .collect();
+    for signature in signatures {
+        results
+            .entry(signature.topic0)
+            .or_default()
+            .push(signature.signature);
+    }
+    Ok(results)
+}
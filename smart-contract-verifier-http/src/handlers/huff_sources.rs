@@ -0,0 +1,182 @@
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    attempts::{self, FailureReason, VerificationAttempt},
+    auth::AdminAuth,
+    compiler_inputs::ArchivedCompilerInput,
+    handlers::{
+        admin_contracts::{self, Submission},
+        solidity_multi_part,
+    },
+    idempotency::IdempotencyCache,
+    metrics,
+    persistence::{ResultMiddlewares, VerifiedContract},
+    verification_response::VerificationResponse,
+    DB,
+};
+use actix_web::{web, web::Json, HttpRequest};
+use serde::Deserialize;
+use smart_contract_verifier::{huff, Address, HuffClient, VerificationError, Version};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tracing::instrument;
+
+const METHOD: &str = "sources";
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct VerificationRequest {
+    /// Checksummed (EIP-55) if provided in mixed case.
+    pub contract_address: Address,
+    pub compiler_version: String,
+    /// (optional) Number of the block at which the deployed code of the contract is fetched.
+    pub block_number: Option<u64>,
+    /// (optional) Tag ("latest", "earliest" or "pending") of the block at which
+    /// the deployed code is fetched. Cannot be set along with `block_number`.
+    pub block_tag: Option<String>,
+    /// (optional) Name of the contract to verify, plain (`Name`) or fully qualified (`file.huff:Name`).
+    /// Required only if several compiled contracts match the bytecode.
+    pub contract_name: Option<String>,
+    /// If set, failed verifications return diagnostics for each candidate contract.
+    #[serde(default)]
+    pub verbose: bool,
+    pub sources: BTreeMap<PathBuf, String>,
+}
+
+impl TryFrom<VerificationRequest> for huff::sources::VerificationRequest {
+    type Error = ApiError;
+
+    fn try_from(value: VerificationRequest) -> Result<Self, Self::Error> {
+        let compiler_version = Version::from_str(&value.compiler_version).map_err(|err| {
+            ApiError::new(
+                ErrorCode::InvalidCompilerVersion,
+                format!("Invalid compiler version: {err}"),
+            )
+        })?;
+        let block = solidity_multi_part::block_id(value.block_number, value.block_tag.as_deref())
+            .map_err(ApiError::invalid_request)?;
+        Ok(Self {
+            contract_address: value.contract_address,
+            compiler_version,
+            block,
+            contract_name: value.contract_name,
+            sources: value.sources,
+        })
+    }
+}
+
+#[instrument(
+    skip(http_request, auth, middlewares, client, idempotency, params),
+    level = "debug"
+)]
+pub async fn verify(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
+    client: web::Data<HuffClient>,
+    idempotency: web::Data<IdempotencyCache>,
+    params: Json<VerificationRequest>,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    idempotency
+        .run(&http_request, async {
+            let verbose = params.verbose;
+            let request: huff::sources::VerificationRequest = params.into_inner().try_into()?;
+
+            let database = DB::new()
+                .await
+                .change_name(&middlewares.chain().namespace());
+            let submission = admin_contracts::check_submission(
+                &database,
+                auth.authenticate(&http_request),
+                &request.contract_address,
+            )
+            .await?;
+            if submission == Submission::Rejected {
+                let message = format!("Contract {} is already verified", request.contract_address);
+                let attempt = VerificationAttempt::new(
+                    &request.contract_address,
+                    METHOD,
+                    &request.compiler_version,
+                    None,
+                    Some((FailureReason::AlreadyVerified, message.clone())),
+                    Duration::default(),
+                );
+                attempts::record(&database, "huff", attempt).await;
+                return Ok(Json(VerificationResponse::err(message)));
+            }
+
+            let started = Instant::now();
+            let result = huff::sources::verify(client.into_inner(), request.clone()).await;
+            let attempt = VerificationAttempt::new(
+                &request.contract_address,
+                METHOD,
+                &request.compiler_version,
+                None,
+                result.as_ref().err().map(attempts::failure),
+                started.elapsed(),
+            );
+            attempts::record(&database, "huff", attempt).await;
+
+            if let Ok(verification_success) = result {
+                let compiler_input = ArchivedCompilerInput::new(
+                    &request.contract_address,
+                    &verification_success,
+                    middlewares.encoding(),
+                );
+                let response =
+                    VerificationResponse::ok(middlewares.verification_result(verification_success));
+                metrics::count_verify_contract("huff", &response.status, METHOD);
+                let verified = VerifiedContract {
+                    contract_address: request.contract_address.to_string(),
+                    result: response.result.clone().unwrap(),
+                    repository: None,
+                    replaced_by: submission.replaced_by(),
+                    debug_info: None,
+                    compiler_input,
+                    job_id: None,
+                };
+                middlewares.run(&verified).await?;
+                return Ok(Json(response));
+            }
+
+            let err = result.unwrap_err();
+            match err {
+                VerificationError::NoMatchingContracts(ref diagnostics) if verbose => Ok(Json(
+                    VerificationResponse::err(&err).with_diagnostics(diagnostics.clone()),
+                )),
+                VerificationError::Compilation(_)
+                | VerificationError::NoMatchingContracts(_)
+                | VerificationError::CompilerVersionMismatch(_) => {
+                    Ok(Json(VerificationResponse::err(err)))
+                }
+                err => Err(err.into()),
+            }
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use smart_contract_verifier::rpc::BlockId;
+
+    #[test]
+    fn request_is_converted() {
+        let request: VerificationRequest = serde_json::from_str(
+            r##"{
+                "contract_address": "0xbebebebebebebebebebebebebebebebebebebebe",
+                "compiler_version": "v0.3.2+commit.e3d4ce5b",
+                "block_number": 100,
+                "sources": { "src/Main.huff": "#define macro MAIN() = takes(0) returns(0) {}" }
+            }"##,
+        )
+        .unwrap();
+        let request = huff::sources::VerificationRequest::try_from(request).unwrap();
+        assert_eq!(BlockId::Number(100), request.block);
+        assert_eq!(None, request.contract_name);
+        assert_eq!(1, request.sources.len());
+    }
+}
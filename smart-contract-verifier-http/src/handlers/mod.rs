@@ -1,10 +1,61 @@
 pub mod status;
 
+pub mod admin_access_log;
+pub mod admin_audit;
+pub mod admin_compilers;
+pub mod admin_contracts;
+pub mod admin_export;
+pub mod admin_import;
+pub mod admin_logs;
+
+pub mod solidity_compile;
+pub mod solidity_erc20_module;
+pub mod solidity_etherscan_import;
+pub mod solidity_flattened;
+pub mod solidity_git_import;
 pub mod solidity_multi_part;
+pub mod solidity_multi_part_upload;
+pub mod solidity_multi_part_zip;
+pub mod solidity_sources_validate;
 pub mod solidity_standard_json;
 pub mod solidity_version_list;
 
 pub mod vyper_multi_part;
 pub mod vyper_version_list;
 
+pub mod huff_sources;
+pub mod huff_version_list;
+
 pub mod sourcify;
+
+pub mod contract_analysis;
+pub mod contract_attempts;
+pub mod contract_attestation;
+pub mod contract_compiler_input;
+pub mod contract_debug_info;
+pub mod contract_derived;
+pub mod contract_highlights;
+pub mod contract_lookalikes;
+pub mod contract_lookup;
+pub mod contract_metadata;
+pub mod contract_metadata_availability;
+pub mod contract_proxy_history;
+pub mod contract_read;
+pub mod contract_standard_json;
+pub mod contract_token_info;
+
+pub mod events;
+pub mod selectors;
+pub mod stats;
+pub mod uploads;
+pub mod verification_intents;
+pub mod verification_jobs;
+
+pub mod tools_abi;
+pub mod tools_abi_diff;
+pub mod tools_bytecode_diff;
+pub mod tools_compute_address;
+pub mod tools_disassemble;
+pub mod tools_source_diff;
+
+pub mod ws_verified;
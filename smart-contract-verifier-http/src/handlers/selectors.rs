@@ -0,0 +1,86 @@
+use crate::{api_error::ApiError, chains::Chain, signatures::normalize_selector, DB};
+use actix_web::{web, web::Json};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::instrument;
+
+/// Max number of selectors which can be resolved by a single bulk request.
+const MAX_BULK_SELECTORS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SelectorResponse {
+    pub selector: String,
+    pub signatures: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct BulkLookupRequest {
+    pub selectors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct BulkLookupResponse {
+    /// Maps requested selectors into known signatures.
+    pub results: BTreeMap<String, Vec<String>>,
+}
+
+#[instrument(level = "debug")]
+pub async fn get_selector(
+    selector: web::Path<String>,
+    chain: Chain,
+) -> Result<Json<SelectorResponse>, ApiError> {
+    let selector = normalize_selector(&selector)
+        .ok_or_else(|| ApiError::invalid_request(format!("Invalid selector: {selector}")))?;
+    let mut results = lookup(&chain, vec![selector.clone()]).await?;
+    Ok(Json(SelectorResponse {
+        signatures: results.remove(&selector).unwrap_or_default(),
+        selector,
+    }))
+}
+
+#[instrument(skip(params), level = "debug")]
+pub async fn lookup_selectors(
+    params: Json<BulkLookupRequest>,
+    chain: Chain,
+) -> Result<Json<BulkLookupResponse>, ApiError> {
+    if params.selectors.len() > MAX_BULK_SELECTORS {
+        return Err(ApiError::invalid_request(format!(
+            "At most {MAX_BULK_SELECTORS} selectors can be requested at once"
+        )));
+    }
+    let selectors = params
+        .into_inner()
+        .selectors
+        .iter()
+        .map(|selector| {
+            normalize_selector(selector)
+                .ok_or_else(|| ApiError::invalid_request(format!("Invalid selector: {selector}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(BulkLookupResponse {
+        results: lookup(&chain, selectors).await?,
+    }))
+}
+
+async fn lookup(
+    chain: &Chain,
+    selectors: Vec<String>,
+) -> Result<BTreeMap<String, Vec<String>>, ApiError> {
+    let database = DB::new().await.change_name(&chain.namespace());
+    let signatures = database
+        .find_function_signatures(&selectors)
+        .await
+        .map_err(ApiError::storage)?;
+
+    let mut results: BTreeMap<_, _> = selectors
+        .into_iter()
+        .map(|selector| (selector, Vec::new()))
+        .collect();
+    for signature in signatures {
+        results
+            .entry(signature.selector)
+            .or_default()
+            .push(signature.signature);
+    }
+    Ok(results)
+}
@@ -0,0 +1,158 @@
+use super::solidity_standard_json::{ParseError, StandardJson};
+use crate::api_error::ApiError;
+use actix_web::{web, web::Json};
+use ethers_solc::{
+    artifacts::{BytecodeObject, Contract, Severity},
+    CompilerOutput,
+};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{solidity, SolidityClient, VerificationError, Version};
+use std::{collections::BTreeMap, str::FromStr};
+use tracing::instrument;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompileRequest {
+    pub compiler_version: String,
+
+    #[serde(flatten)]
+    pub content: StandardJson,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CompileResponse {
+    /// Errors the compilation has failed with. If any, no contracts are returned.
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Compiled contracts grouped by source file names.
+    pub contracts: BTreeMap<String, BTreeMap<String, CompiledContract>>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CompiledContract {
+    pub abi: Option<serde_json::Value>,
+    /// Creation bytecode. Is not hex encoded if there are unlinked libraries.
+    pub bytecode: Option<BytecodeObject>,
+    /// Deployed bytecode. Is not hex encoded if there are unlinked libraries.
+    pub deployed_bytecode: Option<BytecodeObject>,
+}
+
+#[instrument(skip(client, params), level = "debug")]
+pub async fn compile(
+    client: web::Data<SolidityClient>,
+    params: Json<CompileRequest>,
+) -> Result<Json<CompileResponse>, ApiError> {
+    let request: solidity::compile::CompileRequest =
+        params.into_inner().try_into().map_err(ApiError::invalid_request)?;
+    let result = solidity::compile::compile(client.into_inner(), request).await;
+
+    match result {
+        Ok(output) => Ok(Json(output.into())),
+        Err(VerificationError::Compilation(errors)) => Ok(Json(CompileResponse {
+            errors,
+            warnings: vec![],
+            contracts: BTreeMap::new(),
+        })),
+        Err(err) => Err(err.into()),
+    }
+}
+
+impl From<CompilerOutput> for CompileResponse {
+    fn from(output: CompilerOutput) -> Self {
+        let warnings = output
+            .errors
+            .into_iter()
+            .filter(|err| err.severity == Severity::Warning)
+            .map(|err| err.formatted_message.unwrap_or(err.message))
+            .collect();
+        let contracts = output
+            .contracts
+            .into_iter()
+            .map(|(file, contracts)| {
+                let contracts = contracts
+                    .into_iter()
+                    .map(|(name, contract)| (name, contract.into()))
+                    .collect();
+                (file, contracts)
+            })
+            .collect();
+        Self {
+            errors: vec![],
+            warnings,
+            contracts,
+        }
+    }
+}
+
+impl From<Contract> for CompiledContract {
+    fn from(contract: Contract) -> Self {
+        let (bytecode, deployed_bytecode) = match contract.evm {
+            Some(evm) => (
+                evm.bytecode.map(|bytecode| bytecode.object),
+                evm.deployed_bytecode
+                    .and_then(|deployed| deployed.bytecode)
+                    .map(|bytecode| bytecode.object),
+            ),
+            None => (None, None),
+        };
+        Self {
+            abi: contract.abi.map(|abi| abi.abi_value),
+            bytecode,
+            deployed_bytecode,
+        }
+    }
+}
+
+impl TryFrom<CompileRequest> for solidity::compile::CompileRequest {
+    type Error = ParseError;
+
+    fn try_from(value: CompileRequest) -> Result<Self, Self::Error> {
+        let compiler_version = Version::from_str(&value.compiler_version)
+            .map_err(|err| anyhow::anyhow!("Invalid compiler version: {}", err))?;
+        Ok(Self {
+            compiler_version,
+            content: value.content.try_into()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn compiler_output_into_response() {
+        let output: CompilerOutput = serde_json::from_value(serde_json::json!({
+            "errors": [{
+                "component": "general",
+                "formattedMessage": "Warning: SPDX license identifier not provided",
+                "message": "SPDX license identifier not provided",
+                "severity": "warning",
+                "type": "Warning"
+            }],
+            "sources": {},
+            "contracts": {
+                "A.sol": {
+                    "A": {
+                        "abi": [],
+                        "evm": {
+                            "bytecode": { "object": "6080" },
+                            "deployedBytecode": { "object": "6001" }
+                        }
+                    }
+                }
+            }
+        }))
+        .expect("valid compiler output");
+
+        let response = CompileResponse::from(output);
+        assert_eq!(
+            vec!["Warning: SPDX license identifier not provided".to_string()],
+            response.warnings
+        );
+        let contract = &response.contracts["A.sol"]["A"];
+        assert_eq!(Some(serde_json::json!([])), contract.abi);
+        assert!(contract.bytecode.is_some());
+        assert!(contract.deployed_bytecode.is_some());
+    }
+}
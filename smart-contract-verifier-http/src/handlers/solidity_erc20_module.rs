@@ -0,0 +1,132 @@
+use super::{
+    admin_contracts::{self, Submission},
+    solidity_multi_part::{self, MultiPartFiles, VerificationContext},
+};
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    auth::AdminAuth,
+    erc20_module,
+    erc20_module::ModuleVersions,
+    idempotency::IdempotencyCache,
+    persistence::ResultMiddlewares,
+    verification_response::VerificationResponse,
+    DB,
+};
+use actix_web::{web, web::Json, HttpRequest};
+use serde::Deserialize;
+use smart_contract_verifier::{Address, MatchAgainst, SolidityClient};
+use tracing::instrument;
+
+/// Verification request for an ERC-20 contract deployed by the `x/erc20` module.
+/// No sources are provided, as the canonical sources of the module contract are used.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct VerificationRequest {
+    /// Checksummed (EIP-55) if provided in mixed case.
+    pub contract_address: Address,
+    #[serde(default)]
+    pub verbose: bool,
+}
+
+/// Verifies the contract against the versions of the module contract, starting from
+/// the ones the code hash of which matches the deployed code. Failures specific to
+/// a version (e.g., its compiler cannot be fetched) do not prevent the rest from being tried.
+#[instrument(
+    skip(http_request, auth, middlewares, client, versions, idempotency, params),
+    level = "debug"
+)]
+pub async fn verify(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
+    client: web::Data<SolidityClient>,
+    versions: web::Data<ModuleVersions>,
+    idempotency: web::Data<IdempotencyCache>,
+    params: Json<VerificationRequest>,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    idempotency
+        .run(&http_request, async {
+            let params = params.into_inner();
+            let admin = auth.authenticate(&http_request);
+            let database = DB::new()
+                .await
+                .change_name(&middlewares.chain().namespace());
+            // Rejected submissions are not retried with the rest of the versions
+            let rejected = admin_contracts::check_submission(
+                &database,
+                admin.clone(),
+                params.contract_address.as_str(),
+            )
+            .await?
+                == Submission::Rejected;
+
+            let code_hash =
+                erc20_module::deployed_code_hash(client.fetcher(), &params.contract_address).await;
+            let mut response = None;
+            for version in versions.candidates(code_hash.as_deref()) {
+                let request = solidity_multi_part::VerificationRequest {
+                    contract_address: params.contract_address.clone(),
+                    creation_bytecode: None,
+                    compiler_version: version.compiler_version.clone(),
+                    block_number: None,
+                    block_tag: None,
+                    contract_name: Some(version.contract_name.clone()),
+                    // Constructor arguments are provided by the module, so only the deployed code is matched
+                    match_against: Some(MatchAgainst::Deployed),
+                    verbose: params.verbose,
+                    inject_dependencies: false,
+                    packages: vec![],
+                    create2: None,
+                    content: MultiPartFiles {
+                        sources: version.sources.clone(),
+                        evm_version: version.evm_version.clone(),
+                        optimization_runs: version.optimization_runs,
+                        contract_libraries: None,
+                        remappings: vec![],
+                        extra_outputs: vec![],
+                    },
+                };
+                let version_response = match request.try_into() {
+                    Ok(request) => {
+                        solidity_multi_part::verify_and_store(
+                            admin.clone(),
+                            &middlewares,
+                            client.clone(),
+                            request,
+                            VerificationContext::new("erc20-module", params.verbose),
+                        )
+                        .await
+                    }
+                    Err(err) => Err(err),
+                };
+                match version_response {
+                    Ok(version_response) if version_response.result.is_some() || rejected => {
+                        return Ok(version_response)
+                    }
+                    Err(err) if !is_version_failure(&err) => return Err(err),
+                    version_response => response = Some(version_response),
+                }
+            }
+            response.unwrap_or_else(|| {
+                Err(ApiError::new(
+                    ErrorCode::NoMatchingContracts,
+                    "No versions of the module contract match the deployed code",
+                ))
+            })
+        })
+        .await
+}
+
+/// Whether the error is caused by the version of the module contract (e.g., its compiler
+/// cannot be fetched), rather than by the request or the service (e.g., storage failures).
+fn is_version_failure(err: &ApiError) -> bool {
+    matches!(
+        err.code,
+        ErrorCode::InvalidCompilerVersion
+            | ErrorCode::CompilerNotFound
+            | ErrorCode::CompilerVersionMismatch
+            | ErrorCode::CompilerFetchFailed
+            | ErrorCode::CompilationFailed
+            | ErrorCode::CompilationTimeout
+            | ErrorCode::NoMatchingContracts
+    )
+}
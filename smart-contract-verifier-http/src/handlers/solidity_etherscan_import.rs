@@ -0,0 +1,110 @@
+use super::solidity_multi_part::{self, ExpectedContract, MultiPartFiles, VerificationContext};
+use crate::{
+    api_error::ApiError, auth::AdminAuth, etherscan_import, idempotency::IdempotencyCache,
+    persistence::ResultMiddlewares, settings::EtherscanImportSettings,
+    verification_response::VerificationResponse,
+};
+use actix_web::{web, web::Json, HttpRequest};
+use serde::Deserialize;
+use smart_contract_verifier::{Address, MatchAgainst, SolidityClient};
+use std::{collections::BTreeMap, time::Duration};
+use tracing::instrument;
+use url::Url;
+
+/// Verification request for a contract which sources have been verified
+/// on another chain and are fetched from an Etherscan-compatible api.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct VerificationRequest {
+    /// Checksummed (EIP-55) if provided in mixed case.
+    pub contract_address: Address,
+    /// Https url of the explorer api of the other chain (e.g., "https://api.etherscan.io/api").
+    pub explorer_api_url: Url,
+    /// (optional) Address of the contract on the other chain. `contract_address` by default.
+    pub explorer_contract_address: Option<Address>,
+    /// (optional) Api key of the explorer. If omitted, the configured one is used, if any.
+    pub explorer_api_key: Option<String>,
+    /// (optional) Addresses of the libraries on this chain, overriding
+    /// the ones linked on the other chain.
+    pub contract_libraries: Option<BTreeMap<String, String>>,
+    pub creation_bytecode: Option<String>,
+    pub block_number: Option<u64>,
+    pub block_tag: Option<String>,
+    /// Bytecode the compiled contract is required to match: "deployed", "creation" or "both".
+    pub match_against: Option<MatchAgainst>,
+    #[serde(default)]
+    pub verbose: bool,
+}
+
+#[instrument(
+    skip(http_request, auth, middlewares, client, settings, idempotency, params),
+    level = "debug"
+)]
+pub async fn verify(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
+    client: web::Data<SolidityClient>,
+    settings: web::Data<EtherscanImportSettings>,
+    idempotency: web::Data<IdempotencyCache>,
+    params: Json<VerificationRequest>,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    idempotency
+        .run(&http_request, async {
+            let params = params.into_inner();
+            let api_key = params.explorer_api_key.as_deref().or_else(|| {
+                let host = params.explorer_api_url.host_str()?;
+                settings.api_keys.get(host).map(String::as_str)
+            });
+            let explorer_address = params
+                .explorer_contract_address
+                .as_ref()
+                .unwrap_or(&params.contract_address);
+            let imported = etherscan_import::import_contract(
+                &params.explorer_api_url,
+                api_key,
+                explorer_address,
+                Duration::from_secs(settings.timeout_secs),
+            )
+            .await?;
+
+            let mut contract_libraries = imported.contract_libraries;
+            contract_libraries.extend(params.contract_libraries.unwrap_or_default());
+            let expected = ExpectedContract {
+                contract_name: imported.contract_name.clone(),
+                constructor_arguments: None,
+            };
+            let request = solidity_multi_part::VerificationRequest {
+                contract_address: params.contract_address,
+                creation_bytecode: params.creation_bytecode,
+                compiler_version: imported.compiler_version,
+                block_number: params.block_number,
+                block_tag: params.block_tag,
+                contract_name: Some(imported.contract_name),
+                match_against: params.match_against,
+                verbose: params.verbose,
+                inject_dependencies: false,
+                packages: vec![],
+                create2: None,
+                content: MultiPartFiles {
+                    sources: imported.sources,
+                    evm_version: imported.evm_version,
+                    optimization_runs: imported.optimization_runs,
+                    contract_libraries: Some(contract_libraries),
+                    remappings: imported.remappings,
+                    extra_outputs: vec![],
+                },
+            };
+            solidity_multi_part::verify_and_store(
+                auth.authenticate(&http_request),
+                &middlewares,
+                client,
+                request.try_into()?,
+                VerificationContext {
+                    expected: Some(expected),
+                    ..VerificationContext::new("etherscan", params.verbose)
+                },
+            )
+            .await
+        })
+        .await
+}
@@ -0,0 +1,175 @@
+use super::solidity_multi_part::{self, ExpectedContract, MultiPartFiles, VerificationContext};
+use crate::{
+    api_error::ApiError, auth::AdminAuth, idempotency::IdempotencyCache,
+    persistence::ResultMiddlewares, verification_response::VerificationResponse, DisplayBytes,
+};
+use actix_web::{web, web::Json, HttpRequest};
+use serde::Deserialize;
+use smart_contract_verifier::{Address, MatchAgainst, SolidityClient};
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+use tracing::instrument;
+
+/// Number of optimizer runs used if optimization is enabled without specifying the runs.
+const DEFAULT_OPTIMIZATION_RUNS: usize = 200;
+
+/// Single-file verification request, in the shape most explorer UIs and older tooling produce.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct VerificationRequest {
+    /// Checksummed (EIP-55) if provided in mixed case.
+    pub contract_address: Address,
+    /// Flattened source code of the contract with all imports inlined.
+    pub source_code: String,
+    /// Name of the contract to verify; other contracts of the file are not accepted.
+    pub contract_name: String,
+    pub compiler_version: String,
+    #[serde(default)]
+    pub optimization: bool,
+    pub optimization_runs: Option<usize>,
+    pub evm_version: Option<String>,
+    /// Hex encoded ABI-encoded constructor arguments. Checked if `creation_bytecode` is provided.
+    pub constructor_arguments: Option<String>,
+    pub contract_libraries: Option<BTreeMap<String, String>>,
+    pub creation_bytecode: Option<String>,
+    pub block_number: Option<u64>,
+    pub block_tag: Option<String>,
+    /// Bytecode the compiled contract is required to match: "deployed", "creation" or "both".
+    pub match_against: Option<MatchAgainst>,
+    #[serde(default)]
+    pub verbose: bool,
+}
+
+#[instrument(
+    skip(http_request, auth, middlewares, client, idempotency, params),
+    level = "debug"
+)]
+pub async fn verify(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
+    client: web::Data<SolidityClient>,
+    idempotency: web::Data<IdempotencyCache>,
+    params: Json<VerificationRequest>,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    idempotency
+        .run(&http_request, async {
+            let verbose = params.verbose;
+            let (request, expected) = params.into_inner().into_multi_part()?;
+            solidity_multi_part::verify_and_store(
+                auth.authenticate(&http_request),
+                &middlewares,
+                client,
+                request.try_into()?,
+                VerificationContext {
+                    expected: Some(expected),
+                    ..VerificationContext::new("flattened", verbose)
+                },
+            )
+            .await
+        })
+        .await
+}
+
+impl VerificationRequest {
+    /// Builds the multi-part request with a single source file named after the contract.
+    fn into_multi_part(
+        self,
+    ) -> Result<(solidity_multi_part::VerificationRequest, ExpectedContract), ApiError> {
+        let constructor_arguments = match self.constructor_arguments {
+            None => None,
+            Some(arguments) => Some(DisplayBytes::from_str(&arguments).map_err(|err| {
+                ApiError::invalid_request(format!("Invalid constructor arguments: {err:?}"))
+            })?),
+        };
+        let optimization_runs = self
+            .optimization
+            .then(|| self.optimization_runs.unwrap_or(DEFAULT_OPTIMIZATION_RUNS));
+        let file_name = PathBuf::from(format!("{}.sol", self.contract_name));
+
+        let request = solidity_multi_part::VerificationRequest {
+            contract_address: self.contract_address,
+            creation_bytecode: self.creation_bytecode,
+            compiler_version: self.compiler_version,
+            block_number: self.block_number,
+            block_tag: self.block_tag,
+            contract_name: Some(self.contract_name.clone()),
+            match_against: self.match_against,
+            verbose: self.verbose,
+            inject_dependencies: false,
+            packages: vec![],
+            create2: None,
+            content: MultiPartFiles {
+                sources: BTreeMap::from([(file_name, self.source_code)]),
+                evm_version: self.evm_version.unwrap_or_else(|| "default".to_string()),
+                optimization_runs,
+                contract_libraries: self.contract_libraries,
+                remappings: vec![],
+                extra_outputs: vec![],
+            },
+        };
+        let expected = ExpectedContract {
+            contract_name: self.contract_name,
+            constructor_arguments,
+        };
+        Ok((request, expected))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn request(optimization: bool, optimization_runs: Option<usize>) -> VerificationRequest {
+        VerificationRequest {
+            contract_address: Address::from_str("0xbebebebebebebebebebebebebebebebebebebebe")
+                .unwrap(),
+            source_code: "pragma solidity ^0.8.14; contract A {}".to_string(),
+            contract_name: "A".to_string(),
+            compiler_version: "v0.8.14+commit.80d49f37".to_string(),
+            optimization,
+            optimization_runs,
+            evm_version: None,
+            constructor_arguments: Some("0xcafe".to_string()),
+            contract_libraries: None,
+            creation_bytecode: None,
+            block_number: None,
+            block_tag: None,
+            match_against: None,
+            verbose: false,
+        }
+    }
+
+    #[test]
+    fn multi_part_request_is_built() {
+        let (multi_part, expected) = request(true, None).into_multi_part().unwrap();
+        assert_eq!(
+            MultiPartFiles {
+                sources: BTreeMap::from([(
+                    PathBuf::from("A.sol"),
+                    "pragma solidity ^0.8.14; contract A {}".to_string()
+                )]),
+                evm_version: "default".to_string(),
+                optimization_runs: Some(DEFAULT_OPTIMIZATION_RUNS),
+                contract_libraries: None,
+                remappings: vec![],
+                extra_outputs: vec![],
+            },
+            multi_part.content
+        );
+        assert_eq!(
+            ExpectedContract {
+                contract_name: "A".to_string(),
+                constructor_arguments: Some(DisplayBytes::from_str("0xcafe").unwrap()),
+            },
+            expected
+        );
+    }
+
+    #[test]
+    fn optimization_runs_are_ignored_if_disabled() {
+        let (multi_part, _) = request(false, Some(1000)).into_multi_part().unwrap();
+        assert_eq!(None, multi_part.content.optimization_runs);
+        let (multi_part, _) = request(true, Some(1000)).into_multi_part().unwrap();
+        assert_eq!(Some(1000), multi_part.content.optimization_runs);
+    }
+}
@@ -0,0 +1,121 @@
+use super::solidity_multi_part::{self, ExpectedContract, MultiPartFiles, VerificationContext};
+use crate::{
+    api_error::ApiError,
+    auth::AdminAuth,
+    git_import::{self, Limits},
+    idempotency::IdempotencyCache,
+    persistence::ResultMiddlewares,
+    settings::GitImportSettings,
+    verification_response::VerificationResponse,
+    verified_contract_result::RepositorySource,
+};
+use actix_web::{web, web::Json, HttpRequest};
+use serde::Deserialize;
+use smart_contract_verifier::{Address, MatchAgainst, SolidityClient};
+use std::{collections::BTreeMap, time::Duration};
+use tracing::instrument;
+use url::Url;
+
+/// Verification request for a contract which sources are stored in a public git repository.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct VerificationRequest {
+    /// Checksummed (EIP-55) if provided in mixed case.
+    pub contract_address: Address,
+    /// Https url of the repository (e.g., "https://github.com/owner/repo").
+    pub repository_url: Url,
+    /// Full hash of the commit the contract has been compiled at.
+    pub commit: String,
+    /// Path of the contract file inside of the repository (e.g., "src/Token.sol").
+    pub contract_path: String,
+    /// (optional) Name of the contract to verify. If omitted, any contract
+    /// of the file or its imports may be matched.
+    pub contract_name: Option<String>,
+    pub compiler_version: String,
+    pub evm_version: Option<String>,
+    pub optimization_runs: Option<usize>,
+    pub contract_libraries: Option<BTreeMap<String, String>>,
+    pub creation_bytecode: Option<String>,
+    pub block_number: Option<u64>,
+    pub block_tag: Option<String>,
+    /// Bytecode the compiled contract is required to match: "deployed", "creation" or "both".
+    pub match_against: Option<MatchAgainst>,
+    #[serde(default)]
+    pub verbose: bool,
+}
+
+#[instrument(
+    skip(http_request, auth, middlewares, client, settings, idempotency, params),
+    level = "debug"
+)]
+pub async fn verify(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
+    client: web::Data<SolidityClient>,
+    settings: web::Data<GitImportSettings>,
+    idempotency: web::Data<IdempotencyCache>,
+    params: Json<VerificationRequest>,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    idempotency
+        .run(&http_request, async {
+            let params = params.into_inner();
+            let limits = Limits {
+                timeout: Duration::from_secs(settings.timeout_secs),
+                max_size: settings.max_repository_size_mb.saturating_mul(1024 * 1024),
+            };
+            let project = git_import::import_project(
+                &params.repository_url,
+                &params.commit,
+                &params.contract_path,
+                limits,
+            )
+            .await?;
+
+            let repository = RepositorySource {
+                url: params.repository_url.to_string(),
+                commit: params.commit,
+                contract_path: params.contract_path,
+            };
+            let expected = params
+                .contract_name
+                .clone()
+                .map(|contract_name| ExpectedContract {
+                    contract_name,
+                    constructor_arguments: None,
+                });
+            let request = solidity_multi_part::VerificationRequest {
+                contract_address: params.contract_address,
+                creation_bytecode: params.creation_bytecode,
+                compiler_version: params.compiler_version,
+                block_number: params.block_number,
+                block_tag: params.block_tag,
+                contract_name: params.contract_name,
+                match_against: params.match_against,
+                verbose: params.verbose,
+                inject_dependencies: false,
+                packages: vec![],
+                create2: None,
+                content: MultiPartFiles {
+                    sources: project.sources,
+                    evm_version: params.evm_version.unwrap_or_else(|| "default".to_string()),
+                    optimization_runs: params.optimization_runs,
+                    contract_libraries: params.contract_libraries,
+                    remappings: project.remappings,
+                    extra_outputs: vec![],
+                },
+            };
+            solidity_multi_part::verify_and_store(
+                auth.authenticate(&http_request),
+                &middlewares,
+                client,
+                request.try_into()?,
+                VerificationContext {
+                    expected,
+                    repository: Some(repository),
+                    ..VerificationContext::new("git", params.verbose)
+                },
+            )
+            .await
+        })
+        .await
+}
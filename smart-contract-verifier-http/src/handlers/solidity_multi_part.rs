@@ -1,105 +1,353 @@
-use crate::{metrics, verification_response::VerificationResponse, verification_response::VerificationResult, verified_contract_result::Verified_Contract_Result, DB, DisplayBytes};
-use actix_web::{error, web, web::Json};
-use ethers_solc::EvmVersion;
-use serde::Deserialize;
-use smart_contract_verifier::{solidity, SolidityClient, VerificationError, Version};
-use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+use crate::{api_error::{ApiError, ErrorCode}, attempts::{self, FailureReason, VerificationAttempt}, auth::AdminAuth, compiler_inputs::ArchivedCompilerInput, create2::Create2Deployment, dependencies::{PackageRegistry, PackageVersion}, handlers::admin_contracts::{self, Submission}, idempotency::IdempotencyCache, metrics, persistence::{ResultMiddlewares, VerifiedContract}, verification_response::VerificationResponse, verification_response::VerificationResult, verified_contract_result::RepositorySource, DB, DisplayBytes};
+use actix_web::{web, web::Json, HttpRequest};
+use ethers_solc::{remappings::Remapping, EvmVersion};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{
+    progress::{self, Stage}, rpc::BlockId, solidity, tools::linking, Address, ContractDiagnostics,
+    MatchAgainst, SolidityClient, VerificationError, VerificationSuccess, Version,
+};
+use std::{collections::BTreeMap, path::{Path, PathBuf}, str::FromStr, time::{Duration, Instant}};
 use tracing::instrument;
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct VerificationRequest {
-    pub contract_address: String,
+    /// Checksummed (EIP-55) if provided in mixed case.
+    pub contract_address: Address,
     pub creation_bytecode: Option<String>,
     pub compiler_version: String,
+    /// (optional) Number of the block at which the deployed code of the contract is fetched.
+    /// Allows verifying self-destructed or redeployed contracts. The latest block by default.
+    pub block_number: Option<u64>,
+    /// (optional) Tag ("latest", "earliest" or "pending") of the block at which
+    /// the deployed code is fetched. Cannot be set along with `block_number`.
+    pub block_tag: Option<String>,
+    /// (optional) Name of the contract to verify, plain (`Name`) or fully qualified (`file.sol:Name`).
+    /// Required only if several compiled contracts match the bytecode.
+    pub contract_name: Option<String>,
+    /// (optional) Bytecode the compiled contract is required to match: "deployed", "creation" or "both".
+    /// By default, the creation bytecode is matched if provided, and the deployed one otherwise.
+    pub match_against: Option<MatchAgainst>,
+    /// If set, failed verifications return diagnostics for each candidate contract.
+    #[serde(default)]
+    pub verbose: bool,
+    /// If set, missing files of well-known packages (e.g., OpenZeppelin contracts)
+    /// imported by the sources are added from the package registry.
+    #[serde(default)]
+    pub inject_dependencies: bool,
+    /// Versions of the packages the missing files are injected from (e.g., "@openzeppelin/contracts@4.9.3"),
+    /// overriding the pinned ones. Implies `inject_dependencies`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<String>,
+    /// (optional) Deployment of the contract by a factory with `CREATE2`, which creation
+    /// transaction input is not the creation bytecode. Cannot be set along with `creation_bytecode`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create2: Option<Create2Deployment>,
 
     #[serde(flatten)]
     pub content: MultiPartFiles,
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct MultiPartFiles {
     pub sources: BTreeMap<PathBuf, String>,
     pub evm_version: String,
     pub optimization_runs: Option<usize>,
     pub contract_libraries: Option<BTreeMap<String, String>>,
+    /// Import remappings (e.g., "@openzeppelin/=lib/openzeppelin-contracts/").
+    #[serde(default)]
+    pub remappings: Vec<String>,
+    /// Contract level compiler outputs (e.g., "ir", "evm.gasEstimates") to be returned
+    /// in `extra_outputs` of the result in addition to the default ones.
+    #[serde(default)]
+    pub extra_outputs: Vec<String>,
 }
 
-#[instrument(skip(client, params), level = "debug")]
+#[instrument(skip(http_request, auth, middlewares, client, idempotency, registry, params), level = "debug")]
 pub async fn verify(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
     client: web::Data<SolidityClient>,
+    idempotency: web::Data<IdempotencyCache>,
+    registry: Option<web::Data<PackageRegistry>>,
     params: Json<VerificationRequest>,
-) -> Result<Json<VerificationResponse>, actix_web::Error> {
-    let request: smart_contract_verifier::solidity::multi_part::VerificationRequest = params.into_inner().try_into()?;
+) -> Result<Json<VerificationResponse>, ApiError> {
+    idempotency
+        .run(&http_request, async {
+            let params = params.into_inner();
+            let context = VerificationContext { create2: params.create2.clone(), ..VerificationContext::new("multi-part", params.verbose) };
+            let (request, injected_sources) = params.into_verifier_request(registry.as_deref()).await?;
+            let response = verify_and_store(auth.authenticate(&http_request), &middlewares, client, request, context).await?;
+            Ok(Json(response.into_inner().with_injected_sources(injected_sources)))
+        })
+        .await
+}
+
+impl VerificationRequest {
+    /// Converts the request into the one of the verifier. If requested, missing files of the packages
+    /// are injected into the sources first, and the paths of the injected files are returned as well.
+    pub(crate) async fn into_verifier_request(mut self, registry: Option<&PackageRegistry>) -> Result<(solidity::multi_part::VerificationRequest, Vec<String>), ApiError> {
+        let mut injected_sources = vec![];
+        if let Some(packages) = self.requested_packages(registry)? {
+            let registry = registry.expect("is checked along with the packages").with_packages(&packages).await?;
+            injected_sources = registry
+                .inject(&mut self.content.sources, &self.content.remappings)
+                .into_iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect();
+        }
+        Ok((self.try_into()?, injected_sources))
+    }
+
+    /// Checks that the request can be converted into the one of the verifier,
+    /// without injecting missing files (e.g., before the request is queued).
+    pub(crate) fn validate(&self, registry: Option<&PackageRegistry>) -> Result<(), ApiError> {
+        self.requested_packages(registry)?;
+        let _: solidity::multi_part::VerificationRequest = self.clone().try_into()?;
+        Ok(())
+    }
+
+    /// Returns versions of the packages missing files are injected from, if the injection is requested.
+    fn requested_packages(&self, registry: Option<&PackageRegistry>) -> Result<Option<Vec<PackageVersion>>, ApiError> {
+        if !self.inject_dependencies && self.packages.is_empty() {
+            return Ok(None);
+        }
+        if registry.is_none() {
+            return Err(ApiError::invalid_request("Dependencies injection is disabled"));
+        }
+        let packages = self.packages.iter().map(|package| package.parse()).collect::<Result<Vec<PackageVersion>, _>>()?;
+        Ok(Some(packages))
+    }
+}
+
+/// Describes how the verification has been requested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VerificationContext {
+    /// Verification endpoint used (e.g., "multi-part"), recorded with the attempt.
+    pub method: &'static str,
+    /// If set, failed verifications return diagnostics for each candidate contract.
+    pub verbose: bool,
+    pub expected: Option<ExpectedContract>,
+    /// Repository the sources have been taken from, stored along with the result.
+    pub repository: Option<RepositorySource>,
+    /// Verification job the request has been submitted as, stored along with the result.
+    pub job_id: Option<String>,
+    /// Factory deployment the init code derived from the verified contract is checked against.
+    pub create2: Option<Create2Deployment>,
+}
+
+impl VerificationContext {
+    pub fn new(method: &'static str, verbose: bool) -> Self {
+        Self {
+            method,
+            verbose,
+            expected: None,
+            repository: None,
+            job_id: None,
+            create2: None,
+        }
+    }
+}
+
+/// The contract the user expects to be verified, for requests
+/// not allowing to verify any contract found in the sources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ExpectedContract {
+    pub contract_name: String,
+    pub constructor_arguments: Option<DisplayBytes>,
+}
+
+impl ExpectedContract {
+    /// Returns diagnostics describing the mismatch, if the verified contract is not the expected one.
+    /// Constructor arguments are compared only if they have been extracted during verification.
+    fn mismatch(&self, success: &VerificationSuccess) -> Option<ContractDiagnostics> {
+        let reason = if success.contract_name != self.contract_name {
+            format!("Contract {} has been matched instead of {}", success.contract_name, self.contract_name)
+        } else {
+            match (&self.constructor_arguments, &success.constructor_args) {
+                (Some(expected), Some(actual)) if expected != actual => {
+                    "Constructor arguments differ from the provided ones".to_string()
+                }
+                _ => return None,
+            }
+        };
+        Some(ContractDiagnostics {
+            file_path: success.file_path.clone(),
+            contract_name: Some(success.contract_name.clone()),
+            reason,
+            first_diverging_offset: None,
+            metadata_hash_only: false,
+            length_differs: false,
+            hints: vec![],
+        })
+    }
+}
+
+/// Verifies the contract, records the attempt, and passes the result to the middlewares
+/// (which store it) if verification succeeds. `admin` is the admin the verification
+/// has been requested by, if any.
+pub(crate) async fn verify_and_store(
+    admin: Option<String>,
+    middlewares: &ResultMiddlewares,
+    client: web::Data<SolidityClient>,
+    request: solidity::multi_part::VerificationRequest,
+    context: VerificationContext,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    let VerificationContext { method, verbose, expected, repository, job_id, create2 } = context;
+    let database = DB::new().await.change_name(&middlewares.chain().namespace());
+    let submission = admin_contracts::check_submission(
+        &database,
+        admin,
+        &request.contract_address,
+    )
+    .await?;
+
+    let settings = serde_json::json!({
+        "evm_version": request.content.evm_version.map(|version| version.to_string()),
+        "optimization_runs": request.content.optimization_runs,
+        "contract_libraries": request.content.contract_libraries,
+        "remappings": request.content.remappings.iter().map(|remapping| remapping.to_string()).collect::<Vec<_>>(),
+    })
+    .to_string();
+    if submission == Submission::Rejected {
+        let message = format!("Contract {} is already verified", request.contract_address);
+        let attempt = VerificationAttempt::new(
+            &request.contract_address,
+            method,
+            &request.compiler_version,
+            Some(settings.clone()),
+            Some((FailureReason::AlreadyVerified, message.clone())),
+            Duration::default(),
+        );
+        attempts::record(&database, "solidity", attempt).await;
+        return Ok(Json(VerificationResponse::err(message)));
+    }
+
+    let started = Instant::now();
     let result = solidity::multi_part::verify(client.into_inner(), request.clone()).await;
+    let result = result.and_then(|success| {
+        let mismatch = expected
+            .as_ref()
+            .and_then(|expected| expected.mismatch(&success))
+            .or_else(|| create2.as_ref().and_then(|create2| create2.mismatch(&request.contract_address, &success)));
+        match mismatch {
+            Some(diagnostics) => Err(VerificationError::NoMatchingContracts(vec![diagnostics])),
+            None => Ok(success),
+        }
+    });
+
+    let attempt = VerificationAttempt::new(
+        &request.contract_address,
+        method,
+        &request.compiler_version,
+        Some(settings),
+        result.as_ref().err().map(attempts::failure),
+        started.elapsed(),
+    );
+    attempts::record(&database, "solidity", attempt).await;
 
     
 
     if let Ok(verification_success) = result {
-        let response = VerificationResponse::ok(verification_success.into());
-        metrics::count_verify_contract("solidity", &response.status, "multi-part");
-
-        //////////////////////////////////////////////////////////////////////////////
-        //////////// This is to record verification result to database ///////////////
-        //////////////////////////////////////////////////////////////////////////////
-        
-        // Creation object of DB
-        let verify_database = DB::new().await;
-        // Change name of current database from DB
-        let vd = verify_database.change_name("evmos");
-        // Bring result of smart contract verification
-        let cvr = Verified_Contract_Result {
-            contract_address: request.contract_address.to_lowercase(),
-            result: response.result.clone().unwrap()
+        let debug_info = middlewares.debug_info(&request.contract_address, &verification_success);
+        let compiler_input = ArchivedCompilerInput::new(&request.contract_address, &verification_success, middlewares.encoding());
+        let response = VerificationResponse::ok(middlewares.verification_result(verification_success));
+        metrics::count_verify_contract("solidity", &response.status, method);
+        let verified = VerifiedContract {
+            contract_address: request.contract_address.to_string(),
+            result: response.result.clone().unwrap(),
+            repository,
+            replaced_by: submission.replaced_by(),
+            debug_info,
+            compiler_input,
+            job_id,
         };
-        // Add to database called 'evmos'
-        vd.add_contract_verify_response(cvr).await;
-
-        ///////////////////////////////////// End ////////////////////////////////////
-        
+        progress::report(Stage::Storing);
+        middlewares.run(&verified).await?;
         return Ok(Json(response));
     }
 
     let err = result.unwrap_err();
     match err {
+        VerificationError::NoMatchingContracts(ref diagnostics) if verbose => Ok(Json(
+            VerificationResponse::err(&err).with_diagnostics(diagnostics.clone()),
+        )),
         VerificationError::Compilation(_)
-        | VerificationError::NoMatchingContracts
+        | VerificationError::NoMatchingContracts(_)
         | VerificationError::CompilerVersionMismatch(_) => Ok(Json(VerificationResponse::err(err))),
-        VerificationError::Initialization(_) | VerificationError::VersionNotFound(_) => {
-            Err(error::ErrorBadRequest(err))
-        }
-        VerificationError::Internal(_) => Err(error::ErrorInternalServerError(err)),
+        err => Err(err.into()),
     }
 }
 
 impl TryFrom<VerificationRequest> for solidity::multi_part::VerificationRequest {
-    type Error = actix_web::Error;
+    type Error = ApiError;
 
     fn try_from(value: VerificationRequest) -> Result<Self, Self::Error> {
         let contract_address = value.contract_address;
+        // Init code of factory deployments is used as the creation bytecode
+        let init_code = match &value.create2 {
+            Some(create2) => create2.creation_bytecode(&contract_address)?,
+            None => None,
+        };
 
         let creation_bytecode = match value.creation_bytecode {
-            None => None,
+            None => init_code.map(|init_code| init_code.0),
+            Some(_) if init_code.is_some() => {
+                return Err(ApiError::invalid_request("Only one of creation bytecode and CREATE2 init code may be provided"))
+            }
+            // Unlinked bytecode: provided library addresses are substituted into placeholders
+            Some(creation_bytecode) if linking::has_placeholders(&creation_bytecode) => {
+                let libraries = value.content.contract_libraries.clone().unwrap_or_default();
+                let files: Vec<_> = value.content.sources.keys().cloned().collect();
+                Some(
+                    linking::link(&creation_bytecode, &libraries, &files)
+                        .map_err(|err| ApiError::new(ErrorCode::InvalidBytecode, format!("Cannot link creation bytecode: {err}")))?,
+                )
+            }
             Some(creation_bytecode) => Some(
                 DisplayBytes::from_str(&creation_bytecode)
                     .map_err(|err| {
-                        error::ErrorBadRequest(format!("Invalid creation bytecode: {err:?}"))
+                        ApiError::new(ErrorCode::InvalidBytecode, format!("Invalid creation bytecode: {err:?}"))
                     })?
                     .0,
             ),
         };
         let compiler_version = Version::from_str(&value.compiler_version)
-            .map_err(|err| error::ErrorBadRequest(format!("Invalid compiler version: {err}")))?;
+            .map_err(|err| ApiError::new(ErrorCode::InvalidCompilerVersion, format!("Invalid compiler version: {err}")))?;
+        let block =
+            block_id(value.block_number, value.block_tag.as_deref()).map_err(ApiError::invalid_request)?;
         Ok(Self { 
             contract_address,
             creation_bytecode,
             compiler_version,
+            block,
+            contract_name: value.contract_name,
+            match_against: value.match_against.unwrap_or_default(),
             content: value.content.try_into()?,
         })
     }
 }
 
+/// Returns the block the deployed code of the contract should be fetched at.
+pub(crate) fn block_id(
+    block_number: Option<u64>,
+    block_tag: Option<&str>,
+) -> Result<BlockId, anyhow::Error> {
+    match (block_number, block_tag) {
+        (Some(_), Some(_)) => Err(anyhow::anyhow!(
+            "Only one of block number and block tag may be provided"
+        )),
+        (Some(number), None) => Ok(BlockId::Number(number)),
+        (None, Some(tag)) => match BlockId::from_str(tag)? {
+            BlockId::Number(_) => Err(anyhow::anyhow!("Invalid block tag: {tag}")),
+            block => Ok(block),
+        },
+        (None, None) => Ok(BlockId::Latest),
+    }
+}
+
 impl TryFrom<MultiPartFiles> for solidity::multi_part::MultiFileContent {
-    type Error = actix_web::Error;
+    type Error = ApiError;
 
     fn try_from(value: MultiPartFiles) -> Result<Self, Self::Error> {
         let sources: BTreeMap<PathBuf, String> = value
@@ -109,16 +357,36 @@ impl TryFrom<MultiPartFiles> for solidity::multi_part::MultiFileContent {
             .collect();
 
         let evm_version = if value.evm_version != "default" {
-            Some(EvmVersion::from_str(&value.evm_version).map_err(error::ErrorBadRequest)?)
+            Some(EvmVersion::from_str(&value.evm_version).map_err(ApiError::invalid_request)?)
         } else {
             None
         };
+        let remappings = value
+            .remappings
+            .iter()
+            .map(|remapping| Remapping::from_str(remapping))
+            .collect::<Result<_, _>>()
+            .map_err(|err| ApiError::invalid_request(format!("Invalid remapping: {err}")))?;
+        // Fully qualified library names (`file.sol:LibName`) should point to provided sources
+        let unknown_file = value
+            .contract_libraries
+            .iter()
+            .flatten()
+            .filter_map(|(name, _)| name.rsplit_once(':'))
+            .find(|(file, _)| !sources.contains_key(Path::new(file)));
+        if let Some((file, name)) = unknown_file {
+            return Err(ApiError::invalid_request(format!(
+                "Library {name} is declared in {file}, which is not among the sources"
+            )));
+        }
 
         Ok(Self {
             sources,
             evm_version,
             optimization_runs: value.optimization_runs,
             contract_libraries: value.contract_libraries,
+            remappings,
+            extra_outputs: value.extra_outputs,
         })
     }
 }
@@ -154,11 +422,18 @@ mod tests {
                     deployed_bytecode: "0x6001".into(),
                     creation_bytecode: Some("0x6001".into()),
                     compiler_version: "0.8.3".into(),
+                    contract_name: None,
+                    verbose: false,
+                    inject_dependencies: false,
+                    packages: vec![],
+                    create2: None,
                     content: MultiPartFiles {
                         sources: sources(&[("source.sol", "pragma")]),
                         evm_version: format!("{}", EvmVersion::London),
                         optimization_runs: Some(200),
                         contract_libraries: None,
+                        remappings: vec![],
+                        extra_outputs: vec![],
                     },
                 },
             ),
@@ -182,6 +457,11 @@ mod tests {
                     deployed_bytecode: "0x6001".into(),
                     creation_bytecode: Some("0x6001".into()),
                     compiler_version: "0.8.3".into(),
+                    contract_name: None,
+                    verbose: false,
+                    inject_dependencies: false,
+                    packages: vec![],
+                    create2: None,
                     content: MultiPartFiles {
                         sources: sources(&[
                             ("source.sol", "source"),
@@ -195,6 +475,8 @@ mod tests {
                             "Lib.sol".into(),
                             "0x1234567890123456789012345678901234567890".into(),
                         )])),
+                        remappings: vec![],
+                        extra_outputs: vec![],
                     },
                 },
             ),
@@ -209,6 +491,8 @@ mod tests {
             evm_version: "default".to_string(),
             optimization_runs: None,
             contract_libraries: None,
+            remappings: vec![],
+            extra_outputs: vec![],
         };
         let content = solidity::multi_part::MultiFileContent::try_from(multi_part)
             .expect("Structure is valid");
@@ -217,4 +501,57 @@ mod tests {
             "'default' should result in `None`"
         )
     }
+
+    #[test]
+    fn unlinked_creation_bytecode_is_linked() {
+        let placeholder = ethers_solc::utils::library_hash_placeholder("source.sol:L");
+        let request = |library: &str| VerificationRequest {
+            contract_address: Address::from_str("0xbebebebebebebebebebebebebebebebebebebebe").unwrap(),
+            creation_bytecode: Some(format!("0x6080__{placeholder}__")),
+            compiler_version: "v0.8.14+commit.80d49f37".to_string(),
+            block_number: None,
+            block_tag: None,
+            contract_name: None,
+            match_against: None,
+            verbose: false,
+            inject_dependencies: false,
+            packages: vec![],
+            create2: None,
+            content: MultiPartFiles {
+                sources: sources(&[("source.sol", "library L {}")]),
+                evm_version: "default".to_string(),
+                optimization_runs: None,
+                contract_libraries: Some(BTreeMap::from([(
+                    library.to_string(),
+                    "0xcafecafecafecafecafecafecafecafecafecafe".to_string(),
+                )])),
+                remappings: vec![],
+                extra_outputs: vec![],
+            },
+        };
+        let linked = solidity::multi_part::VerificationRequest::try_from(request("L"))
+            .expect("placeholder is linked");
+        assert_eq!(
+            Some(hex::decode("6080cafecafecafecafecafecafecafecafecafecafe").unwrap().into()),
+            linked.creation_bytecode
+        );
+        solidity::multi_part::VerificationRequest::try_from(request("M"))
+            .expect_err("placeholder is unresolved");
+    }
+
+    #[test]
+    fn qualified_libraries_should_point_to_sources() {
+        let multi_part = |library: &str| MultiPartFiles {
+            sources: sources(&[("source.sol", "library L {}")]),
+            evm_version: "default".to_string(),
+            optimization_runs: None,
+            contract_libraries: Some(BTreeMap::from([(library.to_string(), "0x12".to_string())])),
+            remappings: vec![],
+            extra_outputs: vec![],
+        };
+        solidity::multi_part::MultiFileContent::try_from(multi_part("source.sol:L"))
+            .expect("library file is provided");
+        solidity::multi_part::MultiFileContent::try_from(multi_part("other.sol:L"))
+            .expect_err("library file is not provided");
+    }
 }
@@ -0,0 +1,203 @@
+use super::solidity_multi_part::{self, VerificationContext, VerificationRequest};
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    auth::AdminAuth,
+    idempotency::IdempotencyCache,
+    persistence::ResultMiddlewares,
+    verification_response::VerificationResponse,
+};
+use actix_multipart::Multipart;
+use actix_web::{web, web::Json, HttpRequest};
+use futures::TryStreamExt;
+use serde_json::{Map, Value};
+use smart_contract_verifier::SolidityClient;
+use std::{collections::BTreeMap, path::PathBuf};
+use tracing::instrument;
+
+/// Name of the form part containing verification settings as json.
+const SETTINGS_PART: &str = "settings";
+
+/// Max total size (in bytes) of the uploaded form. The same limit is applied to json requests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaxRequestSize(pub usize);
+
+/// Verifies the contract with source files uploaded as `multipart/form-data`.
+///
+/// The `settings` part contains all fields of the multi-part verification request except sources
+/// as json. Each other part is a source file, which path is taken from the part file name.
+#[instrument(
+    skip(
+        http_request,
+        auth,
+        middlewares,
+        client,
+        max_size,
+        idempotency,
+        payload
+    ),
+    level = "debug"
+)]
+pub async fn verify(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
+    client: web::Data<SolidityClient>,
+    max_size: web::Data<MaxRequestSize>,
+    idempotency: web::Data<IdempotencyCache>,
+    payload: Multipart,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    // The form is read beforehand, so that its hash is checked against the idempotency key
+    let form = read_form(payload, max_size.0).await?;
+    idempotency
+        .run(&http_request, async {
+            let (settings, files) = form.into_parts()?;
+            let params = build_request(&settings, Map::new(), sources(files)?)?;
+
+            let context = VerificationContext {
+                create2: params.create2.clone(),
+                ..VerificationContext::new("multi-part-upload", params.verbose)
+            };
+            solidity_multi_part::verify_and_store(
+                auth.authenticate(&http_request),
+                &middlewares,
+                client,
+                params.try_into()?,
+                context,
+            )
+            .await
+        })
+        .await
+}
+
+/// Uploaded form consisting of the settings part and files.
+pub(super) struct Form {
+    settings: Option<Vec<u8>>,
+    /// Uploaded files along with their names.
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl Form {
+    /// Returns the settings and files, failing if the settings part is missing.
+    pub fn into_parts(self) -> Result<(Vec<u8>, Vec<(String, Vec<u8>)>), ApiError> {
+        let settings = self.settings.ok_or_else(|| {
+            ApiError::invalid_request(format!("Missing \"{SETTINGS_PART}\" part of the form"))
+        })?;
+        Ok((settings, self.files))
+    }
+}
+
+/// Reads the settings part and files, failing if their total size exceeds `max_size`.
+pub(super) async fn read_form(mut payload: Multipart, max_size: usize) -> Result<Form, ApiError> {
+    let mut size = 0;
+    let mut settings = None;
+    let mut files = Vec::new();
+    while let Some(mut field) = payload.try_next().await? {
+        let disposition = field.content_disposition();
+        let name = disposition.get_name().unwrap_or_default().to_string();
+        let file_name = disposition.get_filename().map(str::to_string);
+
+        let mut content = Vec::new();
+        while let Some(chunk) = field.try_next().await? {
+            size += chunk.len();
+            if size > max_size {
+                return Err(ApiError::new(
+                    ErrorCode::PayloadTooLarge,
+                    format!("Uploaded files exceed {max_size} bytes"),
+                ));
+            }
+            content.extend_from_slice(&chunk);
+        }
+
+        match file_name {
+            None if name == SETTINGS_PART => settings = Some(content),
+            None => {
+                return Err(ApiError::invalid_request(format!(
+                    "Part \"{name}\" is not a file"
+                )))
+            }
+            Some(file_name) => files.push((file_name, content)),
+        }
+    }
+    Ok(Form { settings, files })
+}
+
+/// Converts uploaded files into sources keyed by their paths.
+fn sources(files: Vec<(String, Vec<u8>)>) -> Result<BTreeMap<PathBuf, String>, ApiError> {
+    let mut sources = BTreeMap::new();
+    for (file_name, content) in files {
+        let content = String::from_utf8(content).map_err(|_| {
+            ApiError::invalid_request(format!("File {file_name} is not valid utf-8"))
+        })?;
+        if sources.insert(PathBuf::from(&file_name), content).is_some() {
+            return Err(ApiError::invalid_request(format!(
+                "File {file_name} is uploaded more than once"
+            )));
+        }
+    }
+    Ok(sources)
+}
+
+/// Combines the settings with uploaded sources into the multi-part verification request.
+/// Fields missing in the settings are taken from `defaults`.
+pub(super) fn build_request(
+    settings: &[u8],
+    defaults: Map<String, Value>,
+    sources: BTreeMap<PathBuf, String>,
+) -> Result<VerificationRequest, ApiError> {
+    let settings: Map<String, Value> = serde_json::from_slice(settings)
+        .map_err(|err| ApiError::invalid_request(format!("Invalid settings: {err}")))?;
+    let mut settings = defaults.into_iter().chain(settings).collect::<Map<_, _>>();
+    if sources.is_empty() {
+        return Err(ApiError::invalid_request(
+            "No source files have been uploaded",
+        ));
+    }
+    let sources = serde_json::to_value(sources).map_err(ApiError::internal)?;
+    if settings.insert("sources".to_string(), sources).is_some() {
+        return Err(ApiError::invalid_request(
+            "Sources should be uploaded as files, not as settings",
+        ));
+    }
+    serde_json::from_value(settings.into())
+        .map_err(|err| ApiError::invalid_request(format!("Invalid settings: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn sources() -> BTreeMap<PathBuf, String> {
+        BTreeMap::from([(
+            PathBuf::from("contracts/A.sol"),
+            "contract A {}".to_string(),
+        )])
+    }
+
+    #[test]
+    fn request_is_built_from_settings_and_files() {
+        let settings = r#"{
+            "contract_address": "0xbebebebebebebebebebebebebebebebebebebebe",
+            "compiler_version": "v0.8.14+commit.80d49f37",
+            "evm_version": "london",
+            "optimization_runs": 200
+        }"#;
+        let request = build_request(settings.as_bytes(), Map::new(), sources()).unwrap();
+        assert_eq!(sources(), request.content.sources);
+        assert_eq!(Some(200), request.content.optimization_runs);
+    }
+
+    #[test]
+    fn sources_in_settings_are_rejected() {
+        let settings = r#"{
+            "contract_address": "0xbebebebebebebebebebebebebebebebebebebebe",
+            "compiler_version": "v0.8.14+commit.80d49f37",
+            "evm_version": "london",
+            "sources": {}
+        }"#;
+        build_request(settings.as_bytes(), Map::new(), sources())
+            .expect_err("sources are duplicated");
+        build_request(b"{}", Map::new(), BTreeMap::new())
+            .expect_err("no sources have been uploaded");
+    }
+}
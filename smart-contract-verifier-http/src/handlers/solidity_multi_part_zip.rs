@@ -0,0 +1,105 @@
+use super::{
+    solidity_multi_part::{self, VerificationContext},
+    solidity_multi_part_upload::{self, MaxRequestSize},
+};
+use crate::{
+    api_error::ApiError, archive, auth::AdminAuth, idempotency::IdempotencyCache,
+    persistence::ResultMiddlewares, uploads::Uploads, verification_response::VerificationResponse,
+};
+use actix_multipart::Multipart;
+use actix_web::{web, web::Json, HttpRequest};
+use serde_json::{Map, Value};
+use smart_contract_verifier::SolidityClient;
+use tracing::instrument;
+
+/// Max total size of files extracted from the archive, relative to the max request size.
+const MAX_EXTRACTED_SIZE_RATIO: usize = 10;
+
+/// Verifies the contract with sources extracted from the zipped project uploaded
+/// as `multipart/form-data`. The form contains the `settings` part (see multi-part upload)
+/// and a single zip archive file. Large archives may be uploaded in chunks beforehand,
+/// in which case the form contains only the settings with `upload_id` of the archive.
+#[instrument(
+    skip(
+        http_request,
+        auth,
+        middlewares,
+        client,
+        max_size,
+        idempotency,
+        payload
+    ),
+    level = "debug"
+)]
+pub async fn verify(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
+    client: web::Data<SolidityClient>,
+    max_size: web::Data<MaxRequestSize>,
+    idempotency: web::Data<IdempotencyCache>,
+    payload: Multipart,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    // Chunked uploads are optional (see `uploads` settings)
+    let uploads = http_request.app_data::<web::Data<Uploads>>().cloned();
+    // The form is read beforehand, so that its hash is checked against the idempotency key
+    let form = solidity_multi_part_upload::read_form(payload, max_size.0).await?;
+    idempotency
+        .run(&http_request, async {
+            let (settings, mut files) = form.into_parts()?;
+            let (settings, upload_id) = take_upload_id(&settings)?;
+            let (archive, max_archive_size) = match (upload_id, files.pop(), files.is_empty()) {
+                (None, Some((_, archive)), true) => (archive, max_size.0),
+                (Some(upload_id), None, _) => {
+                    let uploads =
+                        uploads.ok_or_else(|| ApiError::invalid_request("Uploads are disabled"))?;
+                    (uploads.load(&upload_id).await?, uploads.max_size())
+                }
+                _ => {
+                    return Err(ApiError::invalid_request(
+                        "Exactly one zip archive should be uploaded",
+                    ))
+                }
+            };
+
+            let max_extracted_size = max_archive_size.saturating_mul(MAX_EXTRACTED_SIZE_RATIO);
+            // Decompression of large archives would block the workers otherwise
+            let project = tokio::task::spawn_blocking(move || {
+                archive::extract_project(&archive, max_extracted_size)
+            })
+            .await
+            .map_err(ApiError::internal)??;
+            let params = solidity_multi_part_upload::build_request(
+                &settings,
+                project.settings,
+                project.sources,
+            )?;
+
+            let context = VerificationContext {
+                create2: params.create2.clone(),
+                ..VerificationContext::new("zip", params.verbose)
+            };
+            solidity_multi_part::verify_and_store(
+                auth.authenticate(&http_request),
+                &middlewares,
+                client,
+                params.try_into()?,
+                context,
+            )
+            .await
+        })
+        .await
+}
+
+/// Removes `upload_id` of the archive uploaded beforehand from the settings.
+fn take_upload_id(settings: &[u8]) -> Result<(Vec<u8>, Option<String>), ApiError> {
+    let mut settings: Map<String, Value> = serde_json::from_slice(settings)
+        .map_err(|err| ApiError::invalid_request(format!("Invalid settings: {err}")))?;
+    let upload_id = match settings.remove("upload_id") {
+        None => None,
+        Some(Value::String(upload_id)) => Some(upload_id),
+        Some(_) => return Err(ApiError::invalid_request("`upload_id` should be a string")),
+    };
+    let settings = serde_json::to_vec(&settings).map_err(ApiError::internal)?;
+    Ok((settings, upload_id))
+}
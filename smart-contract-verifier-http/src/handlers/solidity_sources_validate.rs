@@ -0,0 +1,72 @@
+use crate::api_error::{ApiError, ErrorCode};
+use actix_web::{web, web::Json};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{solidity, SolidityClient, VerificationError, Version};
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+use tracing::instrument;
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ValidationRequest {
+    pub compiler_version: String,
+    pub sources: BTreeMap<PathBuf, String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ValidationResponse {
+    pub valid: bool,
+    /// Formatted syntax and analysis errors reported by the compiler.
+    pub errors: Vec<String>,
+}
+
+#[instrument(skip(client, params), level = "debug")]
+pub async fn validate(
+    client: web::Data<SolidityClient>,
+    params: Json<ValidationRequest>,
+) -> Result<Json<ValidationResponse>, ApiError> {
+    let request: solidity::syntax_check::ValidationRequest = params.into_inner().try_into()?;
+    let result = solidity::syntax_check::validate(client.into_inner(), request).await;
+
+    match result {
+        Ok(()) => Ok(Json(ValidationResponse {
+            valid: true,
+            errors: vec![],
+        })),
+        Err(VerificationError::Compilation(errors)) => Ok(Json(ValidationResponse {
+            valid: false,
+            errors,
+        })),
+        Err(err) => Err(err.into()),
+    }
+}
+
+impl TryFrom<ValidationRequest> for solidity::syntax_check::ValidationRequest {
+    type Error = ApiError;
+
+    fn try_from(value: ValidationRequest) -> Result<Self, Self::Error> {
+        let compiler_version = Version::from_str(&value.compiler_version).map_err(|err| {
+            ApiError::new(
+                ErrorCode::InvalidCompilerVersion,
+                format!("Invalid compiler version: {err}"),
+            )
+        })?;
+        Ok(Self {
+            compiler_version,
+            sources: value.sources,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_compiler_version() {
+        let request = ValidationRequest {
+            compiler_version: "0.8.14".into(),
+            sources: BTreeMap::new(),
+        };
+        solidity::syntax_check::ValidationRequest::try_from(request)
+            .expect_err("compiler version must include commit hash");
+    }
+}
@@ -1,76 +1,161 @@
-use crate::{metrics, verification_response::VerificationResponse, verification_response::VerificationResult, verified_contract_result::Verified_Contract_Result, DB, DisplayBytes};
-use actix_web::{error, web, web::Json};
+use crate::{api_error::ApiError, attempts::{self, FailureReason, VerificationAttempt}, auth::AdminAuth, compiler_inputs::ArchivedCompilerInput, create2::Create2Deployment, handlers::{admin_contracts::{self, Submission}, solidity_multi_part}, idempotency::IdempotencyCache, metrics, persistence::{ResultMiddlewares, VerifiedContract}, uploads::Uploads, verification_response::VerificationResponse, verification_response::VerificationResult, DB, DisplayBytes};
+use actix_web::{web, web::Json, HttpRequest};
 use anyhow::anyhow;
-use ethers_solc::CompilerInput;
 use serde::Deserialize;
-use smart_contract_verifier::{solidity, SolidityClient, VerificationError, Version};
-use std::str::FromStr;
+use smart_contract_verifier::{
+    solidity, Address, MatchAgainst, SolidityClient, VerificationError, Version,
+};
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
 use thiserror::Error;
 use tracing::instrument;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct VerificationRequest {
-    pub contract_address: String,
+    /// Checksummed (EIP-55) if provided in mixed case.
+    pub contract_address: Address,
     pub creation_bytecode: Option<String>,
     pub compiler_version: String,
+    /// (optional) Number of the block at which the deployed code of the contract is fetched.
+    /// Allows verifying self-destructed or redeployed contracts. The latest block by default.
+    pub block_number: Option<u64>,
+    /// (optional) Tag ("latest", "earliest" or "pending") of the block at which
+    /// the deployed code is fetched. Cannot be set along with `block_number`.
+    pub block_tag: Option<String>,
+    /// (optional) Name of the contract to verify, plain (`Name`) or fully qualified (`file.sol:Name`).
+    /// Required only if several compiled contracts match the bytecode.
+    pub contract_name: Option<String>,
+    /// (optional) Bytecode the compiled contract is required to match: "deployed", "creation" or "both".
+    /// By default, the creation bytecode is matched if provided, and the deployed one otherwise.
+    pub match_against: Option<MatchAgainst>,
+    /// If set, failed verifications return diagnostics for each candidate contract.
+    #[serde(default)]
+    pub verbose: bool,
+    /// (optional) Id of the upload the standard json input has been uploaded with,
+    /// if it is too large to be sent as `input`.
+    pub upload_id: Option<String>,
+    /// (optional) Deployment of the contract by a factory with `CREATE2`, which creation
+    /// transaction input is not the creation bytecode. Cannot be set along with `creation_bytecode`.
+    pub create2: Option<Create2Deployment>,
 
     #[serde(flatten)]
     pub content: StandardJson,
 }
 
-#[instrument(skip(client, params), level = "debug")]
+#[instrument(skip(http_request, auth, middlewares, client, idempotency, uploads, params), level = "debug")]
 pub async fn verify(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
     client: web::Data<SolidityClient>,
+    idempotency: web::Data<IdempotencyCache>,
+    uploads: Option<web::Data<Uploads>>,
     params: Json<VerificationRequest>,
-) -> Result<Json<VerificationResponse>, actix_web::Error> {
-    let request: smart_contract_verifier::solidity::standard_json::VerificationRequest = {
-        let request: Result<_, ParseError> = params.into_inner().try_into();
-        if let Err(err) = request {
-            match err {
-                ParseError::InvalidContent(_) => return Err(error::ErrorBadRequest(err)),
-                ParseError::BadRequest(_) => return Ok(Json(VerificationResponse::err(err))),
+) -> Result<Json<VerificationResponse>, ApiError> {
+    idempotency
+        .run(&http_request, async {
+            let mut params = params.into_inner();
+            let verbose = params.verbose;
+            if let Some(upload_id) = params.upload_id.take() {
+                let uploads = uploads.ok_or_else(|| ApiError::invalid_request("Uploads are disabled"))?;
+                if !params.content.input.is_empty() {
+                    return Err(ApiError::invalid_request("Only one of `input` and `upload_id` should be set"));
+                }
+                let input = uploads.load(&upload_id).await?;
+                params.content.input = String::from_utf8(input)
+                    .map_err(|_| ApiError::invalid_request(format!("Upload {upload_id} is not valid utf-8")))?;
+            }
+            // Init code of factory deployments is used as the creation bytecode
+            let create2 = params.create2.take();
+            if let Some(init_code) = create2.as_ref().map(|create2| create2.creation_bytecode(&params.contract_address)).transpose()?.flatten() {
+                if params.creation_bytecode.is_some() {
+                    return Err(ApiError::invalid_request("Only one of creation bytecode and CREATE2 init code may be provided"));
+                }
+                params.creation_bytecode = Some(init_code.to_string());
+            }
+            let request: smart_contract_verifier::solidity::standard_json::VerificationRequest = {
+                let request: Result<_, ParseError> = params.try_into();
+                if let Err(err) = request {
+                    match err {
+                        ParseError::InvalidContent(_) => return Err(ApiError::invalid_request(err)),
+                        ParseError::BadRequest(_) => return Ok(Json(VerificationResponse::err(err))),
+                    }
+                }
+                request.unwrap()
+            };
+
+            let database = DB::new().await.change_name(&middlewares.chain().namespace());
+            let submission = admin_contracts::check_submission(
+                &database,
+                auth.authenticate(&http_request),
+                &request.contract_address,
+            )
+            .await?;
+
+            let settings = serde_json::to_string(&request.content.input.settings).ok();
+            if submission == Submission::Rejected {
+                let message = format!("Contract {} is already verified", request.contract_address);
+                let attempt = VerificationAttempt::new(
+                    &request.contract_address,
+                    "json",
+                    &request.compiler_version,
+                    settings.clone(),
+                    Some((FailureReason::AlreadyVerified, message.clone())),
+                    Duration::default(),
+                );
+                attempts::record(&database, "solidity", attempt).await;
+                return Ok(Json(VerificationResponse::err(message)));
             }
-        }
-        request.unwrap()
-    };
-
-    let result = solidity::standard_json::verify(client.into_inner(), request.clone()).await;
-
-    if let Ok(verification_success) = result {
-        let response = VerificationResponse::ok(verification_success.into());
-        metrics::count_verify_contract("solidity", &response.status, "json");
-
-        //////////////////////////////////////////////////////////////////////////////
-        //////////// This is to record verification result to database ///////////////
-        //////////////////////////////////////////////////////////////////////////////
-
-        // Creation object of DB
-        let verify_database = DB::new().await;
-        // Change name of current database from DB
-        let vd = verify_database.change_name("evmos");
-        // Bring result of smart contract verification
-        let cvr = Verified_Contract_Result {
-            contract_address: request.contract_address.to_lowercase(),
-            result: response.result.clone().unwrap()
-        };
-        // Add to database called 'evmos'
-        vd.add_contract_verify_response(cvr).await;
 
-        ///////////////////////////////////// End ////////////////////////////////////
-        
-        return Ok(Json(response));
-    }
+            let started = Instant::now();
+            let result = solidity::standard_json::verify(client.into_inner(), request.clone()).await;
+            let result = result.and_then(|success| match create2.as_ref().and_then(|create2| create2.mismatch(&request.contract_address, &success)) {
+                Some(diagnostics) => Err(VerificationError::NoMatchingContracts(vec![diagnostics])),
+                None => Ok(success),
+            });
+
+            let attempt = VerificationAttempt::new(
+                &request.contract_address,
+                "json",
+                &request.compiler_version,
+                settings,
+                result.as_ref().err().map(attempts::failure),
+                started.elapsed(),
+            );
+            attempts::record(&database, "solidity", attempt).await;
+
+            if let Ok(verification_success) = result {
+                let debug_info = middlewares.debug_info(&request.contract_address, &verification_success);
+                let compiler_input = ArchivedCompilerInput::new(&request.contract_address, &verification_success, middlewares.encoding());
+                let response = VerificationResponse::ok(middlewares.verification_result(verification_success));
+                metrics::count_verify_contract("solidity", &response.status, "json");
+                let verified = VerifiedContract {
+                    contract_address: request.contract_address.to_string(),
+                    result: response.result.clone().unwrap(),
+                    repository: None,
+                    replaced_by: submission.replaced_by(),
+                    debug_info,
+                    compiler_input,
+                    job_id: None,
+                };
+                middlewares.run(&verified).await?;
+                return Ok(Json(response));
+            }
 
-    let err = result.unwrap_err();
-    match err {
-        VerificationError::Compilation(_)
-        | VerificationError::NoMatchingContracts
-        | VerificationError::CompilerVersionMismatch(_) => Ok(Json(VerificationResponse::err(err))),
-        VerificationError::Initialization(_) | VerificationError::VersionNotFound(_) => {
-            Err(error::ErrorBadRequest(err))
-        }
-        VerificationError::Internal(_) => Err(error::ErrorInternalServerError(err)),
-    }
+            let err = result.unwrap_err();
+            match err {
+                VerificationError::NoMatchingContracts(ref diagnostics) if verbose => Ok(Json(
+                    VerificationResponse::err(&err).with_diagnostics(diagnostics.clone()),
+                )),
+                VerificationError::Compilation(_)
+                | VerificationError::NoMatchingContracts(_)
+                | VerificationError::CompilerVersionMismatch(_) => Ok(Json(VerificationResponse::err(err))),
+                err => Err(err.into()),
+            }
+        })
+        .await
 }
 
 #[derive(Error, Debug)]
@@ -83,6 +168,8 @@ pub enum ParseError {
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct StandardJson {
+    /// Is empty if the input is referenced by `upload_id`.
+    #[serde(default)]
     input: String,
 }
 
@@ -101,10 +188,14 @@ impl TryFrom<VerificationRequest> for solidity::standard_json::VerificationReque
         };
         let compiler_version = Version::from_str(&value.compiler_version)
             .map_err(|err| anyhow!("Invalid compiler version: {}", err))?;
+        let block = solidity_multi_part::block_id(value.block_number, value.block_tag.as_deref())?;
         Ok(Self {
             contract_address,
             creation_bytecode,
             compiler_version,
+            block,
+            contract_name: value.contract_name,
+            match_against: value.match_against.unwrap_or_default(),
             content: value.content.try_into()?,
         })
     }
@@ -114,9 +205,13 @@ impl TryFrom<StandardJson> for solidity::standard_json::StandardJsonContent {
     type Error = ParseError;
 
     fn try_from(value: StandardJson) -> Result<Self, Self::Error> {
-        let input: CompilerInput = serde_json::from_str(&value.input)?;
+        let (input, extra_settings) =
+            solidity::extra_settings::split_input(serde_json::from_str(&value.input)?)?;
 
-        Ok(Self { input })
+        Ok(Self {
+            input,
+            extra_settings,
+        })
     }
 }
 
@@ -1,17 +1,34 @@
-use crate::{metrics, verification_response::VerificationResponse};
-use actix_web::{error, web, web::Json};
+use crate::{
+    api_error::ApiError,
+    attempts::{self, FailureReason, VerificationAttempt},
+    auth::AdminAuth,
+    handlers::admin_contracts::{self, Submission},
+    idempotency::IdempotencyCache,
+    metrics,
+    persistence::{ResultMiddlewares, VerifiedContract},
+    precompiles, signatures,
+    verification_response::VerificationResponse,
+    DB,
+};
+use actix_web::{web, web::Json, HttpRequest};
 use serde::{Deserialize, Serialize};
 use smart_contract_verifier::{
     sourcify::{api, Error},
-    SourcifyApiClient,
+    Address, SourcifyApiClient,
+};
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
 };
-use std::collections::BTreeMap;
 use tracing::instrument;
 
+const METHOD: &str = "sourcify";
+
 #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct ApiRequest {
-    pub address: String,
+    /// Checksummed (EIP-55) if provided in mixed case.
+    pub address: Address,
     pub chain: String,
     pub files: BTreeMap<String, String>,
     pub chosen_contract: Option<usize>,
@@ -28,22 +45,108 @@ impl From<ApiRequest> for api::VerificationRequest {
     }
 }
 
-#[instrument(skip(sourcify_client, params), level = "debug")]
+/// Contracts verified by Sourcify on the chain of the service are stored
+/// the same way as contracts verified by the service itself.
+#[instrument(
+    skip(http_request, auth, middlewares, sourcify_client, idempotency, params),
+    level = "debug"
+)]
 pub async fn verify(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
     sourcify_client: web::Data<SourcifyApiClient>,
+    idempotency: web::Data<IdempotencyCache>,
     params: Json<ApiRequest>,
-) -> Result<Json<VerificationResponse>, actix_web::Error> {
-    let request = params.into_inner().try_into()?;
-
-    let response = api::verify(sourcify_client.into_inner(), request).await;
-    let response = match response {
-        Ok(success) => Ok(VerificationResponse::ok(success.into())),
-        Err(err) => match err {
-            Error::Internal(err) => Err(error::ErrorInternalServerError(err)),
-            Error::Verification(err) => Ok(VerificationResponse::err(err)),
-            Error::Validation(err) => Err(error::ErrorBadRequest(err)),
-        },
-    }?;
-    metrics::count_verify_contract("solidity", &response.status, "sourcify");
-    Ok(Json(response))
+) -> Result<Json<VerificationResponse>, ApiError> {
+    idempotency
+        .run(&http_request, async {
+            precompiles::ensure_not_precompile(&params.address)?;
+            let params = params.into_inner();
+            let (contract_address, declared_version) =
+                (params.address.clone(), compiler_version(&params.files));
+            let chain = middlewares.chain();
+            let is_current_chain = params.chain == chain.chain_id.to_string();
+            let request = params.into();
+
+            let database = DB::new().await.change_name(&chain.namespace());
+            let mut submission = None;
+            if is_current_chain {
+                let checked = admin_contracts::check_submission(
+                    &database,
+                    auth.authenticate(&http_request),
+                    &contract_address,
+                )
+                .await?;
+                if checked == Submission::Rejected {
+                    let message = format!("Contract {contract_address} is already verified");
+                    let attempt = VerificationAttempt::new(
+                        &contract_address,
+                        METHOD,
+                        &declared_version,
+                        None,
+                        Some((FailureReason::AlreadyVerified, message.clone())),
+                        Duration::default(),
+                    );
+                    attempts::record(&database, "solidity", attempt).await;
+                    return Ok(Json(VerificationResponse::err(message)));
+                }
+                submission = Some(checked);
+            }
+
+            let started = Instant::now();
+            let response = api::verify(sourcify_client.into_inner(), request).await;
+            let attempt = VerificationAttempt::new(
+                &contract_address,
+                METHOD,
+                response
+                    .as_ref()
+                    .map(|success| success.compiler_version.clone())
+                    .unwrap_or(declared_version),
+                None,
+                response.as_ref().err().map(attempts::sourcify_failure),
+                started.elapsed(),
+            );
+            attempts::record(&database, "solidity", attempt).await;
+
+            let response = match response {
+                Ok(success) => Ok(VerificationResponse::ok(success.into())),
+                Err(err) => match err {
+                    Error::Verification(err) => Ok(VerificationResponse::err(err)),
+                    err => Err(ApiError::from(err)),
+                },
+            }?;
+            metrics::count_verify_contract("solidity", &response.status, METHOD);
+            match (&response.result, submission) {
+                (Some(result), Some(submission)) => {
+                    let verified = VerifiedContract {
+                        contract_address: contract_address.to_string(),
+                        result: result.clone(),
+                        repository: None,
+                        replaced_by: submission.replaced_by(),
+                        debug_info: None,
+                        compiler_input: None,
+                        job_id: None,
+                    };
+                    middlewares.run(&verified).await?;
+                }
+                (Some(result), None) => signatures::record(&database, result).await,
+                (None, _) => {}
+            }
+            Ok(Json(response))
+        })
+        .await
+}
+
+/// Compiler version declared in the metadata file of the request,
+/// recorded with failed attempts. Empty if the metadata file is missing.
+fn compiler_version(files: &BTreeMap<String, String>) -> String {
+    files
+        .iter()
+        .filter(|(name, _)| name.ends_with(".json"))
+        .find_map(|(_, content)| {
+            let metadata: serde_json::Value = serde_json::from_str(content).ok()?;
+            Some(metadata.pointer("/compiler/version")?.as_str()?.to_string())
+        })
+        .unwrap_or_default()
 }
@@ -0,0 +1,69 @@
+use crate::{
+    api_error::ApiError,
+    auth::AdminAuth,
+    chains::Chain,
+    stats::{self, CompilerStats, Stats},
+    DB,
+};
+use actix_web::{web, web::Json, HttpRequest};
+use serde::Deserialize;
+use tracing::instrument;
+
+const DEFAULT_DAYS: u32 = 30;
+const MAX_DAYS: u32 = 365;
+const DEFAULT_MONTHS: u32 = 12;
+const MAX_MONTHS: u32 = 120;
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct StatsQuery {
+    /// Number of the last days daily attempts are returned for.
+    pub days: Option<u32>,
+}
+
+#[instrument(skip(request, auth), level = "debug")]
+pub async fn get_stats(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    query: web::Query<StatsQuery>,
+    chain: Chain,
+) -> Result<Json<Stats>, ApiError> {
+    auth.require_admin(&request)?;
+    let days = query.days.unwrap_or(DEFAULT_DAYS);
+    if !(1..=MAX_DAYS).contains(&days) {
+        return Err(ApiError::invalid_request(format!(
+            "Number of days should be from 1 to {MAX_DAYS}"
+        )));
+    }
+    let database = DB::new().await.change_name(&chain.namespace());
+    let stats = stats::collect(&database, days)
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(Json(stats))
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct CompilerStatsQuery {
+    /// Number of the last months monthly usage is returned for.
+    pub months: Option<u32>,
+}
+
+#[instrument(skip(request, auth), level = "debug")]
+pub async fn get_compiler_stats(
+    request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    query: web::Query<CompilerStatsQuery>,
+    chain: Chain,
+) -> Result<Json<CompilerStats>, ApiError> {
+    auth.require_admin(&request)?;
+    let months = query.months.unwrap_or(DEFAULT_MONTHS);
+    if !(1..=MAX_MONTHS).contains(&months) {
+        return Err(ApiError::invalid_request(format!(
+            "Number of months should be from 1 to {MAX_MONTHS}"
+        )));
+    }
+    let database = DB::new().await.change_name(&chain.namespace());
+    let stats = stats::collect_compilers(&database, months)
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(Json(stats))
+}
@@ -1,5 +1,21 @@
-use actix_web::{HttpResponse, Responder};
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    warm_up::Readiness,
+};
+use actix_web::{web, HttpResponse, Responder};
 
 pub async fn status() -> impl Responder {
     HttpResponse::Ok().finish()
 }
+
+/// Responds with 503 Service Unavailable until compilers warm-up completes.
+pub async fn ready(readiness: web::Data<Readiness>) -> Result<HttpResponse, ApiError> {
+    if readiness.is_ready() {
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        Err(ApiError::new(
+            ErrorCode::NotReady,
+            "Compilers warm-up is in progress",
+        ))
+    }
+}
@@ -0,0 +1,178 @@
+use super::contract_read::stored_abi;
+use crate::{
+    abi::{self, DecodedParam},
+    api_error::ApiError,
+    blobs::BlobStore,
+    chains::{Chain, Chains},
+    signatures::{event_signature, function_signature},
+    DisplayBytes,
+};
+use actix_web::{web, web::Json};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::Address;
+use std::str::FromStr;
+use tracing::instrument;
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct AbiEncodeRequest {
+    /// Address of the verified contract the stored abi of which should be used.
+    pub address: Option<Address>,
+    /// Abi (or a single abi entry) to be used if no address is provided.
+    pub abi: Option<serde_json::Value>,
+    /// Function name, or its canonical signature if the function is overloaded.
+    pub function: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct AbiEncodeResponse {
+    /// Canonical signature of the encoded function.
+    pub function: String,
+    pub selector: DisplayBytes,
+    pub calldata: DisplayBytes,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodeKind {
+    /// Function call input; the function is resolved by the selector.
+    Calldata,
+    /// Data returned by the function.
+    Output,
+    /// Event log; the event is resolved by `topic0`.
+    Log,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct AbiDecodeRequest {
+    /// Address of the verified contract the stored abi of which should be used.
+    pub address: Option<Address>,
+    /// Abi (or a single abi entry) to be used if no address is provided.
+    pub abi: Option<serde_json::Value>,
+    pub kind: DecodeKind,
+    pub data: String,
+    /// Function the output has been returned by. Required to decode outputs.
+    pub function: Option<String>,
+    /// Log topics. Required to decode logs.
+    #[serde(default)]
+    pub topics: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct AbiDecodeResponse {
+    /// Canonical signature of the function or event the data has been decoded for.
+    pub signature: String,
+    pub params: Vec<DecodedParam>,
+}
+
+#[instrument(skip(chains, blobs, params), level = "debug")]
+pub async fn abi_encode(
+    chains: web::Data<Chains>,
+    blobs: Option<web::Data<BlobStore>>,
+    params: Json<AbiEncodeRequest>,
+) -> Result<Json<AbiEncodeResponse>, ApiError> {
+    let params = params.into_inner();
+    let contract = contract(chains.current(), blobs, params.address, params.abi).await?;
+
+    let function = abi::find_function(&contract, &params.function, Some(params.args.len()))
+        .map_err(ApiError::invalid_request)?;
+    let tokens = abi::tokenize(&function.inputs, &params.args).map_err(ApiError::invalid_request)?;
+    let calldata = function
+        .encode_input(&tokens)
+        .map_err(|err| ApiError::invalid_request(format!("Cannot encode arguments: {err}")))?;
+
+    Ok(Json(AbiEncodeResponse {
+        function: function_signature(function),
+        selector: DisplayBytes::from(function.short_signature()),
+        calldata: DisplayBytes::from(calldata),
+    }))
+}
+
+#[instrument(skip(chains, blobs, params), level = "debug")]
+pub async fn abi_decode(
+    chains: web::Data<Chains>,
+    blobs: Option<web::Data<BlobStore>>,
+    params: Json<AbiDecodeRequest>,
+) -> Result<Json<AbiDecodeResponse>, ApiError> {
+    let params = params.into_inner();
+    let contract = contract(chains.current(), blobs, params.address, params.abi).await?;
+    let data = DisplayBytes::from_str(&params.data)
+        .map_err(|err| ApiError::invalid_request(format!("Invalid data: {err:?}")))?
+        .0
+        .to_vec();
+
+    let response = match params.kind {
+        DecodeKind::Calldata => {
+            let function = abi::find_function_by_selector(&contract, &data).ok_or_else(|| {
+                ApiError::invalid_request("No function corresponds to the calldata selector")
+            })?;
+            let tokens = function
+                .decode_input(&data[4..])
+                .map_err(|err| ApiError::invalid_request(format!("Cannot decode calldata: {err}")))?;
+            AbiDecodeResponse {
+                signature: function_signature(function),
+                params: abi::decode_params(&function.inputs, tokens),
+            }
+        }
+        DecodeKind::Output => {
+            let function = params.function.ok_or_else(|| {
+                ApiError::invalid_request("Function is required to decode its output")
+            })?;
+            let function =
+                abi::find_function(&contract, &function, None).map_err(ApiError::invalid_request)?;
+            let tokens = function
+                .decode_output(&data)
+                .map_err(|err| ApiError::invalid_request(format!("Cannot decode output: {err}")))?;
+            AbiDecodeResponse {
+                signature: function_signature(function),
+                params: abi::decode_params(&function.outputs, tokens),
+            }
+        }
+        DecodeKind::Log => {
+            let topics = params
+                .topics
+                .iter()
+                .map(|topic| parse_topic(topic))
+                .collect::<Result<Vec<_>, _>>()?;
+            let event = topics
+                .first()
+                .and_then(|topic0| abi::find_event_by_topic(&contract, topic0))
+                .ok_or_else(|| ApiError::invalid_request("No event corresponds to the log topic0"))?;
+            let log = event
+                .parse_log(ethabi::RawLog { topics, data })
+                .map_err(|err| ApiError::invalid_request(format!("Cannot decode log: {err}")))?;
+            AbiDecodeResponse {
+                signature: event_signature(event),
+                params: abi::decode_log_params(event, log),
+            }
+        }
+    };
+    Ok(Json(response))
+}
+
+/// Loads the stored abi of the verified contract, or parses the provided one.
+async fn contract(
+    chain: &Chain,
+    blobs: Option<web::Data<BlobStore>>,
+    address: Option<Address>,
+    abi: Option<serde_json::Value>,
+) -> Result<ethabi::Contract, ApiError> {
+    match (address, abi) {
+        (Some(address), None) => stored_abi(chain, blobs, &address).await,
+        (None, Some(abi)) => abi::parse_abi(abi)
+            .map_err(|err| ApiError::invalid_request(format!("Invalid abi: {err}"))),
+        _ => Err(ApiError::invalid_request(
+            "Exactly one of address and abi must be provided",
+        )),
+    }
+}
+
+fn parse_topic(topic: &str) -> Result<ethabi::Hash, ApiError> {
+    let invalid_topic = || ApiError::invalid_request(format!("Invalid topic: {topic}"));
+    let bytes = DisplayBytes::from_str(topic).map_err(|_| invalid_topic())?;
+    if bytes.0.len() != 32 {
+        return Err(invalid_topic());
+    }
+    Ok(ethabi::Hash::from_slice(&bytes.0))
+}
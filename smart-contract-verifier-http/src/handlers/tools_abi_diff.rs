@@ -0,0 +1,200 @@
+use super::contract_read::stored_abi;
+use crate::{
+    api_error::ApiError,
+    blobs::BlobStore,
+    chains::Chains,
+    signatures::{event_signature, function_signature},
+};
+use actix_web::{web, web::Json};
+use ethabi::{Contract, Event, Function, Param, StateMutability};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::Address;
+use std::collections::BTreeMap;
+use tracing::instrument;
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct AbiDiffQuery {
+    /// Verified contract the abi is compared from (e.g., the previous implementation).
+    pub from: Address,
+    /// Verified contract the abi is compared to (e.g., the new implementation).
+    pub to: Address,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct AbiDiffResponse {
+    pub functions: EntriesDiff,
+    pub events: EntriesDiff,
+}
+
+/// Entries are matched by their canonical signatures, so that renamed entries
+/// (and entries with changed parameter types) are reported as removed and added.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct EntriesDiff {
+    /// Declarations of the entries present only in the `to` abi.
+    pub added: Vec<String>,
+    /// Declarations of the entries present only in the `from` abi.
+    pub removed: Vec<String>,
+    pub changed: Vec<ChangedEntry>,
+}
+
+/// Entry present in both abis with differing declarations
+/// (e.g., state mutability, return types, parameter names or indexed flags).
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ChangedEntry {
+    /// Canonical signature (e.g., "transfer(address,uint256)").
+    pub signature: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[instrument(skip(chains, blobs), level = "debug")]
+pub async fn abi_diff(
+    chains: web::Data<Chains>,
+    blobs: Option<web::Data<BlobStore>>,
+    query: web::Query<AbiDiffQuery>,
+) -> Result<Json<AbiDiffResponse>, ApiError> {
+    let query = query.into_inner();
+    let chain = chains.current();
+    let (from, to) = futures::try_join!(
+        stored_abi(chain, blobs.clone(), &query.from),
+        stored_abi(chain, blobs, &query.to)
+    )?;
+    Ok(Json(diff(&from, &to)))
+}
+
+fn diff(from: &Contract, to: &Contract) -> AbiDiffResponse {
+    AbiDiffResponse {
+        functions: diff_entries(
+            declarations(from.functions(), function_signature, function_declaration),
+            declarations(to.functions(), function_signature, function_declaration),
+        ),
+        events: diff_entries(
+            declarations(from.events(), event_signature, event_declaration),
+            declarations(to.events(), event_signature, event_declaration),
+        ),
+    }
+}
+
+/// Returns declarations of the entries by their canonical signatures.
+fn declarations<'a, T: 'a>(
+    entries: impl Iterator<Item = &'a T>,
+    signature: fn(&T) -> String,
+    declaration: fn(&T) -> String,
+) -> BTreeMap<String, String> {
+    entries
+        .map(|entry| (signature(entry), declaration(entry)))
+        .collect()
+}
+
+fn diff_entries(from: BTreeMap<String, String>, mut to: BTreeMap<String, String>) -> EntriesDiff {
+    let mut diff = EntriesDiff::default();
+    for (signature, from) in from {
+        match to.remove(&signature) {
+            None => diff.removed.push(from),
+            Some(to) if to != from => diff.changed.push(ChangedEntry {
+                signature,
+                from,
+                to,
+            }),
+            Some(_) => {}
+        }
+    }
+    diff.added = to.into_values().collect();
+    diff
+}
+
+/// Returns the solidity-like declaration of the function
+/// (e.g., "function balanceOf(address account) view returns (uint256)").
+fn function_declaration(function: &Function) -> String {
+    let mut declaration = format!("function {}({})", function.name, params(&function.inputs));
+    match function.state_mutability {
+        StateMutability::Pure => declaration.push_str(" pure"),
+        StateMutability::View => declaration.push_str(" view"),
+        StateMutability::Payable => declaration.push_str(" payable"),
+        StateMutability::NonPayable => {}
+    }
+    if !function.outputs.is_empty() {
+        declaration.push_str(&format!(" returns ({})", params(&function.outputs)));
+    }
+    declaration
+}
+
+/// Returns the solidity-like declaration of the event
+/// (e.g., "event Transfer(address indexed from, address indexed to, uint256 value)").
+fn event_declaration(event: &Event) -> String {
+    let inputs: Vec<_> = event
+        .inputs
+        .iter()
+        .map(|input| {
+            let indexed = if input.indexed { " indexed" } else { "" };
+            named(format!("{}{indexed}", input.kind), &input.name)
+        })
+        .collect();
+    let anonymous = if event.anonymous { " anonymous" } else { "" };
+    format!("event {}({}){anonymous}", event.name, inputs.join(", "))
+}
+
+fn params(params: &[Param]) -> String {
+    let params: Vec<_> = params
+        .iter()
+        .map(|param| named(param.kind.to_string(), &param.name))
+        .collect();
+    params.join(", ")
+}
+
+fn named(kind: String, name: &str) -> String {
+    match name.is_empty() {
+        true => kind,
+        false => format!("{kind} {name}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn contract(abi: serde_json::Value) -> Contract {
+        serde_json::from_value(abi).expect("valid abi")
+    }
+
+    #[test]
+    fn abis_are_compared() {
+        let from = contract(serde_json::json!([
+            {"type": "function", "name": "transfer", "stateMutability": "nonpayable",
+             "inputs": [{"name": "to", "type": "address"}, {"name": "amount", "type": "uint256"}],
+             "outputs": [{"name": "", "type": "bool"}]},
+            {"type": "function", "name": "pause", "stateMutability": "nonpayable", "inputs": [], "outputs": []},
+            {"type": "event", "name": "Transfer", "anonymous": false,
+             "inputs": [{"name": "from", "type": "address", "indexed": true},
+                        {"name": "to", "type": "address", "indexed": true},
+                        {"name": "value", "type": "uint256", "indexed": false}]}
+        ]));
+        let to = contract(serde_json::json!([
+            {"type": "function", "name": "transfer", "stateMutability": "payable",
+             "inputs": [{"name": "to", "type": "address"}, {"name": "amount", "type": "uint256"}],
+             "outputs": [{"name": "", "type": "bool"}]},
+            {"type": "function", "name": "version", "stateMutability": "pure", "inputs": [],
+             "outputs": [{"name": "", "type": "string"}]},
+            {"type": "event", "name": "Transfer", "anonymous": false,
+             "inputs": [{"name": "from", "type": "address", "indexed": true},
+                        {"name": "to", "type": "address", "indexed": true},
+                        {"name": "value", "type": "uint256", "indexed": false}]}
+        ]));
+
+        let expected = AbiDiffResponse {
+            functions: EntriesDiff {
+                added: vec!["function version() pure returns (string)".into()],
+                removed: vec!["function pause()".into()],
+                changed: vec![ChangedEntry {
+                    signature: "transfer(address,uint256)".into(),
+                    from: "function transfer(address to, uint256 amount) returns (bool)".into(),
+                    to: "function transfer(address to, uint256 amount) payable returns (bool)"
+                        .into(),
+                }],
+            },
+            events: EntriesDiff::default(),
+        };
+        assert_eq!(expected, diff(&from, &to));
+    }
+}
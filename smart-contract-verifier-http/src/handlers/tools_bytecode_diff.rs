@@ -0,0 +1,179 @@
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    DisplayBytes,
+};
+use actix_web::{web, web::Json};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{
+    rpc::BytecodeFetcher,
+    solidity,
+    tools::{bytecode_diff, disassembler},
+    Address,
+};
+use std::{collections::BTreeMap, ops::Range, str::FromStr};
+use tracing::instrument;
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct BytecodeDiffRequest {
+    /// Deployed bytecode resultant from local compilation.
+    pub local: String,
+    /// Bytecode to compare with. If omitted, code deployed at `address` is used.
+    pub remote: Option<String>,
+    pub address: Option<Address>,
+    /// `immutableReferences` from the compiler output for the compiled contract.
+    #[serde(default)]
+    pub immutable_references: BTreeMap<String, Vec<ImmutableReference>>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ImmutableReference {
+    pub start: usize,
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct BytecodeDiffResponse {
+    /// `true` if bytecodes differ only by metadata hashes and immutable values.
+    pub identical: bool,
+    pub local_metadata: Option<Span>,
+    pub remote_metadata: Option<Span>,
+    pub chunks: Vec<DiffChunk>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DiffChunk {
+    Equal {
+        local_offset: usize,
+        remote_offset: usize,
+        instructions: usize,
+    },
+    Changed {
+        local: Vec<Instruction>,
+        remote: Vec<Instruction>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Instruction {
+    pub offset: usize,
+    /// Opcode mnemonic, or hex value for undefined opcodes.
+    pub opcode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub push_data: Option<DisplayBytes>,
+}
+
+impl From<disassembler::Instruction> for Instruction {
+    fn from(instruction: disassembler::Instruction) -> Self {
+        Self {
+            offset: instruction.offset,
+            opcode: instruction
+                .name()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("0x{:02x}", instruction.opcode)),
+            push_data: (!instruction.push_data.is_empty())
+                .then(|| DisplayBytes::from(instruction.push_data)),
+        }
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+impl From<bytecode_diff::DiffChunk> for DiffChunk {
+    fn from(chunk: bytecode_diff::DiffChunk) -> Self {
+        match chunk {
+            bytecode_diff::DiffChunk::Equal {
+                local_offset,
+                remote_offset,
+                instructions,
+            } => DiffChunk::Equal {
+                local_offset,
+                remote_offset,
+                instructions,
+            },
+            bytecode_diff::DiffChunk::Changed { local, remote } => DiffChunk::Changed {
+                local: local.into_iter().map(Instruction::from).collect(),
+                remote: remote.into_iter().map(Instruction::from).collect(),
+            },
+        }
+    }
+}
+
+impl From<bytecode_diff::BytecodeDiff> for BytecodeDiffResponse {
+    fn from(diff: bytecode_diff::BytecodeDiff) -> Self {
+        Self {
+            identical: diff.is_identical(),
+            local_metadata: diff.local_metadata.map(Span::from),
+            remote_metadata: diff.remote_metadata.map(Span::from),
+            chunks: diff.chunks.into_iter().map(DiffChunk::from).collect(),
+        }
+    }
+}
+
+#[instrument(skip(fetcher, params), level = "debug")]
+pub async fn bytecode_diff(
+    fetcher: web::Data<BytecodeFetcher>,
+    params: Json<BytecodeDiffRequest>,
+) -> Result<Json<BytecodeDiffResponse>, ApiError> {
+    let params = params.into_inner();
+    let local = DisplayBytes::from_str(&params.local).map_err(|err| {
+        ApiError::new(
+            ErrorCode::InvalidBytecode,
+            format!("Invalid local bytecode: {err:?}"),
+        )
+    })?;
+
+    let remote = match (params.remote, params.address) {
+        (Some(remote), _) => remote,
+        (None, Some(address)) => solidity::multi_part::get_Code(&fetcher, &address)
+            .await
+            .map_err(ApiError::node)?
+            .filter(|code| !code.trim_start_matches("0x").is_empty())
+            .ok_or_else(|| {
+                ApiError::new(
+                    ErrorCode::ContractNotFound,
+                    format!("No code found at {address}"),
+                )
+            })?,
+        (None, None) => {
+            return Err(ApiError::invalid_request(
+                "Either remote bytecode or address must be provided",
+            ))
+        }
+    };
+    let remote = DisplayBytes::from_str(&remote).map_err(|err| {
+        ApiError::new(
+            ErrorCode::InvalidBytecode,
+            format!("Invalid remote bytecode: {err:?}"),
+        )
+    })?;
+
+    let immutable_references: Vec<_> = params
+        .immutable_references
+        .values()
+        .flatten()
+        .map(|reference| reference.start..reference.start + reference.length)
+        .collect();
+
+    // Alignment of the differing parts takes up to a few seconds,
+    // so that it would block the workers otherwise
+    let diff = tokio::task::spawn_blocking(move || {
+        bytecode_diff::diff(&local.0, &remote.0, &immutable_references)
+    })
+    .await
+    .map_err(ApiError::internal)?;
+    Ok(Json(diff.into()))
+}
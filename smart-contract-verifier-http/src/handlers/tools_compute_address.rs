@@ -0,0 +1,209 @@
+use super::solidity_compile::CompileRequest;
+use crate::{api_error::ApiError, DisplayBytes};
+use actix_web::{web, web::Json};
+use ethers_core::{
+    types::{H160, H256},
+    utils::{keccak256, to_checksum},
+};
+use ethers_solc::{artifacts::BytecodeObject, CompilerOutput};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{solidity, tools::contract_address, Address, SolidityClient};
+use std::str::FromStr;
+use tracing::instrument;
+
+/// Opcode the contract is deployed with.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Scheme {
+    /// The address depends on the deployer and its nonce.
+    Create,
+    /// The address depends on the factory, the salt and the init code.
+    Create2,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ComputeAddressRequest {
+    pub scheme: Scheme,
+    /// Account (`create`) or factory (`create2`) deploying the contract.
+    pub deployer: Address,
+    /// Nonce of the deployer. Required for `create`.
+    pub nonce: Option<u64>,
+    /// 32 bytes salt passed to `CREATE2`. Required for `create2`.
+    pub salt: Option<DisplayBytes>,
+    /// Exactly one of `init_code_hash`, `init_code` and `compilation` is required for `create2`.
+    pub init_code_hash: Option<DisplayBytes>,
+    pub init_code: Option<DisplayBytes>,
+    /// Standard json input the init code is compiled from.
+    pub compilation: Option<InitCodeCompilation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InitCodeCompilation {
+    /// Name of the deployed contract, plain (`Name`) or fully qualified (`file.sol:Name`).
+    pub contract_name: String,
+    /// ABI-encoded constructor arguments appended to the creation bytecode.
+    pub constructor_arguments: Option<DisplayBytes>,
+
+    #[serde(flatten)]
+    pub request: CompileRequest,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ComputeAddressResponse {
+    /// Checksummed (EIP-55).
+    pub address: String,
+    pub scheme: Scheme,
+    /// Hash of the init code the address has been derived from. Is returned for `create2` only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init_code_hash: Option<DisplayBytes>,
+}
+
+#[instrument(skip(client, params), level = "debug")]
+pub async fn compute_address(
+    client: Option<web::Data<SolidityClient>>,
+    params: Json<ComputeAddressRequest>,
+) -> Result<Json<ComputeAddressResponse>, ApiError> {
+    let params = params.into_inner();
+    let deployer =
+        H160::from_str(params.deployer.as_str()).expect("address is validated on creation");
+
+    let (address, init_code_hash) = match params.scheme {
+        Scheme::Create => {
+            let nonce = params
+                .nonce
+                .ok_or_else(|| ApiError::invalid_request("`nonce` is required for CREATE"))?;
+            (contract_address::create_address(deployer, nonce), None)
+        }
+        Scheme::Create2 => {
+            let salt = params
+                .salt
+                .ok_or_else(|| ApiError::invalid_request("`salt` is required for CREATE2"))?;
+            let salt = hash("salt", &salt)?;
+            let init_code_hash = match (params.init_code_hash, params.init_code, params.compilation)
+            {
+                (Some(init_code_hash), None, None) => hash("init code hash", &init_code_hash)?,
+                (None, Some(init_code), None) => H256(keccak256(&init_code.0)),
+                (None, None, Some(compilation)) => {
+                    let client = client.ok_or_else(|| {
+                        ApiError::invalid_request(
+                            "Compilation requires solidity verification to be enabled",
+                        )
+                    })?;
+                    H256(keccak256(compile_init_code(client, compilation).await?))
+                }
+                _ => {
+                    return Err(ApiError::invalid_request(
+                        "Exactly one of `init_code_hash`, `init_code` and `compilation` is required for CREATE2",
+                    ))
+                }
+            };
+            let address =
+                contract_address::create2_address_from_hash(deployer, salt, init_code_hash);
+            (address, Some(init_code_hash))
+        }
+    };
+
+    Ok(Json(ComputeAddressResponse {
+        address: to_checksum(&address, None),
+        scheme: params.scheme,
+        init_code_hash: init_code_hash.map(|hash| DisplayBytes::from(hash.as_bytes().to_vec())),
+    }))
+}
+
+fn hash(name: &str, value: &DisplayBytes) -> Result<H256, ApiError> {
+    match value.0.len() {
+        32 => Ok(H256::from_slice(&value.0)),
+        length => Err(ApiError::invalid_request(format!(
+            "{name} must be 32 bytes long, got {length} bytes"
+        ))),
+    }
+}
+
+/// Compiles the input and returns the creation bytecode of the contract
+/// followed by the constructor arguments.
+async fn compile_init_code(
+    client: web::Data<SolidityClient>,
+    compilation: InitCodeCompilation,
+) -> Result<Vec<u8>, ApiError> {
+    let request: solidity::compile::CompileRequest = compilation
+        .request
+        .try_into()
+        .map_err(ApiError::invalid_request)?;
+    let output = solidity::compile::compile(client.into_inner(), request).await?;
+    let mut init_code = creation_bytecode(output, &compilation.contract_name)?;
+    if let Some(constructor_arguments) = compilation.constructor_arguments {
+        init_code.extend_from_slice(&constructor_arguments.0);
+    }
+    Ok(init_code)
+}
+
+fn creation_bytecode(output: CompilerOutput, contract_name: &str) -> Result<Vec<u8>, ApiError> {
+    let (file, name) = match contract_name.rsplit_once(':') {
+        Some((file, name)) => (Some(file), name),
+        None => (None, contract_name),
+    };
+    let mut found = output
+        .contracts
+        .into_iter()
+        .filter(|(path, _)| file.map_or(true, |file| file == path))
+        .filter_map(|(path, mut contracts)| {
+            contracts.remove(name).map(|contract| (path, contract))
+        });
+    let (path, contract) = found.next().ok_or_else(|| {
+        ApiError::invalid_request(format!(
+            "Contract {contract_name} is not found in the sources"
+        ))
+    })?;
+    if found.next().is_some() {
+        return Err(ApiError::invalid_request(format!(
+            "Several contracts are named {name}; use the fully qualified name"
+        )));
+    }
+
+    let bytecode = contract
+        .evm
+        .and_then(|evm| evm.bytecode)
+        .map(|bytecode| bytecode.object);
+    match bytecode {
+        Some(BytecodeObject::Bytecode(bytecode)) if !bytecode.is_empty() => Ok(bytecode.to_vec()),
+        Some(BytecodeObject::Unlinked(_)) => Err(ApiError::invalid_request(format!(
+            "Creation bytecode of {path}:{name} has unlinked libraries; their addresses should be set in the input settings"
+        ))),
+        _ => Err(ApiError::invalid_request(format!(
+            "{path}:{name} has no creation bytecode (e.g., it is abstract or an interface)"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn creation_bytecode_is_found_by_name() {
+        let output = || -> CompilerOutput {
+            serde_json::from_value(serde_json::json!({
+                "sources": {},
+                "contracts": {
+                    "A.sol": {
+                        "A": { "evm": { "bytecode": { "object": "6080" } } },
+                        "I": { "evm": { "bytecode": { "object": "" } } }
+                    },
+                    "B.sol": {
+                        "A": { "evm": { "bytecode": { "object": "6001" } } }
+                    }
+                }
+            }))
+            .expect("valid compiler output")
+        };
+
+        assert_eq!(
+            vec![0x60, 0x01],
+            creation_bytecode(output(), "B.sol:A").unwrap()
+        );
+        assert!(creation_bytecode(output(), "A").is_err());
+        assert!(creation_bytecode(output(), "A.sol:I").is_err());
+        assert!(creation_bytecode(output(), "C").is_err());
+    }
+}
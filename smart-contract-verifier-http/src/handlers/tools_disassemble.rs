@@ -0,0 +1,97 @@
+use super::tools_bytecode_diff::Instruction;
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    DisplayBytes,
+};
+use actix_web::{web, web::Json};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{rpc::BytecodeFetcher, solidity, tools::disassembler, Address};
+use std::{collections::BTreeMap, str::FromStr};
+use tracing::instrument;
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct DisassembleQuery {
+    pub address: Address,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct DisassembleResponse {
+    pub address: Address,
+    pub code_size: usize,
+    pub instructions: Vec<AnnotatedInstruction>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct AnnotatedInstruction {
+    #[serde(flatten)]
+    pub instruction: Instruction,
+    /// Set for valid jump destinations.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub jump_destination: bool,
+    /// Set for PUSH instructions providing a jump destination to the following JUMP or JUMPI.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jump_target: Option<usize>,
+}
+
+#[instrument(skip(fetcher), level = "debug")]
+pub async fn disassemble(
+    fetcher: web::Data<BytecodeFetcher>,
+    query: web::Query<DisassembleQuery>,
+) -> Result<Json<DisassembleResponse>, ApiError> {
+    let address = query.into_inner().address;
+    let code = solidity::multi_part::get_Code(&fetcher, &address)
+        .await
+        .map_err(ApiError::node)?
+        .filter(|code| !code.trim_start_matches("0x").is_empty())
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::ContractNotFound,
+                format!("No code found at {address}"),
+            )
+        })?;
+    let code = DisplayBytes::from_str(&code)
+        .map_err(|err| ApiError::internal(format!("Invalid code: {err:?}")))?;
+
+    Ok(Json(DisassembleResponse {
+        address,
+        code_size: code.0.len(),
+        instructions: annotate(disassembler::disassemble(&code.0)),
+    }))
+}
+
+fn annotate(instructions: Vec<disassembler::Instruction>) -> Vec<AnnotatedInstruction> {
+    let destinations = disassembler::jump_destinations(&instructions);
+    let targets: BTreeMap<_, _> = disassembler::static_jump_targets(&instructions)
+        .into_iter()
+        .collect();
+    instructions
+        .into_iter()
+        .map(|instruction| AnnotatedInstruction {
+            jump_destination: destinations.contains(&instruction.offset),
+            jump_target: targets.get(&instruction.offset).copied(),
+            instruction: instruction.into(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn annotates_jumps() {
+        // PUSH1 0x03 JUMP JUMPDEST STOP
+        let instructions = annotate(disassembler::disassemble(&[0x60, 0x03, 0x56, 0x5b, 0x00]));
+        assert_eq!(
+            json!([
+                { "offset": 0, "opcode": "PUSH1", "push_data": "0x03", "jump_target": 3 },
+                { "offset": 2, "opcode": "JUMP" },
+                { "offset": 3, "opcode": "JUMPDEST", "jump_destination": true },
+                { "offset": 4, "opcode": "STOP" },
+            ]),
+            serde_json::to_value(instructions).unwrap()
+        );
+    }
+}
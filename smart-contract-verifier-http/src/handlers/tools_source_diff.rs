@@ -0,0 +1,78 @@
+use crate::{
+    api_error::ApiError,
+    blobs::BlobStore,
+    chains::Chains,
+    source_diff::{self, FileDiff},
+    DB,
+};
+use actix_web::{web, web::Json};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::Address;
+use std::collections::BTreeMap;
+use tracing::instrument;
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct SourceDiffQuery {
+    /// Verified contract the sources are compared from (e.g., the previous implementation).
+    pub from: Address,
+    /// Verified contract the sources are compared to (e.g., the new implementation).
+    pub to: Address,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SourceDiffResponse {
+    /// Files which have been added, removed or modified, sorted by their paths.
+    pub files: Vec<FileDiff>,
+    /// Number of files which are identical in both contracts.
+    pub unchanged: usize,
+}
+
+#[instrument(skip(chains, blobs), level = "debug")]
+pub async fn source_diff(
+    chains: web::Data<Chains>,
+    blobs: Option<web::Data<BlobStore>>,
+    query: web::Query<SourceDiffQuery>,
+) -> Result<Json<SourceDiffResponse>, ApiError> {
+    let query = query.into_inner();
+    let database = DB::new()
+        .await
+        .change_name(&chains.current().namespace())
+        .with_blobs(blobs.map(web::Data::into_inner));
+    let (from, to) = futures::try_join!(
+        stored_sources(&database, &query.from),
+        stored_sources(&database, &query.to)
+    )?;
+
+    // Alignment of the changed lines takes up to a few seconds per file,
+    // so that it would block the workers otherwise
+    let response = tokio::task::spawn_blocking(move || {
+        let files = source_diff::diff_sources(&from, &to);
+        let changed_in_both = files
+            .iter()
+            .filter(|file| from.contains_key(&file.path) && to.contains_key(&file.path))
+            .count();
+        let unchanged = from.keys().filter(|path| to.contains_key(*path)).count() - changed_in_both;
+        SourceDiffResponse { files, unchanged }
+    })
+    .await
+    .map_err(ApiError::internal)?;
+    Ok(Json(response))
+}
+
+/// Loads the sources of the verified contract, which may be kept in the blob store.
+async fn stored_sources(
+    database: &DB,
+    address: &Address,
+) -> Result<BTreeMap<String, String>, ApiError> {
+    let cvr = database
+        .find_contract_verify_response(address)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| ApiError::not_verified(address))?;
+    if cvr.result.sources.is_empty() {
+        return Err(ApiError::not_found(format!(
+            "Sources of contract {address} are not available"
+        )));
+    }
+    Ok(cvr.result.sources)
+}
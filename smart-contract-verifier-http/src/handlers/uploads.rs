@@ -0,0 +1,65 @@
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    auth::AdminAuth,
+    tenants,
+    uploads::{ChunkRange, UploadSlot, UploadStatus, Uploads},
+};
+use actix_web::{http::header, web, web::Json, HttpRequest};
+use std::str::FromStr;
+use tracing::instrument;
+
+/// Issues a slot the payload should be uploaded into before it is referenced
+/// by the verification request. Requires an admin or a tenant api key, as slots
+/// allow storing payloads of up to the max size each.
+#[instrument(skip(http_request, auth, uploads), level = "debug")]
+pub async fn create_upload(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    uploads: web::Data<Uploads>,
+) -> Result<Json<UploadSlot>, ApiError> {
+    if auth.authenticate(&http_request).is_none() && tenants::current().is_none() {
+        return Err(ApiError::new(
+            ErrorCode::Unauthorized,
+            "Valid api key is required to upload payloads",
+        ));
+    }
+    let connection = http_request.connection_info();
+    let endpoint_url = format!(
+        "{}://{}{}",
+        connection.scheme(),
+        connection.host(),
+        http_request.path()
+    );
+    Ok(Json(uploads.issue_slot(&endpoint_url)?))
+}
+
+/// Receives the whole payload, or a chunk of it if `Content-Range` header is set,
+/// so that uploads interrupted by flaky connections could be resumed.
+#[instrument(skip(http_request, uploads, payload), level = "debug")]
+pub async fn upload(
+    http_request: HttpRequest,
+    uploads: web::Data<Uploads>,
+    upload_id: web::Path<String>,
+    payload: web::Payload,
+) -> Result<Json<UploadStatus>, ApiError> {
+    let status = match http_request.headers().get(header::CONTENT_RANGE) {
+        None => uploads.save(&upload_id, payload).await?,
+        Some(range) => {
+            let range = range.to_str().map_err(|_| {
+                ApiError::invalid_request("Content-Range header is not valid ascii")
+            })?;
+            let range = ChunkRange::from_str(range)?;
+            uploads.save_chunk(&upload_id, range, payload).await?
+        }
+    };
+    Ok(Json(status))
+}
+
+/// Returns the number of bytes received, which the upload should be resumed from.
+#[instrument(skip(uploads), level = "debug")]
+pub async fn get_upload(
+    uploads: web::Data<Uploads>,
+    upload_id: web::Path<String>,
+) -> Result<Json<UploadStatus>, ApiError> {
+    Ok(Json(uploads.status(&upload_id).await?))
+}
@@ -0,0 +1,194 @@
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    auth::AdminAuth,
+    chains::Chains,
+    intents::{self, IntentState, VerificationIntent},
+    jobs::JobState,
+    settings::IntentsSettings,
+    tenants,
+    verification_response::VerificationResponse,
+    DisplayBytes, DB,
+};
+use actix_web::{web, web::Json, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::instrument;
+use url::Url;
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct IntentRequest {
+    /// Keccak256 of the init code the contract is expected to be created with
+    /// (the creation bytecode along with the encoded constructor arguments).
+    pub init_code_hash: DisplayBytes,
+    /// Url the verification result is posted to once the contract is deployed and verified.
+    pub webhook_url: Option<Url>,
+    /// Multi-part verification request without `contract_address`, `creation_bytecode` and `create2`.
+    #[serde(flatten)]
+    pub request: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct IntentSubmitted {
+    pub intent_id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct IntentStatus {
+    pub intent_id: String,
+    pub state: IntentState,
+    pub init_code_hash: String,
+    /// Is set for matched intents.
+    pub contract_address: Option<String>,
+    /// Verification job of the matched contract (see `GET /api/v1/jobs/{job_id}`).
+    pub job_id: Option<String>,
+    /// Is set for matched intents.
+    pub job_state: Option<JobState>,
+    pub verified: bool,
+    /// Is set once the verification job finishes.
+    pub response: Option<VerificationResponse>,
+    /// Error of the last attempt of the verification job.
+    pub error: Option<ApiError>,
+    pub submitted_at: DateTime<Utc>,
+    pub matched_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<VerificationIntent> for IntentStatus {
+    fn from(intent: VerificationIntent) -> Self {
+        let expires_at = intent.expires_at.to_chrono();
+        let state = match intent.state {
+            IntentState::Pending if expires_at <= Utc::now() => IntentState::Expired,
+            state => state,
+        };
+        Self {
+            intent_id: intent.id.to_hex(),
+            state,
+            init_code_hash: intent.init_code_hash,
+            contract_address: intent.contract_address,
+            job_id: intent.job_id.map(|id| id.to_hex()),
+            job_state: None,
+            verified: false,
+            response: None,
+            error: None,
+            submitted_at: intent.submitted_at,
+            matched_at: intent.matched_at,
+            expires_at,
+        }
+    }
+}
+
+/// Registers the multi-part verification request to be run once a contract
+/// is created with the init code of the given hash.
+///
+/// Requires an admin or a tenant api key, as intents are matched in the order they
+/// have been submitted, and their webhooks are requested by the service.
+#[instrument(skip(http_request, auth, settings, chains, params), level = "debug")]
+pub async fn submit_solidity_multi_part(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    settings: web::Data<IntentsSettings>,
+    chains: web::Data<Chains>,
+    params: Json<IntentRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let admin = auth.authenticate(&http_request);
+    if admin.is_none() && tenants::current().is_none() {
+        return Err(ApiError::new(
+            ErrorCode::Unauthorized,
+            "Valid api key is required to submit verification intents",
+        ));
+    }
+    let params = params.into_inner();
+    if let Some(webhook_url) = &params.webhook_url {
+        intents::validate_webhook_url(webhook_url).await?;
+    }
+    let ttl = chrono::Duration::seconds(settings.ttl_secs.get() as i64);
+    let intent = VerificationIntent::new(
+        &params.init_code_hash,
+        params.request,
+        params.webhook_url,
+        admin,
+        ttl,
+    )?;
+    let submitted = IntentSubmitted {
+        intent_id: intent.id.to_hex(),
+        expires_at: intent.expires_at.to_chrono(),
+    };
+    let database = DB::new().await.change_name(&chains.current().namespace());
+    database
+        .add_verification_intent(intent)
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(HttpResponse::Accepted().json(submitted))
+}
+
+#[instrument(skip(chains), level = "debug")]
+pub async fn get_intent(
+    chains: web::Data<Chains>,
+    intent_id: web::Path<String>,
+) -> Result<Json<IntentStatus>, ApiError> {
+    let not_found = || ApiError::not_found(format!("Verification intent {intent_id} is not found"));
+    let id = ObjectId::parse_str(intent_id.as_str()).map_err(|_| not_found())?;
+    let database = DB::new().await.change_name(&chains.current().namespace());
+    let intent = database
+        .find_verification_intent(id)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(not_found)?;
+    let job_id = intent.job_id;
+    let mut status = IntentStatus::from(intent);
+
+    let job = match job_id {
+        Some(job_id) => database
+            .find_verification_job(job_id)
+            .await
+            .map_err(ApiError::storage)?,
+        None => None,
+    };
+    if let Some(job) = job {
+        status.verified = intents::is_verified(&job);
+        status.job_state = Some(job.state);
+        status.response = job.response;
+        status.error = job.error;
+    }
+    Ok(Json(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn expired_intents_are_reported() {
+        let hash = DisplayBytes::from([0u8; 32].to_vec());
+        let request = serde_json::json!({
+            "compiler_version": "v0.8.14+commit.80d49f37",
+            "sources": { "A.sol": "contract A {}" },
+            "evm_version": "london",
+        });
+        let mut intent = VerificationIntent::new(
+            &hash,
+            request.as_object().unwrap().clone(),
+            None,
+            None,
+            chrono::Duration::days(1),
+        )
+        .unwrap();
+        assert_eq!(
+            IntentState::Pending,
+            IntentStatus::from(intent.clone()).state
+        );
+
+        intent.expires_at = bson::DateTime::from_chrono(Utc::now() - chrono::Duration::seconds(1));
+        assert_eq!(
+            IntentState::Expired,
+            IntentStatus::from(intent.clone()).state
+        );
+        intent.state = IntentState::Matched;
+        assert_eq!(IntentState::Matched, IntentStatus::from(intent).state);
+    }
+}
@@ -0,0 +1,291 @@
+use super::solidity_multi_part::VerificationRequest;
+use crate::{
+    api_error::ApiError,
+    auth::AdminAuth,
+    chains::Chains,
+    dependencies::PackageRegistry,
+    jobs::{self, JobKind, JobProgress, JobState, JobsConcurrency, VerificationJob},
+    verification_response::VerificationResponse,
+    DB,
+};
+use actix_web::{http::header, web, web::Bytes, web::Json, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use futures::{stream, Future, Stream};
+use mongodb::bson::oid::ObjectId;
+use serde::Serialize;
+use std::{convert::Infallible, sync::Arc, time::Duration};
+use tokio::time::Instant;
+use tracing::instrument;
+
+/// Interval the job is checked for changes at while its events are streamed.
+const EVENTS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Max time events of a single job are streamed for, so that streams of jobs
+/// which are stuck (or are queued for long) do not poll the database forever.
+const EVENTS_MAX_DURATION: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct JobSubmitted {
+    pub job_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct JobStatus {
+    pub job_id: String,
+    pub state: JobState,
+    pub contract_address: String,
+    pub attempts: u32,
+    /// Stage of the current attempt. Is set for running jobs.
+    pub progress: Option<JobProgress>,
+    /// Position of the job in the queue, starting from 1. Is set for queued jobs.
+    pub queue_position: Option<u64>,
+    /// Time a worker is expected to pick the job up at. Is set for queued jobs.
+    pub estimated_start_at: Option<DateTime<Utc>>,
+    /// Is set for finished jobs, and for failed ones if the contract has not been verified.
+    pub response: Option<VerificationResponse>,
+    /// Error of the last attempt.
+    pub error: Option<ApiError>,
+    pub submitted_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl From<VerificationJob> for JobStatus {
+    fn from(job: VerificationJob) -> Self {
+        Self {
+            job_id: job.id.to_hex(),
+            state: job.state,
+            contract_address: job.contract_address,
+            attempts: job.attempts,
+            progress: job.progress,
+            queue_position: None,
+            estimated_start_at: None,
+            response: job.response,
+            error: job.error,
+            submitted_at: job.submitted_at,
+            finished_at: job.finished_at,
+        }
+    }
+}
+
+/// Queues the multi-part verification request to be run by any instance of the service.
+#[instrument(skip(http_request, auth, registry, chains, params), level = "debug")]
+pub async fn submit_solidity_multi_part(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    registry: Option<web::Data<PackageRegistry>>,
+    chains: web::Data<Chains>,
+    params: Json<VerificationRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let params = params.into_inner();
+    // Invalid requests are rejected right away rather than failing in the background
+    params.validate(registry.as_deref())?;
+
+    let job = VerificationJob::new(
+        JobKind::SolidityMultiPart,
+        &params.contract_address,
+        &params.compiler_version,
+        &params,
+        auth.authenticate(&http_request),
+    )?;
+    let job_id = job.id.to_hex();
+    let database = DB::new().await.change_name(&chains.current().namespace());
+    database
+        .add_verification_job(job)
+        .await
+        .map_err(ApiError::storage)?;
+    Ok(HttpResponse::Accepted().json(JobSubmitted { job_id }))
+}
+
+#[instrument(skip(concurrency, chains), level = "debug")]
+pub async fn get_job(
+    concurrency: web::Data<JobsConcurrency>,
+    chains: web::Data<Chains>,
+    job_id: web::Path<String>,
+) -> Result<Json<JobStatus>, ApiError> {
+    let not_found = || ApiError::not_found(format!("Verification job {job_id} is not found"));
+    let id = ObjectId::parse_str(job_id.as_str()).map_err(|_| not_found())?;
+    let database = DB::new().await.change_name(&chains.current().namespace());
+    let job = database
+        .find_verification_job(id)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(not_found)?;
+    if job.state != JobState::Queued {
+        return Ok(Json(job.into()));
+    }
+
+    // The estimate is auxiliary, so the status is returned without it if it cannot be made
+    let estimate = jobs::estimate_queue(&database, &job, *concurrency)
+        .await
+        .map_err(|err| tracing::warn!("cannot estimate the queue of job {}: {}", job_id, err))
+        .ok();
+    let mut status = JobStatus::from(job);
+    status.queue_position = estimate.map(|estimate| estimate.position);
+    status.estimated_start_at = estimate.map(|estimate| estimate.estimated_start_at);
+    Ok(Json(status))
+}
+
+/// Streams the status of the job as server-sent events, one for each change of the job
+/// (its state or the stage of the verification), until the job is finished or failed,
+/// or the max duration of the stream has passed.
+#[instrument(skip(chains), level = "debug")]
+pub async fn job_events(
+    chains: web::Data<Chains>,
+    job_id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let not_found = || ApiError::not_found(format!("Verification job {job_id} is not found"));
+    let id = ObjectId::parse_str(job_id.as_str()).map_err(|_| not_found())?;
+    let database = Arc::new(DB::new().await.change_name(&chains.current().namespace()));
+    database
+        .find_verification_job(id)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(not_found)?;
+
+    let fetch = move || {
+        let database = database.clone();
+        async move { database.find_verification_job(id).await }
+    };
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(events(fetch, EVENTS_POLL_INTERVAL, EVENTS_MAX_DURATION)))
+}
+
+/// Polls the job with `fetch`, yielding an event whenever its status changes. The stream ends
+/// once the job is done, if it cannot be loaded anymore, or once `max_duration` has passed.
+fn events<F, Fut>(
+    fetch: F,
+    poll_interval: Duration,
+    max_duration: Duration,
+) -> impl Stream<Item = Result<Bytes, Infallible>>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<Option<VerificationJob>, String>>,
+{
+    let deadline = Instant::now() + max_duration;
+    stream::unfold(
+        (fetch, None::<JobStatus>),
+        move |(fetch, last)| async move {
+            if last
+                .as_ref()
+                .map_or(false, |status| status.state.is_final())
+            {
+                return None;
+            }
+            loop {
+                let status = match fetch().await {
+                    Ok(Some(job)) => JobStatus::from(job),
+                    Ok(None) => return None,
+                    Err(err) => {
+                        tracing::warn!("cannot load the verification job: {}", err);
+                        return None;
+                    }
+                };
+                if last.as_ref() != Some(&status) {
+                    return Some((Ok(event(&status)), (fetch, Some(status))));
+                }
+                if Instant::now() >= deadline {
+                    return None;
+                }
+                tokio::time::sleep(poll_interval).await;
+            }
+        },
+    )
+}
+
+/// Formats the status as a server-sent event named after the state of the job.
+fn event(status: &JobStatus) -> Bytes {
+    let data = serde_json::to_string(status).expect("job status should be serializable");
+    Bytes::from(format!(
+        "event: {}\ndata: {data}\n\n",
+        status.state.as_str()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use pretty_assertions::assert_eq;
+    use std::sync::Mutex;
+
+    #[actix_rt::test]
+    async fn events_are_streamed_for_changes_until_job_is_done() {
+        let job = VerificationJob::new(
+            JobKind::SolidityMultiPart,
+            "0xcafe",
+            "v0.8.14+commit.80d49f37",
+            &serde_json::json!({}),
+            None,
+        )
+        .unwrap();
+        let with = |state, progress| VerificationJob {
+            state,
+            progress,
+            ..job.clone()
+        };
+        let compiling = JobProgress::Compiling {
+            metadata_hash: "ipfs".to_string(),
+        };
+        // Unchanged snapshots are not streamed, and nothing is polled after the job is done
+        let snapshots = Mutex::new(vec![
+            with(JobState::Queued, None),
+            with(JobState::Queued, None),
+            with(JobState::Running, Some(JobProgress::DownloadingCompiler)),
+            with(JobState::Running, Some(compiling.clone())),
+            with(JobState::Running, Some(compiling)),
+            with(JobState::Running, Some(JobProgress::Storing)),
+            with(JobState::Finished, None),
+            with(JobState::Running, None),
+        ]);
+        snapshots.lock().unwrap().reverse();
+        let fetch = || {
+            let job = snapshots.lock().unwrap().pop();
+            async move { Ok(job) }
+        };
+
+        let events: Vec<_> = events(fetch, Duration::ZERO, Duration::from_secs(60))
+            .map(|event| String::from_utf8(event.unwrap().to_vec()).unwrap())
+            .collect()
+            .await;
+        let names: Vec<_> = events
+            .iter()
+            .map(|event| event.lines().next().unwrap())
+            .collect();
+        assert_eq!(
+            vec![
+                "event: queued",
+                "event: running",
+                "event: running",
+                "event: running",
+                "event: finished"
+            ],
+            names
+        );
+        assert!(events[2].contains(r#""progress":{"stage":"compiling","metadata_hash":"ipfs"}"#));
+        assert!(events.iter().all(|event| event.ends_with("\n\n")));
+        assert_eq!(1, snapshots.lock().unwrap().len());
+    }
+
+    #[actix_rt::test]
+    async fn events_are_not_streamed_after_max_duration() {
+        let job = VerificationJob::new(
+            JobKind::SolidityMultiPart,
+            "0xcafe",
+            "v0.8.14+commit.80d49f37",
+            &serde_json::json!({}),
+            None,
+        )
+        .unwrap();
+        // The job is never picked up
+        let fetch = || {
+            let job = job.clone();
+            async move { Ok(Some(job)) }
+        };
+
+        let events: Vec<_> = events(fetch, Duration::from_millis(1), Duration::from_millis(20))
+            .collect()
+            .await;
+        assert_eq!(1, events.len());
+    }
+}
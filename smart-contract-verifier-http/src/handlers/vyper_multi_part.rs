@@ -1,16 +1,51 @@
-use crate::{metrics, verification_response::VerificationResponse, DisplayBytes};
-use actix_web::{error, web, web::Json};
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    attempts::{self, FailureReason, VerificationAttempt},
+    auth::AdminAuth,
+    compiler_inputs::ArchivedCompilerInput,
+    handlers::admin_contracts::{self, Submission},
+    idempotency::IdempotencyCache,
+    metrics,
+    persistence::{ResultMiddlewares, VerifiedContract},
+    signatures,
+    verification_response::VerificationResponse,
+    DisplayBytes, DB,
+};
+use actix_web::{web, web::Json, HttpRequest};
 use ethers_solc::EvmVersion;
 use serde::Deserialize;
-use smart_contract_verifier::{vyper, VerificationError, Version, VyperClient};
-use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+use smart_contract_verifier::{
+    vyper, Address, MatchAgainst, VerificationError, Version, VyperClient,
+};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 use tracing::instrument;
 
+const METHOD: &str = "multi-part";
+
 #[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
 pub struct VerificationRequest {
+    /// (optional) Address the contract is deployed at. If set, the attempt is recorded
+    /// for the contract, and verification of precompiles and contracts which have
+    /// already been verified (unless requested by an admin) is rejected.
+    /// Checksummed (EIP-55) if provided in mixed case.
+    pub contract_address: Option<Address>,
     pub deployed_bytecode: String,
     pub creation_bytecode: Option<String>,
     pub compiler_version: String,
+    /// (optional) Name of the contract to verify, plain (`Name`) or fully qualified (`file.vy:Name`).
+    /// Required only if several compiled contracts match the bytecode.
+    pub contract_name: Option<String>,
+    /// (optional) Bytecode the compiled contract is required to match: "deployed", "creation" or "both".
+    /// By default, the creation bytecode is matched if provided, and the deployed one otherwise.
+    pub match_against: Option<MatchAgainst>,
+    /// If set, failed verifications return diagnostics for each candidate contract.
+    #[serde(default)]
+    pub verbose: bool,
 
     #[serde(flatten)]
     pub content: MultiPartFiles,
@@ -23,35 +58,49 @@ pub struct MultiPartFiles {
 }
 
 impl TryFrom<VerificationRequest> for vyper::multi_part::VerificationRequest {
-    type Error = actix_web::Error;
+    type Error = ApiError;
 
     fn try_from(value: VerificationRequest) -> Result<Self, Self::Error> {
         let deployed_bytecode = DisplayBytes::from_str(&value.deployed_bytecode)
-            .map_err(|err| error::ErrorBadRequest(format!("Invalid deployed bytecode: {err}")))?
+            .map_err(|err| {
+                ApiError::new(
+                    ErrorCode::InvalidBytecode,
+                    format!("Invalid deployed bytecode: {err}"),
+                )
+            })?
             .0;
         let creation_bytecode = match value.creation_bytecode {
             None => None,
             Some(creation_bytecode) => Some(
                 DisplayBytes::from_str(&creation_bytecode)
                     .map_err(|err| {
-                        error::ErrorBadRequest(format!("Invalid creation bytecode: {err:?}"))
+                        ApiError::new(
+                            ErrorCode::InvalidBytecode,
+                            format!("Invalid creation bytecode: {err:?}"),
+                        )
                     })?
                     .0,
             ),
         };
-        let compiler_version = Version::from_str(&value.compiler_version)
-            .map_err(|err| error::ErrorBadRequest(format!("Invalid compiler version: {err}")))?;
+        let compiler_version = Version::from_str(&value.compiler_version).map_err(|err| {
+            ApiError::new(
+                ErrorCode::InvalidCompilerVersion,
+                format!("Invalid compiler version: {err}"),
+            )
+        })?;
         Ok(Self {
             deployed_bytecode,
             creation_bytecode,
             compiler_version,
+            contract_name: value.contract_name,
+            match_against: value.match_against.unwrap_or_default(),
             content: value.content.try_into()?,
         })
     }
 }
 
 impl TryFrom<MultiPartFiles> for vyper::multi_part::MultiFileContent {
-    type Error = actix_web::Error;
+    type Error = ApiError;
 
     fn try_from(value: MultiPartFiles) -> Result<Self, Self::Error> {
         let sources: BTreeMap<PathBuf, String> = value
@@ -61,7 +110,7 @@ impl TryFrom<MultiPartFiles> for vyper::multi_part::MultiFileContent {
             .collect();
 
         let evm_version = if let Some(version) = value.evm_version {
-            Some(EvmVersion::from_str(&version).map_err(error::ErrorBadRequest)?)
+            Some(EvmVersion::from_str(&version).map_err(ApiError::invalid_request)?)
         } else {
             // default evm version for vyper
             Some(EvmVersion::Istanbul)
@@ -74,29 +123,133 @@ impl TryFrom<MultiPartFiles> for vyper::multi_part::MultiFileContent {
     }
 }
 
-#[instrument(skip(client, params), level = "debug")]
+/// Returns the attempt to be recorded for the contract. Attempts are recorded per contract,
+/// so none is returned for requests providing bytecodes only.
+fn verification_attempt(
+    contract_address: Option<&Address>,
+    request: &vyper::multi_part::VerificationRequest,
+    failure: Option<(FailureReason, String)>,
+    duration: Duration,
+) -> Option<VerificationAttempt> {
+    let settings = serde_json::json!({
+        "evm_version": request.content.evm_version.map(|version| version.to_string()),
+    })
+    .to_string();
+    Some(VerificationAttempt::new(
+        contract_address?,
+        METHOD,
+        &request.compiler_version,
+        Some(settings),
+        failure,
+        duration,
+    ))
+}
+
+#[instrument(
+    skip(http_request, auth, middlewares, client, idempotency, params),
+    level = "debug"
+)]
 pub async fn verify(
+    http_request: HttpRequest,
+    auth: web::Data<AdminAuth>,
+    middlewares: web::Data<ResultMiddlewares>,
     client: web::Data<VyperClient>,
+    idempotency: web::Data<IdempotencyCache>,
     params: Json<VerificationRequest>,
-) -> Result<Json<VerificationResponse>, actix_web::Error> {
-    let request = params.into_inner().try_into()?;
+) -> Result<Json<VerificationResponse>, ApiError> {
+    idempotency
+        .run(&http_request, async {
+            let params = params.into_inner();
+            let (contract_address, verbose) = (params.contract_address.clone(), params.verbose);
+            let request: vyper::multi_part::VerificationRequest = params.try_into()?;
 
-    let result = vyper::multi_part::verify(client.into_inner(), request).await;
+            let database = DB::new()
+                .await
+                .change_name(&middlewares.chain().namespace());
+            let mut submission = None;
+            if let Some(contract_address) = &contract_address {
+                let checked = admin_contracts::check_submission(
+                    &database,
+                    auth.authenticate(&http_request),
+                    contract_address,
+                )
+                .await?;
+                if checked == Submission::Rejected {
+                    let message = format!("Contract {contract_address} is already verified");
+                    let failure = (FailureReason::AlreadyVerified, message.clone());
+                    let attempt = verification_attempt(
+                        Some(contract_address),
+                        &request,
+                        Some(failure),
+                        Duration::default(),
+                    );
+                    if let Some(attempt) = attempt {
+                        attempts::record(&database, "vyper", attempt).await;
+                    }
+                    return Ok(Json(VerificationResponse::err(message)));
+                }
+                submission = Some(checked);
+            }
 
-    if let Ok(verification_success) = result {
-        let response = VerificationResponse::ok(verification_success.into());
-        metrics::count_verify_contract("vyper", &response.status, "multi-part");
-        return Ok(Json(response));
-    }
+            let started = Instant::now();
+            let result = vyper::multi_part::verify(client.into_inner(), request.clone()).await;
+            let failure = result.as_ref().err().map(attempts::failure);
+            let attempt = verification_attempt(
+                contract_address.as_ref(),
+                &request,
+                failure,
+                started.elapsed(),
+            );
+            if let Some(attempt) = attempt {
+                attempts::record(&database, "vyper", attempt).await;
+            }
 
-    let err = result.unwrap_err();
-    match err {
-        VerificationError::Compilation(_)
-        | VerificationError::NoMatchingContracts
-        | VerificationError::CompilerVersionMismatch(_) => Ok(Json(VerificationResponse::err(err))),
-        VerificationError::Initialization(_) | VerificationError::VersionNotFound(_) => {
-            Err(error::ErrorBadRequest(err))
-        }
-        VerificationError::Internal(_) => Err(error::ErrorInternalServerError(err)),
-    }
+            if let Ok(verification_success) = result {
+                let compiler_input = contract_address.as_ref().and_then(|contract_address| {
+                    ArchivedCompilerInput::new(
+                        &contract_address.to_string(),
+                        &verification_success,
+                        middlewares.encoding(),
+                    )
+                });
+                let response =
+                    VerificationResponse::ok(middlewares.verification_result(verification_success));
+                metrics::count_verify_contract("vyper", &response.status, METHOD);
+                match (contract_address, submission) {
+                    // Contracts are stored only if the address they are deployed at is known
+                    (Some(contract_address), Some(submission)) => {
+                        let verified = VerifiedContract {
+                            contract_address: contract_address.to_string(),
+                            result: response.result.clone().unwrap(),
+                            repository: None,
+                            replaced_by: submission.replaced_by(),
+                            debug_info: None,
+                            compiler_input,
+                            job_id: None,
+                        };
+                        middlewares.run(&verified).await?;
+                    }
+                    _ => {
+                        if let Some(result) = &response.result {
+                            signatures::record(&database, result).await;
+                        }
+                    }
+                }
+                return Ok(Json(response));
+            }
+
+            let err = result.unwrap_err();
+            match err {
+                VerificationError::NoMatchingContracts(ref diagnostics) if verbose => Ok(Json(
+                    VerificationResponse::err(&err).with_diagnostics(diagnostics.clone()),
+                )),
+                VerificationError::Compilation(_)
+                | VerificationError::NoMatchingContracts(_)
+                | VerificationError::CompilerVersionMismatch(_) => {
+                    Ok(Json(VerificationResponse::err(err)))
+                }
+                err => Err(err.into()),
+            }
+        })
+        .await
 }
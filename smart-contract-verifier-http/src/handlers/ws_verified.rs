@@ -0,0 +1,65 @@
+use crate::{
+    notifications::{Notifier, VerifiedNotification},
+    tenants,
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_ws::{Message, MessageStream, Session};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+/// Upgrades the connection to a websocket one, which receives a json message
+/// for every contract verified after the subscription by the tenant of the subscriber.
+pub async fn subscribe(
+    http_request: HttpRequest,
+    body: web::Payload,
+    notifier: web::Data<Notifier>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (response, session, messages) = actix_ws::handle(&http_request, body)?;
+    actix_web::rt::spawn(forward(
+        notifier.subscribe(),
+        tenants::current(),
+        session,
+        messages,
+    ));
+    Ok(response)
+}
+
+async fn forward(
+    mut notifications: Receiver<VerifiedNotification>,
+    tenant: Option<String>,
+    mut session: Session,
+    mut messages: MessageStream,
+) {
+    loop {
+        tokio::select! {
+            notification = notifications.recv() => match notification {
+                Ok(notification) if notification.tenant != tenant => {}
+                Ok(notification) => {
+                    let text = serde_json::to_string(&notification)
+                        .expect("notification should be serializable");
+                    if session.text(text).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "websocket subscriber has missed notifications")
+                }
+                Err(RecvError::Closed) => break,
+            },
+            message = messages.recv() => match message {
+                Some(Ok(Message::Ping(bytes))) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(reason))) => {
+                    let _ = session.close(reason).await;
+                    return;
+                }
+                // Subscribers are not expected to send anything else
+                Some(Ok(_)) => {}
+                Some(Err(_)) | None => break,
+            },
+        }
+    }
+    let _ = session.close(None).await;
+}
@@ -0,0 +1,95 @@
+use crate::{
+    request_id::REQUEST_ID_HEADER,
+    settings::{CorsSettings, SecurityHeadersSettings},
+    strict_mode::OUTCOME_HEADER,
+};
+use actix_cors::Cors;
+use actix_web::{http::header, middleware::DefaultHeaders};
+
+/// Builds the CORS middleware. Response headers read by frontends
+/// (`ETag`, the request id and the verification outcome) are exposed to them.
+pub fn cors(settings: &CorsSettings) -> Cors {
+    let mut cors = Cors::default()
+        .allowed_methods(settings.allowed_methods.iter().map(String::as_str))
+        .expose_headers([header::ETAG.as_str(), REQUEST_ID_HEADER, OUTCOME_HEADER])
+        .max_age(settings.max_age);
+    for origin in &settings.allowed_origins {
+        cors = match origin.as_str() {
+            "*" => cors.allow_any_origin(),
+            origin => cors.allowed_origin(origin),
+        };
+    }
+    match settings.allowed_headers.iter().any(|name| name == "*") {
+        true => cors.allow_any_header(),
+        false => cors.allowed_headers(settings.allowed_headers.iter().map(String::as_str)),
+    }
+}
+
+/// Headers preventing responses from being sniffed, framed or leaking the referrer.
+/// The api serves json only, so no content is allowed to be loaded by the responses.
+pub fn security_headers(settings: &SecurityHeadersSettings) -> DefaultHeaders {
+    let headers = DefaultHeaders::new()
+        .add((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .add((header::X_FRAME_OPTIONS, "DENY"))
+        .add((header::REFERRER_POLICY, "no-referrer"))
+        .add((
+            header::CONTENT_SECURITY_POLICY,
+            "default-src 'none'; frame-ancestors 'none'",
+        ));
+    match settings.hsts_max_age {
+        Some(max_age) => headers.add((
+            header::STRICT_TRANSPORT_SECURITY,
+            format!("max-age={max_age}; includeSubDomains"),
+        )),
+        None => headers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+
+    #[actix_rt::test]
+    async fn headers_are_added() {
+        let cors_settings = CorsSettings {
+            enabled: true,
+            allowed_origins: vec!["https://explorer.example.com".to_string()],
+            ..Default::default()
+        };
+        let security_settings = SecurityHeadersSettings {
+            enabled: true,
+            hsts_max_age: Some(31536000),
+        };
+        let app = test::init_service(
+            App::new()
+                .wrap(security_headers(&security_settings))
+                .wrap(cors(&cors_settings))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let request = test::TestRequest::get()
+            .uri("/")
+            .insert_header((header::ORIGIN, "https://explorer.example.com"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let headers = response.headers();
+        assert_eq!(
+            "https://explorer.example.com",
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN]
+        );
+        assert_eq!("nosniff", headers[header::X_CONTENT_TYPE_OPTIONS]);
+        assert!(headers.contains_key(header::STRICT_TRANSPORT_SECURITY));
+
+        let request = test::TestRequest::get()
+            .uri("/")
+            .insert_header((header::ORIGIN, "https://other.example.com"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::stats::number;
+use chrono::{DateTime, Utc};
+use mongodb::bson::Document;
+use serde::Serialize;
+
+/// Recently verified contract, as listed on the explorer home page.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RecentContract {
+    pub contract_address: String,
+    pub contract_name: String,
+    pub compiler_version: String,
+    /// Is missing for contracts stored before verification times were introduced.
+    pub verified_at: Option<DateTime<Utc>>,
+    /// Licenses declared by SPDX identifiers of the sources.
+    pub licenses: Vec<String>,
+}
+
+impl RecentContract {
+    /// Reads the contract from the projection of a stored verification result.
+    pub fn from_document(document: &Document) -> Self {
+        let empty = Document::new();
+        let result = document.get_document("result").unwrap_or(&empty);
+        let string = |document: &Document, field: &str| {
+            document.get_str(field).unwrap_or_default().to_string()
+        };
+        Self {
+            contract_address: string(document, "contract_address"),
+            contract_name: string(result, "contract_name"),
+            compiler_version: string(result, "compiler_version"),
+            verified_at: document
+                .get_str("verified_at")
+                .ok()
+                .and_then(|verified_at| verified_at.parse().ok()),
+            licenses: result
+                .get_array("licenses")
+                .map(|licenses| {
+                    licenses
+                        .iter()
+                        .filter_map(|license| license.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Number of stored contracts having the same metadata-stripped code.
+///
+/// Counts are incremented when contracts are stored and decremented when they are
+/// retired, so that the most duplicated codes are looked up without scanning all contracts.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DuplicatedCode {
+    pub code_hash: String,
+    pub count: u64,
+    /// The latest stored contract having the code.
+    pub contract_address: String,
+    pub contract_name: String,
+}
+
+impl DuplicatedCode {
+    /// Reads the counter, which may have been built by aggregation of stored contracts.
+    pub fn from_document(document: &Document) -> Self {
+        let string = |field: &str| document.get_str(field).unwrap_or_default().to_string();
+        Self {
+            code_hash: string("_id"),
+            count: number(document.get("count")).unwrap_or_default(),
+            contract_address: string("contract_address"),
+            contract_name: string("contract_name"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn highlights_are_read_from_documents() {
+        let document = doc! {
+            "contract_address": "0xcafe",
+            "result": {
+                "contract_name": "Token",
+                "compiler_version": "v0.8.14+commit.80d49f37",
+                "licenses": ["MIT"],
+            },
+            "verified_at": "2022-08-01T12:00:00Z",
+        };
+        assert_eq!(
+            RecentContract {
+                contract_address: "0xcafe".into(),
+                contract_name: "Token".into(),
+                compiler_version: "v0.8.14+commit.80d49f37".into(),
+                verified_at: Some("2022-08-01T12:00:00Z".parse().unwrap()),
+                licenses: vec!["MIT".into()],
+            },
+            RecentContract::from_document(&document)
+        );
+
+        // Counters built by aggregation are 32-bit
+        let document = doc! {
+            "_id": "0xbeef",
+            "count": 3,
+            "contract_address": "0xcafe",
+            "contract_name": "Clone",
+        };
+        assert_eq!(3, DuplicatedCode::from_document(&document).count);
+    }
+}
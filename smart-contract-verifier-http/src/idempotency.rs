@@ -0,0 +1,386 @@
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    auth::API_KEY_HEADER,
+    rate_limit::client_ip,
+    settings::IdempotencySettings,
+    verification_response::VerificationResponse,
+};
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Payload, Service, ServiceRequest, ServiceResponse},
+    error::PayloadError,
+    web::{Bytes, Json},
+    Error, HttpMessage, HttpRequest,
+};
+use futures::{future::LocalBoxFuture, Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex as AsyncMutex;
+
+/// The header clients provide unique keys of their verification requests through.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+const MAX_KEY_LENGTH: usize = 255;
+
+struct Entry {
+    /// Method and path of the request the key has been used for first.
+    route: String,
+    /// Hash of the body of the request the key has been used for first.
+    body_hash: Option<[u8; 32]>,
+    created_at: Instant,
+    /// Is locked while the request is verified, so that concurrent retries
+    /// wait for the original request instead of verifying the contract again.
+    response: AsyncMutex<Option<VerificationResponse>>,
+}
+
+/// Hash of the request body, computed while the body is read.
+#[derive(Clone, Default)]
+struct BodyHash(Rc<RefCell<Sha256>>);
+
+impl BodyHash {
+    fn finalize(&self) -> [u8; 32] {
+        self.0.borrow().clone().finalize().into()
+    }
+}
+
+/// Hashes bodies of the requests providing idempotency keys while they are read,
+/// so that keys reused for requests with other bodies are rejected.
+pub fn hash_body<S, B>(
+    mut request: ServiceRequest,
+    service: &S,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    if request.headers().contains_key(IDEMPOTENCY_KEY_HEADER) {
+        let hash = BodyHash::default();
+        let payload = request.take_payload().inspect({
+            let hash = hash.clone();
+            move |chunk| {
+                if let Ok(chunk) = chunk {
+                    hash.0.borrow_mut().update(chunk)
+                }
+            }
+        });
+        let payload: Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>> = Box::pin(payload);
+        request.set_payload(Payload::from(payload));
+        request.extensions_mut().insert(hash);
+    }
+    let response = service.call(request);
+    Box::pin(async move { Ok(response.await?.map_into_boxed_body()) })
+}
+
+/// Remembers responses to verification requests by their idempotency keys.
+/// Only successfully processed requests are remembered; failed ones (e.g., because
+/// the node has been unavailable) are processed again when retried.
+///
+/// Keys are scoped to the callers, so that a key used by one caller never returns
+/// the response to another one.
+pub struct IdempotencyCache {
+    enabled: bool,
+    ttl: Duration,
+    max_keys: usize,
+    trust_forwarded_headers: bool,
+    /// Entries keyed by the caller and the idempotency key.
+    entries: Mutex<HashMap<(String, String), Arc<Entry>>>,
+}
+
+impl IdempotencyCache {
+    /// Callers without api keys are told apart by their addresses, which are taken
+    /// from forwarded headers only if `trust_forwarded_headers` is set (see [`client_ip`]).
+    pub fn new(settings: &IdempotencySettings, trust_forwarded_headers: bool) -> Self {
+        Self {
+            enabled: settings.enabled,
+            ttl: Duration::from_secs(settings.ttl_secs),
+            max_keys: settings.max_keys,
+            trust_forwarded_headers,
+            entries: Default::default(),
+        }
+    }
+
+    /// Returns the response remembered for the idempotency key of the request, if any.
+    /// Otherwise, runs the verification and remembers its response.
+    ///
+    /// The request body must have been read by then (e.g., by the extractors of the handler),
+    /// as the key is checked to be used for the same body.
+    pub async fn run<F>(
+        &self,
+        request: &HttpRequest,
+        verify: F,
+    ) -> Result<Json<VerificationResponse>, ApiError>
+    where
+        F: Future<Output = Result<Json<VerificationResponse>, ApiError>>,
+    {
+        let key = match self.enabled {
+            true => idempotency_key(request)?,
+            false => None,
+        };
+        let route = format!("{} {}", request.method(), request.path());
+        let body_hash = request
+            .extensions()
+            .get::<BodyHash>()
+            .map(BodyHash::finalize);
+        let entry = match key {
+            Some(key) => self.entry(
+                (caller(request, self.trust_forwarded_headers), key),
+                route,
+                body_hash,
+            )?,
+            None => None,
+        };
+        let entry = match entry {
+            Some(entry) => entry,
+            None => return verify.await,
+        };
+
+        let mut response = entry.response.lock().await;
+        if let Some(response) = response.as_ref() {
+            tracing::debug!("response to the request with the same idempotency key is returned");
+            return Ok(Json(response.clone()));
+        }
+        let result = verify.await;
+        if let Ok(Json(verified)) = &result {
+            *response = Some(verified.clone());
+        }
+        result
+    }
+
+    /// Returns the entry of the key, inserting it if the key is new. Returns `None`
+    /// if the max number of keys is exceeded, and fails if the key has been used
+    /// for another route or another request body.
+    fn entry(
+        &self,
+        key: (String, String),
+        route: String,
+        body_hash: Option<[u8; 32]>,
+    ) -> Result<Option<Arc<Entry>>, ApiError> {
+        let mut entries = self.entries.lock().expect("lock is not poisoned");
+        entries.retain(|_, entry| entry.created_at.elapsed() < self.ttl);
+        if let Some(entry) = entries.get(&key) {
+            if entry.route != route {
+                return Err(ApiError::new(
+                    ErrorCode::Conflict,
+                    format!("Idempotency key has already been used for {}", entry.route),
+                ));
+            }
+            if entry.body_hash != body_hash {
+                return Err(ApiError::new(
+                    ErrorCode::Conflict,
+                    "Idempotency key has already been used for a request with another body",
+                ));
+            }
+            return Ok(Some(entry.clone()));
+        }
+        if entries.len() >= self.max_keys {
+            tracing::warn!("max number of idempotency keys exceeded; response is not remembered");
+            return Ok(None);
+        }
+        let entry = Arc::new(Entry {
+            route,
+            body_hash,
+            created_at: Instant::now(),
+            response: AsyncMutex::new(None),
+        });
+        entries.insert(key, entry.clone());
+        Ok(Some(entry))
+    }
+}
+
+/// Caller the keys of the request are scoped to: the api key (of a tenant or an admin)
+/// the request is made with, or the client address for requests without api keys.
+fn caller(request: &HttpRequest, trust_forwarded_headers: bool) -> String {
+    if let Some(api_key) = request.headers().get(API_KEY_HEADER) {
+        return format!("key:{}", hex::encode(Sha256::digest(api_key.as_bytes())));
+    }
+    let address = client_ip(request, trust_forwarded_headers).unwrap_or_default();
+    format!("ip:{address}")
+}
+
+fn idempotency_key(request: &HttpRequest) -> Result<Option<String>, ApiError> {
+    let value = match request.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => Ok(Some(key.to_string())),
+        _ => Err(ApiError::invalid_request(format!(
+            "Idempotency key should be a non-empty ascii string of at most {MAX_KEY_LENGTH} characters"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use pretty_assertions::assert_eq;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn request(path: &str, key: &str) -> HttpRequest {
+        TestRequest::post()
+            .uri(path)
+            .insert_header((IDEMPOTENCY_KEY_HEADER, key))
+            .to_http_request()
+    }
+
+    fn with_body(request: HttpRequest, body: &[u8]) -> HttpRequest {
+        let hash = BodyHash::default();
+        hash.0.borrow_mut().update(body);
+        request.extensions_mut().insert(hash);
+        request
+    }
+
+    #[actix_rt::test]
+    async fn repeated_requests_are_verified_once() {
+        let cache = IdempotencyCache::new(&IdempotencySettings::default(), false);
+        let verifications = AtomicUsize::new(0);
+        let verify = || async {
+            verifications.fetch_add(1, Ordering::SeqCst);
+            Ok(Json(VerificationResponse::err("no matching contracts")))
+        };
+
+        let path = "/api/v1/solidity/verify/multiple-files";
+        let first = cache.run(&request(path, "key"), verify()).await.unwrap();
+        let second = cache.run(&request(path, "key"), verify()).await.unwrap();
+        assert_eq!(first.into_inner(), second.into_inner());
+        assert_eq!(1, verifications.load(Ordering::SeqCst));
+
+        cache.run(&request(path, "other"), verify()).await.unwrap();
+        assert_eq!(2, verifications.load(Ordering::SeqCst));
+
+        let err = cache
+            .run(
+                &request("/api/v1/vyper/verify/multiple-files", "key"),
+                verify(),
+            )
+            .await
+            .expect_err("key is used for another route");
+        assert_eq!(ErrorCode::Conflict, err.code);
+    }
+
+    #[actix_rt::test]
+    async fn keys_are_scoped_to_callers_and_bodies() {
+        let cache = IdempotencyCache::new(&IdempotencySettings::default(), false);
+        let verifications = AtomicUsize::new(0);
+        let verify = || async {
+            verifications.fetch_add(1, Ordering::SeqCst);
+            Ok(Json(VerificationResponse::err("no matching contracts")))
+        };
+        let path = "/api/v1/solidity/verify/multiple-files";
+        let from = |address: &str, api_key: Option<&str>| {
+            let mut request = TestRequest::post()
+                .uri(path)
+                .peer_addr(address.parse().unwrap())
+                .insert_header((IDEMPOTENCY_KEY_HEADER, "key"));
+            if let Some(api_key) = api_key {
+                request = request.insert_header((API_KEY_HEADER, api_key));
+            }
+            with_body(request.to_http_request(), b"{}")
+        };
+
+        cache
+            .run(&from("10.0.0.1:1000", None), verify())
+            .await
+            .unwrap();
+        // Retries of the same client come from other ports
+        cache
+            .run(&from("10.0.0.1:2000", None), verify())
+            .await
+            .unwrap();
+        assert_eq!(1, verifications.load(Ordering::SeqCst));
+
+        cache
+            .run(&from("10.0.0.2:1000", None), verify())
+            .await
+            .unwrap();
+        cache
+            .run(&from("10.0.0.1:1000", Some("tenant-key")), verify())
+            .await
+            .unwrap();
+        cache
+            .run(&from("10.0.0.3:1000", Some("tenant-key")), verify())
+            .await
+            .unwrap();
+        assert_eq!(3, verifications.load(Ordering::SeqCst));
+
+        let request = with_body(
+            TestRequest::post()
+                .uri(path)
+                .peer_addr("10.0.0.1:1000".parse().unwrap())
+                .insert_header((IDEMPOTENCY_KEY_HEADER, "key"))
+                .to_http_request(),
+            b"{\"contract_address\":\"0xcafe\"}",
+        );
+        let err = cache
+            .run(&request, verify())
+            .await
+            .expect_err("key is used for another body");
+        assert_eq!(ErrorCode::Conflict, err.code);
+        assert_eq!(3, verifications.load(Ordering::SeqCst));
+    }
+
+    #[actix_rt::test]
+    async fn forwarded_headers_are_trusted_only_if_configured() {
+        let path = "/api/v1/solidity/verify/multiple-files";
+        let forwarded_for = |client: &str| {
+            let request = TestRequest::post()
+                .uri(path)
+                .peer_addr("10.0.0.1:1000".parse().unwrap())
+                .insert_header(("x-forwarded-for", client))
+                .insert_header((IDEMPOTENCY_KEY_HEADER, "key"))
+                .to_http_request();
+            with_body(request, b"{}")
+        };
+
+        for (trust_forwarded_headers, expected_verifications) in [(false, 1), (true, 2)] {
+            let cache =
+                IdempotencyCache::new(&IdempotencySettings::default(), trust_forwarded_headers);
+            let verifications = AtomicUsize::new(0);
+            let verify = || async {
+                verifications.fetch_add(1, Ordering::SeqCst);
+                Ok(Json(VerificationResponse::err("no matching contracts")))
+            };
+            cache
+                .run(&forwarded_for("192.168.0.1"), verify())
+                .await
+                .unwrap();
+            cache
+                .run(&forwarded_for("192.168.0.2"), verify())
+                .await
+                .unwrap();
+            assert_eq!(
+                expected_verifications,
+                verifications.load(Ordering::SeqCst),
+                "trust_forwarded_headers={trust_forwarded_headers}"
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn failed_requests_are_not_remembered() {
+        let cache = IdempotencyCache::new(&IdempotencySettings::default(), false);
+        let request = request("/api/v1/solidity/verify/standard-json", "key");
+        cache
+            .run(&request, async {
+                Err(ApiError::node("node is unavailable"))
+            })
+            .await
+            .expect_err("verification has failed");
+        let response = cache
+            .run(&request, async {
+                Ok(Json(VerificationResponse::err("no matching contracts")))
+            })
+            .await;
+        assert!(response.is_ok());
+    }
+}
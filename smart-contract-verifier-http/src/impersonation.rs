@@ -0,0 +1,79 @@
+use crate::settings::ImpersonationSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Attached to verified contracts which may impersonate well-known tokens or protocols
+/// (e.g., phishing copies of popular tokens with a backdoor).
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ImpersonationWarning {
+    /// Name of the well-known contract, as configured.
+    pub impersonated: String,
+    pub message: String,
+}
+
+/// Code hashes of canonical deployments of well-known contracts by their lowercase names.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CanonicalDeployments {
+    by_name: BTreeMap<String, (String, BTreeSet<String>)>,
+}
+
+impl CanonicalDeployments {
+    pub fn new(settings: &ImpersonationSettings) -> Self {
+        let by_name = settings
+            .canonical
+            .iter()
+            .map(|(name, code_hashes)| {
+                let code_hashes = code_hashes.iter().map(|hash| hash.to_lowercase()).collect();
+                (name.to_lowercase(), (name.clone(), code_hashes))
+            })
+            .collect();
+        Self { by_name }
+    }
+
+    /// Returns the warning if the verified contract is named as a well-known one,
+    /// but its code differs from the canonical deployments.
+    /// Contracts which code hash is unknown (e.g., verified by Sourcify) are not warned about.
+    pub fn check(
+        &self,
+        contract_name: &str,
+        code_hash: Option<&str>,
+    ) -> Option<ImpersonationWarning> {
+        let (impersonated, code_hashes) = self.by_name.get(&contract_name.to_lowercase())?;
+        let code_hash = code_hash?.to_lowercase();
+        (!code_hashes.contains(&code_hash)).then(|| ImpersonationWarning {
+            impersonated: impersonated.clone(),
+            message: format!(
+                "Possible impersonation: the contract is named as {impersonated}, \
+                but its code differs from the canonical {impersonated} deployments"
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn lookalikes_of_canonical_deployments_are_warned_about() {
+        let canonical_hash = format!("0x{}", "ab".repeat(32));
+        let settings = ImpersonationSettings {
+            enabled: true,
+            canonical: BTreeMap::from([("WETH9".to_string(), vec![canonical_hash.clone()])]),
+        };
+        let canonical = CanonicalDeployments::new(&settings);
+
+        assert_eq!(
+            None,
+            canonical.check(
+                "WETH9",
+                Some(&canonical_hash.to_uppercase().replace("0X", "0x"))
+            )
+        );
+        assert_eq!(None, canonical.check("Token", Some("0x1234")));
+        assert_eq!(None, canonical.check("WETH9", None));
+        let warning = canonical.check("weth9", Some("0x1234")).unwrap();
+        assert_eq!("WETH9", warning.impersonated);
+    }
+}
@@ -0,0 +1,376 @@
+use crate::{
+    api_error::ApiError,
+    git_import::{self, GitImportError},
+    handlers::solidity_multi_part::VerificationRequest,
+    jobs::{JobKind, JobState, VerificationJob},
+    verification_response::{VerificationResponse, VerificationStatus},
+    DisplayBytes, DB,
+};
+use chrono::{DateTime, Utc};
+use ethers_core::utils::keccak256;
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{str::FromStr, time::Duration};
+use url::Url;
+
+/// Fields of the multi-part request which are set once the deployment is observed.
+const DEPLOYMENT_FIELDS: &[&str] = &["contract_address", "creation_bytecode", "create2"];
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentState {
+    /// Waits for a contract to be created with the init code.
+    Pending,
+    /// A contract has been created with the init code, and its verification job has been queued.
+    Matched,
+    /// Is never stored: pending intents are reported as expired once `expires_at` passes.
+    Expired,
+}
+
+/// Sources and settings of a contract registered before it is deployed. Once a contract
+/// is created with the init code of the given hash, it is verified as a multi-part job.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct VerificationIntent {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// `0x` prefixed lowercase hex of keccak256 of the init code.
+    pub init_code_hash: String,
+    /// Json body of the multi-part request without the deployment fields.
+    pub request: String,
+    pub compiler_version: String,
+    /// Url notified once the verification job of the matched contract completes.
+    pub webhook_url: Option<Url>,
+    pub admin: Option<String>,
+    pub state: IntentState,
+    pub contract_address: Option<String>,
+    pub job_id: Option<ObjectId>,
+    pub submitted_at: DateTime<Utc>,
+    pub matched_at: Option<DateTime<Utc>>,
+    pub expires_at: bson::DateTime,
+}
+
+impl VerificationIntent {
+    /// Validates the request, which should not contain the deployment fields.
+    pub fn new(
+        init_code_hash: &DisplayBytes,
+        request: Map<String, Value>,
+        webhook_url: Option<Url>,
+        admin: Option<String>,
+        ttl: chrono::Duration,
+    ) -> Result<Self, ApiError> {
+        if init_code_hash.0.len() != 32 {
+            return Err(ApiError::invalid_request(format!(
+                "init_code_hash must be 32 bytes long, got {} bytes",
+                init_code_hash.0.len()
+            )));
+        }
+        if let Some(field) = DEPLOYMENT_FIELDS
+            .iter()
+            .find(|field| request.contains_key(**field))
+        {
+            return Err(ApiError::invalid_request(format!(
+                "`{field}` cannot be set in intents; it is taken from the observed deployment"
+            )));
+        }
+        // Invalid requests are rejected right away rather than failing once the contract is deployed
+        let placeholder =
+            deployment_request(&request, "0x0000000000000000000000000000000000000000", "0x")?;
+        let compiler_version = placeholder.compiler_version.clone();
+        let _: smart_contract_verifier::solidity::multi_part::VerificationRequest =
+            placeholder.try_into()?;
+
+        let now = Utc::now();
+        Ok(Self {
+            id: ObjectId::new(),
+            init_code_hash: format!("0x{}", hex::encode(&init_code_hash.0)),
+            request: serde_json::to_string(&request).map_err(ApiError::internal)?,
+            compiler_version,
+            webhook_url,
+            admin,
+            state: IntentState::Pending,
+            contract_address: None,
+            job_id: None,
+            submitted_at: now,
+            matched_at: None,
+            expires_at: bson::DateTime::from_chrono(now + ttl),
+        })
+    }
+}
+
+/// Returns the multi-part request verifying the contract deployed with the init code.
+fn deployment_request(
+    request: &Map<String, Value>,
+    contract_address: &str,
+    init_code: &str,
+) -> Result<VerificationRequest, ApiError> {
+    let mut request = request.clone();
+    request.insert("contract_address".into(), contract_address.into());
+    request.insert("creation_bytecode".into(), init_code.into());
+    serde_json::from_value(Value::Object(request))
+        .map_err(|err| ApiError::invalid_request(format!("Invalid verification request: {err}")))
+}
+
+/// Returns `0x` prefixed lowercase hex of keccak256 of the hex encoded init code.
+pub fn init_code_hash(init_code: &str) -> Option<String> {
+    let init_code = DisplayBytes::from_str(init_code).ok()?;
+    Some(format!("0x{}", hex::encode(keccak256(&init_code.0))))
+}
+
+/// Checks that the webhook url is a public https url, as the one of imported git repositories.
+pub async fn validate_webhook_url(webhook_url: &Url) -> Result<(), ApiError> {
+    match git_import::validate_url(webhook_url).await {
+        Ok(_) => Ok(()),
+        Err(GitImportError::InvalidUrl) => Err(ApiError::invalid_request(
+            "Webhook url should be a public https url",
+        )),
+        Err(err) => Err(ApiError::invalid_request(format!(
+            "Invalid webhook url: {err}"
+        ))),
+    }
+}
+
+/// Matches the contract created with the init code against pending intents (the earliest
+/// submitted first) and queues verification of the contract. Returns whether a pending
+/// intent has been matched.
+///
+/// The intent is marked as matched only once the verification job has been queued.
+pub async fn match_deployment(
+    database: &DB,
+    contract_address: &str,
+    init_code: &str,
+) -> Result<bool, String> {
+    let init_code_hash = match init_code_hash(init_code) {
+        Some(init_code_hash) => init_code_hash,
+        None => return Ok(false),
+    };
+    let intents = database
+        .find_pending_verification_intents(&init_code_hash)
+        .await?;
+    for intent in intents {
+        let job = match intent_job(&intent, contract_address, init_code) {
+            Ok(job) => job,
+            Err(err) => {
+                tracing::warn!(
+                    intent_id = %intent.id,
+                    "cannot queue verification of the intent: {}",
+                    err
+                );
+                continue;
+            }
+        };
+        let job_id = job.id;
+        database.add_verification_job(job).await?;
+        if !database
+            .match_verification_intent(intent.id, contract_address, job_id)
+            .await?
+        {
+            // The intent has been matched by another instance meanwhile
+            database.delete_queued_verification_job(job_id).await?;
+            continue;
+        }
+        tracing::info!(
+            intent_id = %intent.id,
+            %job_id,
+            "contract {} matches the verification intent",
+            contract_address
+        );
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+fn intent_job(
+    intent: &VerificationIntent,
+    contract_address: &str,
+    init_code: &str,
+) -> Result<VerificationJob, ApiError> {
+    let request: Map<String, Value> =
+        serde_json::from_str(&intent.request).map_err(ApiError::internal)?;
+    let request = deployment_request(&request, contract_address, init_code)?;
+    let mut job = VerificationJob::new(
+        JobKind::SolidityMultiPart,
+        contract_address,
+        &intent.compiler_version,
+        &request,
+        intent.admin.clone(),
+    )?;
+    job.intent_id = Some(intent.id);
+    Ok(job)
+}
+
+/// Body of the request sent to the webhook of the intent.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct IntentNotification {
+    pub intent_id: String,
+    pub job_id: String,
+    pub contract_address: String,
+    pub verified: bool,
+    pub response: Option<VerificationResponse>,
+    pub error: Option<ApiError>,
+}
+
+impl From<&VerificationJob> for IntentNotification {
+    fn from(job: &VerificationJob) -> Self {
+        Self {
+            intent_id: job.intent_id.map(|id| id.to_hex()).unwrap_or_default(),
+            job_id: job.id.to_hex(),
+            contract_address: job.contract_address.clone(),
+            verified: is_verified(job),
+            response: job.response.clone(),
+            error: job.error.clone(),
+        }
+    }
+}
+
+pub fn is_verified(job: &VerificationJob) -> bool {
+    job.state == JobState::Finished
+        && job
+            .response
+            .as_ref()
+            .map_or(false, |response| response.status == VerificationStatus::Ok)
+}
+
+/// Notifies the webhook of the intent the completed job has been queued for, if any.
+///
+/// If the contract has not been verified, it is matched against the next pending intent
+/// with the same init code hash, so that an intent with wrong sources submitted first
+/// does not prevent the contract from being verified with the following ones.
+pub async fn complete(database: &DB, job_id: ObjectId) {
+    let job = database
+        .find_verification_job(job_id)
+        .await
+        .and_then(|job| job.ok_or_else(|| "the job is not found".to_string()));
+    let job = match job {
+        Ok(job) => job,
+        Err(err) => {
+            tracing::warn!("cannot load completed verification job {}: {}", job_id, err);
+            return;
+        }
+    };
+    notify_completion(database, &job).await;
+    if !is_verified(&job) {
+        if let Err(err) = match_next(database, &job).await {
+            tracing::error!(
+                "cannot match the contract of job {} against the next intent: {}",
+                job_id,
+                err
+            );
+        }
+    }
+}
+
+async fn match_next(database: &DB, job: &VerificationJob) -> Result<bool, String> {
+    let request: Map<String, Value> =
+        serde_json::from_str(&job.request).map_err(|err| err.to_string())?;
+    match request.get("creation_bytecode").and_then(Value::as_str) {
+        Some(init_code) => match_deployment(database, &job.contract_address, init_code).await,
+        None => Ok(false),
+    }
+}
+
+async fn notify_completion(database: &DB, job: &VerificationJob) {
+    let result = async {
+        let intent = match job.intent_id {
+            Some(intent_id) => database
+                .find_verification_intent(intent_id)
+                .await?
+                .ok_or("the intent is not found")?,
+            None => return Ok(()),
+        };
+        let webhook_url = match intent.webhook_url {
+            Some(webhook_url) => webhook_url,
+            None => return Ok(()),
+        };
+        // The host is resolved again, and is requested at the validated addresses only
+        let addresses = git_import::validate_url(&webhook_url)
+            .await
+            .map_err(|err| err.to_string())?;
+        git_import::pinned_client(&webhook_url, &addresses)
+            .build()
+            .map_err(|err| err.to_string())?
+            .post(webhook_url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&IntentNotification::from(job))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| err.to_string())?;
+        Ok::<_, String>(())
+    };
+    if let Err(err) = result.await {
+        tracing::warn!(
+            "cannot notify the webhook of the intent of job {}: {}",
+            job.id,
+            err
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn request(extra: Value) -> Map<String, Value> {
+        let mut request = serde_json::json!({
+            "compiler_version": "v0.8.14+commit.80d49f37",
+            "sources": { "A.sol": "contract A {}" },
+            "evm_version": "london",
+        });
+        request
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        request.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn intents_are_validated() {
+        let hash = DisplayBytes::from([0u8; 32].to_vec());
+        let ttl = chrono::Duration::days(1);
+        let intent =
+            VerificationIntent::new(&hash, request(serde_json::json!({})), None, None, ttl)
+                .unwrap();
+        assert_eq!(IntentState::Pending, intent.state);
+        assert_eq!("v0.8.14+commit.80d49f37", intent.compiler_version);
+
+        let with_address = request(serde_json::json!({ "contract_address": "0xcafe" }));
+        assert!(VerificationIntent::new(&hash, with_address, None, None, ttl).is_err());
+        let short_hash = DisplayBytes::from([0u8; 20].to_vec());
+        assert!(VerificationIntent::new(
+            &short_hash,
+            request(serde_json::json!({})),
+            None,
+            None,
+            ttl
+        )
+        .is_err());
+    }
+
+    #[actix_rt::test]
+    async fn webhook_urls_are_validated() {
+        let validate = |url: &str| {
+            let url = Url::parse(url).unwrap();
+            async move { validate_webhook_url(&url).await }
+        };
+        validate("https://140.82.121.4/hook").await.unwrap();
+        for url in [
+            "http://140.82.121.4/hook",
+            "https://127.0.0.1/hook",
+            "https://169.254.169.254/latest/meta-data",
+        ] {
+            assert!(validate(url).await.is_err(), "{url} should be rejected");
+        }
+    }
+
+    #[test]
+    fn init_code_is_hashed() {
+        assert_eq!(
+            Some("0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470".into()),
+            init_code_hash("0x")
+        );
+        assert_eq!(None, init_code_hash("0xzz"));
+    }
+}
@@ -0,0 +1,642 @@
+use crate::{
+    api_error::ApiError,
+    dependencies::PackageRegistry,
+    handlers::solidity_multi_part::{self, VerificationContext},
+    intents,
+    persistence::ResultMiddlewares,
+    settings::JobsSettings,
+    tenants,
+    verification_response::{VerificationResponse, VerificationStatus},
+    DB,
+};
+use actix_web::web;
+use chrono::{DateTime, SecondsFormat, Utc};
+use cron::Schedule;
+use mongodb::bson::{self, doc, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{
+    progress::{self, Stage},
+    scheduler, SolidityClient,
+};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, BinaryHeap},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Waits to be leased by a worker.
+    Queued,
+    /// Is being run by the worker holding the lease.
+    Running,
+    /// The contract has been verified and stored.
+    Finished,
+    /// The contract has not been verified, or verification could not be completed
+    /// (e.g., the request is invalid, or all attempts have failed because of the service).
+    Failed,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Finished => "finished",
+            JobState::Failed => "failed",
+        }
+    }
+
+    /// Whether the job is done and is not going to change anymore.
+    pub fn is_final(&self) -> bool {
+        matches!(self, JobState::Finished | JobState::Failed)
+    }
+}
+
+/// Stage of the verification the running job is at.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum JobProgress {
+    DownloadingCompiler,
+    /// Compiling the input with the metadata hash variant (e.g., "ipfs", "none").
+    Compiling {
+        metadata_hash: String,
+    },
+    Comparing,
+    Storing,
+}
+
+impl From<Stage> for JobProgress {
+    fn from(stage: Stage) -> Self {
+        match stage {
+            Stage::DownloadingCompiler => JobProgress::DownloadingCompiler,
+            Stage::Compiling { metadata_hash } => JobProgress::Compiling { metadata_hash },
+            Stage::Comparing => JobProgress::Comparing,
+            Stage::Storing => JobProgress::Storing,
+        }
+    }
+}
+
+/// Verification endpoint the job has been submitted to.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum JobKind {
+    SolidityMultiPart,
+}
+
+/// Verification request submitted to be run in the background by any instance of the service.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct VerificationJob {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub kind: JobKind,
+    /// Json body of the request.
+    pub request: String,
+    pub contract_address: String,
+    /// Compiler version of the request, used to estimate how long the job takes.
+    #[serde(default)]
+    pub compiler_version: Option<String>,
+    /// Admin the job has been submitted by, allowed to re-verify verified contracts.
+    pub admin: Option<String>,
+    pub state: JobState,
+    /// Number of times the job has been leased.
+    pub attempts: u32,
+    /// Stage of the current attempt. Is set for running jobs.
+    #[serde(default)]
+    pub progress: Option<JobProgress>,
+    /// Worker holding the lease of the job.
+    pub worker: Option<String>,
+    /// Time the job may be leased at: for queued jobs, when they become available
+    /// (e.g., after the retry delay); for running ones, when the lease expires.
+    pub available_at: bson::DateTime,
+    pub response: Option<VerificationResponse>,
+    /// Error of the last attempt.
+    pub error: Option<ApiError>,
+    pub submitted_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Verification intent the job has been queued for, once its contract has been deployed.
+    #[serde(default)]
+    pub intent_id: Option<ObjectId>,
+}
+
+impl VerificationJob {
+    pub fn new(
+        kind: JobKind,
+        contract_address: &str,
+        compiler_version: &str,
+        request: &impl Serialize,
+        admin: Option<String>,
+    ) -> Result<Self, ApiError> {
+        let now = Utc::now();
+        Ok(Self {
+            id: ObjectId::new(),
+            kind,
+            request: serde_json::to_string(request).map_err(ApiError::internal)?,
+            contract_address: contract_address.to_string(),
+            compiler_version: Some(compiler_version.to_string()),
+            admin,
+            state: JobState::Queued,
+            attempts: 0,
+            progress: None,
+            worker: None,
+            available_at: bson::DateTime::from_chrono(now),
+            response: None,
+            error: None,
+            submitted_at: now,
+            finished_at: None,
+            intent_id: None,
+        })
+    }
+}
+
+/// Jobs which are leased before the queued one, grouped by the fields required
+/// to estimate when the queued job starts.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct JobAhead {
+    pub state: JobState,
+    #[serde(default)]
+    pub compiler_version: Option<String>,
+    pub count: u64,
+}
+
+/// Number of jobs an instance runs at a time. Workers wait for the compilers semaphore,
+/// so it is limited by the number of compiler threads as well.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JobsConcurrency(pub usize);
+
+/// Number of the latest attempts the average duration of a compiler version is computed over.
+const DURATION_WINDOW: i64 = 50;
+/// Duration assumed for jobs of compiler versions no attempts have been made with.
+const DEFAULT_JOB_DURATION: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueueEstimate {
+    /// Position of the job among queued ones, starting from 1.
+    pub position: u64,
+    pub estimated_start_at: DateTime<Utc>,
+}
+
+/// Estimates when the queued job is leased, based on the rolling average duration
+/// of the attempts made with the compiler versions of the jobs ahead of it.
+pub async fn estimate_queue(
+    database: &DB,
+    job: &VerificationJob,
+    concurrency: JobsConcurrency,
+) -> Result<QueueEstimate, String> {
+    let mut ahead = database.count_verification_jobs_ahead(job.id).await?;
+    // Running jobs occupy the workers before any queued job is leased
+    ahead.sort_by_key(|job| job.state != JobState::Running);
+    let versions: BTreeSet<_> = ahead
+        .iter()
+        .filter_map(|job| job.compiler_version.clone())
+        .collect();
+    let averages = database
+        .average_attempt_durations(versions.into_iter().collect(), DURATION_WINDOW)
+        .await?;
+
+    let position = ahead
+        .iter()
+        .filter(|job| job.state == JobState::Queued)
+        .map(|job| job.count)
+        .sum::<u64>()
+        + 1;
+    let estimated_start_at = estimate_start(Utc::now(), &ahead, &averages, concurrency.0);
+    Ok(QueueEstimate {
+        position,
+        // Retried jobs are not leased before the retry delay passes
+        estimated_start_at: estimated_start_at.max(job.available_at.to_chrono()),
+    })
+}
+
+/// Assigns the jobs ahead, in the order they are leased, to the worker which becomes free first.
+/// Running jobs are assumed to have just started. The job starts once any worker becomes free.
+///
+/// Jobs of a group are spread evenly over the workers first, and the rest of them are assigned
+/// one by one, so that the estimate does not depend on the length of the queue.
+fn estimate_start(
+    now: DateTime<Utc>,
+    ahead: &[JobAhead],
+    average_durations_ms: &BTreeMap<String, u64>,
+    concurrency: usize,
+) -> DateTime<Utc> {
+    let mut free_at = BinaryHeap::from(vec![Reverse(Duration::ZERO); concurrency.max(1)]);
+    for group in ahead {
+        let duration = group
+            .compiler_version
+            .as_ref()
+            .and_then(|version| average_durations_ms.get(version))
+            .map(|duration_ms| Duration::from_millis(*duration_ms))
+            .unwrap_or(DEFAULT_JOB_DURATION);
+        let workers = free_at.len() as u64;
+        let rounds = u32::try_from(group.count / workers).unwrap_or(u32::MAX);
+        free_at = free_at
+            .into_iter()
+            .map(|Reverse(at)| Reverse(at.saturating_add(duration.saturating_mul(rounds))))
+            .collect();
+        for _ in 0..group.count % workers {
+            if let Some(mut earliest) = free_at.peek_mut() {
+                earliest.0 += duration;
+            }
+        }
+    }
+    let wait = free_at
+        .peek()
+        .map(|earliest| earliest.0)
+        .unwrap_or_default();
+    now + chrono::Duration::from_std(wait).unwrap_or_else(|_| chrono::Duration::zero())
+}
+
+/// Starts workers running jobs of the queue shared by all instances of the service.
+///
+/// Jobs are leased to workers for a limited time, and the leases are renewed while jobs run,
+/// so that jobs of stopped instances are picked up by others. Updates of the jobs are accepted
+/// only from the worker holding the lease, and verified contracts are stored along with
+/// the job id, so that the result of a job is stored once even if it is run several times.
+///
+/// Queues of the tenants are polled in turns along with the default one.
+pub fn spawn_workers(
+    settings: JobsSettings,
+    client: web::Data<SolidityClient>,
+    middlewares: web::Data<ResultMiddlewares>,
+    registry: Option<web::Data<PackageRegistry>>,
+    tenants: Vec<String>,
+) {
+    let instance = ObjectId::new().to_hex();
+    let tenants: Vec<_> = std::iter::once(None)
+        .chain(tenants.into_iter().map(Some))
+        .collect();
+    spawn_cleanup_job(
+        settings.retention_days.get(),
+        middlewares.clone(),
+        tenants.clone(),
+    );
+    for n in 0..settings.workers.get() {
+        let worker = Worker {
+            id: format!("{instance}-{n}"),
+            settings: settings.clone(),
+            client: client.clone(),
+            middlewares: middlewares.clone(),
+            registry: registry.clone(),
+            tenants: tenants.clone(),
+        };
+        tokio::spawn(worker.run());
+    }
+}
+
+/// Periodically removes finished and failed jobs older than the retention period.
+fn spawn_cleanup_job(
+    retention_days: u32,
+    middlewares: web::Data<ResultMiddlewares>,
+    tenants: Vec<Option<String>>,
+) {
+    let schedule = Schedule::from_str("0 30 * * * * *").expect("Is valid schedule"); // every hour
+    scheduler::spawn_job(schedule, "remove outdated verification jobs", move || {
+        let middlewares = middlewares.clone();
+        let tenants = tenants.clone();
+        async move {
+            let finished_before = Utc::now() - chrono::Duration::days(retention_days.into());
+            let finished_before = finished_before.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+            for tenant in tenants {
+                let removed = tenants::scope(tenant, async {
+                    let database = middlewares.database().await;
+                    database
+                        .remove_verification_jobs_finished_before(&finished_before)
+                        .await
+                })
+                .await;
+                match removed {
+                    Ok(removed) => {
+                        tracing::debug!("{} outdated verification jobs removed", removed)
+                    }
+                    Err(err) => tracing::error!("{}", err),
+                }
+            }
+        }
+    });
+}
+
+struct Worker {
+    id: String,
+    settings: JobsSettings,
+    client: web::Data<SolidityClient>,
+    middlewares: web::Data<ResultMiddlewares>,
+    /// Registry missing files of packages are injected from, if enabled.
+    registry: Option<web::Data<PackageRegistry>>,
+    /// Tenants which queues are polled, `None` standing for the default queue.
+    tenants: Vec<Option<String>>,
+}
+
+impl Worker {
+    async fn run(self) {
+        let poll_interval = Duration::from_millis(self.settings.poll_interval_ms.get());
+        // Jobs are abandoned once their leases expire, so they are checked for once per lease
+        let lease = Duration::from_secs(self.settings.lease_secs.get());
+        let mut abandoned_checked_at: Option<Instant> = None;
+        loop {
+            if abandoned_checked_at.map_or(true, |checked_at| checked_at.elapsed() >= lease) {
+                for tenant in &self.tenants {
+                    tenants::scope(tenant.clone(), self.fail_abandoned()).await;
+                }
+                abandoned_checked_at = Some(Instant::now());
+            }
+            let mut leased = false;
+            for tenant in &self.tenants {
+                leased |= tenants::scope(tenant.clone(), self.run_next()).await;
+            }
+            if !leased {
+                tokio::time::sleep(poll_interval).await
+            }
+        }
+    }
+
+    /// Runs the next job of the queue of the current tenant, if any.
+    /// Returns whether a job has been leased.
+    async fn run_next(&self) -> bool {
+        let database = self.middlewares.database().await;
+        match self.lease_next(&database).await {
+            Ok(Some(job)) => {
+                self.run_job(&database, job).await;
+                true
+            }
+            Ok(None) => false,
+            Err(err) => {
+                tracing::error!("cannot lease a verification job: {}", err);
+                false
+            }
+        }
+    }
+
+    /// Fails jobs of the queue of the current tenant which workers have stopped
+    /// after using all the attempts.
+    async fn fail_abandoned(&self) {
+        let database = self.middlewares.database().await;
+        match database
+            .fail_abandoned_verification_jobs(self.settings.max_attempts.get())
+            .await
+        {
+            Ok(0) => {}
+            Ok(abandoned) => {
+                tracing::warn!("{} abandoned verification jobs have failed", abandoned)
+            }
+            Err(err) => tracing::error!("cannot fail abandoned verification jobs: {}", err),
+        }
+    }
+
+    async fn lease_next(&self, database: &DB) -> Result<Option<VerificationJob>, String> {
+        database
+            .lease_verification_job(
+                &self.id,
+                self.lease_until(),
+                self.settings.max_attempts.get(),
+            )
+            .await
+    }
+
+    fn lease_until(&self) -> bson::DateTime {
+        let lease = chrono::Duration::seconds(self.settings.lease_secs.get() as i64);
+        bson::DateTime::from_chrono(Utc::now() + lease)
+    }
+
+    /// Runs the job, renewing its lease and storing the stages the verification enters
+    /// as the progress of the job.
+    #[tracing::instrument(skip_all, fields(job_id = %job.id, attempt = job.attempts))]
+    async fn run_job(&self, database: &DB, job: VerificationJob) {
+        let (stages, mut stages_receiver) = mpsc::unbounded_channel();
+        let verification = progress::scope(
+            move |stage| {
+                let _ = stages.send(JobProgress::from(stage));
+            },
+            self.verify(database, &job),
+        );
+        tokio::pin!(verification);
+        let mut renewal =
+            tokio::time::interval(Duration::from_secs(self.settings.lease_secs.get()) / 3);
+        // The first tick completes immediately
+        renewal.tick().await;
+        let outcome = loop {
+            tokio::select! {
+                outcome = &mut verification => break outcome,
+                Some(stage) = stages_receiver.recv() => {
+                    let update = doc! { "progress": to_bson(&stage) };
+                    if let Err(err) = database.update_leased_verification_job(&job, update).await {
+                        // The progress is auxiliary, so the job goes on
+                        tracing::warn!("cannot update the verification job progress: {}", err);
+                    }
+                }
+                _ = renewal.tick() => {
+                    let update = doc! { "available_at": self.lease_until() };
+                    match database.update_leased_verification_job(&job, update).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            tracing::warn!("the verification job has been leased by another worker");
+                            return;
+                        }
+                        // The lease is kept until it expires
+                        Err(err) => tracing::warn!("cannot renew the verification job lease: {}", err),
+                    }
+                }
+            }
+        };
+
+        let now = Utc::now();
+        let (state, update) = match outcome {
+            // The contract has not been verified, so nothing has been stored
+            Ok(response) if response.status != VerificationStatus::Ok => (
+                JobState::Failed,
+                doc! {
+                    "state": to_bson(&JobState::Failed),
+                    "progress": bson::Bson::Null,
+                    "response": to_bson(&response),
+                    "error": bson::Bson::Null,
+                    "finished_at": to_bson(&now),
+                },
+            ),
+            Ok(response) => (
+                JobState::Finished,
+                doc! {
+                    "state": to_bson(&JobState::Finished),
+                    "progress": bson::Bson::Null,
+                    "response": to_bson(&response),
+                    "error": bson::Bson::Null,
+                    "finished_at": to_bson(&now),
+                },
+            ),
+            Err(err)
+                if err.code.status().is_server_error()
+                    && job.attempts < self.settings.max_attempts.get() =>
+            {
+                tracing::warn!("verification job has failed and will be retried: {}", err);
+                let delay = chrono::Duration::seconds(self.settings.retry_delay_secs as i64);
+                (
+                    JobState::Queued,
+                    doc! {
+                        "state": to_bson(&JobState::Queued),
+                        "progress": bson::Bson::Null,
+                        "available_at": bson::DateTime::from_chrono(now + delay),
+                        "error": to_bson(&err),
+                    },
+                )
+            }
+            Err(err) => (
+                JobState::Failed,
+                doc! {
+                    "state": to_bson(&JobState::Failed),
+                    "progress": bson::Bson::Null,
+                    "error": to_bson(&err),
+                    "finished_at": to_bson(&now),
+                },
+            ),
+        };
+        match database.update_leased_verification_job(&job, update).await {
+            Ok(true) if state != JobState::Queued && job.intent_id.is_some() => {
+                intents::complete(database, job.id).await
+            }
+            Ok(true) => {}
+            Ok(false) => tracing::warn!("the verification job has been leased by another worker"),
+            Err(err) => tracing::error!("cannot update the verification job: {}", err),
+        }
+    }
+
+    async fn verify(
+        &self,
+        database: &DB,
+        job: &VerificationJob,
+    ) -> Result<VerificationResponse, ApiError> {
+        let job_id = job.id.to_hex();
+        if job.attempts > 1 {
+            // The contract may have been stored by the previous attempt, which has been
+            // interrupted before the job has been updated
+            let stored = database
+                .find_contract_verify_response(&job.contract_address)
+                .await
+                .map_err(ApiError::storage)?
+                .filter(|cvr| cvr.job_id.as_deref() == Some(job_id.as_str()));
+            if let Some(cvr) = stored {
+                return Ok(VerificationResponse::ok(cvr.result));
+            }
+        }
+
+        match job.kind {
+            JobKind::SolidityMultiPart => {
+                let params: solidity_multi_part::VerificationRequest =
+                    serde_json::from_str(&job.request).map_err(ApiError::internal)?;
+                let context = VerificationContext {
+                    job_id: Some(job_id),
+                    create2: params.create2.clone(),
+                    ..VerificationContext::new("multi-part", params.verbose)
+                };
+                let (request, injected_sources) = params
+                    .into_verifier_request(self.registry.as_deref())
+                    .await?;
+                let response = solidity_multi_part::verify_and_store(
+                    job.admin.clone(),
+                    &self.middlewares,
+                    self.client.clone(),
+                    request,
+                    context,
+                )
+                .await?;
+                Ok(response
+                    .into_inner()
+                    .with_injected_sources(injected_sources))
+            }
+        }
+    }
+}
+
+fn to_bson<T: Serialize>(value: &T) -> bson::Bson {
+    bson::to_bson(value).unwrap_or(bson::Bson::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn job_round_trips_through_bson() {
+        let request = serde_json::json!({ "contract_address": "0xcafe" });
+        let job = VerificationJob::new(
+            JobKind::SolidityMultiPart,
+            "0xcafe",
+            "v0.8.14+commit.80d49f37",
+            &request,
+            None,
+        )
+        .unwrap();
+        let document = bson::to_document(&job).unwrap();
+        assert_eq!(Some("queued"), document.get_str("state").ok());
+        assert_eq!(Some("solidity-multi-part"), document.get_str("kind").ok());
+        assert_eq!(job, bson::from_document(document).unwrap());
+    }
+
+    #[test]
+    fn progress_is_tagged_with_stage() {
+        let progress = JobProgress::from(Stage::Compiling {
+            metadata_hash: "ipfs".to_string(),
+        });
+        assert_eq!(
+            serde_json::json!({ "stage": "compiling", "metadata_hash": "ipfs" }),
+            serde_json::to_value(&progress).unwrap()
+        );
+        assert_eq!(
+            serde_json::json!({ "stage": "downloading_compiler" }),
+            serde_json::to_value(JobProgress::from(Stage::DownloadingCompiler)).unwrap()
+        );
+    }
+
+    #[test]
+    fn start_is_estimated_from_durations_of_jobs_ahead() {
+        let now = Utc::now();
+        let job = |state, version: &str| JobAhead {
+            state,
+            compiler_version: Some(version.to_string()),
+            count: 1,
+        };
+        let averages = BTreeMap::from([
+            ("v0.8.14".to_string(), 30_000),
+            ("v0.4.24".to_string(), 5_000),
+        ]);
+        let ahead = vec![
+            job(JobState::Running, "v0.8.14"),
+            job(JobState::Queued, "v0.4.24"),
+            job(JobState::Queued, "v0.4.24"),
+            job(JobState::Queued, "v0.6.0"),
+        ];
+        let seconds = |start: DateTime<Utc>| (start - now).num_seconds();
+
+        assert_eq!(0, seconds(estimate_start(now, &[], &averages, 2)));
+        // One worker runs all the jobs ahead one after another
+        assert_eq!(50, seconds(estimate_start(now, &ahead, &averages, 1)));
+        // The second worker runs the queued ones while the first one compiles for 30 seconds
+        assert_eq!(20, seconds(estimate_start(now, &ahead, &averages, 2)));
+    }
+
+    #[test]
+    fn grouped_jobs_are_spread_over_workers() {
+        let now = Utc::now();
+        let averages = BTreeMap::from([("v0.8.14".to_string(), 10_000)]);
+        let ahead = vec![
+            JobAhead {
+                state: JobState::Running,
+                compiler_version: Some("v0.8.14".to_string()),
+                count: 1,
+            },
+            JobAhead {
+                state: JobState::Queued,
+                compiler_version: Some("v0.8.14".to_string()),
+                count: 1_000_000,
+            },
+        ];
+        let seconds = |start: DateTime<Utc>| (start - now).num_seconds();
+
+        // 1,000,001 jobs of 10 seconds each over 4 workers
+        assert_eq!(
+            2_500_000,
+            seconds(estimate_start(now, &ahead, &averages, 4))
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use crate::DB;
+use chrono::Utc;
+use cron::Schedule;
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+lazy_static::lazy_static! {
+    /// Identifies this instance among the replicas of the service sharing the database.
+    static ref INSTANCE: String = ObjectId::new().to_hex();
+}
+
+/// Claims the run of the scheduled job which is due now. Every replica of the service
+/// runs the same schedules, so that only the replica which has claimed the run is to do it.
+/// The claim is held until the next run of the schedule is due.
+pub async fn claim_scheduled_run(
+    database: &DB,
+    job: &str,
+    schedule: &Schedule,
+) -> Result<bool, String> {
+    let now = Utc::now();
+    let until = schedule
+        .after(&now)
+        .next()
+        .unwrap_or(now + chrono::Duration::minutes(1));
+    claim(database, job, until).await
+}
+
+/// Claims the job run once by any of the replicas (e.g., on start). The claim is held
+/// for the given time or until released, so that the job is run again if the replica
+/// running it has stopped before releasing the claim.
+pub async fn claim_once(
+    database: &DB,
+    job: &str,
+    duration: chrono::Duration,
+) -> Result<bool, String> {
+    claim(database, job, Utc::now() + duration).await
+}
+
+/// Releases the claim of the job, if it is held by this instance.
+pub async fn release(database: &DB, job: &str) -> Result<(), String> {
+    database.release_scheduled_run(job, &INSTANCE).await
+}
+
+async fn claim(database: &DB, job: &str, until: chrono::DateTime<Utc>) -> Result<bool, String> {
+    database
+        .claim_scheduled_run(job, &INSTANCE, DateTime::from_chrono(until))
+        .await
+}
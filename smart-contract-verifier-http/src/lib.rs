@@ -1,11 +1,64 @@
+mod abi;
+mod access_log;
+mod analysis;
+mod api_error;
+mod archive;
+mod attempts;
+mod attestation;
+mod audit;
+mod auth;
+mod backup;
+mod blobs;
+mod bootstrap;
+mod caching;
+mod chain_watcher;
+mod chains;
+mod compiler_inputs;
+mod compression;
+mod contract_size;
+mod create2;
+mod debug_info;
+mod dependencies;
+mod derived;
+mod erc20_module;
+mod etherscan_import;
+mod git_import;
 mod handlers;
+mod headers;
+mod highlights;
+mod idempotency;
+mod impersonation;
+mod intents;
+mod jobs;
+mod leader;
+mod licenses;
+mod metadata_availability;
 mod metrics;
+mod notifications;
+mod object_storage;
+mod persistence;
+mod precompiles;
+mod proxy_history;
+mod rate_limit;
+mod read_only;
+mod request_id;
+mod retention;
 mod routers;
 mod run;
 mod settings;
+mod signatures;
+mod slither;
+mod source_diff;
+mod standards;
+mod stats;
+mod strict_mode;
+mod tenants;
+mod token_info;
 mod tracer;
+mod uploads;
 mod verification_response;
 mod versions;
+mod warm_up;
 mod db;
 mod verified_contract_result;
 
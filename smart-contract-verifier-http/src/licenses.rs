@@ -0,0 +1,118 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+const SPDX_MARKER: &str = "SPDX-License-Identifier:";
+
+/// Returns the license specified by the SPDX identifier comment in the source.
+pub fn license(content: &str) -> Option<&str> {
+    let start = content.find(SPDX_MARKER)? + SPDX_MARKER.len();
+    let line = content[start..].lines().next()?;
+    let license = line.trim().trim_end_matches("*/").trim();
+    (!license.is_empty()).then_some(license)
+}
+
+/// Returns distinct licenses of the sources, sorted. License expressions
+/// (e.g., "MIT OR Apache-2.0") are returned as they are declared.
+pub fn detect(sources: &BTreeMap<String, String>) -> Vec<String> {
+    sources
+        .values()
+        .filter_map(|content| license(content))
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Returns SPDX identifiers of the licenses the expression (e.g., "MIT OR Apache-2.0") consists of.
+/// Exceptions (e.g., "Classpath-exception-2.0" in "GPL-2.0-or-later WITH Classpath-exception-2.0")
+/// are not licenses, and are skipped.
+pub fn identifiers(expression: &str) -> Vec<&str> {
+    let mut identifiers = vec![];
+    let mut tokens = expression
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .filter(|token| !token.is_empty());
+    while let Some(token) = tokens.next() {
+        match token {
+            "AND" | "OR" | "and" | "or" => {}
+            "WITH" | "with" => {
+                tokens.next();
+            }
+            identifier => identifiers.push(identifier),
+        }
+    }
+    identifiers
+}
+
+/// Returns distinct SPDX identifiers of all the licenses, sorted,
+/// so that contracts could be filtered by any license of their expressions.
+pub fn distinct_identifiers(licenses: &[String]) -> Vec<String> {
+    licenses
+        .iter()
+        .flat_map(|license| identifiers(license))
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn license_is_parsed() {
+        assert_eq!(Some("MIT"), license("// SPDX-License-Identifier: MIT\n"));
+        assert_eq!(
+            Some("GPL-3.0-or-later"),
+            license("/* SPDX-License-Identifier: GPL-3.0-or-later */\ncontract A {}")
+        );
+        assert_eq!(None, license("contract A {}"));
+    }
+
+    #[test]
+    fn licenses_of_all_sources_are_detected() {
+        let sources = BTreeMap::from([
+            (
+                "A.sol".into(),
+                "// SPDX-License-Identifier: MIT\ncontract A {}".into(),
+            ),
+            (
+                "B.sol".into(),
+                "// SPDX-License-Identifier: MIT OR Apache-2.0\n".into(),
+            ),
+            ("C.vy".into(), "# SPDX-License-Identifier: MIT\n".into()),
+            ("D.sol".into(), "contract D {}".into()),
+        ]);
+        assert_eq!(
+            vec!["MIT".to_string(), "MIT OR Apache-2.0".to_string()],
+            detect(&sources)
+        );
+    }
+
+    #[test]
+    fn expressions_are_split_into_identifiers() {
+        assert_eq!(vec!["MIT"], identifiers("MIT"));
+        assert_eq!(vec!["MIT", "Apache-2.0"], identifiers("MIT OR Apache-2.0"));
+        assert_eq!(
+            vec!["LGPL-2.1-only", "MIT", "BSD-2-Clause"],
+            identifiers("(LGPL-2.1-only OR MIT) AND BSD-2-Clause")
+        );
+        assert_eq!(
+            vec!["GPL-2.0-or-later"],
+            identifiers("GPL-2.0-or-later WITH Classpath-exception-2.0")
+        );
+
+        assert_eq!(
+            vec![
+                "Apache-2.0".to_string(),
+                "MIT".to_string(),
+                "UNLICENSED".to_string()
+            ],
+            distinct_identifiers(&[
+                "MIT".to_string(),
+                "MIT OR Apache-2.0".to_string(),
+                "UNLICENSED".to_string(),
+            ])
+        );
+    }
+}
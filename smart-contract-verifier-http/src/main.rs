@@ -6,7 +6,11 @@ use std::error::Error;
 async fn main() -> Result<(), Box<dyn Error>> {
    let settings = Settings::new().context("failed to parse config")?;
        
-   init_logs(settings.jaeger.clone());
+   init_logs(
+       settings.logs.clone(),
+       settings.jaeger.clone(),
+       settings.otlp.clone(),
+   );
    run(settings).await?;
    
    Ok(())
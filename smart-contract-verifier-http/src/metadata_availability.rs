@@ -0,0 +1,213 @@
+use crate::{
+    chains::Chain, persistence::VerifiedContract, settings::MetadataAvailabilitySettings,
+    DisplayBytes, DB,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{
+    middleware::Middleware,
+    rpc::BytecodeFetcher,
+    tools::metadata_hash::{metadata_hash, MetadataHash},
+};
+use std::{str::FromStr, sync::Arc, time::Duration};
+use url::Url;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AvailabilityStatus {
+    /// The metadata file has been fetched from one of the gateways.
+    Available,
+    /// None of the gateways has served the metadata file.
+    Unavailable,
+    /// No gateways are configured for the network the hash belongs to.
+    Unknown,
+    /// The code references no metadata file (e.g., compiled with `bytecodeHash: none`).
+    NoHash,
+}
+
+/// Whether the metadata file referenced by the code of the verified contract
+/// could be fetched from IPFS or Swarm, i.e., whether anyone could reproduce
+/// the verification without relying on the service.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct MetadataAvailability {
+    pub contract_address: String,
+    /// `ipfs`, `bzzr0` or `bzzr1`.
+    pub network: Option<String>,
+    /// IPFS CID or hex encoded Swarm hash of the metadata file.
+    pub hash: Option<String>,
+    pub status: AvailabilityStatus,
+    /// Gateway the metadata file has been fetched from.
+    pub gateway: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Checks availability of the metadata files of verified contracts
+/// in the background, so that verification responses are not delayed.
+#[derive(Clone)]
+pub struct MetadataAvailabilityCheck {
+    client: reqwest::Client,
+    settings: Arc<MetadataAvailabilitySettings>,
+    /// Chain contracts are verified for. Results are stored in its namespace.
+    chain: Chain,
+    fetcher: Arc<BytecodeFetcher>,
+}
+
+impl MetadataAvailabilityCheck {
+    pub fn new(
+        settings: MetadataAvailabilitySettings,
+        chain: Chain,
+        fetcher: Arc<BytecodeFetcher>,
+    ) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(settings.request_timeout))
+            .build()?;
+        Ok(Self {
+            client,
+            settings: Arc::new(settings),
+            chain,
+            fetcher,
+        })
+    }
+
+    /// Checks the hash of the code deployed on chain rather than of the compiled one,
+    /// as the latter differs from it for partially verified contracts.
+    async fn check(&self, contract_address: &str) -> Result<MetadataAvailability, String> {
+        let code = self
+            .fetcher
+            .get_code(contract_address)
+            .await
+            .map_err(|err| err.to_string())?
+            .ok_or_else(|| "the contract has no code".to_string())?;
+        let code = DisplayBytes::from_str(&code).map_err(|err| err.to_string())?;
+
+        let mut availability = MetadataAvailability {
+            contract_address: contract_address.to_lowercase(),
+            network: None,
+            hash: None,
+            status: AvailabilityStatus::NoHash,
+            gateway: None,
+            checked_at: Utc::now(),
+        };
+        let (network, hash, gateways) = match metadata_hash(&code.0) {
+            None => return Ok(availability),
+            Some(MetadataHash::Ipfs(cid)) => {
+                ("ipfs".to_string(), cid, &self.settings.ipfs_gateways)
+            }
+            Some(MetadataHash::Swarm { version, hash }) => {
+                (version, hash, &self.settings.swarm_gateways)
+            }
+        };
+        availability.status = match gateways.is_empty() {
+            true => AvailabilityStatus::Unknown,
+            false => AvailabilityStatus::Unavailable,
+        };
+        for gateway in gateways {
+            if self.is_served(gateway, &hash).await {
+                availability.status = AvailabilityStatus::Available;
+                availability.gateway = Some(gateway.to_string());
+                break;
+            }
+        }
+        availability.network = Some(network);
+        availability.hash = Some(hash);
+        Ok(availability)
+    }
+
+    /// Only the headers of the metadata file are requested, its contents are not needed.
+    async fn is_served(&self, gateway: &Url, hash: &str) -> bool {
+        let url = match metadata_url(gateway, hash) {
+            Ok(url) => url,
+            Err(_) => return false,
+        };
+        match self.client.head(url).send().await {
+            Ok(response) => response.status().is_success(),
+            Err(err) => {
+                tracing::debug!("cannot fetch metadata {} from {}: {}", hash, gateway, err);
+                false
+            }
+        }
+    }
+}
+
+/// Appends the hash to the path of the gateway. Gateways configured without a trailing slash
+/// are treated as directories, as `Url::join` would replace their last path segment otherwise.
+fn metadata_url(gateway: &Url, hash: &str) -> Result<Url, url::ParseError> {
+    let mut gateway = gateway.clone();
+    if !gateway.path().ends_with('/') {
+        gateway.set_path(&format!("{}/", gateway.path()));
+    }
+    gateway.join(hash)
+}
+
+#[async_trait::async_trait]
+impl Middleware<VerifiedContract> for MetadataAvailabilityCheck {
+    async fn call(&self, verified: &VerifiedContract) -> Result<(), anyhow::Error> {
+        let check = self.clone();
+        let contract_address = verified.contract_address.clone();
+        let namespace = self.chain.namespace();
+        tokio::spawn(async move {
+            let availability = match check.check(&contract_address).await {
+                Ok(availability) => availability,
+                Err(err) => {
+                    tracing::warn!(
+                        "cannot check metadata availability of {}: {}",
+                        contract_address,
+                        err
+                    );
+                    return;
+                }
+            };
+            let database = DB::new().await.change_name(&namespace);
+            if let Err(err) = database.set_metadata_availability(availability).await {
+                tracing::error!("{}", err);
+            }
+        });
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "metadata_availability"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn availability_is_serialized() {
+        let availability = MetadataAvailability {
+            contract_address: "0xcafe".into(),
+            network: Some("ipfs".into()),
+            hash: Some("QmNLei78zWmzUdbeRB3CiUfAizWUrbeeZh5K1rhAQKCh51".into()),
+            status: AvailabilityStatus::Available,
+            gateway: Some("https://ipfs.io/ipfs/".into()),
+            checked_at: DateTime::parse_from_rfc3339("2022-11-01T12:00:00Z")
+                .unwrap()
+                .into(),
+        };
+        assert_eq!(
+            json!({
+                "contract_address": "0xcafe",
+                "network": "ipfs",
+                "hash": "QmNLei78zWmzUdbeRB3CiUfAizWUrbeeZh5K1rhAQKCh51",
+                "status": "available",
+                "gateway": "https://ipfs.io/ipfs/",
+                "checked_at": "2022-11-01T12:00:00Z",
+            }),
+            serde_json::to_value(&availability).unwrap()
+        );
+    }
+
+    #[test]
+    fn hash_is_appended_to_gateways() {
+        let hash = "QmNLei78zWmzUdbeRB3CiUfAizWUrbeeZh5K1rhAQKCh51";
+        let expected = format!("https://ipfs.io/ipfs/{hash}");
+        for gateway in ["https://ipfs.io/ipfs/", "https://ipfs.io/ipfs"] {
+            let gateway = Url::parse(gateway).unwrap();
+            assert_eq!(expected, metadata_url(&gateway, hash).unwrap().as_str());
+        }
+    }
+}
@@ -12,6 +12,12 @@ lazy_static! {
         &["language", "endpoint", "status"],
     )
     .unwrap();
+    pub static ref VERIFICATION_FAILURES: IntCounterVec = register_int_counter_vec!(
+        "smart_contract_verifier_verification_failures",
+        "number of failed contract verifications by failure reason",
+        &["language", "endpoint", "reason"],
+    )
+    .unwrap();
 }
 
 pub fn count_verify_contract(language: &str, status: &VerificationStatus, method: &str) {
@@ -24,6 +30,12 @@ pub fn count_verify_contract(language: &str, status: &VerificationStatus, method
         .inc();
 }
 
+pub fn count_verification_failure(language: &str, method: &str, reason: &str) {
+    VERIFICATION_FAILURES
+        .with_label_values(&[language, method, reason])
+        .inc();
+}
+
 #[derive(Clone)]
 pub struct Metrics {
     metrics_middleware: PrometheusMetrics,
@@ -0,0 +1,119 @@
+use crate::{chains::Chain, persistence::VerifiedContract, tenants};
+use serde::Serialize;
+use smart_contract_verifier::middleware::Middleware;
+use tokio::sync::broadcast;
+
+/// Number of notifications retained for subscribers which have not received them yet.
+/// Subscribers lagging behind further miss the oldest notifications.
+const CAPACITY: usize = 1024;
+
+/// Notification about a newly verified contract sent to subscribers.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct VerifiedNotification {
+    pub address: String,
+    /// Name of the chain in the chains registry.
+    pub chain: String,
+    pub contract_name: String,
+    /// Either "full" or "partial".
+    pub match_type: Option<String>,
+    /// Tenant the contract has been verified by. Only subscribers of the same tenant are notified.
+    #[serde(skip)]
+    pub tenant: Option<String>,
+}
+
+/// Broadcasts notifications about verified contracts to all current subscribers.
+#[derive(Clone)]
+pub struct Notifier {
+    sender: broadcast::Sender<VerifiedNotification>,
+    /// Chain contracts are verified for.
+    chain: Chain,
+}
+
+impl Notifier {
+    pub fn new(chain: Chain) -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self { sender, chain }
+    }
+
+    fn notification(&self, verified: &VerifiedContract) -> VerifiedNotification {
+        VerifiedNotification {
+            address: verified.contract_address.clone(),
+            chain: self.chain.name.clone(),
+            contract_name: verified.result.contract_name.clone(),
+            match_type: verified.result.match_type.clone(),
+            tenant: tenants::current(),
+        }
+    }
+
+    /// Returns the receiver of notifications about contracts verified after the subscription.
+    pub fn subscribe(&self) -> broadcast::Receiver<VerifiedNotification> {
+        self.sender.subscribe()
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware<VerifiedContract> for Notifier {
+    async fn call(&self, verified: &VerifiedContract) -> Result<(), anyhow::Error> {
+        // Fails only if there are no subscribers, which is fine
+        let _ = self.sender.send(self.notification(verified));
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "notify"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chains::Chains, verification_response::VerificationResult};
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn subscribers_are_notified() {
+        let mut result: VerificationResult = serde_json::from_value(serde_json::json!({
+            "file_name": "A.sol",
+            "contract_name": "A",
+            "compiler_version": "v0.8.14+commit.80d49f37",
+            "evm_version": "default",
+            "constructor_arguments": null,
+            "optimization": null,
+            "optimization_runs": null,
+            "contract_libraries": {},
+            "abi": null,
+            "sources": {},
+            "compiler_settings": "{}",
+        }))
+        .unwrap();
+        result.match_type = Some("full".to_string());
+        let verified = VerifiedContract {
+            contract_address: "0xbebebebebebebebebebebebebebebebebebebebe".to_string(),
+            result,
+            repository: None,
+            replaced_by: None,
+            debug_info: None,
+            compiler_input: None,
+            job_id: None,
+        };
+
+        let notifier = Notifier::new(Chains::default().current().clone());
+        // Notifications are dropped while there are no subscribers
+        notifier.call(&verified).await.unwrap();
+        let mut receiver = notifier.subscribe();
+        notifier.call(&verified).await.unwrap();
+
+        let expected = VerifiedNotification {
+            address: "0xbebebebebebebebebebebebebebebebebebebebe".to_string(),
+            chain: "mainnet".to_string(),
+            contract_name: "A".to_string(),
+            match_type: Some("full".to_string()),
+            tenant: None,
+        };
+        assert_eq!(expected, receiver.recv().await.unwrap());
+        assert!(
+            receiver.try_recv().is_err(),
+            "only one notification is expected"
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use s3::Bucket;
+use std::{path::PathBuf, sync::Arc};
+use tokio::io::AsyncWriteExt;
+
+/// Storage of objects (e.g., database snapshots) identified by `/` separated keys.
+pub enum ObjectStorage {
+    /// Objects are stored as files inside of the directory.
+    Local(PathBuf),
+    S3(Arc<Bucket>),
+}
+
+impl ObjectStorage {
+    pub async fn save(&self, key: &str, content: Vec<u8>) -> anyhow::Result<()> {
+        match self {
+            ObjectStorage::Local(dir) => {
+                let path = dir.join(key);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                // Is written under a temporary name, so that partially
+                // written objects are never read
+                let path_tmp = dir.join(format!("{key}.tmp"));
+                tokio::fs::write(&path_tmp, content).await?;
+                tokio::fs::rename(&path_tmp, path).await?;
+            }
+            ObjectStorage::S3(bucket) => {
+                let response = bucket.put_object(key, &content).await?;
+                if response.status_code() != 200 {
+                    anyhow::bail!("s3 responded with {} status", response.status_code());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves the content of the file, copying it in parts.
+    pub async fn save_file(&self, key: &str, mut file: tokio::fs::File) -> anyhow::Result<()> {
+        match self {
+            ObjectStorage::Local(_) => {
+                let mut writer = self.writer(key).await?;
+                tokio::io::copy(&mut file, &mut writer.file).await?;
+                writer.finish().await
+            }
+            ObjectStorage::S3(bucket) => {
+                let status_code = bucket.put_object_stream(&mut file, key).await?;
+                if status_code != 200 {
+                    anyhow::bail!("s3 responded with {} status", status_code);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Starts writing the object in parts, so that it is never held in memory whole.
+    /// Is supported by the local storage only.
+    pub async fn writer(&self, key: &str) -> anyhow::Result<ObjectWriter> {
+        match self {
+            ObjectStorage::Local(dir) => {
+                let path = dir.join(key);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                // Several writers of the same object do not interfere,
+                // and the last finished one wins
+                let suffix: u64 = rand::random();
+                let path_tmp = dir.join(format!("{key}.{suffix:016x}.tmp"));
+                let file = tokio::fs::File::create(&path_tmp).await?;
+                Ok(ObjectWriter {
+                    file,
+                    path_tmp,
+                    path,
+                    finished: false,
+                })
+            }
+            ObjectStorage::S3(_) => anyhow::bail!("s3 objects cannot be written in parts"),
+        }
+    }
+
+    pub async fn load(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        match self {
+            ObjectStorage::Local(dir) => Ok(tokio::fs::read(dir.join(key)).await?),
+            ObjectStorage::S3(bucket) => {
+                let response = bucket.get_object(key).await?;
+                if response.status_code() != 200 {
+                    anyhow::bail!("s3 responded with {} status", response.status_code());
+                }
+                Ok(response.bytes().to_vec())
+            }
+        }
+    }
+
+    /// Returns the size of the object in bytes, or `None` if there is no such object.
+    pub async fn size(&self, key: &str) -> anyhow::Result<Option<u64>> {
+        match self {
+            ObjectStorage::Local(dir) => match tokio::fs::metadata(dir.join(key)).await {
+                Ok(metadata) => Ok(Some(metadata.len())),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(err.into()),
+            },
+            ObjectStorage::S3(bucket) => {
+                let (head, status_code) = bucket.head_object(key).await?;
+                match status_code {
+                    200 => Ok(Some(head.content_length.unwrap_or_default() as u64)),
+                    404 => Ok(None),
+                    status_code => anyhow::bail!("s3 responded with {} status", status_code),
+                }
+            }
+        }
+    }
+
+    pub async fn remove(&self, key: &str) -> anyhow::Result<()> {
+        match self {
+            ObjectStorage::Local(dir) => tokio::fs::remove_file(dir.join(key)).await?,
+            ObjectStorage::S3(bucket) => {
+                bucket.delete_object(key).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns sorted keys of the objects starting with the given prefix.
+    /// For local storage, only objects of the directory the prefix points into are listed.
+    pub async fn list(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let mut keys: Vec<String> = match self {
+            ObjectStorage::Local(dir) => {
+                let (subdir, name_prefix) = match prefix.rsplit_once('/') {
+                    Some((subdir, name_prefix)) => (format!("{subdir}/"), name_prefix),
+                    None => (String::new(), prefix),
+                };
+                let mut entries = match tokio::fs::read_dir(dir.join(&subdir)).await {
+                    Ok(entries) => entries,
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+                    Err(err) => return Err(err.into()),
+                };
+                let mut keys = Vec::new();
+                while let Some(entry) = entries.next_entry().await? {
+                    let name = match entry.file_name().into_string() {
+                        Ok(name) => name,
+                        Err(_) => continue,
+                    };
+                    if name.starts_with(name_prefix) && !name.ends_with(".tmp") {
+                        keys.push(format!("{subdir}{name}"));
+                    }
+                }
+                keys
+            }
+            ObjectStorage::S3(bucket) => bucket
+                .list(prefix.to_string(), None)
+                .await?
+                .into_iter()
+                .flat_map(|result| result.contents)
+                .map(|object| object.key)
+                .collect(),
+        };
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Object of the local storage being written in parts. Is not visible
+/// until finished, and is discarded if dropped before that.
+pub struct ObjectWriter {
+    file: tokio::fs::File,
+    path_tmp: PathBuf,
+    path: PathBuf,
+    finished: bool,
+}
+
+impl ObjectWriter {
+    pub async fn write(&mut self, content: &[u8]) -> anyhow::Result<()> {
+        Ok(self.file.write_all(content).await?)
+    }
+
+    /// Appends the content of another object of the storage, copying it in parts.
+    pub async fn append(&mut self, storage: &ObjectStorage, key: &str) -> anyhow::Result<()> {
+        match storage {
+            ObjectStorage::Local(dir) => {
+                let mut object = tokio::fs::File::open(dir.join(key)).await?;
+                tokio::io::copy(&mut object, &mut self.file).await?;
+                Ok(())
+            }
+            ObjectStorage::S3(_) => self.write(&storage.load(key).await?).await,
+        }
+    }
+
+    pub async fn finish(mut self) -> anyhow::Result<()> {
+        self.file.flush().await?;
+        tokio::fs::rename(&self.path_tmp, &self.path).await?;
+        self.finished = true;
+        Ok(())
+    }
+
+    /// Saves the object unless it exists already, so that of several writers
+    /// only the first finished one wins. Returns whether the object has been saved.
+    pub async fn finish_new(mut self) -> anyhow::Result<bool> {
+        self.file.flush().await?;
+        // Unlike renaming, linking fails if the object exists.
+        // The temporary file is removed on drop either way
+        match tokio::fs::hard_link(&self.path_tmp, &self.path).await {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl Drop for ObjectWriter {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = std::fs::remove_file(&self.path_tmp);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn objects_are_stored_locally() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = ObjectStorage::Local(dir.path().to_path_buf());
+        storage.save("a/1.json", b"1".to_vec()).await.unwrap();
+        storage.save("a/2.json", b"2".to_vec()).await.unwrap();
+        storage.save("b.json", b"b".to_vec()).await.unwrap();
+
+        assert_eq!(b"2".to_vec(), storage.load("a/2.json").await.unwrap());
+        assert_eq!(Some(1), storage.size("a/2.json").await.unwrap());
+        assert_eq!(None, storage.size("a/3.json").await.unwrap());
+        assert_eq!(
+            vec!["a/1.json", "a/2.json"],
+            storage.list("a/").await.unwrap()
+        );
+
+        storage.remove("a/1.json").await.unwrap();
+        assert_eq!(vec!["a/2.json"], storage.list("a/").await.unwrap());
+        assert_eq!(vec!["b.json"], storage.list("b").await.unwrap());
+
+        let mut writer = storage.writer("a/3.json").await.unwrap();
+        writer.write(b"[").await.unwrap();
+        writer.append(&storage, "a/2.json").await.unwrap();
+        // Is not visible until finished
+        assert_eq!(None, storage.size("a/3.json").await.unwrap());
+        assert_eq!(vec!["a/2.json"], storage.list("a/").await.unwrap());
+        writer.write(b"]").await.unwrap();
+        writer.finish().await.unwrap();
+        assert_eq!(b"[2]".to_vec(), storage.load("a/3.json").await.unwrap());
+
+        let mut writer = storage.writer("a/4.json").await.unwrap();
+        writer.write(b"4").await.unwrap();
+        drop(writer);
+        assert_eq!(
+            vec!["a/2.json", "a/3.json"],
+            storage.list("a/").await.unwrap()
+        );
+
+        let mut first = storage.writer("a/5.json").await.unwrap();
+        first.write(b"5").await.unwrap();
+        let mut second = storage.writer("a/5.json").await.unwrap();
+        second.write(b"6").await.unwrap();
+        assert!(first.finish_new().await.unwrap());
+        assert!(!second.finish_new().await.unwrap());
+        assert_eq!(b"5".to_vec(), storage.load("a/5.json").await.unwrap());
+        assert_eq!(
+            vec!["a/2.json", "a/3.json", "a/5.json"],
+            storage.list("a/").await.unwrap()
+        );
+    }
+}
@@ -0,0 +1,407 @@
+use crate::{
+    analysis::StaticAnalysis,
+    api_error::ApiError,
+    blobs::BlobStore,
+    chains::Chain,
+    compiler_inputs::ArchivedCompilerInput,
+    compression::ContentEncoding,
+    debug_info::DebugInfo,
+    derived,
+    impersonation::CanonicalDeployments,
+    metadata_availability::MetadataAvailabilityCheck,
+    notifications::Notifier,
+    signatures,
+    standards::Standard,
+    token_info,
+    verification_response::VerificationResult,
+    verified_contract_result::{
+        AdminActionKind, ContractCreation, RepositorySource, Verified_Contract_Result,
+    },
+    DB,
+};
+use anyhow::anyhow;
+use chrono::Utc;
+use smart_contract_verifier::{middleware::Composition, rpc::BytecodeFetcher, VerificationSuccess};
+use std::{sync::Arc, time::Duration};
+
+/// Successfully verified contract, passed to [`ResultMiddlewares`]
+/// after the verification response has been built.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedContract {
+    pub contract_address: String,
+    pub result: VerificationResult,
+    /// Git repository the sources have been imported from, if any.
+    pub repository: Option<RepositorySource>,
+    /// Set if an admin has re-verified the already verified contract.
+    pub replaced_by: Option<String>,
+    /// Source maps and ASTs of solidity contracts. Are stored only if enabled.
+    pub debug_info: Option<DebugInfo>,
+    /// The exact compiler input the contract has been verified with, if available.
+    pub compiler_input: Option<ArchivedCompilerInput>,
+    /// Verification job the contract has been verified by, if submitted asynchronously.
+    pub job_id: Option<String>,
+}
+
+impl VerifiedContract {
+    fn to_contract_verify_response(&self) -> Verified_Contract_Result {
+        Verified_Contract_Result {
+            contract_address: self.contract_address.clone(),
+            result: self.result.clone(),
+            sources_of: None,
+            sources_blob: None,
+            compressed_sources: None,
+            repository: self.repository.clone(),
+            content_hash: None,
+            license_ids: vec![],
+            verified_at: Some(Utc::now()),
+            job_id: self.job_id.clone(),
+            creation: None,
+            token_info: None,
+        }
+    }
+}
+
+/// Steps invoked with each verified contract, configured once at startup.
+///
+/// The contract is persisted first. Failure to persist it fails the verification request,
+/// and the following middlewares are invoked only if it has been persisted.
+pub struct ResultMiddlewares {
+    persist: PersistMiddleware,
+    middlewares: Composition<VerifiedContract>,
+    /// If set, contracts impersonating well-known ones are warned about.
+    canonical: Option<CanonicalDeployments>,
+}
+
+impl ResultMiddlewares {
+    /// Persists the verified contract and passes it to the rest of the middlewares.
+    pub async fn run(&self, verified: &VerifiedContract) -> Result<(), ApiError> {
+        self.persist
+            .persist(verified)
+            .await
+            .map_err(ApiError::storage)?;
+        self.middlewares.run(verified).await;
+        Ok(())
+    }
+
+    /// Chain contracts are verified for. Verified contracts are stored in its namespace.
+    pub fn chain(&self) -> &Chain {
+        &self.persist.chain
+    }
+
+    /// Database verified contracts are stored in.
+    pub async fn database(&self) -> DB {
+        self.persist.database().await
+    }
+
+    /// Encoding newly stored sources and compiler inputs are compressed with.
+    pub fn encoding(&self) -> ContentEncoding {
+        self.persist.encoding
+    }
+
+    /// Keeps large sources of stored contracts in the blob store, if it is enabled.
+    pub fn with_blobs(mut self, blobs: Option<Arc<BlobStore>>) -> Self {
+        self.persist.blobs = blobs;
+        self
+    }
+
+    pub fn with_encoding(mut self, encoding: ContentEncoding) -> Self {
+        self.persist.encoding = encoding;
+        self
+    }
+
+    /// Warns about contracts named as well-known ones, which code differs
+    /// from the canonical deployments (see `impersonation` settings).
+    pub fn with_canonical_deployments(mut self, canonical: Option<CanonicalDeployments>) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Debug info of the successful verification, if it is to be stored.
+    /// Is not extracted from the compiler output otherwise.
+    pub fn debug_info(
+        &self,
+        contract_address: &str,
+        success: &VerificationSuccess,
+    ) -> Option<DebugInfo> {
+        if !self.persist.store_debug_info {
+            return None;
+        }
+        DebugInfo::new(contract_address, success)
+    }
+
+    /// Converts the successful verification into the result returned and stored,
+    /// along with the impersonation warning (if enabled).
+    pub fn verification_result(&self, success: VerificationSuccess) -> VerificationResult {
+        let mut result = VerificationResult::from(success);
+        result.impersonation_warning = self.canonical.as_ref().and_then(|canonical| {
+            canonical.check(&result.contract_name, result.code_hash.as_deref())
+        });
+        result
+    }
+
+    /// Names of the steps in the order they are invoked.
+    pub fn names(&self) -> Vec<&'static str> {
+        std::iter::once("persist")
+            .chain(self.middlewares.names())
+            .collect()
+    }
+}
+
+/// Middlewares used by the service: the verified contract is persisted first,
+/// then availability of its metadata is checked and its sources are analyzed (if enabled),
+/// and then subscribers are notified about it.
+pub fn result_middlewares(
+    chain: Chain,
+    fetcher: Arc<BytecodeFetcher>,
+    notifier: Notifier,
+    store_debug_info: bool,
+    creation_lookup_timeout: Option<Duration>,
+    metadata_availability: Option<MetadataAvailabilityCheck>,
+    analysis: Option<StaticAnalysis>,
+) -> ResultMiddlewares {
+    let persist = PersistMiddleware {
+        chain,
+        blobs: None,
+        encoding: ContentEncoding::Identity,
+        fetcher,
+        store_debug_info,
+        creation_lookup_timeout,
+    };
+    let mut middlewares = Composition::new();
+    if let Some(metadata_availability) = metadata_availability {
+        middlewares = middlewares.with(metadata_availability);
+    }
+    if let Some(analysis) = analysis {
+        middlewares = middlewares.with(analysis);
+    }
+    ResultMiddlewares {
+        persist,
+        middlewares: middlewares.with(notifier),
+        canonical: None,
+    }
+}
+
+/// Stores verified contracts into the database along with their compiler inputs,
+/// and indexes their signatures and clones. Creation transactions and token metadata
+/// are read from the chain after the contracts have been stored.
+///
+/// If the contract has been re-verified by an admin, the previous verification is retained
+/// as history first. If that fails, the new verification is not stored.
+///
+/// Unlike the rest of the middlewares, failures are returned to the client, since the
+/// verification is lost otherwise.
+pub struct PersistMiddleware {
+    /// Chain contracts are verified for. Contracts are stored in its namespace.
+    chain: Chain,
+    /// Large sources are kept in the blob store, if it is enabled.
+    blobs: Option<Arc<BlobStore>>,
+    /// Encoding sources are compressed with, if they are not kept in the blob store.
+    encoding: ContentEncoding,
+    /// Rpc providers of the chain contracts are verified for.
+    fetcher: Arc<BytecodeFetcher>,
+    /// If set, debug info of the contract is stored as well.
+    store_debug_info: bool,
+    /// Max time the creation transaction of the contract is looked up for.
+    /// If not set, creation transactions are not looked up.
+    creation_lookup_timeout: Option<Duration>,
+}
+
+impl PersistMiddleware {
+    async fn database(&self) -> DB {
+        DB::new()
+            .await
+            .change_name(&self.chain.namespace())
+            .with_blobs(self.blobs.clone())
+            .with_encoding(self.encoding)
+    }
+
+    /// Locates the transaction the contract has been created by and reads its token metadata
+    /// in the background, and sets them to the stored contract. Contracts are stored beforehand,
+    /// so that verification responses are not delayed by rpc providers, and contracts are stored
+    /// even if these cannot be read (e.g., rpc providers are not archive nodes).
+    fn spawn_chain_lookups(&self, contract_address: String, standards: Vec<Standard>) {
+        let (fetcher, timeout) = (self.fetcher.clone(), self.creation_lookup_timeout);
+        let namespace = self.chain.namespace();
+        tokio::spawn(async move {
+            let creation = async { find_creation(&fetcher, &contract_address, timeout?).await };
+            let (creation, token_info) = futures::join!(
+                creation,
+                token_info::fetch(&fetcher, &contract_address, &standards)
+            );
+            let database = DB::new().await.change_name(&namespace);
+            if let Some(creation) = creation {
+                if let Err(err) = database
+                    .set_contract_creation(&contract_address, &creation)
+                    .await
+                {
+                    tracing::warn!("cannot store creation of {}: {}", contract_address, err);
+                }
+            }
+            if let Some(token_info) = token_info {
+                if let Err(err) = database
+                    .set_token_info(&contract_address, &token_info)
+                    .await
+                {
+                    tracing::warn!("cannot store token info of {}: {}", contract_address, err);
+                }
+            }
+        });
+    }
+
+    #[tracing::instrument(
+        name = "persist_contract",
+        skip_all,
+        fields(contract_address = %verified.contract_address),
+        level = "debug"
+    )]
+    async fn persist(&self, verified: &VerifiedContract) -> Result<(), anyhow::Error> {
+        let database = self.database().await;
+        if let Some(admin) = &verified.replaced_by {
+            database
+                .retire_contract_verify_responses(
+                    &verified.contract_address,
+                    AdminActionKind::Replaced,
+                    admin,
+                )
+                .await
+                .map_err(|err| anyhow!(err))?;
+        }
+
+        if let Some(debug_info) = verified
+            .debug_info
+            .as_ref()
+            .filter(|_| self.store_debug_info)
+        {
+            // Debugging data is auxiliary, so the verification is stored anyway
+            if let Err(err) = database.add_debug_info(debug_info.clone()).await {
+                tracing::error!(
+                    "cannot store debug info of {}: {}",
+                    verified.contract_address,
+                    err
+                );
+            }
+        }
+
+        if let Some(compiler_input) = &verified.compiler_input {
+            if let Err(err) = database.add_compiler_input(compiler_input.clone()).await {
+                tracing::error!(
+                    "cannot archive compiler input of {}: {}",
+                    verified.contract_address,
+                    err
+                );
+            }
+        }
+
+        let cvr = verified.to_contract_verify_response();
+        // Index function and event signatures of the verified contract
+        signatures::record(&database, &cvr.result).await;
+        let (contract_address, code_hash, standards) = (
+            cvr.contract_address.clone(),
+            cvr.result.code_hash.clone(),
+            cvr.result.standards.clone(),
+        );
+        database
+            .add_contract_verify_response(cvr)
+            .await
+            .map_err(|err| anyhow!(err))?;
+        // Clones of the contract are verified by similarity in the background
+        derived::spawn_fan_out(
+            self.fetcher.clone(),
+            self.chain.namespace(),
+            contract_address.clone(),
+            code_hash,
+        );
+        self.spawn_chain_lookups(contract_address, standards);
+        Ok(())
+    }
+}
+
+/// Locates the transaction the contract has been created by, giving up after the timeout.
+async fn find_creation(
+    fetcher: &BytecodeFetcher,
+    contract_address: &str,
+    timeout: Duration,
+) -> Option<ContractCreation> {
+    let lookup = fetcher.find_creation_transaction(contract_address);
+    match tokio::time::timeout(timeout, lookup).await {
+        Ok(Ok(tx)) => tx.and_then(ContractCreation::from_transaction),
+        Ok(Err(err)) => {
+            tracing::warn!(
+                "cannot locate creation transaction of {}: {}",
+                contract_address,
+                err
+            );
+            None
+        }
+        Err(_) => {
+            tracing::warn!(
+                "creation transaction of {} has not been located in time",
+                contract_address
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        analysis::{BasicChecks, FindingSeverity},
+        chains::Chains,
+        settings::MetadataAvailabilitySettings,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn middlewares_are_configured() {
+        let chain = Chains::default().current().clone();
+        let notifier = Notifier::new(chain.clone());
+        assert_eq!(
+            vec!["persist", "notify"],
+            result_middlewares(
+                chain.clone(),
+                Arc::default(),
+                notifier.clone(),
+                false,
+                None,
+                None,
+                None
+            )
+            .names()
+        );
+        let settings = MetadataAvailabilitySettings {
+            enabled: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            vec!["persist", "metadata_availability", "notify"],
+            result_middlewares(
+                chain.clone(),
+                Arc::default(),
+                notifier.clone(),
+                false,
+                None,
+                Some(
+                    MetadataAvailabilityCheck::new(settings, chain.clone(), Arc::default())
+                        .unwrap()
+                ),
+                None
+            )
+            .names()
+        );
+        assert_eq!(
+            vec!["persist", "analysis", "notify"],
+            result_middlewares(
+                chain.clone(),
+                Arc::default(),
+                notifier,
+                false,
+                None,
+                None,
+                Some(StaticAnalysis::new(FindingSeverity::default(), chain).with(BasicChecks))
+            )
+            .names()
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::api_error::{ApiError, ErrorCode};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Stateful precompile of Evmos: a native module exposed to the EVM at a fixed address.
+/// Precompiles have no code to be verified, so their canonical ABIs are served instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Precompile {
+    /// Lowercase address the precompile is exposed at.
+    pub address: &'static str,
+    /// Cosmos SDK module the precompile exposes.
+    pub module: &'static str,
+    /// Name of the solidity interface of the precompile.
+    pub contract_name: &'static str,
+    abi: &'static str,
+}
+
+const PRECOMPILES: &[Precompile] = &[
+    Precompile {
+        address: "0x0000000000000000000000000000000000000800",
+        module: "staking",
+        contract_name: "StakingI",
+        abi: include_str!("precompiles/staking.json"),
+    },
+    Precompile {
+        address: "0x0000000000000000000000000000000000000801",
+        module: "distribution",
+        contract_name: "DistributionI",
+        abi: include_str!("precompiles/distribution.json"),
+    },
+    Precompile {
+        address: "0x0000000000000000000000000000000000000802",
+        module: "ibc-transfer",
+        contract_name: "ICS20I",
+        abi: include_str!("precompiles/ics20.json"),
+    },
+];
+
+/// Returns the precompile exposed at the address, if any.
+pub fn find(address: &str) -> Option<&'static Precompile> {
+    PRECOMPILES
+        .iter()
+        .find(|precompile| precompile.address.eq_ignore_ascii_case(address))
+}
+
+/// Fails if the address is taken by a precompile, which cannot be verified.
+pub fn ensure_not_precompile(address: &str) -> Result<(), ApiError> {
+    match find(address) {
+        Some(precompile) => Err(ApiError::new(
+            ErrorCode::PrecompileAddress,
+            format!(
+                "Contract {address} is the {} precompile of Evmos, which has no code to be verified; \
+                its abi is served by the lookup endpoints instead",
+                precompile.module
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+impl Precompile {
+    pub fn abi(&self) -> Value {
+        serde_json::from_str(self.abi).expect("abis of precompiles are valid")
+    }
+
+    /// Is returned by the contract lookup endpoint in place of a stored verification.
+    pub fn lookup(&self) -> PrecompileLookup {
+        PrecompileLookup {
+            contract_address: self.address,
+            status: "verified (precompile)",
+            precompile: self.module,
+            result: PrecompileResult {
+                contract_name: self.contract_name,
+                abi: self.abi().to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct PrecompileLookup {
+    pub contract_address: &'static str,
+    pub status: &'static str,
+    pub precompile: &'static str,
+    pub result: PrecompileResult,
+}
+
+/// Mirrors the fields of stored verification results which apply to precompiles.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct PrecompileResult {
+    pub contract_name: &'static str,
+    /// Json encoded abi, as in stored verification results.
+    pub abi: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn precompiles_are_registered() {
+        for precompile in PRECOMPILES {
+            serde_json::from_str::<ethabi::Contract>(precompile.abi)
+                .unwrap_or_else(|err| panic!("abi of {} is invalid: {err}", precompile.module));
+        }
+        let staking = find("0x0000000000000000000000000000000000000800").unwrap();
+        assert_eq!("StakingI", staking.contract_name);
+        assert!(find("0x0000000000000000000000000000000000000803").is_none());
+
+        let err = ensure_not_precompile("0x0000000000000000000000000000000000000802").unwrap_err();
+        assert_eq!(ErrorCode::PrecompileAddress, err.code);
+        assert!(ensure_not_precompile("0xcafecafecafecafecafecafecafecafecafecafe").is_ok());
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{
+    chains::Chain,
+    derived::{self, DerivedVerification},
+    leader,
+    standards::Standard,
+    tenants, DisplayBytes, DB,
+};
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::{
+    rpc::{BlockId, BytecodeFetcher},
+    scheduler,
+};
+use std::{str::FromStr, sync::Arc};
+
+/// EIP-1967 slot the proxy keeps its implementation in
+/// (`keccak256("eip1967.proxy.implementation") - 1`).
+pub(crate) const IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+/// EIP-1967 slot the beacon proxy keeps its beacon in (`keccak256("eip1967.proxy.beacon") - 1`).
+pub(crate) const BEACON_SLOT: &str =
+    "0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+/// Selector of `implementation()` function of the beacon.
+const IMPLEMENTATION_SELECTOR: &str = "0x5c60da1b";
+
+const WATCHER_JOB: &str = "proxy_history";
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImplementationStatus {
+    Verified,
+    /// The implementation is a clone of a verified contract (see [`derived::find_source`]),
+    /// and has been marked as verified by similarity.
+    Derived,
+    /// Verification is attempted again on the next runs of the watcher.
+    Unverified,
+}
+
+/// Implementation the proxy has been observed to delegate calls to.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ImplementationChange {
+    pub proxy_address: String,
+    pub implementation: String,
+    /// Block the implementation has been first observed at. Upgrades made between
+    /// the runs of the watcher are observed at the block of the next run.
+    pub block_number: u64,
+    pub detected_at: DateTime<Utc>,
+    pub status: ImplementationStatus,
+    /// The verified contract the implementation has been derived from.
+    #[serde(default)]
+    pub source_address: Option<String>,
+}
+
+/// Periodically reads EIP-1967 slots of the verified proxies of the default namespace and
+/// of the tenants, and records their new implementations. Of several replicas of the service,
+/// each scheduled run is made by one only.
+pub fn spawn_watcher(
+    schedule: Schedule,
+    fetcher: Arc<BytecodeFetcher>,
+    chain: Chain,
+    tenants: Vec<String>,
+) {
+    let tenants: Vec<_> = std::iter::once(None)
+        .chain(tenants.into_iter().map(Some))
+        .collect();
+    let job_schedule = schedule.clone();
+    scheduler::spawn_job(job_schedule, "watch proxy implementations", move || {
+        let fetcher = fetcher.clone();
+        let schedule = schedule.clone();
+        let chain = chain.clone();
+        let tenants = tenants.clone();
+        async move {
+            let database = DB::new().await.change_name(&chain.namespace());
+            match leader::claim_scheduled_run(&database, WATCHER_JOB, &schedule).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    tracing::debug!("proxy implementations are watched by another replica");
+                    return;
+                }
+                Err(err) => {
+                    tracing::error!("cannot watch proxy implementations: {}", err);
+                    return;
+                }
+            }
+            let block_number = match fetcher.block_number().await {
+                Ok(block_number) => block_number,
+                Err(err) => {
+                    tracing::error!("cannot fetch the latest block number: {:#}", err);
+                    return;
+                }
+            };
+            for tenant in tenants {
+                let watched = tenants::scope(tenant.clone(), async {
+                    let database = DB::new().await.change_name(&chain.namespace());
+                    watch(&database, &fetcher, block_number).await
+                });
+                if let Err(err) = watched.await {
+                    tracing::error!(?tenant, "cannot watch proxy implementations: {}", err);
+                }
+            }
+        }
+    });
+}
+
+async fn watch(database: &DB, fetcher: &BytecodeFetcher, block_number: u64) -> Result<(), String> {
+    for (standard, beacon) in [
+        (Standard::Eip1967Proxy, false),
+        (Standard::Eip1967BeaconProxy, true),
+    ] {
+        for proxy_address in database.find_contracts_by_standard(standard).await? {
+            let watched = watch_proxy(database, fetcher, &proxy_address, beacon, block_number);
+            if let Err(err) = watched.await {
+                tracing::warn!("cannot watch proxy {}: {}", proxy_address, err);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Records the current implementation of the proxy if it differs from the latest recorded one.
+/// Verification of the recorded implementation is retried until it succeeds.
+async fn watch_proxy(
+    database: &DB,
+    fetcher: &BytecodeFetcher,
+    proxy_address: &str,
+    beacon: bool,
+    block_number: u64,
+) -> Result<(), String> {
+    let implementation = match current_implementation(fetcher, proxy_address, beacon, block_number)
+        .await
+        .map_err(|err| format!("cannot read the implementation: {err:#}"))?
+    {
+        Some(implementation) => implementation,
+        // The proxy has not been initialized yet
+        None => return Ok(()),
+    };
+
+    match database
+        .find_latest_implementation_change(proxy_address)
+        .await?
+    {
+        Some(latest) if latest.implementation == implementation => {
+            if latest.status != ImplementationStatus::Unverified {
+                return Ok(());
+            }
+            let (status, source_address) =
+                verify_implementation(database, fetcher, &implementation).await?;
+            if status != ImplementationStatus::Unverified {
+                database
+                    .set_implementation_status(&ImplementationChange {
+                        status,
+                        source_address,
+                        ..latest
+                    })
+                    .await?;
+            }
+            Ok(())
+        }
+        _ => {
+            let (status, source_address) =
+                verify_implementation(database, fetcher, &implementation).await?;
+            tracing::info!(
+                ?status,
+                block_number,
+                "proxy {} has been upgraded to {}",
+                proxy_address,
+                implementation
+            );
+            database
+                .add_implementation_change(ImplementationChange {
+                    proxy_address: proxy_address.to_string(),
+                    implementation,
+                    block_number,
+                    detected_at: Utc::now(),
+                    status,
+                    source_address,
+                })
+                .await
+        }
+    }
+}
+
+/// Reads the implementation slot of the proxy, or the beacon slot and then
+/// the implementation of the beacon for beacon proxies, both at the given block.
+async fn current_implementation(
+    fetcher: &BytecodeFetcher,
+    proxy_address: &str,
+    beacon: bool,
+    block_number: u64,
+) -> anyhow::Result<Option<String>> {
+    let slot = if beacon {
+        BEACON_SLOT
+    } else {
+        IMPLEMENTATION_SLOT
+    };
+    let value = fetcher
+        .get_storage_at(proxy_address, slot, BlockId::Number(block_number))
+        .await?;
+    let address = match value.as_deref().and_then(address_from_word) {
+        Some(address) => address,
+        None => return Ok(None),
+    };
+    if !beacon {
+        return Ok(Some(address));
+    }
+    let value = fetcher
+        .call_at(
+            &address,
+            IMPLEMENTATION_SELECTOR,
+            BlockId::Number(block_number),
+        )
+        .await?;
+    Ok(value.as_deref().and_then(address_from_word))
+}
+
+/// Returns the address kept in the lower 20 bytes of the 32 bytes word, unless it is zero.
+fn address_from_word(word: &str) -> Option<String> {
+    let word = DisplayBytes::from_str(word).ok()?;
+    if word.0.len() != 32 || word.0[..12].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    let address = &word.0[12..];
+    address
+        .iter()
+        .any(|byte| *byte != 0)
+        .then(|| format!("0x{}", hex::encode(address)))
+}
+
+/// Checks whether the implementation is verified. Otherwise, if it is a clone
+/// of a verified contract, marks it as verified by similarity with that contract.
+async fn verify_implementation(
+    database: &DB,
+    fetcher: &BytecodeFetcher,
+    implementation: &str,
+) -> Result<(ImplementationStatus, Option<String>), String> {
+    if database.is_contract_verified(implementation).await? {
+        return Ok((ImplementationStatus::Verified, None));
+    }
+
+    let code = fetcher
+        .get_code(implementation)
+        .await
+        .map_err(|err| format!("cannot fetch the code of {implementation}: {err:#}"))?;
+    let code = match code.as_deref().map(DisplayBytes::from_str) {
+        Some(Ok(code)) if !code.0.is_empty() => code,
+        _ => return Ok((ImplementationStatus::Unverified, None)),
+    };
+    match derived::find_source(database, implementation, &code.0).await? {
+        Some((source_address, kind)) => {
+            database
+                .add_derived_verification(DerivedVerification {
+                    contract_address: implementation.to_string(),
+                    source_address: source_address.clone(),
+                    kind,
+                    derived_at: Utc::now(),
+                })
+                .await?;
+            Ok((ImplementationStatus::Derived, Some(source_address)))
+        }
+        None => Ok((ImplementationStatus::Unverified, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn addresses_are_read_from_words() {
+        assert_eq!(
+            Some("0xbebebebebebebebebebebebebebebebebebebebe".to_string()),
+            address_from_word("0x000000000000000000000000bebebebebebebebebebebebebebebebebebebebe")
+        );
+        // Uninitialized slot
+        assert_eq!(
+            None,
+            address_from_word("0x0000000000000000000000000000000000000000000000000000000000000000")
+        );
+        // Not an address
+        assert_eq!(
+            None,
+            address_from_word("0x010000000000000000000000bebebebebebebebebebebebebebebebebebebebe")
+        );
+        assert_eq!(None, address_from_word("0x"));
+    }
+}
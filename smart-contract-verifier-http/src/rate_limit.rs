@@ -0,0 +1,350 @@
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    auth::AdminAuth,
+    settings::{CounterStoreSettings, RateBudget, RateLimitSettings, RouteBudgets},
+};
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderValue},
+        Method,
+    },
+    web, Error, HttpRequest, ResponseError,
+};
+use async_trait::async_trait;
+use futures::future::LocalBoxFuture;
+use redis::aio::ConnectionManager;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Only requests to the api are limited (health checks and metrics are not).
+const API_PREFIX: &str = "/api/v1/";
+
+/// Prefixes of the routes verifying or otherwise compiling contracts (for all methods but `GET`).
+/// Verification requests are limited with the heavy budget, and are counted towards quotas of tenants.
+const VERIFICATION_PREFIXES: [&str; 5] = [
+    "/api/v1/solidity/",
+    "/api/v1/vyper/",
+    "/api/v1/huff/",
+    "/api/v1/sourcify/",
+    // Init code of the contract may be compiled from the sources
+    "/api/v1/tools/compute-address",
+];
+
+/// Prefixes of the tool routes which do not compile contracts, but take quadratic time to respond
+/// to (bytecodes and sources are aligned by their longest common subsequence). They are limited
+/// with the heavy budget as well (for all methods), but are not counted towards quotas of tenants.
+const COMPUTATION_PREFIXES: [&str; 2] =
+    ["/api/v1/tools/bytecode-diff", "/api/v1/tools/source-diff"];
+
+/// Whether the request verifies contracts, compiling them (see [`VERIFICATION_PREFIXES`]).
+pub fn is_verification(request: &ServiceRequest) -> bool {
+    request.method() != Method::GET
+        && VERIFICATION_PREFIXES
+            .iter()
+            .any(|prefix| request.path().starts_with(prefix))
+}
+
+/// Address of the client which has sent the request. Forwarded headers are only
+/// taken into account if the service runs behind trusted proxies, as they can be
+/// set to anything by the clients otherwise.
+pub fn client_ip(request: &HttpRequest, trust_forwarded_headers: bool) -> Option<String> {
+    if trust_forwarded_headers {
+        let address = request.connection_info().realip_remote_addr()?.to_string();
+        // Proxies may pass the port along with the address
+        return Some(match address.parse::<std::net::SocketAddr>() {
+            Ok(address) => address.ip().to_string(),
+            Err(_) => address,
+        });
+    }
+    request.peer_addr().map(|address| address.ip().to_string())
+}
+
+const REDIS_KEY_PREFIX: &str = "rate_limit:";
+
+/// Store of request counters, which expire after the given time to live.
+#[async_trait]
+pub trait CounterStore: Send + Sync {
+    /// Increments the counter, returning its new value.
+    async fn increment(&self, key: &str, ttl: Duration) -> anyhow::Result<u64>;
+}
+
+/// Counters local to the instance.
+#[derive(Default)]
+pub struct InMemoryCounters {
+    counters: Mutex<HashMap<String, (Instant, u64)>>,
+}
+
+#[async_trait]
+impl CounterStore for InMemoryCounters {
+    async fn increment(&self, key: &str, ttl: Duration) -> anyhow::Result<u64> {
+        let now = Instant::now();
+        let mut counters = self.counters.lock().expect("lock is not poisoned");
+        counters.retain(|_, (expires_at, _)| *expires_at > now);
+        let (_, count) = counters
+            .entry(key.to_string())
+            .or_insert_with(|| (now + ttl, 0));
+        *count += 1;
+        Ok(*count)
+    }
+}
+
+/// Counters shared by all instances connected to the same redis.
+pub struct RedisCounters {
+    connection: ConnectionManager,
+}
+
+impl RedisCounters {
+    pub async fn connect(url: &str) -> anyhow::Result<Self> {
+        let client = redis::Client::open(url)?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(Self { connection })
+    }
+}
+
+#[async_trait]
+impl CounterStore for RedisCounters {
+    async fn increment(&self, key: &str, ttl: Duration) -> anyhow::Result<u64> {
+        let key = format!("{REDIS_KEY_PREFIX}{key}");
+        let mut connection = self.connection.clone();
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(&key, 1)
+            .expire(&key, ttl.as_secs() as usize)
+            .ignore()
+            .query_async(&mut connection)
+            .await?;
+        Ok(count)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RouteClass {
+    /// Routes compiling contracts, or otherwise taking long to respond to.
+    Heavy,
+    /// Read-only routes.
+    Light,
+}
+
+impl RouteClass {
+    fn of(request: &ServiceRequest) -> Option<Self> {
+        if !request.path().starts_with(API_PREFIX) {
+            return None;
+        }
+        let is_computation = COMPUTATION_PREFIXES
+            .iter()
+            .any(|prefix| request.path().starts_with(prefix));
+        match is_verification(request) || is_computation {
+            true => Some(RouteClass::Heavy),
+            false => Some(RouteClass::Light),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            RouteClass::Heavy => "heavy",
+            RouteClass::Light => "light",
+        }
+    }
+
+    fn budget(&self, budgets: &RouteBudgets) -> RateBudget {
+        match self {
+            RouteClass::Heavy => budgets.heavy,
+            RouteClass::Light => budgets.light,
+        }
+    }
+}
+
+/// Limits requests per client ip address and for all clients together
+/// using fixed windows of the budget periods.
+pub struct RateLimiter {
+    store: Arc<dyn CounterStore>,
+    settings: RateLimitSettings,
+}
+
+impl RateLimiter {
+    pub async fn new(settings: RateLimitSettings) -> anyhow::Result<Self> {
+        let store: Arc<dyn CounterStore> = match &settings.store {
+            CounterStoreSettings::Memory => Arc::new(InMemoryCounters::default()),
+            CounterStoreSettings::Redis(redis) => {
+                Arc::new(RedisCounters::connect(&redis.url).await?)
+            }
+        };
+        Ok(Self::with_store(store, settings))
+    }
+
+    pub fn with_store(store: Arc<dyn CounterStore>, settings: RateLimitSettings) -> Self {
+        Self { store, settings }
+    }
+
+    /// Counts the request, returning the number of seconds the client should wait
+    /// before retrying if the request exceeds any of the budgets.
+    /// Counters which cannot be incremented are not taken into account.
+    async fn check(&self, class: RouteClass, client_ip: Option<String>) -> Option<u64> {
+        let mut budgets = vec![];
+        if let Some(client_ip) = client_ip {
+            budgets.push((
+                format!("ip:{client_ip}"),
+                class.budget(&self.settings.per_ip),
+            ));
+        }
+        if let Some(global) = &self.settings.global {
+            budgets.push(("global".to_string(), class.budget(global)));
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Is after unix epoch")
+            .as_secs();
+        let mut retry_after = None;
+        for (key, budget) in budgets {
+            let period = budget.period_secs.get();
+            let window = now / period;
+            let key = format!("{}:{key}:{window}", class.name());
+            match self
+                .store
+                .increment(&key, Duration::from_secs(period))
+                .await
+            {
+                Ok(count) if count > u64::from(budget.requests.get()) => {
+                    let wait = period - now % period;
+                    retry_after = retry_after.max(Some(wait));
+                }
+                Ok(_) => {}
+                Err(err) => tracing::warn!("cannot increment rate limit counter: {:#}", err),
+            }
+        }
+        retry_after
+    }
+}
+
+/// Rejects requests exceeding the budgets with 429 Too Many Requests, if rate limiting is enabled.
+/// Requests made with admin api keys are not limited.
+pub fn limit<S, B>(
+    request: ServiceRequest,
+    service: &S,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    let is_admin = request
+        .app_data::<web::Data<AdminAuth>>()
+        .and_then(|auth| auth.authenticate(request.request()))
+        .is_some();
+    let limited = request
+        .app_data::<web::Data<RateLimiter>>()
+        .cloned()
+        .zip(RouteClass::of(&request))
+        .filter(|_| !is_admin);
+    let (limiter, class) = match limited {
+        Some(limited) => limited,
+        None => {
+            let response = service.call(request);
+            return Box::pin(async move { Ok(response.await?.map_into_boxed_body()) });
+        }
+    };
+
+    let client_ip = client_ip(request.request(), limiter.settings.trust_forwarded_headers);
+    // Service futures are lazy, so handlers of rejected requests are never run
+    let response = service.call(request);
+    Box::pin(async move {
+        if let Some(retry_after) = limiter.check(class, client_ip).await {
+            drop(response);
+            return Err(too_many_requests(retry_after));
+        }
+        Ok(response.await?.map_into_boxed_body())
+    })
+}
+
+fn too_many_requests(retry_after: u64) -> Error {
+    let err = ApiError::new(
+        ErrorCode::TooManyRequests,
+        format!("Rate limit exceeded, retry in {retry_after} seconds"),
+    )
+    .with_details(serde_json::json!({ "retry_after": retry_after }));
+    let mut response = err.error_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    actix_web::error::InternalError::from_response(err, response).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn routes_are_classified() {
+        let class = |request: TestRequest| RouteClass::of(&request.to_srv_request());
+        assert_eq!(
+            Some(RouteClass::Heavy),
+            class(TestRequest::post().uri("/api/v1/solidity/verify/multiple-files"))
+        );
+        assert_eq!(
+            Some(RouteClass::Heavy),
+            class(TestRequest::post().uri("/api/v1/vyper/verify/multiple-files"))
+        );
+        assert_eq!(
+            Some(RouteClass::Heavy),
+            class(TestRequest::post().uri("/api/v1/sourcify/verify"))
+        );
+        assert_eq!(
+            Some(RouteClass::Heavy),
+            class(TestRequest::post().uri("/api/v1/tools/compute-address"))
+        );
+        assert_eq!(
+            Some(RouteClass::Heavy),
+            class(TestRequest::post().uri("/api/v1/tools/bytecode-diff"))
+        );
+        assert_eq!(
+            Some(RouteClass::Heavy),
+            class(TestRequest::get().uri("/api/v1/tools/source-diff?from=0x01&to=0x02"))
+        );
+        assert_eq!(
+            Some(RouteClass::Light),
+            class(TestRequest::get().uri("/api/v1/solidity/versions"))
+        );
+        assert_eq!(
+            Some(RouteClass::Light),
+            class(TestRequest::post().uri("/api/v1/tools/abi/decode"))
+        );
+        assert_eq!(None, class(TestRequest::get().uri("/health")));
+    }
+
+    #[tokio::test]
+    async fn budgets_are_enforced() {
+        let mut settings = RateLimitSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        settings.per_ip.heavy = RateBudget::new(2, 3600);
+        settings.global = Some(RouteBudgets {
+            heavy: RateBudget::new(3, 3600),
+            light: RateBudget::new(100, 3600),
+        });
+        let limiter = RateLimiter::with_store(Arc::new(InMemoryCounters::default()), settings);
+        let ip = |ip: &str| Some(ip.to_string());
+
+        assert_eq!(None, limiter.check(RouteClass::Heavy, ip("10.0.0.1")).await);
+        assert_eq!(None, limiter.check(RouteClass::Heavy, ip("10.0.0.1")).await);
+        assert!(limiter
+            .check(RouteClass::Heavy, ip("10.0.0.1"))
+            .await
+            .is_some());
+        // Light routes have a separate budget
+        assert_eq!(None, limiter.check(RouteClass::Light, ip("10.0.0.1")).await);
+        // Other clients are limited by the global budget only
+        assert!(limiter
+            .check(RouteClass::Heavy, ip("10.0.0.2"))
+            .await
+            .is_some());
+    }
+}
@@ -0,0 +1,78 @@
+use crate::api_error::{ApiError, ErrorCode};
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::Method,
+    web, Error,
+};
+use futures::future::LocalBoxFuture;
+
+/// Prefixes of the routes changing the state of the service (verification, admin actions)
+/// or compiling contracts, which are rejected for all methods but `GET`.
+const WRITE_PREFIXES: [&str; 6] = [
+    "/api/v1/solidity/",
+    "/api/v1/vyper/",
+    "/api/v1/huff/",
+    "/api/v1/sourcify/",
+    "/api/v1/admin/",
+    "/api/v1/uploads",
+];
+
+/// Is registered as app data if the service runs in read-only mode.
+pub struct ReadOnly;
+
+fn is_write(request: &ServiceRequest) -> bool {
+    let read = [Method::GET, Method::HEAD, Method::OPTIONS].contains(request.method());
+    !read
+        && WRITE_PREFIXES
+            .iter()
+            .any(|prefix| request.path().starts_with(prefix))
+}
+
+/// Rejects verification submissions and admin actions with 503 Service Unavailable,
+/// if the service runs in read-only mode. Lookups and read-only tools are served as usual.
+pub fn reject_writes<S, B>(
+    request: ServiceRequest,
+    service: &S,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    if request.app_data::<web::Data<ReadOnly>>().is_some() && is_write(&request) {
+        let err = ApiError::new(
+            ErrorCode::ReadOnly,
+            "The service runs in read-only mode and does not accept verification requests",
+        );
+        return Box::pin(async move { Err(err.into()) });
+    }
+    let response = service.call(request);
+    Box::pin(async move { Ok(response.await?.map_into_boxed_body()) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn writes_are_detected() {
+        let is_write = |request: TestRequest| super::is_write(&request.to_srv_request());
+        assert!(is_write(
+            TestRequest::post().uri("/api/v1/solidity/verify/multiple-files")
+        ));
+        assert!(is_write(
+            TestRequest::delete().uri("/api/v1/admin/contracts/0xcafe")
+        ));
+        assert!(!is_write(
+            TestRequest::get().uri("/api/v1/solidity/versions")
+        ));
+        assert!(!is_write(
+            TestRequest::post().uri("/api/v1/tools/abi-decode")
+        ));
+        assert!(!is_write(
+            TestRequest::post().uri("/api/v1/selectors/lookup")
+        ));
+    }
+}
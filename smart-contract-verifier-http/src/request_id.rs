@@ -0,0 +1,90 @@
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    Error, HttpMessage,
+};
+use std::future::Future;
+use tracing::Span;
+use tracing_actix_web::{DefaultRootSpanBuilder, RequestId, RootSpanBuilder};
+
+/// The header request ids are accepted from clients and returned to them through.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Returns the id provided by the client in `x-request-id` header, if valid,
+/// or the id generated for the request by `TracingLogger` otherwise.
+pub fn request_id(request: &ServiceRequest) -> Option<String> {
+    let provided = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LENGTH);
+    match provided {
+        Some(request_id) => Some(request_id.to_string()),
+        None => request
+            .extensions()
+            .get::<RequestId>()
+            .map(|request_id| request_id.to_string()),
+    }
+}
+
+/// Attaches the request id to the root span of the request, so that
+/// all spans of the request (compilation, rpc and database calls) could be correlated by it.
+pub struct RequestIdRootSpanBuilder;
+
+impl RootSpanBuilder for RequestIdRootSpanBuilder {
+    fn on_request_start(request: &ServiceRequest) -> Span {
+        let correlation_id = request_id(request).unwrap_or_default();
+        tracing_actix_web::root_span!(request, correlation_id = %correlation_id)
+    }
+
+    fn on_request_end<B>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+        DefaultRootSpanBuilder::on_request_end(span, outcome)
+    }
+}
+
+/// Returns the request id to the client in `x-request-id` header.
+/// Should be registered inside of `TracingLogger` middleware.
+pub fn set_response_header<S, B>(
+    request: ServiceRequest,
+    service: &S,
+) -> impl Future<Output = Result<ServiceResponse<B>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    let request_id = request_id(&request);
+    let response = service.call(request);
+    async move {
+        let mut response = response.await?;
+        if let Some(value) = request_id.and_then(|id| HeaderValue::from_str(&id).ok()) {
+            response
+                .headers_mut()
+                .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn provided_request_id_is_used() {
+        let request = TestRequest::default()
+            .insert_header((REQUEST_ID_HEADER, "some-request-id"))
+            .to_srv_request();
+        assert_eq!(Some("some-request-id".to_string()), request_id(&request));
+    }
+
+    #[test]
+    fn invalid_request_id_is_ignored() {
+        let request = TestRequest::default()
+            .insert_header((REQUEST_ID_HEADER, "a".repeat(MAX_REQUEST_ID_LENGTH + 1)))
+            .to_srv_request();
+        assert_eq!(None, request_id(&request));
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{chains::Chain, DB};
+use cron::Schedule;
+use smart_contract_verifier::{scheduler, SolidityClient, Version};
+use std::{collections::HashSet, str::FromStr, sync::Arc};
+
+/// Keeps compilers referenced by stored contracts (in addition to the configured ones)
+/// from being removed from the disk. The set of referenced versions is loaded
+/// on start and then refreshed on the given schedule.
+pub fn spawn_retention_job(
+    client: Arc<SolidityClient>,
+    configured: HashSet<Version>,
+    schedule: Schedule,
+    chain: Chain,
+) {
+    client.compilers().retain_versions(configured.clone());
+    let configured = Arc::new(configured);
+    let namespace = Arc::new(chain.namespace());
+
+    {
+        let client = client.clone();
+        let configured = configured.clone();
+        let namespace = namespace.clone();
+        tokio::spawn(
+            async move { refresh_retained_versions(&client, &configured, &namespace).await },
+        );
+    }
+    scheduler::spawn_job(schedule, "refresh retained compilers", move || {
+        let client = client.clone();
+        let configured = configured.clone();
+        let namespace = namespace.clone();
+        async move { refresh_retained_versions(&client, &configured, &namespace).await }
+    });
+}
+
+async fn refresh_retained_versions(
+    client: &SolidityClient,
+    configured: &HashSet<Version>,
+    namespace: &str,
+) {
+    let database = DB::new().await.change_name(namespace);
+    let stored_versions = match database.compiler_versions().await {
+        Ok(versions) => versions,
+        Err(err) => {
+            tracing::error!("cannot load compiler versions of stored contracts: {}", err);
+            return;
+        }
+    };
+
+    let mut versions = configured.clone();
+    versions.extend(parse_versions(&stored_versions));
+    tracing::debug!("retaining {} compiler versions", versions.len());
+    client.compilers().retain_versions(versions);
+}
+
+/// Parses stored compiler versions skipping invalid ones.
+fn parse_versions(versions: &[String]) -> HashSet<Version> {
+    versions
+        .iter()
+        .filter_map(|version| Version::from_str(version).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn invalid_versions_are_skipped() {
+        let versions =
+            parse_versions(&["v0.8.17+commit.8df45f5f".to_string(), "0.3.7".to_string()]);
+        assert_eq!(
+            HashSet::from([Version::from_str("v0.8.17+commit.8df45f5f").unwrap()]),
+            versions
+        );
+    }
+}
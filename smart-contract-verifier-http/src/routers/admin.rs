@@ -0,0 +1,75 @@
+use super::router::Router;
+use crate::{
+    audit::Auditor,
+    blobs::BlobStore,
+    chains::Chain,
+    handlers::{
+        admin_access_log, admin_audit, admin_compilers, admin_contracts, admin_export,
+        admin_import, admin_logs,
+    },
+    settings::AdminSettings,
+};
+use actix_web::web;
+use smart_contract_verifier::{rpc::BytecodeFetcher, scheduler, SolidityClient};
+use std::sync::Arc;
+
+pub struct AdminRouter {
+    auditor: web::Data<Auditor>,
+    solidity_client: web::Data<SolidityClient>,
+}
+
+impl AdminRouter {
+    pub fn new(
+        settings: AdminSettings,
+        solidity_client: Arc<SolidityClient>,
+        fetcher: Arc<BytecodeFetcher>,
+        chain: Chain,
+        blobs: Option<Arc<BlobStore>>,
+    ) -> Self {
+        let auditor = Arc::new(Auditor::new(&solidity_client, fetcher, chain, blobs));
+
+        if let Some(schedule) = settings.audit_schedule {
+            let auditor = auditor.clone();
+            scheduler::spawn_job(schedule, "audit stored contracts", move || {
+                let auditor = auditor.clone();
+                async move { auditor.run().await }
+            });
+        }
+
+        Self {
+            auditor: web::Data::from(auditor),
+            solidity_client: web::Data::from(solidity_client),
+        }
+    }
+}
+
+impl Router for AdminRouter {
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config
+            .app_data(self.auditor.clone())
+            .app_data(self.solidity_client.clone())
+            .route(
+                "/access-log",
+                web::get().to(admin_access_log::get_access_log),
+            )
+            .route("/audit", web::post().to(admin_audit::start_audit))
+            .route(
+                "/audit/{address}",
+                web::get().to(admin_audit::get_audit_history),
+            )
+            .route("/replay/{address}", web::post().to(admin_audit::replay))
+            .route(
+                "/actions/{address}",
+                web::get().to(admin_contracts::get_admin_actions),
+            )
+            .route("/compilers", web::get().to(admin_compilers::list_compilers))
+            .route(
+                "/compilers/{version}",
+                web::delete().to(admin_compilers::evict_compiler),
+            )
+            .route("/export", web::get().to(admin_export::export))
+            .route("/import", web::post().to(admin_import::import))
+            .route("/logs/filter", web::get().to(admin_logs::get_log_filter))
+            .route("/logs/filter", web::put().to(admin_logs::set_log_filter));
+    }
+}
@@ -1,23 +1,319 @@
 use super::{
+    admin::AdminRouter,
+    contracts::ContractsRouter,
+    events::EventsRouter,
+    huff::HuffRouter,
     router::{configure_router, Router},
-    solidity::SolidityRouter,
+    selectors::SelectorsRouter,
+    solidity::{self, SolidityRouter},
     sourcify::SourcifyRouter,
+    tools::ToolsRouter,
+    uploads::UploadsRouter,
     vyper::VyperRouter,
 };
-use crate::{handlers::status, settings::Settings};
+use crate::{
+    access_log::{self, AccessLog},
+    analysis::{BasicChecks, StaticAnalysis},
+    api_error,
+    auth::AdminAuth,
+    backup,
+    blobs::BlobStore,
+    bootstrap::{self, Manifest},
+    chain_watcher,
+    chains::{self, Chains},
+    handlers::{
+        solidity_multi_part_upload::MaxRequestSize, stats, status, verification_intents,
+        verification_jobs, ws_verified,
+    },
+    idempotency::IdempotencyCache,
+    impersonation::CanonicalDeployments,
+    jobs::{self, JobsConcurrency},
+    leader,
+    metadata_availability::MetadataAvailabilityCheck,
+    notifications::Notifier,
+    object_storage::ObjectStorage,
+    persistence::{self, ResultMiddlewares},
+    proxy_history,
+    rate_limit::RateLimiter,
+    read_only::ReadOnly,
+    settings::{ObjectStorageSettings, Settings},
+    slither::Slither,
+    tenants::{self, Tenants},
+    uploads::{self, Uploads},
+    warm_up::{self, Readiness},
+    DB,
+};
 use actix_web::web;
-use std::sync::Arc;
+use smart_contract_verifier::{
+    rpc::{BytecodeFetcher, FailoverConfig},
+    Matchers,
+};
+use std::{sync::Arc, time::Duration};
 use tokio::sync::Semaphore;
 
+/// Seeding of the code hash counters, claimed by one of the replicas starting at once.
+const SEED_CODE_HASH_COUNTS_JOB: &str = "seed_code_hash_counts";
+/// Detection of licenses of contracts stored before, claimed by one of the replicas starting at once.
+const BACKFILL_LICENSES_JOB: &str = "backfill_licenses";
+
 pub struct AppRouter {
     solidity: Option<SolidityRouter>,
     vyper: Option<VyperRouter>,
+    huff: Option<HuffRouter>,
     sourcify: Option<SourcifyRouter>,
+    contracts: ContractsRouter,
+    tools: ToolsRouter,
+    selectors: SelectorsRouter,
+    events: EventsRouter,
+    admin: Option<AdminRouter>,
+    uploads: Option<UploadsRouter>,
+    admin_auth: web::Data<AdminAuth>,
+    readiness: web::Data<Readiness>,
+    idempotency: web::Data<IdempotencyCache>,
+    tenants: Option<web::Data<Tenants>>,
+    result_middlewares: web::Data<ResultMiddlewares>,
+    notifier: web::Data<Notifier>,
+    fetcher: web::Data<BytecodeFetcher>,
+    chains: web::Data<Chains>,
+    blobs: Option<Arc<BlobStore>>,
+    access_log: Option<web::Data<AccessLog>>,
+    rate_limiter: Option<web::Data<RateLimiter>>,
+    max_request_size: usize,
+    jobs_concurrency: JobsConcurrency,
+    read_only: bool,
 }
 
 impl AppRouter {
     pub async fn new(settings: Settings) -> anyhow::Result<Self> {
+        let admin_auth = web::Data::new(AdminAuth::new(&settings.admin.api_keys));
+        let max_request_size = settings.server.max_request_size;
+        // Nothing is written to the database by read-only instances,
+        // so background jobs writing to it are not started either
+        let read_only = settings.server.read_only;
+        let chains = web::Data::new(Chains::new(&settings.chains)?);
+        // Background jobs and result middlewares are run for the current chain only
+        let chain = chains.current().clone();
+        let idempotency = web::Data::new(IdempotencyCache::new(
+            &settings.server.idempotency,
+            settings.rate_limit.trust_forwarded_headers,
+        ));
+        let tenants = match settings.tenants.enabled {
+            false => None,
+            true => Some(web::Data::new(Tenants::new(&settings.tenants, &chain))),
+        };
+        let tenant_names = tenants
+            .as_ref()
+            .map(|tenants| tenants.names().to_vec())
+            .unwrap_or_default();
+        let failover_config = FailoverConfig {
+            max_attempts: settings.rpc.max_attempts.get(),
+            initial_backoff: Duration::from_millis(settings.rpc.initial_backoff_ms),
+            max_backoff: Duration::from_millis(settings.rpc.max_backoff_ms),
+            failure_threshold: settings.rpc.failure_threshold.get(),
+            circuit_open_duration: Duration::from_secs(settings.rpc.circuit_open_secs),
+            request_timeout: Duration::from_secs(settings.rpc.request_timeout),
+        };
+        let fetcher = Arc::new(
+            BytecodeFetcher::new(settings.rpc.urls, failover_config)?
+                .with_batch_size(settings.rpc.batch_size.get())
+                .with_other_chains(settings.rpc.other_chains),
+        );
+        if settings.chains.migrate_legacy_database && !read_only {
+            // Is done before any request is served, so that no contract is stored aside
+            let database = DB::new().await.change_name(&chain.namespace());
+            let moved = database
+                .migrate_from(chains::LEGACY_NAMESPACE)
+                .await
+                .map_err(|err| anyhow::anyhow!("cannot migrate the legacy database: {err}"))?;
+            if !moved.is_empty() {
+                tracing::info!(?moved, "legacy collections have been moved");
+            }
+        }
+        let blobs = match settings.blobs.enabled {
+            false => None,
+            true => Some(Arc::new(BlobStore::new(
+                object_storage(&settings.blobs.storage)?,
+                settings.blobs.min_size_kb * 1024,
+                settings.compression.encoding,
+            ))),
+        };
+        let uploads = match settings.uploads.enabled {
+            false => None,
+            true => {
+                let uploads = web::Data::new(Uploads::new(
+                    object_storage(&settings.uploads.storage)?,
+                    &settings.uploads,
+                ));
+                uploads::spawn_cleanup_job(uploads.clone().into_inner());
+                Some(UploadsRouter::new(uploads))
+            }
+        };
+        if settings.backup.restore_on_start && !read_only {
+            // Is done before any request is served, so that new contracts
+            // do not prevent the snapshot from being restored
+            backup::restore_latest(&object_storage(&settings.backup.storage)?, &chain)
+                .await
+                .map_err(|err| anyhow::anyhow!("cannot restore the database: {err:#}"))?;
+        }
+        if settings.backup.enabled {
+            backup::spawn_backup_job(
+                object_storage(&settings.backup.storage)?,
+                settings.backup.schedule.clone(),
+                settings.backup.keep_last.get(),
+                chain.clone(),
+            );
+        }
+        if settings.proxy_history.enabled && !read_only {
+            proxy_history::spawn_watcher(
+                settings.proxy_history.schedule.clone(),
+                fetcher.clone(),
+                chain.clone(),
+                tenant_names.clone(),
+            );
+        }
+        // Intents are matched by the chain watcher and verified as jobs once matched
+        let intents = settings.intents.enabled && !read_only;
+        if intents && !(settings.chain_watcher.enabled && settings.jobs.enabled) {
+            return Err(anyhow::anyhow!(
+                "verification intents require the chain watcher and verification jobs to be enabled"
+            ));
+        }
+        if settings.chain_watcher.enabled && !read_only {
+            chain_watcher::spawn_watcher(
+                settings.chain_watcher.clone(),
+                fetcher.clone(),
+                intents,
+                chain.clone(),
+            );
+        }
+        let access_log = match settings.access_log.enabled && !read_only {
+            false => None,
+            true => {
+                access_log::spawn_cleanup_job(
+                    settings.access_log.retention_days.get(),
+                    chain.clone(),
+                );
+                Some(web::Data::new(AccessLog::new(
+                    settings.access_log,
+                    chain.clone(),
+                )))
+            }
+        };
+        let rate_limiter = match settings.rate_limit.enabled {
+            false => None,
+            true => Some(web::Data::new(
+                RateLimiter::new(settings.rate_limit)
+                    .await
+                    .map_err(|err| anyhow::anyhow!("cannot initialize rate limiter: {err:#}"))?,
+            )),
+        };
+        if !read_only {
+            let chain = chain.clone();
+            let tenant_names = tenant_names.clone();
+            let blobs = blobs.clone();
+            tokio::spawn(async move {
+                for tenant in std::iter::once(None).chain(tenant_names.into_iter().map(Some)) {
+                    let namespace = tenants::scope(tenant, async { chain.namespace() }).await;
+                    let database = DB::new()
+                        .await
+                        .change_name(&namespace)
+                        .with_blobs(blobs.clone());
+                    if let Err(err) = database.create_indexes().await {
+                        tracing::warn!("cannot create database indexes of {}: {}", namespace, err);
+                    }
+                    // Seeded counts are added up, so replicas starting at once must not both seed them
+                    match leader::claim_once(
+                        &database,
+                        SEED_CODE_HASH_COUNTS_JOB,
+                        chrono::Duration::hours(1),
+                    )
+                    .await
+                    {
+                        Ok(true) => {
+                            if let Err(err) = database.seed_code_hash_counts().await {
+                                tracing::warn!(
+                                    "cannot count contracts of {} by code hashes: {}",
+                                    namespace,
+                                    err
+                                );
+                            }
+                            if let Err(err) =
+                                leader::release(&database, SEED_CODE_HASH_COUNTS_JOB).await
+                            {
+                                tracing::warn!("{}", err);
+                            }
+                        }
+                        Ok(false) => {}
+                        Err(err) => tracing::warn!(
+                            "cannot count contracts of {} by code hashes: {}",
+                            namespace,
+                            err
+                        ),
+                    }
+                    // Sources of every contract are loaded, so it is done by a single replica
+                    match leader::claim_once(
+                        &database,
+                        BACKFILL_LICENSES_JOB,
+                        chrono::Duration::hours(1),
+                    )
+                    .await
+                    {
+                        Ok(true) => {
+                            match database.backfill_licenses().await {
+                                Ok(0) => {}
+                                Ok(updated) => tracing::info!(
+                                    "licenses of {} contracts of {} have been detected",
+                                    updated,
+                                    namespace
+                                ),
+                                Err(err) => tracing::warn!(
+                                    "cannot detect licenses of contracts of {}: {}",
+                                    namespace,
+                                    err
+                                ),
+                            }
+                            if let Err(err) =
+                                leader::release(&database, BACKFILL_LICENSES_JOB).await
+                            {
+                                tracing::warn!("{}", err);
+                            }
+                        }
+                        Ok(false) => {}
+                        Err(err) => tracing::warn!(
+                            "cannot detect licenses of contracts of {}: {}",
+                            namespace,
+                            err
+                        ),
+                    }
+                }
+            });
+        }
         let compilers_lock = Arc::new(Semaphore::new(settings.compilers.max_threads.get()));
+        let compilation_timeout = (settings.compilers.timeout_secs > 0)
+            .then(|| Duration::from_secs(settings.compilers.timeout_secs));
+        let jobs_concurrency = JobsConcurrency(
+            settings
+                .jobs
+                .workers
+                .get()
+                .min(settings.compilers.max_threads.get()),
+        );
+        let matchers = Matchers::from_kinds(&settings.comparison.matchers);
+        let reject_hidden_characters = settings.source_screening.reject;
+        let readiness = Readiness::default();
+        let solidity_warm_up = settings.solidity.warm_up.clone();
+        let store_debug_info = settings.solidity.store_debug_info;
+        let notifier = Notifier::new(chain.clone());
+        let creation_lookup_timeout = (settings.rpc.creation_lookup_timeout_secs > 0)
+            .then(|| Duration::from_secs(settings.rpc.creation_lookup_timeout_secs));
+        let metadata_availability = match settings.metadata_availability.enabled {
+            false => None,
+            true => Some(MetadataAvailabilityCheck::new(
+                settings.metadata_availability,
+                chain.clone(),
+                fetcher.clone(),
+            )?),
+        };
         let solidity = match settings.solidity.enabled {
             false => None,
             true => Some(
@@ -25,14 +321,120 @@ impl AppRouter {
                     settings.solidity,
                     settings.extensions.solidity,
                     compilers_lock.clone(),
+                    compilation_timeout,
+                    matchers.clone(),
+                    reject_hidden_characters,
+                    fetcher.clone(),
                 )
-                .await?,
+                .await?
+                .with_jobs(settings.jobs.enabled && !read_only)
+                .with_intents(intents.then(|| settings.intents.clone())),
             ),
         };
+        if let Some(solidity) = &solidity {
+            solidity.spawn_retention_job(chain.clone());
+        }
+        // Only solidity contracts have ASTs to be analyzed
+        let analysis = match (settings.analysis.enabled, &solidity) {
+            (true, Some(solidity)) => {
+                let mut analysis =
+                    StaticAnalysis::new(settings.analysis.min_severity, chain.clone())
+                        .with(BasicChecks);
+                if settings.analysis.slither.enabled {
+                    analysis = analysis.with(Slither::new(
+                        settings.analysis.slither,
+                        solidity.client(),
+                    ));
+                }
+                Some(analysis)
+            }
+            _ => None,
+        };
+        let canonical = settings
+            .impersonation
+            .enabled
+            .then(|| CanonicalDeployments::new(&settings.impersonation));
+        let result_middlewares = web::Data::new(
+            persistence::result_middlewares(
+                chain.clone(),
+                fetcher.clone(),
+                notifier.clone(),
+                store_debug_info,
+                creation_lookup_timeout,
+                metadata_availability,
+                analysis,
+            )
+            .with_blobs(blobs.clone())
+            .with_encoding(settings.compression.encoding)
+            .with_canonical_deployments(canonical),
+        );
+        match (settings.jobs.enabled && !read_only, &solidity) {
+            (false, _) => {}
+            (true, Some(solidity)) => jobs::spawn_workers(
+                settings.jobs,
+                solidity.client(),
+                result_middlewares.clone(),
+                solidity.dependencies(),
+                tenant_names.clone(),
+            ),
+            (true, None) => {
+                return Err(anyhow::anyhow!(
+                    "verification jobs require solidity verification to be enabled"
+                ))
+            }
+        }
+        match (settings.bootstrap.enabled && !read_only, &solidity) {
+            (false, _) => {}
+            (true, Some(solidity)) => {
+                let manifest = Manifest::load(&settings.bootstrap.manifest)?;
+                bootstrap::spawn_bootstrap(
+                    manifest,
+                    solidity.client(),
+                    result_middlewares.clone(),
+                    chain.clone(),
+                );
+            }
+            (true, None) => {
+                return Err(anyhow::anyhow!(
+                    "bootstrapping requires solidity verification to be enabled"
+                ))
+            }
+        }
+        if let Some(solidity) = &solidity {
+            warm_up::spawn_warm_up(
+                solidity.client().into_inner(),
+                solidity_warm_up,
+                readiness.clone(),
+                chain.clone(),
+            )?;
+        }
         let vyper = match settings.vyper.enabled {
             false => None,
             true => Some(
-                VyperRouter::new(settings.vyper, settings.extensions.vyper, compilers_lock).await?,
+                VyperRouter::new(
+                    settings.vyper,
+                    settings.extensions.vyper,
+                    compilers_lock.clone(),
+                    compilation_timeout,
+                    matchers.clone(),
+                    reject_hidden_characters,
+                )
+                .await?,
+            ),
+        };
+        let huff = match settings.huff.enabled {
+            false => None,
+            true => Some(
+                HuffRouter::new(
+                    settings.huff,
+                    settings.extensions.huff,
+                    compilers_lock,
+                    compilation_timeout,
+                    matchers,
+                    reject_hidden_characters,
+                    fetcher.clone(),
+                )
+                .await?,
             ),
         };
         let sourcify = match settings.sourcify.enabled {
@@ -41,23 +443,130 @@ impl AppRouter {
                 Some(SourcifyRouter::new(settings.sourcify, settings.extensions.sourcify).await?)
             }
         };
+        let mut admin_settings = settings.admin;
+        if read_only {
+            // Audits store their results
+            admin_settings.audit_schedule = None;
+        }
+        let admin = match (admin_settings.enabled, &solidity) {
+            (false, _) => None,
+            (true, Some(solidity)) => Some(AdminRouter::new(
+                admin_settings,
+                solidity.client().into_inner(),
+                fetcher.clone(),
+                chain,
+                blobs.clone(),
+            )),
+            (true, None) => {
+                return Err(anyhow::anyhow!(
+                    "admin api requires solidity verification to be enabled"
+                ))
+            }
+        };
         Ok(Self {
             solidity,
             vyper,
+            huff,
             sourcify,
+            contracts: ContractsRouter::new(settings.attestation)?,
+            tools: ToolsRouter::new(solidity.as_ref().map(|solidity| solidity.client())),
+            selectors: SelectorsRouter::default(),
+            events: EventsRouter::default(),
+            admin,
+            uploads,
+            admin_auth,
+            readiness: web::Data::new(readiness),
+            idempotency,
+            tenants,
+            result_middlewares,
+            notifier: web::Data::new(notifier),
+            fetcher: web::Data::from(fetcher),
+            chains,
+            blobs,
+            access_log,
+            rate_limiter,
+            max_request_size,
+            jobs_concurrency,
+            read_only,
         })
     }
 }
 
+fn object_storage(settings: &ObjectStorageSettings) -> anyhow::Result<ObjectStorage> {
+    let storage = match settings {
+        ObjectStorageSettings::Local(local) => ObjectStorage::Local(local.dir.clone()),
+        ObjectStorageSettings::S3(s3) => ObjectStorage::S3(solidity::new_bucket(s3)?),
+    };
+    Ok(storage)
+}
+
 impl Router for AppRouter {
     fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        if let Some(access_log) = &self.access_log {
+            // Is looked up by `access_log::record` middleware
+            service_config.app_data(access_log.clone());
+        }
+        if self.read_only {
+            // Is looked up by `read_only::reject_writes` middleware
+            service_config.app_data(web::Data::new(ReadOnly));
+        }
+        if let Some(rate_limiter) = &self.rate_limiter {
+            // Is looked up by `rate_limit::limit` middleware
+            service_config.app_data(rate_limiter.clone());
+        }
+        if let Some(tenants) = &self.tenants {
+            // Is looked up by `tenants::scope_request` middleware
+            service_config.app_data(tenants.clone());
+        }
+        if let Some(blobs) = &self.blobs {
+            // Is looked up by handlers loading stored contracts
+            service_config.app_data(web::Data::from(blobs.clone()));
+        }
+        if let Some(uploads) = &self.uploads {
+            // Is looked up by verification handlers referencing uploaded payloads
+            service_config.app_data(uploads.uploads());
+        }
         service_config
+            .app_data(self.admin_auth.clone())
+            .app_data(self.readiness.clone())
+            .app_data(self.idempotency.clone())
+            .app_data(self.result_middlewares.clone())
+            .app_data(self.notifier.clone())
+            .app_data(self.fetcher.clone())
+            // Is looked up by `chains::reject_other_chains` middleware and `Chain` extractor
+            .app_data(self.chains.clone())
+            .app_data(
+                web::JsonConfig::default()
+                    .limit(self.max_request_size)
+                    .error_handler(api_error::json_error),
+            )
+            .app_data(web::PathConfig::default().error_handler(api_error::path_error))
+            .app_data(web::QueryConfig::default().error_handler(api_error::query_error))
+            .app_data(web::Data::new(MaxRequestSize(self.max_request_size)))
+            .app_data(web::Data::new(self.jobs_concurrency))
             .route("/health", web::get().to(status::status))
+            .route("/ready", web::get().to(status::ready))
             .service(
                 web::scope("/api/v1")
+                    .route("/stats", web::get().to(stats::get_stats))
+                    .route("/stats/compilers", web::get().to(stats::get_compiler_stats))
+                    .route("/ws/verified", web::get().to(ws_verified::subscribe))
+                    .route("/jobs/{job_id}", web::get().to(verification_jobs::get_job))
+                    .route("/intents/{intent_id}", web::get().to(verification_intents::get_intent))
                     .service(web::scope("/solidity").configure(configure_router(&self.solidity)))
                     .service(web::scope("/vyper").configure(configure_router(&self.vyper)))
-                    .service(web::scope("/sourcify").configure(configure_router(&self.sourcify))),
+                    .service(web::scope("/huff").configure(configure_router(&self.huff)))
+                    .service(web::scope("/sourcify").configure(configure_router(&self.sourcify)))
+                    .service(web::scope("/contracts").configure(configure_router(&self.contracts)))
+                    .service(web::scope("/tools").configure(configure_router(&self.tools)))
+                    .service(web::scope("/selectors").configure(configure_router(&self.selectors)))
+                    .service(web::scope("/events").configure(configure_router(&self.events)))
+                    .service(web::scope("/uploads").configure(configure_router(&self.uploads)))
+                    .service(web::scope("/admin").configure(configure_router(&self.admin))),
+            )
+            .service(
+                web::scope("/api/v2")
+                    .route("/jobs/{job_id}/events", web::get().to(verification_jobs::job_events)),
             );
     }
 }
@@ -0,0 +1,108 @@
+use super::router::Router;
+use crate::{
+    attestation::Attestor,
+    handlers::{
+        admin_contracts, contract_analysis, contract_attempts, contract_attestation,
+        contract_compiler_input, contract_debug_info, contract_derived, contract_highlights,
+        contract_lookalikes, contract_lookup, contract_metadata, contract_metadata_availability,
+        contract_proxy_history, contract_read, contract_standard_json, contract_token_info,
+    },
+    settings::AttestationSettings,
+};
+use actix_web::web;
+
+/// Serves data of the contracts already verified and stored in the database.
+#[derive(Default)]
+pub struct ContractsRouter {
+    attestor: Option<web::Data<Attestor>>,
+}
+
+impl ContractsRouter {
+    pub fn new(settings: AttestationSettings) -> anyhow::Result<Self> {
+        let attestor = match (settings.enabled, settings.private_key) {
+            (true, Some(private_key)) => Some(web::Data::new(Attestor::new(&private_key)?)),
+            _ => None,
+        };
+        if let Some(attestor) = &attestor {
+            tracing::info!(signer = ?attestor.signer(), "attestations are enabled");
+        }
+        Ok(Self { attestor })
+    }
+}
+
+impl Router for ContractsRouter {
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config
+            // Are registered before the contract routes, which would match them otherwise
+            .route("/recent", web::get().to(contract_highlights::get_recent))
+            .route(
+                "/top-duplicated",
+                web::get().to(contract_highlights::get_top_duplicated),
+            )
+            .route("/{address}", web::get().to(contract_lookup::get_contract))
+            .route(
+                "/{address}",
+                web::delete().to(admin_contracts::delete_contract),
+            )
+            .route("/{address}/abi", web::get().to(contract_lookup::get_abi))
+            .route(
+                "/{address}/analysis",
+                web::get().to(contract_analysis::get_analysis),
+            )
+            .route(
+                "/{address}/attempts",
+                web::get().to(contract_attempts::get_attempts),
+            )
+            .route(
+                "/{address}/compiler-input",
+                web::get().to(contract_compiler_input::get_compiler_input),
+            )
+            .route(
+                "/{address}/debug-info",
+                web::get().to(contract_debug_info::get_debug_info),
+            )
+            .route(
+                "/{address}/derived",
+                web::get().to(contract_derived::get_derived),
+            )
+            .route(
+                "/{address}/implementations",
+                web::get().to(contract_proxy_history::get_implementations),
+            )
+            .route(
+                "/{address}/lookalikes",
+                web::get().to(contract_lookalikes::get_lookalikes),
+            )
+            .route(
+                "/{address}/metadata.json",
+                web::get().to(contract_metadata::get_metadata),
+            )
+            .route(
+                "/{address}/metadata-availability",
+                web::get().to(contract_metadata_availability::get_metadata_availability),
+            )
+            .route("/{address}/read", web::post().to(contract_read::read))
+            .route(
+                "/{address}/sources",
+                web::get().to(contract_lookup::get_sources),
+            )
+            .route(
+                "/{address}/standard-json",
+                web::get().to(contract_standard_json::get_standard_json),
+            )
+            .route(
+                "/{address}/storage-layout",
+                web::get().to(contract_lookup::get_storage_layout),
+            )
+            .route(
+                "/{address}/token-info",
+                web::get().to(contract_token_info::get_token_info),
+            );
+        if let Some(attestor) = &self.attestor {
+            service_config.app_data(attestor.clone()).route(
+                "/{address}/attestation",
+                web::get().to(contract_attestation::get_attestation),
+            );
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use super::router::Router;
+use crate::handlers::events;
+use actix_web::web;
+
+/// Resolves event topics into signatures known from verified contracts.
+#[derive(Default)]
+pub struct EventsRouter {}
+
+impl Router for EventsRouter {
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config
+            .route("/lookup", web::post().to(events::lookup_events))
+            .route("/{topic0}", web::get().to(events::get_event));
+    }
+}
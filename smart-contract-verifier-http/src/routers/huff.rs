@@ -0,0 +1,88 @@
+use super::{router::Router, solidity::local_compilers};
+use crate::{
+    handlers::{huff_sources, huff_version_list},
+    settings::{Extensions, FetcherSettings, HuffSettings},
+};
+use actix_web::web;
+use smart_contract_verifier::{
+    middleware::{MetricsMiddleware, Webhook},
+    rpc::BytecodeFetcher,
+    Compilers, Fetcher, HuffClient, HuffCompiler, ListFetcher, LocalFetcher, Matchers,
+};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Semaphore;
+
+pub struct HuffRouter {
+    client: web::Data<HuffClient>,
+}
+
+impl HuffRouter {
+    pub async fn new(
+        settings: HuffSettings,
+        extensions: Extensions,
+        compilers_threads_semaphore: Arc<Semaphore>,
+        compilation_timeout: Option<Duration>,
+        matchers: Matchers,
+        reject_hidden_characters: bool,
+        bytecode_fetcher: Arc<BytecodeFetcher>,
+    ) -> anyhow::Result<Self> {
+        let dir = settings.compilers_dir.clone();
+        let remote_fetcher: Option<Arc<dyn Fetcher>> = match settings.fetcher {
+            FetcherSettings::List(list_settings) => Some(Arc::new(
+                ListFetcher::new(
+                    list_settings.list_url,
+                    settings.compilers_dir,
+                    Some(settings.refresh_versions_schedule),
+                    None,
+                )
+                .await?,
+            )),
+            FetcherSettings::S3(_) => {
+                return Err(anyhow::anyhow!("S3 fetcher for huff not supported"))
+            }
+            FetcherSettings::Disabled if settings.local_compilers.is_empty() => {
+                return Err(anyhow::anyhow!(
+                    "huff requires either compilers fetcher or local compilers to be configured"
+                ))
+            }
+            FetcherSettings::Disabled => None,
+        };
+        let fetcher: Arc<dyn Fetcher> = match remote_fetcher {
+            Some(remote_fetcher) if settings.local_compilers.is_empty() => remote_fetcher,
+            remote_fetcher => Arc::new(LocalFetcher::new(
+                local_compilers(settings.local_compilers)?,
+                remote_fetcher,
+                None,
+            )),
+        };
+        let compilers = Compilers::new(fetcher, HuffCompiler::new(), compilers_threads_semaphore)
+            .with_timeout(compilation_timeout);
+        compilers.load_from_dir(&dir).await;
+
+        // Middlewares are invoked in the order they are added
+        let mut client = HuffClient::new(compilers)
+            .with_fetcher(bytecode_fetcher)
+            .with_matchers(matchers)
+            .with_hidden_characters_rejected(reject_hidden_characters)
+            .with_middleware(MetricsMiddleware::new("huff"));
+        if let Some(webhook) = extensions.webhook {
+            client = client.with_middleware(Webhook::new(webhook, "huff")?);
+        }
+
+        Ok(Self {
+            client: web::Data::new(client),
+        })
+    }
+}
+
+impl Router for HuffRouter {
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config
+            .app_data(self.client.clone())
+            .route("/sources:verify", web::post().to(huff_sources::verify))
+            .route(
+                "/versions",
+                web::get().to(huff_version_list::get_version_list),
+            );
+    }
+}
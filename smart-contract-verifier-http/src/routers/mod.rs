@@ -1,7 +1,14 @@
+mod admin;
 mod app;
+mod contracts;
+mod events;
+mod huff;
 mod router;
+mod selectors;
 mod solidity;
 mod sourcify;
+mod tools;
+mod uploads;
 mod vyper;
 
 pub use app::AppRouter;
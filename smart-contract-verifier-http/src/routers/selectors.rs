@@ -0,0 +1,15 @@
+use super::router::Router;
+use crate::handlers::selectors;
+use actix_web::web;
+
+/// Resolves function selectors into signatures known from verified contracts.
+#[derive(Default)]
+pub struct SelectorsRouter {}
+
+impl Router for SelectorsRouter {
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config
+            .route("/lookup", web::post().to(selectors::lookup_selectors))
+            .route("/{selector}", web::get().to(selectors::get_selector));
+    }
+}
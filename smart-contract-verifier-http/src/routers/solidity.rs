@@ -1,18 +1,57 @@
 use super::router::Router;
 use crate::{
-    handlers::{solidity_multi_part, solidity_standard_json, solidity_version_list},
-    settings::{Extensions, FetcherSettings, S3FetcherSettings, SoliditySettings},
+    chains::Chain,
+    dependencies::PackageRegistry,
+    erc20_module::ModuleVersions,
+    handlers::{
+        solidity_compile, solidity_erc20_module, solidity_etherscan_import, solidity_flattened,
+        solidity_git_import, solidity_multi_part, solidity_multi_part_upload,
+        solidity_multi_part_zip, solidity_sources_validate, solidity_standard_json,
+        solidity_version_list, verification_intents, verification_jobs,
+    },
+    retention,
+    settings::{
+        CompilerExecutor, EtherscanImportSettings, Extensions, FetcherSettings, GitImportSettings,
+        IntentsSettings, ListFetcherSettings, LocalCompilerSettings, S3FetcherSettings,
+        SoliditySettings,
+    },
 };
 use actix_web::web;
+use cron::Schedule;
 use s3::{creds::Credentials, Bucket, Region};
 use smart_contract_verifier::{
-    Compilers, Fetcher, ListFetcher, S3Fetcher, SolcValidator, SolidityClient, SolidityCompiler,
+    middleware::{MetricsMiddleware, Webhook},
+    rpc::BytecodeFetcher,
+    ChecksumAllowlist, Compilers, EvictionPolicy, Fetcher, ListFetcher, LocalCompiler,
+    LocalFetcher, Matchers, S3Fetcher, SolcJs, SolcValidator, SolidityClient, SolidityCompiler,
+    Version,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
 };
-use std::{str::FromStr, sync::Arc};
 use tokio::sync::Semaphore;
+use url::Url;
 
 pub struct SolidityRouter {
     client: web::Data<SolidityClient>,
+    /// Set if verification of sources imported from git repositories is enabled.
+    git_import: Option<web::Data<GitImportSettings>>,
+    /// Set if verification of sources imported from explorers of other chains is enabled.
+    etherscan_import: Option<web::Data<EtherscanImportSettings>>,
+    /// Set if contracts deployed by the `x/erc20` module may be verified with its canonical sources.
+    erc20_module: Option<web::Data<ModuleVersions>>,
+    /// Set if missing sources of well-known packages may be injected into requests.
+    dependencies: Option<web::Data<PackageRegistry>>,
+    /// Set if verification requests may be submitted as jobs run in the background.
+    jobs: bool,
+    /// Set if verification requests may be submitted before the contracts are deployed.
+    intents: Option<web::Data<IntentsSettings>>,
+    /// Compilers kept on the disk regardless of the stored contracts referencing them.
+    retained_versions: HashSet<Version>,
+    refresh_schedule: Schedule,
 }
 
 fn new_region(region: Option<String>, endpoint: Option<String>) -> Option<Region> {
@@ -32,7 +71,7 @@ fn new_region(region: Option<String>, endpoint: Option<String>) -> Option<Region
     }
 }
 
-fn new_bucket(settings: &S3FetcherSettings) -> anyhow::Result<Arc<Bucket>> {
+pub(super) fn new_bucket(settings: &S3FetcherSettings) -> anyhow::Result<Arc<Bucket>> {
     let region = new_region(settings.region.clone(), settings.endpoint.clone())
         .ok_or_else(|| anyhow::anyhow!("got invalid region/endpoint settings"))?;
     let bucket = Arc::new(Bucket::new(
@@ -49,46 +88,150 @@ fn new_bucket(settings: &S3FetcherSettings) -> anyhow::Result<Arc<Bucket>> {
     Ok(bucket)
 }
 
+pub(super) fn list_urls(settings: ListFetcherSettings, allow_nightly: bool) -> Vec<Url> {
+    let mut list_urls = vec![settings.list_url];
+    if allow_nightly {
+        list_urls.extend(settings.nightly_list_url);
+    }
+    list_urls
+}
+
+pub(super) fn local_compilers(
+    settings: BTreeMap<String, LocalCompilerSettings>,
+) -> anyhow::Result<HashMap<Version, LocalCompiler>> {
+    settings
+        .into_iter()
+        .map(|(version, compiler)| {
+            let version = Version::from_str(&version).map_err(|err| {
+                anyhow::anyhow!("invalid local compiler version {version}: {err}")
+            })?;
+            let compiler = LocalCompiler::new(compiler.path, &compiler.sha256)?;
+            Ok((version, compiler))
+        })
+        .collect()
+}
+
+/// Versions of local compilers which are `soljson.js` builds.
+fn solc_js_versions(
+    settings: &BTreeMap<String, LocalCompilerSettings>,
+) -> anyhow::Result<HashSet<Version>> {
+    settings
+        .iter()
+        .filter(|(_, compiler)| compiler.executor == CompilerExecutor::SolcJs)
+        .map(|(version, _)| {
+            Version::from_str(version)
+                .map_err(|err| anyhow::anyhow!("invalid local compiler version {version}: {err}"))
+        })
+        .collect()
+}
+
+fn retained_versions(versions: Vec<String>) -> anyhow::Result<HashSet<Version>> {
+    versions
+        .iter()
+        .map(|version| {
+            Version::from_str(version).map_err(|err| {
+                anyhow::anyhow!("invalid retained compiler version {version}: {err}")
+            })
+        })
+        .collect()
+}
+
 impl SolidityRouter {
     pub async fn new(
         settings: SoliditySettings,
-        /* Otherwise, results in compilation warning if all extensions are disabled */
-        #[allow(unused_variables)] extensions: Extensions,
+        extensions: Extensions,
         compilers_threads_semaphore: Arc<Semaphore>,
+        compilation_timeout: Option<Duration>,
+        matchers: Matchers,
+        reject_hidden_characters: bool,
+        bytecode_fetcher: Arc<BytecodeFetcher>,
     ) -> anyhow::Result<Self> {
         let dir = settings.compilers_dir.clone();
+        let checksums_file = settings
+            .checksums_file
+            .unwrap_or_else(|| dir.join("checksums.json"));
+        let eviction_policy = EvictionPolicy {
+            dir: dir.clone(),
+            max_size: settings
+                .compilers_dir_quota_mb
+                .map(|quota| quota.saturating_mul(1024 * 1024)),
+        };
+        let retained_versions = retained_versions(settings.retained_compilers)?;
+        let git_import = settings
+            .git_import
+            .enabled
+            .then(|| web::Data::new(settings.git_import));
+        let etherscan_import = settings
+            .etherscan_import
+            .enabled
+            .then(|| web::Data::new(settings.etherscan_import));
+        let erc20_module = match settings.erc20_module.enabled {
+            true => Some(web::Data::new(ModuleVersions::load(
+                &settings.erc20_module,
+            )?)),
+            false => None,
+        };
+        let dependencies = match settings.dependencies.enabled {
+            true => Some(web::Data::new(PackageRegistry::load(
+                &settings.dependencies,
+            )?)),
+            false => None,
+        };
         let schedule = settings.refresh_versions_schedule;
-        let validator = Arc::new(SolcValidator::default());
-        let fetcher: Arc<dyn Fetcher> = match settings.fetcher {
-            FetcherSettings::List(list_settings) => Arc::new(
-                ListFetcher::new(
-                    list_settings.list_url,
+        let solc_js = SolcJs::new(
+            settings.solc_js.runtime,
+            solc_js_versions(&settings.local_compilers)?,
+        );
+        let validator = Arc::new(SolcValidator::default().with_solc_js(solc_js.clone()));
+        let remote_fetcher: Option<Arc<dyn Fetcher>> = match settings.fetcher {
+            FetcherSettings::List(list_settings) => Some(Arc::new(
+                ListFetcher::with_lists(
+                    list_urls(list_settings, settings.allow_nightly_compilers),
                     settings.compilers_dir,
-                    Some(schedule),
-                    Some(validator),
+                    Some(schedule.clone()),
+                    Some(validator.clone()),
                 )
                 .await?,
-            ),
-            FetcherSettings::S3(s3_settings) => Arc::new(
+            )),
+            FetcherSettings::S3(s3_settings) => Some(Arc::new(
                 S3Fetcher::new(
                     new_bucket(&s3_settings)?,
                     settings.compilers_dir,
-                    Some(schedule),
-                    Some(validator),
+                    Some(schedule.clone()),
+                    Some(validator.clone()),
                 )
                 .await?,
-            ),
+            )),
+            FetcherSettings::Disabled => None,
+        };
+        let fetcher: Arc<dyn Fetcher> = match remote_fetcher {
+            Some(remote_fetcher) if settings.local_compilers.is_empty() => remote_fetcher,
+            remote_fetcher => Arc::new(LocalFetcher::new(
+                local_compilers(settings.local_compilers)?,
+                remote_fetcher,
+                Some(validator),
+            )),
         };
         let compilers = Compilers::new(
             fetcher,
-            SolidityCompiler::new(),
+            SolidityCompiler::new().with_solc_js(solc_js),
             compilers_threads_semaphore,
-        );
+        )
+        .with_nightly_versions(settings.allow_nightly_compilers)
+        .with_checksum_allowlist(ChecksumAllowlist::load(checksums_file)?)
+        .with_eviction_policy(eviction_policy)
+        .with_timeout(compilation_timeout);
         compilers.load_from_dir(&dir).await;
 
-        /* Otherwise, results in compilation warning if all extensions are disabled */
-        #[allow(unused_mut)]
-        let mut client = SolidityClient::new(compilers);
+        // Middlewares are invoked in the order they are added
+        let mut client = SolidityClient::new(compilers)
+            .with_fetcher(bytecode_fetcher)
+            .with_matchers(matchers)
+            .with_hidden_characters_rejected(reject_hidden_characters)
+            .with_middleware(MetricsMiddleware::new("solidity"));
+        if let Some(webhook) = extensions.webhook {
+            client = client.with_middleware(Webhook::new(webhook, "solidity")?);
+        }
 
         #[cfg(feature = "sig-provider-extension")]
         if let Some(sig_provider) = extensions.sig_provider {
@@ -99,28 +242,110 @@ impl SolidityRouter {
 
         Ok(Self {
             client: web::Data::new(client),
+            git_import,
+            etherscan_import,
+            erc20_module,
+            dependencies,
+            jobs: false,
+            intents: None,
+            retained_versions,
+            refresh_schedule: schedule,
         })
     }
+
+    /// Keeps compilers referenced by the contracts stored for the chain from being removed.
+    pub fn spawn_retention_job(&self, chain: Chain) {
+        retention::spawn_retention_job(
+            self.client.clone().into_inner(),
+            self.retained_versions.clone(),
+            self.refresh_schedule.clone(),
+            chain,
+        );
+    }
+
+    pub fn client(&self) -> web::Data<SolidityClient> {
+        self.client.clone()
+    }
+
+    pub fn dependencies(&self) -> Option<web::Data<PackageRegistry>> {
+        self.dependencies.clone()
+    }
+
+    pub fn with_jobs(mut self, enabled: bool) -> Self {
+        self.jobs = enabled;
+        self
+    }
+
+    pub fn with_intents(mut self, settings: Option<IntentsSettings>) -> Self {
+        self.intents = settings.map(web::Data::new);
+        self
+    }
 }
 
 impl Router for SolidityRouter {
     fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        let mut verify = web::scope("/verify")
+            .route(
+                "/multiple-files",
+                web::post().to(solidity_multi_part::verify),
+            )
+            .route(
+                "/multiple-files:upload",
+                web::post().to(solidity_multi_part_upload::verify),
+            )
+            .route("/zip", web::post().to(solidity_multi_part_zip::verify))
+            .route(
+                "/standard-json",
+                web::post().to(solidity_standard_json::verify),
+            );
+        if let Some(erc20_module) = &self.erc20_module {
+            verify = verify.app_data(erc20_module.clone()).route(
+                "/erc20-module",
+                web::post().to(solidity_erc20_module::verify),
+            );
+        }
+        if self.jobs {
+            verify = verify.route(
+                "/multiple-files:async",
+                web::post().to(verification_jobs::submit_solidity_multi_part),
+            );
+        }
+        if let Some(intents) = &self.intents {
+            verify = verify.app_data(intents.clone()).route(
+                "/multiple-files:intent",
+                web::post().to(verification_intents::submit_solidity_multi_part),
+            );
+        }
         service_config
             .app_data(self.client.clone())
-            .service(
-                web::scope("/verify")
-                    .route(
-                        "/multiple-files",
-                        web::post().to(solidity_multi_part::verify),
-                    )
-                    .route(
-                        "/standard-json",
-                        web::post().to(solidity_standard_json::verify),
-                    ),
+            .service(verify)
+            .route("/compile", web::post().to(solidity_compile::compile))
+            .route(
+                "/sources:verify-flattened",
+                web::post().to(solidity_flattened::verify),
+            )
+            .route(
+                "/sources:validate",
+                web::post().to(solidity_sources_validate::validate),
             )
             .route(
                 "/versions",
                 web::get().to(solidity_version_list::get_version_list),
             );
+        if let Some(dependencies) = &self.dependencies {
+            service_config.app_data(dependencies.clone());
+        }
+        if let Some(git_import) = &self.git_import {
+            service_config.app_data(git_import.clone()).route(
+                "/sources:verify-git",
+                web::post().to(solidity_git_import::verify),
+            );
+        }
+        if let Some(etherscan_import) = &self.etherscan_import {
+            service_config.app_data(etherscan_import.clone()).route(
+                "/sources:verify-etherscan",
+                web::post().to(solidity_etherscan_import::verify),
+            );
+        }
     }
 }
@@ -4,20 +4,17 @@ use crate::{
     settings::{Extensions, SourcifySettings},
 };
 use actix_web::web;
-use smart_contract_verifier::SourcifyApiClient;
+use smart_contract_verifier::{
+    middleware::{MetricsMiddleware, Webhook},
+    SourcifyApiClient,
+};
 
 pub struct SourcifyRouter {
     api_client: web::Data<SourcifyApiClient>,
 }
 
 impl SourcifyRouter {
-    pub async fn new(
-        settings: SourcifySettings,
-        /* Otherwise, results in compilation warning if all extensions are disabled */
-        #[allow(unused_variables)] extensions: Extensions,
-    ) -> anyhow::Result<Self> {
-        /* Otherwise, results in compilation warning if all extensions are disabled */
-        #[allow(unused_mut)]
+    pub async fn new(settings: SourcifySettings, extensions: Extensions) -> anyhow::Result<Self> {
         let mut api_client = {
             SourcifyApiClient::new(
                 settings.api_url,
@@ -27,6 +24,12 @@ impl SourcifyRouter {
             .expect("failed to build sourcify client")
         };
 
+        // Middlewares are invoked in the order they are added
+        api_client = api_client.with_middleware(MetricsMiddleware::new("sourcify"));
+        if let Some(webhook) = extensions.webhook {
+            api_client = api_client.with_middleware(Webhook::new(webhook, "sourcify")?);
+        }
+
         #[cfg(feature = "sig-provider-extension")]
         if let Some(sig_provider) = extensions.sig_provider {
             // TODO(#221): create only one instance of middleware/connection
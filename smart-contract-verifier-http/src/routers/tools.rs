@@ -0,0 +1,48 @@
+use super::router::Router;
+use crate::handlers::{
+    tools_abi, tools_abi_diff, tools_bytecode_diff, tools_compute_address, tools_disassemble,
+    tools_source_diff,
+};
+use actix_web::web;
+use smart_contract_verifier::SolidityClient;
+
+/// Serves utilities helping to inspect bytecodes and debug failed verifications.
+#[derive(Default)]
+pub struct ToolsRouter {
+    /// Is used to compile init codes. Is not set if solidity verification is disabled.
+    solidity_client: Option<web::Data<SolidityClient>>,
+}
+
+impl ToolsRouter {
+    pub fn new(solidity_client: Option<web::Data<SolidityClient>>) -> Self {
+        Self { solidity_client }
+    }
+}
+
+impl Router for ToolsRouter {
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        if let Some(client) = &self.solidity_client {
+            service_config.app_data(client.clone());
+        }
+        service_config
+            .route("/abi-encode", web::post().to(tools_abi::abi_encode))
+            .route("/abi-decode", web::post().to(tools_abi::abi_decode))
+            .route("/abi-diff", web::get().to(tools_abi_diff::abi_diff))
+            .route(
+                "/bytecode-diff",
+                web::post().to(tools_bytecode_diff::bytecode_diff),
+            )
+            .route(
+                "/compute-address",
+                web::post().to(tools_compute_address::compute_address),
+            )
+            .route(
+                "/disassemble",
+                web::get().to(tools_disassemble::disassemble),
+            )
+            .route(
+                "/source-diff",
+                web::get().to(tools_source_diff::source_diff),
+            );
+    }
+}
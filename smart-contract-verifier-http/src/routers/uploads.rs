@@ -0,0 +1,28 @@
+use super::router::Router;
+use crate::{handlers::uploads, uploads::Uploads};
+use actix_web::web;
+
+/// Serves uploads of payloads referenced by verification requests.
+pub struct UploadsRouter {
+    uploads: web::Data<Uploads>,
+}
+
+impl UploadsRouter {
+    pub fn new(uploads: web::Data<Uploads>) -> Self {
+        Self { uploads }
+    }
+
+    /// Is registered as app data, as verification requests may reference uploaded payloads.
+    pub fn uploads(&self) -> web::Data<Uploads> {
+        self.uploads.clone()
+    }
+}
+
+impl Router for UploadsRouter {
+    fn register_routes(&self, service_config: &mut web::ServiceConfig) {
+        service_config
+            .route("", web::post().to(uploads::create_upload))
+            .route("/{upload_id}", web::put().to(uploads::upload))
+            .route("/{upload_id}", web::get().to(uploads::get_upload));
+    }
+}
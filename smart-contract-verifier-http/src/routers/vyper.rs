@@ -1,11 +1,14 @@
-use super::router::Router;
+use super::{router::Router, solidity::list_urls};
 use crate::{
     handlers::{vyper_multi_part, vyper_version_list},
     settings::{Extensions, FetcherSettings, VyperSettings},
 };
 use actix_web::web;
-use smart_contract_verifier::{Compilers, ListFetcher, VyperClient, VyperCompiler};
-use std::sync::Arc;
+use smart_contract_verifier::{
+    middleware::{MetricsMiddleware, Webhook},
+    Compilers, ListFetcher, Matchers, VyperClient, VyperCompiler,
+};
+use std::{sync::Arc, time::Duration};
 use tokio::sync::Semaphore;
 
 pub struct VyperRouter {
@@ -15,32 +18,44 @@ pub struct VyperRouter {
 impl VyperRouter {
     pub async fn new(
         settings: VyperSettings,
-        /* Otherwise, results in compilation warning if all extensions are disabled */
-        #[allow(unused_variables)] extensions: Extensions,
+        extensions: Extensions,
         compilers_threads_semaphore: Arc<Semaphore>,
+        compilation_timeout: Option<Duration>,
+        matchers: Matchers,
+        reject_hidden_characters: bool,
     ) -> anyhow::Result<Self> {
         let dir = settings.compilers_dir.clone();
-        let list_url = match settings.fetcher {
-            FetcherSettings::List(s) => s.list_url,
+        // Vyper compilers are not gated, so the list of nightly builds is used whenever set
+        let list_urls = match settings.fetcher {
+            FetcherSettings::List(s) => list_urls(s, true),
             FetcherSettings::S3(_) => {
                 return Err(anyhow::anyhow!("S3 fetcher for vyper not supported"))
             }
+            FetcherSettings::Disabled => {
+                return Err(anyhow::anyhow!("vyper requires compilers fetcher to be enabled"))
+            }
         };
         let fetcher = Arc::new(
-            ListFetcher::new(
-                list_url,
+            ListFetcher::with_lists(
+                list_urls,
                 settings.compilers_dir,
                 Some(settings.refresh_versions_schedule),
                 None,
             )
             .await?,
         );
-        let compilers = Compilers::new(fetcher, VyperCompiler::new(), compilers_threads_semaphore);
+        let compilers = Compilers::new(fetcher, VyperCompiler::new(), compilers_threads_semaphore)
+            .with_timeout(compilation_timeout);
         compilers.load_from_dir(&dir).await;
 
-        /* Otherwise, results in compilation warning if all extensions are disabled */
-        #[allow(unused_mut)]
-        let mut client = VyperClient::new(compilers);
+        // Middlewares are invoked in the order they are added
+        let mut client = VyperClient::new(compilers)
+            .with_matchers(matchers)
+            .with_hidden_characters_rejected(reject_hidden_characters)
+            .with_middleware(MetricsMiddleware::new("vyper"));
+        if let Some(webhook) = extensions.webhook {
+            client = client.with_middleware(Webhook::new(webhook, "vyper")?);
+        }
 
         #[cfg(feature = "sig-provider-extension")]
         if let Some(sig_provider) = extensions.sig_provider {
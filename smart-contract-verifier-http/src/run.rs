@@ -1,9 +1,16 @@
 use crate::{
+    access_log, api_error, chains, headers, idempotency,
     metrics::Metrics,
+    rate_limit, read_only,
+    request_id::{self, RequestIdRootSpanBuilder},
     routers::{configure_router, AppRouter},
     settings::Settings,
+    strict_mode, tenants,
+};
+use actix_web::{
+    middleware::{Compat, Condition},
+    web, App, HttpServer,
 };
-use actix_web::{App, HttpServer};
 use futures::future;
 use std::sync::Arc;
 use tracing_actix_web::TracingLogger;
@@ -13,6 +20,8 @@ pub async fn run(settings: Settings) -> std::io::Result<()> {
     let metrics_enabled = settings.metrics.enabled;
     let metrics_addr = settings.metrics.addr;
     let metrics_endpoint = settings.metrics.route.clone();
+    let cors_settings = settings.server.cors.clone();
+    let security_headers_settings = settings.server.security_headers.clone();
 
     tracing::info!("smart-contract verifier is starting at {}", socket_addr);
     let app_router = Arc::new(
@@ -25,9 +34,28 @@ pub async fn run(settings: Settings) -> std::io::Result<()> {
         let middleware = metrics.middleware().clone();
         HttpServer::new(move || {
             App::new()
+                .wrap_fn(strict_mode::check_expectation)
+                .wrap_fn(read_only::reject_writes)
+                .wrap_fn(chains::reject_other_chains)
+                .wrap_fn(rate_limit::limit)
+                .wrap_fn(access_log::record)
+                .wrap_fn(idempotency::hash_body)
+                .wrap_fn(tenants::scope_request)
+                .wrap_fn(request_id::set_response_header)
                 .wrap(middleware.clone())
-                .wrap(TracingLogger::default())
+                .wrap(TracingLogger::<RequestIdRootSpanBuilder>::new())
+                // Response bodies are boxed by `Compat`, so that CORS middleware
+                // (which changes the body type) could be applied conditionally
+                .wrap(Compat::new(Condition::new(
+                    security_headers_settings.enabled,
+                    headers::security_headers(&security_headers_settings),
+                )))
+                .wrap(Condition::new(
+                    cors_settings.enabled,
+                    Compat::new(headers::cors(&cors_settings)),
+                ))
                 .configure(configure_router(&*app_router))
+                .default_service(web::to(api_error::route_not_found))
         })
         .bind(socket_addr)?
         .run()
@@ -1,30 +1,57 @@
+use crate::{analysis::FindingSeverity, compression::ContentEncoding};
 use anyhow::anyhow;
 use config::{Config, File};
 use cron::Schedule;
 use serde::{de, Deserialize};
 use serde_with::{serde_as, DisplayFromStr};
 use smart_contract_verifier::{
+    middleware::WebhookConfig, MatcherKind, Matchers, DEFAULT_RPC_URL,
     DEFAULT_SOLIDITY_COMPILER_LIST, DEFAULT_SOURCIFY_HOST, DEFAULT_VYPER_COMPILER_LIST,
 };
 use std::{
+    collections::{BTreeMap, BTreeSet},
     net::SocketAddr,
-    num::{NonZeroU32, NonZeroUsize},
+    num::{NonZeroU32, NonZeroU64, NonZeroUsize},
     path::PathBuf,
     str::FromStr,
 };
 use url::Url;
 
-#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
 #[serde(default, deny_unknown_fields)]
 pub struct Settings {
     pub server: ServerSettings,
     pub solidity: SoliditySettings,
     pub vyper: VyperSettings,
+    pub huff: HuffSettings,
     pub sourcify: SourcifySettings,
+    pub rpc: RpcSettings,
+    pub chains: ChainsSettings,
     pub metrics: MetricsSettings,
+    pub logs: LogsSettings,
     pub jaeger: JaegerSettings,
+    pub otlp: OtlpSettings,
     pub compilers: CompilersSettings,
+    pub comparison: ComparisonSettings,
     pub extensions: ExtensionsSettings,
+    pub admin: AdminSettings,
+    pub attestation: AttestationSettings,
+    pub impersonation: ImpersonationSettings,
+    pub metadata_availability: MetadataAvailabilitySettings,
+    pub analysis: AnalysisSettings,
+    pub backup: BackupSettings,
+    pub blobs: BlobsSettings,
+    pub uploads: UploadsSettings,
+    pub compression: CompressionSettings,
+    pub access_log: AccessLogSettings,
+    pub rate_limit: RateLimitSettings,
+    pub jobs: JobsSettings,
+    pub tenants: TenantsSettings,
+    pub bootstrap: BootstrapSettings,
+    pub proxy_history: ProxyHistorySettings,
+    pub chain_watcher: ChainWatcherSettings,
+    pub intents: IntentsSettings,
+    pub source_screening: SourceScreeningSettings,
 
     // Is required as we deny unknown fields, but allow users provide
     // path to config through PREFIX__CONFIG env variable. If removed,
@@ -36,6 +63,14 @@ pub struct Settings {
 #[serde(default, deny_unknown_fields)]
 pub struct ServerSettings {
     pub addr: SocketAddr,
+    /// Max size (in bytes) of a request body, both for json and multipart requests.
+    pub max_request_size: usize,
+    pub cors: CorsSettings,
+    pub security_headers: SecurityHeadersSettings,
+    pub idempotency: IdempotencySettings,
+    /// If set, verification requests and admin actions are rejected, and the database
+    /// is not written to, so that the instance serves lookups only.
+    pub read_only: bool,
 }
 
 impl Default for ServerSettings {
@@ -43,6 +78,87 @@ impl Default for ServerSettings {
         Self {
             //This is part for creation server
             addr: SocketAddr::from_str("0.0.0.0:3000").expect("should be valid url"),
+            max_request_size: 2 * 1024 * 1024,
+            cors: Default::default(),
+            security_headers: Default::default(),
+            idempotency: Default::default(),
+            read_only: false,
+        }
+    }
+}
+
+/// Cross-origin requests allowed for browsers calling the api directly.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsSettings {
+    pub enabled: bool,
+    /// Origins (e.g., "https://explorer.example.com") allowed to call the api; "*" allows any.
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    /// Request headers allowed in addition to the CORS-safelisted ones; "*" allows any.
+    pub allowed_headers: Vec<String>,
+    /// Period (in seconds) the result of a preflight request may be cached for.
+    pub max_age: Option<usize>,
+}
+
+impl Default for CorsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_origins: vec!["*".to_string()],
+            allowed_methods: ["GET", "POST", "DELETE"].map(str::to_string).to_vec(),
+            allowed_headers: [
+                "content-type",
+                "idempotency-key",
+                "if-none-match",
+                "x-api-key",
+                "x-request-id",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            max_age: Some(3600),
+        }
+    }
+}
+
+/// Standard security headers (e.g., `X-Content-Type-Options`) added to every response.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecurityHeadersSettings {
+    pub enabled: bool,
+    /// If set, `Strict-Transport-Security` header is added with the given max age (in seconds).
+    /// Should be set only if the api is served over https.
+    pub hsts_max_age: Option<u64>,
+}
+
+impl Default for SecurityHeadersSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hsts_max_age: None,
+        }
+    }
+}
+
+/// Responses to verification requests remembered by their `Idempotency-Key` headers,
+/// so that retried requests are not verified again.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdempotencySettings {
+    pub enabled: bool,
+    /// Period (in seconds) the response is returned for repeated requests with the same key.
+    pub ttl_secs: u64,
+    /// Max number of remembered keys. Requests with new keys are verified
+    /// as usual, but their responses are not remembered, when exceeded.
+    pub max_keys: usize,
+}
+
+impl Default for IdempotencySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_secs: 24 * 60 * 60,
+            max_keys: 10000,
         }
     }
 }
@@ -70,6 +186,29 @@ pub struct SoliditySettings {
     #[serde_as(as = "DisplayFromStr")]
     pub refresh_versions_schedule: Schedule,
     pub fetcher: FetcherSettings,
+    /// If set, nightly compiler builds may be used for verification.
+    pub allow_nightly_compilers: bool,
+    /// Compiler binaries available locally by their versions. Are used instead
+    /// of downloading the corresponding versions.
+    pub local_compilers: BTreeMap<String, LocalCompilerSettings>,
+    /// Json file the checksums of compiler binaries are pinned in.
+    /// If omitted, `checksums.json` inside `compilers_dir` is used.
+    pub checksums_file: Option<PathBuf>,
+    /// Max total size of downloaded compilers in megabytes. When exceeded,
+    /// least recently used compilers are removed from the disk.
+    pub compilers_dir_quota_mb: Option<u64>,
+    /// Compiler versions never removed from the disk. Versions referenced
+    /// by stored contracts are retained as well.
+    pub retained_compilers: Vec<String>,
+    pub warm_up: WarmUpSettings,
+    pub git_import: GitImportSettings,
+    pub etherscan_import: EtherscanImportSettings,
+    pub erc20_module: Erc20ModuleSettings,
+    pub dependencies: DependenciesSettings,
+    pub solc_js: SolcJsSettings,
+    /// If set, source maps and ASTs of verified contracts are stored for debuggers
+    /// and coverage tools. Disabled by default, as ASTs are large.
+    pub store_debug_info: bool,
 }
 
 impl Default for SoliditySettings {
@@ -81,6 +220,18 @@ impl Default for SoliditySettings {
             compilers_dir: default_dir,
             refresh_versions_schedule: Schedule::from_str("0 0 * * * * *").unwrap(), // every hour
             fetcher: Default::default(),
+            allow_nightly_compilers: true,
+            local_compilers: Default::default(),
+            checksums_file: None,
+            compilers_dir_quota_mb: None,
+            retained_compilers: vec![],
+            warm_up: Default::default(),
+            git_import: Default::default(),
+            etherscan_import: Default::default(),
+            erc20_module: Default::default(),
+            dependencies: Default::default(),
+            solc_js: Default::default(),
+            store_debug_info: false,
         }
     }
 }
@@ -102,6 +253,7 @@ impl Default for VyperSettings {
         default_dir.push("vyper-compilers");
         let fetcher = FetcherSettings::List(ListFetcherSettings {
             list_url: Url::try_from(DEFAULT_VYPER_COMPILER_LIST).expect("valid url"),
+            nightly_list_url: None,
         });
         Self {
             enabled: true,
@@ -112,11 +264,41 @@ impl Default for VyperSettings {
     }
 }
 
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HuffSettings {
+    pub enabled: bool,
+    pub compilers_dir: PathBuf,
+    #[serde_as(as = "DisplayFromStr")]
+    pub refresh_versions_schedule: Schedule,
+    /// There is no public list of `huffc` releases, so compilers are not downloaded by default.
+    pub fetcher: FetcherSettings,
+    /// `huffc` binaries available locally by their versions (e.g., "v0.3.2+commit.e3d4ce5b").
+    pub local_compilers: BTreeMap<String, LocalCompilerSettings>,
+}
+
+impl Default for HuffSettings {
+    fn default() -> Self {
+        let mut default_dir = std::env::temp_dir();
+        default_dir.push("huff-compilers");
+        Self {
+            enabled: false,
+            compilers_dir: default_dir,
+            refresh_versions_schedule: Schedule::from_str("0 0 * * * * *").unwrap(), // every hour
+            fetcher: FetcherSettings::Disabled,
+            local_compilers: Default::default(),
+        }
+    }
+}
+
 #[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub enum FetcherSettings {
     List(ListFetcherSettings),
     S3(S3FetcherSettings),
+    /// Compilers are never downloaded; only local compilers are available.
+    Disabled,
 }
 
 impl Default for FetcherSettings {
@@ -129,16 +311,163 @@ impl Default for FetcherSettings {
 #[serde(default, deny_unknown_fields)]
 pub struct ListFetcherSettings {
     pub list_url: Url,
+    /// List of nightly builds. For solidity, used only if nightly compilers are allowed.
+    pub nightly_list_url: Option<Url>,
 }
 
 impl Default for ListFetcherSettings {
     fn default() -> Self {
         Self {
             list_url: Url::try_from(DEFAULT_SOLIDITY_COMPILER_LIST).expect("valid url"),
+            nightly_list_url: None,
         }
     }
 }
 
+/// Compilers downloaded on start, so that first verifications do not wait for downloads.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WarmUpSettings {
+    pub versions: Vec<String>,
+    /// Number of compiler versions the most of stored contracts
+    /// have been verified with to be downloaded as well.
+    pub most_used: usize,
+}
+
+/// Verification of contracts which sources are fetched from public git repositories.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitImportSettings {
+    pub enabled: bool,
+    /// Max time for cloning the repository along with its submodules.
+    pub timeout_secs: u64,
+    /// Max size of the cloned repository on the disk in megabytes.
+    pub max_repository_size_mb: u64,
+}
+
+impl Default for GitImportSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_secs: 60,
+            max_repository_size_mb: 100,
+        }
+    }
+}
+
+/// Verification of contracts which sources are imported from Etherscan-compatible apis
+/// of other chains the same contracts have been verified on.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EtherscanImportSettings {
+    pub enabled: bool,
+    /// Max time for fetching the sources from the explorer api.
+    pub timeout_secs: u64,
+    /// Api keys of explorers by their api hosts (e.g., "api.etherscan.io"),
+    /// used if the request provides no key.
+    pub api_keys: BTreeMap<String, String>,
+}
+
+impl Default for EtherscanImportSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_secs: 30,
+            api_keys: BTreeMap::new(),
+        }
+    }
+}
+
+/// Verification of ERC-20 contracts deployed by the `x/erc20` module with the canonical
+/// sources of the module contract.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Erc20ModuleSettings {
+    pub enabled: bool,
+    /// Toml manifest listing versions of the module contract by chain upgrade heights.
+    pub manifest: PathBuf,
+}
+
+impl Default for Erc20ModuleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            manifest: PathBuf::from("config/erc20_module/versions.toml"),
+        }
+    }
+}
+
+/// Injection of sources of well-known packages imported by multi-part requests
+/// but missing in them (e.g., forgotten OpenZeppelin files).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DependenciesSettings {
+    pub enabled: bool,
+    /// Directory containing sources of each package version
+    /// in `<package>@<version>` subdirectory.
+    pub registry_dir: PathBuf,
+    /// Versions of the packages injected by their names (e.g., "@openzeppelin/contracts").
+    pub packages: BTreeMap<String, String>,
+    /// If set, versions of packages declared by requests are fetched from the npm registry
+    /// (or its mirror) and cached in `registry_dir`.
+    pub npm_registry_url: Option<Url>,
+    pub fetch_timeout_secs: u64,
+    /// Max size of a single package tarball in megabytes.
+    pub max_package_size_mb: u64,
+}
+
+impl Default for DependenciesSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            registry_dir: PathBuf::from("packages"),
+            packages: BTreeMap::new(),
+            npm_registry_url: None,
+            fetch_timeout_secs: 60,
+            max_package_size_mb: 50,
+        }
+    }
+}
+
+/// Running of emscripten compiler builds (`soljson.js`) for platforms
+/// the native compiler binaries are not available for.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SolcJsSettings {
+    /// JavaScript runtime compatible with Node.js the builds are run with.
+    pub runtime: PathBuf,
+}
+
+impl Default for SolcJsSettings {
+    fn default() -> Self {
+        Self {
+            runtime: PathBuf::from("node"),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct LocalCompilerSettings {
+    pub path: PathBuf,
+    /// Hex encoded sha256 hash the binary is expected to have.
+    pub sha256: String,
+    #[serde(default)]
+    pub executor: CompilerExecutor,
+}
+
+/// The way a local compiler is run.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CompilerExecutor {
+    /// The compiler is a native binary.
+    #[default]
+    Native,
+    /// The compiler is a `soljson.js` build run with the solc-js runtime.
+    /// Supported for solidity compilers only.
+    SolcJs,
+}
+
 #[derive(Deserialize, Default, Clone, PartialEq, Eq, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct S3FetcherSettings {
@@ -171,6 +500,82 @@ impl Default for SourcifySettings {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcSettings {
+    /// Rpc providers of the chain. Requests are sent to the healthiest of them
+    /// and fail over to the others in case of errors.
+    pub urls: Vec<Url>,
+    /// Max number of attempts made for a single request across all providers.
+    pub max_attempts: NonZeroU32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Number of consecutive failures after which the provider
+    /// is not used for `circuit_open_secs` seconds.
+    pub failure_threshold: NonZeroU32,
+    pub circuit_open_secs: u64,
+    pub request_timeout: u64,
+    /// Max number of requests sent in a single JSON-RPC batch
+    /// (used to fetch the code of many contracts at once, e.g. during audits).
+    pub batch_size: NonZeroUsize,
+    /// Rpc endpoints of other chains (by their names). If a contract is not found
+    /// on the current chain, these are checked to hint the user to the right one.
+    pub other_chains: BTreeMap<String, Url>,
+    /// Max time (in seconds) the creation transaction of a verified contract is looked up for
+    /// in the background once the contract is stored. Zero disables the lookup.
+    pub creation_lookup_timeout_secs: u64,
+}
+
+impl Default for RpcSettings {
+    fn default() -> Self {
+        Self {
+            urls: vec![Url::try_from(DEFAULT_RPC_URL).expect("valid url")],
+            max_attempts: NonZeroU32::new(3).expect("Is not zero"),
+            initial_backoff_ms: 200,
+            max_backoff_ms: 5000,
+            failure_threshold: NonZeroU32::new(5).expect("Is not zero"),
+            circuit_open_secs: 30,
+            request_timeout: 10,
+            batch_size: NonZeroUsize::new(100).expect("Is not zero"),
+            other_chains: BTreeMap::new(),
+            creation_lookup_timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChainsSettings {
+    /// Name of the chain contracts are verified for, i.e., the one `rpc.urls` belong to.
+    pub current: String,
+    /// Known chains by their names. Verified contracts of each chain
+    /// are stored in a separate database namespace.
+    pub registry: BTreeMap<String, ChainSettings>,
+    /// If set, contracts stored before namespacing by chains are moved
+    /// into the namespace of the current chain on start. Is disabled by default,
+    /// as the legacy contracts must be moved into the chain they have been verified for.
+    pub migrate_legacy_database: bool,
+}
+
+impl Default for ChainsSettings {
+    fn default() -> Self {
+        Self {
+            current: "mainnet".to_string(),
+            registry: BTreeMap::from([
+                ("mainnet".to_string(), ChainSettings { chain_id: 9001 }),
+                ("testnet".to_string(), ChainSettings { chain_id: 9000 }),
+            ]),
+            migrate_legacy_database: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainSettings {
+    pub chain_id: u64,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct MetricsSettings {
@@ -189,6 +594,34 @@ impl Default for MetricsSettings {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogsSettings {
+    pub format: LogFormat,
+    /// Log levels in `RUST_LOG` syntax (e.g., "info,smart_contract_verifier=debug").
+    /// `RUST_LOG` env variable takes precedence if set.
+    pub filter: String,
+}
+
+impl Default for LogsSettings {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Text,
+            filter: "info".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// One json object per line (e.g., for Loki or ELK ingestion).
+    Json,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct JaegerSettings {
@@ -205,10 +638,35 @@ impl Default for JaegerSettings {
     }
 }
 
+/// Export of traces to an OpenTelemetry collector over OTLP (grpc).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OtlpSettings {
+    pub enabled: bool,
+    pub endpoint: String,
+    /// Share of traces started by the service which are recorded (from 0 to 1).
+    /// Traces started by the callers are recorded if the callers have sampled them.
+    pub sampling_ratio: f64,
+    pub timeout_secs: u64,
+}
+
+impl Default for OtlpSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:4317".to_string(),
+            sampling_ratio: 1.0,
+            timeout_secs: 10,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct CompilersSettings {
     pub max_threads: NonZeroUsize,
+    /// Max time (in seconds) a single compilation may take. Zero disables the limit.
+    pub timeout_secs: u64,
 }
 
 impl Default for CompilersSettings {
@@ -217,7 +675,27 @@ impl Default for CompilersSettings {
             tracing::warn!("cannot get number of CPU cores: {}", e);
             NonZeroUsize::new(8).unwrap()
         });
-        Self { max_threads }
+        Self {
+            max_threads,
+            timeout_secs: 600,
+        }
+    }
+}
+
+/// Strategies compiled bytecodes are compared with the on-chain ones,
+/// shared by all languages.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ComparisonSettings {
+    /// Matchers tried in order; the first matching one determines the match type.
+    pub matchers: Vec<MatcherKind>,
+}
+
+impl Default for ComparisonSettings {
+    fn default() -> Self {
+        Self {
+            matchers: Matchers::DEFAULT_KINDS.to_vec(),
+        }
     }
 }
 
@@ -227,15 +705,499 @@ pub struct ExtensionsSettings {
     pub solidity: Extensions,
     pub sourcify: Extensions,
     pub vyper: Extensions,
+    pub huff: Extensions,
 }
 
 #[derive(Default, Deserialize, Clone, PartialEq, Eq, Debug)]
 #[serde(default, deny_unknown_fields)]
 pub struct Extensions {
+    /// If set, the url is notified about each verified contract.
+    pub webhook: Option<WebhookConfig>,
     #[cfg(feature = "sig-provider-extension")]
     pub sig_provider: Option<sig_provider_extension::Config>,
 }
 
+#[serde_as]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminSettings {
+    pub enabled: bool,
+    /// If set, all stored contracts are periodically re-verified
+    /// against the current on-chain bytecode.
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub audit_schedule: Option<Schedule>,
+    /// Maps admin names into api keys they authenticate with.
+    /// Admins are allowed to remove stored contracts and to override existing verifications.
+    pub api_keys: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttestationSettings {
+    pub enabled: bool,
+    /// Hex encoded secp256k1 private key attestations are signed with.
+    pub private_key: Option<String>,
+}
+
+/// Warnings attached to verified contracts named as well-known ones
+/// (e.g., "WETH9"), which code differs from their canonical deployments.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImpersonationSettings {
+    pub enabled: bool,
+    /// Maps names of well-known contracts into hex encoded keccak256 hashes of the
+    /// metadata-stripped deployed code of their canonical deployments (see `code_hash` output).
+    /// Names are matched case-insensitively.
+    pub canonical: BTreeMap<String, Vec<String>>,
+}
+
+/// Checks run after verification that the metadata files referenced by the hashes
+/// in the code of verified contracts are available on IPFS or Swarm.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetadataAvailabilitySettings {
+    pub enabled: bool,
+    /// Gateways metadata files are requested from by their IPFS CIDs appended to the urls.
+    /// Are tried in order until one of them serves the file.
+    pub ipfs_gateways: Vec<Url>,
+    /// Gateways metadata files are requested from by their hex encoded Swarm hashes.
+    /// If empty, availability of the files on Swarm is reported as unknown.
+    pub swarm_gateways: Vec<Url>,
+    /// Timeout (in seconds) of a single request to a gateway.
+    pub request_timeout: u64,
+}
+
+impl Default for MetadataAvailabilitySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ipfs_gateways: vec![Url::try_from("https://ipfs.io/ipfs/").expect("valid url")],
+            swarm_gateways: vec![],
+            request_timeout: 10,
+        }
+    }
+}
+
+/// Static analysis of verified solidity contracts, run in the background after they are stored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalysisSettings {
+    pub enabled: bool,
+    /// Findings less severe than that are not stored.
+    pub min_severity: FindingSeverity,
+    pub slither: SlitherSettings,
+}
+
+/// Slither run in addition to the built-in checks. Requires `slither` to be installed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SlitherSettings {
+    pub enabled: bool,
+    /// Path to the slither executable.
+    pub path: PathBuf,
+    /// Slither is killed if it does not complete in that many seconds.
+    pub timeout_secs: u64,
+    /// Max number of slither processes run at once.
+    pub max_concurrency: NonZeroUsize,
+    /// Command (with its arguments) slither is run by to be isolated from the host
+    /// (e.g., `["firejail", "--quiet", "--net=none"]`). Is required: slither is not run
+    /// without a sandbox. `{sources}` and `{solc}` arguments are replaced with the directory
+    /// of the sources and the path to the compiler. By default, it is run by bubblewrap
+    /// without network access, with only the system directories (read-only), the sources
+    /// and the compiler mounted.
+    pub sandbox: Vec<String>,
+}
+
+impl Default for SlitherSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: PathBuf::from("slither"),
+            timeout_secs: 120,
+            max_concurrency: NonZeroUsize::new(2).expect("non zero"),
+            sandbox: [
+                "bwrap",
+                "--unshare-all",
+                "--die-with-parent",
+                "--ro-bind",
+                "/usr",
+                "/usr",
+                "--ro-bind-try",
+                "/bin",
+                "/bin",
+                "--ro-bind-try",
+                "/lib",
+                "/lib",
+                "--ro-bind-try",
+                "/lib64",
+                "/lib64",
+                "--proc",
+                "/proc",
+                "--dev",
+                "/dev",
+                "--tmpfs",
+                "/tmp",
+                "--bind",
+                "{sources}",
+                "{sources}",
+                "--ro-bind",
+                "{solc}",
+                "{solc}",
+                "--",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+/// Periodic snapshots of the database of the current chain.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    #[serde_as(as = "DisplayFromStr")]
+    pub schedule: Schedule,
+    pub storage: ObjectStorageSettings,
+    /// Number of the latest snapshots kept in the storage. Older ones are removed.
+    pub keep_last: NonZeroUsize,
+    /// If set, the latest snapshot is restored on start when the database
+    /// has no verified contracts (e.g., the service is deployed with a fresh database).
+    pub restore_on_start: bool,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        let mut default_dir = std::env::temp_dir();
+        default_dir.push("verifier-backups");
+        Self {
+            enabled: false,
+            schedule: Schedule::from_str("0 0 0 * * * *").unwrap(), // every day
+            storage: ObjectStorageSettings::Local(LocalStorageSettings { dir: default_dir }),
+            keep_last: NonZeroUsize::new(7).expect("Is not zero"),
+            restore_on_start: false,
+        }
+    }
+}
+
+/// Storage large sources and compiler outputs are kept in, so that documents
+/// of the database keep only references to them.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlobsSettings {
+    pub enabled: bool,
+    pub storage: ObjectStorageSettings,
+    /// Sources and compiler outputs which take less space (in kilobytes, serialized as json)
+    /// are kept in the database.
+    pub min_size_kb: usize,
+}
+
+impl Default for BlobsSettings {
+    fn default() -> Self {
+        let mut default_dir = std::env::temp_dir();
+        default_dir.push("verifier-blobs");
+        Self {
+            enabled: false,
+            storage: ObjectStorageSettings::Local(LocalStorageSettings { dir: default_dir }),
+            min_size_kb: 256,
+        }
+    }
+}
+
+/// Storage of payloads too large to be sent in verification requests (e.g., standard-json inputs),
+/// which are uploaded beforehand and referenced by the requests.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UploadsSettings {
+    pub enabled: bool,
+    /// Payloads are uploaded to s3 storage directly through pre-signed urls,
+    /// and through the upload endpoint of the service to local one.
+    pub storage: ObjectStorageSettings,
+    /// Max size (in bytes) of an uploaded payload.
+    pub max_size: usize,
+    /// Time (in seconds) after which upload slots expire and uploaded payloads are removed.
+    pub ttl_secs: NonZeroU64,
+}
+
+impl Default for UploadsSettings {
+    fn default() -> Self {
+        let mut default_dir = std::env::temp_dir();
+        default_dir.push("verifier-uploads");
+        Self {
+            enabled: false,
+            storage: ObjectStorageSettings::Local(LocalStorageSettings { dir: default_dir }),
+            max_size: 256 * 1024 * 1024,
+            ttl_secs: NonZeroU64::new(3600).expect("Is not zero"),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub enum ObjectStorageSettings {
+    Local(LocalStorageSettings),
+    S3(S3FetcherSettings),
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct LocalStorageSettings {
+    pub dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompressionSettings {
+    /// Encoding newly stored sources and compiler outputs are compressed with.
+    /// Stored ones are read regardless of the encoding they have been stored with.
+    pub encoding: ContentEncoding,
+}
+
+/// Log of requests submitted to the service (who has submitted what, and with which outcome).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AccessLogSettings {
+    pub enabled: bool,
+    /// Records older than that (in days) are removed.
+    pub retention_days: NonZeroU32,
+    /// If set, submitted source contents are replaced with their sizes before being stored.
+    pub redact_sources: bool,
+    /// If set, the last octet of IPv4 (the last 80 bits of IPv6) client addresses is zeroed.
+    pub anonymize_ips: bool,
+    /// Request bodies larger than that (in bytes) are not stored.
+    pub max_body_size: usize,
+}
+
+impl Default for AccessLogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: NonZeroU32::new(30).expect("Is not zero"),
+            redact_sources: true,
+            anonymize_ips: false,
+            max_body_size: 1024 * 1024,
+        }
+    }
+}
+
+/// Limits of requests per client ip address and (optionally) for all clients together.
+/// Requests made with admin api keys are not limited.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitSettings {
+    pub enabled: bool,
+    pub store: CounterStoreSettings,
+    /// If set, client addresses are taken from `Forwarded` and `X-Forwarded-For` headers.
+    /// Should be enabled only behind a proxy setting them, as clients could spoof them otherwise.
+    pub trust_forwarded_headers: bool,
+    pub per_ip: RouteBudgets,
+    pub global: Option<RouteBudgets>,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            store: CounterStoreSettings::Memory,
+            trust_forwarded_headers: false,
+            per_ip: RouteBudgets {
+                heavy: RateBudget::new(10, 60),
+                light: RateBudget::new(300, 60),
+            },
+            global: None,
+        }
+    }
+}
+
+/// Store request counters are kept in.
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub enum CounterStoreSettings {
+    /// Counters are local to the instance.
+    Memory,
+    /// Counters are shared by all instances (replicas) using the same redis.
+    Redis(RedisSettings),
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RedisSettings {
+    pub url: String,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RouteBudgets {
+    /// Budget of compile-heavy routes (verification and compilation requests).
+    pub heavy: RateBudget,
+    /// Budget of the rest (read-only) routes.
+    pub light: RateBudget,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RateBudget {
+    /// Number of requests allowed per period.
+    pub requests: NonZeroU32,
+    pub period_secs: NonZeroU64,
+}
+
+impl RateBudget {
+    pub fn new(requests: u32, period_secs: u64) -> Self {
+        Self {
+            requests: NonZeroU32::new(requests).expect("Is not zero"),
+            period_secs: NonZeroU64::new(period_secs).expect("Is not zero"),
+        }
+    }
+}
+
+/// Queue of verification jobs submitted asynchronously. The queue is kept in the database,
+/// so that jobs submitted to any replica could be run by workers of any other one.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobsSettings {
+    pub enabled: bool,
+    /// Number of jobs run by the instance concurrently.
+    pub workers: NonZeroUsize,
+    /// Time (in seconds) a job is leased to a worker for. Leases are renewed while jobs run,
+    /// so that jobs of stopped workers are picked up by others once their leases expire.
+    pub lease_secs: NonZeroU64,
+    /// Number of times a job is run before it is considered failed, if it fails
+    /// because of the service (e.g., the node is unavailable) rather than the request.
+    pub max_attempts: NonZeroU32,
+    /// Time (in seconds) after which failed jobs are retried.
+    pub retry_delay_secs: u64,
+    /// Time (in milliseconds) idle workers wait for before checking the queue again.
+    pub poll_interval_ms: NonZeroU64,
+    /// Finished and failed jobs older than that (in days) are removed.
+    pub retention_days: NonZeroU32,
+}
+
+impl Default for JobsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            workers: NonZeroUsize::new(2).expect("Is not zero"),
+            lease_secs: NonZeroU64::new(60).expect("Is not zero"),
+            max_attempts: NonZeroU32::new(3).expect("Is not zero"),
+            retry_delay_secs: 10,
+            poll_interval_ms: NonZeroU64::new(1000).expect("Is not zero"),
+            retention_days: NonZeroU32::new(7).expect("Is not zero"),
+        }
+    }
+}
+
+/// Tenants the service is shared by (e.g., operators of different appchains). Contracts verified
+/// with api keys of a tenant are stored in its own namespaces and are looked up with them only.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantsSettings {
+    pub enabled: bool,
+    /// Maps tenant names into their settings. Names consist of lowercase letters,
+    /// digits and underscores, as they become part of database names.
+    pub registry: BTreeMap<String, TenantSettings>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantSettings {
+    /// Api keys requests of the tenant are authenticated with (see `admin.api_keys`).
+    pub api_keys: Vec<String>,
+    /// Max number of verification requests per day (UTC). Unlimited if not set.
+    pub max_verifications_per_day: Option<u64>,
+    /// Max size (in bytes) of the database of the tenant. Unlimited if not set.
+    pub max_storage_bytes: Option<u64>,
+}
+
+/// Seeding of the canonical contracts of the chain (e.g., WEVMOS) on startup.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BootstrapSettings {
+    pub enabled: bool,
+    /// Toml manifest listing the canonical contracts along with their standard json inputs.
+    pub manifest: PathBuf,
+}
+
+impl Default for BootstrapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            manifest: PathBuf::from("config/canonical_contracts.toml"),
+        }
+    }
+}
+
+/// Periodic checks of the implementations of verified EIP-1967 proxies.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProxyHistorySettings {
+    pub enabled: bool,
+    #[serde_as(as = "DisplayFromStr")]
+    pub schedule: Schedule,
+}
+
+impl Default for ProxyHistorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            schedule: Schedule::from_str("0 */10 * * * * *").unwrap(), // every 10 minutes
+        }
+    }
+}
+
+/// Verification by similarity of contracts created in new blocks of the chain.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChainWatcherSettings {
+    pub enabled: bool,
+    /// Websocket rpc endpoint new blocks are subscribed to (via `eth_subscribe`).
+    pub ws_url: Url,
+    /// Max number of blocks missed while disconnected which are processed after reconnection.
+    pub max_catch_up_blocks: u64,
+    pub reconnect_delay_secs: u64,
+}
+
+impl Default for ChainWatcherSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ws_url: Url::try_from("ws://127.0.0.1:8546").expect("valid url"),
+            max_catch_up_blocks: 100,
+            reconnect_delay_secs: 5,
+        }
+    }
+}
+
+/// Sources submitted before the contracts are deployed, which are verified as jobs once
+/// the chain watcher observes contracts created with the expected init code.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IntentsSettings {
+    pub enabled: bool,
+    /// Time (in seconds) after which intents which have not been matched by deployments expire.
+    pub ttl_secs: NonZeroU64,
+}
+
+impl Default for IntentsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: NonZeroU64::new(7 * 24 * 60 * 60).expect("Is not zero"), // a week
+        }
+    }
+}
+
+/// Screening of submitted sources for hidden and lookalike characters (the "Trojan Source"
+/// class). Found characters are reported as `source_warnings` of verification results.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourceScreeningSettings {
+    /// If set, sources containing such characters are rejected before they are compiled.
+    pub reject: bool,
+}
+
 impl Settings {
     pub fn new() -> anyhow::Result<Self> {
         let config_path = std::env::var("SMART_CONTRACT_VERIFIER__CONFIG");
@@ -257,12 +1219,100 @@ impl Settings {
     }
 
     fn validate(&self) -> anyhow::Result<()> {
+        for origin in &self.server.cors.allowed_origins {
+            let valid = origin == "*"
+                || Url::parse(origin)
+                    .map_or(false, |url| ["http", "https"].contains(&url.scheme()));
+            if !valid {
+                return Err(anyhow!("invalid cors origin: {origin}"));
+            }
+        }
+
+        let huff_solc_js = self
+            .huff
+            .local_compilers
+            .values()
+            .any(|compiler| compiler.executor != CompilerExecutor::Native);
+        if huff_solc_js {
+            return Err(anyhow!("huff local compilers should be native binaries"));
+        }
+
+        if self.analysis.slither.enabled && self.analysis.slither.sandbox.is_empty() {
+            return Err(anyhow!(
+                "slither requires a sandbox command to be run by (analysis.slither.sandbox)"
+            ));
+        }
+
+        if self.comparison.matchers.is_empty() {
+            return Err(anyhow!(
+                "at least one bytecode matcher should be configured"
+            ));
+        }
+
+        let mut tenant_keys = BTreeSet::new();
+        for (name, tenant) in &self.tenants.registry {
+            let valid = !name.is_empty()
+                && name.len() <= 32
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid {
+                return Err(anyhow!("invalid tenant name: {name}"));
+            }
+            for key in &tenant.api_keys {
+                if self
+                    .admin
+                    .api_keys
+                    .values()
+                    .any(|admin_key| admin_key == key)
+                    || !tenant_keys.insert(key)
+                {
+                    return Err(anyhow!("api keys of tenant {name} are not unique"));
+                }
+            }
+        }
+
+        for (name, code_hashes) in &self.impersonation.canonical {
+            let valid = code_hashes.iter().all(|code_hash| {
+                code_hash.len() == 66
+                    && code_hash.starts_with("0x")
+                    && code_hash[2..].chars().all(|c| c.is_ascii_hexdigit())
+            });
+            if !valid {
+                return Err(anyhow!(
+                    "invalid code hashes of canonical {name} deployments"
+                ));
+            }
+        }
+
+        if self.attestation.enabled && self.attestation.private_key.is_none() {
+            return Err(anyhow!("attestation requires `private_key` to be set"));
+        }
+
+        if !self.chains.registry.contains_key(&self.chains.current) {
+            return Err(anyhow!(
+                "current chain {} is missing in the chains registry",
+                self.chains.current
+            ));
+        }
+
         // Validate s3 fetcher
         if let FetcherSettings::S3(settings) = &self.solidity.fetcher {
             if settings.region.is_none() && settings.endpoint.is_none() {
                 return Err(anyhow!("for s3 fetcher settings at least one of `region` or `endpoint` should be defined"));
             }
         };
+        for storage in [
+            &self.backup.storage,
+            &self.blobs.storage,
+            &self.uploads.storage,
+        ] {
+            if let ObjectStorageSettings::S3(settings) = storage {
+                if settings.region.is_none() && settings.endpoint.is_none() {
+                    return Err(anyhow!("for s3 object storage at least one of `region` or `endpoint` should be defined"));
+                }
+            };
+        }
 
         Ok(())
     }
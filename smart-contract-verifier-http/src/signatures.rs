@@ -0,0 +1,168 @@
+use crate::{verification_response::VerificationResult, DB};
+use serde::{Deserialize, Serialize};
+
+/// Function signature observed in the ABI of some verified contract.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FunctionSignature {
+    /// Lowercase hex encoded selector with "0x" prefix (e.g., "0xa9059cbb").
+    pub selector: String,
+    /// Canonical signature (e.g., "transfer(address,uint256)").
+    pub signature: String,
+}
+
+/// Event signature observed in the ABI of some verified contract.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EventSignature {
+    /// Lowercase hex encoded `topic0` hash with "0x" prefix.
+    pub topic0: String,
+    /// Canonical signature (e.g., "Transfer(address,address,uint256)").
+    pub signature: String,
+}
+
+/// Extracts signatures of all functions declared in the ABI.
+pub fn function_signatures(contract: &ethabi::Contract) -> Vec<FunctionSignature> {
+    let mut signatures: Vec<_> = contract
+        .functions()
+        .map(|function| FunctionSignature {
+            selector: format!("0x{}", hex::encode(function.short_signature())),
+            signature: function_signature(function),
+        })
+        .collect();
+    signatures.sort();
+    signatures
+}
+
+/// Returns canonical signature of the function (e.g., "transfer(address,uint256)").
+pub fn function_signature(function: &ethabi::Function) -> String {
+    // `Function::signature` includes output types after a colon
+    let mut signature = function.signature();
+    if let Some(colon) = signature.find(':') {
+        signature.truncate(colon);
+    }
+    signature
+}
+
+/// Extracts signatures of all non-anonymous events declared in the ABI.
+/// Anonymous events do not emit `topic0`, so they could not be resolved anyway.
+pub fn event_signatures(contract: &ethabi::Contract) -> Vec<EventSignature> {
+    let mut signatures: Vec<_> = contract
+        .events()
+        .filter(|event| !event.anonymous)
+        .map(|event| EventSignature {
+            topic0: format!("0x{}", hex::encode(event.signature().as_bytes())),
+            signature: event_signature(event),
+        })
+        .collect();
+    signatures.sort();
+    signatures
+}
+
+/// Returns canonical signature of the event (e.g., "Transfer(address,address,uint256)").
+pub fn event_signature(event: &ethabi::Event) -> String {
+    let inputs: Vec<_> = event
+        .inputs
+        .iter()
+        .map(|input| input.kind.to_string())
+        .collect();
+    format!("{}({})", event.name, inputs.join(","))
+}
+
+/// Normalizes user provided selector into the stored representation.
+/// Returns `None` if the value is not a valid 4 bytes hex string.
+pub fn normalize_selector(selector: &str) -> Option<String> {
+    normalize_hex(selector, 4)
+}
+
+/// Normalizes user provided event topic into the stored representation.
+/// Returns `None` if the value is not a valid 32 bytes hex string.
+pub fn normalize_topic(topic: &str) -> Option<String> {
+    normalize_hex(topic, 32)
+}
+
+fn normalize_hex(value: &str, bytes: usize) -> Option<String> {
+    let value = value.trim_start_matches("0x").to_lowercase();
+    (value.len() == bytes * 2 && hex::decode(&value).is_ok()).then(|| format!("0x{value}"))
+}
+
+/// Stores function and event signatures declared in the ABI of the verified contract.
+///
+/// Errors are only logged, as they should not affect the verification response.
+pub async fn record(database: &DB, result: &VerificationResult) {
+    let abi = match &result.abi {
+        Some(abi) => abi,
+        None => return,
+    };
+    let contract: ethabi::Contract = match serde_json::from_str(abi) {
+        Ok(contract) => contract,
+        Err(err) => {
+            tracing::warn!("cannot parse abi of verified contract: {}", err);
+            return;
+        }
+    };
+    if let Err(err) = database
+        .add_function_signatures(function_signatures(&contract))
+        .await
+    {
+        tracing::error!("{}", err);
+    }
+    if let Err(err) = database
+        .add_event_signatures(event_signatures(&contract))
+        .await
+    {
+        tracing::error!("{}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const ABI: &str = r#"[
+        {"type":"function","name":"transfer","inputs":[{"name":"to","type":"address"},{"name":"amount","type":"uint256"}],"outputs":[{"name":"","type":"bool"}],"stateMutability":"nonpayable"},
+        {"type":"function","name":"balanceOf","inputs":[{"name":"owner","type":"address"}],"outputs":[{"name":"","type":"uint256"}],"stateMutability":"view"},
+        {"type":"event","name":"Transfer","inputs":[{"name":"from","type":"address","indexed":true},{"name":"to","type":"address","indexed":true},{"name":"value","type":"uint256","indexed":false}],"anonymous":false}
+    ]"#;
+
+    #[test]
+    fn extracts_function_signatures() {
+        assert_eq!(
+            vec![
+                FunctionSignature {
+                    selector: "0x70a08231".into(),
+                    signature: "balanceOf(address)".into(),
+                },
+                FunctionSignature {
+                    selector: "0xa9059cbb".into(),
+                    signature: "transfer(address,uint256)".into(),
+                },
+            ],
+            function_signatures(&serde_json::from_str(ABI).expect("abi is valid"))
+        );
+    }
+
+    #[test]
+    fn extracts_event_signatures() {
+        assert_eq!(
+            vec![EventSignature {
+                topic0: "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+                    .into(),
+                signature: "Transfer(address,address,uint256)".into(),
+            }],
+            event_signatures(&serde_json::from_str(ABI).expect("abi is valid"))
+        );
+    }
+
+    #[test]
+    fn normalizes_selectors() {
+        assert_eq!(Some("0xa9059cbb".into()), normalize_selector("0xA9059CBB"));
+        assert_eq!(Some("0xa9059cbb".into()), normalize_selector("a9059cbb"));
+        assert_eq!(None, normalize_selector("0xa9059c"));
+        assert_eq!(None, normalize_selector("0xzz059cbb"));
+        assert_eq!(
+            Some(format!("0x{}", "ab".repeat(32))),
+            normalize_topic(&"AB".repeat(32))
+        );
+        assert_eq!(None, normalize_topic("0xa9059cbb"));
+    }
+}
@@ -0,0 +1,345 @@
+use crate::{
+    analysis::{AnalyzedContract, Analyzer, Finding, FindingSeverity},
+    archive::safe_path,
+    settings::SlitherSettings,
+};
+use actix_web::web;
+use serde::Deserialize;
+use smart_contract_verifier::{SolidityClient, Version};
+use std::{ffi::OsStr, path::Path, process::Stdio, str::FromStr, sync::Arc, time::Duration};
+use tokio::{process::Command, sync::Semaphore};
+
+/// Runs Slither (https://github.com/crytic/slither) over the sources of verified contracts,
+/// compiled with the same compiler version the contracts have been verified with.
+///
+/// Sources are written into a temporary directory Slither is run in, with a clean environment,
+/// and is killed if it does not complete in time. Further isolation (e.g., no network access,
+/// only the system directories, the sources and the compiler mounted) is provided by the sandbox
+/// command Slither is run by, which is required.
+pub struct Slither {
+    settings: SlitherSettings,
+    client: web::Data<SolidityClient>,
+    /// Limits the number of Slither processes run at once.
+    permits: Arc<Semaphore>,
+}
+
+impl Slither {
+    pub fn new(settings: SlitherSettings, client: web::Data<SolidityClient>) -> Self {
+        let permits = Arc::new(Semaphore::new(settings.max_concurrency.get()));
+        Self {
+            settings,
+            client,
+            permits,
+        }
+    }
+}
+
+/// Placeholders of the sandbox arguments replaced with the directory of the sources
+/// and the path to the compiler, so that the sandbox could mount only them.
+const SOURCES_PLACEHOLDER: &str = "{sources}";
+const SOLC_PLACEHOLDER: &str = "{solc}";
+
+/// Command running Slither by the sandbox. Slither is never run without one,
+/// as it compiles and processes sources submitted by anyone.
+fn command(settings: &SlitherSettings, sources: &Path, solc: &Path) -> Result<Command, String> {
+    let (sandbox, args) = settings
+        .sandbox
+        .split_first()
+        .ok_or("slither is not run without a sandbox")?;
+    let args = args.iter().map(|arg| match arg.as_str() {
+        SOURCES_PLACEHOLDER => sources.as_os_str(),
+        SOLC_PLACEHOLDER => solc.as_os_str(),
+        arg => OsStr::new(arg),
+    });
+    let mut command = Command::new(sandbox);
+    command
+        .args(args)
+        .arg(&settings.path)
+        .env_clear()
+        .envs(std::env::var_os("PATH").map(|path| ("PATH", path)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+    Ok(command)
+}
+
+#[async_trait::async_trait]
+impl Analyzer for Slither {
+    fn name(&self) -> &'static str {
+        "slither"
+    }
+
+    async fn analyze(&self, contract: &AnalyzedContract<'_>) -> Result<Vec<Finding>, String> {
+        let result = &contract.verified.result;
+        let version = Version::from_str(&result.compiler_version).map_err(|err| err.to_string())?;
+        // Is leased until the analysis finishes, so that it is not evicted meanwhile
+        let solc = self
+            .client
+            .compilers()
+            .lease(&version)
+            .await
+            .map_err(|err| format!("cannot fetch the compiler: {err}"))?;
+
+        let dir = tempfile::tempdir().map_err(|err| err.to_string())?;
+        for (name, content) in &result.sources {
+            let path = safe_path(name).ok_or_else(|| format!("unsafe source path: {name}"))?;
+            let path = dir.path().join(path);
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|err| err.to_string())?;
+            }
+            tokio::fs::write(&path, content)
+                .await
+                .map_err(|err| err.to_string())?;
+        }
+
+        let target = safe_path(&result.file_name)
+            .ok_or_else(|| format!("unsafe source path: {}", result.file_name))?;
+        let mut command = command(&self.settings, dir.path(), &solc.path)?;
+        command
+            .current_dir(dir.path())
+            .env("HOME", dir.path())
+            // Relative to the current directory, so that file names are never taken for options
+            .arg(Path::new(".").join(target))
+            .arg("--solc")
+            .arg(&solc.path)
+            .args(["--json", "-"]);
+        let remappings = remappings(&result.compiler_settings);
+        if !remappings.is_empty() {
+            command.arg("--solc-remaps").arg(remappings.join(" "));
+        }
+        let solc_args = solc_args(&result.compiler_settings);
+        if !solc_args.is_empty() {
+            command.arg("--solc-args").arg(solc_args.join(" "));
+        }
+
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .map_err(|err| err.to_string())?;
+        let timeout = Duration::from_secs(self.settings.timeout_secs);
+        // Slither exits with non-zero codes if anything is found, so only its report is checked
+        let output = tokio::time::timeout(timeout, command.output())
+            .await
+            .map_err(|_| format!("slither has not completed in {} seconds", timeout.as_secs()))?
+            .map_err(|err| format!("cannot run slither: {err}"))?;
+        parse_report(&output.stdout)
+    }
+}
+
+/// Remappings the contract has been compiled with, taken from the stored compiler settings.
+fn remappings(compiler_settings: &str) -> Vec<String> {
+    serde_json::from_str::<serde_json::Value>(compiler_settings)
+        .ok()
+        .and_then(|settings| settings.get("remappings").cloned())
+        .and_then(|remappings| serde_json::from_value(remappings).ok())
+        .unwrap_or_default()
+}
+
+/// Compiler options matching the optimizer, `viaIR` and `evmVersion` settings the contract
+/// has been compiled with, so that Slither analyzes the same code.
+fn solc_args(compiler_settings: &str) -> Vec<String> {
+    let settings: serde_json::Value = match serde_json::from_str(compiler_settings) {
+        Ok(settings) => settings,
+        Err(_) => return vec![],
+    };
+    let mut args = vec![];
+    let optimizer = &settings["optimizer"];
+    if optimizer["enabled"].as_bool() == Some(true) {
+        args.push("--optimize".to_string());
+        if let Some(runs) = optimizer["runs"].as_u64() {
+            args.extend(["--optimize-runs".to_string(), runs.to_string()]);
+        }
+    }
+    if settings["viaIR"].as_bool() == Some(true) {
+        args.push("--via-ir".to_string());
+    }
+    if let Some(evm_version) = settings["evmVersion"].as_str() {
+        // Arguments are split on whitespaces by Slither, so only plain names are passed
+        if evm_version.chars().all(|c| c.is_ascii_alphanumeric()) {
+            args.extend(["--evm-version".to_string(), evm_version.to_string()]);
+        }
+    }
+    args
+}
+
+#[derive(Debug, Deserialize)]
+struct Report {
+    success: bool,
+    error: Option<String>,
+    #[serde(default)]
+    results: Results,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Results {
+    #[serde(default)]
+    detectors: Vec<Detection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Detection {
+    check: String,
+    impact: String,
+    description: String,
+    #[serde(default)]
+    elements: Vec<Element>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Element {
+    source_mapping: Option<SourceMapping>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceMapping {
+    filename_relative: Option<String>,
+    #[serde(default)]
+    lines: Vec<usize>,
+}
+
+fn parse_report(output: &[u8]) -> Result<Vec<Finding>, String> {
+    let report: Report =
+        serde_json::from_slice(output).map_err(|err| format!("invalid slither report: {err}"))?;
+    if !report.success {
+        let error = report.error.unwrap_or_default();
+        return Err(format!("slither has failed: {error}"));
+    }
+    let findings = report
+        .results
+        .detectors
+        .into_iter()
+        .map(|detection| {
+            let location = detection
+                .elements
+                .into_iter()
+                .find_map(|element| element.source_mapping);
+            let (file_name, line) = match location {
+                Some(location) => (location.filename_relative, location.lines.first().copied()),
+                None => (None, None),
+            };
+            Finding {
+                analyzer: String::new(),
+                check: detection.check,
+                severity: match detection.impact.as_str() {
+                    "High" => FindingSeverity::High,
+                    "Medium" => FindingSeverity::Medium,
+                    "Low" => FindingSeverity::Low,
+                    // "Informational" and "Optimization"
+                    _ => FindingSeverity::Info,
+                },
+                message: detection.description.trim().to_string(),
+                src: None,
+                file_name,
+                line,
+            }
+        })
+        .collect();
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn slither_is_run_by_sandbox() {
+        let (sources, solc) = (Path::new("/tmp/.tmpAbCd"), Path::new("/compilers/solc"));
+        let sandboxed = command(&SlitherSettings::default(), sources, solc).unwrap();
+        let sandboxed = sandboxed.as_std();
+        assert_eq!(OsStr::new("bwrap"), sandboxed.get_program());
+        let args: Vec<_> = sandboxed.get_args().collect();
+        assert!(args.contains(&OsStr::new("--unshare-all")));
+        // Only the sources and the compiler are mounted besides the system directories
+        let mounted = |path: &Path| {
+            args.windows(2)
+                .any(|pair| pair == [path.as_os_str(), path.as_os_str()])
+        };
+        assert!(mounted(sources));
+        assert!(mounted(solc));
+        assert!(!args.contains(&OsStr::new(SOURCES_PLACEHOLDER)));
+        assert_eq!(Some(&OsStr::new("slither")), args.last());
+
+        let settings = SlitherSettings {
+            sandbox: vec![],
+            ..Default::default()
+        };
+        command(&settings, sources, solc).expect_err("slither should not be run without a sandbox");
+    }
+
+    #[test]
+    fn compiler_settings_are_passed() {
+        let settings = json!({
+            "optimizer": { "enabled": true, "runs": 200 },
+            "viaIR": true,
+            "evmVersion": "london",
+            "remappings": ["@oz/=lib/openzeppelin/"],
+        });
+        assert_eq!(
+            vec![
+                "--optimize",
+                "--optimize-runs",
+                "200",
+                "--via-ir",
+                "--evm-version",
+                "london"
+            ],
+            solc_args(&settings.to_string())
+        );
+        assert_eq!(
+            vec!["@oz/=lib/openzeppelin/".to_string()],
+            remappings(&settings.to_string())
+        );
+
+        let settings = json!({ "optimizer": { "enabled": false, "runs": 200 } });
+        assert!(solc_args(&settings.to_string()).is_empty());
+        assert!(solc_args("not a json").is_empty());
+    }
+
+    #[test]
+    fn report_is_parsed() {
+        let report = json!({
+            "success": true,
+            "error": null,
+            "results": {
+                "detectors": [{
+                    "check": "reentrancy-eth",
+                    "impact": "High",
+                    "confidence": "Medium",
+                    "description": "Reentrancy in Vault.withdraw() (contracts/Vault.sol#10-15)\n",
+                    "elements": [{
+                        "type": "function",
+                        "name": "withdraw",
+                        "source_mapping": {
+                            "start": 120,
+                            "length": 200,
+                            "filename_relative": "contracts/Vault.sol",
+                            "lines": [10, 11, 12, 13, 14, 15],
+                        },
+                    }],
+                }],
+            },
+        });
+        let findings = parse_report(report.to_string().as_bytes()).unwrap();
+        assert_eq!(
+            vec![Finding {
+                analyzer: String::new(),
+                check: "reentrancy-eth".into(),
+                severity: FindingSeverity::High,
+                message: "Reentrancy in Vault.withdraw() (contracts/Vault.sol#10-15)".into(),
+                src: None,
+                file_name: Some("contracts/Vault.sol".into()),
+                line: Some(10),
+            }],
+            findings
+        );
+
+        let failed = json!({ "success": false, "error": "Invalid compilation", "results": {} });
+        assert!(parse_report(failed.to_string().as_bytes()).is_err());
+    }
+}
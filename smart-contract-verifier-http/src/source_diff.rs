@@ -0,0 +1,223 @@
+use serde::Serialize;
+use smart_contract_verifier::tools::lcs;
+use std::collections::BTreeMap;
+
+/// Number of unchanged lines shown around changed ones.
+const CONTEXT_LINES: usize = 3;
+/// Limits the memory used to align the changed parts of the files. Parts which are too large
+/// are not aligned, and are shown as removed and added entirely.
+const MAX_ALIGNMENT_CELLS: usize = 4_000_000;
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Changes of a single source file between two verified contracts.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct FileDiff {
+    pub path: String,
+    pub status: FileStatus,
+    /// Unified diff of the file contents.
+    pub diff: String,
+}
+
+/// Aligns the files by their paths and returns the diffs of the files which differ.
+/// Contents which differ only in line endings (CRLF or LF, or the newline at the end
+/// of the file) are considered the same, as the diffs are made of lines.
+pub fn diff_sources(
+    from: &BTreeMap<String, String>,
+    to: &BTreeMap<String, String>,
+) -> Vec<FileDiff> {
+    let mut paths: Vec<_> = from.keys().chain(to.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let (status, from_content, to_content) = match (from.get(path), to.get(path)) {
+                (Some(from), Some(to)) if from.lines().eq(to.lines()) => return None,
+                (Some(from), Some(to)) => (FileStatus::Modified, from.as_str(), to.as_str()),
+                (Some(from), None) => (FileStatus::Removed, from.as_str(), ""),
+                (None, Some(to)) => (FileStatus::Added, "", to.as_str()),
+                (None, None) => return None,
+            };
+            let (from_name, to_name) = match status {
+                FileStatus::Added => ("/dev/null".to_string(), format!("b/{path}")),
+                FileStatus::Removed => (format!("a/{path}"), "/dev/null".to_string()),
+                FileStatus::Modified => (format!("a/{path}"), format!("b/{path}")),
+            };
+            let mut diff = format!("--- {from_name}\n+++ {to_name}\n");
+            diff.push_str(&unified_diff(from_content, to_content));
+            Some(FileDiff {
+                path: path.clone(),
+                status,
+                diff,
+            })
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Line<'a> {
+    Equal(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Returns the hunks of the unified diff (without the file headers).
+fn unified_diff(from: &str, to: &str) -> String {
+    let lines = diff_lines(from, to);
+    let changed: Vec<_> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Line::Equal(_)))
+        .map(|(index, _)| index)
+        .collect();
+
+    // Ranges of the diff lines shown in the hunks; close changes share a hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for index in changed {
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut output = String::new();
+    for (start, end) in hunks {
+        let count = |lines: &[Line], removed: bool| {
+            lines
+                .iter()
+                .filter(|line| match line {
+                    Line::Equal(_) => true,
+                    Line::Removed(_) => removed,
+                    Line::Added(_) => !removed,
+                })
+                .count()
+        };
+        let (from_before, to_before) =
+            (count(&lines[..start], true), count(&lines[..start], false));
+        let (from_count, to_count) = (
+            count(&lines[start..end], true),
+            count(&lines[start..end], false),
+        );
+        // Empty ranges start at the line preceding them
+        let position = |before: usize, count: usize| before + usize::from(count > 0);
+        output.push_str(&format!(
+            "@@ -{},{from_count} +{},{to_count} @@\n",
+            position(from_before, from_count),
+            position(to_before, to_count),
+        ));
+        for line in &lines[start..end] {
+            let (prefix, text) = match line {
+                Line::Equal(text) => (' ', text),
+                Line::Removed(text) => ('-', text),
+                Line::Added(text) => ('+', text),
+            };
+            output.push(prefix);
+            output.push_str(text);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// Aligns the lines of the files by their longest common subsequence.
+fn diff_lines<'a>(from: &'a str, to: &'a str) -> Vec<Line<'a>> {
+    let from: Vec<_> = from.lines().collect();
+    let to: Vec<_> = to.lines().collect();
+    let pairs = lcs::align(&from, &to, MAX_ALIGNMENT_CELLS, |from, to| from == to);
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    // The end of both files is aligned as well, so that the lines after the last match are added
+    for (from_index, to_index) in pairs.into_iter().chain([(from.len(), to.len())]) {
+        lines.extend(from[i..from_index].iter().map(|line| Line::Removed(line)));
+        lines.extend(to[j..to_index].iter().map(|line| Line::Added(line)));
+        if let Some(line) = from.get(from_index) {
+            lines.push(Line::Equal(line));
+        }
+        i = from_index + 1;
+        j = to_index + 1;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn sources(sources: &[(&str, &str)]) -> BTreeMap<String, String> {
+        sources
+            .iter()
+            .map(|(path, content)| (path.to_string(), content.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn sources_are_diffed() {
+        let from = sources(&[
+            ("Lib.sol", "library Lib {}\n"),
+            ("Old.sol", "contract Old {}\n"),
+            (
+                "Token.sol",
+                "contract Token {\n  uint a;\n  uint b;\n  uint c;\n  uint d;\n  uint e;\n  function f() {}\n}\n",
+            ),
+        ]);
+        let to = sources(&[
+            ("Lib.sol", "library Lib {}\n"),
+            ("New.sol", "contract New {}\n"),
+            (
+                "Token.sol",
+                "contract Token {\n  uint a;\n  uint b;\n  uint c;\n  uint d;\n  uint e;\n  function g() {}\n}\n",
+            ),
+        ]);
+
+        let expected = vec![
+            FileDiff {
+                path: "New.sol".into(),
+                status: FileStatus::Added,
+                diff: "--- /dev/null\n+++ b/New.sol\n@@ -0,0 +1,1 @@\n+contract New {}\n".into(),
+            },
+            FileDiff {
+                path: "Old.sol".into(),
+                status: FileStatus::Removed,
+                diff: "--- a/Old.sol\n+++ /dev/null\n@@ -1,1 +0,0 @@\n-contract Old {}\n".into(),
+            },
+            FileDiff {
+                path: "Token.sol".into(),
+                status: FileStatus::Modified,
+                diff: "--- a/Token.sol\n+++ b/Token.sol\n@@ -4,5 +4,5 @@\n   uint c;\n   uint d;\n   uint e;\n-  function f() {}\n+  function g() {}\n }\n".into(),
+            },
+        ];
+        assert_eq!(expected, diff_sources(&from, &to));
+    }
+
+    #[test]
+    fn line_endings_are_not_compared() {
+        let from = sources(&[("A.sol", "contract A {\r\n}\r\n")]);
+        let to = sources(&[("A.sol", "contract A {\n}")]);
+        assert_eq!(Vec::<FileDiff>::new(), diff_sources(&from, &to));
+    }
+
+    #[test]
+    fn lines_after_the_last_match_are_diffed() {
+        let diff = diff_lines("a\nb\nc\n", "b\nd\n");
+        assert_eq!(
+            vec![
+                Line::Removed("a"),
+                Line::Equal("b"),
+                Line::Removed("c"),
+                Line::Added("d"),
+            ],
+            diff
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{
+    proxy_history::{BEACON_SLOT, IMPLEMENTATION_SLOT},
+    signatures::{event_signatures, function_signatures},
+};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::tools::disassembler;
+use std::collections::BTreeSet;
+
+/// Well-known interfaces a verified contract may implement.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Standard {
+    Erc20,
+    Erc721,
+    Erc1155,
+    /// Permit extension of ERC-20.
+    Erc2612,
+    /// Proxy storing its implementation in EIP-1967 slot.
+    Eip1967Proxy,
+    /// Proxy storing its beacon in EIP-1967 slot.
+    Eip1967BeaconProxy,
+    /// Implementation upgradable via UUPS (EIP-1822) proxy.
+    Eip1822Proxiable,
+}
+
+const ERC20_FUNCTIONS: &[&str] = &[
+    "totalSupply()",
+    "balanceOf(address)",
+    "transfer(address,uint256)",
+    "transferFrom(address,address,uint256)",
+    "approve(address,uint256)",
+    "allowance(address,address)",
+];
+const ERC721_FUNCTIONS: &[&str] = &[
+    "balanceOf(address)",
+    "ownerOf(uint256)",
+    "safeTransferFrom(address,address,uint256)",
+    "safeTransferFrom(address,address,uint256,bytes)",
+    "transferFrom(address,address,uint256)",
+    "approve(address,uint256)",
+    "setApprovalForAll(address,bool)",
+    "getApproved(uint256)",
+    "isApprovedForAll(address,address)",
+];
+const ERC1155_FUNCTIONS: &[&str] = &[
+    "balanceOf(address,uint256)",
+    "balanceOfBatch(address[],uint256[])",
+    "setApprovalForAll(address,bool)",
+    "isApprovedForAll(address,address)",
+    "safeTransferFrom(address,address,uint256,uint256,bytes)",
+    "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+];
+const ERC2612_FUNCTIONS: &[&str] = &[
+    "permit(address,address,uint256,uint256,uint8,bytes32,bytes32)",
+    "nonces(address)",
+    "DOMAIN_SEPARATOR()",
+];
+const EIP1822_FUNCTIONS: &[&str] = &["proxiableUUID()"];
+const EIP1967_PROXY_EVENTS: &[&str] = &["Upgraded(address)"];
+const EIP1967_BEACON_PROXY_EVENTS: &[&str] = &["BeaconUpgraded(address)"];
+
+const PUSH32: u8 = 0x7f;
+const DELEGATECALL: u8 = 0xf4;
+
+/// Detects standards the contract implements judging by its ABI and deployed bytecode (if known).
+///
+/// Implementations upgradable via UUPS proxies emit the same `Upgraded` event as EIP-1967 proxies,
+/// so proxies are required to have a fallback function as well, and their bytecode (if known)
+/// to read the EIP-1967 slot and to delegate calls.
+pub fn detect(contract: &ethabi::Contract, deployed_bytecode: Option<&[u8]>) -> Vec<Standard> {
+    let functions: BTreeSet<_> = function_signatures(contract)
+        .into_iter()
+        .map(|function| function.signature)
+        .collect();
+    let events: BTreeSet<_> = event_signatures(contract)
+        .into_iter()
+        .map(|event| event.signature)
+        .collect();
+    let implements = |signatures: &[&str], declared: &BTreeSet<String>| {
+        signatures
+            .iter()
+            .all(|signature| declared.contains(*signature))
+    };
+
+    let mut standards = Vec::new();
+    // ERC-721 shares most of the function signatures with ERC-20,
+    // but lacks `totalSupply` and `allowance`, so the two are not confused.
+    if implements(ERC20_FUNCTIONS, &functions) {
+        standards.push(Standard::Erc20);
+    }
+    if implements(ERC721_FUNCTIONS, &functions) {
+        standards.push(Standard::Erc721);
+    }
+    if implements(ERC1155_FUNCTIONS, &functions) {
+        standards.push(Standard::Erc1155);
+    }
+    if implements(ERC20_FUNCTIONS, &functions) && implements(ERC2612_FUNCTIONS, &functions) {
+        standards.push(Standard::Erc2612);
+    }
+    let is_proxy = |slot: &str| {
+        contract.fallback && deployed_bytecode.map_or(true, |code| delegates_to_slot(code, slot))
+    };
+    if implements(EIP1967_PROXY_EVENTS, &events) && is_proxy(IMPLEMENTATION_SLOT) {
+        standards.push(Standard::Eip1967Proxy);
+    }
+    if implements(EIP1967_BEACON_PROXY_EVENTS, &events) && is_proxy(BEACON_SLOT) {
+        standards.push(Standard::Eip1967BeaconProxy);
+    }
+    if implements(EIP1822_FUNCTIONS, &functions) {
+        standards.push(Standard::Eip1822Proxiable);
+    }
+    standards
+}
+
+/// Same as [`detect`], but accepts json encoded ABI (the bytecode is not known).
+/// Returns no standards if the ABI could not be parsed.
+pub fn detect_from_json(abi: &str) -> Vec<Standard> {
+    serde_json::from_str(abi)
+        .map(|contract| detect(&contract, None))
+        .unwrap_or_default()
+}
+
+/// Whether the bytecode pushes the slot (to load the implementation or beacon from it)
+/// and delegates calls.
+fn delegates_to_slot(deployed_bytecode: &[u8], slot: &str) -> bool {
+    let slot = hex::decode(slot.trim_start_matches("0x")).expect("Is valid slot");
+    let instructions = disassembler::disassemble(deployed_bytecode);
+    instructions
+        .iter()
+        .any(|instruction| instruction.opcode == PUSH32 && instruction.push_data == slot)
+        && instructions
+            .iter()
+            .any(|instruction| instruction.opcode == DELEGATECALL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn function(signature: &str) -> serde_json::Value {
+        let (name, inputs) = signature.trim_end_matches(')').split_once('(').unwrap();
+        let inputs: Vec<_> = inputs
+            .split(',')
+            .filter(|input| !input.is_empty())
+            .map(|input| serde_json::json!({ "name": "", "type": input }))
+            .collect();
+        serde_json::json!({
+            "type": "function",
+            "name": name,
+            "inputs": inputs,
+            "outputs": [],
+            "stateMutability": "nonpayable",
+        })
+    }
+
+    fn abi(functions: &[&[&str]]) -> String {
+        let functions: Vec<_> = functions
+            .iter()
+            .flat_map(|functions| functions.iter())
+            .map(|signature| function(signature))
+            .collect();
+        serde_json::to_string(&functions).unwrap()
+    }
+
+    #[test]
+    fn detects_erc20_with_permit() {
+        assert_eq!(
+            vec![Standard::Erc20, Standard::Erc2612],
+            detect_from_json(&abi(&[ERC20_FUNCTIONS, ERC2612_FUNCTIONS]))
+        );
+    }
+
+    #[test]
+    fn erc721_is_not_erc20() {
+        assert_eq!(vec![Standard::Erc721], detect_from_json(&abi(&[ERC721_FUNCTIONS])));
+    }
+
+    #[test]
+    fn detects_erc1155_and_uups() {
+        assert_eq!(
+            vec![Standard::Erc1155, Standard::Eip1822Proxiable],
+            detect_from_json(&abi(&[ERC1155_FUNCTIONS, EIP1822_FUNCTIONS]))
+        );
+    }
+
+    const UPGRADED_EVENT: &str = r#"{"type":"event","name":"Upgraded","inputs":[{"name":"implementation","type":"address","indexed":true}],"anonymous":false}"#;
+    const FALLBACK: &str = r#"{"type":"fallback","stateMutability":"payable"}"#;
+
+    #[test]
+    fn detects_proxies() {
+        let abi = format!("[{UPGRADED_EVENT},{FALLBACK}]");
+        assert_eq!(vec![Standard::Eip1967Proxy], detect_from_json(&abi));
+
+        let contract: ethabi::Contract = serde_json::from_str(&abi).unwrap();
+        // PUSH32 <slot> SLOAD ... DELEGATECALL
+        let proxy = hex::decode(format!(
+            "7f{}54f4",
+            IMPLEMENTATION_SLOT.trim_start_matches("0x")
+        ))
+        .unwrap();
+        assert_eq!(vec![Standard::Eip1967Proxy], detect(&contract, Some(&proxy)));
+        // Does not delegate calls
+        let proxy_like = hex::decode(format!(
+            "7f{}5400",
+            IMPLEMENTATION_SLOT.trim_start_matches("0x")
+        ))
+        .unwrap();
+        assert_eq!(Vec::<Standard>::new(), detect(&contract, Some(&proxy_like)));
+    }
+
+    #[test]
+    fn uups_implementation_is_not_proxy() {
+        let abi = format!("[{UPGRADED_EVENT},{}]", function("proxiableUUID()"));
+        assert_eq!(vec![Standard::Eip1822Proxiable], detect_from_json(&abi));
+    }
+
+    #[test]
+    fn invalid_abi() {
+        assert_eq!(Vec::<Standard>::new(), detect_from_json("not an abi"));
+    }
+}
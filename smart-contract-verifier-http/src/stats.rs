@@ -0,0 +1,421 @@
+use crate::DB;
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use mongodb::bson::{doc, Bson, Document};
+use serde::Serialize;
+use smart_contract_verifier::Version;
+use std::{collections::BTreeMap, str::FromStr};
+
+/// Number of compiler versions returned in the statistics.
+const TOP_COMPILER_VERSIONS: i64 = 10;
+
+/// Language of the attempts recorded before the language has been stored with them.
+pub const DEFAULT_LANGUAGE: &str = "solidity";
+
+/// Verification attempts grouped by some key, as aggregated by the database.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AttemptGroup {
+    /// `None` if all attempts are in the same group.
+    pub key: Option<String>,
+    pub total: u64,
+    pub successful: u64,
+    /// Total duration of the attempts.
+    pub duration_ms: u64,
+}
+
+pub(crate) fn number(value: Option<&Bson>) -> Option<u64> {
+    match value {
+        Some(Bson::Int32(value)) => Some(*value as u64),
+        Some(Bson::Int64(value)) => Some(*value as u64),
+        Some(Bson::Double(value)) => Some(*value as u64),
+        _ => None,
+    }
+}
+
+impl AttemptGroup {
+    pub fn from_document(document: &Document) -> Self {
+        let number = |key: &str| number(document.get(key)).unwrap_or_default();
+        Self {
+            key: document.get_str("_id").ok().map(str::to_string),
+            total: number("total"),
+            successful: number("successful"),
+            duration_ms: number("duration_ms"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct AttemptCounts {
+    pub total: u64,
+    pub successful: u64,
+    /// Share of successful attempts; `null` if there have been no attempts.
+    pub success_rate: Option<f64>,
+    /// Average duration of the attempts, including compilation and bytecode fetching.
+    pub average_duration_ms: Option<u64>,
+}
+
+impl From<&AttemptGroup> for AttemptCounts {
+    fn from(group: &AttemptGroup) -> Self {
+        let average = |value: u64| (group.total > 0).then(|| value / group.total);
+        Self {
+            total: group.total,
+            successful: group.successful,
+            success_rate: (group.total > 0).then(|| group.successful as f64 / group.total as f64),
+            average_duration_ms: average(group.duration_ms),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DailyCounts {
+    /// In the `YYYY-MM-DD` format (UTC).
+    pub date: String,
+    #[serde(flatten)]
+    pub counts: AttemptCounts,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CompilerVersionUsage {
+    pub compiler_version: String,
+    /// Number of stored contracts verified with the version.
+    pub contracts: u64,
+}
+
+/// Aggregated data for operating dashboards.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Stats {
+    pub verified_contracts: u64,
+    pub attempts: AttemptCounts,
+    /// Attempts made in each of the last days, the earliest day goes first.
+    pub daily_attempts: Vec<DailyCounts>,
+    pub top_compiler_versions: Vec<CompilerVersionUsage>,
+    pub languages: BTreeMap<String, AttemptCounts>,
+}
+
+/// Computes the statistics from stored contracts and verification attempts.
+/// Daily attempts are returned for the given number of days, today included.
+pub async fn collect(database: &DB, days: u32) -> Result<Stats, String> {
+    let today = Utc::now().date_naive();
+    let since = today - Duration::days(days.saturating_sub(1) as i64);
+
+    let verified_contracts = database.count_verified_contracts().await?;
+    let totals = database
+        .group_verification_attempts(doc! {}, Bson::Null)
+        .await?;
+    let daily = database
+        .group_verification_attempts(
+            doc! { "attempted_at": { "$gte": since.to_string() } },
+            doc! { "$substrCP": ["$attempted_at", 0, 10] }.into(),
+        )
+        .await?;
+    let languages = database
+        .group_verification_attempts(
+            doc! {},
+            doc! { "$ifNull": ["$language", DEFAULT_LANGUAGE] }.into(),
+        )
+        .await?;
+    let top_compiler_versions = database
+        .compiler_version_usage(TOP_COMPILER_VERSIONS)
+        .await?
+        .into_iter()
+        .map(|(compiler_version, contracts)| CompilerVersionUsage {
+            compiler_version,
+            contracts,
+        })
+        .collect();
+
+    Ok(Stats {
+        verified_contracts,
+        attempts: totals.first().map(AttemptCounts::from).unwrap_or_default(),
+        daily_attempts: daily_counts(&daily, since, today),
+        top_compiler_versions,
+        languages: languages
+            .iter()
+            .filter_map(|group| Some((group.key.clone()?, group.into())))
+            .collect(),
+    })
+}
+
+/// Stored contracts grouped by their compiler settings and the month
+/// (`YYYY-MM`) they have been verified in, as aggregated by the database.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SettingsGroup {
+    pub compiler_version: String,
+    pub evm_version: String,
+    pub optimization: bool,
+    pub optimization_runs: Option<u64>,
+    /// `None` for contracts stored before verification times were introduced.
+    pub month: Option<String>,
+    pub contracts: u64,
+}
+
+impl SettingsGroup {
+    pub fn from_document(document: &Document) -> Self {
+        let empty = Document::new();
+        let key = document.get_document("_id").unwrap_or(&empty);
+        let string =
+            |key: &Document, field: &str| key.get_str(field).unwrap_or_default().to_string();
+        Self {
+            compiler_version: string(key, "compiler_version"),
+            evm_version: string(key, "evm_version"),
+            optimization: key.get_bool("optimization").unwrap_or_default(),
+            optimization_runs: number(key.get("optimization_runs")),
+            month: Some(string(key, "month")).filter(|month| !month.is_empty()),
+            contracts: number(document.get("contracts")).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    pub value: String,
+    pub contracts: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct OptimizerUsage {
+    pub enabled: u64,
+    pub disabled: u64,
+    /// Contracts compiled with the optimizer enabled by the number of runs.
+    pub runs: Vec<Usage>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MonthlyUsage {
+    /// In the `YYYY-MM` format (UTC).
+    pub month: String,
+    pub contracts: u64,
+    /// Contracts by compiler release series (e.g., "0.8").
+    pub compiler_series: BTreeMap<String, u64>,
+    pub evm_versions: BTreeMap<String, u64>,
+}
+
+/// Compiler versions and settings solidity contracts have been verified with.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CompilerStats {
+    pub contracts: u64,
+    /// The most used values go first.
+    pub compiler_versions: Vec<Usage>,
+    pub compiler_series: Vec<Usage>,
+    pub evm_versions: Vec<Usage>,
+    pub optimizer: OptimizerUsage,
+    /// Contracts verified in each of the last months, the earliest month goes first.
+    pub monthly: Vec<MonthlyUsage>,
+}
+
+/// Computes compiler usage of stored solidity contracts.
+/// Monthly usage is returned for the given number of months, the current one included.
+pub async fn collect_compilers(database: &DB, months: u32) -> Result<CompilerStats, String> {
+    let groups = database.group_compiler_settings().await?;
+    Ok(compiler_stats(&groups, Utc::now().date_naive(), months))
+}
+
+fn compiler_stats(groups: &[SettingsGroup], today: NaiveDate, months: u32) -> CompilerStats {
+    let mut versions = BTreeMap::new();
+    let mut series = BTreeMap::new();
+    let mut evm_versions = BTreeMap::new();
+    let mut runs = BTreeMap::new();
+    let mut optimizer = OptimizerUsage::default();
+    let mut monthly: BTreeMap<_, _> = last_months(today, months)
+        .into_iter()
+        .map(|month| {
+            let usage = MonthlyUsage {
+                month: month.clone(),
+                contracts: 0,
+                compiler_series: BTreeMap::new(),
+                evm_versions: BTreeMap::new(),
+            };
+            (month, usage)
+        })
+        .collect();
+
+    for group in groups {
+        let compiler_series = compiler_series(&group.compiler_version);
+        *versions.entry(group.compiler_version.clone()).or_default() += group.contracts;
+        *series.entry(compiler_series.clone()).or_default() += group.contracts;
+        *evm_versions.entry(group.evm_version.clone()).or_default() += group.contracts;
+        match group.optimization {
+            true => {
+                optimizer.enabled += group.contracts;
+                let group_runs = group.optimization_runs.map(|runs| runs.to_string());
+                *runs.entry(group_runs.unwrap_or_default()).or_default() += group.contracts;
+            }
+            false => optimizer.disabled += group.contracts,
+        }
+        if let Some(usage) = group
+            .month
+            .as_ref()
+            .and_then(|month| monthly.get_mut(month))
+        {
+            usage.contracts += group.contracts;
+            *usage.compiler_series.entry(compiler_series).or_default() += group.contracts;
+            *usage
+                .evm_versions
+                .entry(group.evm_version.clone())
+                .or_default() += group.contracts;
+        }
+    }
+    optimizer.runs = by_usage(runs);
+
+    CompilerStats {
+        contracts: groups.iter().map(|group| group.contracts).sum(),
+        compiler_versions: by_usage(versions),
+        compiler_series: by_usage(series),
+        evm_versions: by_usage(evm_versions),
+        optimizer,
+        monthly: monthly.into_values().collect(),
+    }
+}
+
+/// Returns `major.minor` of the version, or the version as is if it cannot be parsed.
+fn compiler_series(compiler_version: &str) -> String {
+    match Version::from_str(compiler_version) {
+        Ok(version) => format!("{}.{}", version.version().major, version.version().minor),
+        Err(_) => compiler_version.to_string(),
+    }
+}
+
+fn by_usage(counts: BTreeMap<String, u64>) -> Vec<Usage> {
+    let mut usage: Vec<_> = counts
+        .into_iter()
+        .map(|(value, contracts)| Usage { value, contracts })
+        .collect();
+    usage.sort_by(|a, b| b.contracts.cmp(&a.contracts));
+    usage
+}
+
+/// Returns `YYYY-MM` of the last months, the current one included, the earliest first.
+fn last_months(today: NaiveDate, months: u32) -> Vec<String> {
+    let current = today.year() * 12 + today.month0() as i32;
+    (0..months as i32)
+        .rev()
+        .map(|offset| current - offset)
+        .map(|month| {
+            format!(
+                "{:04}-{:02}",
+                month.div_euclid(12),
+                month.rem_euclid(12) + 1
+            )
+        })
+        .collect()
+}
+
+/// Returns counts for each day from `since` to `until` inclusive;
+/// days without attempts are filled with zeros.
+fn daily_counts(groups: &[AttemptGroup], since: NaiveDate, until: NaiveDate) -> Vec<DailyCounts> {
+    let by_date: BTreeMap<_, _> = groups
+        .iter()
+        .filter_map(|group| Some((group.key.as_deref()?, group)))
+        .collect();
+    since
+        .iter_days()
+        .take_while(|date| *date <= until)
+        .map(|date| {
+            let date = date.to_string();
+            let counts = by_date
+                .get(date.as_str())
+                .map(|group| AttemptCounts::from(*group))
+                .unwrap_or_default();
+            DailyCounts { date, counts }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn group(key: &str, total: u64, successful: u64, duration_ms: u64) -> AttemptGroup {
+        AttemptGroup {
+            key: Some(key.to_string()),
+            total,
+            successful,
+            duration_ms,
+        }
+    }
+
+    #[test]
+    fn counts_are_computed() {
+        let counts = AttemptCounts::from(&group("solidity", 4, 3, 10000));
+        assert_eq!(Some(0.75), counts.success_rate);
+        assert_eq!(Some(2500), counts.average_duration_ms);
+
+        let counts = AttemptCounts::from(&AttemptGroup::default());
+        assert_eq!(None, counts.success_rate);
+        assert_eq!(None, counts.average_duration_ms);
+
+        let document =
+            doc! { "_id": "solidity", "total": 4_i64, "successful": 3, "duration_ms": 10000.0 };
+        assert_eq!(
+            group("solidity", 4, 3, 10000),
+            AttemptGroup::from_document(&document)
+        );
+    }
+
+    #[test]
+    fn missing_days_are_filled() {
+        let date = |date: &str| NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        let groups = [group("2022-10-02", 2, 1, 0), group("2022-09-01", 1, 1, 0)];
+        let daily = daily_counts(&groups, date("2022-10-01"), date("2022-10-03"));
+        assert_eq!(
+            vec!["2022-10-01", "2022-10-02", "2022-10-03"],
+            daily
+                .iter()
+                .map(|day| day.date.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(0, daily[0].counts.total);
+        assert_eq!(2, daily[1].counts.total);
+        assert_eq!(Some(0.5), daily[1].counts.success_rate);
+    }
+
+    #[test]
+    fn compiler_usage_is_aggregated() {
+        let settings =
+            |version: &str, evm: &str, runs: Option<u64>, month: &str, contracts| SettingsGroup {
+                compiler_version: version.to_string(),
+                evm_version: evm.to_string(),
+                optimization: runs.is_some(),
+                optimization_runs: runs,
+                month: Some(month.to_string()).filter(|month| !month.is_empty()),
+                contracts,
+            };
+        let groups = [
+            settings("v0.8.14+commit.80d49f37", "london", Some(200), "2022-12", 3),
+            settings("v0.8.17+commit.8df45f5f", "default", None, "2023-01", 2),
+            settings("v0.4.24+commit.e67f0147", "byzantium", Some(200), "", 1),
+        ];
+        let today = NaiveDate::parse_from_str("2023-01-15", "%Y-%m-%d").unwrap();
+        let stats = compiler_stats(&groups, today, 2);
+
+        assert_eq!(6, stats.contracts);
+        let series: Vec<_> = stats
+            .compiler_series
+            .iter()
+            .map(|usage| (usage.value.as_str(), usage.contracts))
+            .collect();
+        assert_eq!(vec![("0.8", 5), ("0.4", 1)], series);
+        assert_eq!(4, stats.optimizer.enabled);
+        assert_eq!(2, stats.optimizer.disabled);
+        assert_eq!(
+            vec![Usage {
+                value: "200".into(),
+                contracts: 4
+            }],
+            stats.optimizer.runs
+        );
+        assert_eq!(
+            vec!["2022-12", "2023-01"],
+            stats
+                .monthly
+                .iter()
+                .map(|usage| usage.month.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            BTreeMap::from([("london".to_string(), 3)]),
+            stats.monthly[0].evm_versions
+        );
+        assert_eq!(2, stats.monthly[1].contracts);
+    }
+}
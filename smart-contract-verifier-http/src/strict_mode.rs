@@ -0,0 +1,267 @@
+use crate::{api_error::ApiError, rate_limit};
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    error::ErrorInternalServerError,
+    http::{
+        header::{self, HeaderName, HeaderValue},
+        StatusCode,
+    },
+    Error,
+};
+use futures::future::LocalBoxFuture;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// The header verification requests enable the strict mode with.
+pub const EXPECT_HEADER: &str = "x-verification-expect";
+/// The header the outcome of the verification is returned in, in the strict mode.
+pub const OUTCOME_HEADER: &str = "x-verification-outcome";
+
+/// How the contract has matched the compiled sources.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchOutcome {
+    Full,
+    Partial,
+    /// The contract has been verified, but the response does not tell how it has matched.
+    Unknown,
+    Mismatch,
+}
+
+impl MatchOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MatchOutcome::Full => "full",
+            MatchOutcome::Partial => "partial",
+            MatchOutcome::Unknown => "unknown",
+            MatchOutcome::Mismatch => "mismatch",
+        }
+    }
+}
+
+/// The least outcome the client (e.g., a release pipeline) accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expectation {
+    /// Either full or partial match.
+    Match,
+    Full,
+}
+
+impl FromStr for Expectation {
+    type Err = ApiError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "match" => Ok(Expectation::Match),
+            "full" => Ok(Expectation::Full),
+            _ => Err(ApiError::invalid_request(format!(
+                "Invalid {EXPECT_HEADER} header: expected \"match\" or \"full\", got \"{value}\""
+            ))),
+        }
+    }
+}
+
+impl Expectation {
+    /// Returns the status of the response with the given outcome. Outcomes which do not meet
+    /// the expectation are reported with distinct error statuses, so that `curl --fail` fails.
+    pub fn status(&self, outcome: MatchOutcome) -> StatusCode {
+        match (self, outcome) {
+            (_, MatchOutcome::Full) | (Expectation::Match, MatchOutcome::Partial) => StatusCode::OK,
+            (Expectation::Match, MatchOutcome::Unknown) => StatusCode::OK,
+            // Full matches cannot be confirmed
+            (Expectation::Full, MatchOutcome::Partial | MatchOutcome::Unknown) => {
+                StatusCode::PRECONDITION_FAILED
+            }
+            (_, MatchOutcome::Mismatch) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+/// Returns the outcome of the json verification response. Verification responses have
+/// a `status` of "0" for verified contracts; other responses (e.g., errors) have no status.
+fn outcome(response: &Value) -> Option<MatchOutcome> {
+    match response.get("status")?.as_str()? {
+        "0" => match response
+            .pointer("/result/match_type")
+            .and_then(Value::as_str)
+        {
+            Some("full") => Some(MatchOutcome::Full),
+            Some("partial") => Some(MatchOutcome::Partial),
+            _ => Some(MatchOutcome::Unknown),
+        },
+        _ => Some(MatchOutcome::Mismatch),
+    }
+}
+
+/// Whether the request is made to one of the routes verifying contracts, whose responses
+/// are verification responses. The expectation header of other requests is ignored.
+fn is_verification_route(request: &ServiceRequest) -> bool {
+    rate_limit::is_verification(request) && request.path().contains("verify")
+}
+
+/// Reports the outcome of verification requests submitted with `x-verification-expect` header
+/// in `x-verification-outcome` response header and `outcome` field, and fails the requests
+/// whose outcome does not meet the expectation. Other responses are passed through unchanged.
+pub fn check_expectation<S, B>(
+    request: ServiceRequest,
+    service: &S,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    let expectation = request
+        .headers()
+        .get(EXPECT_HEADER)
+        .filter(|_| is_verification_route(&request))
+        .map(|value| match value.to_str() {
+            Ok(value) => Expectation::from_str(value),
+            Err(_) => Err(ApiError::invalid_request(format!(
+                "Invalid {EXPECT_HEADER} header"
+            ))),
+        })
+        .transpose();
+    let expectation = match expectation {
+        Ok(Some(expectation)) => expectation,
+        Ok(None) => {
+            let response = service.call(request);
+            return Box::pin(async move { Ok(response.await?.map_into_boxed_body()) });
+        }
+        Err(err) => return Box::pin(async move { Err(err.into()) }),
+    };
+
+    let response = service.call(request);
+    Box::pin(async move {
+        let response = response.await?;
+        let is_json = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map_or(false, |value| value.starts_with("application/json"));
+        if !response.status().is_success() || !is_json {
+            return Ok(response.map_into_boxed_body());
+        }
+
+        let (http_request, response) = response.into_parts();
+        let (mut response, response_body) = response.into_parts();
+        let response_body = body::to_bytes(response_body).await.map_err(|err| {
+            let err: Box<dyn std::error::Error> = err.into();
+            ErrorInternalServerError(err.to_string())
+        })?;
+        let mut verification: Value = match serde_json::from_slice(&response_body) {
+            Ok(verification) => verification,
+            Err(_) => {
+                let response = response.set_body(BoxBody::new(response_body));
+                return Ok(ServiceResponse::new(http_request, response));
+            }
+        };
+        let outcome = match outcome(&verification) {
+            Some(outcome) => outcome,
+            // E.g., a job has been queued instead
+            None => {
+                let response = response.set_body(BoxBody::new(response_body));
+                return Ok(ServiceResponse::new(http_request, response));
+            }
+        };
+
+        if let Some(object) = verification.as_object_mut() {
+            object.insert("outcome".into(), outcome.as_str().into());
+        }
+        *response.status_mut() = expectation.status(outcome);
+        response.headers_mut().insert(
+            HeaderName::from_static(OUTCOME_HEADER),
+            HeaderValue::from_static(outcome.as_str()),
+        );
+        let response = response.set_body(BoxBody::new(verification.to_string()));
+        Ok(ServiceResponse::new(http_request, response))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+    use pretty_assertions::assert_eq;
+
+    const VERIFY_PATH: &str = "/api/v1/solidity/verify/standard-json";
+    const OTHER_PATH: &str = "/api/v1/solidity/compile";
+
+    #[test]
+    fn outcomes_are_checked() {
+        let partial = serde_json::json!({ "status": "0", "result": { "match_type": "partial" } });
+        let full = serde_json::json!({ "status": "0", "result": { "match_type": "full" } });
+        let unknown = serde_json::json!({ "status": "0", "result": {} });
+        let mismatch = serde_json::json!({ "status": "1", "result": null });
+        assert_eq!(Some(MatchOutcome::Partial), outcome(&partial));
+        assert_eq!(Some(MatchOutcome::Full), outcome(&full));
+        assert_eq!(Some(MatchOutcome::Unknown), outcome(&unknown));
+        assert_eq!(Some(MatchOutcome::Mismatch), outcome(&mismatch));
+        assert_eq!(None, outcome(&serde_json::json!({ "job_id": "6389" })));
+
+        assert_eq!(
+            StatusCode::OK,
+            Expectation::Match.status(MatchOutcome::Partial)
+        );
+        assert_eq!(
+            StatusCode::PRECONDITION_FAILED,
+            Expectation::Full.status(MatchOutcome::Partial)
+        );
+        assert_eq!(
+            StatusCode::OK,
+            Expectation::Match.status(MatchOutcome::Unknown)
+        );
+        assert_eq!(
+            StatusCode::PRECONDITION_FAILED,
+            Expectation::Full.status(MatchOutcome::Unknown)
+        );
+        assert_eq!(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Expectation::Match.status(MatchOutcome::Mismatch)
+        );
+        assert!(Expectation::from_str("exact").is_err());
+    }
+
+    #[actix_rt::test]
+    async fn unmet_expectations_fail_requests() {
+        let partial_match = || async {
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "OK",
+                "status": "0",
+                "result": { "match_type": "partial" },
+            }))
+        };
+        let app = test::init_service(
+            App::new()
+                .wrap_fn(check_expectation)
+                .route(VERIFY_PATH, web::post().to(partial_match))
+                .route(OTHER_PATH, web::post().to(partial_match)),
+        )
+        .await;
+
+        let request = test::TestRequest::post()
+            .uri(VERIFY_PATH)
+            .insert_header((EXPECT_HEADER, "full"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::PRECONDITION_FAILED, response.status());
+        assert_eq!("partial", response.headers()[OUTCOME_HEADER]);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(Some("partial"), body["outcome"].as_str());
+
+        // Responses are passed through unchanged without the header
+        let request = test::TestRequest::post().uri(VERIFY_PATH).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(!response.headers().contains_key(OUTCOME_HEADER));
+
+        // and to other routes, even with the header
+        let request = test::TestRequest::post()
+            .uri(OTHER_PATH)
+            .insert_header((EXPECT_HEADER, "full"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(!response.headers().contains_key(OUTCOME_HEADER));
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{
+    api_error::{ApiError, ErrorCode},
+    auth::{self, KeyHash},
+    chains::Chain,
+    rate_limit,
+    settings::{TenantSettings, TenantsSettings},
+    DB,
+};
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    web, Error, HttpRequest,
+};
+use chrono::Utc;
+use futures::future::LocalBoxFuture;
+use std::{future::Future, sync::Arc};
+
+tokio::task_local! {
+    /// Tenant the request is being served for. Is not set for requests without tenant api keys.
+    static TENANT: Option<String>;
+}
+
+/// Name of the tenant the current request (or background job) is served for, if any.
+/// Namespaces of the chains are derived from it.
+pub fn current() -> Option<String> {
+    TENANT.try_with(Clone::clone).ok().flatten()
+}
+
+/// Runs the future on behalf of the tenant, so that it stores and looks up
+/// verified contracts in namespaces of the tenant.
+pub async fn scope<F: Future>(tenant: Option<String>, future: F) -> F::Output {
+    TENANT.scope(tenant, future).await
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tenant {
+    pub name: String,
+    settings: TenantSettings,
+    /// Chain contracts are verified for. Quotas are counted in its namespaces.
+    chain: Chain,
+}
+
+impl Tenant {
+    /// Counts the verification request towards the daily quota of the tenant,
+    /// and fails with 403 Forbidden if the tenant has exhausted any of its quotas.
+    /// Returns the day the request has been counted on, if it has been counted.
+    /// Is run in the scope of the tenant.
+    async fn check_quotas(&self) -> Result<Option<String>, ApiError> {
+        let database = DB::new().await.change_name(&self.chain.namespace());
+        let mut counted = None;
+        if let Some(max) = self.settings.max_verifications_per_day {
+            let today = Utc::now().format("%Y-%m-%d").to_string();
+            let verifications = database
+                .increment_daily_verifications(&today)
+                .await
+                .map_err(ApiError::storage)?;
+            if verifications > max {
+                self.refund_verification(&today).await;
+                return Err(ApiError::new(
+                    ErrorCode::QuotaExceeded,
+                    format!(
+                        "Tenant {} has exhausted {max} verifications per day",
+                        self.name
+                    ),
+                ));
+            }
+            counted = Some(today);
+        }
+        if let Some(max) = self.settings.max_storage_bytes {
+            let size = database.storage_size().await.map_err(ApiError::storage)?;
+            if size >= max {
+                if let Some(day) = &counted {
+                    self.refund_verification(day).await;
+                }
+                return Err(ApiError::new(
+                    ErrorCode::QuotaExceeded,
+                    format!("Tenant {} has exhausted {max} bytes of storage", self.name),
+                ));
+            }
+        }
+        Ok(counted)
+    }
+
+    /// Takes back the verification request counted towards the daily quota,
+    /// so that requests rejected or failed with errors are not counted.
+    async fn refund_verification(&self, day: &str) {
+        let database = DB::new().await.change_name(&self.chain.namespace());
+        if let Err(err) = database.refund_daily_verification(day).await {
+            tracing::warn!("tenant {}: {}", self.name, err);
+        }
+    }
+}
+
+/// Resolves api keys provided with requests into the tenants they belong to.
+#[derive(Clone, Debug, Default)]
+pub struct Tenants {
+    /// Hashes of api keys with the tenants they belong to.
+    by_key: Vec<(KeyHash, Arc<Tenant>)>,
+    names: Vec<String>,
+}
+
+impl Tenants {
+    pub fn new(settings: &TenantsSettings, chain: &Chain) -> Self {
+        let mut by_key = Vec::new();
+        for (name, tenant_settings) in &settings.registry {
+            let tenant = Arc::new(Tenant {
+                name: name.clone(),
+                settings: tenant_settings.clone(),
+                chain: chain.clone(),
+            });
+            for key in &tenant_settings.api_keys {
+                by_key.push((auth::key_hash(key), tenant.clone()));
+            }
+        }
+        Self {
+            by_key,
+            names: settings.registry.keys().cloned().collect(),
+        }
+    }
+
+    /// Names of all registered tenants.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn authenticate(&self, request: &HttpRequest) -> Option<Arc<Tenant>> {
+        auth::lookup(&self.by_key, request).cloned()
+    }
+}
+
+/// Serves requests made with api keys of tenants in the scope of the tenants,
+/// rejecting verification requests of the tenants which have exhausted their quotas.
+pub fn scope_request<S, B>(
+    request: ServiceRequest,
+    service: &S,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    let tenant = request
+        .app_data::<web::Data<Tenants>>()
+        .and_then(|tenants| tenants.authenticate(request.request()));
+    let check_quotas = rate_limit::is_verification(&request);
+    // Service futures are lazy, so handlers are run in the scope of the tenant
+    let response = service.call(request);
+    let tenant = match tenant {
+        Some(tenant) => tenant,
+        None => return Box::pin(async move { Ok(response.await?.map_into_boxed_body()) }),
+    };
+    Box::pin(scope(Some(tenant.name.clone()), async move {
+        let counted = match check_quotas {
+            true => tenant.check_quotas().await?,
+            false => None,
+        };
+        let response = response.await;
+        if let Some(day) = counted {
+            // Unsuccessful verifications are responded with 200 OK as well,
+            // so only requests failed with errors are refunded
+            let succeeded = matches!(&response, Ok(response) if response.status().is_success());
+            if !succeeded {
+                tenant.refund_verification(&day).await;
+            }
+        }
+        Ok(response?.map_into_boxed_body())
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{auth::API_KEY_HEADER, chains::Chains};
+    use actix_web::test::TestRequest;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn namespaces_are_scoped_by_tenants() {
+        let settings = TenantsSettings {
+            enabled: true,
+            registry: BTreeMap::from([(
+                "appchain".to_string(),
+                TenantSettings {
+                    api_keys: vec!["appchain-key".to_string()],
+                    ..Default::default()
+                },
+            )]),
+        };
+        let chains = Chains::default();
+        let tenants = Tenants::new(&settings, chains.current());
+        let request = TestRequest::default()
+            .insert_header((API_KEY_HEADER, "appchain-key"))
+            .to_http_request();
+        let tenant = tenants.authenticate(&request).unwrap();
+        assert_eq!("appchain", tenant.name);
+        assert!(tenants
+            .authenticate(&TestRequest::default().to_http_request())
+            .is_none());
+
+        assert_eq!("evmos_9001", chains.current().namespace());
+        let namespace = scope(Some(tenant.name.clone()), async {
+            chains.current().namespace()
+        })
+        .await;
+        assert_eq!("evmos_appchain_9001", namespace);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{standards::Standard, DisplayBytes};
+use chrono::{DateTime, Utc};
+use ethabi::{ParamType, Token};
+use serde::{Deserialize, Serialize};
+use smart_contract_verifier::rpc::BytecodeFetcher;
+use std::{str::FromStr, time::Duration};
+
+/// Max time token metadata is read for, so that lookups do not pile up on slow providers.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// ERC-721 collections usually start numbering tokens either from 0 or from 1.
+const SAMPLE_TOKEN_IDS: [u64; 2] = [0, 1];
+
+/// Metadata of a verified ERC-20 or ERC-721 token, read from chain in the background.
+/// Functions which are optional in the standards are left unset if they revert.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TokenInfo {
+    /// `erc20` or `erc721`.
+    pub standard: Standard,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    /// Is read for ERC-20 tokens only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+    /// URI of the metadata of the first token of the collection. Is read for ERC-721 tokens only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_uri: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Reads metadata of the token if the contract implements ERC-20 or ERC-721.
+/// Returns `None` for other contracts, or if nothing could be read in time.
+pub async fn fetch(
+    fetcher: &BytecodeFetcher,
+    contract_address: &str,
+    standards: &[Standard],
+) -> Option<TokenInfo> {
+    let standard = [Standard::Erc20, Standard::Erc721]
+        .into_iter()
+        .find(|standard| standards.contains(standard))?;
+    match tokio::time::timeout(FETCH_TIMEOUT, read(fetcher, contract_address, standard)).await {
+        Ok(info) => info,
+        Err(_) => {
+            tracing::warn!(
+                "token metadata of {} has not been read in time",
+                contract_address
+            );
+            None
+        }
+    }
+}
+
+async fn read(
+    fetcher: &BytecodeFetcher,
+    contract_address: &str,
+    standard: Standard,
+) -> Option<TokenInfo> {
+    let (name, symbol) = futures::join!(
+        call(fetcher, contract_address, "name", &[], &[]),
+        call(fetcher, contract_address, "symbol", &[], &[]),
+    );
+    let mut info = TokenInfo {
+        standard,
+        name: name.as_deref().and_then(decode_text),
+        symbol: symbol.as_deref().and_then(decode_text),
+        decimals: None,
+        token_uri: None,
+        fetched_at: Utc::now(),
+    };
+    match standard {
+        Standard::Erc20 => {
+            let decimals = call(fetcher, contract_address, "decimals", &[], &[]).await;
+            info.decimals = decimals.as_deref().and_then(decode_decimals);
+        }
+        _ => {
+            for token_id in SAMPLE_TOKEN_IDS {
+                let uri = call(
+                    fetcher,
+                    contract_address,
+                    "tokenURI",
+                    &[ParamType::Uint(256)],
+                    &[Token::Uint(token_id.into())],
+                )
+                .await;
+                info.token_uri = uri.as_deref().and_then(decode_text);
+                if info.token_uri.is_some() {
+                    break;
+                }
+            }
+        }
+    }
+    (info.name.is_some() || info.symbol.is_some()).then_some(info)
+}
+
+/// Calls the function of the contract with the given arguments.
+/// Returns `None` if the call reverts or fails.
+async fn call(
+    fetcher: &BytecodeFetcher,
+    contract_address: &str,
+    function: &str,
+    params: &[ParamType],
+    args: &[Token],
+) -> Option<Vec<u8>> {
+    let mut calldata = ethabi::short_signature(function, params).to_vec();
+    calldata.extend(ethabi::encode(args));
+    let output = fetcher
+        .call(contract_address, &format!("0x{}", hex::encode(calldata)))
+        .await;
+    match output {
+        Ok(output) => DisplayBytes::from_str(&output?)
+            .ok()
+            .map(|output| output.0.to_vec()),
+        Err(err) => {
+            tracing::debug!("cannot call {} of {}: {}", function, contract_address, err);
+            None
+        }
+    }
+}
+
+/// Decodes a returned `string`. Some early tokens (e.g., MKR) return `bytes32` instead,
+/// padded with zeros on the right.
+fn decode_text(output: &[u8]) -> Option<String> {
+    let text = match ethabi::decode(&[ParamType::String], output) {
+        Ok(tokens) => tokens.into_iter().next()?.into_string()?,
+        Err(_) if output.len() == 32 => {
+            let end = output.iter().rposition(|byte| *byte != 0)? + 1;
+            String::from_utf8(output[..end].to_vec()).ok()?
+        }
+        Err(_) => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+fn decode_decimals(output: &[u8]) -> Option<u8> {
+    let value = ethabi::decode(&[ParamType::Uint(256)], output)
+        .ok()?
+        .into_iter()
+        .next()?
+        .into_uint()?;
+    u8::try_from(value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn outputs_are_decoded() {
+        let string = ethabi::encode(&[Token::String("Wrapped Ether".into())]);
+        assert_eq!(Some("Wrapped Ether".to_string()), decode_text(&string));
+
+        let mut bytes32 = b"MKR".to_vec();
+        bytes32.resize(32, 0);
+        assert_eq!(Some("MKR".to_string()), decode_text(&bytes32));
+        assert_eq!(None, decode_text(&[0; 32]));
+        assert_eq!(None, decode_text(&[]));
+
+        let decimals = ethabi::encode(&[Token::Uint(18.into())]);
+        assert_eq!(Some(18), decode_decimals(&decimals));
+        let decimals = ethabi::encode(&[Token::Uint(256.into())]);
+        assert_eq!(None, decode_decimals(&decimals));
+    }
+}
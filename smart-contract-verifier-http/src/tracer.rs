@@ -1,37 +1,122 @@
-use crate::settings::JaegerSettings;
-use opentelemetry::{sdk::trace::Tracer, trace::TraceError};
-use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, prelude::*};
+use crate::settings::{JaegerSettings, LogFormat, LogsSettings, OtlpSettings};
+use lazy_static::lazy_static;
+use opentelemetry::{
+    sdk::{
+        propagation::TraceContextPropagator,
+        trace::{self, Sampler, Tracer},
+        Resource,
+    },
+    trace::TraceError,
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use std::{sync::RwLock, time::Duration};
+use tracing_subscriber::{
+    filter::LevelFilter, layer::SubscriberExt, prelude::*, reload, EnvFilter, Layer, Registry,
+};
 
-pub fn init_logs(jaeger_settings: JaegerSettings) {
-    let stdout = tracing_subscriber::fmt::layer().with_filter(
-        tracing_subscriber::EnvFilter::builder()
-            .with_default_directive(LevelFilter::INFO.into())
-            .from_env_lossy(),
-    );
-    let registry = tracing_subscriber::registry()
-        // output logs (tracing) to stdout with log level taken from env (default is INFO)
-        .with(stdout);
-    if jaeger_settings.enabled {
-        let tracer =
-            init_jaeger_tracer(&jaeger_settings.agent_endpoint).expect("failed to init tracer");
-        registry
-            // output traces to jaeger with default log level (default is DEBUG)
-            .with(
-                tracing_opentelemetry::layer()
-                    .with_tracer(tracer)
-                    .with_filter(LevelFilter::DEBUG),
-            )
-            .try_init()
-    } else {
-        registry.try_init()
+const SERVICE_NAME: &str = "smart_contract_verifier";
+
+lazy_static! {
+    static ref LOG_FILTER: RwLock<Option<reload::Handle<EnvFilter, Registry>>> = RwLock::new(None);
+}
+
+pub fn init_logs(
+    logs_settings: LogsSettings,
+    jaeger_settings: JaegerSettings,
+    otlp_settings: OtlpSettings,
+) {
+    let filter = EnvFilter::builder().with_default_directive(LevelFilter::INFO.into());
+    let filter = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(_) => filter.from_env_lossy(),
+        Err(_) => filter
+            .parse(&logs_settings.filter)
+            .expect("invalid logs filter"),
+    };
+    let (filter, handle) = reload::Layer::new(filter);
+    *LOG_FILTER.write().expect("lock is not poisoned") = Some(handle);
+    let stdout = match logs_settings.format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .boxed(),
     }
-    .expect("failed to register tracer with registry");
+    .with_filter(filter);
+    // Trace context of incoming requests (`traceparent` header) is picked up by `TracingLogger`,
+    // so that spans of the service are attached to traces of the callers
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    let jaeger = jaeger_settings.enabled.then(|| {
+        init_jaeger_tracer(&jaeger_settings.agent_endpoint).expect("failed to init tracer")
+    });
+    let otlp = otlp_settings
+        .enabled
+        .then(|| init_otlp_tracer(&otlp_settings).expect("failed to init otlp tracer"));
+    tracing_subscriber::registry()
+        // output logs (tracing) to stdout with log levels taken from env or settings (default is INFO)
+        .with(stdout)
+        // output traces to jaeger with default log level (default is DEBUG)
+        .with(jaeger.map(|tracer| {
+            tracing_opentelemetry::layer()
+                .with_tracer(tracer)
+                .with_filter(LevelFilter::DEBUG)
+        }))
+        // output traces to the otlp collector with the same level
+        .with(otlp.map(|tracer| {
+            tracing_opentelemetry::layer()
+                .with_tracer(tracer)
+                .with_filter(LevelFilter::DEBUG)
+        }))
+        .try_init()
+        .expect("failed to register tracer with registry");
+}
+
+/// Returns directives of the filter applied to the logs written to stdout.
+pub fn log_filter() -> Option<String> {
+    let handle = LOG_FILTER.read().expect("lock is not poisoned").clone()?;
+    handle.with_current(|filter| filter.to_string()).ok()
+}
+
+/// Replaces the filter applied to the logs written to stdout, so that log levels
+/// could be changed while the service runs. Traces exported to collectors are not affected.
+pub fn set_log_filter(directives: &str) -> anyhow::Result<()> {
+    let filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .parse(directives)?;
+    let handle = LOG_FILTER
+        .read()
+        .expect("lock is not poisoned")
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("logs are not initialized"))?;
+    handle.reload(filter)?;
+    Ok(())
 }
 
 fn init_jaeger_tracer(agent_endpoint: &str) -> Result<Tracer, TraceError> {
     opentelemetry_jaeger::new_pipeline()
         .with_agent_endpoint(agent_endpoint)
-        .with_service_name("smart_contract_verifier")
+        .with_service_name(SERVICE_NAME)
         .with_auto_split_batch(true)
         .install_batch(opentelemetry::runtime::Tokio)
 }
+
+fn init_otlp_tracer(settings: &OtlpSettings) -> Result<Tracer, TraceError> {
+    let exporter = opentelemetry_otlp::new_exporter()
+        .tonic()
+        .with_endpoint(&settings.endpoint)
+        .with_timeout(Duration::from_secs(settings.timeout_secs));
+    // Sampling decisions of the callers (e.g., explorer backends) are respected,
+    // so that distributed traces are either recorded entirely or not at all
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+        settings.sampling_ratio,
+    )));
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(
+            trace::config()
+                .with_sampler(sampler)
+                .with_resource(Resource::new([KeyValue::new("service.name", SERVICE_NAME)])),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)
+}
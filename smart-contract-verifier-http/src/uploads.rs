@@ -0,0 +1,627 @@
+use crate::{
+    object_storage::{ObjectStorage, ObjectWriter},
+    settings::UploadsSettings,
+};
+use actix_web::web;
+use chrono::{DateTime, Duration, Utc};
+use cron::Schedule;
+use futures::StreamExt;
+use mongodb::bson::oid::ObjectId;
+use serde::Serialize;
+use smart_contract_verifier::scheduler;
+use std::{ops::RangeInclusive, str::FromStr, sync::Arc};
+use thiserror::Error;
+
+const KEY_PREFIX: &str = "uploads/";
+/// Chunks of payloads uploaded in parts are kept aside until the last one is received.
+const CHUNKS_PREFIX: &str = "upload-chunks/";
+/// Length of the random part of upload ids, in bytes.
+const SECRET_LEN: usize = 16;
+
+#[derive(Error, Debug)]
+pub enum UploadError {
+    #[error("upload id {0} is invalid")]
+    InvalidId(String),
+    #[error("upload {0} has expired")]
+    Expired(String),
+    #[error("payload of upload {0} has not been uploaded")]
+    NotUploaded(String),
+    #[error("uploaded payload exceeds {0} bytes")]
+    TooLarge(usize),
+    #[error("invalid content range: {0}")]
+    InvalidRange(String),
+    #[error("chunk is expected to start at byte {0}")]
+    UnexpectedOffset(usize),
+    #[error("payload size has been set to {0} bytes by the first chunk")]
+    TotalMismatch(usize),
+    #[error("upload {0} has been completed")]
+    Completed(String),
+    #[error("upload has been interrupted: {0}")]
+    Interrupted(String),
+    #[error("payloads should be uploaded through pre-signed urls")]
+    PreSigned,
+    #[error("upload storage error: {0:#}")]
+    Storage(anyhow::Error),
+}
+
+/// Slot a payload should be uploaded into with a `PUT` request before it expires.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct UploadSlot {
+    pub upload_id: String,
+    /// Either a pre-signed url of the object storage, or the upload endpoint of the service.
+    pub upload_url: String,
+    /// Max size of the payload, in bytes.
+    pub max_size: usize,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Range of the payload a chunk covers, as sent in the `Content-Range` header
+/// (e.g., "bytes 0-1048575/52428800"). Bounds are inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkRange {
+    pub start: usize,
+    pub end: usize,
+    /// Size of the whole payload.
+    pub total: usize,
+}
+
+impl ChunkRange {
+    fn len(&self) -> usize {
+        self.end + 1 - self.start
+    }
+}
+
+impl FromStr for ChunkRange {
+    type Err = UploadError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || UploadError::InvalidRange(value.to_string());
+        let (range, total) = value
+            .strip_prefix("bytes ")
+            .and_then(|range| range.split_once('/'))
+            .ok_or_else(invalid)?;
+        let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+        let range = Self {
+            start: start.parse().map_err(|_| invalid())?,
+            end: end.parse().map_err(|_| invalid())?,
+            total: total.parse().map_err(|_| invalid())?,
+        };
+        match range.start <= range.end && range.end < range.total {
+            true => Ok(range),
+            false => Err(invalid()),
+        }
+    }
+}
+
+/// Progress of the upload, which allows resuming uploads in chunks after failures.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct UploadStatus {
+    pub upload_id: String,
+    /// Number of bytes received so far. The next chunk should start at it.
+    pub received: usize,
+    /// Whether the whole payload has been received, so that it could be referenced.
+    pub completed: bool,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Payloads too large to be sent in verification requests (e.g., standard-json inputs),
+/// which are uploaded beforehand and referenced by the requests through upload ids.
+///
+/// Upload ids start with an object id, so that the time a slot has been issued at is known
+/// without keeping uploads in the database, followed by a random part, so that payloads
+/// could not be replaced by anyone but the uploader.
+pub struct Uploads {
+    storage: ObjectStorage,
+    max_size: usize,
+    ttl: Duration,
+}
+
+impl Uploads {
+    pub fn new(storage: ObjectStorage, settings: &UploadsSettings) -> Self {
+        Self {
+            storage,
+            max_size: settings.max_size,
+            ttl: Duration::seconds(settings.ttl_secs.get() as i64),
+        }
+    }
+
+    /// Issues a new upload slot. Payloads are uploaded to s3 storage directly through
+    /// pre-signed urls, and to `endpoint_url` (followed by the upload id) otherwise.
+    pub fn issue_slot(&self, endpoint_url: &str) -> Result<UploadSlot, UploadError> {
+        let id = ObjectId::new();
+        let secret: [u8; SECRET_LEN] = rand::random();
+        let upload_id = format!("{}{}", id.to_hex(), hex::encode(secret));
+        let upload_url = match &self.storage {
+            ObjectStorage::S3(bucket) => bucket
+                .presign_put(key(&upload_id), self.ttl.num_seconds() as u32, None)
+                .map_err(|err| UploadError::Storage(err.into()))?,
+            ObjectStorage::Local(_) => {
+                format!("{}/{upload_id}", endpoint_url.trim_end_matches('/'))
+            }
+        };
+        Ok(UploadSlot {
+            upload_id,
+            upload_url,
+            max_size: self.max_size,
+            expires_at: id.timestamp().to_chrono() + self.ttl,
+        })
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Saves the whole payload uploaded to the service, writing it to the storage
+    /// as it is received, so that it is neither limited by the max size of json requests
+    /// nor held in memory.
+    pub async fn save(
+        &self,
+        upload_id: &str,
+        payload: web::Payload,
+    ) -> Result<UploadStatus, UploadError> {
+        let expires_at = self.check_expiry(upload_id)?;
+        let (writer, received) = self
+            .write_payload(&key(upload_id), payload, 0..=self.max_size)
+            .await?;
+        writer.finish().await.map_err(UploadError::Storage)?;
+        Ok(UploadStatus {
+            upload_id: upload_id.to_string(),
+            received,
+            completed: true,
+            expires_at,
+        })
+    }
+
+    /// Saves a chunk of the payload uploaded in parts. Chunks are accepted in order only,
+    /// and the payload is assembled once the last one is received.
+    ///
+    /// Chunks are saved only if there is no chunk starting at the same offset, so that
+    /// of concurrent requests sending the next chunk only one succeeds.
+    pub async fn save_chunk(
+        &self,
+        upload_id: &str,
+        range: ChunkRange,
+        payload: web::Payload,
+    ) -> Result<UploadStatus, UploadError> {
+        if range.total > self.max_size {
+            return Err(UploadError::TooLarge(self.max_size));
+        }
+        let status = self.status(upload_id).await?;
+        if status.completed {
+            return Err(UploadError::Completed(upload_id.to_string()));
+        }
+        if range.start != status.received {
+            return Err(UploadError::UnexpectedOffset(status.received));
+        }
+        self.check_total(upload_id, range.total).await?;
+        let (writer, _) = self
+            .write_payload(
+                &chunk_key(upload_id, range.start),
+                payload,
+                range.len()..=range.len(),
+            )
+            .await?;
+        if !writer.finish_new().await.map_err(UploadError::Storage)? {
+            let received = self.status(upload_id).await?.received;
+            return Err(UploadError::UnexpectedOffset(received));
+        }
+
+        let received = range.end + 1;
+        let completed = received == range.total;
+        if completed {
+            self.assemble(upload_id).await?;
+        }
+        Ok(UploadStatus {
+            received,
+            completed,
+            ..status
+        })
+    }
+
+    pub async fn status(&self, upload_id: &str) -> Result<UploadStatus, UploadError> {
+        let expires_at = self.check_expiry(upload_id)?;
+        let size = self
+            .storage
+            .size(&key(upload_id))
+            .await
+            .map_err(UploadError::Storage)?;
+        let received = match size {
+            Some(size) => size as usize,
+            None => received(&self.chunks(upload_id).await?),
+        };
+        Ok(UploadStatus {
+            upload_id: upload_id.to_string(),
+            received,
+            completed: size.is_some(),
+            expires_at,
+        })
+    }
+
+    /// Stores the size of the payload along with the first chunk, and checks
+    /// that the following chunks are sent for the payload of the same size.
+    async fn check_total(&self, upload_id: &str, total: usize) -> Result<(), UploadError> {
+        let key = total_key(upload_id);
+        let mut writer = self
+            .storage
+            .writer(&key)
+            .await
+            .map_err(UploadError::Storage)?;
+        writer
+            .write(total.to_string().as_bytes())
+            .await
+            .map_err(UploadError::Storage)?;
+        if writer.finish_new().await.map_err(UploadError::Storage)? {
+            return Ok(());
+        }
+        match self.total(upload_id).await? {
+            Some(stored) if stored != total => Err(UploadError::TotalMismatch(stored)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the size of the payload set by the first chunk.
+    async fn total(&self, upload_id: &str) -> Result<Option<usize>, UploadError> {
+        let key = total_key(upload_id);
+        if self
+            .storage
+            .size(&key)
+            .await
+            .map_err(UploadError::Storage)?
+            .is_none()
+        {
+            return Ok(None);
+        }
+        let total = self
+            .storage
+            .load(&key)
+            .await
+            .map_err(UploadError::Storage)?;
+        String::from_utf8_lossy(&total)
+            .parse()
+            .map(Some)
+            .map_err(|_| {
+                UploadError::Storage(anyhow::anyhow!("invalid size of upload {upload_id}"))
+            })
+    }
+
+    /// Returns ranges of the received chunks sorted by their start.
+    /// Chunks are keyed by their start, and their ends are known from their sizes.
+    async fn chunks(&self, upload_id: &str) -> Result<Vec<(String, ChunkRange)>, UploadError> {
+        let total = match self.total(upload_id).await? {
+            Some(total) => total,
+            None => return Ok(vec![]),
+        };
+        let keys = self
+            .storage
+            .list(&format!("{CHUNKS_PREFIX}{upload_id}-"))
+            .await
+            .map_err(UploadError::Storage)?;
+        let mut chunks = Vec::new();
+        for key in keys {
+            let start = match parse_chunk_key(&key) {
+                Some(start) => start,
+                None => continue,
+            };
+            let size = self
+                .storage
+                .size(&key)
+                .await
+                .map_err(UploadError::Storage)?
+                .unwrap_or_default() as usize;
+            if size > 0 {
+                let end = start + size - 1;
+                chunks.push((key, ChunkRange { start, end, total }));
+            }
+        }
+        Ok(chunks)
+    }
+
+    /// Concatenates the chunks into the payload, copying them in parts.
+    async fn assemble(&self, upload_id: &str) -> Result<(), UploadError> {
+        let chunks = self.chunks(upload_id).await?;
+        let mut writer = self
+            .storage
+            .writer(&key(upload_id))
+            .await
+            .map_err(UploadError::Storage)?;
+        let mut assembled = 0;
+        for (key, range) in &chunks {
+            if range.start != assembled {
+                return Err(UploadError::UnexpectedOffset(assembled));
+            }
+            writer
+                .append(&self.storage, key)
+                .await
+                .map_err(UploadError::Storage)?;
+            assembled = range.end + 1;
+        }
+        writer.finish().await.map_err(UploadError::Storage)?;
+        let keys = chunks.into_iter().map(|(key, _)| key);
+        for key in keys.chain([total_key(upload_id)]) {
+            if let Err(err) = self.storage.remove(&key).await {
+                // Are removed along with expired uploads otherwise
+                tracing::warn!("cannot remove the upload chunk {}: {:#}", key, err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the uploaded payload. The size is checked before the payload is read,
+    /// as it could not be limited for payloads uploaded through pre-signed urls.
+    pub async fn load(&self, upload_id: &str) -> Result<Vec<u8>, UploadError> {
+        self.check_expiry(upload_id)?;
+        let size = self
+            .storage
+            .size(&key(upload_id))
+            .await
+            .map_err(UploadError::Storage)?
+            .ok_or_else(|| UploadError::NotUploaded(upload_id.to_string()))?;
+        if size > self.max_size as u64 {
+            return Err(UploadError::TooLarge(self.max_size));
+        }
+        self.storage.load(&key(upload_id)).await.map_err(|err| {
+            tracing::debug!("cannot load upload {}: {:#}", upload_id, err);
+            UploadError::NotUploaded(upload_id.to_string())
+        })
+    }
+
+    /// Removes expired payloads and chunks, as well as objects which are not uploads.
+    /// Returns the number of removed objects.
+    async fn remove_expired(&self) -> anyhow::Result<usize> {
+        let mut keys = self.storage.list(KEY_PREFIX).await?;
+        keys.extend(self.storage.list(CHUNKS_PREFIX).await?);
+        let mut removed = 0;
+        for key in keys {
+            let upload_id = match key.strip_prefix(KEY_PREFIX) {
+                Some(upload_id) => Some(upload_id),
+                None => key
+                    .strip_prefix(CHUNKS_PREFIX)
+                    .and_then(|name| name.split_once('-'))
+                    .map(|(upload_id, _)| upload_id),
+            };
+            let expired = upload_id.map_or(true, |upload_id| self.check_expiry(upload_id).is_err());
+            if expired {
+                self.storage.remove(&key).await?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Writes the payload into the object as it is received. Returns the writer to be finished
+    /// by the caller if the size of the payload is in the given range, along with the size.
+    ///
+    /// Payloads are uploaded to s3 storage directly through pre-signed urls only.
+    async fn write_payload(
+        &self,
+        key: &str,
+        mut payload: web::Payload,
+        size: RangeInclusive<usize>,
+    ) -> Result<(ObjectWriter, usize), UploadError> {
+        let max_size = *size.end();
+        if let ObjectStorage::S3(_) = self.storage {
+            return Err(UploadError::PreSigned);
+        }
+        let mut writer = self
+            .storage
+            .writer(key)
+            .await
+            .map_err(UploadError::Storage)?;
+        let mut written = 0;
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk.map_err(|err| UploadError::Interrupted(err.to_string()))?;
+            if written + chunk.len() > max_size {
+                return Err(UploadError::TooLarge(max_size));
+            }
+            writer.write(&chunk).await.map_err(UploadError::Storage)?;
+            written += chunk.len();
+        }
+        if written < *size.start() {
+            return Err(UploadError::Interrupted(format!(
+                "{written} bytes have been received instead of {}",
+                size.start()
+            )));
+        }
+        Ok((writer, written))
+    }
+
+    /// Returns the time the upload expires at.
+    fn check_expiry(&self, upload_id: &str) -> Result<DateTime<Utc>, UploadError> {
+        let expires_at = issued_at(upload_id)? + self.ttl;
+        match expires_at < Utc::now() {
+            true => Err(UploadError::Expired(upload_id.to_string())),
+            false => Ok(expires_at),
+        }
+    }
+}
+
+fn key(upload_id: &str) -> String {
+    format!("{KEY_PREFIX}{upload_id}")
+}
+
+/// The start is zero-padded, so that keys of chunks are sorted by it.
+fn chunk_key(upload_id: &str, start: usize) -> String {
+    format!("{CHUNKS_PREFIX}{upload_id}-{start:016}")
+}
+
+/// Returns the start of the chunk, or `None` for other objects of the upload.
+fn parse_chunk_key(key: &str) -> Option<usize> {
+    let (_, start) = key.strip_prefix(CHUNKS_PREFIX)?.split_once('-')?;
+    start.parse().ok()
+}
+
+fn total_key(upload_id: &str) -> String {
+    format!("{CHUNKS_PREFIX}{upload_id}-total")
+}
+
+/// Returns the number of bytes received in contiguous chunks from the start of the payload.
+fn received(chunks: &[(String, ChunkRange)]) -> usize {
+    let mut received = 0;
+    for (_, range) in chunks {
+        if range.start != received {
+            break;
+        }
+        received = range.end + 1;
+    }
+    received
+}
+
+fn issued_at(upload_id: &str) -> Result<DateTime<Utc>, UploadError> {
+    let invalid = || UploadError::InvalidId(upload_id.to_string());
+    if upload_id.len() != 24 + 2 * SECRET_LEN || hex::decode(upload_id).is_err() {
+        return Err(invalid());
+    }
+    let id = ObjectId::parse_str(&upload_id[..24]).map_err(|_| invalid())?;
+    Ok(id.timestamp().to_chrono())
+}
+
+/// Periodically removes expired payloads from the storage.
+pub fn spawn_cleanup_job(uploads: Arc<Uploads>) {
+    let schedule = Schedule::from_str("0 0 * * * * *").expect("Is valid schedule"); // every hour
+    scheduler::spawn_job(schedule, "remove expired uploads", move || {
+        let uploads = uploads.clone();
+        async move {
+            match uploads.remove_expired().await {
+                Ok(removed) => tracing::debug!("{} expired uploads removed", removed),
+                Err(err) => tracing::error!("cannot remove expired uploads: {:#}", err),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test::TestRequest, FromRequest};
+    use pretty_assertions::assert_eq;
+    use std::num::NonZeroU64;
+
+    async fn payload(content: &[u8]) -> web::Payload {
+        let (request, mut payload) = TestRequest::default()
+            .set_payload(content.to_vec())
+            .to_http_parts();
+        web::Payload::from_request(&request, &mut payload)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn expired_uploads_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = ObjectStorage::Local(dir.path().to_path_buf());
+        let settings = UploadsSettings {
+            ttl_secs: NonZeroU64::new(60).unwrap(),
+            max_size: 16,
+            ..Default::default()
+        };
+        let uploads = Uploads::new(storage, &settings);
+
+        let slot = uploads
+            .issue_slot("http://localhost/api/v1/uploads/")
+            .unwrap();
+        assert_eq!(
+            format!("http://localhost/api/v1/uploads/{}", slot.upload_id),
+            slot.upload_url
+        );
+        uploads
+            .storage
+            .save(&key(&slot.upload_id), b"{}".to_vec())
+            .await
+            .unwrap();
+        let expired = format!("{}{}", "0".repeat(24), "ab".repeat(SECRET_LEN));
+        uploads
+            .storage
+            .save(&key(&expired), b"{}".to_vec())
+            .await
+            .unwrap();
+
+        assert_eq!(b"{}".to_vec(), uploads.load(&slot.upload_id).await.unwrap());
+        assert!(matches!(
+            uploads.load(&expired).await,
+            Err(UploadError::Expired(_))
+        ));
+        assert!(matches!(
+            uploads.load("../backups").await,
+            Err(UploadError::InvalidId(_))
+        ));
+        // Payloads uploaded through pre-signed urls are not limited while uploaded
+        let large = uploads.issue_slot("").unwrap().upload_id;
+        uploads
+            .storage
+            .save(&key(&large), vec![0; uploads.max_size + 1])
+            .await
+            .unwrap();
+        assert!(matches!(
+            uploads.load(&large).await,
+            Err(UploadError::TooLarge(_))
+        ));
+        uploads.storage.remove(&key(&large)).await.unwrap();
+
+        assert_eq!(1, uploads.remove_expired().await.unwrap());
+        assert_eq!(
+            vec![key(&slot.upload_id)],
+            uploads.storage.list(KEY_PREFIX).await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn chunks_are_assembled_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = ObjectStorage::Local(dir.path().to_path_buf());
+        let uploads = Uploads::new(storage, &UploadsSettings::default());
+        let upload_id = uploads.issue_slot("").unwrap().upload_id;
+        let range = |range: &str| ChunkRange::from_str(range).unwrap();
+
+        let status = uploads
+            .save_chunk(&upload_id, range("bytes 0-3/8"), payload(b"abcd").await)
+            .await
+            .unwrap();
+        assert_eq!((4, false), (status.received, status.completed));
+        // The chunk is sent again, as if its response has been lost
+        assert!(matches!(
+            uploads
+                .save_chunk(&upload_id, range("bytes 0-3/8"), payload(b"abcd").await)
+                .await,
+            Err(UploadError::UnexpectedOffset(4))
+        ));
+        assert!(matches!(
+            uploads.load(&upload_id).await,
+            Err(UploadError::NotUploaded(_))
+        ));
+        // Incomplete chunks are discarded
+        assert!(matches!(
+            uploads
+                .save_chunk(&upload_id, range("bytes 4-7/8"), payload(b"ef").await)
+                .await,
+            Err(UploadError::Interrupted(_))
+        ));
+        assert_eq!(4, uploads.status(&upload_id).await.unwrap().received);
+        assert!(matches!(
+            uploads
+                .save_chunk(&upload_id, range("bytes 4-7/9"), payload(b"efgh").await)
+                .await,
+            Err(UploadError::TotalMismatch(8))
+        ));
+
+        let status = uploads
+            .save_chunk(&upload_id, range("bytes 4-7/8"), payload(b"efgh").await)
+            .await
+            .unwrap();
+        assert_eq!((8, true), (status.received, status.completed));
+        assert_eq!(status, uploads.status(&upload_id).await.unwrap());
+        assert_eq!(
+            b"abcdefgh".to_vec(),
+            uploads.load(&upload_id).await.unwrap()
+        );
+        assert!(uploads.chunks(&upload_id).await.unwrap().is_empty());
+        assert!(uploads
+            .storage
+            .list(CHUNKS_PREFIX)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(ChunkRange::from_str("bytes 4-3/8").is_err());
+        assert!(ChunkRange::from_str("bytes 0-8/8").is_err());
+    }
+}
@@ -1,6 +1,17 @@
-use crate::DisplayBytes;
+use crate::{
+    contract_size::ContractSize,
+    impersonation::ImpersonationWarning,
+    licenses,
+    standards::{self, Standard},
+    DisplayBytes,
+};
+use ethers_solc::artifacts::{output_selection::OutputSelection, Contract, Severity};
 use serde::{Deserialize, Serialize};
-use smart_contract_verifier::{SourcifySuccess, VerificationSuccess};
+use smart_contract_verifier::{
+    solidity::STORAGE_LAYOUT_MIN_VERSION,
+    tools::hidden_characters::{self, Finding},
+    ContractDiagnostics, DiagnosticsHint, SourcifySuccess, VerificationSuccess,
+};
 use std::{collections::BTreeMap, fmt::Display};
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
@@ -8,6 +19,60 @@ pub struct VerificationResponse {
     pub message: String,
     pub result: Option<VerificationResult>,
     pub status: VerificationStatus,
+    /// Is returned for failed verifications if requested with `verbose` flag.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagnostics: Option<Vec<Diagnostics>>,
+    /// Files of well-known packages added to the sources, if requested with `inject_dependencies` flag.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub injected_sources: Vec<String>,
+}
+
+/// Explains why a candidate contract has not matched the remote bytecode.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Diagnostics {
+    pub file_name: String,
+    pub contract_name: Option<String>,
+    pub reason: String,
+    pub first_diverging_offset: Option<usize>,
+    pub metadata_hash_only: bool,
+    pub length_differs: bool,
+    pub hints: Vec<Hint>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Hint {
+    OptimizerSettings,
+    EvmVersion,
+    ConstructorArguments,
+    Libraries,
+    CompilerVersion,
+}
+
+impl From<DiagnosticsHint> for Hint {
+    fn from(hint: DiagnosticsHint) -> Self {
+        match hint {
+            DiagnosticsHint::OptimizerSettings => Hint::OptimizerSettings,
+            DiagnosticsHint::EvmVersion => Hint::EvmVersion,
+            DiagnosticsHint::ConstructorArguments => Hint::ConstructorArguments,
+            DiagnosticsHint::Libraries => Hint::Libraries,
+            DiagnosticsHint::CompilerVersion => Hint::CompilerVersion,
+        }
+    }
+}
+
+impl From<ContractDiagnostics> for Diagnostics {
+    fn from(diagnostics: ContractDiagnostics) -> Self {
+        Self {
+            file_name: diagnostics.file_path,
+            contract_name: diagnostics.contract_name,
+            reason: diagnostics.reason,
+            first_diverging_offset: diagnostics.first_diverging_offset,
+            metadata_hash_only: diagnostics.metadata_hash_only,
+            length_differs: diagnostics.length_differs,
+            hints: diagnostics.hints.into_iter().map(Hint::from).collect(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
@@ -30,10 +95,90 @@ impl From<smart_contract_verifier::BytecodePart> for BytecodePart {
     }
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompilerMessageSeverity {
+    Warning,
+    Info,
+}
+
+/// Non-fatal message reported by the compiler (e.g., missing SPDX license identifier).
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CompilerMessage {
+    pub severity: CompilerMessageSeverity,
+    pub error_code: Option<String>,
+    /// Formatted message including the source location, if provided by the compiler.
+    pub message: String,
+}
+
+impl CompilerMessage {
+    /// Returns `None` for errors, as the compilation could not have succeeded with them.
+    fn new(error: ethers_solc::artifacts::Error) -> Option<Self> {
+        let severity = match error.severity {
+            Severity::Warning => CompilerMessageSeverity::Warning,
+            Severity::Info => CompilerMessageSeverity::Info,
+            Severity::Error => return None,
+        };
+        Some(Self {
+            severity,
+            error_code: error.error_code.map(|code| code.to_string()),
+            message: error.formatted_message.unwrap_or(error.message),
+        })
+    }
+}
+
+/// Hidden or lookalike character found in the verified sources.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SourceWarning {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    /// "bidi_control", "invisible" or "homoglyph".
+    pub kind: String,
+    /// Code point of the character (e.g., "U+202E").
+    pub code_point: String,
+    pub message: String,
+}
+
+impl From<Finding> for SourceWarning {
+    fn from(finding: Finding) -> Self {
+        Self {
+            message: finding.to_string(),
+            kind: finding.kind.as_str().to_string(),
+            file: finding.file,
+            line: finding.line,
+            column: finding.column,
+            code_point: finding.code_point,
+        }
+    }
+}
+
+/// Returns hidden and lookalike characters of the sources, up to
+/// [`hidden_characters::MAX_FINDINGS`] of them.
+fn source_warnings(sources: &BTreeMap<String, String>) -> Vec<SourceWarning> {
+    sources
+        .iter()
+        .flat_map(|(path, content)| hidden_characters::scan(path, content))
+        .take(hidden_characters::MAX_FINDINGS)
+        .map(SourceWarning::from)
+        .collect()
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct VerificationResult {
     pub file_name: String,
     pub contract_name: String,
+    /// Name of the contract prefixed with its source file name (`<file_name>:<contract_name>`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fully_qualified_name: Option<String>,
+    /// Fully qualified names of other compiled contracts matching the bytecode as well.
+    /// If not empty, the verified contract may be chosen wrongly, and `contract_name`
+    /// should be specified in the request.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other_matches: Vec<String>,
+    /// How the deployed bytecode matches the local compilation: "full" or "partial".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_type: Option<String>,
     pub compiler_version: String,
     pub evm_version: String,
     pub constructor_arguments: Option<DisplayBytes>,
@@ -43,20 +188,120 @@ pub struct VerificationResult {
     pub abi: Option<String>,
     pub sources: BTreeMap<String, String>,
     pub compiler_settings: String,
+    /// Standards the contract implements judging by its ABI.
+    #[serde(default)]
+    pub standards: Vec<Standard>,
+    /// Distinct licenses declared by SPDX identifiers of the sources, sorted.
+    /// Is empty for contracts stored before licenses have been detected.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub licenses: Vec<String>,
+    /// Warnings and info messages reported on local compilation.
+    #[serde(default)]
+    pub compiler_messages: Vec<CompilerMessage>,
+    /// Hex encoded keccak256 hash of the deployed code with the metadata stripped.
+    /// Identical deployments (e.g., factory-deployed clones) have the same hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_hash: Option<String>,
+    /// Deployed code size and the estimated deployment cost of the locally compiled contract.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract_size: Option<ContractSize>,
+    /// Is set if the contract is named as a well-known one (e.g., a popular token),
+    /// but its code differs from the canonical deployments (see `impersonation` settings).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonation_warning: Option<ImpersonationWarning>,
+    /// Hidden or lookalike characters found in the sources (the "Trojan Source" class),
+    /// which make the code read differently than it compiles.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_warnings: Vec<SourceWarning>,
 
+    /// Creation bytecode of the locally compiled contract (without constructor arguments).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_creation_bytecode: Option<DisplayBytes>,
+    /// Deployed bytecode of the locally compiled contract.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_deployed_bytecode: Option<DisplayBytes>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub local_creation_input_parts: Option<Vec<BytecodePart>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub local_deployed_bytecode_parts: Option<Vec<BytecodePart>>,
+
+    /// NatSpec developer documentation of the contract, as returned by the compiler.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub devdoc: Option<serde_json::Value>,
+    /// NatSpec user documentation of the contract, as returned by the compiler.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub userdoc: Option<serde_json::Value>,
+    /// Storage layout of the contract, as returned by the compiler.
+    /// Available for solidity contracts compiled with compiler v0.5.13 and later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_layout: Option<serde_json::Value>,
+    /// Contract level compiler outputs requested in addition to the default ones
+    /// (e.g., "ir", "evm.gasEstimates"), by their names in the output selection.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_outputs: BTreeMap<String, serde_json::Value>,
 }
 
 impl From<VerificationSuccess> for VerificationResult {
     fn from(verification_success: VerificationSuccess) -> Self {
+        // Documentation is compiled for solidity contracts only
+        let contract = verification_success
+            .compiler_output
+            .contracts
+            .get(&verification_success.file_path)
+            .and_then(|contracts| contracts.get(&verification_success.contract_name));
+        let devdoc = contract
+            .filter(|contract| contract.devdoc.kind.is_some())
+            .and_then(|contract| serde_json::to_value(&contract.devdoc).ok());
+        let userdoc = contract
+            .filter(|contract| contract.userdoc.kind.is_some())
+            .and_then(|contract| serde_json::to_value(&contract.userdoc).ok());
+        let storage_layout = contract
+            .filter(|_| verification_success.file_path.ends_with(".sol"))
+            .filter(|_| {
+                verification_success.compiler_version.version() >= &STORAGE_LAYOUT_MIN_VERSION
+            })
+            .and_then(|contract| serde_json::to_value(&contract.storage_layout).ok());
         let compiler_input = verification_success.compiler_input;
+        let extra_outputs = contract
+            .map(|contract| {
+                extra_outputs(
+                    &compiler_input.settings.output_selection,
+                    &verification_success.file_path,
+                    &verification_success.contract_name,
+                    contract,
+                )
+            })
+            .unwrap_or_default();
         let compiler_settings = serde_json::to_string(&compiler_input.settings).unwrap();
+        let contract_size = ContractSize::new(
+            &verification_success.creation_bytecode.0,
+            &verification_success.deployed_bytecode.0,
+            verification_success
+                .constructor_args
+                .as_ref()
+                .map(|args| args.0.as_ref())
+                .unwrap_or_default(),
+        );
+        let contract_size = match contract {
+            Some(contract) => contract_size.with_gas_estimates(contract),
+            None => contract_size,
+        };
+        let code_hash = verification_success
+            .code_hash
+            .map(|code_hash| format!("{code_hash:#x}"));
+        let sources: BTreeMap<_, _> = compiler_input
+            .sources
+            .into_iter()
+            .map(|(path, source)| (path.to_string_lossy().to_string(), source.content))
+            .collect();
+        let source_warnings = source_warnings(&sources);
+        let licenses = licenses::detect(&sources);
         VerificationResult {
             file_name: verification_success.file_path,
             contract_name: verification_success.contract_name,
+            fully_qualified_name: Some(verification_success.fully_qualified_name),
+            other_matches: verification_success.other_matches,
+            match_type: Some(verification_success.match_type.as_str().to_string()),
             compiler_version: verification_success.compiler_version.to_string(),
             evm_version: compiler_input
                 .settings
@@ -73,17 +318,34 @@ impl From<VerificationSuccess> for VerificationResult {
                 .into_iter()
                 .flat_map(|(_path, libs)| libs)
                 .collect(),
+            standards: verification_success
+                .abi
+                .as_ref()
+                .map(|abi| {
+                    standards::detect(abi, Some(verification_success.deployed_bytecode.0.as_ref()))
+                })
+                .unwrap_or_default(),
             abi: verification_success.abi.as_ref().map(|abi| {
                 serde_json::to_string(abi)
                     .expect("Is result of local compilation and, thus, should be always valid")
             }),
-            sources: compiler_input
-                .sources
+            licenses,
+            sources,
+            compiler_settings,
+            compiler_messages: verification_success
+                .compiler_output
+                .errors
                 .into_iter()
-                .map(|(path, source)| (path.to_string_lossy().to_string(), source.content))
+                .filter_map(CompilerMessage::new)
                 .collect(),
-            compiler_settings,
+            code_hash,
+            contract_size: Some(contract_size),
+            // Is set by the middlewares, as canonical deployments are configured there
+            impersonation_warning: None,
+            source_warnings,
 
+            local_creation_bytecode: Some(verification_success.creation_bytecode),
+            local_deployed_bytecode: Some(verification_success.deployed_bytecode),
             local_creation_input_parts: Some(
                 verification_success
                     .local_bytecode_parts
@@ -100,15 +362,65 @@ impl From<VerificationSuccess> for VerificationResult {
                     .map(|part| part.into())
                     .collect(),
             ),
+            devdoc,
+            userdoc,
+            storage_layout,
+            extra_outputs,
         }
     }
 }
 
+/// Contract level outputs which are either always returned in dedicated fields
+/// or are compiled internally and are not returned at all.
+const DEFAULT_OUTPUTS: [&str; 7] = [
+    "abi",
+    "evm.bytecode",
+    "evm.deployedBytecode",
+    "evm.methodIdentifiers",
+    "devdoc",
+    "userdoc",
+    "storageLayout",
+];
+
+/// Returns outputs selected for the contract in addition to the default ones.
+/// Output names are dot separated paths inside the contract output (e.g., "evm.gasEstimates").
+fn extra_outputs(
+    selection: &OutputSelection,
+    file_path: &str,
+    contract_name: &str,
+    contract: &Contract,
+) -> BTreeMap<String, serde_json::Value> {
+    let contract = match serde_json::to_value(contract) {
+        Ok(contract) => contract,
+        Err(_) => return BTreeMap::new(),
+    };
+    selection
+        .0
+        .iter()
+        .filter(|(file, _)| *file == "*" || *file == file_path)
+        .flat_map(|(_, contracts)| contracts.iter())
+        .filter(|(contract, _)| *contract == "*" || *contract == contract_name)
+        .flat_map(|(_, outputs)| outputs)
+        .filter(|output| !DEFAULT_OUTPUTS.contains(&output.as_str()))
+        .filter_map(|output| {
+            let pointer = format!("/{}", output.replace('.', "/"));
+            let value = contract.pointer(&pointer)?;
+            Some((output.clone(), value.clone()))
+        })
+        .collect()
+}
+
 impl From<SourcifySuccess> for VerificationResult {
     fn from(sourcify_success: SourcifySuccess) -> Self {
         Self {
+            fully_qualified_name: Some(format!(
+                "{}:{}",
+                sourcify_success.file_name, sourcify_success.contract_name
+            )),
             file_name: sourcify_success.file_name,
             contract_name: sourcify_success.contract_name,
+            other_matches: vec![],
+            match_type: Some(sourcify_success.match_type.as_str().to_string()),
             compiler_version: sourcify_success.compiler_version,
             evm_version: sourcify_success.evm_version,
             constructor_arguments: sourcify_success
@@ -117,13 +429,28 @@ impl From<SourcifySuccess> for VerificationResult {
             optimization: sourcify_success.optimization,
             optimization_runs: sourcify_success.optimization_runs,
             contract_libraries: sourcify_success.contract_libraries,
+            standards: standards::detect_from_json(&sourcify_success.abi),
             abi: Some(sourcify_success.abi),
+            source_warnings: source_warnings(&sourcify_success.sources),
+            licenses: licenses::detect(&sourcify_success.sources),
             sources: sourcify_success.sources,
             compiler_settings: sourcify_success.compiler_settings,
+            // Compilation is performed by Sourcify, which does not return compiler messages
+            compiler_messages: vec![],
+            // Sourcify does not return the deployed code
+            code_hash: None,
+            contract_size: None,
+            impersonation_warning: None,
 
             // We have no notion of bytecode parts for Sourcify verification
+            local_creation_bytecode: None,
+            local_deployed_bytecode: None,
             local_creation_input_parts: None,
             local_deployed_bytecode_parts: None,
+            devdoc: None,
+            userdoc: None,
+            storage_layout: None,
+            extra_outputs: BTreeMap::new(),
         }
     }
 }
@@ -142,6 +469,8 @@ impl VerificationResponse {
             message: "OK".to_string(),
             result: Some(result),
             status: VerificationStatus::Ok,
+            diagnostics: None,
+            injected_sources: vec![],
         }
     }
 
@@ -150,14 +479,27 @@ impl VerificationResponse {
             message: message.to_string(),
             result: None,
             status: VerificationStatus::Failed,
+            diagnostics: None,
+            injected_sources: vec![],
         }
     }
+
+    pub fn with_injected_sources(mut self, injected_sources: Vec<String>) -> Self {
+        self.injected_sources = injected_sources;
+        self
+    }
+
+    pub fn with_diagnostics(mut self, diagnostics: Vec<ContractDiagnostics>) -> Self {
+        self.diagnostics = Some(diagnostics.into_iter().map(Diagnostics::from).collect());
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::tests::parse::test_serialize_json_ok;
+    use pretty_assertions::assert_eq;
     use serde_json::json;
     use std::str::FromStr;
 
@@ -168,6 +510,9 @@ mod tests {
                 VerificationResponse::ok(VerificationResult {
                     file_name: "File.sol".to_string(),
                     contract_name: "contract_name".to_string(),
+                    fully_qualified_name: None,
+                    other_matches: vec![],
+                    match_type: None,
                     compiler_version: "compiler_version".to_string(),
                     evm_version: "evm_version".to_string(),
                     constructor_arguments: Some(DisplayBytes::from([0xca, 0xfe])),
@@ -185,6 +530,19 @@ mod tests {
                     )
                     .unwrap(),
                     compiler_settings: "compiler_settings".into(),
+                    standards: vec![Standard::Erc20],
+                    licenses: vec![],
+                    compiler_messages: vec![CompilerMessage {
+                        severity: CompilerMessageSeverity::Warning,
+                        error_code: Some("1878".into()),
+                        message: "Warning: SPDX license identifier not provided".into(),
+                    }],
+                    code_hash: None,
+                    contract_size: None,
+                    impersonation_warning: None,
+                    source_warnings: vec![],
+                    local_creation_bytecode: None,
+                    local_deployed_bytecode: None,
                     local_creation_input_parts: Some(vec![
                         BytecodePart::Main {
                             data: DisplayBytes::from_str("0x1234").unwrap(),
@@ -194,6 +552,10 @@ mod tests {
                         },
                     ]),
                     local_deployed_bytecode_parts: Some(vec![]),
+                    devdoc: None,
+                    userdoc: None,
+                    storage_layout: None,
+                    extra_outputs: BTreeMap::new(),
                 }),
                 json!({
                     "message": "OK",
@@ -211,6 +573,12 @@ mod tests {
                         "optimization_runs": 200,
                         "abi": "abi",
                         "compiler_settings": "compiler_settings",
+                        "standards": ["erc20"],
+                        "compiler_messages": [{
+                            "severity": "warning",
+                            "error_code": "1878",
+                            "message": "Warning: SPDX license identifier not provided",
+                        }],
                         "sources": {
                             "source.sol": "content",
                         },
@@ -230,6 +598,77 @@ mod tests {
                     "result": null,
                 }),
             ),
+            (
+                VerificationResponse::err("No matching contracts").with_diagnostics(vec![
+                    ContractDiagnostics {
+                        file_path: "A.sol".into(),
+                        contract_name: Some("A".into()),
+                        reason: "bytecode does not match compilation output".into(),
+                        first_diverging_offset: Some(3),
+                        metadata_hash_only: false,
+                        length_differs: false,
+                        hints: vec![DiagnosticsHint::OptimizerSettings],
+                    },
+                ]),
+                json!({
+                    "message": "No matching contracts",
+                    "status": "1",
+                    "result": null,
+                    "diagnostics": [{
+                        "file_name": "A.sol",
+                        "contract_name": "A",
+                        "reason": "bytecode does not match compilation output",
+                        "first_diverging_offset": 3,
+                        "metadata_hash_only": false,
+                        "length_differs": false,
+                        "hints": ["optimizer_settings"],
+                    }],
+                }),
+            ),
         ])
     }
+
+    #[test]
+    fn extra_outputs_are_extracted() {
+        let contract: Contract = serde_json::from_value(json!({
+            "abi": [],
+            "ir": "object \"A_1\" {}",
+            "evm": {
+                "methodIdentifiers": {},
+                "gasEstimates": {
+                    "creation": {
+                        "codeDepositCost": "infinite",
+                        "executionCost": "infinite",
+                        "totalCost": "infinite"
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let selection: OutputSelection = serde_json::from_value(json!({
+            "*": { "*": ["abi", "evm.methodIdentifiers", "ir"] },
+            "A.sol": { "A": ["evm.gasEstimates", "evm.assembly"] },
+            "B.sol": { "*": ["irOptimized"] }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            BTreeMap::from([
+                ("ir".to_string(), json!("object \"A_1\" {}")),
+                (
+                    "evm.gasEstimates".to_string(),
+                    json!({
+                        "creation": {
+                            "codeDepositCost": "infinite",
+                            "executionCost": "infinite",
+                            "totalCost": "infinite"
+                        },
+                        "external": {},
+                        "internal": {}
+                    })
+                ),
+            ]),
+            extra_outputs(&selection, "A.sol", "A", &contract)
+        );
+    }
 }
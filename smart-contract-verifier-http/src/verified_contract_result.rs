@@ -1,9 +1,115 @@
 use serde::{Deserialize, Serialize};
-use crate::verification_response::VerificationResult;
+use chrono::{DateTime, TimeZone, Utc};
+use crate::{caching, compression::Compressed, token_info::TokenInfo, verification_response::VerificationResult};
+use smart_contract_verifier::rpc::CreationTransaction;
 
 // struct to store contract verified result with contract address
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Verified_Contract_Result {
     pub contract_address: String,
-    pub result: VerificationResult
-}
\ No newline at end of file
+    pub result: VerificationResult,
+    /// If set, sources are not stored with this result, but shared with the result
+    /// of the given contract which has the same code and sources (see `DB::add_contract_verify_response`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sources_of: Option<String>,
+    /// If set, sources are not stored with this result, but kept in the blob store
+    /// under the given key (see `DB::add_contract_verify_response`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sources_blob: Option<String>,
+    /// If set, sources are not stored with this result, but compressed into this field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compressed_sources: Option<Compressed>,
+    /// Git repository the sources have been imported from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<RepositorySource>,
+    /// Hash of the result used as an ETag of read endpoints. Is set when the result is stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Distinct SPDX identifiers of `result.licenses` recent contracts are filtered by, so that
+    /// license expressions are matched by any of their licenses. Is set when the result is stored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub license_ids: Vec<String>,
+    /// Is missing for results stored before verification times were introduced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified_at: Option<DateTime<Utc>>,
+    /// Verification job the result has been stored by, if submitted asynchronously.
+    /// Allows retried jobs to tell whether the result has already been stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    /// Transaction the contract has been created by, if it could be located when the result was stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation: Option<ContractCreation>,
+    /// Name, symbol and other metadata read from chain, if the contract is an ERC-20 or ERC-721 token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_info: Option<TokenInfo>,
+}
+
+impl Verified_Contract_Result {
+    /// Hash of the stored content; computed for results stored before hashes were introduced.
+    pub fn content_hash(&self) -> String {
+        self.content_hash
+            .clone()
+            .unwrap_or_else(|| caching::content_hash(&self.result))
+    }
+
+    /// Drops the fields describing how the result is stored, so that they are not returned by lookups.
+    pub fn without_storage_details(self) -> Self {
+        Self {
+            sources_of: None,
+            sources_blob: None,
+            compressed_sources: None,
+            content_hash: None,
+            license_ids: vec![],
+            ..self
+        }
+    }
+}
+
+// struct to record the transaction and the block the contract has been deployed at
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ContractCreation {
+    pub tx_hash: String,
+    pub deployer: String,
+    pub block_number: u64,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ContractCreation {
+    /// Returns `None` if the block timestamp is out of the supported range.
+    pub fn from_transaction(tx: CreationTransaction) -> Option<Self> {
+        let timestamp = Utc.timestamp_opt(i64::try_from(tx.timestamp).ok()?, 0).single()?;
+        Some(Self {
+            tx_hash: tx.tx_hash.to_lowercase(),
+            deployer: tx.deployer.to_lowercase(),
+            block_number: tx.block_number,
+            timestamp,
+        })
+    }
+}
+
+// struct to record the git repository and commit verified sources have been taken from
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RepositorySource {
+    pub url: String,
+    pub commit: String,
+    /// Path of the verified contract file inside of the repository.
+    pub contract_path: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminActionKind {
+    /// Stored verification has been removed
+    Removed,
+    /// Stored verification has been replaced by a new one
+    Replaced,
+}
+
+// struct to record who removed or replaced stored verification results
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AdminAction {
+    pub contract_address: String,
+    pub action: AdminActionKind,
+    pub actor: String,
+    pub performed_at: DateTime<Utc>,
+}
@@ -0,0 +1,106 @@
+use crate::{chains::Chain, settings::WarmUpSettings, DB};
+use futures::StreamExt;
+use smart_contract_verifier::{SolidityClient, Version};
+use std::{
+    collections::BTreeSet,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Max number of compilers downloaded simultaneously during the warm-up.
+const WARM_UP_CONCURRENCY: usize = 4;
+
+/// Indicates whether the service is ready to serve requests without additional delays.
+#[derive(Clone, Debug)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Self(Arc::new(AtomicBool::new(true)))
+    }
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    fn set_ready(&self, ready: bool) {
+        self.0.store(ready, Ordering::Release)
+    }
+}
+
+/// Downloads configured and the most used solidity compilers in the background.
+/// The service is not ready until the warm-up completes.
+pub fn spawn_warm_up(
+    client: Arc<SolidityClient>,
+    settings: WarmUpSettings,
+    readiness: Readiness,
+    chain: Chain,
+) -> anyhow::Result<()> {
+    let configured = parse_versions(&settings.versions)?;
+    if configured.is_empty() && settings.most_used == 0 {
+        return Ok(());
+    }
+
+    readiness.set_ready(false);
+    tokio::spawn(async move {
+        let mut versions = configured;
+        if settings.most_used > 0 {
+            versions.extend(most_used_versions(&chain, settings.most_used).await);
+        }
+        warm_up(&client, versions).await;
+        readiness.set_ready(true);
+    });
+    Ok(())
+}
+
+async fn warm_up(client: &SolidityClient, versions: BTreeSet<Version>) {
+    tracing::info!("warming up {} compiler versions", versions.len());
+    futures::stream::iter(versions)
+        .for_each_concurrent(WARM_UP_CONCURRENCY, |version| async move {
+            if let Err(err) = client.compilers().fetch(&version).await {
+                tracing::warn!("cannot warm up compiler {}: {}", version, err);
+            }
+        })
+        .await;
+    tracing::info!("compilers warm-up finished");
+}
+
+async fn most_used_versions(chain: &Chain, limit: usize) -> BTreeSet<Version> {
+    let database = DB::new().await.change_name(&chain.namespace());
+    match database.most_used_compiler_versions(limit as i64).await {
+        Ok(versions) => versions
+            .iter()
+            .filter_map(|version| Version::from_str(version).ok())
+            .collect(),
+        Err(err) => {
+            tracing::error!("cannot load the most used compiler versions: {}", err);
+            BTreeSet::new()
+        }
+    }
+}
+
+fn parse_versions(versions: &[String]) -> anyhow::Result<BTreeSet<Version>> {
+    versions
+        .iter()
+        .map(|version| {
+            Version::from_str(version)
+                .map_err(|err| anyhow::anyhow!("invalid warm-up compiler version {version}: {err}"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_versions_fail() {
+        parse_versions(&["v0.8.17+commit.8df45f5f".to_string()]).expect("version is valid");
+        parse_versions(&["v0.8.17".to_string()]).expect_err("commit hash is missing");
+    }
+}
@@ -92,7 +92,8 @@ async fn test_setup(dir: &str, input: &mut TestInput) -> (ServiceResponse, Optio
             "sources": BTreeMap::from([(contract_path, input.source_code.as_ref().unwrap())]),
             "evm_version": input.evm_version,
             "contract_libraries": input.contract_libraries,
-            "optimization_runs": optimization_runs
+            "optimization_runs": optimization_runs,
+            "match_against": input.match_against
         })
     } else {
         json!({
@@ -101,7 +102,8 @@ async fn test_setup(dir: &str, input: &mut TestInput) -> (ServiceResponse, Optio
             "compiler_version": input.compiler_version,
             "sources": BTreeMap::from([(contract_path, input.source_code.as_ref().unwrap())]),
             "evm_version": input.evm_version,
-            "contract_libraries": input.contract_libraries
+            "contract_libraries": input.contract_libraries,
+            "match_against": input.match_against
         })
     };
 
@@ -408,6 +410,63 @@ mod failure_tests {
     }
 }
 
+mod match_against_tests {
+    use super::*;
+
+    #[actix_rt::test]
+    async fn verifies_against_both_bytecodes() {
+        let contract_dir = "simple_storage";
+        let test_input =
+            TestInput::new("SimpleStorage", "v0.4.24+commit.e67f0147").with_match_against("both");
+        test_success(contract_dir, test_input).await;
+    }
+
+    #[actix_rt::test]
+    async fn both_bytecodes_are_required_to_match() {
+        let contract_dir = "simple_storage";
+        // The creation transaction input matches, but the deployed bytecode is of another contract
+        let deployed_bytecode =
+            fs::read_to_string(format!("{CONTRACTS_DIR}/solidity_0.5.14/deployed_bytecode"))
+                .expect("Error while reading deployed_bytecode");
+        let test_input = TestInput::new("SimpleStorage", "v0.4.24+commit.e67f0147")
+            .with_deployed_bytecode(deployed_bytecode.clone())
+            .with_match_against("both");
+        test_failure(
+            contract_dir,
+            test_input,
+            "No contract could be verified with provided data",
+        )
+        .await;
+
+        // The creation transaction input alone is matched otherwise
+        let test_input = TestInput::new("SimpleStorage", "v0.4.24+commit.e67f0147")
+            .with_deployed_bytecode(deployed_bytecode)
+            .with_match_against("creation");
+        test_success(contract_dir, test_input).await;
+    }
+
+    #[actix_rt::test]
+    async fn deployed_bytecode_is_matched_only() {
+        let contract_dir = "simple_storage";
+        // The creation transaction input is ignored even if it does not match
+        let test_input = TestInput::new("SimpleStorage", "v0.4.24+commit.e67f0147")
+            .with_creation_tx_input("0x6080".to_string())
+            .with_match_against("deployed");
+        test_success(contract_dir, test_input).await;
+    }
+
+    #[actix_rt::test]
+    async fn creation_bytecode_is_required_to_match_against_it() {
+        let contract_dir = "simple_storage";
+        for match_against in ["creation", "both"] {
+            let test_input = TestInput::new("SimpleStorage", "v0.4.24+commit.e67f0147")
+                .ignore_creation_tx_input()
+                .with_match_against(match_against);
+            test_error(contract_dir, test_input, StatusCode::BAD_REQUEST, None).await;
+        }
+    }
+}
+
 mod bad_request_error_tests {
     use super::*;
 
@@ -12,6 +12,8 @@ pub struct TestInput {
     pub has_constructor_args: bool,
     pub is_yul: bool,
     pub ignore_creation_tx_input: bool,
+    /// If None, the default (`auto`) is used
+    pub match_against: Option<&'static str>,
 
     /// If None, the input would be read from the corresponding file
     pub source_code: Option<String>,
@@ -35,6 +37,7 @@ impl TestInput {
             has_constructor_args: false,
             is_yul: false,
             ignore_creation_tx_input: false,
+            match_against: None,
 
             source_code: None,
             creation_tx_input: None,
@@ -74,6 +77,11 @@ impl TestInput {
         self
     }
 
+    pub fn with_match_against(mut self, match_against: &'static str) -> Self {
+        self.match_against = Some(match_against);
+        self
+    }
+
     pub fn with_source_code(mut self, source_code: String) -> Self {
         self.source_code = Some(source_code);
         self
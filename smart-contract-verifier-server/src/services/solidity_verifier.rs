@@ -100,14 +100,17 @@ impl SolidityVerifier for SolidityVerifierService {
         let err = result.unwrap_err();
         match err {
             VerificationError::Compilation(_)
-            | VerificationError::NoMatchingContracts
+            | VerificationError::NoMatchingContracts(_)
             | VerificationError::CompilerVersionMismatch(_) => {
                 Ok(Response::new(VerifyResponseWrapper::err(err).into_inner()))
             }
-            VerificationError::Initialization(_) | VerificationError::VersionNotFound(_) => {
+            VerificationError::Initialization(_)
+            | VerificationError::VersionNotFound(_)
+            | VerificationError::HiddenCharacters(_) => {
                 Err(Status::invalid_argument(err.to_string()))
             }
             VerificationError::Internal(_) => Err(Status::internal(err.to_string())),
+            VerificationError::ContractNotFound(_) => Err(Status::not_found(err.to_string())),
         }
     }
 
@@ -146,14 +149,17 @@ impl SolidityVerifier for SolidityVerifierService {
         let err = result.unwrap_err();
         match err {
             VerificationError::Compilation(_)
-            | VerificationError::NoMatchingContracts
+            | VerificationError::NoMatchingContracts(_)
             | VerificationError::CompilerVersionMismatch(_) => {
                 Ok(Response::new(VerifyResponseWrapper::err(err).into_inner()))
             }
-            VerificationError::Initialization(_) | VerificationError::VersionNotFound(_) => {
+            VerificationError::Initialization(_)
+            | VerificationError::VersionNotFound(_)
+            | VerificationError::HiddenCharacters(_) => {
                 Err(Status::invalid_argument(err.to_string()))
             }
             VerificationError::Internal(_) => Err(Status::internal(err.to_string())),
+            VerificationError::ContractNotFound(_) => Err(Status::not_found(err.to_string())),
         }
     }
 
@@ -78,11 +78,14 @@ impl TryFrom<VerifySolidityMultiPartRequestWrapper> for VerificationRequest {
             deployed_bytecode,
             creation_bytecode,
             compiler_version,
+            contract_name: None,
             content: MultiFileContent {
                 sources,
                 evm_version,
                 optimization_runs: request.optimization_runs.map(|i| i as usize),
                 contract_libraries: Some(request.libraries.into_iter().collect()),
+                remappings: vec![],
+                extra_outputs: vec![],
             },
         })
     }
@@ -111,11 +114,14 @@ mod tests {
             creation_bytecode: Some(DisplayBytes::from_str("0x1234").unwrap().0),
             deployed_bytecode: DisplayBytes::from_str("").unwrap().0,
             compiler_version: Version::from_str("v0.8.17+commit.8df45f5f").unwrap(),
+            contract_name: None,
             content: MultiFileContent {
                 sources: BTreeMap::from([("source_path".into(), "source_content".into())]),
                 evm_version: Some(EvmVersion::London),
                 optimization_runs: Some(200),
                 contract_libraries: Some(BTreeMap::from([("Lib".into(), "0xcafe".into())])),
+                remappings: vec![],
+                extra_outputs: vec![],
             },
         };
 
@@ -70,6 +70,7 @@ impl TryFrom<VerifySolidityStandardJsonRequestWrapper> for VerificationRequest {
             deployed_bytecode,
             creation_bytecode,
             compiler_version,
+            contract_name: None,
             content: StandardJsonContent { input },
         })
     }
@@ -101,6 +102,7 @@ mod tests {
             creation_bytecode: Some(DisplayBytes::from_str("0x1234").unwrap().0),
             deployed_bytecode: DisplayBytes::from_str("").unwrap().0,
             compiler_version: Version::from_str("v0.8.17+commit.8df45f5f").unwrap(),
+            contract_name: None,
             content: StandardJsonContent { input },
         };
 
@@ -103,9 +103,14 @@ mod tests {
             compiler_version: Version::from_str("v0.8.17+commit.8df45f5f").unwrap(),
             file_path: "file_name".to_string(),
             contract_name: "contract_name".to_string(),
+            fully_qualified_name: "file_name:contract_name".to_string(),
             abi: Some(Default::default()),
             constructor_args: Some(DisplayBytes::from_str("0x123456").unwrap()),
+            creation_bytecode: Default::default(),
+            deployed_bytecode: Default::default(),
+            other_matches: vec![],
             local_bytecode_parts: Default::default(),
+            code_hash: None,
             match_type: MatchType::Partial,
         };
 
@@ -1,7 +1,7 @@
 use crate::proto::VerifySourcifyRequest;
 use serde::{Deserialize, Serialize};
-use smart_contract_verifier::sourcify::api::VerificationRequest;
-use std::ops::Deref;
+use smart_contract_verifier::{sourcify::api::VerificationRequest, Address};
+use std::{ops::Deref, str::FromStr};
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct VerifySourcifyRequestWrapper(VerifySourcifyRequest);
@@ -35,8 +35,10 @@ impl TryFrom<VerifySourcifyRequestWrapper> for VerificationRequest {
 
     fn try_from(request: VerifySourcifyRequestWrapper) -> Result<Self, Self::Error> {
         let request = request.into_inner();
+        let address = Address::from_str(&request.address)
+            .map_err(|err| tonic::Status::invalid_argument(format!("Invalid address: {err}")))?;
         Ok(Self {
-            address: request.address,
+            address,
             chain: request.chain,
             files: request.files,
             chosen_contract: request.chosen_contract.map(|i| i as usize),
@@ -53,7 +55,7 @@ mod tests {
     #[test]
     fn try_into_verification_request() {
         let request = VerifySourcifyRequest {
-            address: "0x0123456789abcdef".to_string(),
+            address: "0xcafecafecafecafecafecafecafecafecafecafe".to_string(),
             chain: "77".to_string(),
             files: BTreeMap::from([("metadata".into(), "metadata_content".into())]),
             chosen_contract: Some(2),
@@ -65,7 +67,9 @@ mod tests {
                 .expect("Try_into verification request failed");
 
         let expected = VerificationRequest {
-            address: "0x0123456789abcdef".to_string(),
+            address: "0xcafecafecafecafecafecafecafecafecafecafe"
+                .parse()
+                .unwrap(),
             chain: "77".to_string(),
             files: BTreeMap::from([("metadata".into(), "metadata_content".into())]),
             chosen_contract: Some(2),
@@ -80,6 +80,7 @@ impl TryFrom<VerifyVyperMultiPartRequestWrapper> for VerificationRequest {
             deployed_bytecode,
             creation_bytecode,
             compiler_version,
+            contract_name: None,
             content: MultiFileContent {
                 sources,
                 evm_version,
@@ -113,6 +114,7 @@ mod tests {
             creation_bytecode: Some(DisplayBytes::from_str("0x1234").unwrap().0),
             deployed_bytecode: DisplayBytes::from_str("").unwrap().0,
             compiler_version: Version::from_str("0.3.7+commit.6020b8bb").unwrap(),
+            contract_name: None,
             content: MultiFileContent {
                 sources: BTreeMap::from([("source_path".into(), "source_content".into())]),
                 evm_version: Some(EvmVersion::Byzantium),
@@ -1,3 +1,11 @@
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Deref,
+    str::FromStr,
+};
+use thiserror::Error;
+
 /// The enum representing how provided bytecode corresponds
 /// to the local result of source codes compilation.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -5,3 +13,195 @@ pub enum MatchType {
     Partial,
     Full,
 }
+
+impl MatchType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchType::Partial => "partial",
+            MatchType::Full => "full",
+        }
+    }
+}
+
+/// Bytecode the compiled contract is required to match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, DeserializeFromStr, SerializeDisplay)]
+pub enum MatchAgainst {
+    /// The creation transaction input if provided, the deployed bytecode otherwise.
+    #[default]
+    Auto,
+    /// The deployed bytecode only, even if the creation transaction input is provided
+    /// (e.g., if constructor arguments cannot be matched).
+    Deployed,
+    /// The creation transaction input, which is required to be provided.
+    Creation,
+    /// Both the creation transaction input and the deployed bytecode.
+    Both,
+}
+
+impl FromStr for MatchAgainst {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "deployed" => Ok(Self::Deployed),
+            "creation" => Ok(Self::Creation),
+            "both" => Ok(Self::Both),
+            _ => Err(anyhow::anyhow!(
+                "expected one of \"deployed\", \"creation\" or \"both\", got \"{s}\""
+            )),
+        }
+    }
+}
+
+impl Display for MatchAgainst {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            Self::Auto => "auto",
+            Self::Deployed => "deployed",
+            Self::Creation => "creation",
+            Self::Both => "both",
+        };
+        f.write_str(value)
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("address must be 20 bytes (40 hex characters) long, got {0} characters")]
+    InvalidLength(usize),
+    #[error("address is not a valid hex string")]
+    InvalidHex,
+    #[error("address has invalid EIP-55 checksum (expected {0})")]
+    InvalidChecksum(String),
+}
+
+/// Account address, `0x` prefixed and normalized to lowercase.
+///
+/// Mixed-case addresses are expected to follow EIP-55 checksum
+/// and are rejected if it does not match.
+///
+/// Addresses are parsed where they enter the service (requests, responses of nodes
+/// and Sourcify, imported records). Stored documents and the responses built from them
+/// keep the normalized string, so that it could be used in database queries as is.
+#[derive(
+    Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, DeserializeFromStr, SerializeDisplay,
+)]
+pub struct Address(String);
+
+impl Address {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the EIP-55 checksummed representation of the address.
+    pub fn to_checksum(&self) -> String {
+        let address = ethers_core::types::Address::from_str(&self.0)
+            .expect("address is validated on creation");
+        ethers_core::utils::to_checksum(&address, None)
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix("0x").unwrap_or(s);
+        if hex.len() != 40 {
+            return Err(AddressError::InvalidLength(hex.len()));
+        }
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(AddressError::InvalidHex);
+        }
+
+        let address = Self(format!("0x{}", hex.to_lowercase()));
+        // Addresses in a single case carry no checksum
+        let is_mixed_case = hex.chars().any(|c| c.is_ascii_lowercase())
+            && hex.chars().any(|c| c.is_ascii_uppercase());
+        if is_mixed_case {
+            let checksummed = address.to_checksum();
+            if checksummed[2..] != *hex {
+                return Err(AddressError::InvalidChecksum(checksummed));
+            }
+        }
+        Ok(address)
+    }
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Deref for Address {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Address {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_address() {
+        let address = Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")
+            .expect("checksum is valid");
+        assert_eq!(
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+            address.as_str()
+        );
+        assert_eq!(
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            address.to_checksum()
+        );
+
+        Address::from_str("0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED")
+            .expect("single case addresses are not checksummed");
+        Address::from_str("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").expect("prefix is optional");
+    }
+
+    #[test]
+    fn invalid_address() {
+        assert_eq!(
+            Err(AddressError::InvalidChecksum(
+                "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".into()
+            )),
+            Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD")
+        );
+        assert_eq!(
+            Err(AddressError::InvalidLength(4)),
+            Address::from_str("0x1234")
+        );
+        assert_eq!(
+            Err(AddressError::InvalidHex),
+            Address::from_str("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaeg")
+        );
+    }
+
+    #[test]
+    fn match_against_round_trips() {
+        for match_against in [
+            MatchAgainst::Auto,
+            MatchAgainst::Deployed,
+            MatchAgainst::Creation,
+            MatchAgainst::Both,
+        ] {
+            assert_eq!(
+                match_against,
+                MatchAgainst::from_str(&match_against.to_string()).unwrap()
+            );
+        }
+        MatchAgainst::from_str("runtime").expect_err("unknown bytecode");
+    }
+}
@@ -0,0 +1,154 @@
+use super::{fetcher::FetchError, version::Version};
+use crate::metrics;
+use mismatch::Mismatch;
+use primitive_types::H256;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Allowlist of compiler binary checksums.
+///
+/// The checksum of a binary is pinned the first time the binary is seen
+/// (or in advance, via the allowlist file), and binaries whose checksums
+/// differ from the pinned ones are refused to be executed.
+#[derive(Default)]
+pub struct ChecksumAllowlist {
+    file: Option<PathBuf>,
+    pins: parking_lot::RwLock<BTreeMap<Version, H256>>,
+}
+
+impl ChecksumAllowlist {
+    /// Creates an allowlist, which keeps pinned checksums in memory only.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Loads pinned checksums from the json file (mapping versions into sha256 hashes).
+    /// Newly pinned checksums are persisted into the same file.
+    /// Missing file is treated as an empty allowlist.
+    pub fn load(file: PathBuf) -> anyhow::Result<Self> {
+        let pins = match std::fs::read(&file) {
+            Ok(content) => parse_pins(&content)
+                .map_err(|err| anyhow::anyhow!("invalid allowlist {}: {}", file.display(), err))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            file: Some(file),
+            pins: parking_lot::RwLock::new(pins),
+        })
+    }
+
+    pub fn get(&self, ver: &Version) -> Option<H256> {
+        self.pins.read().get(ver).cloned()
+    }
+
+    /// Checks the binary against the pinned checksum, pinning the checksum of the binary
+    /// if there is no one yet. Returns the checksum of the binary on success.
+    pub async fn check(&self, ver: &Version, path: &Path) -> Result<H256, FetchError> {
+        let data = tokio::fs::read(path).await?;
+        let found =
+            tokio::task::spawn_blocking(move || H256::from_slice(&Sha256::digest(data))).await?;
+
+        let (pinned, is_new) = {
+            let mut pins = self.pins.write();
+            match pins.get(ver) {
+                Some(pinned) => (*pinned, false),
+                None => {
+                    pins.insert(ver.clone(), found);
+                    (found, true)
+                }
+            }
+        };
+        if pinned != found {
+            metrics::COMPILER_CHECKSUM_MISMATCHES.inc();
+            tracing::error!(
+                "checksum of compiler {} at {:?} differs from the pinned one",
+                ver,
+                path
+            );
+            return Err(FetchError::ChecksumChanged(Mismatch::new(pinned, found)));
+        }
+        if is_new {
+            tracing::info!("pinned checksum {:#x} for compiler {}", found, ver);
+            self.persist().await?;
+        }
+        Ok(found)
+    }
+
+    async fn persist(&self) -> std::io::Result<()> {
+        let file = match &self.file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        let content = {
+            let pins: BTreeMap<_, _> = self
+                .pins
+                .read()
+                .iter()
+                .map(|(ver, sha256)| (ver.to_string(), *sha256))
+                .collect();
+            serde_json::to_vec_pretty(&pins).expect("serialization of checksums cannot fail")
+        };
+        let mut file_tmp = file.clone();
+        file_tmp.set_extension("tmp");
+        tokio::fs::write(&file_tmp, content).await?;
+        tokio::fs::rename(&file_tmp, file).await
+    }
+}
+
+fn parse_pins(content: &[u8]) -> anyhow::Result<BTreeMap<Version, H256>> {
+    let pins: BTreeMap<String, H256> = serde_json::from_slice(content)?;
+    pins.into_iter()
+        .map(|(ver, sha256)| Ok((Version::from_str(&ver)?, sha256)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn version() -> Version {
+        Version::from_str("v0.8.17+commit.8df45f5f").unwrap()
+    }
+
+    #[tokio::test]
+    async fn pins_checksum_on_first_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("solc");
+        let allowlist_file = dir.path().join("checksums.json");
+        std::fs::write(&binary, "this is a compiler binary").unwrap();
+
+        let allowlist = ChecksumAllowlist::load(allowlist_file.clone()).unwrap();
+        let sha256 = allowlist.check(&version(), &binary).await.unwrap();
+        assert_eq!(Some(sha256), allowlist.get(&version()));
+
+        let reloaded = ChecksumAllowlist::load(allowlist_file).unwrap();
+        assert_eq!(
+            Some(sha256),
+            reloaded.get(&version()),
+            "pinned checksum must be persisted"
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_changed_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("solc");
+        std::fs::write(&binary, "this is a compiler binary").unwrap();
+
+        let allowlist = ChecksumAllowlist::new();
+        allowlist.check(&version(), &binary).await.unwrap();
+
+        std::fs::write(&binary, "this is a replaced binary").unwrap();
+        let err = allowlist
+            .check(&version(), &binary)
+            .await
+            .expect_err("binary has been changed");
+        assert!(matches!(err, FetchError::ChecksumChanged(_)), "{err:?}");
+    }
+}
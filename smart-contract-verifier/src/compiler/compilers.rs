@@ -1,14 +1,17 @@
 use super::{
-    download_cache::DownloadCache,
+    checksums::ChecksumAllowlist,
+    download_cache::{CachedCompiler, CompilerLease, DownloadCache, EvictionError, EvictionPolicy},
     fetcher::{FetchError, Fetcher},
     version::Version,
 };
 use crate::metrics::{self, GuardedGauge};
 use ethers_solc::{artifacts::Severity, error::SolcError, CompilerInput, CompilerOutput};
 use std::{
+    collections::HashSet,
     fmt::Debug,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 use thiserror::Error;
 use tokio::sync::{AcquireError, Semaphore};
@@ -26,6 +29,8 @@ pub enum Error {
     Compilation(Vec<String>),
     #[error("failed to acquire lock: {0}")]
     Acquire(#[from] AcquireError),
+    #[error("Compilation has not finished in {} seconds", .0.as_secs())]
+    Timeout(Duration),
 }
 
 #[async_trait::async_trait]
@@ -43,6 +48,8 @@ pub struct Compilers<C> {
     fetcher: Arc<dyn Fetcher>,
     evm_compiler: C,
     threads_semaphore: Arc<Semaphore>,
+    allow_nightly: bool,
+    timeout: Option<Duration>,
 }
 
 impl<C> Compilers<C>
@@ -59,23 +66,79 @@ where
             fetcher,
             evm_compiler,
             threads_semaphore,
+            allow_nightly: true,
+            timeout: None,
         }
     }
+
+    /// Specifies whether nightly compiler builds may be used. If not, nightly
+    /// versions are excluded from the list of available versions, and are treated as not found.
+    pub fn with_nightly_versions(mut self, allow_nightly: bool) -> Self {
+        self.allow_nightly = allow_nightly;
+        self
+    }
+
+    /// Pins checksums of compiler binaries in the given allowlist. Binaries whose
+    /// checksums differ from the pinned ones are refused to be executed.
+    /// Should be called before compilers are loaded from the directory.
+    pub fn with_checksum_allowlist(mut self, checksums: ChecksumAllowlist) -> Self {
+        self.cache = self.cache.with_allowlist(checksums);
+        self
+    }
+
+    /// Limits the disk space occupied by downloaded compilers.
+    pub fn with_eviction_policy(mut self, eviction_policy: EvictionPolicy) -> Self {
+        self.cache = self.cache.with_eviction_policy(eviction_policy);
+        self
+    }
+
+    /// Limits the time a single compilation may take, not counting the time
+    /// spent downloading the compiler and waiting for a free thread. Is not limited if `None`.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Downloads the compiler, if it has not been downloaded yet,
+    /// and returns the path to its binary.
+    pub async fn fetch(&self, compiler_version: &Version) -> Result<PathBuf, Error> {
+        if !self.allow_nightly && !compiler_version.is_release() {
+            return Err(Error::VersionNotFound(compiler_version.clone()));
+        }
+        let path_result = {
+            self.cache
+                .get(self.fetcher.as_ref(), compiler_version)
+                .await
+        };
+        match path_result {
+            Err(FetchError::NotFound(version)) => Err(Error::VersionNotFound(version)),
+            res => Ok(res?),
+        }
+    }
+
+    /// Downloads the compiler, if it has not been downloaded yet, and leases it,
+    /// so that it is not evicted while the lease is held (e.g., while compiling).
+    pub async fn lease(&self, compiler_version: &Version) -> Result<CompilerLease, Error> {
+        if !self.allow_nightly && !compiler_version.is_release() {
+            return Err(Error::VersionNotFound(compiler_version.clone()));
+        }
+        match self
+            .cache
+            .lease(self.fetcher.as_ref(), compiler_version)
+            .await
+        {
+            Err(FetchError::NotFound(version)) => Err(Error::VersionNotFound(version)),
+            res => Ok(res?),
+        }
+    }
+
     #[instrument(name = "download_and_compile", skip(self, input), level = "debug")]
     pub async fn compile(
         &self,
         compiler_version: &Version,
         input: &CompilerInput,
     ) -> Result<CompilerOutput, Error> {
-        let path_result = {
-            self.cache
-                .get(self.fetcher.as_ref(), compiler_version)
-                .await
-        };
-        let path = match path_result {
-            Err(FetchError::NotFound(version)) => return Err(Error::VersionNotFound(version)),
-            res => res?,
-        };
+        let compiler = self.lease(compiler_version).await?;
 
         let output = {
             let span = tracing::debug_span!(
@@ -90,9 +153,15 @@ where
             };
             let _compile_timer_guard = metrics::COMPILE_TIME.start_timer();
             let _compile_gauge_guard = metrics::COMPILATIONS_IN_FLIGHT.guarded_inc();
-            self.evm_compiler
-                .compile(&path, compiler_version, input)
-                .await?
+            let compilation = self
+                .evm_compiler
+                .compile(&compiler.path, compiler_version, input);
+            match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, compilation)
+                    .await
+                    .map_err(|_| Error::Timeout(timeout))??,
+                None => compilation.await?,
+            }
         };
 
         // Compilations errors, warnings and info messages are returned in `CompilerOutput.error`
@@ -115,7 +184,11 @@ where
     }
 
     pub fn all_versions(&self) -> Vec<Version> {
-        self.fetcher.all_versions()
+        let mut versions = self.fetcher.all_versions();
+        if !self.allow_nightly {
+            versions.retain(|version| version.is_release());
+        }
+        versions
     }
 
     pub fn all_versions_sorted_str(&self) -> Vec<String> {
@@ -125,6 +198,23 @@ where
         versions.into_iter().map(|v| v.to_string()).collect()
     }
 
+    /// Returns compiler binaries available locally, along with their pinned checksums.
+    pub fn cached_compilers(&self) -> Vec<CachedCompiler> {
+        self.cache.cached_compilers()
+    }
+
+    /// Removes the compiler binary from the disk. The compiler would be
+    /// downloaded again when requested next time.
+    pub async fn evict(&self, ver: &Version) -> Result<(), EvictionError> {
+        self.cache.evict(ver).await
+    }
+
+    /// Replaces the set of versions, which must never be evicted from the disk
+    /// (e.g., versions referenced by stored contracts).
+    pub fn retain_versions(&self, versions: HashSet<Version>) {
+        self.cache.set_retained(versions)
+    }
+
     pub async fn load_from_dir(&self, dir: &PathBuf) {
         match self.cache.load_from_dir(dir).await {
             Ok(_) => {}
@@ -143,6 +233,7 @@ where
 mod tests {
     use super::{super::list_fetcher::ListFetcher, *};
     use crate::{consts::DEFAULT_SOLIDITY_COMPILER_LIST, solidity::SolidityCompiler};
+    use async_trait::async_trait;
     use ethers_solc::artifacts::{Source, Sources};
     use std::{default::Default, env::temp_dir, str::FromStr};
     use tokio::sync::{OnceCell, Semaphore};
@@ -263,4 +354,54 @@ mod tests {
             _ => panic!("Invalid compilation error: {result:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn returns_timeout_error() {
+        struct MockFetcher;
+
+        #[async_trait]
+        impl Fetcher for MockFetcher {
+            async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+                Ok(PathBuf::from(ver.to_string()))
+            }
+
+            fn all_versions(&self) -> Vec<Version> {
+                vec![]
+            }
+        }
+
+        struct SlowCompiler;
+
+        #[async_trait]
+        impl EvmCompiler for SlowCompiler {
+            async fn compile(
+                &self,
+                _path: &Path,
+                _ver: &Version,
+                _input: &CompilerInput,
+            ) -> Result<CompilerOutput, SolcError> {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(CompilerOutput::default())
+            }
+        }
+
+        let timeout = Duration::from_millis(10);
+        let compilers = Compilers::new(
+            Arc::new(MockFetcher),
+            SlowCompiler,
+            Arc::new(Semaphore::new(1)),
+        )
+        .with_timeout(Some(timeout));
+        let input: CompilerInput = Input::with_source_code("".into()).into();
+        let version = Version::from_str("v0.8.10+commit.fc410830").expect("Compiler version");
+
+        let result = compilers
+            .compile(&version, &input)
+            .await
+            .expect_err("Compilation should time out");
+        assert!(
+            matches!(result, Error::Timeout(duration) if duration == timeout),
+            "Invalid compilation error: {result:?}"
+        );
+    }
 }
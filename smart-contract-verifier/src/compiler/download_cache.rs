@@ -1,21 +1,125 @@
 use super::{
+    checksums::ChecksumAllowlist,
     fetcher::{FetchError, Fetcher},
     version::Version,
 };
-use crate::metrics;
-use std::{collections::HashMap, path::PathBuf, str::FromStr, sync::Arc};
+use crate::{metrics, progress};
+use primitive_types::H256;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::SystemTime,
+};
+use thiserror::Error;
 use tracing::Instrument;
 
+/// Compiler binary available in the cache.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedCompiler {
+    pub version: Version,
+    pub path: PathBuf,
+    /// Checksum pinned for the binary. Is `None` if checksums are not pinned.
+    pub sha256: Option<H256>,
+    /// Size of the binary in bytes. Is `None` if the binary could not be accessed.
+    pub size: Option<u64>,
+    /// The last time the compiler has been requested since the service started.
+    pub last_used: Option<SystemTime>,
+    /// Indicates that the compiler is never evicted by the eviction policy.
+    pub retained: bool,
+}
+
+/// Limits the disk space occupied by downloaded compilers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvictionPolicy {
+    /// Directory compilers are downloaded into. Compilers located outside
+    /// of it (e.g., registered local binaries) are never evicted.
+    pub dir: PathBuf,
+    /// Max total size of downloaded compilers in bytes. When exceeded,
+    /// least recently used compilers are evicted.
+    pub max_size: Option<u64>,
+}
+
+#[derive(Debug, Error)]
+pub enum EvictionError {
+    #[error("compiler version {0} is not cached")]
+    NotCached(Version),
+    #[error("compiler version {0} is retained and cannot be evicted")]
+    Retained(Version),
+    #[error("compiler version {0} is located outside of the compilers directory")]
+    External(Version),
+    #[error("compiler version {0} is in use")]
+    Leased(Version),
+    #[error("cannot remove compiler: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Number of leases taken for each version.
+type Leases = Arc<parking_lot::Mutex<HashMap<Version, usize>>>;
+
+/// Compiler downloaded into the cache, which is not evicted until the lease is dropped,
+/// so that it is not removed from the disk while compilations are running.
+#[derive(Debug)]
+pub struct CompilerLease {
+    pub path: PathBuf,
+    version: Version,
+    leases: Leases,
+}
+
+impl CompilerLease {
+    fn new(version: &Version, leases: &Leases) -> Self {
+        *leases.lock().entry(version.clone()).or_default() += 1;
+        Self {
+            path: PathBuf::new(),
+            version: version.clone(),
+            leases: leases.clone(),
+        }
+    }
+}
+
+impl Drop for CompilerLease {
+    fn drop(&mut self) {
+        let mut leases = self.leases.lock();
+        if let Some(count) = leases.get_mut(&self.version) {
+            *count -= 1;
+            if *count == 0 {
+                leases.remove(&self.version);
+            }
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct DownloadCache {
     cache: parking_lot::Mutex<HashMap<Version, Arc<tokio::sync::RwLock<Option<PathBuf>>>>>,
+    checksums: Option<ChecksumAllowlist>,
+    eviction_policy: Option<EvictionPolicy>,
+    retained: parking_lot::RwLock<HashSet<Version>>,
+    last_used: parking_lot::Mutex<HashMap<Version, SystemTime>>,
+    leases: Leases,
 }
 
 impl DownloadCache {
     pub fn new() -> Self {
-        DownloadCache {
-            cache: Default::default(),
-        }
+        Default::default()
+    }
+
+    /// Makes the cache refuse to return binaries whose checksums
+    /// differ from the ones pinned in the allowlist.
+    pub fn with_allowlist(mut self, checksums: ChecksumAllowlist) -> Self {
+        self.checksums = Some(checksums);
+        self
+    }
+
+    pub fn with_eviction_policy(mut self, eviction_policy: EvictionPolicy) -> Self {
+        self.eviction_policy = Some(eviction_policy);
+        self
+    }
+
+    /// Replaces the set of versions, which are never evicted by the eviction policy.
+    pub fn set_retained(&self, versions: HashSet<Version>) {
+        *self.retained.write() = versions;
     }
 
     async fn try_get(&self, ver: &Version) -> Option<PathBuf> {
@@ -31,6 +135,41 @@ impl DownloadCache {
             None => None,
         }
     }
+
+    async fn check_checksum(&self, ver: &Version, file: &Path) -> Result<(), FetchError> {
+        match &self.checksums {
+            Some(checksums) => checksums.check(ver, file).await.map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns compilers installed into the cache, along with their pinned checksums.
+    /// Compilers being installed at the moment are skipped.
+    pub fn cached_compilers(&self) -> Vec<CachedCompiler> {
+        let installed: Vec<_> = {
+            let cache = self.cache.lock();
+            cache
+                .iter()
+                .filter_map(|(ver, lock)| Some((ver.clone(), lock.try_read().ok()?.clone()?)))
+                .collect()
+        };
+        let mut compilers: Vec<_> = installed
+            .into_iter()
+            .map(|(version, path)| CachedCompiler {
+                sha256: self
+                    .checksums
+                    .as_ref()
+                    .and_then(|checksums| checksums.get(&version)),
+                size: std::fs::metadata(&path).ok().map(|metadata| metadata.len()),
+                last_used: self.last_used.lock().get(&version).cloned(),
+                retained: self.retained.read().contains(&version),
+                version,
+                path,
+            })
+            .collect();
+        compilers.sort_by(|x, y| x.version.cmp(&y.version).reverse());
+        compilers
+    }
 }
 
 impl DownloadCache {
@@ -40,17 +179,42 @@ impl DownloadCache {
         ver: &Version,
     ) -> Result<PathBuf, FetchError> {
         metrics::DOWNLOAD_CACHE_TOTAL.inc();
-        match self.try_get(ver).await {
+        let (file, fetched) = match self.try_get(ver).await {
             Some(file) => {
                 metrics::DOWNLOAD_CACHE_HITS.inc();
-                Ok(file)
+                (file, false)
             }
             None => {
+                progress::report(progress::Stage::DownloadingCompiler);
                 let _timer = metrics::COMPILER_FETCH_TIME.start_timer();
                 let span = tracing::debug_span!("fetch compiler", ver = ver.to_string());
-                self.fetch(fetcher, ver).instrument(span).await
+                (self.fetch(fetcher, ver).instrument(span).await?, true)
             }
+        };
+        // The binary is checked before each usage, as it could
+        // have been replaced on the disk since the installation
+        self.check_checksum(ver, &file).await?;
+        self.last_used.lock().insert(ver.clone(), SystemTime::now());
+        if fetched {
+            self.enforce_quota(ver).await;
         }
+        Ok(file)
+    }
+
+    /// Same as [`DownloadCache::get`], but the compiler is leased before it is looked up,
+    /// so that it could not be evicted in between.
+    pub async fn lease<D: Fetcher + ?Sized>(
+        &self,
+        fetcher: &D,
+        ver: &Version,
+    ) -> Result<CompilerLease, FetchError> {
+        let mut lease = CompilerLease::new(ver, &self.leases);
+        lease.path = self.get(fetcher, ver).await?;
+        Ok(lease)
+    }
+
+    fn is_leased(&self, ver: &Version) -> bool {
+        self.leases.lock().contains_key(ver)
     }
 
     async fn fetch<D: Fetcher + ?Sized>(
@@ -69,11 +233,95 @@ impl DownloadCache {
                 tracing::info!(target: "compiler_cache", "installing file version {}", ver);
                 let file = fetcher.fetch(ver).await?;
                 *entry = Some(file.clone());
+                metrics::CACHED_COMPILERS.inc();
                 Ok(file)
             }
         }
     }
 
+    /// Removes the compiler from the cache and from the disk.
+    /// The compiler would be downloaded again on the next request.
+    pub async fn evict(&self, ver: &Version) -> Result<(), EvictionError> {
+        let lock = {
+            let cache = self.cache.lock();
+            cache.get(ver).cloned()
+        }
+        .ok_or_else(|| EvictionError::NotCached(ver.clone()))?;
+        let mut entry = lock.write().await;
+        let path = entry
+            .clone()
+            .ok_or_else(|| EvictionError::NotCached(ver.clone()))?;
+        let dir = match &self.eviction_policy {
+            Some(policy) if path.starts_with(&policy.dir) => &policy.dir,
+            _ => return Err(EvictionError::External(ver.clone())),
+        };
+        if self.retained.read().contains(ver) {
+            return Err(EvictionError::Retained(ver.clone()));
+        }
+        // Leases are taken before the entry is read, so no lease could be taken
+        // for the compiler being removed while the entry is locked
+        if self.is_leased(ver) {
+            return Err(EvictionError::Leased(ver.clone()));
+        }
+
+        // Downloaded compilers are stored in directories named after their versions
+        let version_dir = dir.join(ver.to_string());
+        if path.starts_with(&version_dir) {
+            tokio::fs::remove_dir_all(&version_dir).await?;
+        } else {
+            tokio::fs::remove_file(&path).await?;
+        }
+        *entry = None;
+        self.last_used.lock().remove(ver);
+        metrics::CACHED_COMPILERS.dec();
+        metrics::EVICTED_COMPILERS.inc();
+        tracing::info!(target: "compiler_cache", "evicted file version {}", ver);
+        Ok(())
+    }
+
+    /// Evicts least recently used compilers (except the requested one and the ones in use)
+    /// until downloaded compilers fit into the disk quota.
+    async fn enforce_quota(&self, requested: &Version) {
+        let (dir, max_size) = match &self.eviction_policy {
+            Some(EvictionPolicy {
+                dir,
+                max_size: Some(max_size),
+            }) => (dir, *max_size),
+            _ => return,
+        };
+
+        let mut compilers: Vec<_> = self
+            .cached_compilers()
+            .into_iter()
+            .filter(|compiler| compiler.path.starts_with(dir))
+            .collect();
+        let mut total_size: u64 = compilers.iter().filter_map(|compiler| compiler.size).sum();
+        compilers.retain(|compiler| {
+            !compiler.retained
+                && &compiler.version != requested
+                && !self.is_leased(&compiler.version)
+        });
+        // Compilers not used since the service started go first
+        compilers.sort_by_key(|compiler| compiler.last_used);
+
+        for compiler in compilers {
+            if total_size <= max_size {
+                break;
+            }
+            match self.evict(&compiler.version).await {
+                Ok(()) => total_size = total_size.saturating_sub(compiler.size.unwrap_or_default()),
+                Err(err) => tracing::warn!("cannot evict compiler {}: {}", compiler.version, err),
+            }
+        }
+        if total_size > max_size {
+            tracing::warn!(
+                "downloaded compilers take {} bytes, which exceeds the quota of {} bytes",
+                total_size,
+                max_size
+            );
+        }
+    }
+
     pub async fn load_from_dir(&self, dir: &PathBuf) -> std::io::Result<()> {
         let paths = DownloadCache::read_dir_paths(dir)?;
         let versions = DownloadCache::filter_versions(paths);
@@ -102,19 +350,23 @@ impl DownloadCache {
     async fn add_versions(&self, versions: HashMap<Version, PathBuf>) {
         for (version, path) in versions {
             let solc_path = path.join("solc");
-            if solc_path.exists() {
-                tracing::info!("found local compiler version {}", version);
-                let lock = {
-                    let mut cache = self.cache.lock();
-                    Arc::clone(cache.entry(version.clone()).or_default())
-                };
-                *lock.write().await = Some(solc_path);
-            } else {
+            if !solc_path.exists() {
                 tracing::warn!(
                     "found verions {} but file {:?} doesn't exists",
                     version,
                     solc_path
                 );
+            } else if let Err(err) = self.check_checksum(&version, &solc_path).await {
+                tracing::warn!("skipping local compiler version {}: {}", version, err);
+            } else {
+                tracing::info!("found local compiler version {}", version);
+                let lock = {
+                    let mut cache = self.cache.lock();
+                    Arc::clone(cache.entry(version.clone()).or_default())
+                };
+                if lock.write().await.replace(solc_path).is_none() {
+                    metrics::CACHED_COMPILERS.inc();
+                }
             }
         }
     }
@@ -274,6 +526,78 @@ mod tests {
         assert_eq!(versions, filtered_versions,);
     }
 
+    #[tokio::test]
+    async fn least_recently_used_compilers_are_evicted() {
+        struct MockFileFetcher {
+            dir: PathBuf,
+        }
+
+        #[async_trait]
+        impl Fetcher for MockFileFetcher {
+            async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+                let folder = self.dir.join(ver.to_string());
+                std::fs::create_dir_all(&folder)?;
+                let file = folder.join("solc");
+                std::fs::write(&file, "0123456789")?;
+                Ok(file)
+            }
+
+            fn all_versions(&self) -> Vec<Version> {
+                vec![]
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let fetcher = MockFileFetcher {
+            dir: dir.path().to_path_buf(),
+        };
+        let cache = DownloadCache::new().with_eviction_policy(EvictionPolicy {
+            dir: dir.path().to_path_buf(),
+            max_size: Some(25),
+        });
+
+        let vers: Vec<_> = (0..4).map(new_version).collect();
+        cache.get(&fetcher, &vers[0]).await.unwrap();
+        cache.get(&fetcher, &vers[1]).await.unwrap();
+        cache.set_retained(HashSet::from([vers[0].clone()]));
+        cache.get(&fetcher, &vers[2]).await.unwrap();
+
+        let cached: Vec<_> = cache
+            .cached_compilers()
+            .into_iter()
+            .map(|compiler| compiler.version)
+            .collect();
+        assert_eq!(vec![vers[2].clone(), vers[0].clone()], cached);
+        assert!(!dir.path().join(vers[1].to_string()).exists());
+
+        let err = cache
+            .evict(&vers[0])
+            .await
+            .expect_err("retained compilers cannot be evicted");
+        assert!(matches!(err, EvictionError::Retained(_)), "{err:?}");
+
+        // Evicted compilers are downloaded again
+        cache.get(&fetcher, &vers[1]).await.unwrap();
+        assert!(dir.path().join(vers[1].to_string()).exists());
+
+        // Compilers in use are not evicted until released
+        let lease = cache.lease(&fetcher, &vers[1]).await.unwrap();
+        assert_eq!(
+            dir.path().join(vers[1].to_string()).join("solc"),
+            lease.path
+        );
+        cache.get(&fetcher, &vers[3]).await.unwrap();
+        assert!(dir.path().join(vers[1].to_string()).exists());
+        let err = cache
+            .evict(&vers[1])
+            .await
+            .expect_err("leased compilers cannot be evicted");
+        assert!(matches!(err, EvictionError::Leased(_)), "{err:?}");
+        drop(lease);
+        cache.evict(&vers[1]).await.unwrap();
+        assert!(!dir.path().join(vers[1].to_string()).exists());
+    }
+
     #[tokio::test]
     async fn load_downloaded_compiler() {
         let ver = Version::from_str("0.7.0+commit.9e61f92b").unwrap();
@@ -29,6 +29,8 @@ pub enum FetchError {
     Schedule(#[from] tokio::task::JoinError),
     #[error("validation failed: {0}")]
     Validation(anyhow::Error),
+    #[error("checksum of compiler binary differs from the pinned one: {0}")]
+    ChecksumChanged(Mismatch<H256>),
 }
 
 #[async_trait]
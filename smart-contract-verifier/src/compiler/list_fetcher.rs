@@ -9,7 +9,7 @@ use cron::Schedule;
 use primitive_types::H256;
 use std::{collections::HashMap, fmt::Debug, path::PathBuf, sync::Arc};
 use thiserror::Error;
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
 use url::Url;
 
 type VersionsMap = HashMap<Version, FileInfo>;
@@ -47,16 +47,20 @@ enum ListError {
 }
 
 struct ListVersionFetcher {
-    list_url: Url,
+    list_urls: Vec<Url>,
 }
 
 impl ListVersionFetcher {
     fn new(list_url: Url) -> ListVersionFetcher {
-        ListVersionFetcher { list_url }
+        Self::with_lists(vec![list_url])
     }
 
-    async fn fetch_json_versions(&self) -> Result<json::List, ListError> {
-        reqwest::get(self.list_url.as_str())
+    fn with_lists(list_urls: Vec<Url>) -> ListVersionFetcher {
+        ListVersionFetcher { list_urls }
+    }
+
+    async fn fetch_json_versions(&self, list_url: &Url) -> Result<json::List, ListError> {
+        reqwest::get(list_url.as_str())
             .await
             .map_err(ListError::ListJsonFetch)?
             .json()
@@ -64,12 +68,26 @@ impl ListVersionFetcher {
             .map_err(ListError::ParseListJson)
     }
 
-    fn parse_json_versions(&self, list_json: json::List) -> Result<VersionsMap, ListError> {
+    async fn fetch_list_versions(&self, list_url: &Url) -> Result<VersionsMap, ListError> {
+        let list_json = self.fetch_json_versions(list_url).await?;
+        debug!(
+            "found list json file {} of len = {}",
+            list_url,
+            list_json.builds.len()
+        );
+        self.parse_json_versions(list_json, list_url)
+    }
+
+    fn parse_json_versions(
+        &self,
+        list_json: json::List,
+        list_url: &Url,
+    ) -> Result<VersionsMap, ListError> {
         let mut versions = HashMap::default();
         for json_compiler_info in list_json.builds {
             let version = json_compiler_info.long_version.clone();
-            let file_info = FileInfo::try_from((json_compiler_info, &self.list_url))
-                .map_err(ListError::Path)?;
+            let file_info =
+                FileInfo::try_from((json_compiler_info, list_url)).map_err(ListError::Path)?;
             versions.insert(version, file_info);
         }
         Ok(versions)
@@ -87,9 +105,20 @@ impl VersionsFetcher for ListVersionFetcher {
 
     #[instrument(skip(self), level = "debug")]
     async fn fetch_versions(&self) -> Result<Self::Versions, Self::Error> {
-        let list_json = self.fetch_json_versions().await?;
-        debug!("found list json file of len = {}", list_json.builds.len());
-        self.parse_json_versions(list_json)
+        // If the same version is listed several times, the first list takes precedence.
+        // Only the first list is required; failures of additional lists (e.g., of nightly builds)
+        // are logged, so that the versions of the first list remain available
+        let mut versions = VersionsMap::default();
+        for (index, list_url) in self.list_urls.iter().enumerate().rev() {
+            match self.fetch_list_versions(list_url).await {
+                Ok(list_versions) => versions.extend(list_versions),
+                Err(err) if index > 0 => {
+                    warn!("skipping additional compilers list {}: {}", list_url, err)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(versions)
     }
 }
 
@@ -106,7 +135,18 @@ impl ListFetcher {
         refresh_schedule: Option<Schedule>,
         validator: Option<Arc<dyn FileValidator>>,
     ) -> anyhow::Result<Self> {
-        let fetcher = Arc::new(ListVersionFetcher::new(list_url));
+        Self::with_lists(vec![list_url], folder, refresh_schedule, validator).await
+    }
+
+    /// Creates the fetcher of compilers listed in any of the given lists
+    /// (e.g., the list of releases and the list of nightly builds).
+    pub async fn with_lists(
+        list_urls: Vec<Url>,
+        folder: PathBuf,
+        refresh_schedule: Option<Schedule>,
+        validator: Option<Arc<dyn FileValidator>>,
+    ) -> anyhow::Result<Self> {
+        let fetcher = Arc::new(ListVersionFetcher::with_lists(list_urls));
         let versions = VersionsRefresher::new(fetcher, refresh_schedule).await?;
         Ok(Self {
             versions,
@@ -283,8 +323,10 @@ mod tests {
     fn parse_versions() {
         let list_json_file: json::List = serde_json::from_str(DEFAULT_LIST_JSON).unwrap();
         let download_url = Url::from_str(DEFAULT_DOWNLOAD_PREFIX).expect("valid url");
-        let fetcher = ListVersionFetcher::new(download_url);
-        let verions = fetcher.parse_json_versions(list_json_file).unwrap();
+        let fetcher = ListVersionFetcher::new(download_url.clone());
+        let verions = fetcher
+            .parse_json_versions(list_json_file, &download_url)
+            .unwrap();
         assert_has_version(
             &verions,
             "0.8.15-nightly.2022.5.27+commit.095cc647",
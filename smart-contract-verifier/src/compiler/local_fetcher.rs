@@ -0,0 +1,165 @@
+use super::{
+    fetcher::{validate_checksum, FetchError, Fetcher, FileValidator},
+    version::Version,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use parking_lot::Mutex;
+use primitive_types::H256;
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::SystemTime,
+};
+use tracing::instrument;
+
+/// Compiler binary available on the local file system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalCompiler {
+    pub path: PathBuf,
+    pub sha256: H256,
+}
+
+impl LocalCompiler {
+    /// Accepts hex encoded sha256 hash of the binary expected to be found at the path.
+    pub fn new(path: PathBuf, sha256: &str) -> anyhow::Result<Self> {
+        let sha256 = H256::from_str(sha256)
+            .map_err(|err| anyhow::anyhow!("invalid sha256 of {}: {}", path.display(), err))?;
+        Ok(Self { path, sha256 })
+    }
+}
+
+/// Serves compilers registered as local binaries without downloading them.
+/// All other versions are delegated to the inner fetcher, if any.
+pub struct LocalFetcher {
+    compilers: HashMap<Version, LocalCompiler>,
+    inner: Option<Arc<dyn Fetcher>>,
+    validator: Option<Arc<dyn FileValidator>>,
+    /// Modification time and size of the binaries which have passed the validation.
+    validated: Mutex<HashMap<Version, (SystemTime, u64)>>,
+}
+
+impl LocalFetcher {
+    pub fn new(
+        compilers: HashMap<Version, LocalCompiler>,
+        inner: Option<Arc<dyn Fetcher>>,
+        validator: Option<Arc<dyn FileValidator>>,
+    ) -> Self {
+        Self {
+            compilers,
+            inner,
+            validator,
+            validated: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl Fetcher for LocalFetcher {
+    #[instrument(skip(self), level = "debug")]
+    async fn fetch(&self, ver: &Version) -> Result<PathBuf, FetchError> {
+        let compiler = match (self.compilers.get(ver), &self.inner) {
+            (Some(compiler), _) => compiler,
+            (None, Some(inner)) => return inner.fetch(ver).await,
+            (None, None) => return Err(FetchError::NotFound(ver.clone())),
+        };
+
+        // The binary could have been replaced since the service started, so its checksum
+        // is validated on the first usage and again whenever the file has been modified
+        let metadata = tokio::fs::metadata(&compiler.path).await?;
+        let file_version = (metadata.modified()?, metadata.len());
+        if self.validated.lock().get(ver) == Some(&file_version) {
+            return Ok(compiler.path.clone());
+        }
+        let data = Bytes::from(tokio::fs::read(&compiler.path).await?);
+        validate_checksum(&data, compiler.sha256)?;
+        if let Some(validator) = &self.validator {
+            validator
+                .validate(ver, &compiler.path)
+                .await
+                .map_err(FetchError::Validation)?;
+        }
+        self.validated.lock().insert(ver.clone(), file_version);
+        Ok(compiler.path.clone())
+    }
+
+    fn all_versions(&self) -> Vec<Version> {
+        let inner_versions = self
+            .inner
+            .as_ref()
+            .map(|inner| inner.all_versions())
+            .unwrap_or_default();
+        let versions: BTreeSet<_> = self
+            .compilers
+            .keys()
+            .cloned()
+            .chain(inner_versions)
+            .collect();
+        versions.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use sha2::{Digest, Sha256};
+
+    fn version(version: &str) -> Version {
+        Version::from_str(version).unwrap()
+    }
+
+    fn local_compiler(dir: &tempfile::TempDir, data: &str) -> LocalCompiler {
+        let path = dir.path().join("solc");
+        std::fs::write(&path, data).unwrap();
+        LocalCompiler::new(path, &hex::encode(Sha256::digest(data.as_bytes()))).unwrap()
+    }
+
+    #[tokio::test]
+    async fn fetches_local_compiler() {
+        let dir = tempfile::tempdir().unwrap();
+        let compiler = local_compiler(&dir, "this is a compiler binary");
+        let ver = version("v0.8.17+commit.8df45f5f");
+        let fetcher =
+            LocalFetcher::new(HashMap::from([(ver.clone(), compiler.clone())]), None, None);
+
+        assert_eq!(vec![ver.clone()], fetcher.all_versions());
+        assert_eq!(compiler.path, fetcher.fetch(&ver).await.unwrap());
+        let err = fetcher
+            .fetch(&version("v0.8.16+commit.07a7930e"))
+            .await
+            .expect_err("version is not registered");
+        assert!(matches!(err, FetchError::NotFound(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn checksum_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut compiler = local_compiler(&dir, "this is a compiler binary");
+        compiler.sha256 = H256::zero();
+        let ver = version("v0.8.17+commit.8df45f5f");
+        let fetcher = LocalFetcher::new(HashMap::from([(ver.clone(), compiler)]), None, None);
+
+        let err = fetcher.fetch(&ver).await.expect_err("checksum is invalid");
+        assert!(matches!(err, FetchError::HashMismatch(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn replaced_compiler_is_validated_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let compiler = local_compiler(&dir, "this is a compiler binary");
+        let ver = version("v0.8.17+commit.8df45f5f");
+        let fetcher =
+            LocalFetcher::new(HashMap::from([(ver.clone(), compiler.clone())]), None, None);
+        fetcher.fetch(&ver).await.expect("checksum is valid");
+
+        std::fs::write(&compiler.path, "this is a replaced compiler binary").unwrap();
+        let err = fetcher
+            .fetch(&ver)
+            .await
+            .expect_err("replaced binary does not match the checksum");
+        assert!(matches!(err, FetchError::HashMismatch(_)), "{err:?}");
+    }
+}
@@ -1,15 +1,22 @@
 mod version;
 
+mod checksums;
 mod fetcher;
 mod list_fetcher;
+mod local_fetcher;
+mod process;
 mod s3_fetcher;
 mod versions_fetcher;
 
 mod compilers;
 mod download_cache;
 
+pub use checksums::ChecksumAllowlist;
 pub use compilers::{Compilers, Error, EvmCompiler};
+pub use download_cache::{CachedCompiler, CompilerLease, EvictionError, EvictionPolicy};
 pub use fetcher::{Fetcher, FileValidator};
 pub use list_fetcher::ListFetcher;
+pub use local_fetcher::{LocalCompiler, LocalFetcher};
+pub use process::compile_standard_json;
 pub use s3_fetcher::S3Fetcher;
 pub use version::Version;
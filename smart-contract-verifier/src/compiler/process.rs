@@ -0,0 +1,34 @@
+use ethers_solc::error::{SolcError, SolcIoError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{path::Path, process::Stdio};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+/// Runs the compiler with `--standard-json` flag, same as `Solc::async_compile_as`,
+/// but kills the compiler process if the compilation is dropped (e.g., on timeouts),
+/// so that abandoned compilations do not keep running.
+pub async fn compile_standard_json<T: Serialize, D: DeserializeOwned>(
+    compiler: &Path,
+    input: &T,
+) -> Result<D, SolcError> {
+    let io_error = |err| SolcError::Io(SolcIoError::new(err, compiler));
+    let content = serde_json::to_vec(input)?;
+    let mut child = Command::new(compiler)
+        .arg("--standard-json")
+        .kill_on_drop(true)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(io_error)?;
+    // stdin is closed when dropped, so that the compiler could start the compilation
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&content).await.map_err(io_error)?;
+    }
+    let output = child.wait_with_output().await.map_err(io_error)?;
+    if !output.status.success() {
+        return Err(SolcError::SolcError(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
@@ -13,3 +13,5 @@ pub const DEFAULT_VYPER_COMPILER_LIST: &str =
     "https://raw.githubusercontent.com/blockscout/solc-bin/main/vyper.macos.list.json";
 
 pub const DEFAULT_SOURCIFY_HOST: &str = "https://sourcify.dev/server/";
+
+pub const DEFAULT_RPC_URL: &str = "https://evmos-evm.publicnode.com";
@@ -0,0 +1,114 @@
+use super::compiler::HuffCompiler;
+use crate::{
+    compiler::Compilers,
+    middleware::{Composition, Middleware},
+    rpc::BytecodeFetcher,
+    verifier::{LanguageVerifier, Matchers, Success},
+};
+use std::sync::Arc;
+
+pub struct Client {
+    compilers: Arc<Compilers<HuffCompiler>>,
+    middlewares: Composition<Success>,
+    matchers: Matchers,
+    reject_hidden_characters: bool,
+    /// Rpc providers the deployed code of verified contracts is fetched from.
+    fetcher: Arc<BytecodeFetcher>,
+}
+
+impl Client {
+    /// Convenience method to initialize new huff client.
+    ///
+    /// If you need to keep a reference to the compilers after initialization, use [`new_arc`].
+    ///
+    /// [`new_arc`]: Self::new_arc
+    pub fn new(compilers: Compilers<HuffCompiler>) -> Self {
+        Self::new_arc(Arc::new(compilers))
+    }
+
+    /// Initialize new huff client. [`new`] is more ergonomic if you don't need the `Arc`.
+    ///
+    /// [`new`]: Self::new
+    pub fn new_arc(compilers: Arc<Compilers<HuffCompiler>>) -> Self {
+        Self {
+            compilers,
+            middlewares: Composition::new(),
+            matchers: Matchers::default(),
+            reject_hidden_characters: false,
+            fetcher: Arc::new(BytecodeFetcher::default()),
+        }
+    }
+
+    /// Convenience method to attach middleware.
+    ///
+    /// If you need to keep a reference to the middleware after attaching, use [`with_middleware_arc`].
+    ///
+    /// [`with_middleware_arc`]: Self::with_middleware_arc
+    pub fn with_middleware(self, middleware: impl Middleware<Success>) -> Self {
+        self.with_middleware_arc(Arc::new(middleware))
+    }
+
+    /// Add middleware to the end of the client middleware chain.
+    /// [`with_middleware`] is more ergonomic if you don't need the `Arc`.
+    ///
+    /// [`with_middleware`]: Self::with_middleware
+    pub fn with_middleware_arc(mut self, middleware: Arc<impl Middleware<Success>>) -> Self {
+        self.middlewares.push(middleware);
+        self
+    }
+
+    /// Replaces the default rpc provider the deployed code of contracts is fetched from.
+    pub fn with_fetcher(mut self, fetcher: Arc<BytecodeFetcher>) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
+    /// Replaces the default strategies the compiled bytecodes are compared with the on-chain one.
+    pub fn with_matchers(mut self, matchers: Matchers) -> Self {
+        self.matchers = matchers;
+        self
+    }
+
+    /// Rejects sources containing hidden or lookalike characters before they are compiled.
+    pub fn with_hidden_characters_rejected(mut self, reject: bool) -> Self {
+        self.reject_hidden_characters = reject;
+        self
+    }
+
+    pub fn compilers(&self) -> &Compilers<HuffCompiler> {
+        self.compilers.as_ref()
+    }
+
+    pub fn fetcher(&self) -> &Arc<BytecodeFetcher> {
+        &self.fetcher
+    }
+
+    /// Provides a reference to the middleware chain invoked after successful verifications.
+    pub fn middlewares(&self) -> &Composition<Success> {
+        &self.middlewares
+    }
+}
+
+impl LanguageVerifier for Client {
+    type Compiler = HuffCompiler;
+
+    fn language(&self) -> &'static str {
+        "huff"
+    }
+
+    fn compilers(&self) -> &Compilers<HuffCompiler> {
+        self.compilers()
+    }
+
+    fn middlewares(&self) -> &Composition<Success> {
+        self.middlewares()
+    }
+
+    fn matchers(&self) -> &Matchers {
+        &self.matchers
+    }
+
+    fn rejects_hidden_characters(&self) -> bool {
+        self.reject_hidden_characters
+    }
+}
@@ -0,0 +1,198 @@
+use crate::compiler::{EvmCompiler, Version};
+use ethers_solc::{
+    artifacts::{Contract, Severity},
+    error::{SolcError, SolcIoError},
+    CompilerInput, CompilerOutput,
+};
+use std::{collections::BTreeMap, path::Path, process::Stdio};
+use tokio::process::Command;
+
+/// Compiles Huff sources using `huffc` binaries.
+///
+/// Each source file defining the `MAIN` macro is compiled as a separate contract
+/// named after the file (e.g., `src/Token.huff` results in the `Token` contract).
+/// Other files may only be included by the main ones.
+#[derive(Default)]
+pub struct HuffCompiler {}
+
+impl HuffCompiler {
+    pub fn new() -> Self {
+        HuffCompiler {}
+    }
+}
+
+#[async_trait::async_trait]
+impl EvmCompiler for HuffCompiler {
+    async fn compile(
+        &self,
+        path: &Path,
+        _ver: &Version,
+        input: &CompilerInput,
+    ) -> Result<CompilerOutput, SolcError> {
+        let files_dir = write_sources(input).await?;
+
+        let mut output = CompilerOutput {
+            errors: vec![],
+            sources: BTreeMap::new(),
+            contracts: BTreeMap::new(),
+        };
+        for (name, source) in &input.sources {
+            if !defines_main_macro(&source.content) {
+                continue;
+            }
+            let contract_name = match name.file_stem() {
+                Some(stem) => stem.to_string_lossy().to_string(),
+                None => continue,
+            };
+            let bytecode = run_huffc(path, files_dir.path(), name, "--bytecode").await?;
+            let deployed_bytecode =
+                run_huffc(path, files_dir.path(), name, "--bin-runtime").await?;
+            match (bytecode, deployed_bytecode) {
+                (Ok(bytecode), Ok(deployed_bytecode)) => {
+                    output
+                        .contracts
+                        .entry(name.to_string_lossy().to_string())
+                        .or_default()
+                        .insert(contract_name, contract(&bytecode, &deployed_bytecode)?);
+                }
+                (Err(message), _) | (_, Err(message)) => {
+                    output.errors.push(compiler_error(message))
+                }
+            }
+        }
+        if output.contracts.is_empty() && output.errors.is_empty() {
+            output.errors.push(compiler_error(
+                "no source file defines the MAIN macro".to_string(),
+            ));
+        }
+        Ok(output)
+    }
+}
+
+/// Returns whether the source defines the `MAIN` macro, i.e., could be compiled into a contract.
+fn defines_main_macro(source: &str) -> bool {
+    source.lines().any(|line| {
+        let tokens: Vec<_> = line.split_whitespace().take(3).collect();
+        matches!(
+            tokens.as_slice(),
+            ["#define", "macro", name, ..] if *name == "MAIN" || name.starts_with("MAIN(")
+        )
+    })
+}
+
+async fn write_sources(input: &CompilerInput) -> Result<tempfile::TempDir, SolcError> {
+    let files_dir = tempfile::tempdir().map_err(|e| SolcError::Message(e.to_string()))?;
+    for (name, source) in &input.sources {
+        let file_path = files_dir.path().join(name);
+        // name itself may contain some paths inside
+        if let Some(prefix) = file_path.parent() {
+            tokio::fs::create_dir_all(prefix)
+                .await
+                .map_err(|e| SolcError::Message(e.to_string()))?;
+        }
+        tokio::fs::write(&file_path, source.content.as_bytes())
+            .await
+            .map_err(|e| SolcError::Message(e.to_string()))?;
+    }
+    Ok(files_dir)
+}
+
+/// Runs `huffc` for the file with the given output flag. Returns the hex encoded
+/// bytecode printed by the compiler, or the compilation error message.
+async fn run_huffc(
+    huffc: &Path,
+    files_dir: &Path,
+    file: &Path,
+    flag: &str,
+) -> Result<Result<String, String>, SolcError> {
+    let output = Command::new(huffc)
+        .current_dir(files_dir)
+        .arg(file)
+        .arg(flag)
+        .kill_on_drop(true)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|err| SolcError::Io(SolcIoError::new(err, huffc)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Ok(Err(format!("{}: {}", file.display(), stderr)));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_bytecode(&stdout)
+        .ok_or_else(|| format!("{}: compiler has returned no bytecode", file.display())))
+}
+
+/// Compiler may print some messages before the bytecode, so the last non-empty line is taken.
+fn parse_bytecode(stdout: &str) -> Option<String> {
+    let bytecode = stdout
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    let bytecode = bytecode.trim_start_matches("0x");
+    let is_hex = !bytecode.is_empty() && bytecode.chars().all(|c| c.is_ascii_hexdigit());
+    is_hex.then(|| bytecode.to_string())
+}
+
+fn contract(bytecode: &str, deployed_bytecode: &str) -> Result<Contract, SolcError> {
+    let contract = serde_json::json!({
+        "abi": [],
+        "evm": {
+            "bytecode": {
+                "object": bytecode,
+            },
+            "deployedBytecode": {
+                "object": deployed_bytecode,
+            },
+        },
+    });
+    Ok(serde_json::from_value(contract)?)
+}
+
+fn compiler_error(message: String) -> ethers_solc::artifacts::Error {
+    ethers_solc::artifacts::Error {
+        source_location: None,
+        secondary_source_locations: vec![],
+        r#type: "".to_string(),
+        component: "".to_string(),
+        severity: Severity::Error,
+        error_code: None,
+        message,
+        formatted_message: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn main_macro_is_detected() {
+        assert!(defines_main_macro(
+            "#define macro MAIN() = takes(0) returns(0) {\n    0x00 calldataload\n}"
+        ));
+        assert!(defines_main_macro(
+            "  #define macro MAIN () = takes (0) returns (0) {}"
+        ));
+        assert!(!defines_main_macro(
+            "#define macro MAIN_HELPER() = takes(0) returns(0) {}"
+        ));
+        assert!(!defines_main_macro(
+            "#include \"./Lib.huff\"\n#define function owner() view returns (address)"
+        ));
+    }
+
+    #[test]
+    fn bytecode_is_parsed() {
+        assert_eq!(Some("6003".to_string()), parse_bytecode("0x6003\n"));
+        assert_eq!(
+            Some("60036000".to_string()),
+            parse_bytecode("Compiling...\n60036000\n\n")
+        );
+        assert_eq!(None, parse_bytecode("Error: invalid macro\n"));
+        assert_eq!(None, parse_bytecode(""));
+    }
+}
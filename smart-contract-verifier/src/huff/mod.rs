@@ -0,0 +1,7 @@
+mod client;
+mod compiler;
+
+pub mod sources;
+
+pub use client::Client;
+pub use compiler::HuffCompiler;
@@ -0,0 +1,61 @@
+use super::client::Client;
+use crate::{
+    common_types::{Address, MatchAgainst},
+    compiler::Version,
+    rpc::BlockId,
+    solidity::multi_part::deployed_code,
+    verifier::{ContractVerifier, Error, Success},
+};
+use ethers_solc::{
+    artifacts::{Settings, Source, Sources},
+    CompilerInput,
+};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+use tracing::instrument;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationRequest {
+    pub contract_address: Address,
+    pub compiler_version: Version,
+    /// Block at which the deployed code of the contract is fetched.
+    pub block: BlockId,
+    /// Name of the contract to verify, plain or fully qualified (`file.huff:Name`).
+    /// Required only if several compiled contracts match the bytecode.
+    pub contract_name: Option<String>,
+
+    pub sources: BTreeMap<PathBuf, String>,
+}
+
+fn compiler_input(sources: BTreeMap<PathBuf, String>) -> CompilerInput {
+    let sources: Sources = sources
+        .into_iter()
+        .map(|(name, content)| (name, Source { content }))
+        .collect();
+    CompilerInput {
+        language: "Huff".to_string(),
+        sources,
+        settings: Settings::default(),
+    }
+}
+
+/// Verifies the runtime bytecode of the contract deployed at the address.
+/// Huff contracts are compiled without metadata, so only full matches are possible.
+#[instrument(
+    skip(client, request),
+    fields(contract_address = %request.contract_address),
+    level = "debug"
+)]
+pub async fn verify(client: Arc<Client>, request: VerificationRequest) -> Result<Success, Error> {
+    let deployed_bytecode =
+        deployed_code(client.fetcher(), &request.contract_address, request.block).await?;
+    let compiler_input = compiler_input(request.sources);
+    let verifier = ContractVerifier::new(
+        client.as_ref(),
+        &request.compiler_version,
+        None,
+        deployed_bytecode,
+        request.contract_name,
+        MatchAgainst::Deployed,
+    )?;
+    verifier.verify_any([compiler_input]).await
+}
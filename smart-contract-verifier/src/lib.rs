@@ -1,14 +1,18 @@
+pub mod huff;
 pub mod solidity;
 pub mod sourcify;
 pub mod vyper;
 
 pub mod middleware;
+pub mod progress;
+pub mod rpc;
+pub mod scheduler;
+pub mod tools;
 
 mod common_types;
 mod compiler;
 mod consts;
 mod metrics;
-mod scheduler;
 mod verifier;
 
 #[cfg(test)]
@@ -17,16 +21,25 @@ mod tests;
 pub(crate) use blockscout_display_bytes::Bytes as DisplayBytes;
 
 pub use consts::{
-    DEFAULT_SOLIDITY_COMPILER_LIST, DEFAULT_SOURCIFY_HOST, DEFAULT_VYPER_COMPILER_LIST,
+    DEFAULT_RPC_URL, DEFAULT_SOLIDITY_COMPILER_LIST, DEFAULT_SOURCIFY_HOST,
+    DEFAULT_VYPER_COMPILER_LIST,
 };
 
 pub use middleware::Middleware;
 
-pub use common_types::MatchType;
-pub use compiler::{Compilers, Fetcher, ListFetcher, S3Fetcher, Version};
+pub use common_types::{Address, AddressError, MatchAgainst, MatchType};
+pub use compiler::{
+    CachedCompiler, ChecksumAllowlist, Compilers, Error as CompilerError, EvictionError,
+    EvictionPolicy, Fetcher, ListFetcher, LocalCompiler, LocalFetcher, S3Fetcher, Version,
+};
 pub use sourcify::{Error as SourcifyError, Success as SourcifySuccess};
-pub use verifier::{BytecodePart, Error as VerificationError, Success as VerificationSuccess};
+pub use verifier::{
+    BytecodeMatcher, BytecodePart, ContractDiagnostics, ContractNotFound,
+    Error as VerificationError, Hint as DiagnosticsHint, LanguageVerifier, MatchInput,
+    MatcherKind, Matchers, Success as VerificationSuccess,
+};
 
-pub use solidity::{Client as SolidityClient, SolcValidator, SolidityCompiler};
+pub use huff::{Client as HuffClient, HuffCompiler};
+pub use solidity::{Client as SolidityClient, SolcJs, SolcValidator, SolidityCompiler};
 pub use sourcify::SourcifyApiClient;
 pub use vyper::{Client as VyperClient, VyperCompiler};
@@ -1,6 +1,8 @@
 use lazy_static::lazy_static;
 use prometheus::{
-    register_gauge, register_histogram, register_int_counter, Gauge, Histogram, IntCounter,
+    register_gauge, register_gauge_vec, register_histogram, register_int_counter,
+    register_int_counter_vec, register_int_gauge, Gauge, GaugeVec, Histogram, IntCounter,
+    IntCounterVec, IntGauge,
 };
 
 lazy_static! {
@@ -14,6 +16,21 @@ lazy_static! {
         "number of cache hits in DownloadCache",
     )
     .unwrap();
+    pub static ref CACHED_COMPILERS: IntGauge = register_int_gauge!(
+        "smart_contract_verifier_cached_compilers",
+        "number of compiler binaries available in DownloadCache",
+    )
+    .unwrap();
+    pub static ref EVICTED_COMPILERS: IntCounter = register_int_counter!(
+        "smart_contract_verifier_evicted_compilers",
+        "number of compiler binaries evicted from DownloadCache",
+    )
+    .unwrap();
+    pub static ref COMPILER_CHECKSUM_MISMATCHES: IntCounter = register_int_counter!(
+        "smart_contract_verifier_compiler_checksum_mismatches",
+        "number of compiler binaries refused as their checksums differ from the pinned ones",
+    )
+    .unwrap();
     pub static ref COMPILER_FETCH_TIME: Histogram = register_histogram!(
         "smart_contract_verifier_compiler_fetch_time_seconds",
         "download time for compilers in seconds",
@@ -40,6 +57,36 @@ lazy_static! {
         "number of compilations in queue",
     )
     .unwrap();
+    pub static ref RPC_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "smart_contract_verifier_rpc_requests",
+        "number of requests to rpc providers",
+        &["provider", "method", "status"],
+    )
+    .unwrap();
+    pub static ref RPC_PROVIDER_HEALTH: GaugeVec = register_gauge_vec!(
+        "smart_contract_verifier_rpc_provider_health",
+        "health score of rpc providers (ratio of recent successful requests)",
+        &["provider"],
+    )
+    .unwrap();
+    pub static ref VERIFIED_CONTRACTS: IntCounterVec = register_int_counter_vec!(
+        "smart_contract_verifier_verified_contracts",
+        "number of successful verifications",
+        &["language", "match_type"],
+    )
+    .unwrap();
+    pub static ref VERIFICATIONS: IntCounterVec = register_int_counter_vec!(
+        "smart_contract_verifier_verifications",
+        "number of finished verifications by their result (success, no_match, failure)",
+        &["language", "result"],
+    )
+    .unwrap();
+    pub static ref MIDDLEWARE_FAILURES: IntCounterVec = register_int_counter_vec!(
+        "smart_contract_verifier_middleware_failures",
+        "number of verification outputs middlewares have failed to process",
+        &["middleware"],
+    )
+    .unwrap();
 }
 
 pub struct GaugeGuard(&'static Gauge);
@@ -0,0 +1,43 @@
+use super::Middleware;
+use crate::{metrics, MatchType, SourcifySuccess, VerificationSuccess};
+
+/// Accounts successful verifications in `smart_contract_verifier_verified_contracts` metric.
+pub struct MetricsMiddleware {
+    language: &'static str,
+}
+
+impl MetricsMiddleware {
+    pub fn new(language: &'static str) -> Self {
+        Self { language }
+    }
+
+    fn count(&self, match_type: MatchType) {
+        metrics::VERIFIED_CONTRACTS
+            .with_label_values(&[self.language, match_type.as_str()])
+            .inc();
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware<VerificationSuccess> for MetricsMiddleware {
+    async fn call(&self, output: &VerificationSuccess) -> Result<(), anyhow::Error> {
+        self.count(output.match_type);
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "metrics"
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware<SourcifySuccess> for MetricsMiddleware {
+    async fn call(&self, output: &SourcifySuccess) -> Result<(), anyhow::Error> {
+        self.count(output.match_type);
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "metrics"
+    }
+}
@@ -0,0 +1,159 @@
+mod metrics;
+mod webhook;
+
+pub use metrics::MetricsMiddleware;
+pub use webhook::{Config as WebhookConfig, Webhook};
+
+use crate::metrics as global_metrics;
+use std::sync::Arc;
+
+/// When implemented the struct could be added as a verification success
+/// post-processing step.
+//
+// Output type implemented as a generic and not as an associated type,
+// so that the same middleware provider could process different output types.
+#[async_trait::async_trait]
+pub trait Middleware<Output>: 'static + Send + Sync {
+    /// Invoked with a verification output after successful verification.
+    ///
+    /// Returned errors are logged and accounted in metrics by the caller, and
+    /// do not prevent the following middlewares from being invoked. They are never
+    /// propagated to the client the verification has been requested by.
+    async fn call(&self, output: &Output) -> Result<(), anyhow::Error>;
+
+    /// Name of the middleware used in logs and metrics.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// Ordered chain of middlewares. Middlewares are invoked in the order they have been added.
+#[derive(Clone)]
+pub struct Composition<Output> {
+    middleware_stack: Vec<Arc<dyn Middleware<Output>>>,
+}
+
+impl<Output> Default for Composition<Output> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Output> Composition<Output> {
+    /// Initialize empty composition.
+    pub fn new() -> Self {
+        Self {
+            middleware_stack: vec![],
+        }
+    }
+
+    /// Convenience method to attach middleware.
+    ///
+    /// If you need to keep a reference to the middleware after attaching, use [`with_arc`].
+    ///
+    /// [`with_arc`]: Self::with_arc
+    pub fn with<M>(self, middleware: M) -> Self
+    where
+        M: Middleware<Output>,
+    {
+        self.with_arc(Arc::new(middleware))
+    }
+
+    /// Add middleware to the composition. [`with`] is more ergonomic if you don't need the `Arc`.
+    ///
+    /// [`with`]: Self::with
+    pub fn with_arc(mut self, middleware: Arc<dyn Middleware<Output>>) -> Self {
+        self.push(middleware);
+        self
+    }
+
+    /// Add middleware to the end of the chain.
+    pub fn push(&mut self, middleware: Arc<dyn Middleware<Output>>) {
+        self.middleware_stack.push(middleware);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.middleware_stack.is_empty()
+    }
+
+    /// Names of the middlewares in the order they are invoked.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.middleware_stack.iter().map(|m| m.name()).collect()
+    }
+
+    /// Invokes all middlewares in order. Failed middlewares are logged
+    /// and do not prevent the following ones from being invoked.
+    pub async fn run(&self, output: &Output) {
+        for middleware in &self.middleware_stack {
+            if let Err(err) = middleware.call(output).await {
+                tracing::error!(
+                    middleware = middleware.name(),
+                    "middleware has failed to process the verification output: {:#}",
+                    err
+                );
+                global_metrics::MIDDLEWARE_FAILURES
+                    .with_label_values(&[middleware.name()])
+                    .inc();
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<Output: 'static + Sync> Middleware<Output> for Composition<Output> {
+    async fn call(&self, output: &Output) -> Result<(), anyhow::Error> {
+        self.run(output).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockall::{mock, Sequence};
+
+    mock! {
+        Middleware<Output: 'static + Send + Sync> {}
+
+        #[async_trait::async_trait]
+        impl<Output: 'static + Send + Sync> super::Middleware<Output> for Middleware<Output> {
+            async fn call(&self, output: &Output) -> Result<(), anyhow::Error>;
+        }
+    }
+
+    #[tokio::test]
+    async fn composition() {
+        let mut middleware1 = MockMiddleware::<()>::new();
+        let mut middleware2 = MockMiddleware::<()>::new();
+
+        middleware1.expect_call().times(1).returning(|_| Ok(()));
+        middleware2.expect_call().times(1).returning(|_| Ok(()));
+
+        let composition = Composition::new()
+            .with(middleware1)
+            .with_arc(Arc::new(middleware2));
+        composition.run(&()).await;
+    }
+
+    #[tokio::test]
+    async fn failed_middleware_does_not_stop_the_chain() {
+        let mut sequence = Sequence::new();
+        let mut middleware1 = MockMiddleware::<()>::new();
+        let mut middleware2 = MockMiddleware::<()>::new();
+
+        middleware1
+            .expect_call()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Err(anyhow::anyhow!("webhook is unavailable")));
+        middleware2
+            .expect_call()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(()));
+
+        let composition = Composition::new().with(middleware1).with(middleware2);
+        assert_eq!(2, composition.names().len());
+        composition.run(&()).await;
+    }
+}
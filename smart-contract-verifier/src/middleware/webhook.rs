@@ -0,0 +1,145 @@
+use super::Middleware;
+use crate::{SourcifySuccess, VerificationSuccess};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub url: Url,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+/// Body of the request sent to the webhook for each verified contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Notification<'a> {
+    language: &'a str,
+    file_name: &'a str,
+    contract_name: &'a str,
+    compiler_version: String,
+    match_type: &'a str,
+}
+
+/// Notifies an external service about verified contracts by POSTing
+/// a json [`Notification`] to the configured url.
+pub struct Webhook {
+    url: Url,
+    language: &'static str,
+    client: reqwest::Client,
+}
+
+impl Webhook {
+    pub fn new(config: Config, language: &'static str) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+        Ok(Self {
+            url: config.url,
+            language,
+            client,
+        })
+    }
+
+    async fn notify(&self, notification: Notification<'_>) -> Result<(), anyhow::Error> {
+        self.client
+            .post(self.url.clone())
+            .json(&notification)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware<VerificationSuccess> for Webhook {
+    async fn call(&self, output: &VerificationSuccess) -> Result<(), anyhow::Error> {
+        self.notify(Notification {
+            language: self.language,
+            file_name: &output.file_path,
+            contract_name: &output.contract_name,
+            compiler_version: output.compiler_version.to_string(),
+            match_type: output.match_type.as_str(),
+        })
+        .await
+    }
+
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware<SourcifySuccess> for Webhook {
+    async fn call(&self, output: &SourcifySuccess) -> Result<(), anyhow::Error> {
+        self.notify(Notification {
+            language: self.language,
+            file_name: &output.file_name,
+            contract_name: &output.contract_name,
+            compiler_version: output.compiler_version.clone(),
+            match_type: output.match_type.as_str(),
+        })
+        .await
+    }
+
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{
+        matchers::{body_json, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[tokio::test]
+    async fn notification_is_sent() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_json(serde_json::json!({
+                "language": "solidity",
+                "file_name": "A.sol",
+                "contract_name": "A",
+                "compiler_version": "v0.8.14+commit.80d49f37",
+                "match_type": "full",
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = Config {
+            url: Url::parse(&server.uri()).unwrap(),
+            timeout_secs: default_timeout_secs(),
+        };
+        let webhook = Webhook::new(config, "solidity").unwrap();
+        let notification = Notification {
+            language: "solidity",
+            file_name: "A.sol",
+            contract_name: "A",
+            compiler_version: "v0.8.14+commit.80d49f37".to_string(),
+            match_type: "full",
+        };
+        webhook.notify(notification.clone()).await.unwrap();
+        server.verify().await;
+
+        server.reset().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        assert!(
+            webhook.notify(notification).await.is_err(),
+            "failed responses should be reported"
+        );
+    }
+}
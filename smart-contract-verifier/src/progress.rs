@@ -0,0 +1,52 @@
+//! Progress of the verification, reported to the observer in the scope
+//! (e.g., to show the stage of a verification run in the background).
+
+use std::{future::Future, sync::Arc};
+
+/// Stage the verification has entered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// The compiler is missing locally and is being downloaded.
+    DownloadingCompiler,
+    /// The input is being compiled with the metadata hash variant (e.g., "ipfs", "none").
+    Compiling { metadata_hash: String },
+    /// Compiled bytecodes are being compared with the on-chain ones.
+    Comparing,
+    /// The verified contract is being stored. Is reported by the caller storing it.
+    Storing,
+}
+
+type Observer = Arc<dyn Fn(Stage) + Send + Sync>;
+
+tokio::task_local! {
+    static OBSERVER: Observer;
+}
+
+/// Runs `f` reporting stages of the verifications made by it to the `observer`.
+pub async fn scope<F: Future>(observer: impl Fn(Stage) + Send + Sync + 'static, f: F) -> F::Output {
+    OBSERVER.scope(Arc::new(observer), f).await
+}
+
+/// Reports the stage to the observer of the current scope, if any.
+pub fn report(stage: Stage) {
+    let _ = OBSERVER.try_with(|observer| observer(stage));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn stages_are_reported_within_scope_only() {
+        let stages = Arc::new(Mutex::new(Vec::new()));
+        let observed = stages.clone();
+        scope(move |stage| observed.lock().unwrap().push(stage), async {
+            report(Stage::Comparing)
+        })
+        .await;
+        report(Stage::DownloadingCompiler);
+        assert_eq!(vec![Stage::Comparing], *stages.lock().unwrap());
+    }
+}
@@ -0,0 +1,1235 @@
+//! Access to the chain through a set of rpc providers with retries,
+//! failover between providers and circuit breaking of failing ones.
+
+use crate::{consts::DEFAULT_RPC_URL, metrics, Address};
+use futures::Future;
+use parking_lot::RwLock;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tracing::instrument;
+use url::Url;
+use web3_rpc::web3::Web3;
+
+/// Max number of requests sent in a single JSON-RPC batch.
+const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Block at which the state of the chain is requested.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlockId {
+    #[default]
+    Latest,
+    Earliest,
+    Pending,
+    Number(u64),
+}
+
+impl FromStr for BlockId {
+    type Err = anyhow::Error;
+
+    /// Parses either a block tag, or a decimal or `0x` prefixed hex block number.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let block = match s {
+            "latest" => Self::Latest,
+            "earliest" => Self::Earliest,
+            "pending" => Self::Pending,
+            _ => {
+                let number = match s.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16),
+                    None => s.parse(),
+                };
+                Self::Number(number.map_err(|_| anyhow::anyhow!("invalid block: {s}"))?)
+            }
+        };
+        Ok(block)
+    }
+}
+
+impl Display for BlockId {
+    /// Formats the block the way it is passed in rpc requests.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Latest => write!(f, "latest"),
+            Self::Earliest => write!(f, "earliest"),
+            Self::Pending => write!(f, "pending"),
+            Self::Number(number) => write!(f, "{number:#x}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FailoverConfig {
+    /// Max number of attempts made for a single request across all providers.
+    pub max_attempts: u32,
+    /// Delay before the request is retried once all providers have failed it.
+    /// Doubles after each round of attempts up to `max_backoff`.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Number of consecutive failures after which the provider is not used for `circuit_open_duration`.
+    pub failure_threshold: u32,
+    pub circuit_open_duration: Duration,
+    pub request_timeout: Duration,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            failure_threshold: 5,
+            circuit_open_duration: Duration::from_secs(30),
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Health {
+    /// Exponentially weighted ratio of successful requests.
+    score: f64,
+    consecutive_failures: u32,
+    circuit_open_until: Option<Instant>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            score: 1.0,
+            consecutive_failures: 0,
+            circuit_open_until: None,
+        }
+    }
+}
+
+struct Provider {
+    /// Used as a metrics label. Does not include the path,
+    /// which may contain api keys.
+    name: String,
+    url: Url,
+    web3: Web3,
+    http: reqwest::Client,
+    health: RwLock<Health>,
+}
+
+impl Provider {
+    fn new(url: &Url) -> Self {
+        let name = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => url.scheme().to_string(),
+        };
+        Self {
+            name,
+            url: url.clone(),
+            web3: Web3::new(url.to_string()),
+            http: reqwest::Client::new(),
+            health: Default::default(),
+        }
+    }
+
+    /// Sends `eth_getCode` requests for all addresses in a single JSON-RPC batch.
+    /// Errors returned for separate items do not fail the whole batch.
+    async fn get_code_batch(
+        &self,
+        addresses: &[String],
+    ) -> Result<Vec<Result<Option<String>, anyhow::Error>>, anyhow::Error> {
+        let requests: Vec<_> = addresses
+            .iter()
+            .enumerate()
+            .map(|(id, address)| {
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "method": "eth_getCode",
+                    "params": [address, "latest"],
+                })
+            })
+            .collect();
+        let responses: Vec<JsonRpcResponse> = self
+            .http
+            .post(self.url.clone())
+            .json(&requests)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        // Responses to batch requests may be returned in any order
+        let mut results: Vec<Option<Result<Option<String>, anyhow::Error>>> =
+            addresses.iter().map(|_| None).collect();
+        for response in responses {
+            let result = usize::try_from(response.id)
+                .ok()
+                .and_then(|id| results.get_mut(id));
+            if let Some(result) = result {
+                *result = Some(match response.error {
+                    Some(err) => Err(err.into()),
+                    None => Ok(response.result),
+                });
+            }
+        }
+        Ok(results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| Err(anyhow::anyhow!("no response in the batch")))
+            })
+            .collect())
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<Option<T>, anyhow::Error> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": method,
+            "params": params,
+        });
+        let response: JsonRpcResponse<T> = self
+            .http
+            .post(self.url.clone())
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        match response.error {
+            Some(err) => Err(err.into()),
+            None => Ok(response.result),
+        }
+    }
+
+    fn is_available(&self, now: Instant) -> bool {
+        self.health
+            .read()
+            .circuit_open_until
+            .map_or(true, |until| until <= now)
+    }
+
+    fn on_success(&self) {
+        let mut health = self.health.write();
+        health.score = health.score * 0.9 + 0.1;
+        health.consecutive_failures = 0;
+        health.circuit_open_until = None;
+        metrics::RPC_PROVIDER_HEALTH
+            .with_label_values(&[self.name.as_str()])
+            .set(health.score);
+    }
+
+    fn on_failure(&self, config: &FailoverConfig) {
+        let mut health = self.health.write();
+        health.score *= 0.9;
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= config.failure_threshold {
+            if health.circuit_open_until.is_none() {
+                tracing::warn!(
+                    "rpc provider {} is unavailable after {} consecutive failures",
+                    self.name,
+                    health.consecutive_failures
+                );
+            }
+            health.circuit_open_until = Some(Instant::now() + config.circuit_open_duration);
+        }
+        metrics::RPC_PROVIDER_HEALTH
+            .with_label_values(&[self.name.as_str()])
+            .set(health.score);
+    }
+}
+
+/// Fetches the code of contracts (and performs other read-only requests)
+/// using the healthiest of the configured rpc providers.
+pub struct BytecodeFetcher {
+    providers: Vec<Provider>,
+    config: FailoverConfig,
+    batch_size: usize,
+    chain_id: RwLock<Option<u64>>,
+    /// Chains (by name) checked for the code which has not been found on the current one.
+    other_chains: Vec<(String, Web3)>,
+}
+
+impl Default for BytecodeFetcher {
+    fn default() -> Self {
+        let url = Url::parse(DEFAULT_RPC_URL).expect("default rpc url is valid");
+        Self::new(vec![url], FailoverConfig::default()).expect("providers are not empty")
+    }
+}
+
+impl BytecodeFetcher {
+    /// Providers are preferred in the given order unless their health scores differ.
+    pub fn new(urls: Vec<Url>, config: FailoverConfig) -> anyhow::Result<Self> {
+        if urls.is_empty() {
+            anyhow::bail!("at least one rpc provider is required");
+        }
+        Ok(Self {
+            providers: urls.iter().map(Provider::new).collect(),
+            config,
+            batch_size: DEFAULT_BATCH_SIZE,
+            chain_id: RwLock::new(None),
+            other_chains: vec![],
+        })
+    }
+
+    /// Sets rpc endpoints of other chains (by their names) used to hint users
+    /// whose contracts are not found on the current chain.
+    pub fn with_other_chains(mut self, chains: BTreeMap<String, Url>) -> Self {
+        self.other_chains = chains
+            .into_iter()
+            .map(|(name, url)| (name, Web3::new(url.to_string())))
+            .collect();
+        self
+    }
+
+    /// Returns the id of the chain the providers serve. Is requested once and then cached.
+    pub async fn chain_id(&self) -> Result<u64, anyhow::Error> {
+        if let Some(chain_id) = *self.chain_id.read() {
+            return Ok(chain_id);
+        }
+        let chain_id: String = self
+            .send("eth_chainId", serde_json::json!([]))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("chain id is missing in the response"))?;
+        let chain_id = parse_quantity(&chain_id)?;
+        *self.chain_id.write() = Some(chain_id);
+        Ok(chain_id)
+    }
+
+    /// Returns names of other configured chains that have code at the address.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn find_on_other_chains(&self, contract_address: &str) -> Vec<String> {
+        let mut chains = vec![];
+        for (name, web3) in &self.other_chains {
+            let response = tokio::time::timeout(
+                self.config.request_timeout,
+                web3.eth_get_code(contract_address, None),
+            )
+            .await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("request timed out")));
+            match response {
+                Ok(response) if !is_empty_code(response.result.as_deref()) => {
+                    chains.push(name.clone())
+                }
+                Ok(_) => {}
+                Err(err) => tracing::warn!("cannot fetch code from {} chain: {}", name, err),
+            }
+        }
+        chains
+    }
+
+    /// Sets the max number of requests sent in a single JSON-RPC batch.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    pub async fn get_code(&self, contract_address: &str) -> Result<Option<String>, anyhow::Error> {
+        self.request("eth_getCode", |provider| async move {
+            provider.web3.eth_get_code(contract_address, None).await
+        })
+        .await
+        .map(|response| response.result)
+    }
+
+    /// Fetches the code of the contract as of the given block.
+    /// Blocks other than the latest one may require providers to be archive nodes.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn get_code_at(
+        &self,
+        contract_address: &str,
+        block: BlockId,
+    ) -> Result<Option<String>, anyhow::Error> {
+        if block == BlockId::Latest {
+            return self.get_code(contract_address).await;
+        }
+        let params = serde_json::json!([contract_address, block.to_string()]);
+        self.request("eth_getCode", |provider| {
+            provider.send("eth_getCode", params.clone())
+        })
+        .await
+    }
+
+    /// Returns the number of the latest block.
+    pub async fn block_number(&self) -> Result<u64, anyhow::Error> {
+        let block_number: String = self
+            .send("eth_blockNumber", serde_json::json!([]))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("block number is missing in the response"))?;
+        parse_quantity(&block_number)
+    }
+
+    /// Reads the 32 bytes storage slot of the contract at the given block.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn get_storage_at(
+        &self,
+        contract_address: &str,
+        slot: &str,
+        block: BlockId,
+    ) -> Result<Option<String>, anyhow::Error> {
+        self.send(
+            "eth_getStorageAt",
+            serde_json::json!([contract_address, slot, block.to_string()]),
+        )
+        .await
+    }
+
+    /// Executes `eth_call` of the given calldata against the contract at the latest block.
+    pub async fn call(
+        &self,
+        contract_address: &str,
+        data: &str,
+    ) -> Result<Option<String>, anyhow::Error> {
+        self.call_at(contract_address, data, BlockId::Latest).await
+    }
+
+    /// Executes `eth_call` of the given calldata against the contract at the given block.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn call_at(
+        &self,
+        contract_address: &str,
+        data: &str,
+        block: BlockId,
+    ) -> Result<Option<String>, anyhow::Error> {
+        // Is sent as is rather than through `web3`, so that error codes of reverted calls are kept
+        let params = serde_json::json!([
+            { "to": contract_address, "data": data },
+            block.to_string()
+        ]);
+        self.request("eth_call", |provider| {
+            provider.send("eth_call", params.clone())
+        })
+        .await
+    }
+
+    /// Locates the transaction the contract has been created by.
+    ///
+    /// `ots_getContractCreator` is used if supported by the providers (e.g., Erigon).
+    /// Otherwise, the creation block is found by a binary search over the code of the contract
+    /// at past blocks, which requires an archive node, and the transaction deploying the contract
+    /// is looked up among the transactions of the block. Returns `None` if the transaction
+    /// cannot be located (e.g., the contract has been created by another contract).
+    #[instrument(skip(self), level = "debug")]
+    pub async fn find_creation_transaction(
+        &self,
+        contract_address: &str,
+    ) -> Result<Option<CreationTransaction>, anyhow::Error> {
+        let contract_address = contract_address.to_lowercase();
+        let creator: Result<Option<ContractCreator>, _> = self
+            .send(
+                "ots_getContractCreator",
+                serde_json::json!([contract_address]),
+            )
+            .await;
+        let (tx_hash, deployer, block_number) = match creator {
+            Ok(None) => return Ok(None),
+            Ok(Some(creator)) => {
+                let tx: RpcTransaction = self
+                    .send(
+                        "eth_getTransactionByHash",
+                        serde_json::json!([creator.hash]),
+                    )
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("creation transaction is not found"))?;
+                let block_number = tx
+                    .block_number
+                    .ok_or_else(|| anyhow::anyhow!("creation transaction is pending"))?;
+                (
+                    creator.hash,
+                    creator.creator,
+                    parse_quantity(&block_number)?,
+                )
+            }
+            Err(err) => {
+                tracing::debug!("ots_getContractCreator is not available: {}", err);
+                match self.find_creation_block(&contract_address).await? {
+                    Some(block_number) => {
+                        match self
+                            .find_deployment(&contract_address, block_number)
+                            .await?
+                        {
+                            Some(tx) => (tx.hash, tx.from, block_number),
+                            None => return Ok(None),
+                        }
+                    }
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        let block: RpcBlock<String> = self
+            .send(
+                "eth_getBlockByNumber",
+                serde_json::json!([BlockId::Number(block_number).to_string(), false]),
+            )
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("block {block_number} is not found"))?;
+        Ok(Some(CreationTransaction {
+            tx_hash,
+            deployer,
+            block_number,
+            timestamp: parse_quantity(&block.timestamp)?,
+        }))
+    }
+
+    /// Returns the first block the contract has code at, if it has code at the latest one.
+    async fn find_creation_block(
+        &self,
+        contract_address: &str,
+    ) -> Result<Option<u64>, anyhow::Error> {
+        let latest = self.block_number().await?;
+        let has_code = |block| async move {
+            self.get_code_at(contract_address, BlockId::Number(block))
+                .await
+                .map(|code| !is_empty_code(code.as_deref()))
+        };
+        if !has_code(latest).await? {
+            return Ok(None);
+        }
+        let (mut low, mut high) = (0, latest);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if has_code(middle).await? {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+        Ok(Some(low))
+    }
+
+    /// Returns the transaction of the block which has deployed the contract directly.
+    async fn find_deployment(
+        &self,
+        contract_address: &str,
+        block_number: u64,
+    ) -> Result<Option<RpcTransaction>, anyhow::Error> {
+        let block: RpcBlock<RpcTransaction> = self
+            .send(
+                "eth_getBlockByNumber",
+                serde_json::json!([BlockId::Number(block_number).to_string(), true]),
+            )
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("block {block_number} is not found"))?;
+        for tx in block.transactions.into_iter().filter(|tx| tx.to.is_none()) {
+            let receipt: Option<RpcReceipt> = self
+                .send("eth_getTransactionReceipt", serde_json::json!([tx.hash]))
+                .await?;
+            let created = receipt.and_then(|receipt| receipt.contract_address);
+            if created.as_deref() == Some(contract_address) {
+                return Ok(Some(tx));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the contracts created in the block, including the ones created by other
+    /// contracts (e.g., factories), if `trace_block` is supported by the providers
+    /// (e.g., Erigon). Otherwise, only the contracts deployed by the transactions directly are found.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn find_created_contracts(
+        &self,
+        block_number: u64,
+    ) -> Result<Vec<CreatedContract>, anyhow::Error> {
+        let block = BlockId::Number(block_number).to_string();
+        let traces: Result<Option<Vec<RpcTrace>>, _> =
+            self.send("trace_block", serde_json::json!([block])).await;
+        let created: Vec<CreatedContract> = match traces {
+            Ok(traces) => traces
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|trace| trace.kind == "create")
+                .filter_map(|trace| {
+                    Some(CreatedContract {
+                        address: trace.result?.address?,
+                        init_code: trace.action.and_then(|action| action.init),
+                    })
+                })
+                .collect(),
+            // Other errors (e.g., timeouts of nodes tracing large blocks) are returned,
+            // as the fallback would miss contracts created by other contracts
+            Err(err) if is_method_not_found(&err) => {
+                tracing::debug!("trace_block is not available: {}", err);
+                let block: RpcBlock<RpcTransaction> = self
+                    .send("eth_getBlockByNumber", serde_json::json!([block, true]))
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("block {block_number} is not found"))?;
+                let mut created = vec![];
+                for tx in block.transactions.into_iter().filter(|tx| tx.to.is_none()) {
+                    let receipt: Option<RpcReceipt> = self
+                        .send("eth_getTransactionReceipt", serde_json::json!([tx.hash]))
+                        .await?;
+                    created.extend(receipt.and_then(|receipt| receipt.contract_address).map(
+                        |address| CreatedContract {
+                            address,
+                            init_code: tx.input.clone(),
+                        },
+                    ));
+                }
+                created
+            }
+            Err(err) => return Err(err),
+        };
+        Ok(created)
+    }
+
+    /// Sends the request to the healthiest provider.
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<Option<T>, anyhow::Error> {
+        self.request(method, |provider| provider.send(method, params.clone()))
+            .await
+    }
+
+    /// Fetches the code of all contracts using JSON-RPC batches of at most `batch_size` requests.
+    /// Results are returned in the order of addresses. A failure of the whole batch
+    /// is reported for every address of the batch.
+    #[instrument(skip_all, fields(addresses = addresses.len()), level = "debug")]
+    pub async fn get_code_batch(
+        &self,
+        addresses: &[String],
+    ) -> Vec<Result<Option<String>, anyhow::Error>> {
+        let mut results = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(self.batch_size) {
+            match self
+                .request("eth_getCode_batch", |provider| {
+                    provider.get_code_batch(chunk)
+                })
+                .await
+            {
+                Ok(chunk_results) => results.extend(chunk_results),
+                Err(err) => {
+                    let message = err.to_string();
+                    results.extend(chunk.iter().map(|_| Err(anyhow::anyhow!("{message}"))));
+                }
+            }
+        }
+        results
+    }
+
+    /// Returns providers available at the moment, the healthiest go first.
+    fn ranked_providers(&self) -> Vec<&Provider> {
+        let now = Instant::now();
+        let mut providers: Vec<_> = self
+            .providers
+            .iter()
+            .filter(|provider| provider.is_available(now))
+            .collect();
+        providers.sort_by(|x, y| {
+            let (x, y) = (x.health.read().score, y.health.read().score);
+            y.total_cmp(&x)
+        });
+        providers
+    }
+
+    async fn request<'a, T, F, Fut>(&'a self, method: &str, send: F) -> Result<T, anyhow::Error>
+    where
+        F: Fn(&'a Provider) -> Fut,
+        Fut: Future<Output = Result<T, anyhow::Error>>,
+    {
+        let providers = self.ranked_providers();
+        if providers.is_empty() {
+            anyhow::bail!("all rpc providers are unavailable");
+        }
+
+        let mut backoff = self.config.initial_backoff;
+        let mut last_error = None;
+        for attempt in 0..self.config.max_attempts.max(1) as usize {
+            let provider = providers[attempt % providers.len()];
+            if attempt > 0 && attempt % providers.len() == 0 {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(self.config.max_backoff);
+            }
+
+            let result = tokio::time::timeout(self.config.request_timeout, send(provider))
+                .await
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("request timed out")));
+            match result {
+                Ok(response) => {
+                    provider.on_success();
+                    metrics::RPC_REQUESTS
+                        .with_label_values(&[provider.name.as_str(), method, "ok"])
+                        .inc();
+                    return Ok(response);
+                }
+                // The node has processed the request, so there is no reason to retry it
+                Err(err) if is_execution_error(&err) => {
+                    provider.on_success();
+                    metrics::RPC_REQUESTS
+                        .with_label_values(&[provider.name.as_str(), method, "ok"])
+                        .inc();
+                    return Err(err);
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "rpc request {} to {} failed (attempt {}): {}",
+                        method,
+                        provider.name,
+                        attempt + 1,
+                        err
+                    );
+                    provider.on_failure(&self.config);
+                    metrics::RPC_REQUESTS
+                        .with_label_values(&[provider.name.as_str(), method, "error"])
+                        .inc();
+                    last_error = Some(err);
+                }
+            }
+        }
+        Err(last_error.expect("at least one attempt has been made"))
+    }
+}
+
+/// Transaction the contract has been created by.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreationTransaction {
+    pub tx_hash: String,
+    /// Sender of the transaction.
+    pub deployer: String,
+    pub block_number: u64,
+    /// Unix timestamp of the block.
+    pub timestamp: u64,
+}
+
+/// Contract created in a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreatedContract {
+    pub address: Address,
+    /// Init code the contract has been created with, if returned by the provider.
+    pub init_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse<T = String> {
+    id: u64,
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContractCreator {
+    hash: String,
+    creator: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTransaction {
+    hash: String,
+    from: String,
+    to: Option<String>,
+    block_number: Option<String>,
+    #[serde(default)]
+    input: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcBlock<T> {
+    timestamp: String,
+    #[serde(default)]
+    transactions: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcReceipt {
+    contract_address: Option<Address>,
+}
+
+/// Call trace of a block as returned by `trace_block`.
+#[derive(Debug, Deserialize)]
+struct RpcTrace {
+    #[serde(rename = "type")]
+    kind: String,
+    action: Option<RpcTraceAction>,
+    /// Is missing for failed calls.
+    result: Option<RpcTraceResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcTraceAction {
+    /// Is set for contract creations only.
+    init: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcTraceResult {
+    /// Is set for contract creations only.
+    address: Option<Address>,
+}
+
+/// Error returned by the node in the JSON-RPC response.
+#[derive(Debug, Deserialize, thiserror::Error)]
+#[error("{message} (code {code})")]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// Code of the JSON-RPC errors returned for methods not supported by the node.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+fn is_method_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<JsonRpcError>()
+        .map_or(false, |err| err.code == METHOD_NOT_FOUND)
+}
+
+/// Returns true if there is no code at the address (e.g. it is an externally owned account).
+pub fn is_empty_code(code: Option<&str>) -> bool {
+    code.map(|code| code.trim_start_matches("0x").is_empty())
+        .unwrap_or(true)
+}
+
+/// Code of the JSON-RPC errors returned for reverted calls (EIP-1474).
+const EXECUTION_REVERTED: i64 = 3;
+/// Code of the JSON-RPC errors returned by some nodes (e.g., OpenEthereum) for failed executions.
+const VM_EXECUTION_ERROR: i64 = -32015;
+/// Code of the generic JSON-RPC server errors, which some nodes (e.g., older geth and ethermint
+/// based ones) return for reverted calls as well as for failures of the node.
+const SERVER_ERROR: i64 = -32000;
+
+/// Whether the error has been returned by the node after the request has been processed
+/// (e.g., the call has been reverted, or the method is not supported), so that sending
+/// the request again or to another provider would return the same error.
+fn is_execution_error(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<JsonRpcError>() {
+        Some(err) => match err.code {
+            EXECUTION_REVERTED | VM_EXECUTION_ERROR | METHOD_NOT_FOUND => true,
+            SERVER_ERROR => err.message.starts_with("execution reverted"),
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+fn parse_quantity(quantity: &str) -> Result<u64, anyhow::Error> {
+    Ok(u64::from_str_radix(quantity.trim_start_matches("0x"), 16)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::{
+        matchers::{body_partial_json, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn config() -> FailoverConfig {
+        FailoverConfig {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            failure_threshold: 1,
+            circuit_open_duration: Duration::from_secs(60),
+            request_timeout: Duration::from_secs(5),
+        }
+    }
+
+    async fn code_response(server: &MockServer, status: u16) {
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "eth_getCode" }),
+            ))
+            .respond_with(ResponseTemplate::new(status).set_body_json(
+                serde_json::json!({ "jsonrpc": "2.0", "id": "318", "result": "0x6080" }),
+            ))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_healthy_provider() {
+        let failing = MockServer::start().await;
+        code_response(&failing, 503).await;
+        let healthy = MockServer::start().await;
+        code_response(&healthy, 200).await;
+
+        let fetcher = BytecodeFetcher::new(
+            vec![
+                failing.uri().parse().unwrap(),
+                healthy.uri().parse().unwrap(),
+            ],
+            config(),
+        )
+        .unwrap();
+
+        let code = fetcher
+            .get_code("0x0000000000000000000000000000000000000001")
+            .await;
+        assert_eq!(Some("0x6080".to_string()), code.unwrap());
+
+        // The circuit of the failing provider is open, so the healthy one is the only one used
+        let providers = fetcher.ranked_providers();
+        assert_eq!(1, providers.len());
+        assert_eq!(
+            healthy.address().to_string(),
+            providers[0].name,
+            "only the healthy provider should be available"
+        );
+    }
+
+    #[test]
+    fn parse_block_id() {
+        assert_eq!(BlockId::Latest, BlockId::from_str("latest").unwrap());
+        assert_eq!(BlockId::Number(16), BlockId::from_str("16").unwrap());
+        assert_eq!(BlockId::Number(16), BlockId::from_str("0x10").unwrap());
+        BlockId::from_str("finalized").expect_err("unsupported tag");
+        assert_eq!("0x10", BlockId::Number(16).to_string());
+    }
+
+    #[tokio::test]
+    async fn code_at_block() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "method": "eth_getCode",
+                "params": ["0x0000000000000000000000000000000000000001", "0x10"],
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({ "jsonrpc": "2.0", "id": 0, "result": "0x6080" }),
+            ))
+            .mount(&server)
+            .await;
+
+        let fetcher = BytecodeFetcher::new(vec![server.uri().parse().unwrap()], config()).unwrap();
+        let code = fetcher
+            .get_code_at(
+                "0x0000000000000000000000000000000000000001",
+                BlockId::Number(16),
+            )
+            .await;
+        assert_eq!(Some("0x6080".to_string()), code.unwrap());
+    }
+
+    #[tokio::test]
+    async fn batch_item_errors() {
+        let server = MockServer::start().await;
+        // Responses are out of order and the second one is missing
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                { "jsonrpc": "2.0", "id": 2, "result": "0x6080" },
+                { "jsonrpc": "2.0", "id": 0, "error": { "code": -32602, "message": "invalid address" } },
+            ])))
+            .mount(&server)
+            .await;
+
+        let fetcher = BytecodeFetcher::new(vec![server.uri().parse().unwrap()], config()).unwrap();
+        let addresses = [
+            "0x01".to_string(),
+            "0x0000000000000000000000000000000000000002".to_string(),
+            "0x0000000000000000000000000000000000000003".to_string(),
+        ];
+        let results = fetcher.get_code_batch(&addresses).await;
+        assert_eq!(3, results.len());
+        assert!(
+            results[0]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .contains("invalid address"),
+            "{:?}",
+            results[0]
+        );
+        results[1].as_ref().expect_err("response is missing");
+        assert_eq!(Some("0x6080"), results[2].as_ref().unwrap().as_deref());
+    }
+
+    #[tokio::test]
+    async fn all_providers_unavailable() {
+        let failing = MockServer::start().await;
+        code_response(&failing, 503).await;
+
+        let fetcher = BytecodeFetcher::new(vec![failing.uri().parse().unwrap()], config()).unwrap();
+        fetcher
+            .get_code("0x0000000000000000000000000000000000000001")
+            .await
+            .expect_err("provider fails");
+        let err = fetcher
+            .get_code("0x0000000000000000000000000000000000000001")
+            .await
+            .expect_err("circuit is open");
+        assert!(err.to_string().contains("unavailable"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn chain_id_is_requested_once() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "eth_chainId" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({ "jsonrpc": "2.0", "id": 0, "result": "0x2329" }),
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let fetcher = BytecodeFetcher::new(vec![server.uri().parse().unwrap()], config()).unwrap();
+        assert_eq!(9001, fetcher.chain_id().await.unwrap());
+        assert_eq!(9001, fetcher.chain_id().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn contracts_are_looked_for_on_other_chains() {
+        let with_code = MockServer::start().await;
+        code_response(&with_code, 200).await;
+        let without_code = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(
+                    serde_json::json!({ "jsonrpc": "2.0", "id": 0, "result": "0x" }),
+                ),
+            )
+            .mount(&without_code)
+            .await;
+        let failing = MockServer::start().await;
+        code_response(&failing, 503).await;
+
+        let current = MockServer::start().await;
+        let fetcher = BytecodeFetcher::new(vec![current.uri().parse().unwrap()], config())
+            .unwrap()
+            .with_other_chains(BTreeMap::from([
+                ("evmos".to_string(), with_code.uri().parse().unwrap()),
+                (
+                    "evmos-testnet".to_string(),
+                    without_code.uri().parse().unwrap(),
+                ),
+                ("ethereum".to_string(), failing.uri().parse().unwrap()),
+            ]));
+        // Chains without the code and unavailable ones are not hinted
+        assert_eq!(
+            vec!["evmos".to_string()],
+            fetcher
+                .find_on_other_chains("0x0000000000000000000000000000000000000001")
+                .await
+        );
+    }
+
+    async fn rpc_response(
+        server: &MockServer,
+        request: serde_json::Value,
+        response: serde_json::Value,
+    ) {
+        let mut body = serde_json::json!({ "jsonrpc": "2.0", "id": 0 });
+        body.as_object_mut()
+            .unwrap()
+            .extend(response.as_object().unwrap().clone());
+        Mock::given(method("POST"))
+            .and(body_partial_json(request))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn creation_transaction_is_found_by_code_history() {
+        let contract = "0x0000000000000000000000000000000000000001";
+        let server = MockServer::start().await;
+        rpc_response(
+            &server,
+            serde_json::json!({ "method": "ots_getContractCreator" }),
+            serde_json::json!({ "error": { "code": -32601, "message": "method not found" } }),
+        )
+        .await;
+        rpc_response(
+            &server,
+            serde_json::json!({ "method": "eth_blockNumber" }),
+            serde_json::json!({ "result": "0x2" }),
+        )
+        .await;
+        for (block, code) in [("0x0", "0x"), ("0x1", "0x6080"), ("0x2", "0x6080")] {
+            rpc_response(
+                &server,
+                serde_json::json!({ "method": "eth_getCode", "params": [contract, block] }),
+                serde_json::json!({ "result": code }),
+            )
+            .await;
+        }
+        rpc_response(
+            &server,
+            serde_json::json!({ "method": "eth_getBlockByNumber", "params": ["0x1", true] }),
+            serde_json::json!({ "result": {
+                "timestamp": "0x63000000",
+                "transactions": [
+                    { "hash": "0xaa", "from": "0xbb", "to": "0xcc", "blockNumber": "0x1" },
+                    { "hash": "0xdd", "from": "0xee", "to": null, "blockNumber": "0x1" },
+                ],
+            } }),
+        )
+        .await;
+        rpc_response(
+            &server,
+            serde_json::json!({ "method": "eth_getBlockByNumber", "params": ["0x1", false] }),
+            serde_json::json!({ "result": { "timestamp": "0x63000000" } }),
+        )
+        .await;
+        rpc_response(
+            &server,
+            serde_json::json!({ "method": "eth_getTransactionReceipt", "params": ["0xdd"] }),
+            serde_json::json!({ "result": { "contractAddress": contract } }),
+        )
+        .await;
+
+        let fetcher = BytecodeFetcher::new(vec![server.uri().parse().unwrap()], config()).unwrap();
+        let creation = fetcher.find_creation_transaction(contract).await.unwrap();
+        assert_eq!(
+            Some(CreationTransaction {
+                tx_hash: "0xdd".to_string(),
+                deployer: "0xee".to_string(),
+                block_number: 1,
+                timestamp: 0x63000000,
+            }),
+            creation
+        );
+    }
+
+    #[test]
+    fn execution_errors_are_told_by_codes() {
+        let error = |code, message: &str| {
+            anyhow::Error::from(JsonRpcError {
+                code,
+                message: message.to_string(),
+            })
+        };
+        assert!(is_execution_error(&error(3, "execution reverted")));
+        assert!(is_execution_error(&error(
+            -32000,
+            "execution reverted: Ownable: caller is not the owner"
+        )));
+        assert!(is_execution_error(&error(
+            METHOD_NOT_FOUND,
+            "the method trace_block does not exist"
+        )));
+        assert!(!is_execution_error(&error(-32000, "header not found")));
+        // Errors not returned by the node are retried, whatever their messages are
+        assert!(!is_execution_error(&anyhow::anyhow!(
+            "cannot connect to the reverted node"
+        )));
+    }
+
+    #[tokio::test]
+    async fn reverted_calls_are_not_retried() {
+        let reverting = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "eth_call" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "error": { "code": 3, "message": "execution reverted" },
+            })))
+            .expect(1)
+            .mount(&reverting)
+            .await;
+        let other = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(0)
+            .mount(&other)
+            .await;
+
+        let fetcher = BytecodeFetcher::new(
+            vec![
+                Url::parse(&reverting.uri()).unwrap(),
+                Url::parse(&other.uri()).unwrap(),
+            ],
+            config(),
+        )
+        .unwrap();
+        let err = fetcher
+            .call("0xbebebebebebebebebebebebebebebebebebebebe", "0x5c60da1b")
+            .await
+            .expect_err("the call is reverted");
+        assert!(is_execution_error(&err), "{err:#}");
+    }
+
+    #[tokio::test]
+    async fn calls_are_made_at_the_given_block() {
+        let beacon = "0xbebebebebebebebebebebebebebebebebebebebe";
+        let implementation = "0x000000000000000000000000cafecafecafecafecafecafecafecafecafecafe";
+        let server = MockServer::start().await;
+        rpc_response(
+            &server,
+            serde_json::json!({
+                "method": "eth_call",
+                "params": [{ "to": beacon, "data": "0x5c60da1b" }, "0x10"],
+            }),
+            serde_json::json!({ "result": implementation }),
+        )
+        .await;
+
+        let fetcher =
+            BytecodeFetcher::new(vec![Url::parse(&server.uri()).unwrap()], config()).unwrap();
+        assert_eq!(
+            Some(implementation.to_string()),
+            fetcher
+                .call_at(beacon, "0x5c60da1b", BlockId::Number(16))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn created_contracts_are_not_looked_up_in_receipts_after_trace_failures() {
+        let server = MockServer::start().await;
+        rpc_response(
+            &server,
+            serde_json::json!({ "method": "trace_block", "params": ["0x10"] }),
+            serde_json::json!({ "error": { "code": -32000, "message": "execution timeout" } }),
+        )
+        .await;
+        rpc_response(
+            &server,
+            serde_json::json!({ "method": "eth_getBlockByNumber", "params": ["0x10", true] }),
+            serde_json::json!({ "result": { "timestamp": "0x63000000", "transactions": [] } }),
+        )
+        .await;
+
+        let fetcher = BytecodeFetcher::new(vec![server.uri().parse().unwrap()], config()).unwrap();
+        assert!(fetcher.find_created_contracts(16).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn created_contracts_are_found_by_traces() {
+        let server = MockServer::start().await;
+        rpc_response(
+            &server,
+            serde_json::json!({ "method": "trace_block", "params": ["0x10"] }),
+            serde_json::json!({ "result": [
+                { "type": "call", "result": { "output": "0x" } },
+                {
+                    "type": "create",
+                    "action": { "init": "0x6080" },
+                    "result": { "address": "0x00000000000000000000000000000000000000AA" },
+                },
+                // Reverted creation
+                { "type": "create", "error": "Reverted" },
+            ] }),
+        )
+        .await;
+
+        let fetcher = BytecodeFetcher::new(vec![server.uri().parse().unwrap()], config()).unwrap();
+        let created = fetcher.find_created_contracts(16).await.unwrap();
+        assert_eq!(
+            vec![CreatedContract {
+                address: "0x00000000000000000000000000000000000000aa"
+                    .parse()
+                    .unwrap(),
+                init_code: Some("0x6080".to_string()),
+            }],
+            created
+        );
+    }
+}
@@ -1,10 +1,27 @@
 use super::compiler::SolidityCompiler;
-use crate::{compiler::Compilers, middleware::Middleware, verifier::Success};
+use crate::{
+    compiler::{self, Compilers, Version},
+    middleware::{Composition, Middleware},
+    rpc::BytecodeFetcher,
+    verifier::{LanguageVerifier, Matchers, Success},
+};
+use ethers_solc::{CompilerInput, CompilerOutput};
 use std::sync::Arc;
 
+/// Outputs compiled along with the requested ones, so that the documentation
+/// could be returned for verified contracts. They do not affect the bytecode.
+const DOCUMENTATION_OUTPUTS: [&str; 2] = ["devdoc", "userdoc"];
+
+/// The first compiler version which supports `storageLayout` output.
+pub const STORAGE_LAYOUT_MIN_VERSION: semver::Version = semver::Version::new(0, 5, 13);
+
 pub struct Client {
     compilers: Arc<Compilers<SolidityCompiler>>,
-    middleware: Option<Arc<dyn Middleware<Success>>>,
+    middlewares: Composition<Success>,
+    matchers: Matchers,
+    reject_hidden_characters: bool,
+    /// Rpc providers the deployed code of verified contracts is fetched from.
+    fetcher: Arc<BytecodeFetcher>,
 }
 
 impl Client {
@@ -23,7 +40,10 @@ impl Client {
     pub fn new_arc(compilers: Arc<Compilers<SolidityCompiler>>) -> Self {
         Self {
             compilers,
-            middleware: None,
+            middlewares: Composition::new(),
+            matchers: Matchers::default(),
+            reject_hidden_characters: false,
+            fetcher: Arc::new(BytecodeFetcher::default()),
         }
     }
 
@@ -36,11 +56,42 @@ impl Client {
         self.with_middleware_arc(Arc::new(middleware))
     }
 
-    /// Add middleware to the client. [`with_middleware`] is more ergonomic if you don't need the `Arc`.
+    /// Add middleware to the end of the client middleware chain.
+    /// [`with_middleware`] is more ergonomic if you don't need the `Arc`.
     ///
     /// [`with_middleware`]: Self::with_middleware
     pub fn with_middleware_arc(mut self, middleware: Arc<impl Middleware<Success>>) -> Self {
-        self.middleware = Some(middleware);
+        self.middlewares.push(middleware);
+        self
+    }
+
+    /// Returns the client sharing the compilers and the settings of this one, but without
+    /// the middlewares (e.g., to re-verify contracts without storing them again).
+    pub fn without_middlewares(&self) -> Self {
+        Self {
+            compilers: self.compilers.clone(),
+            middlewares: Composition::new(),
+            matchers: self.matchers.clone(),
+            reject_hidden_characters: self.reject_hidden_characters,
+            fetcher: self.fetcher.clone(),
+        }
+    }
+
+    /// Replaces the default rpc provider the deployed code of contracts is fetched from.
+    pub fn with_fetcher(mut self, fetcher: Arc<BytecodeFetcher>) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
+    /// Replaces the default strategies the compiled bytecodes are compared with the on-chain one.
+    pub fn with_matchers(mut self, matchers: Matchers) -> Self {
+        self.matchers = matchers;
+        self
+    }
+
+    /// Rejects sources containing hidden or lookalike characters before they are compiled.
+    pub fn with_hidden_characters_rejected(mut self, reject: bool) -> Self {
+        self.reject_hidden_characters = reject;
         self
     }
 
@@ -48,8 +99,120 @@ impl Client {
         self.compilers.as_ref()
     }
 
-    /// Provides a reference to the middleware, if there is any.
-    pub fn middleware(&self) -> Option<&dyn Middleware<Success>> {
-        self.middleware.as_ref().map(|m| m.as_ref())
+    pub fn fetcher(&self) -> &Arc<BytecodeFetcher> {
+        &self.fetcher
+    }
+
+    /// Provides a reference to the middleware chain invoked after successful verifications.
+    pub fn middlewares(&self) -> &Composition<Success> {
+        &self.middlewares
+    }
+}
+
+#[async_trait::async_trait]
+impl LanguageVerifier for Client {
+    type Compiler = SolidityCompiler;
+
+    fn language(&self) -> &'static str {
+        "solidity"
+    }
+
+    fn compilers(&self) -> &Compilers<SolidityCompiler> {
+        self.compilers()
+    }
+
+    fn middlewares(&self) -> &Composition<Success> {
+        self.middlewares()
+    }
+
+    fn matchers(&self) -> &Matchers {
+        &self.matchers
+    }
+
+    fn rejects_hidden_characters(&self) -> bool {
+        self.reject_hidden_characters
+    }
+
+    async fn compile(
+        &self,
+        compiler_version: &Version,
+        input: &CompilerInput,
+    ) -> Result<CompilerOutput, compiler::Error> {
+        let mut input = input.clone();
+        with_additional_outputs(&mut input, compiler_version);
+        self.compilers().compile(compiler_version, &input).await
+    }
+}
+
+/// Adds documentation outputs and the storage layout (if supported by the compiler)
+/// to the contract level output selection of the input.
+fn with_additional_outputs(input: &mut CompilerInput, compiler_version: &Version) {
+    let storage_layout =
+        (compiler_version.version() >= &STORAGE_LAYOUT_MIN_VERSION).then_some("storageLayout");
+    let additional_outputs = DOCUMENTATION_OUTPUTS.into_iter().chain(storage_layout);
+    for file_selection in input.settings.output_selection.0.values_mut() {
+        let contract_selections = file_selection
+            .iter_mut()
+            .filter(|(contract, _)| !contract.is_empty());
+        for (_, outputs) in contract_selections {
+            for output in additional_outputs.clone() {
+                if !outputs.iter().any(|selected| selected == output) {
+                    outputs.push(output.to_string());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::{collections::BTreeMap, str::FromStr};
+
+    fn input() -> CompilerInput {
+        CompilerInput::with_sources(BTreeMap::from([(
+            "A.sol".into(),
+            ethers_solc::artifacts::Source {
+                content: "contract A {}".into(),
+            },
+        )]))
+        .remove(0)
+    }
+
+    #[test]
+    fn documentation_is_selected() {
+        let version = Version::from_str("v0.5.12+commit.7709ece9").unwrap();
+        let mut input = input();
+        with_additional_outputs(&mut input, &version);
+        with_additional_outputs(&mut input, &version);
+
+        let selection = serde_json::to_value(&input.settings.output_selection).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "*": {
+                    "": ["ast"],
+                    "*": [
+                        "abi",
+                        "evm.bytecode",
+                        "evm.deployedBytecode",
+                        "evm.methodIdentifiers",
+                        "devdoc",
+                        "userdoc"
+                    ]
+                }
+            }),
+            selection
+        );
+    }
+
+    #[test]
+    fn storage_layout_is_selected_if_supported() {
+        let version = Version::from_str("v0.5.13+commit.5b0b510c").unwrap();
+        let mut input = input();
+        with_additional_outputs(&mut input, &version);
+
+        let outputs = &input.settings.output_selection.0["*"]["*"];
+        assert_eq!(Some("storageLayout"), outputs.last().map(String::as_str));
     }
 }
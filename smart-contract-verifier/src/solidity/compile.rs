@@ -0,0 +1,22 @@
+use super::{client::Client, standard_json::StandardJsonContent};
+use crate::{compiler::Version, verifier::Error};
+use ethers_solc::{CompilerInput, CompilerOutput};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct CompileRequest {
+    pub compiler_version: Version,
+    pub content: StandardJsonContent,
+}
+
+/// Compiles the standard-json input without comparing the result with any on-chain bytecode.
+/// Compilation errors are returned as [`Error::Compilation`], while warnings
+/// are retained in the output.
+pub async fn compile(client: Arc<Client>, request: CompileRequest) -> Result<CompilerOutput, Error> {
+    let input = CompilerInput::from(request.content);
+    let output = client
+        .compilers()
+        .compile(&request.compiler_version, &input)
+        .await?;
+    Ok(output)
+}
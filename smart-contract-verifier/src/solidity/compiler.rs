@@ -1,14 +1,22 @@
-use super::solc_cli;
-use crate::compiler::{EvmCompiler, Version};
-use ethers_solc::{error::SolcError, CompilerOutput, Solc};
+use super::{extra_settings, solc_cli, solc_js::SolcJs};
+use crate::compiler::{self, EvmCompiler, Version};
+use ethers_solc::{error::SolcError, CompilerOutput};
 use std::path::Path;
 
 #[derive(Default)]
-pub struct SolidityCompiler {}
+pub struct SolidityCompiler {
+    solc_js: Option<SolcJs>,
+}
 
 impl SolidityCompiler {
     pub fn new() -> Self {
-        SolidityCompiler {}
+        SolidityCompiler { solc_js: None }
+    }
+
+    /// Compilers of the versions specified by `solc_js` are run as `soljson.js` builds.
+    pub fn with_solc_js(mut self, solc_js: SolcJs) -> Self {
+        self.solc_js = Some(solc_js);
+        self
     }
 }
 
@@ -20,10 +28,15 @@ impl EvmCompiler for SolidityCompiler {
         ver: &Version,
         input: &ethers_solc::CompilerInput,
     ) -> Result<CompilerOutput, SolcError> {
-        if ver.version() < &semver::Version::new(0, 4, 11) {
-            solc_cli::compile_using_cli(path, input).await
-        } else {
-            Solc::from(path).async_compile(input).await
+        match &self.solc_js {
+            Some(solc_js) if solc_js.is_used_for(ver) => solc_js.compile(path, input).await,
+            _ if ver.version() < &semver::Version::new(0, 4, 11) => {
+                solc_cli::compile_using_cli(path, input).await
+            }
+            _ => match extra_settings::merge(input) {
+                Some(input) => compiler::compile_standard_json(path, &input?).await,
+                None => compiler::compile_standard_json(path, input).await,
+            },
         }
     }
 }
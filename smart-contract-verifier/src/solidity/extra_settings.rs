@@ -0,0 +1,128 @@
+//! Settings of the standard json input which are not modelled by `ethers_solc`:
+//! the EOF settings (`eofVersion`) and evm versions released after it (e.g., "osaka"),
+//! required to compile contracts with experimental via-IR pipelines targeting EOF.
+//!
+//! Such settings are split from the input before it is parsed into [`CompilerInput`],
+//! and are merged back into the input passed to the compiler.
+
+use crate::compiler::Version;
+use ethers_solc::{CompilerInput, EvmVersion};
+use serde_json::{Map, Value};
+use std::{future::Future, str::FromStr};
+
+/// Minimal compiler version accepting the `eofVersion` setting.
+pub const EOF_MIN_VERSION: semver::Version = semver::Version::new(0, 8, 29);
+
+tokio::task_local! {
+    static EXTRA_SETTINGS: Map<String, Value>;
+}
+
+/// Parses the standard json input, returning settings `CompilerInput` would have dropped
+/// or failed on separately.
+pub fn split_input(
+    mut input: Value,
+) -> Result<(CompilerInput, Map<String, Value>), serde_json::Error> {
+    let mut extra = Map::new();
+    if let Some(settings) = input.get_mut("settings").and_then(Value::as_object_mut) {
+        if let Some(eof_version) = settings.remove("eofVersion") {
+            extra.insert("eofVersion".into(), eof_version);
+        }
+        let unknown_evm_version = settings
+            .get("evmVersion")
+            .and_then(Value::as_str)
+            .map(|evm_version| EvmVersion::from_str(evm_version).is_err())
+            .unwrap_or_default();
+        if unknown_evm_version {
+            extra.extend(settings.remove_entry("evmVersion"));
+        }
+    }
+    Ok((serde_json::from_value(input)?, extra))
+}
+
+/// Returns an error message if the compiler does not support the settings.
+pub fn validate(version: &Version, extra: &Map<String, Value>) -> Result<(), String> {
+    if extra.contains_key("eofVersion") && version.version() < &EOF_MIN_VERSION {
+        return Err(format!(
+            "eofVersion setting requires compiler v{EOF_MIN_VERSION} or later"
+        ));
+    }
+    Ok(())
+}
+
+/// Runs `f` with `extra` settings merged into every input compiled by the solidity compiler.
+pub async fn scope<F: Future>(extra: Map<String, Value>, f: F) -> F::Output {
+    EXTRA_SETTINGS.scope(extra, f).await
+}
+
+/// Returns the input to be passed to the compiler, if any extra settings are in the scope.
+pub(super) fn merge(input: &CompilerInput) -> Option<Result<Value, serde_json::Error>> {
+    let extra = EXTRA_SETTINGS
+        .try_with(|extra| extra.clone())
+        .ok()
+        .filter(|extra| !extra.is_empty())?;
+    let merge = || {
+        let mut input = serde_json::to_value(input)?;
+        if let Some(settings) = input.get_mut("settings").and_then(Value::as_object_mut) {
+            settings.extend(extra);
+        }
+        Ok(input)
+    };
+    Some(merge())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn input(settings: Value) -> Value {
+        json!({
+            "language": "Solidity",
+            "sources": { "A.sol": { "content": "contract A {}" } },
+            "settings": settings,
+        })
+    }
+
+    #[tokio::test]
+    async fn eof_settings_are_passed_to_compiler() {
+        let (compiler_input, extra) = split_input(input(json!({
+            "viaIR": true,
+            "evmVersion": "osaka",
+            "eofVersion": 1,
+        })))
+        .unwrap();
+        assert_eq!(
+            json!({ "evmVersion": "osaka", "eofVersion": 1 }),
+            Value::Object(extra.clone())
+        );
+        assert_eq!(None, compiler_input.settings.evm_version);
+
+        assert!(merge(&compiler_input).is_none());
+        let merged = scope(extra, async { merge(&compiler_input) })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(json!("osaka"), merged["settings"]["evmVersion"]);
+        assert_eq!(json!(1), merged["settings"]["eofVersion"]);
+        assert_eq!(json!(true), merged["settings"]["viaIR"]);
+    }
+
+    #[test]
+    fn known_settings_are_kept_in_input() {
+        let (compiler_input, extra) =
+            split_input(input(json!({ "evmVersion": "london" }))).unwrap();
+        assert!(extra.is_empty());
+        assert_eq!(
+            Some(EvmVersion::London),
+            compiler_input.settings.evm_version
+        );
+
+        let extra = Map::from_iter([("eofVersion".to_string(), json!(1))]);
+        let old = Version::from_str("v0.8.28+commit.7893614a").unwrap();
+        let new = Version::from_str("v0.8.29+commit.ab55807c").unwrap();
+        assert!(validate(&old, &extra).is_err());
+        assert_eq!(Ok(()), validate(&new, &extra));
+        assert_eq!(Ok(()), validate(&old, &Map::new()));
+    }
+}
@@ -1,11 +1,17 @@
 mod client;
 mod compiler;
 mod solc_cli;
+mod solc_js;
 mod validator;
 
+pub mod compile;
+pub mod extra_settings;
 pub mod multi_part;
+pub mod normalization;
 pub mod standard_json;
+pub mod syntax_check;
 
-pub use client::Client;
+pub use client::{Client, STORAGE_LAYOUT_MIN_VERSION};
 pub use compiler::SolidityCompiler;
+pub use solc_js::SolcJs;
 pub use validator::SolcValidator;
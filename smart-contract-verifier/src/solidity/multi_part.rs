@@ -1,25 +1,36 @@
 use super::client::Client;
 use crate::{
+    common_types::{Address, MatchAgainst},
     compiler::Version,
-    verifier::{ContractVerifier, Error, Success},
+    rpc::{self, BlockId, BytecodeFetcher},
+    verifier::{ContractNotFound, ContractVerifier, Error, Success},
 };
+use anyhow::anyhow;
 use bytes::Bytes;
 use ethers_solc::{
     artifacts::{BytecodeHash, Libraries, Settings, SettingsMetadata, Source, Sources},
+    remappings::Remapping,
     CompilerInput, EvmVersion,
 };
 use semver::VersionReq;
 use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
-use web3_rpc::web3::Web3;
 use actix_web::error;
 use std::str::FromStr;
 use crate::DisplayBytes;
+use tracing::instrument;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VerificationRequest {
-    pub contract_address: String,
+    pub contract_address: Address,
     pub creation_bytecode: Option<Bytes>,
     pub compiler_version: Version,
+    /// Block at which the deployed code of the contract is fetched.
+    pub block: BlockId,
+    /// Name of the contract to verify, plain or fully qualified (`file.sol:Name`).
+    /// Required only if several compiled contracts match the bytecode.
+    pub contract_name: Option<String>,
+    /// Bytecode the compiled contract is required to match.
+    pub match_against: MatchAgainst,
 
     pub content: MultiFileContent,
 }
@@ -29,7 +40,14 @@ pub struct MultiFileContent {
     pub sources: BTreeMap<PathBuf, String>,
     pub evm_version: Option<EvmVersion>,
     pub optimization_runs: Option<usize>,
+    /// Library addresses by their names. Names may be fully qualified (`file.sol:LibName`),
+    /// so that libraries with the same name declared in different files are distinguished.
     pub contract_libraries: Option<BTreeMap<String, String>>,
+    /// Import remappings (e.g., "@openzeppelin/=lib/openzeppelin-contracts/").
+    pub remappings: Vec<Remapping>,
+    /// Contract level outputs (e.g., "ir", "evm.gasEstimates") to be compiled
+    /// in addition to the ones required for verification.
+    pub extra_outputs: Vec<String>,
 }
 
 impl From<MultiFileContent> for Vec<CompilerInput> {
@@ -38,16 +56,22 @@ impl From<MultiFileContent> for Vec<CompilerInput> {
         settings.optimizer.enabled = Some(content.optimization_runs.is_some());
         settings.optimizer.runs = content.optimization_runs;
         if let Some(libs) = content.contract_libraries {
-            // we have to know filename for library, but we don't know,
-            // so we assume that every file MAY contains all libraries
-            let libs = content
-                .sources
-                .keys()
-                .map(|filename| (PathBuf::from(filename), libs.clone()))
-                .collect();
-            settings.libraries = Libraries { libs };
+            settings.libraries = libraries(&content.sources, libs);
         }
         settings.evm_version = content.evm_version;
+        settings.remappings = content.remappings;
+        for file_selection in settings.output_selection.0.values_mut() {
+            let contract_selections = file_selection
+                .iter_mut()
+                .filter(|(contract, _)| !contract.is_empty());
+            for (_, outputs) in contract_selections {
+                for output in &content.extra_outputs {
+                    if !outputs.contains(output) {
+                        outputs.push(output.clone());
+                    }
+                }
+            }
+        }
 
         let sources: Sources = content
             .sources
@@ -62,10 +86,65 @@ impl From<MultiFileContent> for Vec<CompilerInput> {
     }
 }
 
-pub async fn get_Code(contract_address: &str) -> Result<Option<String>, anyhow::Error> {
-    let rpc = Web3::new("https://evmos-evm.publicnode.com".to_string());
-    match rpc.eth_get_code(contract_address, None).await {
-        Ok(r) =>  {println!("Fetching success!"); return Ok(r.result)},
+/// Assigns libraries to the files declaring them. Fully qualified names (`file.sol:LibName`)
+/// point to the file directly; plain names are assigned to every file declaring
+/// the library, or to all files if no declaration is found.
+fn libraries(
+    sources: &BTreeMap<PathBuf, String>,
+    contract_libraries: BTreeMap<String, String>,
+) -> Libraries {
+    let mut libs: BTreeMap<PathBuf, BTreeMap<String, String>> = BTreeMap::new();
+    for (name, address) in contract_libraries {
+        if let Some((file, name)) = name.rsplit_once(':') {
+            libs.entry(PathBuf::from(file))
+                .or_default()
+                .insert(name.to_string(), address);
+            continue;
+        }
+        let declaring: Vec<_> = sources
+            .iter()
+            .filter(|(_, content)| declares_library(content, &name))
+            .map(|(file, _)| file)
+            .collect();
+        let files = match declaring.is_empty() {
+            true => sources.keys().collect(),
+            false => declaring,
+        };
+        for file in files {
+            libs.entry(file.clone())
+                .or_default()
+                .insert(name.clone(), address.clone());
+        }
+    }
+    Libraries { libs }
+}
+
+fn declares_library(source: &str, name: &str) -> bool {
+    let tokens: Vec<_> = source
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+        .filter(|token| !token.is_empty())
+        .collect();
+    tokens
+        .windows(2)
+        .any(|pair| pair[0] == "library" && pair[1] == name)
+}
+
+pub async fn get_Code(
+    fetcher: &BytecodeFetcher,
+    contract_address: &str,
+) -> Result<Option<String>, anyhow::Error> {
+    get_code_at(fetcher, contract_address, BlockId::Latest).await
+}
+
+/// Fetches the deployed code of the contract as of the given block.
+#[instrument(skip(fetcher), level = "debug")]
+pub async fn get_code_at(
+    fetcher: &BytecodeFetcher,
+    contract_address: &str,
+    block: BlockId,
+) -> Result<Option<String>, anyhow::Error> {
+    match fetcher.get_code_at(contract_address, block).await {
+        Ok(code) => Ok(code),
         Err(e) => {
             tracing::error!("There is no contract {}", e);
             Err(e)
@@ -73,47 +152,98 @@ pub async fn get_Code(contract_address: &str) -> Result<Option<String>, anyhow::
     }
 }
 
+/// Fetches the deployed code of the contract checking there is a contract at the address,
+/// so that no compilation is made for externally owned accounts or mistyped addresses.
+pub(crate) async fn deployed_code(
+    fetcher: &BytecodeFetcher,
+    contract_address: &Address,
+    block: BlockId,
+) -> Result<Bytes, Error> {
+    let code = get_code_at(fetcher, contract_address, block)
+        .await
+        .map_err(|err| Error::Internal(anyhow!("cannot fetch deployed code: {err}")))?;
+    match code {
+        Some(code) if !rpc::is_empty_code(Some(&code)) => DisplayBytes::from_str(&code)
+            .map(|code| code.0)
+            .map_err(|err| Error::Internal(anyhow!("invalid deployed code: {err:?}"))),
+        _ => Err(Error::ContractNotFound(
+            contract_not_found(fetcher, contract_address).await,
+        )),
+    }
+}
+
+async fn contract_not_found(
+    fetcher: &BytecodeFetcher,
+    contract_address: &Address,
+) -> ContractNotFound {
+    let chain_id = match fetcher.chain_id().await {
+        Ok(chain_id) => Some(chain_id),
+        Err(err) => {
+            tracing::warn!("cannot fetch chain id: {}", err);
+            None
+        }
+    };
+    let other_chains = fetcher.find_on_other_chains(contract_address).await;
+    let hint = (!other_chains.is_empty()).then(|| {
+        format!(
+            "The address has code on {} chain(s); make sure the contract is verified on the right chain",
+            other_chains.join(", ")
+        )
+    });
+    ContractNotFound {
+        address: contract_address.clone(),
+        chain_id,
+        hint,
+    }
+}
+
+/// Executes `eth_call` of the given calldata against the contract at the latest block.
+/// Returns `None` if the node has not returned any result (e.g., the call has been reverted).
+#[instrument(skip(fetcher), level = "debug")]
+pub async fn call(
+    fetcher: &BytecodeFetcher,
+    contract_address: &str,
+    data: &str,
+) -> Result<Option<String>, anyhow::Error> {
+    fetcher.call(contract_address, data).await
+}
+
+#[instrument(
+    skip(client, request),
+    fields(contract_address = %request.contract_address),
+    level = "debug"
+)]
 pub async fn verify(client: Arc<Client>, request: VerificationRequest) -> Result<Success, Error> {
     let compiler_version = request.compiler_version;
 
-    let _deployed_bytecode = get_Code(request.contract_address.as_str()).await.expect("invalid address address.");
-    
-    let deployed_bytecode = DisplayBytes::from_str(_deployed_bytecode.expect("no deployed bytecode for this address.").as_str()).expect("invalide bytecode").0;
-    println!("deployed bytecode is {:?}", deployed_bytecode);
+    let deployed_bytecode =
+        deployed_code(client.fetcher(), &request.contract_address, request.block).await?;
+    tracing::debug!(
+        deployed_bytecode = %hex::encode(&deployed_bytecode),
+        "deployed bytecode has been fetched"
+    );
     let verifier = ContractVerifier::new(
-                client.compilers(),
+                client.as_ref(),
                 &compiler_version,
                 request.creation_bytecode,
-                deployed_bytecode
+                deployed_bytecode,
+                request.contract_name,
+                request.match_against,
             )?;
-    // println!("in solidity::multi_part::verify: {:?}", get_Code(request.contract_address.as_str()).await);
-    // let deployed_bytecode = DisplayBytes::from_str(&value.deployed_bytecode)
-    //         .map_err(|err| error::ErrorBadRequest(format!("Invalid deployed bytecode: {err:?}")))?
-    //         .0;
 
+    // Settings metadata options are tried in order of their probability,
+    // so that diagnostics are reported for the most probable settings
     let compiler_inputs: Vec<CompilerInput> = request.content.into();
-    for mut compiler_input in compiler_inputs {
-        for metadata in settings_metadata(&compiler_version) {
-            compiler_input.settings.metadata = metadata;
-            let result = verifier.verify(&compiler_input).await;
-
-            // If no matching contracts have been found, try the next settings metadata option
-            if let Err(Error::NoMatchingContracts) = result {
-                continue;
-            }
-
-            // If any error, it is uncorrectable and should be returned immediately, otherwise
-            // we allow middlewares to process success and only then return it to the caller
-            let success = result?;
-            if let Some(middleware) = client.middleware() {
-                middleware.call(&success).await;
-            }
-            return Ok(success);
-        }
-    }
-
-    // No contracts could be verified
-    Err(Error::NoMatchingContracts)
+    let compiler_inputs = compiler_inputs.into_iter().flat_map(|compiler_input| {
+        settings_metadata(&compiler_version)
+            .into_iter()
+            .map(move |metadata| {
+                let mut compiler_input = compiler_input.clone();
+                compiler_input.settings.metadata = metadata;
+                compiler_input
+            })
+    });
+    verifier.verify_any(compiler_inputs).await
 }
 
 /// Iterates through possible bytecode if required and creates
@@ -176,6 +306,8 @@ mod tests {
                 "some_library".into(),
                 "some_address".into(),
             )])),
+            remappings: vec![],
+            extra_outputs: vec![],
         };
         let expected = r#"{"language":"Solidity","sources":{"source.sol":{"content":"pragma"}},"settings":{"optimizer":{"enabled":true,"runs":200},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"evmVersion":"london","libraries":{"source.sol":{"some_library":"some_address"}}}}"#;
         test_to_input(multi_part, vec![expected]);
@@ -184,11 +316,62 @@ mod tests {
             evm_version: Some(EvmVersion::SpuriousDragon),
             optimization_runs: None,
             contract_libraries: None,
+            remappings: vec![],
+            extra_outputs: vec![],
         };
         let expected = r#"{"language":"Solidity","sources":{"source.sol":{"content":""}},"settings":{"optimizer":{"enabled":false},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"evmVersion":"spuriousDragon","libraries":{}}}"#;
         test_to_input(multi_part, vec![expected]);
     }
 
+    #[test]
+    fn libraries_are_assigned_to_declaring_files() {
+        let multi_part = MultiFileContent {
+            sources: sources(&[
+                ("a.sol", "library L {} contract A {}"),
+                ("b.sol", "library L {} contract B {}"),
+                ("c.sol", "library M {}"),
+            ]),
+            evm_version: Some(EvmVersion::London),
+            optimization_runs: None,
+            contract_libraries: Some(BTreeMap::from([
+                ("b.sol:L".into(), "0xb".into()),
+                ("M".into(), "0xc".into()),
+            ])),
+            remappings: vec![],
+            extra_outputs: vec![],
+        };
+        let expected = r#"{"language":"Solidity","sources":{"a.sol":{"content":"library L {} contract A {}"},"b.sol":{"content":"library L {} contract B {}"},"c.sol":{"content":"library M {}"}},"settings":{"optimizer":{"enabled":false},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"evmVersion":"london","libraries":{"b.sol":{"L":"0xb"},"c.sol":{"M":"0xc"}}}}"#;
+        test_to_input(multi_part, vec![expected]);
+    }
+
+    #[test]
+    fn remappings_to_input() {
+        let multi_part = MultiFileContent {
+            sources: sources(&[("source.sol", "pragma")]),
+            evm_version: Some(EvmVersion::London),
+            optimization_runs: None,
+            contract_libraries: None,
+            remappings: vec![Remapping::from_str("forge-std/=lib/forge-std/src/").unwrap()],
+            extra_outputs: vec![],
+        };
+        let expected = r#"{"language":"Solidity","sources":{"source.sol":{"content":"pragma"}},"settings":{"remappings":["forge-std/=lib/forge-std/src/"],"optimizer":{"enabled":false},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"evmVersion":"london","libraries":{}}}"#;
+        test_to_input(multi_part, vec![expected]);
+    }
+
+    #[test]
+    fn extra_outputs_to_input() {
+        let multi_part = MultiFileContent {
+            sources: sources(&[("source.sol", "pragma")]),
+            evm_version: Some(EvmVersion::London),
+            optimization_runs: None,
+            contract_libraries: None,
+            remappings: vec![],
+            extra_outputs: vec!["ir".into(), "abi".into()],
+        };
+        let expected = r#"{"language":"Solidity","sources":{"source.sol":{"content":"pragma"}},"settings":{"optimizer":{"enabled":false},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers","ir"]}},"evmVersion":"london","libraries":{}}}"#;
+        test_to_input(multi_part, vec![expected]);
+    }
+
     #[test]
     fn yul_and_solidity_to_inputs() {
         let multi_part = MultiFileContent {
@@ -196,6 +379,8 @@ mod tests {
             evm_version: Some(EvmVersion::London),
             optimization_runs: Some(200),
             contract_libraries: None,
+            remappings: vec![],
+            extra_outputs: vec![],
         };
         let expected_solidity = r#"{"language":"Solidity","sources":{"source.sol":{"content":"pragma"}},"settings":{"optimizer":{"enabled":true,"runs":200},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"evmVersion":"london","libraries":{}}}"#;
         let expected_yul = r#"{"language":"Yul","sources":{"source2.yul":{"content":"object \"A\" {}"}},"settings":{"optimizer":{"enabled":true,"runs":200},"outputSelection":{"*":{"":["ast"],"*":["abi","evm.bytecode","evm.deployedBytecode","evm.methodIdentifiers"]}},"evmVersion":"london","libraries":{}}}"#;
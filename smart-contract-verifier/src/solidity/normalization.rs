@@ -0,0 +1,116 @@
+use ethers_solc::{remappings::Remapping, CompilerInput};
+
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
+/// Returns the input with byte order marks stripped from the sources, their line endings
+/// converted to LF and the remappings sorted, if it differs from the given one. Sources
+/// are sorted by their paths already, so inputs submitted in different but equivalent
+/// forms (e.g., checked out on different platforms) are compiled and stored the same way.
+///
+/// Contents of the sources are part of the metadata, so contracts compiled from
+/// the submitted input may only partially match the normalized one.
+pub fn normalize(input: &CompilerInput) -> Option<CompilerInput> {
+    let mut normalized = input.clone();
+    let mut changed = false;
+    for source in normalized.sources.values_mut() {
+        if let Some(content) = normalize_content(&source.content) {
+            source.content = content;
+            changed = true;
+        }
+    }
+    if let Some(remappings) = normalize_remappings(&normalized.settings.remappings) {
+        normalized.settings.remappings = remappings;
+        changed = true;
+    }
+    changed.then_some(normalized)
+}
+
+/// Hex encoded keccak256 hash of the normalized input, so that inputs submitted
+/// in equivalent forms have the same hash. Identifies the input the contract
+/// has been verified with, whichever of the forms has been compiled.
+pub fn input_hash(input: &CompilerInput) -> String {
+    let normalized = normalize(input);
+    let content = serde_json::to_vec(normalized.as_ref().unwrap_or(input))
+        .expect("compiler input is serializable");
+    hex::encode(ethers_core::utils::keccak256(content))
+}
+
+fn normalize_content(content: &str) -> Option<String> {
+    let normalized = content
+        .strip_prefix(BYTE_ORDER_MARK)
+        .unwrap_or(content)
+        .replace("\r\n", "\n");
+    (normalized != content).then_some(normalized)
+}
+
+/// Returns the remappings sorted and deduplicated. The compiler applies the last of
+/// the remappings of the same prefix, so such remappings are kept in their order.
+fn normalize_remappings(remappings: &[Remapping]) -> Option<Vec<Remapping>> {
+    let mut sorted = remappings.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    let ambiguous = sorted.windows(2).any(|pair| pair[0].name == pair[1].name);
+    (!ambiguous && sorted != remappings).then_some(sorted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_solc::artifacts::Source;
+    use pretty_assertions::assert_eq;
+    use std::{collections::BTreeMap, str::FromStr};
+
+    fn input(content: &str, remappings: &[&str]) -> CompilerInput {
+        let mut input = CompilerInput::with_sources(BTreeMap::from([(
+            "A.sol".into(),
+            Source {
+                content: content.into(),
+            },
+        )]))
+        .remove(0);
+        input.settings.remappings = remappings
+            .iter()
+            .map(|remapping| Remapping::from_str(remapping).unwrap())
+            .collect();
+        input
+    }
+
+    #[test]
+    fn inputs_are_normalized() {
+        let submitted = input(
+            "\u{feff}contract A {\r\n}\r\n",
+            &[
+                "forge-std/=lib/forge-std/src/",
+                "@oz/=lib/oz/",
+                "@oz/=lib/oz/",
+            ],
+        );
+        let normalized = normalize(&submitted).unwrap();
+        let expected = input(
+            "contract A {\n}\n",
+            &["@oz/=lib/oz/", "forge-std/=lib/forge-std/src/"],
+        );
+        assert_eq!(
+            serde_json::to_value(&expected).unwrap(),
+            serde_json::to_value(&normalized).unwrap()
+        );
+        assert!(normalize(&normalized).is_none());
+    }
+
+    #[test]
+    fn equivalent_inputs_have_the_same_hash() {
+        let submitted = input("\u{feff}contract A {\r\n}", &["b/=b/", "a/=a/"]);
+        let normalized = input("contract A {\n}", &["a/=a/", "b/=b/"]);
+        assert_eq!(input_hash(&normalized), input_hash(&submitted));
+        assert_ne!(
+            input_hash(&normalized),
+            input_hash(&input("contract B {\n}", &["a/=a/", "b/=b/"]))
+        );
+    }
+
+    #[test]
+    fn remappings_of_the_same_prefix_keep_their_order() {
+        let submitted = input("contract A {}", &["@oz/=lib/oz-v5/", "@oz/=lib/oz-v4/"]);
+        assert!(normalize(&submitted).is_none());
+    }
+}
@@ -246,6 +246,7 @@ pub async fn compile_using_cli(
         Command::new(solc)
             .args(input_args.build())
             .args(input_files.build()?)
+            .kill_on_drop(true)
             .stderr(Stdio::piped())
             .stdout(Stdio::piped())
             .output()
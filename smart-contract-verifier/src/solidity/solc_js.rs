@@ -0,0 +1,167 @@
+//! Module for compiling solidity contracts using emscripten builds of the compiler
+//! (`soljson.js`, the ones solc-js is based on). Such builds run on any platform
+//! a JavaScript runtime is available for, so they are used for compiler versions
+//! which have no native binaries for the platform the service is deployed on.
+
+use crate::compiler::Version;
+use ethers_solc::{
+    error::{SolcError, SolcIoError},
+    CompilerInput, CompilerOutput,
+};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+/// Script run by the JavaScript runtime. Accepts the path to `soljson.js`
+/// and the command ("version" or "compile") as arguments. Standard json input
+/// is read from stdin, and the result is printed to stdout.
+const RUNNER_SCRIPT: &str = r#"
+const soljson = require(require('path').resolve(process.argv[1]));
+const bind = (name, ret, args) => soljson['_' + name] ? soljson.cwrap(name, ret, args) : null;
+if (process.argv[2] === 'version') {
+    const version = bind('solidity_version', 'string', []) || bind('version', 'string', []);
+    process.stdout.write(version());
+} else {
+    const chunks = [];
+    process.stdin.on('data', (chunk) => chunks.push(chunk));
+    process.stdin.on('end', () => {
+        const compile = bind('solidity_compile', 'string', ['string', 'number', 'number'])
+            || bind('compileStandard', 'string', ['string', 'number']);
+        if (!compile) {
+            process.stderr.write('standard json input is not supported by the compiler');
+            process.exit(1);
+        }
+        process.stdout.write(compile(Buffer.concat(chunks).toString(), 0, 0));
+    });
+}
+"#;
+
+/// Runs `soljson.js` compilers of the specified versions via a JavaScript runtime
+/// compatible with Node.js (e.g., `node`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolcJs {
+    runtime: PathBuf,
+    versions: HashSet<Version>,
+}
+
+impl SolcJs {
+    pub fn new(runtime: PathBuf, versions: HashSet<Version>) -> Self {
+        Self { runtime, versions }
+    }
+
+    /// Returns whether the compiler of the version is a `soljson.js` build.
+    pub fn is_used_for(&self, ver: &Version) -> bool {
+        self.versions.contains(ver)
+    }
+
+    pub async fn compile(
+        &self,
+        soljson: &Path,
+        input: &CompilerInput,
+    ) -> Result<CompilerOutput, SolcError> {
+        let input = serde_json::to_vec(input)?;
+        let stdout = self.run(soljson, "compile", Some(&input)).await?;
+        Ok(serde_json::from_slice(&stdout)?)
+    }
+
+    pub async fn version(&self, soljson: &Path) -> Result<semver::Version, SolcError> {
+        let stdout = self.run(soljson, "version", None).await?;
+        let version = String::from_utf8_lossy(&stdout);
+        semver::Version::parse(version.trim())
+            .map_err(|err| SolcError::Message(format!("invalid compiler version: {err}")))
+    }
+
+    async fn run(
+        &self,
+        soljson: &Path,
+        command: &str,
+        stdin: Option<&[u8]>,
+    ) -> Result<Vec<u8>, SolcError> {
+        let mut child = Command::new(&self.runtime)
+            .arg("-e")
+            .arg(RUNNER_SCRIPT)
+            .arg(soljson)
+            .arg(command)
+            .kill_on_drop(true)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| SolcError::Io(SolcIoError::new(err, &self.runtime)))?;
+        // stdin is closed when dropped, so that the runner could start the compilation
+        if let Some(mut child_stdin) = child.stdin.take() {
+            child_stdin
+                .write_all(stdin.unwrap_or_default())
+                .await
+                .map_err(|err| SolcError::Io(SolcIoError::new(err, &self.runtime)))?;
+        }
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|err| SolcError::Io(SolcIoError::new(err, &self.runtime)))?;
+        if !output.status.success() {
+            return Err(SolcError::SolcError(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ));
+        }
+        Ok(output.stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::{fs::OpenOptions, io::Write, os::unix::prelude::OpenOptionsExt, str::FromStr};
+
+    /// Creates a fake runtime which prints the given output ignoring its input.
+    fn runtime(dir: &tempfile::TempDir, name: &str, output: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .mode(0o777)
+            .open(&path)
+            .unwrap();
+        writeln!(file, "#!/bin/sh\ncat > /dev/null\necho '{output}'").unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn runs_compiler_using_runtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let ver = Version::from_str("v0.8.17+commit.8df45f5f").unwrap();
+        let soljson = dir.path().join("soljson.js");
+        let input = CompilerInput {
+            language: "Solidity".to_string(),
+            sources: Default::default(),
+            settings: Default::default(),
+        };
+
+        let solc_js = SolcJs::new(
+            runtime(
+                &dir,
+                "compile",
+                r#"{"errors":[],"sources":{},"contracts":{}}"#,
+            ),
+            HashSet::from([ver.clone()]),
+        );
+        assert!(solc_js.is_used_for(&ver));
+        let output = solc_js
+            .compile(&soljson, &input)
+            .await
+            .expect("output is valid");
+        assert!(output.contracts.is_empty());
+
+        let solc_js = SolcJs::new(
+            runtime(&dir, "version", "0.8.17+commit.8df45f5f.Emscripten.clang"),
+            HashSet::new(),
+        );
+        assert!(!solc_js.is_used_for(&ver));
+        let version = solc_js.version(&soljson).await.unwrap();
+        assert_eq!((0, 8, 17), (version.major, version.minor, version.patch));
+    }
+}
@@ -1,21 +1,33 @@
-use super::client::Client;
+use super::{
+    client::Client,
+    extra_settings,
+    multi_part::{deployed_code, get_code_at},
+    normalization,
+};
 use crate::{
+    common_types::{Address, MatchAgainst},
     compiler::Version,
+    rpc::{BlockId, BytecodeFetcher},
     verifier::{ContractVerifier, Error, Success},
 };
 use bytes::Bytes;
 use ethers_solc::{artifacts::output_selection::OutputSelection, CompilerInput};
 use std::sync::Arc;
-use web3_rpc::web3::Web3;
 use actix_web::error;
-use std::str::FromStr;
-use crate::DisplayBytes;
+use tracing::instrument;
 
 #[derive(Clone)]
 pub struct VerificationRequest {
-    pub contract_address: String,
+    pub contract_address: Address,
     pub creation_bytecode: Option<Bytes>,
     pub compiler_version: Version,
+    /// Block at which the deployed code of the contract is fetched.
+    pub block: BlockId,
+    /// Name of the contract to verify, plain or fully qualified (`file.sol:Name`).
+    /// Required only if several compiled contracts match the bytecode.
+    pub contract_name: Option<String>,
+    /// Bytecode the compiled contract is required to match.
+    pub match_against: MatchAgainst,
 
     pub content: StandardJsonContent,
 }
@@ -23,50 +35,130 @@ pub struct VerificationRequest {
 #[derive(Clone)]
 pub struct StandardJsonContent {
     pub input: CompilerInput,
+    /// Settings not modelled by `CompilerInput` (see [`extra_settings`]).
+    pub extra_settings: serde_json::Map<String, serde_json::Value>,
 }
 
 impl From<StandardJsonContent> for CompilerInput {
     fn from(content: StandardJsonContent) -> Self {
         let mut input = content.input;
 
-        // always overwrite output selection as it customizes what compiler outputs and
-        // is not what is returned to the user, but only used internally by our service
-        let output_selection = OutputSelection::default_output_selection();
+        // the requested output selection is kept, so that additional outputs could be
+        // returned to the user, but is extended with the outputs required for verification
+        let requested = std::mem::take(&mut input.settings.output_selection);
+        let mut output_selection = OutputSelection::default_output_selection();
+        merge_output_selection(&mut output_selection, requested);
         input.settings.output_selection = output_selection;
 
         input
     }
 }
 
-pub async fn get_Code(contract_address: &str) -> Result<Option<String>, anyhow::Error> {
-    let rpc = Web3::new("https://evmos-evm.publicnode.com".to_string());
-    match rpc.eth_get_code(contract_address, None).await {
-        Ok(r) =>  {println!("Fetching success!"); return Ok(r.result)},
-        Err(e) => {
-            tracing::error!("There is no contract {}", e);
-            Err(e)
+/// Adds outputs selected by `other` into `selection`. Outputs selected for the same file
+/// and contract are united, as the compiler does for overlapping selections.
+fn merge_output_selection(selection: &mut OutputSelection, other: OutputSelection) {
+    for (file, contracts) in other.0 {
+        let file_selection = selection.0.entry(file).or_default();
+        for (contract, outputs) in contracts {
+            let selected = file_selection.entry(contract).or_default();
+            for output in outputs {
+                if !selected.contains(&output) {
+                    selected.push(output);
+                }
+            }
         }
     }
 }
 
+pub async fn get_Code(
+    fetcher: &BytecodeFetcher,
+    contract_address: &str,
+) -> Result<Option<String>, anyhow::Error> {
+    get_code_at(fetcher, contract_address, BlockId::Latest).await
+}
+
+#[instrument(
+    skip(client, request),
+    fields(contract_address = %request.contract_address),
+    level = "debug"
+)]
 pub async fn verify(client: Arc<Client>, request: VerificationRequest) -> Result<Success, Error> {
+    let extra_settings = request.content.extra_settings.clone();
+    extra_settings::validate(&request.compiler_version, &extra_settings)
+        .map_err(|err| Error::Compilation(vec![err]))?;
     let compiler_input = CompilerInput::from(request.content);
-    let _deployed_bytecode = get_Code(request.contract_address.as_str()).await.expect("invalid address address.");
-    
-    let deployed_bytecode = DisplayBytes::from_str(_deployed_bytecode.expect("no deployed bytecode for this address.").as_str()).expect("invalide bytecode").0;
+    let deployed_bytecode =
+        deployed_code(client.fetcher(), &request.contract_address, request.block).await?;
 
     let verifier = ContractVerifier::new(
-        client.compilers(),
+        client.as_ref(),
         &request.compiler_version,
         request.creation_bytecode,
-        deployed_bytecode
+        deployed_bytecode,
+        request.contract_name,
+        request.match_against,
     )?;
-    let result = verifier.verify(&compiler_input).await;
+    // The submitted input is compiled first, as the deployed contract has most likely been
+    // compiled from it. The normalized one is tried as well if the submitted one matches
+    // partially or fails (e.g., the sources have been checked out with other line endings)
+    let verification = async {
+        match normalization::normalize(&compiler_input) {
+            Some(normalized) => {
+                verifier
+                    .verify_any_preferring_full([compiler_input, normalized])
+                    .await
+            }
+            None => verifier.verify_any([compiler_input]).await,
+        }
+    };
+    let success = extra_settings::scope(extra_settings.clone(), verification).await?;
+    Ok(Success {
+        extra_settings,
+        ..success
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn requested_output_selection_is_kept() {
+        let mut input = CompilerInput::with_sources(BTreeMap::from([(
+            "A.sol".into(),
+            ethers_solc::artifacts::Source {
+                content: "contract A {}".into(),
+            },
+        )]))
+        .remove(0);
+        input.settings.output_selection = serde_json::from_value(serde_json::json!({
+            "*": { "*": ["abi", "ir"] },
+            "A.sol": { "A": ["evm.gasEstimates"] }
+        }))
+        .unwrap();
+        let input = CompilerInput::from(StandardJsonContent {
+            input,
+            extra_settings: Default::default(),
+        });
 
-    // If case of success, we allow middlewares to process success and only then return it to the caller
-    let success = result?;
-    if let Some(middleware) = client.middleware() {
-        middleware.call(&success).await;
+        let selection = serde_json::to_value(&input.settings.output_selection).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "*": {
+                    "": ["ast"],
+                    "*": [
+                        "abi",
+                        "evm.bytecode",
+                        "evm.deployedBytecode",
+                        "evm.methodIdentifiers",
+                        "ir"
+                    ]
+                },
+                "A.sol": { "A": ["evm.gasEstimates"] }
+            }),
+            selection
+        );
     }
-    Ok(success)
 }
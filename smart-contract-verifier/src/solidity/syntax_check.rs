@@ -0,0 +1,79 @@
+use super::client::Client;
+use crate::{compiler::Version, verifier::Error};
+use ethers_solc::{
+    artifacts::{output_selection::OutputSelection, Settings, Source, Sources},
+    CompilerInput,
+};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationRequest {
+    pub compiler_version: Version,
+    pub sources: BTreeMap<PathBuf, String>,
+}
+
+impl From<ValidationRequest> for Vec<CompilerInput> {
+    fn from(request: ValidationRequest) -> Self {
+        let mut settings = Settings::default();
+        // Only the AST is requested, so that the compiler stops after
+        // parsing and analysis, and no bytecode is generated.
+        settings.output_selection = OutputSelection::from(BTreeMap::from([(
+            "*".to_string(),
+            BTreeMap::from([("".to_string(), vec!["ast".to_string()])]),
+        )]));
+        // Let the compiler choose the default evm version, as older compilers
+        // do not recognize the newer ones.
+        settings.evm_version = None;
+
+        let sources: Sources = request
+            .sources
+            .into_iter()
+            .map(|(name, content)| (name, Source { content }))
+            .collect();
+        // Yul sources are not analyzed separately from the code generation, so are skipped
+        CompilerInput::with_sources(sources)
+            .into_iter()
+            .filter(|input| input.language == "Solidity")
+            .map(|input| input.settings(settings.clone()))
+            .collect()
+    }
+}
+
+/// Checks that the sources are accepted by the compiler without generating the bytecode,
+/// which is much faster than the full compilation.
+/// Syntax and analysis errors are returned as [`Error::Compilation`].
+pub async fn validate(client: Arc<Client>, request: ValidationRequest) -> Result<(), Error> {
+    let compiler_version = request.compiler_version.clone();
+    let inputs: Vec<CompilerInput> = request.into();
+    for input in inputs {
+        client
+            .compilers()
+            .compile(&compiler_version, &input)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn no_bytecode_is_requested() {
+        let request = ValidationRequest {
+            compiler_version: Version::from_str("v0.8.14+commit.80d49f37").unwrap(),
+            sources: BTreeMap::from([
+                (PathBuf::from("A.sol"), "contract A {}".to_string()),
+                (PathBuf::from("B.yul"), "object \"B\" {}".to_string()),
+            ]),
+        };
+        let inputs: Vec<CompilerInput> = request.into();
+        assert_eq!(1, inputs.len(), "yul sources must be skipped");
+        for input in inputs {
+            let selection = serde_json::to_value(&input.settings.output_selection).unwrap();
+            assert_eq!(serde_json::json!({ "*": { "": ["ast"] } }), selection);
+            assert_eq!(None, input.settings.evm_version);
+        }
+    }
+}
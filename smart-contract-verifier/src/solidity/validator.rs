@@ -1,17 +1,31 @@
+use super::solc_js::SolcJs;
 use crate::compiler::{FileValidator, Version};
 use anyhow::Context;
 use async_trait::async_trait;
 use ethers_solc::Solc;
 use std::path::Path;
 
-#[derive(Default, Copy, Clone)]
-pub struct SolcValidator {}
+#[derive(Default, Clone)]
+pub struct SolcValidator {
+    solc_js: Option<SolcJs>,
+}
+
+impl SolcValidator {
+    /// Compilers of the versions specified by `solc_js` are validated as `soljson.js` builds.
+    pub fn with_solc_js(mut self, solc_js: SolcJs) -> Self {
+        self.solc_js = Some(solc_js);
+        self
+    }
+}
 
 #[async_trait]
 impl FileValidator for SolcValidator {
     async fn validate(&self, ver: &Version, path: &Path) -> Result<(), anyhow::Error> {
-        let solc = Solc::new(path);
-        let solc_ver = solc.version().context("could not get compiler version")?;
+        let solc_ver = match &self.solc_js {
+            Some(solc_js) if solc_js.is_used_for(ver) => solc_js.version(path).await,
+            _ => Solc::new(path).version(),
+        }
+        .context("could not get compiler version")?;
         // ignore build and pre metadata
         let solc_ver = semver::Version::new(solc_ver.major, solc_ver.minor, solc_ver.patch);
 
@@ -2,13 +2,13 @@ use super::{
     api_client::SourcifyApiClient,
     types::{ApiRequest, ApiVerificationResponse, Error, Files, ResultItem, Success},
 };
-use crate::MatchType;
+use crate::{Address, MatchType};
 use anyhow::anyhow;
 use std::{collections::BTreeMap, sync::Arc};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VerificationRequest {
-    pub address: String,
+    pub address: Address,
     pub chain: String,
     pub files: BTreeMap<String, String>,
     pub chosen_contract: Option<usize>,
@@ -53,16 +53,17 @@ pub async fn verify(
                     )
                 })
                 .map_err(Error::Internal)?;
-            let files = Files::try_from((api_files_response, &params.chain, &params.address))
-                .map_err(|err| anyhow!("error while parsing Sourcify files response: {}", err))
-                .map_err(Error::Internal)?;
+            // Paths of the files contain the checksummed address
+            let address = params.address.to_checksum();
+            let files =
+                Files::try_from((api_files_response, params.chain.as_str(), address.as_str()))
+                    .map_err(|err| anyhow!("error while parsing Sourcify files response: {}", err))
+                    .map_err(Error::Internal)?;
             let match_type = match_type_from_verification_result(result)?;
             let success = Success::try_from((files, match_type))
                 .map_err(|err| Error::Validation(err.to_string()))?;
 
-            if let Some(middleware) = sourcify_client.middleware() {
-                middleware.call(&success).await;
-            }
+            sourcify_client.middlewares().run(&success).await;
 
             Ok(success)
         }
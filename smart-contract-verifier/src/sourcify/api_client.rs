@@ -1,5 +1,5 @@
 use super::types::{ApiFilesResponse, ApiRequest, ApiVerificationResponse, Success};
-use crate::middleware::Middleware;
+use crate::middleware::{Composition, Middleware};
 use reqwest::Url;
 use reqwest_middleware::ClientWithMiddleware;
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
@@ -8,7 +8,7 @@ use std::{num::NonZeroU32, sync::Arc, time::Duration};
 pub struct SourcifyApiClient {
     host: Url,
     reqwest_client: ClientWithMiddleware,
-    middleware: Option<Arc<dyn Middleware<Success>>>,
+    middlewares: Composition<Success>,
 }
 
 impl SourcifyApiClient {
@@ -30,7 +30,7 @@ impl SourcifyApiClient {
         Ok(Self {
             host,
             reqwest_client,
-            middleware: None,
+            middlewares: Composition::new(),
         })
     }
 
@@ -43,17 +43,18 @@ impl SourcifyApiClient {
         self.with_middleware_arc(Arc::new(middleware))
     }
 
-    /// Add middleware to the client. [`with_middleware`] is more ergonomic if you don't need the `Arc`.
+    /// Add middleware to the end of the client middleware chain.
+    /// [`with_middleware`] is more ergonomic if you don't need the `Arc`.
     ///
     /// [`with_middleware`]: Self::with_middleware
     pub fn with_middleware_arc(mut self, middleware: Arc<impl Middleware<Success>>) -> Self {
-        self.middleware = Some(middleware);
+        self.middlewares.push(middleware);
         self
     }
 
-    /// Provides a reference to the middleware, if there is any.
-    pub fn middleware(&self) -> Option<&dyn Middleware<Success>> {
-        self.middleware.as_ref().map(|m| m.as_ref())
+    /// Provides a reference to the middleware chain invoked after successful verifications.
+    pub fn middlewares(&self) -> &Composition<Success> {
+        &self.middlewares
     }
 }
 
@@ -78,7 +79,14 @@ impl SourcifyApiClient {
     ) -> Result<ApiFilesResponse, anyhow::Error> {
         let url = self
             .host
-            .join(format!("files/any/{}/{}", &params.chain, &params.address).as_str())
+            .join(
+                format!(
+                    "files/any/{}/{}",
+                    &params.chain,
+                    params.address.to_checksum()
+                )
+                .as_str(),
+            )
             .expect("should be valid url");
         self.reqwest_client
             .get(url)
@@ -1,4 +1,4 @@
-use crate::MatchType;
+use crate::{Address, MatchType};
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -9,7 +9,7 @@ use thiserror::Error;
 #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct ApiRequest {
-    pub address: String,
+    pub address: Address,
     pub chain: String,
     pub files: Files,
     pub chosen_contract: Option<usize>,
@@ -63,7 +63,7 @@ pub(super) enum ApiVerificationResponse {
 
 #[derive(Deserialize, Serialize)]
 pub(super) struct ResultItem {
-    pub address: String,
+    pub address: Address,
     pub status: String,
     #[serde(rename = "storageTimestamp")]
     pub storage_timestamp: Option<String>,
@@ -131,7 +131,9 @@ mod tests {
                     }
                 }"#,
                 ApiRequest {
-                    address: "0xcafecafecafecafecafecafecafecafecafecafe".to_string(),
+                    address: "0xcafecafecafecafecafecafecafecafecafecafe"
+                        .parse()
+                        .unwrap(),
                     chain: "100".to_string(),
                     files: Files(BTreeMap::from([
                         ("source.sol".to_string(), "pragma ...".to_string()),
@@ -151,7 +153,9 @@ mod tests {
                     "chosenContract": 1
                 }"#,
                 ApiRequest {
-                    address: "0xcafecafecafecafecafecafecafecafecafecafe".to_string(),
+                    address: "0xcafecafecafecafecafecafecafecafecafecafe"
+                        .parse()
+                        .unwrap(),
                     chain: "100".to_string(),
                     files: Files(BTreeMap::from([
                         ("source.sol".to_string(), "pragma ...".to_string()),
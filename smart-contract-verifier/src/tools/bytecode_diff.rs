@@ -0,0 +1,229 @@
+//! Opcode-level comparison of locally compiled and on-chain bytecodes.
+
+use super::{
+    disassembler::{disassemble, Instruction},
+    lcs,
+};
+use solidity_metadata::MetadataHash;
+use std::ops::Range;
+
+/// Max number of instruction pairs compared while aligning differing parts.
+/// Larger differences are reported as a single changed chunk.
+const MAX_ALIGNMENT_CELLS: usize = 4_000_000;
+
+const PUSH32: u8 = 0x7f;
+
+/// A contiguous part of the bytecodes, which either corresponds in both of them or not.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffChunk {
+    /// Instructions are the same (after masking) in both bytecodes.
+    Equal {
+        local_offset: usize,
+        remote_offset: usize,
+        instructions: usize,
+    },
+    /// Instructions differ. One of the sides may be empty if instructions
+    /// have only been added or removed.
+    Changed {
+        local: Vec<Instruction>,
+        remote: Vec<Instruction>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BytecodeDiff {
+    /// Location of the metadata hash excluded from comparison in the local bytecode.
+    pub local_metadata: Option<Range<usize>>,
+    /// Location of the metadata hash excluded from comparison in the remote bytecode.
+    pub remote_metadata: Option<Range<usize>>,
+    pub chunks: Vec<DiffChunk>,
+}
+
+impl BytecodeDiff {
+    /// Returns `true` if bytecodes differ only by masked parts.
+    pub fn is_identical(&self) -> bool {
+        self.chunks
+            .iter()
+            .all(|chunk| matches!(chunk, DiffChunk::Equal { .. }))
+    }
+}
+
+/// Compares the local (compiled) bytecode with the remote (on-chain) one.
+///
+/// Trailing metadata hashes are excluded from comparison. Immutable values are masked
+/// at the given byte ranges of the local bytecode (`immutableReferences` of the compiler output).
+/// If no ranges are provided, zero PUSH32 values of the local bytecode are considered to be immutables.
+pub fn diff(local: &[u8], remote: &[u8], immutable_references: &[Range<usize>]) -> BytecodeDiff {
+    let local_metadata = metadata_range(local);
+    let remote_metadata = metadata_range(remote);
+
+    let local_code_end = local_metadata
+        .as_ref()
+        .map_or(local.len(), |range| range.start);
+    let remote_code_end = remote_metadata
+        .as_ref()
+        .map_or(remote.len(), |range| range.start);
+    let local_instructions = disassemble(&local[..local_code_end]);
+    let remote_instructions = disassemble(&remote[..remote_code_end]);
+
+    let matches = |local: &Instruction, remote: &Instruction| {
+        if local.opcode != remote.opcode {
+            return false;
+        }
+        if local.push_data == remote.push_data {
+            return true;
+        }
+        let is_immutable = if immutable_references.is_empty() {
+            local.opcode == PUSH32 && local.push_data.iter().all(|byte| *byte == 0)
+        } else {
+            let data = local.offset + 1..local.offset + local.size();
+            immutable_references
+                .iter()
+                .any(|reference| reference.start < data.end && data.start < reference.end)
+        };
+        is_immutable && local.push_data.len() == remote.push_data.len()
+    };
+
+    let pairs = lcs::align(
+        &local_instructions,
+        &remote_instructions,
+        MAX_ALIGNMENT_CELLS,
+        matches,
+    );
+    BytecodeDiff {
+        local_metadata,
+        remote_metadata,
+        chunks: chunks(&local_instructions, &remote_instructions, &pairs),
+    }
+}
+
+/// Returns the location of the CBOR encoded metadata hash placed at the end of the bytecode.
+fn metadata_range(bytecode: &[u8]) -> Option<Range<usize>> {
+    let length_start = bytecode.len().checked_sub(2)?;
+    let length = u16::from_be_bytes([bytecode[length_start], bytecode[length_start + 1]]) as usize;
+    let start = length_start.checked_sub(length)?;
+    match MetadataHash::from_cbor(&bytecode[start..length_start]) {
+        Ok((_, parsed_length)) if parsed_length == length => Some(start..bytecode.len()),
+        _ => None,
+    }
+}
+
+fn chunks(
+    local: &[Instruction],
+    remote: &[Instruction],
+    pairs: &[(usize, usize)],
+) -> Vec<DiffChunk> {
+    fn push_changed(chunks: &mut Vec<DiffChunk>, local: &[Instruction], remote: &[Instruction]) {
+        if !local.is_empty() || !remote.is_empty() {
+            chunks.push(DiffChunk::Changed {
+                local: local.to_vec(),
+                remote: remote.to_vec(),
+            })
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for &(local_index, remote_index) in pairs {
+        push_changed(&mut chunks, &local[i..local_index], &remote[j..remote_index]);
+        match chunks.last_mut() {
+            Some(DiffChunk::Equal { instructions, .. }) if i == local_index && j == remote_index => {
+                *instructions += 1
+            }
+            _ => chunks.push(DiffChunk::Equal {
+                local_offset: local[local_index].offset,
+                remote_offset: remote[remote_index].offset,
+                instructions: 1,
+            }),
+        }
+        i = local_index + 1;
+        j = remote_index + 1;
+    }
+    push_changed(&mut chunks, &local[i..], &remote[j..]);
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DisplayBytes;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    // {"ipfs": h'1220EB23CE2C13EA8739368F952F6C6A4B1F0623D147D2A19B6D4D26A61AB03FCD3E', "solc": 0.8.14}
+    const METADATA_HASH: &str = "a2646970667358221220eb23ce2c13ea8739368f952f6c6a4b1f0623d147d2a19b6d4d26a61ab03fcd3e64736f6c634300080e0033";
+
+    fn bytes(value: &str) -> Vec<u8> {
+        DisplayBytes::from_str(value).expect("valid bytes").0.to_vec()
+    }
+
+    #[test]
+    fn identical_up_to_metadata() {
+        let local = bytes(&format!("0x6080604052{METADATA_HASH}"));
+        let remote = bytes("0x6080604052");
+        let diff = diff(&local, &remote, &[]);
+        assert!(diff.is_identical());
+        assert_eq!(Some(5..local.len()), diff.local_metadata);
+        assert_eq!(None, diff.remote_metadata);
+        assert_eq!(
+            vec![DiffChunk::Equal {
+                local_offset: 0,
+                remote_offset: 0,
+                instructions: 3
+            }],
+            diff.chunks
+        );
+    }
+
+    #[test]
+    fn changed_instruction_is_aligned() {
+        // PUSH1 0x80 PUSH1 0x40 MSTORE CALLVALUE
+        let local = bytes("0x608060405234");
+        // PUSH1 0x80 PUSH1 0x60 MSTORE CALLVALUE
+        let remote = bytes("0x608060605234");
+        let diff = diff(&local, &remote, &[]);
+        assert!(!diff.is_identical());
+        assert_eq!(3, diff.chunks.len());
+        match &diff.chunks[1] {
+            DiffChunk::Changed { local, remote } => {
+                assert_eq!(vec![0x40], local[0].push_data);
+                assert_eq!(vec![0x60], remote[0].push_data);
+            }
+            chunk => panic!("expected changed chunk, got {chunk:?}"),
+        }
+        assert_eq!(
+            DiffChunk::Equal {
+                local_offset: 4,
+                remote_offset: 4,
+                instructions: 2
+            },
+            diff.chunks[2]
+        );
+    }
+
+    #[test]
+    fn immutables_are_masked() {
+        let zeros = "00".repeat(32);
+        let value = "ab".repeat(32);
+        let local = bytes(&format!("0x7f{zeros}50"));
+        let remote = bytes(&format!("0x7f{value}50"));
+        assert!(diff(&local, &remote, &[]).is_identical());
+        assert!(diff(&local, &remote, &[1..33]).is_identical());
+        assert!(
+            !diff(&local, &remote, &[40..72]).is_identical(),
+            "data outside of immutable references must be compared"
+        );
+    }
+
+    #[test]
+    fn added_instructions() {
+        let diff = diff(&bytes("0x6001"), &bytes("0x60015050"), &[]);
+        assert_eq!(
+            DiffChunk::Changed {
+                local: vec![],
+                remote: disassemble(&bytes("0x60015050"))[1..].to_vec(),
+            },
+            diff.chunks[1]
+        );
+    }
+}
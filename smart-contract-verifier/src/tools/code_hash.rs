@@ -0,0 +1,72 @@
+//! Hashes of the deployed code which do not depend on the metadata appended by the compiler.
+//! Contracts compiled from the same sources with different comments or file names,
+//! and factory-deployed clones share the same hash.
+
+use minicbor::{data::Type, Decoder};
+use primitive_types::H256;
+
+/// Returns the code without the CBOR encoded metadata appended to its end.
+/// The code is returned unchanged if it does not end with valid metadata.
+pub fn strip_metadata(code: &[u8]) -> &[u8] {
+    if code.len() < 2 {
+        return code;
+    }
+    let length = u16::from_be_bytes([code[code.len() - 2], code[code.len() - 1]]) as usize;
+    if length + 2 > code.len() {
+        return code;
+    }
+    let metadata_start = code.len() - 2 - length;
+    let metadata = &code[metadata_start..code.len() - 2];
+
+    let mut decoder = Decoder::new(metadata);
+    let is_metadata = matches!(decoder.datatype(), Ok(Type::Map | Type::MapIndef))
+        && decoder.skip().is_ok()
+        && decoder.position() == metadata.len();
+    match is_metadata {
+        true => &code[..metadata_start],
+        false => code,
+    }
+}
+
+/// Returns keccak256 hash of the code with the metadata stripped.
+pub fn stripped_code_hash(code: &[u8]) -> H256 {
+    H256(ethers_core::utils::keccak256(strip_metadata(code)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const MAIN: [u8; 5] = [0x60, 0x80, 0x60, 0x40, 0x52];
+
+    fn with_metadata(ipfs_hash_byte: u8) -> Vec<u8> {
+        // {"ipfs": <34 bytes>, "solc": 0x00080e}
+        let mut metadata = hex::decode("a264697066735822").unwrap();
+        metadata.extend([ipfs_hash_byte; 34]);
+        metadata.extend(hex::decode("64736f6c634300080e").unwrap());
+
+        let mut code = MAIN.to_vec();
+        code.extend(&metadata);
+        code.extend((metadata.len() as u16).to_be_bytes());
+        code
+    }
+
+    #[test]
+    fn metadata_is_stripped() {
+        assert_eq!(&MAIN, strip_metadata(&with_metadata(0x11)));
+        assert_eq!(
+            stripped_code_hash(&with_metadata(0x11)),
+            stripped_code_hash(&with_metadata(0x22))
+        );
+    }
+
+    #[test]
+    fn code_without_metadata_is_unchanged() {
+        assert_eq!(&MAIN, strip_metadata(&MAIN));
+        let mut code = MAIN.to_vec();
+        code.extend([0x00, 0x02]);
+        assert_eq!(&code, strip_metadata(&code));
+        assert_eq!(&[0x01], strip_metadata(&[0x01]));
+    }
+}
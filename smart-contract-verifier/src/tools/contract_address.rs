@@ -0,0 +1,73 @@
+//! Addresses contracts are created at, derived from the parameters of their deployment.
+//! Allow checking that the contract has been deployed with the given init code
+//! before the init code is used as the creation bytecode for verification,
+//! and computing addresses of contracts before they are deployed.
+
+use ethers_core::{
+    types::{H160, H256},
+    utils::keccak256,
+};
+
+/// Returns the address the contract deployed with `CREATE` (e.g., by a transaction) is created at:
+/// `keccak256(rlp([deployer, nonce]))[12..]`.
+pub fn create_address(deployer: H160, nonce: u64) -> H160 {
+    ethers_core::utils::get_contract_address(deployer, nonce)
+}
+
+/// Returns the address the contract deployed by the factory with `CREATE2` is created at
+/// (https://eips.ethereum.org/EIPS/eip-1014).
+pub fn create2_address(factory: H160, salt: H256, init_code: &[u8]) -> H160 {
+    create2_address_from_hash(factory, salt, H256(keccak256(init_code)))
+}
+
+/// Same as [`create2_address`], for the case only the hash of the init code is known:
+/// `keccak256(0xff ++ factory ++ salt ++ init_code_hash)[12..]`.
+pub fn create2_address_from_hash(factory: H160, salt: H256, init_code_hash: H256) -> H160 {
+    let mut preimage = Vec::with_capacity(1 + 20 + 32 + 32);
+    preimage.push(0xff);
+    preimage.extend_from_slice(factory.as_bytes());
+    preimage.extend_from_slice(salt.as_bytes());
+    preimage.extend_from_slice(init_code_hash.as_bytes());
+    H160::from_slice(&keccak256(preimage)[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn create_address_is_derived() {
+        let deployer = H160::from_str("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0").unwrap();
+        assert_eq!(
+            H160::from_str("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d").unwrap(),
+            create_address(deployer, 0)
+        );
+        assert_eq!(
+            H160::from_str("343c43a37d37dff08ae8c4a11544c718abb4fcf8").unwrap(),
+            create_address(deployer, 1)
+        );
+    }
+
+    #[test]
+    fn create2_address_is_derived() {
+        // Examples of EIP-1014
+        assert_eq!(
+            H160::from_str("4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38").unwrap(),
+            create2_address(H160::zero(), H256::zero(), &[0x00])
+        );
+        assert_eq!(
+            H160::from_str("1d8bfDC5D46DC4f61D6b6115972536eBE6A8854C").unwrap(),
+            create2_address(
+                H160::from_low_u64_be(0xdeadbeef),
+                H256::from_low_u64_be(0xcafebabe),
+                &hex::decode("deadbeef".repeat(11)).unwrap()
+            )
+        );
+        assert_eq!(
+            H160::from_str("E33C0C7F7df4809055C3ebA6c09CFe4BaF1BD9e0").unwrap(),
+            create2_address(H160::zero(), H256::zero(), &[])
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Minimal EVM disassembler splitting raw bytecode into instructions.
+
+use std::collections::BTreeSet;
+
+const JUMP: u8 = 0x56;
+const JUMPI: u8 = 0x57;
+const JUMPDEST: u8 = 0x5b;
+
+/// A single instruction of the bytecode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    /// Offset of the opcode from the beginning of the bytecode.
+    pub offset: usize,
+    pub opcode: u8,
+    /// Immediate data of PUSH instructions. May be shorter than expected
+    /// if the bytecode ends unexpectedly.
+    pub push_data: Vec<u8>,
+}
+
+impl Instruction {
+    /// Returns the mnemonic of the opcode, or `None` if the opcode is not defined.
+    pub fn name(&self) -> Option<&'static str> {
+        opcode_name(self.opcode)
+    }
+
+    /// Returns the total size of the instruction including push data.
+    pub fn size(&self) -> usize {
+        1 + self.push_data.len()
+    }
+
+    pub fn is_jump(&self) -> bool {
+        self.opcode == JUMP || self.opcode == JUMPI
+    }
+
+    /// Interprets push data as a bytecode offset. Returns `None` for
+    /// non-PUSH instructions and values too large to be an offset.
+    pub fn push_value(&self) -> Option<usize> {
+        if self.push_data.is_empty() {
+            return None;
+        }
+        let significant = self
+            .push_data
+            .iter()
+            .skip_while(|byte| **byte == 0)
+            .copied()
+            .collect::<Vec<_>>();
+        if significant.len() > std::mem::size_of::<usize>() {
+            return None;
+        }
+        Some(
+            significant
+                .into_iter()
+                .fold(0usize, |value, byte| (value << 8) | byte as usize),
+        )
+    }
+}
+
+/// Returns offsets of all valid jump destinations. JUMPDEST bytes
+/// inside push data are not valid destinations.
+pub fn jump_destinations(instructions: &[Instruction]) -> BTreeSet<usize> {
+    instructions
+        .iter()
+        .filter(|instruction| instruction.opcode == JUMPDEST)
+        .map(|instruction| instruction.offset)
+        .collect()
+}
+
+/// Returns jump targets for PUSH instructions immediately followed by JUMP or JUMPI,
+/// mapping offsets of the PUSH instructions into their target offsets.
+/// Only targets which are valid jump destinations are returned.
+pub fn static_jump_targets(instructions: &[Instruction]) -> Vec<(usize, usize)> {
+    let destinations = jump_destinations(instructions);
+    instructions
+        .windows(2)
+        .filter(|pair| pair[1].is_jump())
+        .filter_map(|pair| {
+            let target = pair[0].push_value()?;
+            destinations
+                .contains(&target)
+                .then_some((pair[0].offset, target))
+        })
+        .collect()
+}
+
+/// Splits the bytecode into instructions.
+///
+/// Does not distinguish code from data sections (e.g., metadata hash or
+/// constructor arguments), so that data is disassembled as if it was code.
+pub fn disassemble(bytecode: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+    while offset < bytecode.len() {
+        let opcode = bytecode[offset];
+        let push_size = push_size(opcode);
+        let data_start = (offset + 1).min(bytecode.len());
+        let data_end = (offset + 1 + push_size).min(bytecode.len());
+        instructions.push(Instruction {
+            offset,
+            opcode,
+            push_data: bytecode[data_start..data_end].to_vec(),
+        });
+        offset += 1 + push_size;
+    }
+    instructions
+}
+
+/// Returns the number of immediate bytes following the opcode.
+pub fn push_size(opcode: u8) -> usize {
+    match opcode {
+        0x60..=0x7f => (opcode - 0x5f) as usize,
+        _ => 0,
+    }
+}
+
+pub fn opcode_name(opcode: u8) -> Option<&'static str> {
+    const PUSH: [&str; 32] = [
+        "PUSH1", "PUSH2", "PUSH3", "PUSH4", "PUSH5", "PUSH6", "PUSH7", "PUSH8", "PUSH9", "PUSH10",
+        "PUSH11", "PUSH12", "PUSH13", "PUSH14", "PUSH15", "PUSH16", "PUSH17", "PUSH18", "PUSH19",
+        "PUSH20", "PUSH21", "PUSH22", "PUSH23", "PUSH24", "PUSH25", "PUSH26", "PUSH27", "PUSH28",
+        "PUSH29", "PUSH30", "PUSH31", "PUSH32",
+    ];
+    const DUP: [&str; 16] = [
+        "DUP1", "DUP2", "DUP3", "DUP4", "DUP5", "DUP6", "DUP7", "DUP8", "DUP9", "DUP10", "DUP11",
+        "DUP12", "DUP13", "DUP14", "DUP15", "DUP16",
+    ];
+    const SWAP: [&str; 16] = [
+        "SWAP1", "SWAP2", "SWAP3", "SWAP4", "SWAP5", "SWAP6", "SWAP7", "SWAP8", "SWAP9", "SWAP10",
+        "SWAP11", "SWAP12", "SWAP13", "SWAP14", "SWAP15", "SWAP16",
+    ];
+    const LOG: [&str; 5] = ["LOG0", "LOG1", "LOG2", "LOG3", "LOG4"];
+
+    let name = match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "KECCAK256",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "PREVRANDAO",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x49 => "BLOBHASH",
+        0x4a => "BLOBBASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x5c => "TLOAD",
+        0x5d => "TSTORE",
+        0x5e => "MCOPY",
+        0x5f => "PUSH0",
+        0x60..=0x7f => PUSH[(opcode - 0x60) as usize],
+        0x80..=0x8f => DUP[(opcode - 0x80) as usize],
+        0x90..=0x9f => SWAP[(opcode - 0x90) as usize],
+        0xa0..=0xa4 => LOG[(opcode - 0xa0) as usize],
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn disassembles_push_data() {
+        let instructions = disassemble(&[0x60, 0x80, 0x60, 0x40, 0x52, 0x00]);
+        let names: Vec<_> = instructions.iter().map(|i| i.name().unwrap()).collect();
+        assert_eq!(vec!["PUSH1", "PUSH1", "MSTORE", "STOP"], names);
+        assert_eq!(vec![0x40], instructions[1].push_data);
+        assert_eq!(2, instructions[1].offset);
+        assert_eq!(4, instructions[2].offset);
+    }
+
+    #[test]
+    fn truncated_push_data() {
+        let instructions = disassemble(&[0x61, 0x01]);
+        assert_eq!(1, instructions.len());
+        assert_eq!(vec![0x01], instructions[0].push_data);
+    }
+
+    #[test]
+    fn jump_targets() {
+        // PUSH1 0x04 JUMP INVALID JUMPDEST PUSH1 0x5b PUSH1 0x06 JUMPI
+        let instructions = disassemble(&[0x60, 0x04, 0x56, 0xfe, 0x5b, 0x60, 0x5b, 0x60, 0x06, 0x57]);
+        assert_eq!(BTreeSet::from([4]), jump_destinations(&instructions));
+        // The second jump targets push data, which is not a valid destination
+        assert_eq!(vec![(0, 4)], static_jump_targets(&instructions));
+    }
+
+    #[test]
+    fn push_values() {
+        let instructions = disassemble(&[0x61, 0x01, 0x02, 0x50]);
+        assert_eq!(Some(0x0102), instructions[0].push_value());
+        assert_eq!(None, instructions[1].push_value());
+    }
+
+    #[test]
+    fn undefined_opcodes() {
+        assert_eq!(None, opcode_name(0x0c));
+        assert_eq!(Some("PUSH32"), opcode_name(0x7f));
+        assert_eq!(Some("SWAP16"), opcode_name(0x9f));
+        assert_eq!(Some("LOG4"), opcode_name(0xa4));
+    }
+
+    #[test]
+    fn cancun_opcodes() {
+        assert_eq!(Some("BLOBHASH"), opcode_name(0x49));
+        assert_eq!(Some("BLOBBASEFEE"), opcode_name(0x4a));
+        assert_eq!(Some("TLOAD"), opcode_name(0x5c));
+        assert_eq!(Some("TSTORE"), opcode_name(0x5d));
+        assert_eq!(Some("MCOPY"), opcode_name(0x5e));
+    }
+}
@@ -0,0 +1,180 @@
+//! EVM Object Format (EOF) containers produced by the compiler for `eofVersion` inputs.
+//! https://eips.ethereum.org/EIPS/eip-3540
+//!
+//! The data section of a deployed container is extended with auxiliary data
+//! (e.g., immutable values) on deployment, and its size in the header is updated accordingly.
+//! So, unlike legacy bytecodes, the compiled container is not a prefix of the deployed one.
+
+use bytes::Bytes;
+
+const MAGIC: [u8; 2] = [0xef, 0x00];
+const KIND_TYPES: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_CONTAINER: u8 = 0x03;
+const KIND_DATA: u8 = 0x04;
+const TERMINATOR: u8 = 0x00;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EofHeader {
+    pub version: u8,
+    pub types_size: u16,
+    pub code_sizes: Vec<u16>,
+    pub container_sizes: Vec<u32>,
+    /// Declared size of the data section.
+    pub data_size: u16,
+    /// Offset of the data size in the container.
+    data_size_offset: usize,
+}
+
+impl EofHeader {
+    /// Whether both containers consist of the same sections,
+    /// not considering the size of the data section.
+    pub fn same_sections(&self, other: &Self) -> bool {
+        self.version == other.version
+            && self.types_size == other.types_size
+            && self.code_sizes == other.code_sizes
+            && self.container_sizes == other.container_sizes
+    }
+}
+
+pub fn is_eof(code: &[u8]) -> bool {
+    code.starts_with(&MAGIC)
+}
+
+/// Returns `None` if the code is not an EOF container or its header is malformed.
+pub fn parse_header(code: &[u8]) -> Option<EofHeader> {
+    let mut reader = Reader { code, position: 0 };
+    if reader.take(2)? != MAGIC {
+        return None;
+    }
+    let version = reader.u8()?;
+
+    reader.expect(KIND_TYPES)?;
+    let types_size = reader.u16()?;
+    reader.expect(KIND_CODE)?;
+    let code_sizes = (0..reader.u16()?)
+        .map(|_| reader.u16())
+        .collect::<Option<Vec<_>>>()?;
+    let mut container_sizes = vec![];
+    if reader.peek()? == KIND_CONTAINER {
+        reader.expect(KIND_CONTAINER)?;
+        container_sizes = (0..reader.u16()?)
+            .map(|_| reader.u32())
+            .collect::<Option<Vec<_>>>()?;
+    }
+    reader.expect(KIND_DATA)?;
+    let data_size_offset = reader.position;
+    let data_size = reader.u16()?;
+    reader.expect(TERMINATOR)?;
+
+    Some(EofHeader {
+        version,
+        types_size,
+        code_sizes,
+        container_sizes,
+        data_size,
+        data_size_offset,
+    })
+}
+
+/// If both codes are EOF containers of the same sections but different data sizes,
+/// returns the remote code with the data size set to the local one. Auxiliary data
+/// of the remote container is then appended to the local container, as the metadata
+/// and constructor arguments are appended to legacy bytecodes.
+pub fn align_data_size(remote: &[u8], local: &[u8]) -> Option<Bytes> {
+    if !is_eof(remote) || !is_eof(local) {
+        return None;
+    }
+    let (remote_header, local_header) = (parse_header(remote)?, parse_header(local)?);
+    if !remote_header.same_sections(&local_header)
+        || remote_header.data_size == local_header.data_size
+    {
+        return None;
+    }
+    let mut aligned = remote.to_vec();
+    let offset = remote_header.data_size_offset;
+    aligned[offset..offset + 2].copy_from_slice(&local_header.data_size.to_be_bytes());
+    Some(aligned.into())
+}
+
+struct Reader<'a> {
+    code: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.code.get(self.position..self.position + len)?;
+        self.position += len;
+        Some(bytes)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.code.get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        (self.u8()? == byte).then_some(())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_be_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes(self.take(4)?.try_into().ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Container of one code section (`PUSH0 DATALOADN 0 STOP`) and a data section.
+    fn container(data_size: u16, data: &[u8]) -> Vec<u8> {
+        let code = [0x5f, 0xd1, 0x00, 0x00, 0x00];
+        let mut container = vec![0xef, 0x00, 0x01, 0x01, 0x00, 0x04, 0x02, 0x00, 0x01];
+        container.extend((code.len() as u16).to_be_bytes());
+        container.push(0x04);
+        container.extend(data_size.to_be_bytes());
+        container.push(0x00);
+        container.extend([0x00, 0x80, 0x00, 0x01]);
+        container.extend(code);
+        container.extend(data);
+        container
+    }
+
+    #[test]
+    fn header_is_parsed() {
+        let header = parse_header(&container(2, &[0xaa, 0xbb])).unwrap();
+        assert_eq!(1, header.version);
+        assert_eq!(4, header.types_size);
+        assert_eq!(vec![5], header.code_sizes);
+        assert!(header.container_sizes.is_empty());
+        assert_eq!(2, header.data_size);
+
+        assert_eq!(None, parse_header(&[0x60, 0x80, 0x60, 0x40]));
+        assert_eq!(None, parse_header(&container(2, &[])[..12]));
+    }
+
+    #[test]
+    fn deployed_data_size_is_aligned() {
+        let local = container(2, &[0xaa, 0xbb]);
+        // 32 bytes of an immutable value appended on deployment
+        let mut data = vec![0xaa, 0xbb];
+        data.extend([0x11; 32]);
+        let remote = container(34, &data);
+
+        let aligned = align_data_size(&remote, &local).unwrap();
+        assert!(aligned.starts_with(&local));
+        assert_eq!(remote.len(), aligned.len());
+
+        assert_eq!(None, align_data_size(&local, &local));
+        assert_eq!(None, align_data_size(&[0x60, 0x80], &local));
+    }
+}
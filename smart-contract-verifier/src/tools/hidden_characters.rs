@@ -0,0 +1,187 @@
+//! Screening of sources for characters which make the code read differently than it compiles
+//! (the "Trojan Source" class): bidirectional controls reordering the displayed text,
+//! invisible characters, and identifiers mixing Latin letters with their lookalikes.
+
+use ethers_solc::artifacts::Sources;
+use serde::Serialize;
+use std::fmt::{self, Display, Formatter};
+
+const BYTE_ORDER_MARK: char = '\u{feff}';
+/// Findings reported for all sources at most, so that e.g. generated sources
+/// full of invisible characters do not bloat the verification results.
+pub const MAX_FINDINGS: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// Bidirectional control character, which reorders the displayed text.
+    BidiControl,
+    /// Zero-width or otherwise invisible character.
+    Invisible,
+    /// Word mixing Latin letters with Cyrillic or Greek ones, which look the same.
+    Homoglyph,
+}
+
+impl FindingKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FindingKind::BidiControl => "bidi_control",
+            FindingKind::Invisible => "invisible",
+            FindingKind::Homoglyph => "homoglyph",
+        }
+    }
+}
+
+impl Display for FindingKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FindingKind::BidiControl => write!(f, "bidirectional control character"),
+            FindingKind::Invisible => write!(f, "invisible character"),
+            FindingKind::Homoglyph => write!(f, "lookalike character"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub file: String,
+    /// 1-based line of the character.
+    pub line: usize,
+    /// 1-based column of the character, counted in characters.
+    pub column: usize,
+    pub kind: FindingKind,
+    /// Code point of the character (e.g., "U+202E").
+    pub code_point: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {} {}",
+            self.file, self.line, self.column, self.kind, self.code_point
+        )
+    }
+}
+
+fn bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}' | '\u{200e}' | '\u{200f}' | '\u{061c}'
+    )
+}
+
+fn invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00ad}'
+            | '\u{115f}'
+            | '\u{1160}'
+            | '\u{180e}'
+            | '\u{200b}'..='\u{200d}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{3164}'
+            | '\u{ffa0}'
+            | BYTE_ORDER_MARK
+    )
+}
+
+fn lookalike(c: char) -> bool {
+    matches!(c, '\u{0370}'..='\u{03ff}' | '\u{0400}'..='\u{04ff}')
+}
+
+/// Returns the hidden and lookalike characters of the source. A byte order mark at
+/// the start of the source is not reported. Lookalike characters are reported only
+/// in words which contain Latin letters as well, so non-English comments are not.
+pub fn scan(file: &str, content: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let content = content.strip_prefix(BYTE_ORDER_MARK).unwrap_or(content);
+    for (line_index, line) in content.lines().enumerate() {
+        let finding = |column_index: usize, c: char, kind| Finding {
+            file: file.to_string(),
+            line: line_index + 1,
+            column: column_index + 1,
+            kind,
+            code_point: format!("U+{:04X}", c as u32),
+        };
+        let mut word: Vec<(usize, char)> = Vec::new();
+        // Is chained with a space, so that the last word of the line is checked as well
+        for (column_index, c) in line.chars().chain(std::iter::once(' ')).enumerate() {
+            if bidi_control(c) {
+                findings.push(finding(column_index, c, FindingKind::BidiControl));
+            } else if invisible(c) {
+                findings.push(finding(column_index, c, FindingKind::Invisible));
+            }
+            if c.is_alphanumeric() || c == '_' || c == '$' {
+                word.push((column_index, c));
+                continue;
+            }
+            if word.iter().any(|(_, c)| c.is_ascii_alphabetic()) {
+                findings.extend(
+                    word.iter()
+                        .filter(|(_, c)| lookalike(*c))
+                        .map(|(column_index, c)| {
+                            finding(*column_index, *c, FindingKind::Homoglyph)
+                        }),
+                );
+            }
+            word.clear();
+        }
+    }
+    findings
+}
+
+/// Returns the hidden and lookalike characters of the sources (see [`scan`]),
+/// up to [`MAX_FINDINGS`] of them.
+pub fn scan_sources(sources: &Sources) -> Vec<Finding> {
+    sources
+        .iter()
+        .flat_map(|(path, source)| scan(&path.to_string_lossy(), &source.content))
+        .take(MAX_FINDINGS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn finding(line: usize, column: usize, kind: FindingKind, code_point: &str) -> Finding {
+        Finding {
+            file: "A.sol".into(),
+            line,
+            column,
+            kind,
+            code_point: code_point.into(),
+        }
+    }
+
+    #[test]
+    fn hidden_characters_are_found() {
+        let content = "\u{feff}contract A {\n    // \u{202e} } \u{2066}if (isAdmin)\u{2069} \u{2066} begin admins only\n    uint256 x\u{200b}y;\n}\n";
+        assert_eq!(
+            vec![
+                finding(2, 8, FindingKind::BidiControl, "U+202E"),
+                finding(2, 12, FindingKind::BidiControl, "U+2066"),
+                finding(2, 25, FindingKind::BidiControl, "U+2069"),
+                finding(2, 27, FindingKind::BidiControl, "U+2066"),
+                finding(3, 14, FindingKind::Invisible, "U+200B"),
+            ],
+            scan("A.sol", content)
+        );
+    }
+
+    #[test]
+    fn lookalike_letters_are_found_in_latin_words() {
+        // Cyrillic "а" in `pаused`; the Greek comment is not reported
+        let content = "bool pаused; // παύση\n";
+        assert_eq!(
+            vec![finding(1, 7, FindingKind::Homoglyph, "U+0430")],
+            scan("A.sol", content)
+        );
+        assert_eq!(
+            "A.sol:1:7: lookalike character U+0430",
+            scan("A.sol", content)[0].to_string()
+        );
+    }
+}
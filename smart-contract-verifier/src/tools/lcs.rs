@@ -0,0 +1,97 @@
+//! Alignment of two sequences by their longest common subsequence, shared by diffs
+//! of bytecodes (instructions) and sources (lines).
+
+/// Returns indices of matching element pairs in increasing order.
+///
+/// Common prefix and suffix are matched as is. The longest common subsequence of the parts
+/// between them takes O(n·m) time and memory, so it is computed only if the table of
+/// the lengths has at most `max_cells` cells. Larger parts are left unmatched.
+pub fn align<T, F>(from: &[T], to: &[T], max_cells: usize, matches: F) -> Vec<(usize, usize)>
+where
+    F: Fn(&T, &T) -> bool,
+{
+    let prefix = from
+        .iter()
+        .zip(to)
+        .take_while(|(from, to)| matches(from, to))
+        .count();
+    let suffix = from[prefix..]
+        .iter()
+        .rev()
+        .zip(to[prefix..].iter().rev())
+        .take_while(|(from, to)| matches(from, to))
+        .count();
+
+    let mut pairs: Vec<_> = (0..prefix).map(|i| (i, i)).collect();
+
+    let from_middle = &from[prefix..from.len() - suffix];
+    let to_middle = &to[prefix..to.len() - suffix];
+    let (n, m) = (from_middle.len(), to_middle.len());
+    let cells = (n + 1).checked_mul(m + 1).unwrap_or(usize::MAX);
+    if n > 0 && m > 0 && cells <= max_cells {
+        let mut lengths = vec![0u32; cells];
+        let index = |i: usize, j: usize| i * (m + 1) + j;
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[index(i, j)] = if matches(&from_middle[i], &to_middle[j]) {
+                    lengths[index(i + 1, j + 1)] + 1
+                } else {
+                    lengths[index(i + 1, j)].max(lengths[index(i, j + 1)])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if matches(&from_middle[i], &to_middle[j]) {
+                pairs.push((prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if lengths[index(i + 1, j)] >= lengths[index(i, j + 1)] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+
+    pairs.extend(
+        (0..suffix)
+            .rev()
+            .map(|i| (from.len() - 1 - i, to.len() - 1 - i)),
+    );
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn common_subsequence_is_aligned() {
+        let from = ['a', 'b', 'c', 'd', 'e'];
+        let to = ['a', 'x', 'c', 'y', 'e'];
+        assert_eq!(
+            vec![(0, 0), (2, 2), (4, 4)],
+            align(&from, &to, usize::MAX, |a, b| a == b)
+        );
+
+        let to = ['b', 'd'];
+        assert_eq!(
+            vec![(1, 0), (3, 1)],
+            align(&from, &to, usize::MAX, |a, b| a == b)
+        );
+    }
+
+    #[test]
+    fn large_parts_are_left_unmatched() {
+        let from = ['a', 'b', 'c', 'd', 'e'];
+        let to = ['a', 'c', 'x', 'e'];
+        // Only common prefix and suffix are matched if the table does not fit
+        assert_eq!(vec![(0, 0), (4, 3)], align(&from, &to, 4, |a, b| a == b));
+        assert_eq!(
+            vec![(0, 0), (2, 1), (4, 3)],
+            align(&from, &to, 12, |a, b| a == b)
+        );
+    }
+}
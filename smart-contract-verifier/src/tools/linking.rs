@@ -0,0 +1,120 @@
+//! Linking of library addresses into bytecode which still contains unresolved
+//! `__$...$__` (or legacy `__LibName___...__`) placeholders.
+
+use bytes::Bytes;
+use ethers_core::types::Address;
+use ethers_solc::artifacts::BytecodeObject;
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+use thiserror::Error;
+
+/// Length of the placeholder in hex characters (20 bytes of the address).
+const PLACEHOLDER_LENGTH: usize = 40;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LinkingError {
+    #[error("invalid address of library {0}: {1}")]
+    InvalidAddress(String, String),
+    #[error("unresolved library placeholders: {}", .0.join(", "))]
+    UnresolvedPlaceholders(Vec<String>),
+    #[error("bytecode is not valid hex")]
+    InvalidBytecode,
+}
+
+/// Returns `true` if the hex encoded bytecode contains library placeholders.
+pub fn has_placeholders(bytecode: &str) -> bool {
+    bytecode.contains("__")
+}
+
+/// Substitutes library addresses into the placeholders of the hex encoded bytecode.
+///
+/// Libraries are keyed either by fully qualified names (`file.sol:LibName`), or by plain names,
+/// which are linked as declared in any of `source_files`.
+pub fn link(
+    bytecode: &str,
+    libraries: &BTreeMap<String, String>,
+    source_files: &[PathBuf],
+) -> Result<Bytes, LinkingError> {
+    let bytecode = bytecode.strip_prefix("0x").unwrap_or(bytecode);
+    let mut object = BytecodeObject::Unlinked(bytecode.to_string());
+    for (name, address) in libraries {
+        let address = Address::from_str(address)
+            .map_err(|err| LinkingError::InvalidAddress(name.clone(), err.to_string()))?;
+        object.link_fully_qualified(name, address);
+        if !name.contains(':') {
+            for file in source_files {
+                object.link(file.to_string_lossy(), name, address);
+            }
+        }
+    }
+
+    if let Some(bytes) = object.resolve() {
+        return Ok(bytes.0.clone());
+    }
+    let unresolved = match &object {
+        BytecodeObject::Unlinked(unlinked) => placeholders(unlinked),
+        BytecodeObject::Bytecode(_) => vec![],
+    };
+    match unresolved.is_empty() {
+        true => Err(LinkingError::InvalidBytecode),
+        false => Err(LinkingError::UnresolvedPlaceholders(unresolved)),
+    }
+}
+
+/// Placeholders left in the bytecode: 40 characters starting and ending with `__`.
+fn placeholders(bytecode: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut rest = bytecode;
+    while let Some(start) = rest.find("__") {
+        let candidate = rest.get(start..start + PLACEHOLDER_LENGTH);
+        match candidate {
+            Some(placeholder) if placeholder.ends_with("__") => {
+                if !result.iter().any(|known| known == placeholder) {
+                    result.push(placeholder.to_string());
+                }
+                rest = &rest[start + PLACEHOLDER_LENGTH..];
+            }
+            _ => rest = &rest[start + 2..],
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_solc::utils::library_hash_placeholder;
+    use pretty_assertions::assert_eq;
+
+    const ADDRESS: &str = "0xcafecafecafecafecafecafecafecafecafecafe";
+
+    fn bytecode(placeholder: &str) -> String {
+        format!("0x6080__{placeholder}__6000")
+    }
+
+    #[test]
+    fn placeholders_are_linked() {
+        let expected = Bytes::from(hex::decode(format!("6080{}6000", &ADDRESS[2..])).unwrap());
+        let hashed = bytecode(&library_hash_placeholder("contracts/L.sol:L"));
+        let files = [PathBuf::from("contracts/L.sol")];
+
+        let libraries = BTreeMap::from([("contracts/L.sol:L".to_string(), ADDRESS.to_string())]);
+        assert_eq!(Ok(expected.clone()), link(&hashed, &libraries, &[]));
+        let libraries = BTreeMap::from([("L".to_string(), ADDRESS.to_string())]);
+        assert_eq!(Ok(expected.clone()), link(&hashed, &libraries, &files));
+
+        let legacy = bytecode(&format!("L{}", "_".repeat(35)));
+        assert_eq!(Ok(expected), link(&legacy, &libraries, &[]));
+    }
+
+    #[test]
+    fn unresolved_placeholders_are_reported() {
+        let placeholder = library_hash_placeholder("contracts/L.sol:L");
+        let libraries = BTreeMap::from([("M".to_string(), ADDRESS.to_string())]);
+        assert_eq!(
+            Err(LinkingError::UnresolvedPlaceholders(vec![format!(
+                "__{placeholder}__"
+            )])),
+            link(&bytecode(&placeholder), &libraries, &[])
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! Hashes of the metadata files the compiler appends to the code, which allow
+//! the metadata (and the sources it references) to be fetched from IPFS or Swarm.
+
+use super::code_hash::strip_metadata;
+use minicbor::Decoder;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MetadataHash {
+    /// Base58 encoded CIDv0 of the metadata file on IPFS.
+    Ipfs(String),
+    /// Hex encoded Swarm hash of the metadata file, along with
+    /// the version of the hashing scheme (`bzzr0` or `bzzr1`).
+    Swarm { version: String, hash: String },
+}
+
+/// Returns the hash of the metadata file referenced by the CBOR encoded metadata
+/// at the end of the code, if any. Metadata appended with `bytecodeHash: none` has no hash.
+pub fn metadata_hash(code: &[u8]) -> Option<MetadataHash> {
+    let stripped = strip_metadata(code);
+    if stripped.len() == code.len() {
+        return None;
+    }
+    let metadata = &code[stripped.len()..code.len() - 2];
+
+    let mut decoder = Decoder::new(metadata);
+    // Metadata produced by the compilers is always a map of definite length
+    let entries = decoder.map().ok()??;
+    for _ in 0..entries {
+        match decoder.str().ok()? {
+            "ipfs" => {
+                let multihash = decoder.bytes().ok()?;
+                return Some(MetadataHash::Ipfs(bs58::encode(multihash).into_string()));
+            }
+            version @ ("bzzr0" | "bzzr1") => {
+                return Some(MetadataHash::Swarm {
+                    version: version.to_string(),
+                    hash: hex::encode(decoder.bytes().ok()?),
+                });
+            }
+            _ => decoder.skip().ok()?,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const MAIN: [u8; 5] = [0x60, 0x80, 0x60, 0x40, 0x52];
+
+    fn with_metadata(metadata: &str) -> Vec<u8> {
+        let metadata = hex::decode(metadata).unwrap();
+        let mut code = MAIN.to_vec();
+        code.extend(&metadata);
+        code.extend((metadata.len() as u16).to_be_bytes());
+        code
+    }
+
+    #[test]
+    fn hashes_are_decoded() {
+        // {"ipfs": 0x1220 + <32 bytes>, "solc": 0x00080e}
+        let multihash = format!("1220{}", "11".repeat(32));
+        let code = with_metadata(&format!("a264697066735822{multihash}64736f6c634300080e"));
+        let cid = match metadata_hash(&code) {
+            Some(MetadataHash::Ipfs(cid)) => cid,
+            other => panic!("ipfs hash expected, got {other:?}"),
+        };
+        assert!(cid.starts_with("Qm"));
+        assert_eq!(
+            hex::decode(multihash).unwrap(),
+            bs58::decode(cid).into_vec().unwrap()
+        );
+
+        // {"bzzr0": <32 bytes>}
+        let code = with_metadata(&format!("a165627a7a72305820{}", "22".repeat(32)));
+        assert_eq!(
+            Some(MetadataHash::Swarm {
+                version: "bzzr0".into(),
+                hash: "22".repeat(32)
+            }),
+            metadata_hash(&code)
+        );
+    }
+
+    #[test]
+    fn code_without_hash_has_none() {
+        assert_eq!(None, metadata_hash(&MAIN));
+        // {"solc": 0x00080e}, i.e. compiled with `bytecodeHash: none`
+        assert_eq!(None, metadata_hash(&with_metadata("a164736f6c634300080e")));
+    }
+}
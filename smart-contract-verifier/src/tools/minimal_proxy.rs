@@ -0,0 +1,44 @@
+//! Detection of EIP-1167 minimal proxies (https://eips.ethereum.org/EIPS/eip-1167),
+//! which delegate all calls to the implementation address embedded into their code.
+
+use primitive_types::H160;
+
+const PREFIX: [u8; 10] = [0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36, 0x3d, 0x73];
+const SUFFIX: [u8; 15] = [
+    0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60, 0x2b, 0x57, 0xfd, 0x5b, 0xf3,
+];
+
+/// Returns the implementation address if the deployed code is an EIP-1167 minimal proxy.
+pub fn implementation(code: &[u8]) -> Option<H160> {
+    if code.len() != PREFIX.len() + 20 + SUFFIX.len() {
+        return None;
+    }
+    let (prefix, rest) = code.split_at(PREFIX.len());
+    let (address, suffix) = rest.split_at(20);
+    (prefix == PREFIX && suffix == SUFFIX).then(|| H160::from_slice(address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn minimal_proxy_is_detected() {
+        let code = hex::decode(
+            "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf3",
+        )
+        .unwrap();
+        assert_eq!(Some(H160::repeat_byte(0xbe)), implementation(&code));
+    }
+
+    #[test]
+    fn other_code_is_not_proxy() {
+        let code = hex::decode(
+            "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf300",
+        )
+        .unwrap();
+        assert_eq!(None, implementation(&code));
+        assert_eq!(None, implementation(&[]));
+    }
+}
@@ -0,0 +1,12 @@
+//! Utilities helping to inspect bytecodes and sources, and debug failed verifications.
+
+pub mod bytecode_diff;
+pub mod code_hash;
+pub mod contract_address;
+pub mod disassembler;
+pub mod eof;
+pub mod hidden_characters;
+pub mod lcs;
+pub mod linking;
+pub mod metadata_hash;
+pub mod minimal_proxy;
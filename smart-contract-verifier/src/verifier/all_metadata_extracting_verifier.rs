@@ -1,17 +1,18 @@
 use super::{
     base::{self, VerificationSuccess},
-    bytecode::{Bytecode, BytecodePart, LocalBytecode, Source},
+    bytecode::{Bytecode, LocalBytecode, Source},
     errors::{BytecodeInitError, VerificationError, VerificationErrorKind},
+    matcher::{MatchInput, Matchers},
 };
 use crate::{
+    tools::eof,
     verifier::bytecode::{CreationTxInput, DeployedBytecode},
     DisplayBytes, MatchType,
 };
 use bytes::Bytes;
 use ethabi::{Constructor, Token};
 use ethers_solc::{artifacts::Contract, Artifact, CompilerOutput};
-use mismatch::Mismatch;
-use solidity_metadata::MetadataHash;
+use std::ops::Range;
 
 /// Verifier used for contract verification.
 ///
@@ -20,6 +21,10 @@ use solidity_metadata::MetadataHash;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Verifier<T> {
     remote_bytecode: Bytecode<T>,
+    /// If set, only the contract with the given name is considered.
+    /// May be either a plain (`Name`) or fully qualified (`file.sol:Name`) name.
+    contract_name: Option<String>,
+    matchers: Matchers,
 }
 
 impl<T: Source + Send + Sync> base::Verifier for Verifier<T> {
@@ -35,15 +40,45 @@ impl<T: Source> Verifier<T> {
         let bytecode = Bytecode::new(input)?;
         Ok(Self {
             remote_bytecode: bytecode,
+            contract_name: None,
+            matchers: Matchers::default(),
         })
     }
 
+    /// Restricts verification to the contract with the given name, so that
+    /// the contract could be chosen among several ones with the same bytecode.
+    pub fn with_contract_name(mut self, contract_name: Option<String>) -> Self {
+        self.contract_name = contract_name;
+        self
+    }
+
+    /// Sets strategies the compiled bytecodes are compared with the remote one.
+    pub fn with_matchers(mut self, matchers: Matchers) -> Self {
+        self.matchers = matchers;
+        self
+    }
+
+    fn is_requested(&self, file_path: &str, contract_name: &str) -> bool {
+        match &self.contract_name {
+            None => true,
+            Some(requested) => match requested.rsplit_once(':') {
+                Some((requested_path, requested_name)) => {
+                    requested_path == file_path && requested_name == contract_name
+                }
+                None => requested == contract_name,
+            },
+        }
+    }
+
     /// Verifies input data provided on initialization by comparing it
     /// with compiler output received when compiling source data locally.
     ///
     /// Iterates through all contracts received from local compilation and
     /// returns [`VerificationSuccess`] with file path and contract name
-    /// of succeeded contract, if any. Otherwise, returns [`None`].
+    /// of succeeded contract, if any. Otherwise, returns all verification errors.
+    ///
+    /// If several contracts match, full matches are preferred and the first one
+    /// is returned; the rest are listed in [`VerificationSuccess::other_matches`].
     pub fn verify(
         &self,
         output: &CompilerOutput,
@@ -67,6 +102,8 @@ impl<T: Source> Verifier<T> {
             };
 
         let mut errors = Vec::new();
+        let mut successes: Vec<VerificationSuccess> = Vec::new();
+        let mut requested_found = false;
         for (path, contracts) in &output.contracts {
             let contracts_modified = {
                 if let Some(contracts_modified) = output_modified.contracts.get(path) {
@@ -82,6 +119,11 @@ impl<T: Source> Verifier<T> {
             };
 
             for (name, contract) in contracts {
+                if !self.is_requested(path, name) {
+                    continue;
+                }
+                requested_found = true;
+
                 let contract_modified = {
                     if let Some(contract) = contracts_modified.get(name) {
                         contract
@@ -104,17 +146,18 @@ impl<T: Source> Verifier<T> {
                         constructor_args,
                         local_bytecode,
                         match_type,
-                    }) => {
-                        return Ok(VerificationSuccess {
-                            file_path: path.clone(),
-                            contract_name: name.clone(),
-                            abi,
-                            constructor_args: constructor_args.map(DisplayBytes::from),
-
-                            local_bytecode_parts: local_bytecode.into(),
-                            match_type,
-                        })
-                    }
+                    }) => successes.push(VerificationSuccess {
+                        file_path: path.clone(),
+                        contract_name: name.clone(),
+                        abi,
+                        constructor_args: constructor_args.map(DisplayBytes::from),
+
+                        creation_bytecode: local_bytecode.creation_tx_input().clone().into(),
+                        deployed_bytecode: local_bytecode.deployed_bytecode().clone().into(),
+                        local_bytecode_parts: local_bytecode.into(),
+                        match_type,
+                        other_matches: vec![],
+                    }),
                     Err(err) => {
                         let error =
                             VerificationError::with_contract(path.clone(), name.clone(), err);
@@ -136,6 +179,26 @@ impl<T: Source> Verifier<T> {
             }
         }
 
+        if !successes.is_empty() {
+            let chosen = successes
+                .iter()
+                .position(|success| success.match_type == MatchType::Full)
+                .unwrap_or_default();
+            let mut success = successes.remove(chosen);
+            success.other_matches = successes
+                .iter()
+                .map(|other| format!("{}:{}", other.file_path, other.contract_name))
+                .collect();
+            return Ok(success);
+        }
+
+        if let (false, Some(contract_name)) = (requested_found, &self.contract_name) {
+            errors.push(VerificationError::new(
+                contract_name.clone(),
+                VerificationErrorKind::ContractNameNotFound,
+            ));
+        }
+
         Err(errors)
     }
 
@@ -151,7 +214,7 @@ impl<T: Source> Verifier<T> {
                 // Corresponding bytecode was not linked properly
                 BytecodeInitError::InvalidCreationTxInput(_)
                 | BytecodeInitError::InvalidDeployedBytecode(_) => {
-                    VerificationErrorKind::LibraryMissed
+                    VerificationErrorKind::LibraryMissed(missing_libraries(contract))
                 }
             })?;
         let deployed_bytecode: Bytecode<DeployedBytecode> =
@@ -160,7 +223,7 @@ impl<T: Source> Verifier<T> {
                 // Corresponding bytecode was not linked properly
                 BytecodeInitError::InvalidCreationTxInput(_)
                 | BytecodeInitError::InvalidDeployedBytecode(_) => {
-                    VerificationErrorKind::LibraryMissed
+                    VerificationErrorKind::LibraryMissed(missing_libraries(contract))
                 }
             })?;
 
@@ -179,7 +242,13 @@ impl<T: Source> Verifier<T> {
             (creation_tx_input_modified, deployed_bytecode_modified),
         )?;
 
-        let match_type = Self::compare_creation_tx_inputs(&self.remote_bytecode, &local_bytecode)?;
+        // Immutable values are written into the deployed code by the constructor,
+        // so the creation transaction input contains no values to mask
+        let immutable_references: Vec<_> = match T::has_constructor_args() {
+            true => vec![],
+            false => immutable_references(contract),
+        };
+        let match_type = self.compare_creation_tx_inputs(&local_bytecode, &immutable_references)?;
 
         let abi = contract.get_abi().map(|abi| abi.into_owned());
 
@@ -198,110 +267,23 @@ impl<T: Source> Verifier<T> {
     }
 
     fn compare_creation_tx_inputs(
-        remote_bytecode: &Bytecode<T>,
+        &self,
         local_bytecode: &LocalBytecode<T>,
+        immutable_references: &[Range<usize>],
     ) -> Result<MatchType, VerificationErrorKind> {
-        let remote_creation_tx_input = remote_bytecode.bytecode();
+        let remote_bytecode = self.remote_bytecode.bytecode();
         let local_creation_tx_input = local_bytecode.bytecode();
-
-        if remote_creation_tx_input.starts_with(local_creation_tx_input) {
-            // If local compilation bytecode is prefix of remote one,
-            // metadata parts are the same and we do not need to compare bytecode parts.
-            return Ok(MatchType::Full);
-        }
-
-        if remote_creation_tx_input.len() < local_creation_tx_input.len() {
-            return Err(VerificationErrorKind::BytecodeLengthMismatch {
-                part: Mismatch::new(
-                    local_creation_tx_input.len(),
-                    remote_creation_tx_input.len(),
-                ),
-                raw: Mismatch::new(
-                    local_creation_tx_input.clone().into(),
-                    remote_creation_tx_input.clone().into(),
-                ),
-            });
-        }
-
-        Self::compare_bytecode_parts(
-            remote_creation_tx_input,
-            local_creation_tx_input,
-            local_bytecode.bytecode_parts(),
-        )?;
-
-        Ok(MatchType::Partial)
-    }
-
-    /// Performs an actual comparison of locally compiled bytecode
-    /// with remote bytecode provided for verification.
-    ///
-    /// # Panics
-    ///
-    /// The function will panic if `remote_raw.len()` is less than `local_raw.len()`.
-    fn compare_bytecode_parts(
-        remote_raw: &Bytes,
-        local_raw: &Bytes,
-        local_parts: &Vec<BytecodePart>,
-    ) -> Result<(), VerificationErrorKind> {
-        // A caller should ensure that this precondition holds.
-        // Currently only `compare_creation_tx_inputs` calls current function,
-        // and it guarantees that `remote_creation_tx_input.len() < local_creation_tx_input.len()`
-        assert!(
-            // if that fails, we would be out of range further anyway
-            remote_raw.len() >= local_raw.len(),
-            "Local bytecode is greater than remote"
-        );
-
-        let mut i = 0usize; // keep track of current processing position of `remote_raw`
-
-        for part in local_parts {
-            match part {
-                BytecodePart::Main { raw } => {
-                    if raw != &remote_raw[i..i + raw.len()] {
-                        return Err(VerificationErrorKind::BytecodeMismatch {
-                            part: Mismatch::new(
-                                raw.clone().into(),
-                                remote_raw.slice(i..i + raw.len()).into(),
-                            ),
-                            raw: Mismatch::new(local_raw.clone().into(), remote_raw.clone().into()),
-                        });
-                    }
-                }
-                BytecodePart::Metadata { metadata, raw, .. } => {
-                    let (remote_metadata, remote_metadata_length) =
-                        MetadataHash::from_cbor(&remote_raw[i..])
-                            .map_err(|err| VerificationErrorKind::MetadataParse(err.to_string()))?;
-
-                    let start_index = i + remote_metadata_length;
-                    let raw_start_index = raw.len() - 2;
-                    if remote_raw[start_index..start_index + 2]
-                        != raw[raw_start_index..raw_start_index + 2]
-                    {
-                        return Err(VerificationErrorKind::MetadataParse(
-                            "metadata length mismatch".into(),
-                        ));
-                    }
-
-                    // We may say the compiler versions does not correspond to each other only in case if both compiler versions are present.
-                    // Otherwise, we cannot say for sure if compiler version is invalid.
-                    if let (Some(metadata_solc), Some(remote_metadata_solc)) =
-                        (&metadata.solc, &remote_metadata.solc)
-                    {
-                        if metadata_solc != remote_metadata_solc {
-                            let expected_solc = metadata_solc.clone();
-                            let remote_solc = remote_metadata_solc.clone();
-                            return Err(VerificationErrorKind::CompilerVersionMismatch(
-                                Mismatch::new(expected_solc, remote_solc),
-                            ));
-                        }
-                    }
-                }
-            }
-
-            i += part.size();
-        }
-
-        Ok(())
+        // Data sections of deployed EOF containers include auxiliary data not known
+        // at compilation, which is compared as a trailing part after the alignment.
+        let aligned = eof::align_data_size(remote_bytecode, local_creation_tx_input);
+
+        self.matchers.compare(&MatchInput {
+            remote: aligned.as_ref().unwrap_or(remote_bytecode),
+            local: local_creation_tx_input,
+            local_parts: local_bytecode.bytecode_parts(),
+            immutable_references,
+            is_creation_tx_input: T::has_constructor_args(),
+        })
     }
 
     /// Extracts constructor arguments from the creation transaction input specified on
@@ -365,6 +347,41 @@ struct ComparisonSuccess<T> {
     pub match_type: MatchType,
 }
 
+/// Fully qualified names of libraries referenced by the unlinked bytecode of the contract.
+fn missing_libraries(contract: &Contract) -> Vec<String> {
+    let link_references = contract
+        .evm
+        .as_ref()
+        .and_then(|evm| evm.bytecode.as_ref())
+        .map(|bytecode| &bytecode.link_references);
+    link_references
+        .into_iter()
+        .flatten()
+        .flat_map(|(file, libraries)| {
+            libraries
+                .keys()
+                .map(move |library| format!("{file}:{library}"))
+        })
+        .collect()
+}
+
+/// Ranges of the deployed bytecode immutable values are written into.
+fn immutable_references(contract: &Contract) -> Vec<Range<usize>> {
+    let immutable_references = contract
+        .evm
+        .as_ref()
+        .and_then(|evm| evm.deployed_bytecode.as_ref())
+        .map(|bytecode| &bytecode.immutable_references);
+    immutable_references
+        .into_iter()
+        .flat_map(|references| references.values().flatten())
+        .map(|offsets| {
+            let start = offsets.start as usize;
+            start..start + offsets.length as usize
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod verifier_initialization_tests {
     use super::{
@@ -399,6 +416,21 @@ mod verifier_initialization_tests {
         Verifier::new(bytecode)
     }
 
+    #[test]
+    fn requested_contract_is_chosen_by_name() {
+        let verifier = new_verifier::<DeployedBytecode>(DEFAULT_DEPLOYED_BYTECODE).unwrap();
+        assert!(verifier.is_requested("A.sol", "A"));
+
+        let verifier = verifier.with_contract_name(Some("A".into()));
+        assert!(verifier.is_requested("A.sol", "A"));
+        assert!(verifier.is_requested("contracts/B.sol", "A"));
+        assert!(!verifier.is_requested("A.sol", "B"));
+
+        let verifier = verifier.with_contract_name(Some("contracts/A.sol:A".into()));
+        assert!(verifier.is_requested("contracts/A.sol", "A"));
+        assert!(!verifier.is_requested("A.sol", "A"));
+    }
+
     #[test]
     fn initialization_with_valid_creation_tx_input() {
         let verifier = new_verifier::<CreationTxInput>(DEFAULT_CREATION_TX_INPUT);
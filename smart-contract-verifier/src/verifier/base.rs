@@ -28,8 +28,14 @@ pub struct VerificationSuccess {
     pub abi: Option<ethabi::Contract>,
     pub constructor_args: Option<DisplayBytes>,
 
+    /// Creation bytecode of the compiled contract (without constructor arguments).
+    pub creation_bytecode: DisplayBytes,
+    pub deployed_bytecode: DisplayBytes,
     pub local_bytecode_parts: LocalBytecodeParts,
     pub match_type: MatchType,
+    /// Fully qualified names (`<file_path>:<contract_name>`) of other contracts
+    /// which bytecode has matched as well.
+    pub other_matches: Vec<String>,
 }
 
 /// Combine different verifiers
@@ -188,6 +188,16 @@ impl<T> LocalBytecode<T> {
         })
     }
 
+    /// Creation bytecode of the locally compiled contract.
+    pub fn creation_tx_input(&self) -> &Bytes {
+        self.creation_tx_input.bytecode()
+    }
+
+    /// Deployed bytecode of the locally compiled contract.
+    pub fn deployed_bytecode(&self) -> &Bytes {
+        self.deployed_bytecode.bytecode()
+    }
+
     pub fn bytecode(&self) -> &Bytes
     where
         T: Source,
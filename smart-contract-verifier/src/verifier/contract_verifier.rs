@@ -1,18 +1,28 @@
 use super::{
     all_metadata_extracting_verifier, base,
+    diagnostics::{self, ContractDiagnostics},
     base::LocalBytecodeParts,
     bytecode::{CreationTxInput, DeployedBytecode},
     errors::{BytecodeInitError, VerificationError, VerificationErrorKind},
+    language::LanguageVerifier,
 };
 use crate::{
-    compiler::{self, Compilers, EvmCompiler},
-    DisplayBytes, MatchType,
+    compiler, metrics,
+    progress::{self, Stage},
+    tools::{code_hash, hidden_characters},
+    Address, DisplayBytes, MatchAgainst, MatchType,
 };
 use anyhow::anyhow;
 use bytes::Bytes;
 use ethers_solc::{CompilerInput, CompilerOutput};
 use mismatch::Mismatch;
-use std::{ops::Add, path::PathBuf};
+use primitive_types::H256;
+use serde::Serialize;
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Add,
+    path::PathBuf,
+};
 use thiserror::Error;
 use tracing::instrument;
 
@@ -26,10 +36,39 @@ pub enum Error {
     Compilation(Vec<String>),
     #[error("{0}")]
     Internal(anyhow::Error),
+    /// Contains diagnostics for each candidate contract explaining why it has not matched.
     #[error("No contract could be verified with provided data")]
-    NoMatchingContracts,
+    NoMatchingContracts(Vec<ContractDiagnostics>),
     #[error("Invalid compiler version: {0}")]
     CompilerVersionMismatch(Mismatch<semver::Version>),
+    #[error("{0}")]
+    ContractNotFound(ContractNotFound),
+    /// Sources contain hidden or lookalike characters, and the language is set to reject them.
+    #[error("Sources contain {} hidden or lookalike characters", .0.len())]
+    HiddenCharacters(Vec<hidden_characters::Finding>),
+}
+
+/// There is no contract code at the address (it is an externally owned account,
+/// or the contract has not been deployed or has been destroyed).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ContractNotFound {
+    pub address: Address,
+    pub chain_id: Option<u64>,
+    /// Set if the contract has been found on one of other configured chains.
+    pub hint: Option<String>,
+}
+
+impl Display for ContractNotFound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "No contract found at {}", self.address)?;
+        if let Some(chain_id) = self.chain_id {
+            write!(f, " on chain {chain_id}")?;
+        }
+        if let Some(hint) = &self.hint {
+            write!(f, ". {hint}")?;
+        }
+        Ok(())
+    }
 }
 
 impl From<BytecodeInitError> for Error {
@@ -52,49 +91,190 @@ impl From<compiler::Error> for Error {
 #[derive(Clone, Debug)]
 pub struct Success {
     pub compiler_input: CompilerInput,
+    /// Settings of the compiler input not modelled by `CompilerInput`
+    /// (see `solidity::extra_settings`), e.g. `eofVersion`.
+    pub extra_settings: serde_json::Map<String, serde_json::Value>,
     pub compiler_output: CompilerOutput,
     pub compiler_version: compiler::Version,
     pub file_path: String,
     pub contract_name: String,
+    /// Name of the contract prefixed with its source file path (`<file_path>:<contract_name>`).
+    pub fully_qualified_name: String,
     pub abi: Option<ethabi::Contract>,
     pub constructor_args: Option<DisplayBytes>,
+    /// Creation bytecode of the compiled contract artifact (without constructor arguments).
+    pub creation_bytecode: DisplayBytes,
+    /// Deployed bytecode of the compiled contract artifact.
+    pub deployed_bytecode: DisplayBytes,
     pub local_bytecode_parts: LocalBytecodeParts,
     pub match_type: MatchType,
+    /// Fully qualified names of other contracts which bytecode has matched as well.
+    /// Is not empty if the contract to verify is ambiguous; the name of the contract
+    /// may be specified in the request to choose among them.
+    pub other_matches: Vec<String>,
+    /// Hash of the metadata-stripped deployed code the contract has been verified against.
+    /// Is `None` if the deployed code has not been provided.
+    pub code_hash: Option<H256>,
 }
 
-pub struct ContractVerifier<'a, T> {
-    compilers: &'a Compilers<T>,
+pub struct ContractVerifier<'a, L> {
+    language: &'a L,
     compiler_version: &'a compiler::Version,
     verifier: Box<dyn base::Verifier<Input = (CompilerOutput, CompilerOutput)>>,
+    /// Is set if the contract matched against the creation transaction input
+    /// is required to match the deployed bytecode as well.
+    deployed_verifier: Option<all_metadata_extracting_verifier::Verifier<DeployedBytecode>>,
+    code_hash: Option<H256>,
 }
 
-impl<'a, T: EvmCompiler> ContractVerifier<'a, T> {
+impl<'a, L: LanguageVerifier> ContractVerifier<'a, L> {
     pub fn new(
-        compilers: &'a Compilers<T>,
+        language: &'a L,
         compiler_version: &'a compiler::Version,
         creation_tx_input: Option<Bytes>,
         deployed_bytecode: Bytes,
+        contract_name: Option<String>,
+        match_against: MatchAgainst,
     ) -> Result<Self, Error> {
+        let code_hash = (!deployed_bytecode.is_empty())
+            .then(|| code_hash::stripped_code_hash(&deployed_bytecode));
+        let creation_tx_input = match (match_against, creation_tx_input) {
+            (MatchAgainst::Deployed, _) => None,
+            (MatchAgainst::Creation | MatchAgainst::Both, None) => {
+                return Err(Error::Initialization(anyhow!(
+                    "creation bytecode is required to match against it"
+                )))
+            }
+            (_, creation_tx_input) => creation_tx_input,
+        };
+        let matchers = language.matchers();
+        let deployed_verifier = match match_against {
+            MatchAgainst::Both => Some(
+                all_metadata_extracting_verifier::Verifier::<DeployedBytecode>::new(
+                    deployed_bytecode.clone(),
+                )?
+                .with_matchers(matchers.clone()),
+            ),
+            _ => None,
+        };
         let verifier: Box<dyn base::Verifier<Input = (CompilerOutput, CompilerOutput)>> =
             match creation_tx_input {
-                None => Box::new(all_metadata_extracting_verifier::Verifier::<
-                    DeployedBytecode,
-                >::new(deployed_bytecode)?),
-                Some(creation_tx_input) => Box::new(all_metadata_extracting_verifier::Verifier::<
-                    CreationTxInput,
-                >::new(creation_tx_input)?),
+                None => Box::new(
+                    all_metadata_extracting_verifier::Verifier::<DeployedBytecode>::new(
+                        deployed_bytecode,
+                    )?
+                    .with_contract_name(contract_name)
+                    .with_matchers(matchers.clone()),
+                ),
+                Some(creation_tx_input) => Box::new(
+                    all_metadata_extracting_verifier::Verifier::<CreationTxInput>::new(
+                        creation_tx_input,
+                    )?
+                    .with_contract_name(contract_name)
+                    .with_matchers(matchers.clone()),
+                ),
             };
         Ok(Self {
-            compilers,
+            language,
             compiler_version,
             verifier,
+            deployed_verifier,
+            code_hash,
         })
     }
 
-    #[instrument(skip(self, compiler_input), level = "debug")]
+    /// Verifies the contract against each of the compiler inputs in order,
+    /// until some of them succeeds or fails with an uncorrectable error.
+    ///
+    /// Middlewares of the language are invoked with the success before it is returned.
+    /// If no contracts have matched, diagnostics are reported for the first input only,
+    /// so the inputs should be sorted by their probability.
+    pub async fn verify_any(
+        &self,
+        compiler_inputs: impl IntoIterator<Item = CompilerInput>,
+    ) -> Result<Success, Error> {
+        self.verify_inputs(compiler_inputs, false).await
+    }
+
+    /// Verifies the contract against each of the compiler inputs in order as [`Self::verify_any`]
+    /// does, but goes on after partial matches and any failures, so that a following input which
+    /// matches the contract fully is preferred over the first partially matching one. If none
+    /// of the inputs matches, the failure of the first input is returned.
+    pub async fn verify_any_preferring_full(
+        &self,
+        compiler_inputs: impl IntoIterator<Item = CompilerInput>,
+    ) -> Result<Success, Error> {
+        self.verify_inputs(compiler_inputs, true).await
+    }
+
+    #[instrument(skip_all, fields(language = self.language.language()), level = "debug")]
+    async fn verify_inputs(
+        &self,
+        compiler_inputs: impl IntoIterator<Item = CompilerInput>,
+        prefer_full: bool,
+    ) -> Result<Success, Error> {
+        let mut diagnostics = None;
+        let mut partial = None;
+        let mut failure = None;
+        let mut result = None;
+        for compiler_input in compiler_inputs {
+            match self.verify(&compiler_input).await {
+                Err(Error::NoMatchingContracts(contracts)) if !prefer_full => {
+                    diagnostics.get_or_insert(contracts);
+                }
+                Ok(success) if prefer_full && success.match_type == MatchType::Partial => {
+                    partial.get_or_insert(success);
+                }
+                Err(err) if prefer_full => {
+                    failure.get_or_insert(err);
+                }
+                verified => {
+                    result = Some(verified);
+                    break;
+                }
+            }
+        }
+        let result = match (result, partial, failure) {
+            (Some(Ok(success)), _, _) => Ok(success),
+            (_, Some(partial), _) => Ok(partial),
+            (Some(Err(err)), None, _) | (None, None, Some(err)) => Err(err),
+            (None, None, None) => Err(Error::NoMatchingContracts(diagnostics.unwrap_or_default())),
+        };
+
+        let status = match &result {
+            Ok(_) => "success",
+            Err(Error::NoMatchingContracts(_)) => "no_match",
+            Err(_) => "failure",
+        };
+        metrics::VERIFICATIONS
+            .with_label_values(&[self.language.language(), status])
+            .inc();
+
+        let success = result?;
+        self.language.middlewares().run(&success).await;
+        Ok(success)
+    }
+
+    /// Compiles the input and compares its bytecode with the on-chain one.
+    /// Each call is traced as a separate compile attempt, tagged with its metadata hash variant.
+    #[instrument(
+        name = "compile_attempt",
+        skip_all,
+        fields(metadata_hash = %metadata_hash_variant(compiler_input)),
+        level = "debug"
+    )]
     pub async fn verify(&self, compiler_input: &CompilerInput) -> Result<Success, Error> {
+        if self.language.rejects_hidden_characters() {
+            let findings = hidden_characters::scan_sources(&compiler_input.sources);
+            if !findings.is_empty() {
+                return Err(Error::HiddenCharacters(findings));
+            }
+        }
+        progress::report(Stage::Compiling {
+            metadata_hash: metadata_hash_variant(compiler_input),
+        });
         let compiler_output = self
-            .compilers
+            .language
             .compile(self.compiler_version, compiler_input)
             .await?;
         let compiler_output_modified = {
@@ -115,14 +295,16 @@ impl<'a, T: EvmCompiler> ContractVerifier<'a, T> {
                 non_used_contract_name,
                 "0xcafecafecafecafecafecafecafecafecafecafe".into(),
             );
-            self.compilers
+            self.language
                 .compile(self.compiler_version, &compiler_input)
                 .await?
         };
 
         let outputs = (compiler_output, compiler_output_modified);
-        let verification_success = self.verifier.verify(&outputs).map_err(|errs| {
-            errs.into_iter()
+        progress::report(Stage::Comparing);
+        let comparison = tracing::debug_span!("compare_bytecode").in_scope(|| self.compare(&outputs));
+        let verification_success = comparison.map_err(|errs| {
+            errs.iter()
                 .find_map(|err| match err {
                     // Even one CompilerVersionMismatch error indicates that provided
                     // compiler version does not correspond to on chain bytecode.
@@ -142,28 +324,78 @@ impl<'a, T: EvmCompiler> ContractVerifier<'a, T> {
                             }),
                         ..
                     } => Some(Error::CompilerVersionMismatch(Mismatch::new(
-                        version,
+                        version.clone(),
                         self.compiler_version.version().clone(),
                     ))),
                     _ => None,
                 })
-                .unwrap_or(Error::NoMatchingContracts)
+                .unwrap_or_else(|| Error::NoMatchingContracts(diagnostics::from_errors(&errs)))
         })?;
 
+        if !verification_success.other_matches.is_empty() {
+            tracing::warn!(
+                other_matches = ?verification_success.other_matches,
+                "several contracts match the bytecode, {}:{} has been chosen",
+                verification_success.file_path,
+                verification_success.contract_name
+            );
+        }
+
         let (compiler_output, _) = outputs;
         // We accept compiler input and compiler version by reference, so that we
         // avoid their cloning if verification fails.
         // In case of success, they will be cloned exactly once.
         Ok(Success {
             compiler_input: compiler_input.clone(),
+            extra_settings: Default::default(),
             compiler_output,
             compiler_version: self.compiler_version.clone(),
+            fully_qualified_name: format!(
+                "{}:{}",
+                verification_success.file_path, verification_success.contract_name
+            ),
             file_path: verification_success.file_path,
             contract_name: verification_success.contract_name,
             abi: verification_success.abi,
             constructor_args: verification_success.constructor_args,
+            creation_bytecode: verification_success.creation_bytecode,
+            deployed_bytecode: verification_success.deployed_bytecode,
             local_bytecode_parts: verification_success.local_bytecode_parts,
             match_type: verification_success.match_type,
+            other_matches: verification_success.other_matches,
+            code_hash: self.code_hash,
         })
     }
 }
+
+impl<'a, L> ContractVerifier<'a, L> {
+    /// Matches compiled contracts against the remote bytecode. If both bytecodes are required
+    /// to match, the matched contract is checked against the deployed bytecode as well;
+    /// the match is full only if both matches are full.
+    fn compare(
+        &self,
+        outputs: &(CompilerOutput, CompilerOutput),
+    ) -> Result<base::VerificationSuccess, Vec<VerificationError>> {
+        let mut success = self.verifier.verify(outputs)?;
+        if let Some(deployed_verifier) = &self.deployed_verifier {
+            let fully_qualified_name = format!("{}:{}", success.file_path, success.contract_name);
+            let deployed = deployed_verifier
+                .clone()
+                .with_contract_name(Some(fully_qualified_name))
+                .verify(&outputs.0, &outputs.1)?;
+            if deployed.match_type == MatchType::Partial {
+                success.match_type = MatchType::Partial;
+            }
+        }
+        Ok(success)
+    }
+}
+
+/// Metadata hash settings of the input ("ipfs", "bzzr1", "none"),
+/// or "default" if they are left to the compiler.
+fn metadata_hash_variant(compiler_input: &CompilerInput) -> String {
+    match compiler_input.settings.metadata.as_ref().and_then(|metadata| metadata.bytecode_hash) {
+        Some(bytecode_hash) => bytecode_hash.to_string(),
+        None => "default".to_string(),
+    }
+}
@@ -0,0 +1,225 @@
+use super::errors::{VerificationError, VerificationErrorKind};
+use crate::DisplayBytes;
+use mismatch::Mismatch;
+
+/// Opcodes introduced by hard forks. Their presence in only one of
+/// the bytecodes usually indicates that evm versions differ.
+const FORK_SPECIFIC_OPCODES: [u8; 7] = [
+    0x1b, // SHL (constantinople)
+    0x1c, // SHR (constantinople)
+    0x1d, // SAR (constantinople)
+    0x46, // CHAINID (istanbul)
+    0x47, // SELFBALANCE (istanbul)
+    0x48, // BASEFEE (london)
+    0x5f, // PUSH0 (shanghai)
+];
+
+/// Possible causes of the mismatch the user may want to check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hint {
+    /// Optimizer is enabled/disabled wrongly, or the number of optimizer runs is wrong.
+    OptimizerSettings,
+    /// Evm version the contract has been compiled for is wrong.
+    EvmVersion,
+    /// Constructor arguments appended to the creation transaction input do not
+    /// correspond to the constructor of the contract.
+    ConstructorArguments,
+    /// Some of the libraries used by the contract are not linked.
+    Libraries,
+    /// Compiler version is wrong.
+    CompilerVersion,
+}
+
+/// Describes why a single contract obtained during local compilation
+/// does not correspond to the remote bytecode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractDiagnostics {
+    pub file_path: String,
+    pub contract_name: Option<String>,
+    /// Short description of the mismatch.
+    pub reason: String,
+    /// Offset of the first byte local and remote bytecodes differ at, if bytecodes have been compared.
+    pub first_diverging_offset: Option<usize>,
+    /// Indicates that executable parts correspond to each other, and only metadata hashes differ.
+    pub metadata_hash_only: bool,
+    /// Indicates that local bytecode is longer than the remote one.
+    pub length_differs: bool,
+    pub hints: Vec<Hint>,
+}
+
+impl ContractDiagnostics {
+    /// Returns diagnostics of the failed contract verification, or `None`
+    /// if the contract could not be a candidate for verification at all (e.g., is abstract).
+    pub fn new(error: &VerificationError) -> Option<Self> {
+        let mut diagnostics = Self {
+            file_path: error.file_path.clone(),
+            contract_name: error.contract_name.clone(),
+            reason: String::new(),
+            first_diverging_offset: None,
+            metadata_hash_only: false,
+            length_differs: false,
+            hints: vec![],
+        };
+        match &error.kind {
+            VerificationErrorKind::AbstractContract => return None,
+            VerificationErrorKind::InternalError(_) => {
+                diagnostics.reason = "internal error".into();
+            }
+            VerificationErrorKind::LibraryMissed(libraries) => {
+                diagnostics.reason = match libraries.is_empty() {
+                    true => "library missed".into(),
+                    false => format!("libraries are not linked: {}", libraries.join(", ")),
+                };
+                diagnostics.hints.push(Hint::Libraries);
+            }
+            VerificationErrorKind::BytecodeLengthMismatch { raw, .. } => {
+                diagnostics.reason = "local bytecode is longer than the remote one".into();
+                diagnostics.first_diverging_offset = first_diverging_offset(raw);
+                diagnostics.length_differs = true;
+                diagnostics.hints = bytecode_hints(raw, diagnostics.first_diverging_offset);
+            }
+            VerificationErrorKind::BytecodeMismatch { raw, .. } => {
+                diagnostics.reason = "bytecode does not match compilation output".into();
+                diagnostics.first_diverging_offset = first_diverging_offset(raw);
+                diagnostics.hints = bytecode_hints(raw, diagnostics.first_diverging_offset);
+            }
+            // Executable parts may still differ, as the remote metadata cannot be compared at all
+            VerificationErrorKind::MetadataParse(err) => {
+                diagnostics.reason =
+                    format!("metadata of the remote bytecode cannot be parsed: {err}");
+            }
+            VerificationErrorKind::CompilerVersionMismatch(mismatch) => {
+                diagnostics.reason =
+                    format!("compiler versions included into metadata hash differ: {mismatch}");
+                diagnostics.metadata_hash_only = true;
+                diagnostics.hints.push(Hint::CompilerVersion);
+            }
+            VerificationErrorKind::InvalidConstructorArguments(_) => {
+                diagnostics.reason =
+                    "constructor arguments do not correspond to the constructor".into();
+                diagnostics.hints.push(Hint::ConstructorArguments);
+            }
+            VerificationErrorKind::ContractNameNotFound => {
+                diagnostics.reason = "contract has not been found among compiled contracts".into();
+            }
+        }
+        Some(diagnostics)
+    }
+}
+
+/// Returns diagnostics for all candidate contracts the verification has failed for.
+pub fn from_errors(errors: &[VerificationError]) -> Vec<ContractDiagnostics> {
+    errors.iter().filter_map(ContractDiagnostics::new).collect()
+}
+
+fn first_diverging_offset(raw: &Mismatch<DisplayBytes>) -> Option<usize> {
+    let (local, remote) = (&raw.expected, &raw.found);
+    let offset = local
+        .0
+        .iter()
+        .zip(remote.0.iter())
+        .position(|(local, remote)| local != remote)
+        .unwrap_or_else(|| local.0.len().min(remote.0.len()));
+    Some(offset)
+}
+
+fn bytecode_hints(raw: &Mismatch<DisplayBytes>, offset: Option<usize>) -> Vec<Hint> {
+    let fork_specific_opcode_at_offset = match offset {
+        Some(offset) => [&raw.expected, &raw.found].iter().any(|bytecode| {
+            bytecode
+                .0
+                .get(offset)
+                .map(|byte| FORK_SPECIFIC_OPCODES.contains(byte))
+                .unwrap_or_default()
+        }),
+        None => false,
+    };
+    if fork_specific_opcode_at_offset {
+        vec![Hint::EvmVersion, Hint::OptimizerSettings]
+    } else {
+        vec![Hint::OptimizerSettings, Hint::EvmVersion]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    fn bytes(value: &str) -> DisplayBytes {
+        DisplayBytes::from_str(value).expect("valid bytes")
+    }
+
+    fn error(kind: VerificationErrorKind) -> VerificationError {
+        VerificationError::with_contract("A.sol".into(), "A".into(), kind)
+    }
+
+    #[test]
+    fn bytecode_mismatch() {
+        let raw = Mismatch::new(bytes("0x6080604052"), bytes("0x6080604152"));
+        let diagnostics = ContractDiagnostics::new(&error(VerificationErrorKind::BytecodeMismatch {
+            part: raw.clone(),
+            raw,
+        }))
+        .expect("diagnostics should exist");
+        assert_eq!(Some(3), diagnostics.first_diverging_offset);
+        assert!(!diagnostics.metadata_hash_only);
+        assert!(!diagnostics.length_differs);
+        assert_eq!(vec![Hint::OptimizerSettings, Hint::EvmVersion], diagnostics.hints);
+    }
+
+    #[test]
+    fn unparsable_metadata_is_not_reported_as_metadata_hash_mismatch() {
+        let diagnostics = ContractDiagnostics::new(&error(VerificationErrorKind::MetadataParse(
+            "metadata length mismatch".into(),
+        )))
+        .expect("diagnostics should exist");
+        assert_eq!(
+            "metadata of the remote bytecode cannot be parsed: metadata length mismatch",
+            diagnostics.reason
+        );
+        assert!(!diagnostics.metadata_hash_only);
+    }
+
+    #[test]
+    fn fork_specific_opcode_suggests_evm_version() {
+        let raw = Mismatch::new(bytes("0x60805f"), bytes("0x6080600052"));
+        let diagnostics =
+            ContractDiagnostics::new(&error(VerificationErrorKind::BytecodeLengthMismatch {
+                part: Mismatch::new(3, 5),
+                raw,
+            }))
+            .expect("diagnostics should exist");
+        assert_eq!(Some(2), diagnostics.first_diverging_offset);
+        assert!(diagnostics.length_differs);
+        assert_eq!(Hint::EvmVersion, diagnostics.hints[0]);
+    }
+
+    #[test]
+    fn metadata_only_mismatch() {
+        let diagnostics = ContractDiagnostics::new(&error(
+            VerificationErrorKind::CompilerVersionMismatch(Mismatch::new(
+                semver::Version::new(0, 8, 14),
+                semver::Version::new(0, 8, 15),
+            )),
+        ))
+        .expect("diagnostics should exist");
+        assert!(diagnostics.metadata_hash_only);
+        assert_eq!(None, diagnostics.first_diverging_offset);
+        assert_eq!(vec![Hint::CompilerVersion], diagnostics.hints);
+    }
+
+    #[test]
+    fn abstract_contracts_are_skipped() {
+        let errors = vec![
+            error(VerificationErrorKind::AbstractContract),
+            error(VerificationErrorKind::InvalidConstructorArguments(bytes(
+                "0x01",
+            ))),
+        ];
+        let diagnostics = from_errors(&errors);
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(vec![Hint::ConstructorArguments], diagnostics[0].hints);
+    }
+}
@@ -20,8 +20,9 @@ pub enum BytecodeInitError {
 pub enum VerificationErrorKind {
     #[error("internal error: {0}")]
     InternalError(String),
-    #[error("library missed")]
-    LibraryMissed,
+    /// Contains fully qualified names of the libraries which placeholders have not been linked.
+    #[error("library missed: {}", .0.join(", "))]
+    LibraryMissed(Vec<String>),
     #[error("contract is abstract")]
     AbstractContract,
     #[error("bytecode length is less than expected: {part}; bytecodes: {raw}")]
@@ -40,6 +41,8 @@ pub enum VerificationErrorKind {
     CompilerVersionMismatch(Mismatch<semver::Version>),
     #[error("invalid constructor arguments: {0}")]
     InvalidConstructorArguments(DisplayBytes),
+    #[error("requested contract has not been found among compiled contracts")]
+    ContractNameNotFound,
 }
 
 /// Error obtained as a result of a single contract verification.
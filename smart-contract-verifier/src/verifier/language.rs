@@ -0,0 +1,47 @@
+use super::{contract_verifier::Success, matcher::Matchers};
+use crate::{
+    compiler::{self, Compilers, EvmCompiler, Version},
+    middleware::Composition,
+};
+use ethers_solc::{CompilerInput, CompilerOutput};
+
+/// Language specific steps of the verification pipeline.
+///
+/// Implemented by the language clients, so that all languages share the same
+/// [`ContractVerifier`] flow: compilation, extraction of candidate contracts,
+/// comparison of their bytecodes with the on-chain one, metrics and middlewares.
+/// Bytecode comparison works on EVM bytecode and is the same for all languages.
+///
+/// [`ContractVerifier`]: super::ContractVerifier
+#[async_trait::async_trait]
+pub trait LanguageVerifier: Send + Sync {
+    type Compiler: EvmCompiler + Send + Sync;
+
+    /// Name of the language used in logs and metrics (e.g., "solidity").
+    fn language(&self) -> &'static str;
+
+    fn compilers(&self) -> &Compilers<Self::Compiler>;
+
+    /// Middlewares invoked with successful verifications.
+    fn middlewares(&self) -> &Composition<Success>;
+
+    /// Strategies the compiled bytecodes are compared with the on-chain one.
+    fn matchers(&self) -> &Matchers;
+
+    /// If set, sources containing hidden or lookalike characters are rejected
+    /// before they are compiled (see [`hidden_characters`]).
+    ///
+    /// [`hidden_characters`]: crate::tools::hidden_characters
+    fn rejects_hidden_characters(&self) -> bool {
+        false
+    }
+
+    /// Compiles the input with the given compiler version.
+    async fn compile(
+        &self,
+        compiler_version: &Version,
+        input: &CompilerInput,
+    ) -> Result<CompilerOutput, compiler::Error> {
+        self.compilers().compile(compiler_version, input).await
+    }
+}
@@ -0,0 +1,388 @@
+//! Strategies the locally compiled bytecode is compared with the on-chain one.
+//!
+//! Matchers are composed into [`Matchers`] and tried in order, so that operators
+//! could tune the strictness of verification, and new strategies could be added
+//! without changes in the verification flow.
+
+use super::{bytecode::BytecodePart, errors::VerificationErrorKind};
+use crate::MatchType;
+use bytes::Bytes;
+use mismatch::Mismatch;
+use serde::{Deserialize, Serialize};
+use solidity_metadata::MetadataHash;
+use std::{
+    fmt::{self, Debug, Formatter},
+    ops::Range,
+    sync::Arc,
+};
+
+/// Bytecodes compared by a [`BytecodeMatcher`].
+pub struct MatchInput<'a> {
+    /// On-chain bytecode: creation transaction input or deployed code.
+    pub remote: &'a Bytes,
+    pub local: &'a Bytes,
+    /// The local bytecode split into the main parts and metadata hashes.
+    pub local_parts: &'a [BytecodePart],
+    /// Ranges of the local bytecode immutable values are written into on deployment.
+    /// Always empty for creation transaction inputs.
+    pub immutable_references: &'a [Range<usize>],
+    /// If set, the remote bytecode may be followed by constructor arguments.
+    pub is_creation_tx_input: bool,
+}
+
+pub trait BytecodeMatcher: Send + Sync {
+    /// Name of the strategy used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Returns how the remote bytecode corresponds to the local one, if it matches.
+    /// Bytes following the local bytecode are validated as constructor arguments afterwards.
+    fn matches(&self, input: &MatchInput) -> Result<MatchType, VerificationErrorKind>;
+}
+
+/// Built-in matchers which may be enabled in the configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatcherKind {
+    /// The remote bytecode is byte-for-byte equal to the local one,
+    /// except for constructor arguments following creation transaction inputs.
+    Exact,
+    /// The remote bytecode starts with the local one; the rest is constructor arguments.
+    ConstructorArgAware,
+    /// Bytecodes differ only in metadata hashes. Results in partial matches.
+    MetadataStripped,
+    /// Deployed bytecodes differ only in immutable values and metadata hashes.
+    /// The match is full if metadata hashes are equal.
+    ImmutableMasked,
+}
+
+impl MatcherKind {
+    fn matcher(self) -> Arc<dyn BytecodeMatcher> {
+        match self {
+            MatcherKind::Exact => Arc::new(ExactMatcher),
+            MatcherKind::ConstructorArgAware => Arc::new(ConstructorArgAwareMatcher),
+            MatcherKind::MetadataStripped => Arc::new(MetadataStrippedMatcher),
+            MatcherKind::ImmutableMasked => Arc::new(ImmutableMaskedMatcher),
+        }
+    }
+}
+
+/// Ordered composition of matchers. The first matching one determines the match type.
+#[derive(Clone)]
+pub struct Matchers(Vec<Arc<dyn BytecodeMatcher>>);
+
+impl Matchers {
+    /// Matchers used unless configured otherwise.
+    pub const DEFAULT_KINDS: [MatcherKind; 2] = [
+        MatcherKind::ConstructorArgAware,
+        MatcherKind::MetadataStripped,
+    ];
+
+    pub fn from_kinds(kinds: &[MatcherKind]) -> Self {
+        Self(kinds.iter().map(|kind| kind.matcher()).collect())
+    }
+
+    /// Adds a custom matcher to the end of the composition.
+    pub fn with_matcher(mut self, matcher: impl BytecodeMatcher + 'static) -> Self {
+        self.0.push(Arc::new(matcher));
+        self
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.0.iter().map(|matcher| matcher.name()).collect()
+    }
+
+    /// Tries the matchers in order. If none of them matches, the error
+    /// of the last (supposedly, the most lenient) one is returned.
+    pub fn compare(&self, input: &MatchInput) -> Result<MatchType, VerificationErrorKind> {
+        let mut result = Err(VerificationErrorKind::InternalError(
+            "no bytecode matchers are configured".into(),
+        ));
+        for matcher in &self.0 {
+            result = matcher.matches(input);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+}
+
+impl Default for Matchers {
+    fn default() -> Self {
+        Self::from_kinds(&Self::DEFAULT_KINDS)
+    }
+}
+
+impl Debug for Matchers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Matchers").field(&self.names()).finish()
+    }
+}
+
+impl PartialEq for Matchers {
+    fn eq(&self, other: &Self) -> bool {
+        self.names() == other.names()
+    }
+}
+
+impl Eq for Matchers {}
+
+struct ExactMatcher;
+
+impl BytecodeMatcher for ExactMatcher {
+    fn name(&self) -> &'static str {
+        "exact"
+    }
+
+    fn matches(&self, input: &MatchInput) -> Result<MatchType, VerificationErrorKind> {
+        let remote = match input.is_creation_tx_input && input.remote.len() > input.local.len() {
+            true => &input.remote[..input.local.len()],
+            false => &input.remote[..],
+        };
+        match remote == &input.local[..] {
+            true => Ok(MatchType::Full),
+            false => Err(prefix_mismatch(input.remote, input.local)),
+        }
+    }
+}
+
+struct ConstructorArgAwareMatcher;
+
+impl BytecodeMatcher for ConstructorArgAwareMatcher {
+    fn name(&self) -> &'static str {
+        "constructor_arg_aware"
+    }
+
+    fn matches(&self, input: &MatchInput) -> Result<MatchType, VerificationErrorKind> {
+        // If the local bytecode is a prefix of the remote one, metadata parts
+        // are the same, and the rest is checked as constructor arguments.
+        match input.remote.starts_with(input.local) {
+            true => Ok(MatchType::Full),
+            false => Err(prefix_mismatch(input.remote, input.local)),
+        }
+    }
+}
+
+struct MetadataStrippedMatcher;
+
+impl BytecodeMatcher for MetadataStrippedMatcher {
+    fn name(&self) -> &'static str {
+        "metadata_stripped"
+    }
+
+    fn matches(&self, input: &MatchInput) -> Result<MatchType, VerificationErrorKind> {
+        check_length(input.remote, input.local)?;
+        compare_parts(input.remote, input.local, input.local_parts)?;
+        Ok(MatchType::Partial)
+    }
+}
+
+struct ImmutableMaskedMatcher;
+
+impl BytecodeMatcher for ImmutableMaskedMatcher {
+    fn name(&self) -> &'static str {
+        "immutable_masked"
+    }
+
+    fn matches(&self, input: &MatchInput) -> Result<MatchType, VerificationErrorKind> {
+        check_length(input.remote, input.local)?;
+        let mut masked = input.remote.to_vec();
+        for range in input.immutable_references {
+            if range.end <= input.local.len() {
+                masked[range.clone()].copy_from_slice(&input.local[range.clone()]);
+            }
+        }
+        let masked = Bytes::from(masked);
+        if masked.starts_with(input.local) {
+            return Ok(MatchType::Full);
+        }
+        compare_parts(&masked, input.local, input.local_parts)?;
+        Ok(MatchType::Partial)
+    }
+}
+
+fn check_length(remote: &Bytes, local: &Bytes) -> Result<(), VerificationErrorKind> {
+    if remote.len() < local.len() {
+        return Err(VerificationErrorKind::BytecodeLengthMismatch {
+            part: Mismatch::new(local.len(), remote.len()),
+            raw: Mismatch::new(local.clone().into(), remote.clone().into()),
+        });
+    }
+    Ok(())
+}
+
+fn prefix_mismatch(remote: &Bytes, local: &Bytes) -> VerificationErrorKind {
+    if let Err(err) = check_length(remote, local) {
+        return err;
+    }
+    VerificationErrorKind::BytecodeMismatch {
+        part: Mismatch::new(local.clone().into(), remote.slice(..local.len()).into()),
+        raw: Mismatch::new(local.clone().into(), remote.clone().into()),
+    }
+}
+
+/// Compares main parts of the bytecodes and checks that the remote bytecode
+/// contains metadata hashes of the same length and compiler version
+/// where the local one does.
+///
+/// # Panics
+///
+/// The function will panic if `remote_raw.len()` is less than `local_raw.len()`.
+fn compare_parts(
+    remote_raw: &Bytes,
+    local_raw: &Bytes,
+    local_parts: &[BytecodePart],
+) -> Result<(), VerificationErrorKind> {
+    // A caller should ensure that this precondition holds.
+    assert!(
+        // if that fails, we would be out of range further anyway
+        remote_raw.len() >= local_raw.len(),
+        "Local bytecode is greater than remote"
+    );
+
+    let mut i = 0usize; // keep track of current processing position of `remote_raw`
+
+    for part in local_parts {
+        match part {
+            BytecodePart::Main { raw } => {
+                if raw != &remote_raw[i..i + raw.len()] {
+                    return Err(VerificationErrorKind::BytecodeMismatch {
+                        part: Mismatch::new(
+                            raw.clone().into(),
+                            remote_raw.slice(i..i + raw.len()).into(),
+                        ),
+                        raw: Mismatch::new(local_raw.clone().into(), remote_raw.clone().into()),
+                    });
+                }
+            }
+            BytecodePart::Metadata { metadata, raw, .. } => {
+                let (remote_metadata, remote_metadata_length) =
+                    MetadataHash::from_cbor(&remote_raw[i..])
+                        .map_err(|err| VerificationErrorKind::MetadataParse(err.to_string()))?;
+
+                let start_index = i + remote_metadata_length;
+                let raw_start_index = raw.len() - 2;
+                if remote_raw[start_index..start_index + 2]
+                    != raw[raw_start_index..raw_start_index + 2]
+                {
+                    return Err(VerificationErrorKind::MetadataParse(
+                        "metadata length mismatch".into(),
+                    ));
+                }
+
+                // We may say the compiler versions does not correspond to each other only in case if both compiler versions are present.
+                // Otherwise, we cannot say for sure if compiler version is invalid.
+                if let (Some(metadata_solc), Some(remote_metadata_solc)) =
+                    (&metadata.solc, &remote_metadata.solc)
+                {
+                    if metadata_solc != remote_metadata_solc {
+                        let expected_solc = metadata_solc.clone();
+                        let remote_solc = remote_metadata_solc.clone();
+                        return Err(VerificationErrorKind::CompilerVersionMismatch(
+                            Mismatch::new(expected_solc, remote_solc),
+                        ));
+                    }
+                }
+            }
+        }
+
+        i += part.size();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn input<'a>(
+        remote: &'a Bytes,
+        local: &'a Bytes,
+        local_parts: &'a [BytecodePart],
+        immutable_references: &'a [Range<usize>],
+    ) -> MatchInput<'a> {
+        MatchInput {
+            remote,
+            local,
+            local_parts,
+            immutable_references,
+            is_creation_tx_input: false,
+        }
+    }
+
+    fn creation_tx_input<'a>(
+        remote: &'a Bytes,
+        local: &'a Bytes,
+        local_parts: &'a [BytecodePart],
+    ) -> MatchInput<'a> {
+        MatchInput {
+            is_creation_tx_input: true,
+            ..input(remote, local, local_parts, &[])
+        }
+    }
+
+    #[test]
+    fn matchers_are_tried_in_order() {
+        let local = Bytes::from_static(&[0x60, 0x80, 0x60, 0x40]);
+        let parts = [BytecodePart::Main { raw: local.clone() }];
+        let with_args = Bytes::from_static(&[0x60, 0x80, 0x60, 0x40, 0x01]);
+
+        let exact = Matchers::from_kinds(&[MatcherKind::Exact]);
+        assert_eq!(
+            Ok(MatchType::Full),
+            exact.compare(&input(&local, &local, &parts, &[]))
+        );
+        assert!(exact
+            .compare(&input(&with_args, &local, &parts, &[]))
+            .is_err());
+        assert_eq!(
+            Ok(MatchType::Full),
+            exact.compare(&creation_tx_input(&with_args, &local, &parts))
+        );
+        assert_eq!(
+            Ok(MatchType::Full),
+            Matchers::default().compare(&input(&with_args, &local, &parts, &[]))
+        );
+
+        let shorter = Bytes::from_static(&[0x60, 0x80]);
+        assert!(matches!(
+            Matchers::default().compare(&input(&shorter, &local, &parts, &[])),
+            Err(VerificationErrorKind::BytecodeLengthMismatch { .. })
+        ));
+        assert!(Matchers::from_kinds(&[])
+            .compare(&input(&local, &local, &parts, &[]))
+            .is_err());
+    }
+
+    #[test]
+    fn immutables_are_masked() {
+        // PUSH32 <zero immutable> POP
+        let mut local = vec![0x7f];
+        local.extend([0u8; 32]);
+        local.push(0x50);
+        let mut remote = local.clone();
+        remote[1..33].copy_from_slice(&[0x11; 32]);
+        let (local, remote) = (Bytes::from(local), Bytes::from(remote));
+        let parts = [BytecodePart::Main { raw: local.clone() }];
+        let references = [1..33];
+
+        assert!(Matchers::default()
+            .compare(&input(&remote, &local, &parts, &references))
+            .is_err());
+        let matchers = Matchers::default().with_matcher(ImmutableMaskedMatcher);
+        assert_eq!(
+            vec![
+                "constructor_arg_aware",
+                "metadata_stripped",
+                "immutable_masked"
+            ],
+            matchers.names()
+        );
+        assert_eq!(
+            Ok(MatchType::Full),
+            matchers.compare(&input(&remote, &local, &parts, &references))
+        );
+    }
+}
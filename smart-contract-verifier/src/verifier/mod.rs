@@ -2,9 +2,15 @@
 mod all_metadata_extracting_verifier;
 mod base;
 mod bytecode;
+mod diagnostics;
 mod errors;
+mod language;
+mod matcher;
 
 mod contract_verifier;
 
 pub use bytecode::BytecodePart;
-pub use contract_verifier::{ContractVerifier, Error, Success};
+pub use contract_verifier::{ContractNotFound, ContractVerifier, Error, Success};
+pub use diagnostics::{ContractDiagnostics, Hint};
+pub use language::LanguageVerifier;
+pub use matcher::{BytecodeMatcher, MatchInput, MatcherKind, Matchers};
@@ -1,10 +1,16 @@
 use super::compiler::VyperCompiler;
-use crate::{compiler::Compilers, middleware::Middleware, verifier::Success};
+use crate::{
+    compiler::Compilers,
+    middleware::{Composition, Middleware},
+    verifier::{LanguageVerifier, Matchers, Success},
+};
 use std::sync::Arc;
 
 pub struct Client {
     compilers: Arc<Compilers<VyperCompiler>>,
-    middleware: Option<Arc<dyn Middleware<Success>>>,
+    middlewares: Composition<Success>,
+    matchers: Matchers,
+    reject_hidden_characters: bool,
 }
 
 impl Client {
@@ -23,7 +29,9 @@ impl Client {
     pub fn new_arc(compilers: Arc<Compilers<VyperCompiler>>) -> Self {
         Self {
             compilers,
-            middleware: None,
+            middlewares: Composition::new(),
+            matchers: Matchers::default(),
+            reject_hidden_characters: false,
         }
     }
 
@@ -36,11 +44,24 @@ impl Client {
         self.with_middleware_arc(Arc::new(middleware))
     }
 
-    /// Add middleware to the client. [`with_middleware`] is more ergonomic if you don't need the `Arc`.
+    /// Add middleware to the end of the client middleware chain.
+    /// [`with_middleware`] is more ergonomic if you don't need the `Arc`.
     ///
     /// [`with_middleware`]: Self::with_middleware
     pub fn with_middleware_arc(mut self, middleware: Arc<impl Middleware<Success>>) -> Self {
-        self.middleware = Some(middleware);
+        self.middlewares.push(middleware);
+        self
+    }
+
+    /// Replaces the default strategies the compiled bytecodes are compared with the on-chain one.
+    pub fn with_matchers(mut self, matchers: Matchers) -> Self {
+        self.matchers = matchers;
+        self
+    }
+
+    /// Rejects sources containing hidden or lookalike characters before they are compiled.
+    pub fn with_hidden_characters_rejected(mut self, reject: bool) -> Self {
+        self.reject_hidden_characters = reject;
         self
     }
 
@@ -48,8 +69,32 @@ impl Client {
         self.compilers.as_ref()
     }
 
-    /// Provides a reference to the middleware, if there is any.
-    pub fn middleware(&self) -> Option<&dyn Middleware<Success>> {
-        self.middleware.as_ref().map(|m| m.as_ref())
+    /// Provides a reference to the middleware chain invoked after successful verifications.
+    pub fn middlewares(&self) -> &Composition<Success> {
+        &self.middlewares
+    }
+}
+
+impl LanguageVerifier for Client {
+    type Compiler = VyperCompiler;
+
+    fn language(&self) -> &'static str {
+        "vyper"
+    }
+
+    fn compilers(&self) -> &Compilers<VyperCompiler> {
+        self.compilers()
+    }
+
+    fn middlewares(&self) -> &Composition<Success> {
+        self.middlewares()
+    }
+
+    fn matchers(&self) -> &Matchers {
+        &self.matchers
+    }
+
+    fn rejects_hidden_characters(&self) -> bool {
+        self.reject_hidden_characters
     }
 }
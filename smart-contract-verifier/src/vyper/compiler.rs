@@ -1,5 +1,5 @@
-use crate::compiler::{EvmCompiler, Version};
-use ethers_solc::{error::SolcError, CompilerInput, CompilerOutput, Solc};
+use crate::compiler::{self, EvmCompiler, Version};
+use ethers_solc::{error::SolcError, CompilerInput, CompilerOutput};
 use std::path::Path;
 
 #[derive(Default)]
@@ -19,7 +19,8 @@ impl EvmCompiler for VyperCompiler {
         _ver: &Version,
         input: &CompilerInput,
     ) -> Result<CompilerOutput, SolcError> {
-        let vyper_output: types::VyperCompilerOutput = Solc::from(path).compile_as(input)?;
+        let vyper_output: types::VyperCompilerOutput =
+            compiler::compile_standard_json(path, input).await?;
         Ok(CompilerOutput::from(vyper_output))
     }
 }
@@ -1,5 +1,6 @@
 use super::client::Client;
 use crate::{
+    common_types::MatchAgainst,
     compiler::Version,
     verifier::{ContractVerifier, Error, Success},
 };
@@ -15,6 +16,11 @@ pub struct VerificationRequest {
     pub deployed_bytecode: Bytes,
     pub creation_bytecode: Option<Bytes>,
     pub compiler_version: Version,
+    /// Name of the contract to verify, plain or fully qualified (`file.vy:Name`).
+    /// Required only if several compiled contracts match the bytecode.
+    pub contract_name: Option<String>,
+    /// Bytecode the compiled contract is required to match.
+    pub match_against: MatchAgainst,
 
     pub content: MultiFileContent,
 }
@@ -53,17 +59,12 @@ impl From<MultiFileContent> for CompilerInput {
 pub async fn verify(client: Arc<Client>, request: VerificationRequest) -> Result<Success, Error> {
     let compiler_input = CompilerInput::from(request.content);
     let verifier = ContractVerifier::new(
-        client.compilers(),
+        client.as_ref(),
         &request.compiler_version,
         request.creation_bytecode,
         request.deployed_bytecode,
+        request.contract_name,
+        request.match_against,
     )?;
-
-    // If case of success, we allow middlewares to process success and only then return it to the caller;
-    // Otherwise, we just return an error
-    let success = verifier.verify(&compiler_input).await?;
-    if let Some(middleware) = client.middleware() {
-        middleware.call(&success).await;
-    }
-    Ok(success)
+    verifier.verify_any([compiler_input]).await
 }
@@ -7,14 +7,14 @@ mock! {
 
     #[async_trait::async_trait]
     impl<Output: 'static + Send + Sync> smart_contract_verifier::Middleware<Output> for Middleware<Output> {
-        async fn call(&self, output: &Output) -> ();
+        async fn call(&self, output: &Output) -> Result<(), anyhow::Error>;
     }
 }
 
 #[rstest::fixture]
 fn middleware<Output: 'static + Send + Sync>() -> MockMiddleware<Output> {
     let mut middleware = MockMiddleware::<Output>::new();
-    middleware.expect_call().times(1).return_const(());
+    middleware.expect_call().times(1).returning(|_| Ok(()));
     middleware
 }
 
@@ -177,7 +177,9 @@ mod sourcify {
 
     fn default_request() -> VerificationRequest {
         VerificationRequest {
-            address: "0xfa300CcA91991cB2cB3900610339ad37f7659ff8".to_string(),
+            address: "0xfa300CcA91991cB2cB3900610339ad37f7659ff8"
+                .parse()
+                .unwrap(),
             chain: "77".to_string(),
             files: BTreeMap::from([
                 ("metadata.json".to_string(), r#"{"compiler":{"version":"0.8.7+commit.e28d00a7"},"language":"Solidity","output":{"abi":[],"devdoc":{"kind":"dev","methods":{},"version":1},"userdoc":{"kind":"user","methods":{},"version":1}},"settings":{"compilationTarget":{"contracts/B.sol":"A"},"evmVersion":"london","libraries":{},"metadata":{"bytecodeHash":"ipfs"},"optimizer":{"enabled":false,"runs":200},"remappings":[]},"sources":{"contracts/B.sol":{"keccak256":"0x4c9cd5fa73d82532d860e3b4efb4ef9c3663fbac49298b03c67e60fcf41b37ca","urls":["bzz-raw://2b30acfab9fe7b536e72443dfe216f41001030a107808964cc6ff7536e62cbe1","dweb:/ipfs/QmRCCB29ZeP2iLxNhBSeUpXE4zp1YEabJasVmvuBRQ7azE"]}},"version":1}"#.to_string()),
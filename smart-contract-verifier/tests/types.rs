@@ -60,11 +60,14 @@ pub mod solidity {
                 deployed_bytecode: source.deployed_bytecode,
                 creation_bytecode: source.creation_bytecode,
                 compiler_version: source.compiler_version,
+                contract_name: None,
                 content: multi_part::MultiFileContent {
                     sources: source.sources,
                     evm_version: source.evm_version,
                     optimization_runs: source.optimization_runs,
                     contract_libraries: source.contract_libraries,
+                    remappings: vec![],
+                    extra_outputs: vec![],
                 },
             }
         }
@@ -84,6 +87,7 @@ pub mod solidity {
                 deployed_bytecode: multi_part_request.deployed_bytecode,
                 creation_bytecode: multi_part_request.creation_bytecode,
                 compiler_version: multi_part_request.compiler_version,
+                contract_name: multi_part_request.contract_name,
                 content: standard_json::StandardJsonContent { input },
             }
         }
@@ -143,6 +147,7 @@ pub mod vyper {
                 deployed_bytecode: source.deployed_bytecode,
                 creation_bytecode: source.creation_bytecode,
                 compiler_version: source.compiler_version,
+                contract_name: None,
                 content: multi_part::MultiFileContent {
                     sources: source.sources,
                     evm_version: source.evm_version,